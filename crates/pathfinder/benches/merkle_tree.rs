@@ -44,6 +44,34 @@ pub fn chunked_inserts(
     hash
 }
 
+pub fn chunked_inserts_in_memory(
+    storage: &pathfinder_lib::storage::merkle_tree::InMemoryNodeStorage,
+    keys: &[StarkHash],
+    batch_size: usize,
+) -> StarkHash {
+    let mut hash = stark_hash::StarkHash::ZERO;
+
+    for keys in keys.chunks(batch_size) {
+        let mut uut =
+            pathfinder_lib::state::merkle_tree::MerkleTree::with_storage(storage.clone(), hash)
+                .unwrap();
+
+        keys.iter()
+            .enumerate()
+            .try_for_each(|(value, key)| {
+                uut.set(
+                    key.view_bits(),
+                    stark_hash::StarkHash::from_be_slice(&value.to_be_bytes()).unwrap(),
+                )
+            })
+            .unwrap();
+
+        hash = uut.commit().unwrap();
+    }
+
+    hash
+}
+
 pub fn criterion_benchmark(c: &mut Criterion) {
     let s = pathfinder_lib::storage::Storage::in_memory().unwrap();
     let mut connection = s.connection().unwrap();
@@ -80,6 +108,18 @@ pub fn criterion_benchmark(c: &mut Criterion) {
             criterion::BatchSize::PerIteration,
         )
     });
+
+    // The in-memory node storage, as a baseline for the database backed trees above.
+    c.bench_function("merkle_tree of 1000 in 10 batches in memory", |b| {
+        b.iter_batched_ref(
+            || gen_random_keys(1000),
+            |keys| {
+                let storage = pathfinder_lib::storage::merkle_tree::InMemoryNodeStorage::default();
+                black_box(chunked_inserts_in_memory(&storage, keys, 100))
+            },
+            criterion::BatchSize::PerIteration,
+        )
+    });
 }
 
 criterion_group!(benches, criterion_benchmark);
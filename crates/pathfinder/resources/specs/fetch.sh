@@ -0,0 +1,15 @@
+#! /usr/bin/env bash
+# Fetches the StarkNet JSON-RPC specification files which the v0.2 API is tested against, see
+# `rpc::v02::conformance`. The files are not committed yet, so the test using them is ignored:
+# run this, then commit the files and remove the test's `#[ignore]`.
+set -e;
+set -o pipefail;
+
+VERSION="v0.2.1-rc1"
+DIR="$(dirname "${0}")/${VERSION}"
+
+mkdir -p "${DIR}"
+for FILE in starknet_api_openrpc.json starknet_write_api.json; do
+     curl -sSfL -o "${DIR}/${FILE}" \
+          "https://raw.githubusercontent.com/starkware-libs/starknet-specs/${VERSION}/api/${FILE}"
+done
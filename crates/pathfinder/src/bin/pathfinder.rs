@@ -6,11 +6,12 @@ use pathfinder_lib::{
     cairo, config,
     core::{self, Chain, EthereumChain},
     ethereum::transport::{EthereumTransport, HttpTransport},
+    feeder_gateway,
     monitoring::{self, metrics::middleware::RpcMetricsMiddleware},
     rpc, sequencer, state,
-    storage::{JournalMode, Storage},
+    storage::{maintenance::DatabaseLock, JournalMode, Storage},
 };
-use std::sync::{atomic::AtomicBool, Arc};
+use std::sync::Arc;
 use tracing::info;
 
 #[tokio::main]
@@ -21,6 +22,11 @@ async fn main() -> anyhow::Result<()> {
 
     setup_tracing();
 
+    // Database maintenance runs instead of the node.
+    if std::env::args_os().nth(1).as_deref() == Some(std::ffi::OsStr::new("db")) {
+        return db_command(std::env::args_os().skip(1)).await;
+    }
+
     let config =
         config::Configuration::parse_cmd_line_and_cfg_file().context("Parsing configuration")?;
 
@@ -32,19 +38,24 @@ async fn main() -> anyhow::Result<()> {
 
     permission_check(&config.data_directory)?;
 
-    let pathfinder_ready = match config.monitoring_addr {
+    let readiness = match config.monitoring_addr {
         Some(monitoring_addr) => {
-            let ready = Arc::new(AtomicBool::new(false));
+            let readiness = monitoring::Readiness::new(monitoring::ReadinessPolicy {
+                max_lag: config.ready_max_lag,
+                max_l1_age: config.ready_max_l1_age,
+            });
             let prometheus_handle = PrometheusBuilder::new()
                 .install_recorder()
                 .context("Creating Prometheus recorder")?;
             let _jh =
-                monitoring::spawn_server(monitoring_addr, ready.clone(), prometheus_handle).await;
-            Some(ready)
+                monitoring::spawn_server(monitoring_addr, readiness.clone(), prometheus_handle)
+                    .await;
+            Some(readiness)
         }
         None => None,
     };
 
+    let l1_confirmation = config.ethereum.confirmation;
     let eth_transport =
         HttpTransport::from_config(config.ethereum).context("Creating Ethereum transport")?;
 
@@ -56,41 +67,88 @@ async fn main() -> anyhow::Result<()> {
 Hint: Make sure the provided ethereum.url and ethereum.password are good.",
     )?;
 
-    let starknet_chain = match (ethereum_chain, config.integration, config.testnet2) {
-        (EthereumChain::Mainnet, false, false) => Chain::Mainnet,
-        (EthereumChain::Mainnet, _, _) => {
-            anyhow::bail!("'--integration' and '--testnet2' flags are invalid on Ethereum mainnet");
+    let starknet_chain = match &config.chain_spec {
+        Some(path) => {
+            let spec = core::ChainSpec::load(path).context("Loading chain spec")?;
+            anyhow::ensure!(
+                ethereum_chain.id() == spec.ethereum_chain_id,
+                "Ethereum chain id {} does not match the chain spec's {}",
+                ethereum_chain.id(),
+                spec.ethereum_chain_id
+            );
+            info!(name=%spec.name, "Using custom chain spec");
+            Chain::Custom(spec)
         }
-        (EthereumChain::Goerli, false, false) => Chain::Testnet,
-        (EthereumChain::Goerli, false, true) => Chain::Testnet2,
-        (EthereumChain::Goerli, true, true) => {
-            anyhow::bail!("'--integration' and '--testnet2' flags cannot be used together")
-        }
-        (EthereumChain::Goerli, true, false) => Chain::Integration,
+        None => match (ethereum_chain, config.integration, config.testnet2) {
+            (EthereumChain::Mainnet, false, false) => Chain::Mainnet,
+            (EthereumChain::Mainnet, _, _) => {
+                anyhow::bail!(
+                    "'--integration' and '--testnet2' flags are invalid on Ethereum mainnet"
+                );
+            }
+            (EthereumChain::Goerli, false, false) => Chain::Testnet,
+            (EthereumChain::Goerli, false, true) => Chain::Testnet2,
+            (EthereumChain::Goerli, true, true) => {
+                anyhow::bail!("'--integration' and '--testnet2' flags cannot be used together")
+            }
+            (EthereumChain::Goerli, true, false) => Chain::Integration,
+            (EthereumChain::Other(id), _, _) => {
+                anyhow::bail!("Ethereum chain id {id} does not host a public Starknet network, use '--chain-spec' for custom chains")
+            }
+        },
     };
 
     let database_path = config.data_directory.join(match starknet_chain {
-        Chain::Mainnet => "mainnet.sqlite",
-        Chain::Testnet => "goerli.sqlite",
-        Chain::Testnet2 => "testnet2.sqlite",
-        Chain::Integration => "integration.sqlite",
+        Chain::Mainnet => "mainnet.sqlite".to_owned(),
+        Chain::Testnet => "goerli.sqlite".to_owned(),
+        Chain::Testnet2 => "testnet2.sqlite".to_owned(),
+        Chain::Integration => "integration.sqlite".to_owned(),
+        Chain::Custom(spec) => format!("{}.sqlite", spec.name),
     });
     let journal_mode = match config.sqlite_wal {
         false => JournalMode::Rollback,
         true => JournalMode::WAL,
     };
-    let storage = Storage::migrate(database_path.clone(), journal_mode).unwrap();
-    info!(location=?database_path, "Database migrated.");
+    // Held until the node exits, preventing `pathfinder db` commands from running meanwhile.
+    let _database_lock = DatabaseLock::shared(&database_path).context("Locking database")?;
+    let storage = match config.read_only {
+        true => {
+            let storage = Storage::open_read_only(database_path.clone())
+                .context("Opening database read-only")?;
+            info!(location=?database_path, "Database opened read-only.");
+            storage
+        }
+        false => {
+            let storage = Storage::migrate(database_path.clone(), journal_mode).unwrap();
+            info!(location=?database_path, "Database migrated.");
+            storage
+        }
+    };
     verify_database_chain(&storage, starknet_chain).context("Verifying database")?;
 
     let sequencer = match config.sequencer_url {
         Some(url) => {
             info!(?url, "Using custom Sequencer address");
             let client = sequencer::Client::with_url(url).unwrap();
-            let sequencer_chain = client.chain().await.unwrap();
-            if sequencer_chain != starknet_chain {
-                tracing::error!(sequencer=%sequencer_chain, ethereum=%starknet_chain, "Sequencer and Ethereum network mismatch");
-                anyhow::bail!("Sequencer and Ethereum network mismatch. Sequencer is on {sequencer_chain} but Ethereum is on {starknet_chain}");
+            match starknet_chain {
+                Chain::Custom(spec) => {
+                    let genesis_hash = client
+                        .genesis_hash()
+                        .await
+                        .context("Fetching genesis block hash from sequencer")?;
+                    anyhow::ensure!(
+                        genesis_hash == spec.genesis.block_hash,
+                        "Sequencer genesis block hash {} does not match the chain spec",
+                        genesis_hash.0
+                    );
+                }
+                _ => {
+                    let sequencer_chain = client.chain().await.unwrap();
+                    if sequencer_chain != starknet_chain {
+                        tracing::error!(sequencer=%sequencer_chain, ethereum=%starknet_chain, "Sequencer and Ethereum network mismatch");
+                        anyhow::bail!("Sequencer and Ethereum network mismatch. Sequencer is on {sequencer_chain} but Ethereum is on {starknet_chain}");
+                    }
+                }
             }
             client
         }
@@ -98,17 +156,28 @@ Hint: Make sure the provided ethereum.url and ethereum.password are good.",
     };
     let sync_state = Arc::new(state::SyncState::default());
     let pending_state = state::PendingData::default();
+    let response_cache = rpc::cache::ResponseCache::new(config.rpc_cache_size);
     let pending_interval = match config.poll_pending {
         true => Some(std::time::Duration::from_secs(5)),
         false => None,
     };
+    let block_validation_mode = match config.strict_block_validation {
+        true => state::l2::BlockValidationMode::Strict,
+        false => state::l2::BlockValidationMode::AllowMismatch,
+    };
+
+    // Set once a shutdown signal is received, stopping the tasks below.
+    let (shutdown_tx, shutdown_rx) = tokio::sync::watch::channel(false);
+    // Set once the servers have stopped, so that the calls they were serving can still complete.
+    let (python_shutdown_tx, python_shutdown_rx) = tokio::sync::watch::channel(false);
 
     // TODO: the error could be recovered, but currently it's required for startup. There should
     // not be other reason for the start to fail than python script not firing up.
-    let (call_handle, cairo_handle) = cairo::ext_py::start(
-        storage.path().into(),
+    let (call_handle, mut cairo_handle) = cairo::ext_py::start(
+        python_database_path(&storage, config.read_only),
         config.python_subprocesses,
-        futures::future::pending(),
+        config.python_limits,
+        shutdown_started(python_shutdown_rx),
         starknet_chain,
     )
     .await
@@ -116,81 +185,361 @@ Hint: Make sure the provided ethereum.url and ethereum.password are good.",
         "Creating python process for call handling. Have you setup our Python dependencies?",
     )?;
 
-    let sync_handle = tokio::spawn(state::sync(
-        storage.clone(),
-        eth_transport.clone(),
-        starknet_chain,
-        sequencer.clone(),
-        sync_state.clone(),
-        state::l1::sync,
-        state::l2::sync,
-        pending_state.clone(),
-        pending_interval,
-    ));
+    let mut sync_handle = match config.sync_rpc_url {
+        _ if config.read_only => {
+            info!("Read-only mode, following the node writing to the database");
+            tokio::spawn(state::replica::follow(
+                storage.clone(),
+                starknet_chain,
+                sequencer.clone(),
+                sync_state.clone(),
+                pending_state.clone(),
+                response_cache.clone(),
+                std::time::Duration::from_secs(1),
+                pending_interval,
+                shutdown_started(shutdown_rx.clone()),
+            ))
+        }
+        Some(url) => {
+            info!(%url, "Syncing from JSON-RPC node");
+            let client = sequencer::JsonRpcClient::new(url).context("Creating JSON-RPC client")?;
+            let chain_id = client
+                .chain_id()
+                .await
+                .context("Fetching chain id from JSON-RPC node")?;
+            anyhow::ensure!(
+                chain_id == starknet_chain.starknet_chain_id(),
+                "JSON-RPC node chain id {} does not match {}",
+                chain_id,
+                starknet_chain
+            );
+            if block_validation_mode != state::l2::BlockValidationMode::Strict {
+                info!("Block hashes are always verified when syncing from a JSON-RPC node");
+            }
+
+            tokio::spawn(state::sync(
+                storage.clone(),
+                eth_transport.clone(),
+                starknet_chain,
+                client,
+                sync_state.clone(),
+                move |tx, transport, chain, head| {
+                    state::l1::sync(tx, transport, chain, head, l1_confirmation)
+                },
+                state::l2::sync,
+                pending_state.clone(),
+                response_cache.clone(),
+                pending_interval,
+                state::l2::BlockValidationMode::Strict,
+                shutdown_started(shutdown_rx.clone()),
+            ))
+        }
+        None => tokio::spawn(state::sync(
+            storage.clone(),
+            eth_transport.clone(),
+            starknet_chain,
+            sequencer.clone(),
+            sync_state.clone(),
+            move |tx, transport, chain, head| {
+                state::l1::sync(tx, transport, chain, head, l1_confirmation)
+            },
+            state::l2::sync,
+            pending_state.clone(),
+            response_cache.clone(),
+            pending_interval,
+            block_validation_mode,
+            shutdown_started(shutdown_rx.clone()),
+        )),
+    };
+
+    // The message logs are written by the node syncing the database.
+    let mut messages_handle = match config.read_only {
+        true => tokio::spawn(std::future::pending()),
+        false => tokio::spawn(state::messages::sync(
+            storage.clone(),
+            eth_transport.clone(),
+            starknet_chain,
+        )),
+    };
+
+    // Finality is only informational, so failing to track it is not fatal.
+    if !config.read_only {
+        let finality = state::l1::track_finality(
+            storage.clone(),
+            eth_transport.clone(),
+            starknet_chain,
+            l1_confirmation,
+        );
+        tokio::spawn(async move {
+            if let Err(error) = finality.await {
+                tracing::error!(reason=?error, "Tracking L1 finality failed");
+            }
+        });
+    }
 
     let shared = rpc::gas_price::Cached::new(Arc::new(eth_transport));
 
-    let api = rpc::v01::api::RpcApi::new(storage, sequencer, starknet_chain, sync_state)
+    let feeder_gateway_context = config.feeder_gateway_addr.map(|addr| {
+        (
+            addr,
+            feeder_gateway::Context::new(storage.clone(), starknet_chain, pending_state.clone()),
+        )
+    });
+
+    let monitored_node = monitoring::Node {
+        storage: storage.clone(),
+        sync_state: sync_state.clone(),
+        pending_data: config.poll_pending.then(|| pending_state.clone()),
+        call_handle: Some(call_handle.clone()),
+        python_subprocesses: config.python_subprocesses,
+    };
+
+    let api = rpc::v01::api::RpcApi::new(storage.clone(), sequencer, starknet_chain, sync_state)
         .with_call_handling(call_handle)
-        .with_eth_gas_price(shared);
+        .with_eth_gas_price(shared)
+        .with_response_cache(response_cache);
     let api = match config.poll_pending {
         true => api.with_pending_data(pending_state),
         false => api,
     };
 
-    let (rpc_handle, local_addr) = rpc::RpcServer::new(config.http_rpc_addr, api)
+    let rpc_server = rpc::RpcServer::new(config.http_rpc_addr, api)
         .with_middleware(RpcMetricsMiddleware)
-        .run()
-        .await
-        .context("Starting the RPC server")?;
+        .with_http_options(config.rpc_http.clone());
+    let rpc_server = match &config.rpc_auth {
+        Some(auth) => {
+            info!(keys=%auth.keys.len(), "RPC access control enabled");
+            rpc_server.with_access_control(rpc::access::AccessControl::new(auth.clone()))
+        }
+        None => rpc_server,
+    };
+    let rpc_server = match &config.rpc_ipc {
+        Some(ipc) => {
+            info!(path=%ipc.path.display(), "📡 IPC-RPC server enabled");
+            rpc_server.with_ipc(ipc.clone())
+        }
+        None => rpc_server,
+    };
+    let (mut rpc_handle, local_addr) = rpc_server.run().await.context("Starting the RPC server")?;
 
     info!("📡 HTTP-RPC server started on: {}", local_addr);
 
-    let update_handle = tokio::spawn(pathfinder_lib::update::poll_github_for_releases());
+    let mut feeder_gateway_handle = match feeder_gateway_context {
+        Some((addr, context)) => {
+            let (handle, local_addr) =
+                feeder_gateway::spawn_server(addr, context, shutdown_started(shutdown_rx.clone()))
+                    .await
+                    .context("Starting the feeder gateway server")?;
+            info!("📡 Feeder gateway server started on: {}", local_addr);
+            handle
+        }
+        None => tokio::spawn(shutdown_started(shutdown_rx.clone())),
+    };
+
+    let mut update_handle = tokio::spawn(pathfinder_lib::update::poll_github_for_releases());
 
     // We are now ready.
-    if let Some(ready) = pathfinder_ready {
-        ready.store(true, std::sync::atomic::Ordering::Relaxed);
+    if let Some(readiness) = &readiness {
+        readiness.started(monitored_node);
     }
 
     // Monitor our spawned process tasks.
     tokio::select! {
-        result = sync_handle => {
+        result = &mut sync_handle => {
             match result {
                 Ok(task_result) => tracing::error!("Sync process ended unexpected with: {:?}", task_result),
                 Err(err) => tracing::error!("Sync process ended unexpected; failed to join task handle: {:?}", err),
             }
         }
-        result = cairo_handle => {
+        result = &mut messages_handle => {
+            match result {
+                Ok(task_result) => tracing::error!("Message sync process ended unexpected with: {:?}", task_result),
+                Err(err) => tracing::error!("Message sync process ended unexpected; failed to join task handle: {:?}", err),
+            }
+        }
+        result = &mut cairo_handle => {
             match result {
                 Ok(task_result) => tracing::error!("Cairo process ended unexpected with: {:?}", task_result),
                 Err(err) => tracing::error!("Cairo process ended unexpected; failed to join task handle: {:?}", err),
             }
         }
-        _result = rpc_handle => {
+        _result = &mut rpc_handle => {
             // This handle returns () so its not very useful.
             tracing::error!("RPC server process ended unexpected");
         }
-        result = update_handle => {
+        _result = &mut feeder_gateway_handle => {
+            tracing::error!("Feeder gateway server process ended unexpected");
+        }
+        result = &mut update_handle => {
             match result {
                 Ok(_) => tracing::error!("Release monitoring process ended unexpectedly"),
                 Err(err) => tracing::error!(error=%err, "Release monitoring process ended unexpectedly"),
             }
         }
+        _ = shutdown_signal() => {
+            info!("Shutting down, this takes up to {:?}", SHUTDOWN_TIMEOUT);
+            let deadline = tokio::time::Instant::now() + SHUTDOWN_TIMEOUT;
+
+            if let Some(readiness) = &readiness {
+                readiness.stopped();
+            }
+            let _ = shutdown_tx.send(true);
+            update_handle.abort();
+            messages_handle.abort();
+
+            // Stops accepting connections, and lets the requests in progress complete.
+            match tokio::time::timeout_at(deadline, rpc_handle.stop()).await {
+                Ok(Ok(())) => {}
+                Ok(Err(err)) => tracing::warn!(error=?err, "Stopping the RPC server failed"),
+                Err(_) => tracing::warn!("RPC requests did not complete in time"),
+            }
+            if tokio::time::timeout_at(deadline, feeder_gateway_handle).await.is_err() {
+                tracing::warn!("Feeder gateway requests did not complete in time");
+            }
+            match tokio::time::timeout_at(deadline, sync_handle).await {
+                Ok(Ok(Ok(()))) => {}
+                Ok(Ok(Err(err))) => tracing::error!(error=?err, "Sync process failed"),
+                Ok(Err(err)) => tracing::error!(error=%err, "Sync process failed"),
+                Err(_) => tracing::warn!("Sync did not stop in time"),
+            }
+
+            // Only once nothing can call them anymore.
+            let _ = python_shutdown_tx.send(true);
+            if tokio::time::timeout_at(deadline, cairo_handle).await.is_err() {
+                tracing::warn!("Python subprocesses did not stop in time");
+            }
+
+            if !config.read_only && config.sqlite_wal {
+                if let Err(err) = checkpoint_wal(&storage) {
+                    tracing::warn!(error=?err, "Checkpointing the WAL failed");
+                }
+            }
+            info!("Shutdown complete");
+        }
     }
 
     Ok(())
 }
 
+/// Time allowed for the tasks to stop on shutdown, before the process exits regardless. Kept below
+/// Docker's default stop timeout of 10 seconds.
+const SHUTDOWN_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(8);
+
+/// Resolves once SIGINT (Ctrl-C) or SIGTERM is received.
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        match signal(SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {}
+                    _ = sigterm.recv() => {}
+                }
+            }
+            Err(err) => {
+                tracing::warn!(error=%err, "Listening for SIGTERM failed");
+                let _ = tokio::signal::ctrl_c().await;
+            }
+        }
+    }
+
+    #[cfg(not(unix))]
+    let _ = tokio::signal::ctrl_c().await;
+}
+
+/// Resolves once shutdown has started.
+fn shutdown_started(
+    mut shutdown: tokio::sync::watch::Receiver<bool>,
+) -> impl std::future::Future<Output = ()> + Send + 'static {
+    async move {
+        while !*shutdown.borrow() {
+            if shutdown.changed().await.is_err() {
+                break;
+            }
+        }
+    }
+}
+
+/// Moves the WAL's contents into the database and truncates it, so that the database file is
+/// complete by itself.
+fn checkpoint_wal(storage: &Storage) -> anyhow::Result<()> {
+    let connection = storage.connection().context("Create database connection")?;
+    let busy = connection
+        .query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |row| {
+            row.get::<_, bool>(0)
+        })
+        .context("Checkpointing WAL")?;
+    anyhow::ensure!(!busy, "Database is still in use");
+
+    Ok(())
+}
+
+/// The database path handed to the Python subprocesses, which open the database read-only if
+/// this node is a read-only replica.
+fn python_database_path(storage: &Storage, read_only: bool) -> std::path::PathBuf {
+    match read_only {
+        true => format!("file:{}?mode=ro", storage.path().display()).into(),
+        false => storage.path().into(),
+    }
+}
+
 /// Verifies that the database matches the expected chain; throws an error if it does not.
+///
+/// Databases of custom chains are tagged with the [chain spec's identity](core::ChainSpec::identity)
+/// the first time they are used.
 fn verify_database_chain(storage: &Storage, expected: core::Chain) -> anyhow::Result<()> {
-    use pathfinder_lib::storage::StarknetBlocksTable;
+    use pathfinder_lib::storage::{ChainSpecTable, StarknetBlocksTable};
 
     let mut connection = storage.connection().context("Create database connection")?;
     let transaction = connection
         .transaction()
         .context("Create database transaction")?;
 
+    let tag = ChainSpecTable::get(&transaction).context("Read chain spec tag from the DB")?;
+
+    if let Chain::Custom(spec) = expected {
+        match tag {
+            Some((name, identity)) => anyhow::ensure!(
+                identity == spec.identity(),
+                "Database ({}) does not match the expected network ({})",
+                name,
+                expected
+            ),
+            None => {
+                let genesis = StarknetBlocksTable::get_hash(
+                    &transaction,
+                    core::StarknetBlockNumber::GENESIS.into(),
+                )
+                .context("Read genesis block from the DB")?;
+                if let Some(genesis) = genesis {
+                    anyhow::ensure!(
+                        genesis == spec.genesis.block_hash,
+                        "Database genesis block {} does not match the expected network ({})",
+                        genesis.0,
+                        expected
+                    );
+                }
+
+                ChainSpecTable::set(&transaction, spec).context("Tag DB with chain spec")?;
+                transaction
+                    .commit()
+                    .context("Commit database transaction")?;
+            }
+        }
+
+        return Ok(());
+    }
+
+    if let Some((name, _)) = tag {
+        anyhow::bail!(
+            "Database ({}) does not match the expected network ({})",
+            name,
+            expected
+        );
+    }
+
     let db_chain = match StarknetBlocksTable::get_chain(&transaction)
         .context("Get chain from genesis block in the DB")?
     {
@@ -208,6 +557,277 @@ fn verify_database_chain(storage: &Storage, expected: core::Chain) -> anyhow::Re
     Ok(())
 }
 
+/// Runs a `pathfinder db` maintenance command, see `pathfinder db --help`.
+///
+/// Each command locks the database exclusively, and fails if any node is using it.
+async fn db_command(args: impl Iterator<Item = std::ffi::OsString>) -> anyhow::Result<()> {
+    use clap::{Arg, Command};
+    use pathfinder_lib::{core::StarknetBlockNumber, state::repair, storage::maintenance};
+    use std::path::PathBuf;
+
+    fn database_arg() -> Arg<'static> {
+        Arg::new("database")
+            .help("Path to the database file")
+            .value_name("DATABASE")
+            .required(true)
+    }
+
+    fn block_arg(name: &'static str, help: &'static str) -> Arg<'static> {
+        Arg::new(name)
+            .long(name)
+            .help(help)
+            .takes_value(true)
+            .value_name("BLOCK")
+            .required(true)
+    }
+
+    fn block_number(args: &clap::ArgMatches, name: &str) -> anyhow::Result<StarknetBlockNumber> {
+        let number = args
+            .value_of(name)
+            .expect("block arguments are required")
+            .parse()
+            .with_context(|| format!("Parsing --{}", name))?;
+        StarknetBlockNumber::new(number).with_context(|| format!("--{} is too large", name))
+    }
+
+    let args = Command::new("pathfinder db")
+        .about("Database maintenance. Any node using the database must be stopped first.")
+        .subcommand_required(true)
+        .arg_required_else_help(true)
+        .subcommand(
+            Command::new("stats")
+                .about("Shows the row count and size of each table")
+                .arg(database_arg()),
+        )
+        .subcommand(
+            Command::new("check")
+                .about("Checks the consistency of the state tries")
+                .long_about("Checks that the trie roots of a random sample of blocks and contract states exist and hash correctly, and that the reference counts of all trie nodes cover their parents.")
+                .arg(database_arg())
+                .arg(
+                    Arg::new("samples")
+                        .long("samples")
+                        .help("Number of blocks and contract states to sample")
+                        .takes_value(true)
+                        .value_name("COUNT")
+                        .default_value("1000"),
+                ),
+        )
+        .subcommand(
+            Command::new("vacuum")
+                .about("Writes a compacted copy of the database to a new file")
+                .arg(database_arg())
+                .arg(
+                    Arg::new("destination")
+                        .help("Path of the new database file, which must not exist")
+                        .value_name("DESTINATION")
+                        .required(true),
+                ),
+        )
+        .subcommand(
+            Command::new("migrate")
+                .about("Migrates the database to the latest schema version")
+                .arg(database_arg())
+                .arg(
+                    Arg::new("dry-run")
+                        .long("dry-run")
+                        .help("Apply the migrations and roll them back, leaving the database unchanged"),
+                ),
+        )
+        .subcommand(
+            Command::new("rollback")
+                .about("Removes all blocks after the given block")
+                .long_about("Removes all blocks after the given block, as if the chain was reorganized to it. The node syncs the removed blocks again when started.")
+                .arg(database_arg())
+                .arg(block_arg("to", "The last block to keep")),
+        )
+        .subcommand(
+            Command::new("resync")
+                .about("Downloads a range of blocks again and compares them with the database")
+                .arg(database_arg())
+                .arg(block_arg("from", "The first block to compare"))
+                .arg(block_arg("to", "The last block to compare"))
+                .arg(
+                    Arg::new("sequencer-url")
+                        .long("sequencer-url")
+                        .help("Sequencer to download blocks from, required for custom chains")
+                        .takes_value(true)
+                        .value_name("URL"),
+                )
+                .arg(
+                    Arg::new("repair")
+                        .long("repair")
+                        .help("Remove all blocks from the first difference onwards, so that the node syncs them again"),
+                ),
+        )
+        .get_matches_from(args);
+
+    let (command, args) = args.subcommand().expect("subcommand is required");
+    let database = PathBuf::from(args.value_of("database").expect("database is required"));
+    anyhow::ensure!(
+        database.is_file(),
+        "Database {} does not exist",
+        database.display()
+    );
+
+    let _lock = DatabaseLock::exclusive(&database).context("Locking database")?;
+    let mut connection = maintenance::open(&database)?;
+
+    match command {
+        "stats" => {
+            let stats = maintenance::stats(&connection)?;
+            let size = |size: Option<u64>| match size {
+                Some(size) => format!("{:.1} MiB", size as f64 / (1024.0 * 1024.0)),
+                None => "n/a".to_owned(),
+            };
+
+            println!("Schema version: {}", stats.schema_version);
+            println!("Database size:  {}", size(Some(stats.size)));
+            println!("Trie nodes:     {}", stats.trie_nodes);
+            println!("Event index:    {}", size(stats.event_index_size));
+            println!();
+            println!("{:<40} {:>14} {:>14}", "Table", "Rows", "Size");
+            for table in stats.tables {
+                println!(
+                    "{:<40} {:>14} {:>14}",
+                    table.name,
+                    table.rows,
+                    size(table.size)
+                );
+            }
+        }
+        "check" => {
+            let samples = args
+                .value_of("samples")
+                .expect("has default value")
+                .parse()
+                .context("Parsing --samples")?;
+            let transaction = connection
+                .transaction()
+                .context("Create database transaction")?;
+            let report = maintenance::check(&transaction, samples)?;
+
+            for problem in &report.problems {
+                println!("{}", problem);
+            }
+            println!(
+                "Checked {} blocks and {} contract states.",
+                report.blocks_checked, report.contract_states_checked
+            );
+            anyhow::ensure!(
+                report.problems.is_empty(),
+                "Found {} problems",
+                report.problems.len()
+            );
+        }
+        "vacuum" => {
+            let destination = PathBuf::from(
+                args.value_of("destination")
+                    .expect("destination is required"),
+            );
+            let size_before = std::fs::metadata(&database)
+                .context("Reading database size")?
+                .len();
+            let size = maintenance::vacuum_into(&connection, &destination)?;
+
+            println!(
+                "Wrote {} ({} bytes, {} before).",
+                destination.display(),
+                size,
+                size_before
+            );
+        }
+        "migrate" => {
+            let dry_run = args.is_present("dry-run");
+            let migration = maintenance::migrate(&mut connection, dry_run)?;
+
+            match (migration.from == migration.to, dry_run) {
+                (true, _) => println!("Already at the latest version {}.", migration.to),
+                (false, true) => println!(
+                    "Migrating from version {} to {} would take {:?}, nothing was changed.",
+                    migration.from, migration.to, migration.duration
+                ),
+                (false, false) => println!(
+                    "Migrated from version {} to {} in {:?}.",
+                    migration.from, migration.to, migration.duration
+                ),
+            }
+        }
+        "rollback" => {
+            maintenance::ensure_migrated(&connection)?;
+            let to = block_number(args, "to")?;
+
+            let transaction = connection
+                .transaction()
+                .context("Create database transaction")?;
+            repair::rollback(&transaction, Some(to))?;
+            transaction
+                .commit()
+                .context("Commit database transaction")?;
+
+            println!("Rolled back to block {}.", to);
+        }
+        "resync" => {
+            use pathfinder_lib::storage::StarknetBlocksTable;
+
+            maintenance::ensure_migrated(&connection)?;
+            let from = block_number(args, "from")?;
+            let to = block_number(args, "to")?;
+            anyhow::ensure!(from <= to, "--from must not be after --to");
+
+            let sequencer = match args.value_of("sequencer-url") {
+                Some(url) => {
+                    let url = url.parse().context("Parsing --sequencer-url")?;
+                    sequencer::Client::with_url(url).context("Creating sequencer client")?
+                }
+                None => {
+                    let transaction = connection
+                        .transaction()
+                        .context("Create database transaction")?;
+                    let chain = StarknetBlocksTable::get_chain(&transaction)
+                        .context("Determining chain, use --sequencer-url for custom chains")?
+                        .context("Database is empty")?;
+                    sequencer::Client::new(chain).context("Creating sequencer client")?
+                }
+            };
+
+            let mismatches = repair::resync(&mut connection, &sequencer, from, to).await?;
+            for mismatch in &mismatches {
+                println!("Block {}: {}", mismatch.block, mismatch.reason);
+            }
+            println!(
+                "Compared blocks {} to {}, found {} differences.",
+                from,
+                to,
+                mismatches.len()
+            );
+
+            if let (Some(first), true) = (mismatches.first(), args.is_present("repair")) {
+                let keep = match first.block {
+                    StarknetBlockNumber::GENESIS => None,
+                    other => Some(other - 1),
+                };
+
+                let transaction = connection
+                    .transaction()
+                    .context("Create database transaction")?;
+                repair::rollback(&transaction, keep)?;
+                transaction
+                    .commit()
+                    .context("Commit database transaction")?;
+
+                println!(
+                    "Removed all blocks from block {} onwards, they are synced again when the node is started.",
+                    first.block
+                );
+            }
+        }
+        other => unreachable!("unknown subcommand {}", other),
+    }
+
+    Ok(())
+}
+
 #[cfg(feature = "tokio-console")]
 fn setup_tracing() {
     use tracing_subscriber::prelude::*;
@@ -2,7 +2,7 @@
 //!
 //! The python processes are executing `$REPO_ROOT/py/src/call.py` and communicate over by sending
 //! and receiving json + `'\n'`. Main entry point is the [`service::start`] which manages running
-//! given number of N processes. The python script uses sqlite to read pathfinder's database, which
+//! a pool of processes, scaled within a [`PoolSize`]. The python script uses sqlite to read pathfinder's database, which
 //! should not cause issues in WAL mode.
 //!
 //! Use of the call functionality happens through [`Handle::call`], which hands out futures in
@@ -21,12 +21,14 @@ use crate::rpc::v02::types::request::{BroadcastedInvokeTransaction, BroadcastedT
 use crate::sequencer::reply::StateUpdate;
 use crate::sequencer::request::add_transaction;
 use std::sync::Arc;
-use tokio::sync::{mpsc, oneshot, Mutex};
+use tokio::sync::oneshot;
 
 mod de;
 
 use de::ErrorKind;
 
+mod queue;
+
 mod ser;
 
 use ser::UsedChain;
@@ -36,16 +38,52 @@ mod sub_process;
 
 mod service;
 
-pub use service::start;
+pub use service::{start, PoolSize};
+
+/// Who a command is executed for. The commands of each caller wait in a lane of their own, so that
+/// a busy caller cannot starve the others.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Caller {
+    /// Commands queued outside of a request, whose caller is not known.
+    Unknown,
+    /// A request with an API key, by the name of the key.
+    Key(String),
+    /// A request without an API key, by its peer address.
+    Peer(std::net::IpAddr),
+    /// A connection to an [IPC](crate::rpc::ipc) socket, by its number.
+    Ipc(u64),
+}
+
+tokio::task_local! {
+    static CALLER: Caller;
+}
+
+/// Executes the commands `f` queues as `caller`.
+pub async fn with_caller<F: std::future::Future>(caller: Caller, f: F) -> F::Output {
+    CALLER.scope(caller, f).await
+}
 
 /// Handle to the python executors work queue. Cloneable and shareable.
 #[derive(Clone)]
 pub struct Handle {
-    command_tx: mpsc::Sender<(Command, tracing::Span)>,
-    chain: UsedChain,
+    queue: Arc<queue::CommandQueue>,
+    /// [None] on custom chains, where calls are refused.
+    chain: Option<UsedChain>,
+    workers: Arc<std::sync::atomic::AtomicUsize>,
 }
 
 impl Handle {
+    /// Number of sub-processes currently running or being launched.
+    pub fn running_workers(&self) -> usize {
+        self.workers.load(std::sync::atomic::Ordering::Relaxed)
+    }
+
+    /// Queues the command for the python executors, in the lane of the current [`Caller`].
+    fn send(&self, command: Command, span: tracing::Span) -> Result<(), CallFailure> {
+        let caller = CALLER.try_with(Caller::clone).unwrap_or(Caller::Unknown);
+        self.queue.push(caller, command, span)
+    }
+
     /// Execute the given call on the python cairo-lang executors.
     pub async fn call(
         &self,
@@ -54,23 +92,21 @@ impl Handle {
         diffs: Option<Arc<StateUpdate>>,
     ) -> Result<Vec<CallResultValue>, CallFailure> {
         use tracing::field::Empty;
+        let chain = self.chain.ok_or(CallFailure::UnsupportedChain)?;
         let (response, rx) = oneshot::channel();
 
         let continued_span = tracing::info_span!("ext_py_call", pid = Empty);
 
-        self.command_tx
-            .send((
-                Command::Call {
-                    call,
-                    at_block,
-                    chain: self.chain,
-                    diffs,
-                    response,
-                },
-                continued_span,
-            ))
-            .await
-            .map_err(|_| CallFailure::Shutdown)?;
+        self.send(
+            Command::Call {
+                call,
+                at_block,
+                chain,
+                diffs,
+                response,
+            },
+            continued_span,
+        )?;
 
         match rx.await {
             Ok(x) => x,
@@ -89,6 +125,7 @@ impl Handle {
         diffs: Option<Arc<StateUpdate>>,
     ) -> Result<FeeEstimate, CallFailure> {
         use tracing::field::Empty;
+        let chain = self.chain.ok_or(CallFailure::UnsupportedChain)?;
         let (response, rx) = oneshot::channel();
 
         let continued_span = tracing::info_span!("ext_py_est_fee", pid = Empty);
@@ -123,6 +160,7 @@ impl Handle {
                     })?,
                     sender_address: tx.sender_address,
                     nonce: tx.nonce,
+                    compiled_class_hash: tx.compiled_class_hash,
                 })
             }
             BroadcastedTransaction::Invoke(BroadcastedInvokeTransaction::V0(tx)) => {
@@ -149,20 +187,17 @@ impl Handle {
             }
         };
 
-        self.command_tx
-            .send((
-                Command::EstimateFee {
-                    transaction,
-                    at_block,
-                    gas_price,
-                    chain: self.chain,
-                    diffs,
-                    response,
-                },
-                continued_span,
-            ))
-            .await
-            .map_err(|_| CallFailure::Shutdown)?;
+        self.send(
+            Command::EstimateFee {
+                transaction,
+                at_block,
+                gas_price,
+                chain,
+                diffs,
+                response,
+            },
+            continued_span,
+        )?;
 
         match rx.await {
             Ok(x) => x,
@@ -184,8 +219,26 @@ pub enum CallFailure {
     ExecutionFailed(String),
     /// Internal, opaque-ish failure reason, none of them signal an issue with the call.
     Internal(&'static str),
+    /// Execution did not complete within [`Limits::timeout`].
+    Timeout,
     /// Channel related issue or shutting down.
     Shutdown,
+    /// Too many commands are already waiting for a subprocess.
+    Overloaded,
+    /// Calls cannot be executed on custom chains, see [`UsedChain::for_chain`].
+    UnsupportedChain,
+}
+
+/// Limits for the python subprocesses, enforced on the rust side.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Limits {
+    /// A command not completed within this time fails with [`CallFailure::Timeout`], and the
+    /// subprocess executing it is killed and replaced.
+    pub timeout: Option<std::time::Duration>,
+    /// A subprocess is replaced after executing this many commands.
+    pub max_requests: Option<std::num::NonZeroUsize>,
+    /// A subprocess is replaced after a command if its resident memory exceeds this many bytes.
+    pub max_memory: Option<u64>,
 }
 
 /// Where should the call code get the used `BlockInfo::gas_price`
@@ -227,10 +280,6 @@ impl From<ErrorKind> for CallFailure {
     }
 }
 
-/// Alias for the "mpmc" queue. Flume could had been used, but this is probably as fast as it needs
-/// to be.
-type SharedReceiver<T> = Arc<Mutex<mpsc::Receiver<T>>>;
-
 /// Command from outside of the module wrapped by [`Handle`] to be sent for execution in python.
 ///
 /// The used chain is tagged along not to require knowledge of it at the callers of [`Handle`] but to
@@ -294,6 +343,9 @@ enum SubprocessExitReason {
     Shutdown,
     Death,
     Cancellation,
+    Timeout,
+    Recycled,
+    ScaledDown,
     // If you add more reasons, remember to modify `all_labels`
 }
 
@@ -304,6 +356,9 @@ impl SubprocessExitReason {
             SubprocessExitReason::Shutdown => "shutdown",
             SubprocessExitReason::Death => "subprocess_died",
             SubprocessExitReason::Cancellation => "request_cancelled",
+            SubprocessExitReason::Timeout => "request_timeout",
+            SubprocessExitReason::Recycled => "recycled",
+            SubprocessExitReason::ScaledDown => "scaled_down",
         }
     }
 
@@ -311,9 +366,17 @@ impl SubprocessExitReason {
         use SubprocessExitReason::*;
         // this is quite the hassle maintaining this but so far we don't really have a better way
         // in rust than to do this
-        [UnrecoverableIO, Shutdown, Death, Cancellation]
-            .into_iter()
-            .map(|x| x.as_label())
+        [
+            UnrecoverableIO,
+            Shutdown,
+            Death,
+            Cancellation,
+            Timeout,
+            Recycled,
+            ScaledDown,
+        ]
+        .into_iter()
+        .map(|x| x.as_label())
     }
 }
 
@@ -325,6 +388,8 @@ enum SubprocessError {
     InvalidJson(serde_json::Error),
     /// Python sent us a response we couldn't understand
     InvalidResponse,
+    /// Python did not respond within [`Limits::timeout`]
+    Timeout,
 }
 
 impl From<std::io::Error> for SubprocessError {
@@ -371,16 +436,18 @@ mod tests {
             conn.execute("pragma user_version = 0", []).unwrap();
         }
 
-        let (_work_tx, work_rx) = tokio::sync::mpsc::channel(1);
-        let work_rx = tokio::sync::Mutex::new(work_rx);
+        let queue = std::sync::Arc::new(super::queue::CommandQueue::new(super::queue::MAX_DEPTH));
         let (status_tx, _status_rx) = tokio::sync::mpsc::channel(1);
         let (_shutdown_tx, shutdown_rx) = tokio::sync::broadcast::channel(1);
+        let (_retire_tx, retire_rx) = oneshot::channel();
 
         let err = launch_python(
             db_file.path().into(),
-            work_rx.into(),
+            queue,
             status_tx,
             shutdown_rx,
+            retire_rx,
+            super::Limits::default(),
         )
         .await;
 
@@ -412,7 +479,8 @@ mod tests {
 
         let (handle, jh) = super::start(
             PathBuf::from(db_file.path()),
-            std::num::NonZeroUsize::new(2).unwrap(),
+            super::PoolSize::fixed(std::num::NonZeroUsize::new(2).unwrap()),
+            super::Limits::default(),
             async move {
                 let _ = shutdown_rx.await;
             },
@@ -487,7 +555,8 @@ mod tests {
 
         let (handle, jh) = super::start(
             PathBuf::from(db_file.path()),
-            std::num::NonZeroUsize::new(1).unwrap(),
+            super::PoolSize::fixed(std::num::NonZeroUsize::new(1).unwrap()),
+            super::Limits::default(),
             async move {
                 let _ = shutdown_rx.await;
             },
@@ -583,7 +652,8 @@ mod tests {
 
         let (handle, jh) = super::start(
             PathBuf::from(db_file.path()),
-            std::num::NonZeroUsize::new(1).unwrap(),
+            super::PoolSize::fixed(std::num::NonZeroUsize::new(1).unwrap()),
+            super::Limits::default(),
             async move {
                 let _ = shutdown_rx.await;
             },
@@ -653,7 +723,8 @@ mod tests {
 
         let (handle, jh) = super::start(
             PathBuf::from(db_file.path()),
-            std::num::NonZeroUsize::new(1).unwrap(),
+            super::PoolSize::fixed(std::num::NonZeroUsize::new(1).unwrap()),
+            super::Limits::default(),
             async move {
                 let _ = shutdown_rx.await;
             },
@@ -693,6 +764,218 @@ mod tests {
         jh.await.unwrap();
     }
 
+    #[test_log::test(tokio::test)]
+    async fn in_flight_call_survives_shutdown() {
+        let db_file = tempfile::NamedTempFile::new().unwrap();
+
+        let s = crate::storage::Storage::migrate(
+            PathBuf::from(db_file.path()),
+            crate::storage::JournalMode::WAL,
+        )
+        .unwrap();
+
+        let mut conn = s.connection().unwrap();
+        conn.execute("PRAGMA foreign_keys = off", []).unwrap();
+
+        let tx = conn.transaction().unwrap();
+
+        deploy_test_contract_in_block_one(&tx);
+
+        tx.commit().unwrap();
+
+        let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
+
+        let (handle, jh) = super::start(
+            PathBuf::from(db_file.path()),
+            super::PoolSize::fixed(std::num::NonZeroUsize::new(1).unwrap()),
+            super::Limits::default(),
+            async move {
+                let _ = shutdown_rx.await;
+            },
+            crate::core::Chain::Testnet,
+        )
+        .await
+        .unwrap();
+
+        let call = tokio::task::spawn({
+            let handle = handle.clone();
+            async move { handle.call(get_value_call(), some_block_hash(), None).await }
+        });
+
+        // shut down only once the subprocess has taken the call
+        while handle.queue.take_load().peak_busy == 0 {
+            tokio::time::sleep(std::time::Duration::from_millis(1)).await;
+        }
+        shutdown_tx.send(()).unwrap();
+
+        let result = call.await.unwrap().unwrap();
+        assert_eq!(
+            result,
+            &[crate::core::CallResultValue(StarkHash::from(3u64))]
+        );
+
+        jh.await.unwrap();
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn timeout_replaces_subprocess() {
+        use crate::monitoring::metrics::test::{FakeRecorder, RecorderGuard};
+
+        let recorder = FakeRecorder::for_metrics(&["extpy_processes_exited_total"]);
+        let metrics = recorder.handle();
+        let _guard = RecorderGuard::lock(recorder);
+
+        let db_file = tempfile::NamedTempFile::new().unwrap();
+
+        let s = crate::storage::Storage::migrate(
+            PathBuf::from(db_file.path()),
+            crate::storage::JournalMode::WAL,
+        )
+        .unwrap();
+
+        let mut conn = s.connection().unwrap();
+        conn.execute("PRAGMA foreign_keys = off", []).unwrap();
+
+        let tx = conn.transaction().unwrap();
+
+        deploy_test_contract_in_block_one(&tx);
+
+        tx.commit().unwrap();
+
+        let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
+
+        let (handle, jh) = super::start(
+            PathBuf::from(db_file.path()),
+            super::PoolSize::fixed(std::num::NonZeroUsize::new(1).unwrap()),
+            super::Limits {
+                // no call completes this fast
+                timeout: Some(std::time::Duration::from_millis(1)),
+                ..Default::default()
+            },
+            async move {
+                let _ = shutdown_rx.await;
+            },
+            crate::core::Chain::Testnet,
+        )
+        .await
+        .unwrap();
+
+        let result = handle
+            .call(get_value_call(), some_block_hash(), None)
+            .await
+            .unwrap_err();
+        assert_eq!(result, super::CallFailure::Timeout);
+
+        // the subprocess was killed, so this can only be served by its replacement
+        let result = tokio::time::timeout(
+            std::time::Duration::from_secs(60),
+            handle.call(get_value_call(), some_block_hash(), None),
+        )
+        .await
+        .expect("subprocess was not replaced")
+        .unwrap_err();
+        assert_eq!(result, super::CallFailure::Timeout);
+
+        assert!(
+            metrics.get_counter_value_by_label(
+                "extpy_processes_exited_total",
+                [("reason", "request_timeout")]
+            ) >= 1
+        );
+        assert_eq!(handle.running_workers(), 1);
+
+        shutdown_tx.send(()).unwrap();
+        jh.await.unwrap();
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn recycles_after_max_requests() {
+        use crate::monitoring::metrics::test::{FakeRecorder, RecorderGuard};
+
+        let recorder = FakeRecorder::for_metrics(&["extpy_processes_exited_total"]);
+        let metrics = recorder.handle();
+        let _guard = RecorderGuard::lock(recorder);
+
+        let db_file = tempfile::NamedTempFile::new().unwrap();
+
+        let s = crate::storage::Storage::migrate(
+            PathBuf::from(db_file.path()),
+            crate::storage::JournalMode::WAL,
+        )
+        .unwrap();
+
+        let mut conn = s.connection().unwrap();
+        conn.execute("PRAGMA foreign_keys = off", []).unwrap();
+
+        let tx = conn.transaction().unwrap();
+
+        deploy_test_contract_in_block_one(&tx);
+
+        tx.commit().unwrap();
+
+        let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
+
+        let (handle, jh) = super::start(
+            PathBuf::from(db_file.path()),
+            super::PoolSize::fixed(std::num::NonZeroUsize::new(1).unwrap()),
+            super::Limits {
+                max_requests: Some(std::num::NonZeroUsize::new(1).unwrap()),
+                ..Default::default()
+            },
+            async move {
+                let _ = shutdown_rx.await;
+            },
+            crate::core::Chain::Testnet,
+        )
+        .await
+        .unwrap();
+
+        for _ in 0..3 {
+            let result = handle
+                .call(get_value_call(), some_block_hash(), None)
+                .await
+                .unwrap();
+            assert_eq!(
+                result,
+                &[crate::core::CallResultValue(StarkHash::from(3u64))]
+            );
+        }
+
+        // each replacement is launched only after the previous subprocess exited
+        assert!(
+            metrics.get_counter_value_by_label(
+                "extpy_processes_exited_total",
+                [("reason", "recycled")]
+            ) >= 2
+        );
+
+        shutdown_tx.send(()).unwrap();
+        jh.await.unwrap();
+    }
+
+    /// Reads the storage value of the contract deployed by [deploy_test_contract_in_block_one].
+    fn get_value_call() -> super::Call {
+        super::Call {
+            contract_address: crate::core::ContractAddress::new_or_panic(starkhash!(
+                "057dde83c18c0efe7123c36a52d704cf27d5c38cdf0b1e1edc3b0dae3ee4e374"
+            )),
+            calldata: vec![crate::core::CallParam(starkhash!("84"))],
+            entry_point_selector: Some(crate::core::EntryPoint::hashed(&b"get_value"[..])),
+            signature: Default::default(),
+            max_fee: super::Call::DEFAULT_MAX_FEE,
+            version: super::Call::DEFAULT_VERSION,
+            nonce: super::Call::DEFAULT_NONCE,
+        }
+    }
+
+    /// The block created by [deploy_test_contract_in_block_one].
+    fn some_block_hash() -> super::BlockHashNumberOrLatest {
+        crate::core::StarknetBlockHash(
+            StarkHash::from_be_slice(&b"some blockhash somewhere"[..]).unwrap(),
+        )
+        .into()
+    }
+
     #[test_log::test(tokio::test)]
     async fn call_with_pending_updates() {
         use crate::sequencer::reply::StateUpdate;
@@ -718,7 +1001,8 @@ mod tests {
 
         let (handle, jh) = super::start(
             PathBuf::from(db_file.path()),
-            std::num::NonZeroUsize::new(1).unwrap(),
+            super::PoolSize::fixed(std::num::NonZeroUsize::new(1).unwrap()),
+            super::Limits::default(),
             async move {
                 let _ = shutdown_rx.await;
             },
@@ -772,6 +1056,7 @@ mod tests {
                 },
                 deployed_contracts: vec![],
                 declared_contracts: vec![],
+                declared_classes: Vec::new(),
                 nonces: std::collections::HashMap::new(),
             },
         });
@@ -0,0 +1,456 @@
+//! The queue of commands waiting for a python subprocess.
+
+use super::{CallFailure, Caller, Command};
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+
+static METRIC_QUEUE_DEPTH: &str = "extpy_queue_depth";
+static METRIC_QUEUE_WAIT: &str = "extpy_queue_wait_seconds";
+
+/// Number of commands which can wait for a subprocess, further ones fail right away with
+/// [`CallFailure::Overloaded`].
+pub(super) const MAX_DEPTH: usize = 1024;
+
+/// Multi-producer multi-consumer queue shared by the [`Handle`](super::Handle) and the
+/// subprocesses.
+///
+/// Each [`Caller`] has a lane of its own, and the lanes are taken from in turns, so that a burst
+/// of commands from one caller cannot starve the others. The queue also tracks how long commands
+/// wait and how many subprocesses are busy, which drives the scaling of the pool.
+pub(super) struct CommandQueue {
+    lanes: Mutex<Lanes>,
+    notify: Notify,
+    /// Number of subprocesses executing a command.
+    busy: AtomicUsize,
+    /// Highest value of `busy` since the last [`CommandQueue::take_load`].
+    peak_busy: AtomicUsize,
+    /// Longest wait in microseconds since the last [`CommandQueue::take_load`].
+    max_wait_us: AtomicU64,
+}
+
+struct Lanes {
+    lanes: HashMap<Caller, VecDeque<Queued>>,
+    /// The callers with waiting commands, in the order their lanes are taken from.
+    turns: VecDeque<Caller>,
+    /// Number of commands waiting in all of the lanes.
+    depth: usize,
+    max_depth: usize,
+    closed: bool,
+}
+
+/// A command waiting in the [`CommandQueue`].
+pub(super) struct Queued {
+    pub command: Command,
+    pub span: tracing::Span,
+    enqueued_at: Instant,
+}
+
+/// The load of the subprocesses since the previous [`CommandQueue::take_load`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct Load {
+    /// Highest number of subprocesses executing a command at the same time.
+    pub peak_busy: usize,
+    /// Longest time a command waited for a subprocess.
+    pub max_wait: Duration,
+}
+
+/// Counts a subprocess as busy until dropped.
+pub(super) struct Busy<'a>(&'a CommandQueue);
+
+impl Drop for Busy<'_> {
+    fn drop(&mut self) {
+        self.0.busy.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+impl CommandQueue {
+    /// Creates a queue holding up to `max_depth` waiting commands.
+    pub(super) fn new(max_depth: usize) -> Self {
+        metrics::register_gauge!(METRIC_QUEUE_DEPTH);
+        metrics::describe_gauge!(
+            METRIC_QUEUE_DEPTH,
+            metrics::Unit::Count,
+            "number of calls and fee estimations waiting for a subprocess."
+        );
+        metrics::register_histogram!(METRIC_QUEUE_WAIT);
+        metrics::describe_histogram!(
+            METRIC_QUEUE_WAIT,
+            metrics::Unit::Seconds,
+            "time calls and fee estimations waited for a subprocess."
+        );
+
+        Self {
+            lanes: Mutex::new(Lanes {
+                lanes: HashMap::new(),
+                turns: VecDeque::new(),
+                depth: 0,
+                max_depth,
+                closed: false,
+            }),
+            notify: Notify::new(),
+            busy: AtomicUsize::new(0),
+            peak_busy: AtomicUsize::new(0),
+            max_wait_us: AtomicU64::new(0),
+        }
+    }
+
+    /// Queues the command in the lane of `caller`.
+    ///
+    /// Fails with [`CallFailure::Shutdown`] once the queue has been closed, and with
+    /// [`CallFailure::Overloaded`] if it is full.
+    pub(super) fn push(
+        &self,
+        caller: Caller,
+        command: Command,
+        span: tracing::Span,
+    ) -> Result<(), CallFailure> {
+        {
+            let mut lanes = self.lanes.lock().unwrap_or_else(|e| e.into_inner());
+            if lanes.closed {
+                return Err(CallFailure::Shutdown);
+            }
+            if lanes.depth >= lanes.max_depth {
+                return Err(CallFailure::Overloaded);
+            }
+
+            lanes.push(
+                caller,
+                Queued {
+                    command,
+                    span,
+                    enqueued_at: Instant::now(),
+                },
+            );
+            lanes.set_depth_gauge();
+        }
+
+        self.notify.notify_one();
+        Ok(())
+    }
+
+    /// Waits for the next command, skipping those whose caller has already left.
+    ///
+    /// Never returns once the queue has been closed, so this needs to be raced against a shutdown
+    /// signal.
+    pub(super) async fn pop(&self) -> Queued {
+        loop {
+            let next = {
+                let mut lanes = self.lanes.lock().unwrap_or_else(|e| e.into_inner());
+                let next = lanes.pop_open();
+                if next.is_some() && !lanes.is_empty() {
+                    // notify_one stores only a single permit, so pass it on for the rest.
+                    self.notify.notify_one();
+                }
+                lanes.set_depth_gauge();
+                next
+            };
+
+            if let Some(queued) = next {
+                let wait = queued.enqueued_at.elapsed();
+                metrics::histogram!(METRIC_QUEUE_WAIT, wait.as_secs_f64());
+                self.max_wait_us
+                    .fetch_max(wait.as_micros() as u64, Ordering::Relaxed);
+                return queued;
+            }
+
+            self.notify.notified().await;
+        }
+    }
+
+    /// Counts the calling subprocess as busy while the returned guard is alive.
+    pub(super) fn busy(&self) -> Busy<'_> {
+        let busy = self.busy.fetch_add(1, Ordering::Relaxed) + 1;
+        self.peak_busy.fetch_max(busy, Ordering::Relaxed);
+        Busy(self)
+    }
+
+    /// Returns the load since the previous call.
+    pub(super) fn take_load(&self) -> Load {
+        let busy = self.busy.load(Ordering::Relaxed);
+        let peak_busy = self.peak_busy.swap(busy, Ordering::Relaxed).max(busy);
+        let max_wait_us = self.max_wait_us.swap(0, Ordering::Relaxed);
+
+        // Commands still waiting count as well, otherwise a pool without any free subprocess
+        // would look idle.
+        let oldest_waiting = {
+            let lanes = self.lanes.lock().unwrap_or_else(|e| e.into_inner());
+            lanes.oldest().map(|queued| queued.enqueued_at.elapsed())
+        };
+        if oldest_waiting.is_some() {
+            // A subprocess which exited while being woken up could have swallowed the wakeup.
+            self.notify.notify_one();
+        }
+
+        Load {
+            peak_busy,
+            max_wait: Duration::from_micros(max_wait_us).max(oldest_waiting.unwrap_or_default()),
+        }
+    }
+
+    /// Rejects any new commands, and drops the queued ones which fails them with
+    /// [`CallFailure::Shutdown`](super::CallFailure::Shutdown).
+    pub(super) fn close(&self) {
+        let mut lanes = self.lanes.lock().unwrap_or_else(|e| e.into_inner());
+        lanes.closed = true;
+        lanes.lanes.clear();
+        lanes.turns.clear();
+        lanes.depth = 0;
+        lanes.set_depth_gauge();
+    }
+}
+
+impl Lanes {
+    fn is_empty(&self) -> bool {
+        self.depth == 0
+    }
+
+    fn push(&mut self, caller: Caller, queued: Queued) {
+        let lane = self.lanes.entry(caller.clone()).or_default();
+        if lane.is_empty() {
+            self.turns.push_back(caller);
+        }
+        lane.push_back(queued);
+        self.depth += 1;
+    }
+
+    /// Takes the next command in turns from the lanes, dropping the ones whose caller has left.
+    fn pop_open(&mut self) -> Option<Queued> {
+        loop {
+            let caller = self.turns.pop_front()?;
+            let lane = self
+                .lanes
+                .get_mut(&caller)
+                .expect("Callers with a turn have a lane");
+            let queued = lane.pop_front().expect("Lanes with a turn are not empty");
+            self.depth -= 1;
+
+            // the caller's next command has to wait for the others to take their turn
+            if lane.is_empty() {
+                self.lanes.remove(&caller);
+            } else {
+                self.turns.push_back(caller);
+            }
+
+            if queued.command.is_closed() {
+                // quickly loadshed, as the caller has already left.
+                continue;
+            }
+
+            return Some(queued);
+        }
+    }
+
+    fn oldest(&self) -> Option<&Queued> {
+        self.lanes
+            .values()
+            .filter_map(VecDeque::front)
+            .min_by_key(|queued| queued.enqueued_at)
+    }
+
+    fn set_depth_gauge(&self) {
+        metrics::gauge!(METRIC_QUEUE_DEPTH, self.depth as f64);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cairo::ext_py::{BlockHashNumberOrLatest, GasPriceSource};
+    use crate::core::{CallResultValue, ContractAddress, EntryPoint};
+    use crate::monitoring::metrics::test::{FakeRecorder, RecorderGuard};
+    use crate::rpc::v01::types::request::Call;
+    use crate::sequencer::request::add_transaction::{AddTransaction, InvokeFunction};
+    use tokio::sync::oneshot;
+
+    type CallReceiver = oneshot::Receiver<Result<Vec<CallResultValue>, CallFailure>>;
+
+    /// Returns a call, and the receiver which needs to be kept alive for it to stay queued.
+    fn call() -> (Command, CallReceiver) {
+        let (response, rx) = oneshot::channel();
+        let command = Command::Call {
+            call: Call {
+                contract_address: ContractAddress::new_or_panic(crate::starkhash!("01")),
+                calldata: vec![],
+                entry_point_selector: Some(EntryPoint(crate::starkhash!("02"))),
+                signature: vec![],
+                max_fee: Call::DEFAULT_MAX_FEE,
+                version: Call::DEFAULT_VERSION,
+                nonce: Call::DEFAULT_NONCE,
+            },
+            at_block: BlockHashNumberOrLatest::Latest,
+            chain: crate::cairo::ext_py::UsedChain::Goerli,
+            diffs: None,
+            response,
+        };
+        (command, rx)
+    }
+
+    /// Returns a fee estimation, and the receiver which needs to be kept alive for it to stay
+    /// queued.
+    fn estimate() -> (Command, impl Sized) {
+        let (response, rx) = oneshot::channel();
+        let command = Command::EstimateFee {
+            transaction: AddTransaction::Invoke(InvokeFunction {
+                version: Call::DEFAULT_VERSION,
+                max_fee: Call::DEFAULT_MAX_FEE,
+                signature: vec![],
+                nonce: None,
+                contract_address: ContractAddress::new_or_panic(crate::starkhash!("01")),
+                entry_point_selector: Some(EntryPoint(crate::starkhash!("02"))),
+                calldata: vec![],
+            }),
+            at_block: BlockHashNumberOrLatest::Latest,
+            gas_price: GasPriceSource::PastBlock,
+            chain: crate::cairo::ext_py::UsedChain::Goerli,
+            diffs: None,
+            response,
+        };
+        (command, rx)
+    }
+
+    fn is_call(queued: &Queued) -> bool {
+        matches!(queued.command, Command::Call { .. })
+    }
+
+    fn key(name: &str) -> Caller {
+        Caller::Key(name.to_owned())
+    }
+
+    #[tokio::test]
+    async fn lanes_take_turns() {
+        let _guard = RecorderGuard::lock_as_noop();
+        let queue = CommandQueue::new(MAX_DEPTH);
+
+        let mut receivers = Vec::new();
+        for _ in 0..3 {
+            let (command, rx) = estimate();
+            queue
+                .push(key("busy"), command, tracing::Span::none())
+                .unwrap();
+            receivers.push(rx);
+        }
+        let (command, _call_rx) = call();
+        queue
+            .push(key("quiet"), command, tracing::Span::none())
+            .unwrap();
+
+        // The call does not wait for all of the earlier fee estimations of the other caller.
+        assert!(!is_call(&queue.pop().await));
+        assert!(is_call(&queue.pop().await));
+        assert!(!is_call(&queue.pop().await));
+        assert!(!is_call(&queue.pop().await));
+    }
+
+    #[tokio::test]
+    async fn skips_closed() {
+        let _guard = RecorderGuard::lock_as_noop();
+        let queue = CommandQueue::new(MAX_DEPTH);
+
+        let (command, rx) = call();
+        queue
+            .push(Caller::Unknown, command, tracing::Span::none())
+            .unwrap();
+        drop(rx);
+        let (command, _rx) = estimate();
+        queue
+            .push(Caller::Unknown, command, tracing::Span::none())
+            .unwrap();
+
+        assert!(!is_call(&queue.pop().await));
+    }
+
+    #[tokio::test]
+    async fn close_fails_queued() {
+        let _guard = RecorderGuard::lock_as_noop();
+        let queue = CommandQueue::new(MAX_DEPTH);
+
+        let (command, rx) = call();
+        queue
+            .push(Caller::Unknown, command, tracing::Span::none())
+            .unwrap();
+        queue.close();
+
+        assert!(rx.await.is_err());
+        let (command, _rx) = call();
+        assert_eq!(
+            queue.push(Caller::Unknown, command, tracing::Span::none()),
+            Err(CallFailure::Shutdown)
+        );
+    }
+
+    #[tokio::test]
+    async fn full_queue_overloads() {
+        let _guard = RecorderGuard::lock_as_noop();
+        let queue = CommandQueue::new(2);
+
+        let mut receivers = Vec::new();
+        for caller in [key("a"), key("b")] {
+            let (command, rx) = call();
+            queue.push(caller, command, tracing::Span::none()).unwrap();
+            receivers.push(rx);
+        }
+
+        // The limit is shared by all of the lanes.
+        let (command, _rx) = call();
+        assert_eq!(
+            queue.push(key("c"), command, tracing::Span::none()),
+            Err(CallFailure::Overloaded)
+        );
+
+        let _queued = queue.pop().await;
+        let (command, _rx) = call();
+        queue
+            .push(key("c"), command, tracing::Span::none())
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn load() {
+        let _guard = RecorderGuard::lock_as_noop();
+        let queue = CommandQueue::new(MAX_DEPTH);
+
+        let (command, _rx) = call();
+        queue
+            .push(Caller::Unknown, command, tracing::Span::none())
+            .unwrap();
+        let _queued = queue.pop().await;
+
+        let first = queue.busy();
+        let second = queue.busy();
+        drop(first);
+        assert_eq!(queue.take_load().peak_busy, 2);
+
+        drop(second);
+        let load = queue.take_load();
+        assert_eq!(load.peak_busy, 1);
+        assert_eq!(load.max_wait, Duration::ZERO);
+        assert_eq!(queue.take_load().peak_busy, 0);
+    }
+
+    #[tokio::test]
+    async fn depth() {
+        let recorder = FakeRecorder::for_metrics(&["extpy_queue_depth"]);
+        let handle = recorder.handle();
+        let _guard = RecorderGuard::lock(recorder);
+
+        let queue = CommandQueue::new(MAX_DEPTH);
+        let (command, _call_rx) = call();
+        queue
+            .push(Caller::Unknown, command, tracing::Span::none())
+            .unwrap();
+        let (command, _estimate_rx) = estimate();
+        queue
+            .push(Caller::Unknown, command, tracing::Span::none())
+            .unwrap();
+        assert_eq!(handle.get_gauge_value_by_label(METRIC_QUEUE_DEPTH, []), 2.0);
+
+        let _queued = queue.pop().await;
+        assert_eq!(handle.get_gauge_value_by_label(METRIC_QUEUE_DEPTH, []), 1.0);
+
+        queue.close();
+        assert_eq!(handle.get_gauge_value_by_label(METRIC_QUEUE_DEPTH, []), 0.0);
+    }
+}
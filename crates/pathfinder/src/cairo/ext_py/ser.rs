@@ -50,13 +50,18 @@ pub(crate) enum UsedChain {
     Goerli,
 }
 
-impl From<crate::core::Chain> for UsedChain {
-    fn from(c: crate::core::Chain) -> Self {
+impl UsedChain {
+    /// The chain whose general config the python side should use, or [None] for custom chains.
+    ///
+    /// The python side only knows the chain ids and fee token addresses of the public chains, and
+    /// using another chain's would give wrong transaction hashes and validation results.
+    pub fn for_chain(c: crate::core::Chain) -> Option<Self> {
         match c {
-            crate::core::Chain::Mainnet => UsedChain::Mainnet,
-            crate::core::Chain::Testnet => UsedChain::Goerli,
-            crate::core::Chain::Testnet2 => UsedChain::Goerli,
-            crate::core::Chain::Integration => UsedChain::Goerli,
+            crate::core::Chain::Mainnet => Some(UsedChain::Mainnet),
+            crate::core::Chain::Testnet => Some(UsedChain::Goerli),
+            crate::core::Chain::Testnet2 => Some(UsedChain::Goerli),
+            crate::core::Chain::Integration => Some(UsedChain::Goerli),
+            crate::core::Chain::Custom(_) => None,
         }
     }
 }
@@ -1,14 +1,22 @@
 //! Starting and maintaining processes, and the main entry point
 
-use super::{sub_process::launch_python, Command, Handle, SharedReceiver, SubProcessEvent};
+use super::{
+    queue::{CommandQueue, Load, MAX_DEPTH},
+    sub_process::launch_python,
+    Handle, Limits, SubProcessEvent, SubprocessExitInfo, UsedChain,
+};
 use anyhow::Context;
+use std::future::Future;
+use std::num::NonZeroUsize;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
-use tokio::sync::{broadcast, mpsc, Mutex};
+use tokio::sync::{broadcast, mpsc, oneshot};
 use tracing::Instrument;
-use tracing::{info, trace, warn};
+use tracing::{debug, info, trace, warn};
 
-/// Starts to maintain a pool of `count` sub-processes which execute the calls.
+/// Starts to maintain a pool of sub-processes which execute the calls, within the bounds of
+/// `size`.
 ///
 /// In general, the launching currently assumes `python3` is a compatible Python
 /// interpreter in an environment where our Python dependencies are set up properly.
@@ -19,40 +27,42 @@ use tracing::{info, trace, warn};
 /// - By installing Python dependencies in a way that the _global_ `python3` interpreter can
 /// import them.
 ///
+/// All of the minimum number of sub-processes are launched right away, after which the pool scales
+/// between the bounds according to how long commands wait for a sub-process.
+/// Subprocesses exceeding the [`Limits`] are replaced.
+///
 /// Returns an error if executing calls in a sub-process is not supported.
-#[tracing::instrument(name = "ext_py", skip_all, fields(%count))]
+#[tracing::instrument(name = "ext_py", skip_all, fields(min = %size.min, max = %size.max))]
 pub async fn start(
     database_path: PathBuf,
-    count: std::num::NonZeroUsize,
+    size: PoolSize,
+    limits: Limits,
     stop_flag: impl std::future::Future<Output = ()> + Send + 'static,
     chain: crate::core::Chain,
 ) -> anyhow::Result<(Handle, tokio::task::JoinHandle<()>)> {
     use futures::stream::StreamExt;
 
-    // channel sizes are conservative but probably enough for many workers; should investigate mpmc
-    // if the lock overhead on command_rx before making these deeper.
-    let (command_tx, command_rx) = mpsc::channel(1);
+    let queue = Arc::new(CommandQueue::new(MAX_DEPTH));
     let (status_tx, mut status_rx) = mpsc::channel(1);
     // this will never need to become deeper
     let (child_shutdown_tx, _) = broadcast::channel(1);
-    let command_rx: SharedReceiver<(Command, tracing::Span)> = Arc::new(Mutex::new(command_rx));
 
     let metrics = Metrics::register();
 
     // TODO: might be better to use tokio's JoinSet?
     let mut joinhandles = futures::stream::FuturesUnordered::new();
+    let mut workers = Workers::default();
 
-    let jh = tokio::task::spawn(
-        launch_python(
-            database_path.clone(),
-            Arc::clone(&command_rx),
-            status_tx.clone(),
-            child_shutdown_tx.subscribe(),
-        )
-        .in_current_span(),
-    );
-
-    joinhandles.push(jh);
+    let (worker, _, retire_rx) = workers.acquire();
+    joinhandles.push(spawn_subprocess(
+        worker,
+        database_path.clone(),
+        Arc::clone(&queue),
+        status_tx.clone(),
+        child_shutdown_tx.subscribe(),
+        retire_rx,
+        limits,
+    ));
 
     // race the process launched notification or the task completion from joinhandles
     tokio::select! {
@@ -64,7 +74,7 @@ pub async fn start(
                 },
             }
         },
-        Some(res) = &mut joinhandles.next() => {
+        Some((_, res)) = &mut joinhandles.next() => {
             match res {
                 Ok(Ok(t)) => unreachable!("First subprocess should not have exited successfully: {:?}", t),
                 // this is the failure to start
@@ -75,38 +85,59 @@ pub async fn start(
         }
     };
 
+    // warm start: the first one worked, so launch the rest of the minimum ahead of any demand
+    for _ in 1..size.min.get() {
+        let (worker, _, retire_rx) = workers.acquire();
+        joinhandles.push(spawn_subprocess(
+            worker,
+            database_path.clone(),
+            Arc::clone(&queue),
+            status_tx.clone(),
+            child_shutdown_tx.subscribe(),
+            retire_rx,
+            limits,
+        ));
+    }
+
+    let running = Arc::new(AtomicUsize::new(joinhandles.len()));
+
     let handle = Handle {
-        command_tx: command_tx.clone(),
-        chain: chain.into(),
+        queue: Arc::clone(&queue),
+        chain: UsedChain::for_chain(chain),
+        workers: Arc::clone(&running),
     };
 
     let jh = tokio::task::spawn(
         async move {
-            const WAIT_BEFORE_SPAWN: std::time::Duration = std::time::Duration::from_secs(1);
+            const SCALING_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
 
-            // use a sleep activated periodically before launching new processes
+            // scaling decisions are taken periodically, which also limits launching new processes
             // not to overwhelm the system
-            let wait_before_spawning = tokio::time::sleep(WAIT_BEFORE_SPAWN);
-            tokio::pin!(wait_before_spawning);
+            let scaling_interval = tokio::time::sleep(SCALING_INTERVAL);
+            tokio::pin!(scaling_interval);
 
             tokio::pin!(stop_flag);
 
+            let mut scaler = Scaler::new(size);
+
             loop {
-                let mut spawn = false;
+                let mut scaling = Scaling::Keep;
                 tokio::select! {
                     _ = &mut stop_flag => {
                         trace!("Starting shutdown");
-                        // this should be enough to kick everyone off the locking, queue receiving
+                        // fail the waiting commands, and kick every subprocess off the queue
+                        queue.close();
                         let _ = child_shutdown_tx.send(());
 
                         loop {
                             let next = joinhandles.next().await;
 
                             match next {
-                                Some(res) => { on_joined_subprocess(res, &metrics); },
+                                Some((_, res)) => { on_joined_subprocess(res, &metrics); },
                                 None => break,
                             }
                         }
+                        running.store(0, Ordering::Relaxed);
                         info!("Shutdown complete");
                         return;
                     }
@@ -117,33 +148,53 @@ pub async fn start(
                             },
                         }
                     },
-                    Some(res) = joinhandles.next() => {
+                    Some((worker, res)) = joinhandles.next() => {
+                        workers.release(worker);
                         let allow_spawn_right_away = on_joined_subprocess(res, &metrics);
                         // we should spawn it immediatedly if empty
-                        spawn = allow_spawn_right_away && joinhandles.is_empty();
+                        if allow_spawn_right_away && joinhandles.is_empty() {
+                            scaling = Scaling::Replace;
+                        }
                     }
-                    _ = &mut wait_before_spawning => {
-                        // spawn if needed
-                        spawn = count.get() > joinhandles.len();
-                        wait_before_spawning
+                    _ = &mut scaling_interval => {
+                        scaling = scaler.decide(workers.active(), queue.take_load());
+                        scaling_interval
                             .as_mut()
-                            .reset(tokio::time::Instant::now() + WAIT_BEFORE_SPAWN);
+                            .reset(tokio::time::Instant::now() + SCALING_INTERVAL);
                     }
                 }
 
-                if spawn {
-                    let jh = tokio::task::spawn(
-                        launch_python(
+                match scaling {
+                    Scaling::Keep => {}
+                    Scaling::Replace | Scaling::Up => {
+                        let (worker, restart, retire_rx) = workers.acquire();
+                        if restart {
+                            metrics.increment_restarted(worker);
+                        }
+                        if scaling == Scaling::Up {
+                            debug!(%worker, "Scaling up");
+                            metrics.increment_scaling("up");
+                        }
+
+                        joinhandles.push(spawn_subprocess(
+                            worker,
                             database_path.clone(),
-                            Arc::clone(&command_rx),
+                            Arc::clone(&queue),
                             status_tx.clone(),
                             child_shutdown_tx.subscribe(),
-                        )
-                        .in_current_span(),
-                    );
-
-                    joinhandles.push(jh);
+                            retire_rx,
+                            limits,
+                        ));
+                    }
+                    Scaling::Down => {
+                        if let Some(worker) = workers.retire() {
+                            debug!(%worker, "Scaling down");
+                            metrics.increment_scaling("down");
+                        }
+                    }
                 }
+
+                running.store(joinhandles.len(), Ordering::Relaxed);
             }
         }
         .in_current_span(),
@@ -152,11 +203,188 @@ pub async fn start(
     Ok((handle, jh))
 }
 
+/// Bounds for the number of subprocesses in the pool.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolSize {
+    min: NonZeroUsize,
+    max: NonZeroUsize,
+}
+
+impl PoolSize {
+    /// Returns `None` if `max` is less than `min`.
+    pub fn new(min: NonZeroUsize, max: NonZeroUsize) -> Option<Self> {
+        (min <= max).then(|| PoolSize { min, max })
+    }
+
+    /// A pool which keeps `count` subprocesses running and does not scale.
+    pub fn fixed(count: NonZeroUsize) -> Self {
+        PoolSize {
+            min: count,
+            max: count,
+        }
+    }
+
+    /// The number of subprocesses launched on start and kept running when idle.
+    pub fn min(&self) -> NonZeroUsize {
+        self.min
+    }
+
+    /// The number of subprocesses the pool can scale up to.
+    pub fn max(&self) -> NonZeroUsize {
+        self.max
+    }
+}
+
+/// Spawns a task launching a subprocess, which resolves to the `worker` number and the outcome.
+fn spawn_subprocess(
+    worker: usize,
+    database_path: PathBuf,
+    queue: Arc<CommandQueue>,
+    status_tx: mpsc::Sender<SubProcessEvent>,
+    shutdown_rx: broadcast::Receiver<()>,
+    retire_rx: oneshot::Receiver<()>,
+    limits: Limits,
+) -> impl Future<Output = (usize, SubprocessJoinResult)> {
+    use futures::FutureExt;
+
+    tokio::task::spawn(
+        launch_python(
+            database_path,
+            queue,
+            status_tx,
+            shutdown_rx,
+            retire_rx,
+            limits,
+        )
+        .in_current_span(),
+    )
+    .map(move |res| (worker, res))
+}
+
+type SubprocessJoinResult =
+    Result<Result<SubprocessExitInfo, anyhow::Error>, tokio::task::JoinError>;
+
+/// Numbers the subprocesses from zero, reusing the numbers of the exited ones, so that a new
+/// subprocess can be seen as a restart of an earlier one.
+#[derive(Default)]
+struct Workers {
+    /// The subprocess with the number of the index is running if the value is `Some`, and
+    /// retiring unless it holds the sender used to retire it.
+    running: Vec<Option<Option<oneshot::Sender<()>>>>,
+}
+
+impl Workers {
+    /// Returns the lowest free number, whether it was used by an earlier subprocess, and the
+    /// receiver through which the subprocess is retired.
+    fn acquire(&mut self) -> (usize, bool, oneshot::Receiver<()>) {
+        let (retire_tx, retire_rx) = oneshot::channel();
+        let (worker, restart) = match self.running.iter().position(Option::is_none) {
+            Some(worker) => (worker, true),
+            None => {
+                self.running.push(None);
+                (self.running.len() - 1, false)
+            }
+        };
+        self.running[worker] = Some(Some(retire_tx));
+        (worker, restart, retire_rx)
+    }
+
+    fn release(&mut self, worker: usize) {
+        self.running[worker] = None;
+    }
+
+    /// Number of running subprocesses which are not retiring.
+    fn active(&self) -> usize {
+        self.running
+            .iter()
+            .filter(|worker| matches!(worker, Some(Some(_))))
+            .count()
+    }
+
+    /// Retires the active subprocess with the highest number, returning its number.
+    ///
+    /// The subprocess exits once it has finished its current command, if any.
+    fn retire(&mut self) -> Option<usize> {
+        let worker = self
+            .running
+            .iter()
+            .rposition(|worker| matches!(worker, Some(Some(_))))?;
+        if let Some(Some(retire_tx)) = self.running[worker].as_mut().map(Option::take) {
+            // the subprocess might have just exited on its own, which is fine
+            let _ = retire_tx.send(());
+        }
+        Some(worker)
+    }
+}
+
+/// A decision of the [`Scaler`], or the need to replace an exited subprocess right away.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Scaling {
+    Keep,
+    Replace,
+    Up,
+    Down,
+}
+
+/// Commands waiting longer than this for a subprocess scale the pool up.
+const SCALE_UP_WAIT: std::time::Duration = std::time::Duration::from_millis(100);
+
+/// Number of consecutive idle intervals before the pool scales down.
+const SCALE_DOWN_INTERVALS: u32 = 60;
+
+/// Decides once per interval whether the pool should grow or shrink.
+///
+/// The pool grows by one subprocess per interval when commands had to wait for a subprocess, or
+/// when all of the subprocesses were busy, so that there is a subprocess ready before commands
+/// start to wait. The pool shrinks by one subprocess after it has had at least two idle
+/// subprocesses for [`SCALE_DOWN_INTERVALS`] intervals in a row. The pool never shrinks below its
+/// minimum, and exited subprocesses below the minimum are replaced.
+struct Scaler {
+    size: PoolSize,
+    idle_intervals: u32,
+}
+
+impl Scaler {
+    fn new(size: PoolSize) -> Self {
+        Scaler {
+            size,
+            idle_intervals: 0,
+        }
+    }
+
+    /// Returns the decision given the number of `active` subprocesses and their `load` over the
+    /// last interval.
+    fn decide(&mut self, active: usize, load: Load) -> Scaling {
+        if active < self.size.min.get() {
+            self.idle_intervals = 0;
+            return Scaling::Replace;
+        }
+
+        if load.max_wait >= SCALE_UP_WAIT || load.peak_busy >= active {
+            self.idle_intervals = 0;
+            return if active < self.size.max.get() {
+                Scaling::Up
+            } else {
+                Scaling::Keep
+            };
+        }
+
+        if active > self.size.min.get() && load.peak_busy + 1 < active {
+            self.idle_intervals += 1;
+            if self.idle_intervals >= SCALE_DOWN_INTERVALS {
+                self.idle_intervals = 0;
+                return Scaling::Down;
+            }
+        } else {
+            self.idle_intervals = 0;
+        }
+
+        Scaling::Keep
+    }
+}
+
 /// Returns if a new subprocess should be launched without wait
-fn on_joined_subprocess(
-    res: Result<Result<super::SubprocessExitInfo, anyhow::Error>, tokio::task::JoinError>,
-    metrics: &Metrics,
-) -> bool {
+fn on_joined_subprocess(res: SubprocessJoinResult, metrics: &Metrics) -> bool {
     match res {
         Ok(Ok((pid, exit_status, exit_reason))) => {
             info!(%pid, ?exit_status, ?exit_reason, "Subprocess exited");
@@ -183,6 +411,8 @@ fn on_joined_subprocess(
 static METRIC_LAUNCHED_PROCESSES: &str = "extpy_processes_launched_total";
 static METRIC_EXITED_PROCESSES: &str = "extpy_processes_exited_total";
 static METRIC_FAILED_PROCESSES: &str = "extpy_processes_failed_total";
+static METRIC_RESTARTED_PROCESSES: &str = "extpy_processes_restarted_total";
+static METRIC_SCALING_DECISIONS: &str = "extpy_scaling_decisions_total";
 
 struct Metrics {
     launched: metrics::Counter,
@@ -216,6 +446,21 @@ impl Metrics {
             "number of abnormally, due to bug, exited subprocesses."
         );
 
+        metrics::describe_counter!(
+            METRIC_RESTARTED_PROCESSES,
+            metrics::Unit::Count,
+            "number of times a subprocess was replaced after exiting, by `worker` number."
+        );
+
+        for direction in ["up", "down"] {
+            metrics::register_counter!(METRIC_SCALING_DECISIONS, "direction" => direction);
+        }
+        metrics::describe_counter!(
+            METRIC_SCALING_DECISIONS,
+            metrics::Unit::Count,
+            "number of times the pool scaled `up` or `down` by one subprocess."
+        );
+
         Metrics { launched, failed }
     }
 
@@ -231,4 +476,167 @@ impl Metrics {
     fn increment_failed(&self) {
         self.failed.increment(1);
     }
+
+    fn increment_restarted(&self, worker: usize) {
+        metrics::increment_counter!(METRIC_RESTARTED_PROCESSES, "worker" => worker.to_string());
+    }
+
+    fn increment_scaling(&self, direction: &'static str) {
+        metrics::increment_counter!(METRIC_SCALING_DECISIONS, "direction" => direction);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::monitoring::metrics::test::{FakeRecorder, RecorderGuard};
+    use std::time::Duration;
+
+    /// Returns the number and whether it is a restart, dropping the receiver.
+    fn acquire(workers: &mut Workers) -> (usize, bool) {
+        let (worker, restart, _) = workers.acquire();
+        (worker, restart)
+    }
+
+    #[test]
+    fn worker_numbers() {
+        let mut workers = Workers::default();
+
+        assert_eq!(acquire(&mut workers), (0, false));
+        assert_eq!(acquire(&mut workers), (1, false));
+        assert_eq!(acquire(&mut workers), (2, false));
+
+        workers.release(1);
+        workers.release(0);
+        assert_eq!(acquire(&mut workers), (0, true));
+        assert_eq!(acquire(&mut workers), (1, true));
+        assert_eq!(acquire(&mut workers), (3, false));
+    }
+
+    #[test]
+    fn retire() {
+        let mut workers = Workers::default();
+        let (_, _, mut first) = workers.acquire();
+        let (_, _, mut second) = workers.acquire();
+        assert_eq!(workers.active(), 2);
+
+        assert_eq!(workers.retire(), Some(1));
+        assert_eq!(second.try_recv(), Ok(()));
+        assert!(first.try_recv().is_err());
+        assert_eq!(workers.active(), 1);
+
+        // the retiring one keeps its number until it has exited
+        assert_eq!(acquire(&mut workers), (2, false));
+
+        assert_eq!(workers.retire(), Some(2));
+        assert_eq!(workers.retire(), Some(0));
+        assert_eq!(first.try_recv(), Ok(()));
+        assert_eq!(workers.retire(), None);
+    }
+
+    fn pool_size(min: usize, max: usize) -> PoolSize {
+        PoolSize::new(
+            NonZeroUsize::new(min).unwrap(),
+            NonZeroUsize::new(max).unwrap(),
+        )
+        .unwrap()
+    }
+
+    fn load(peak_busy: usize, max_wait_ms: u64) -> Load {
+        Load {
+            peak_busy,
+            max_wait: Duration::from_millis(max_wait_ms),
+        }
+    }
+
+    #[test]
+    fn pool_size_bounds() {
+        assert!(
+            PoolSize::new(NonZeroUsize::new(2).unwrap(), NonZeroUsize::new(1).unwrap()).is_none()
+        );
+        assert_eq!(
+            PoolSize::fixed(NonZeroUsize::new(2).unwrap()),
+            pool_size(2, 2)
+        );
+    }
+
+    #[test]
+    fn scaler_replaces_below_min() {
+        let mut scaler = Scaler::new(pool_size(2, 4));
+        assert_eq!(scaler.decide(1, load(0, 0)), Scaling::Replace);
+        assert_eq!(scaler.decide(2, load(0, 0)), Scaling::Keep);
+    }
+
+    #[test]
+    fn scaler_scales_up_ahead_of_demand() {
+        let mut scaler = Scaler::new(pool_size(2, 3));
+        // commands did not have to wait, but there was no idle subprocess left
+        assert_eq!(scaler.decide(2, load(2, 0)), Scaling::Up);
+        assert_eq!(scaler.decide(3, load(2, 150)), Scaling::Up);
+        // not above the maximum
+        assert_eq!(scaler.decide(3, load(3, 0)), Scaling::Keep);
+        assert_eq!(scaler.decide(3, load(3, 500)), Scaling::Keep);
+    }
+
+    #[test]
+    fn scaler_scales_down_when_idle() {
+        let mut scaler = Scaler::new(pool_size(1, 4));
+
+        for _ in 1..SCALE_DOWN_INTERVALS {
+            assert_eq!(scaler.decide(4, load(2, 0)), Scaling::Keep);
+        }
+        // a single busier interval starts the count over
+        assert_eq!(scaler.decide(4, load(3, 0)), Scaling::Keep);
+        for _ in 1..SCALE_DOWN_INTERVALS {
+            assert_eq!(scaler.decide(4, load(2, 0)), Scaling::Keep);
+        }
+        assert_eq!(scaler.decide(4, load(2, 0)), Scaling::Down);
+
+        // never below the minimum
+        for _ in 0..SCALE_DOWN_INTERVALS {
+            assert_eq!(scaler.decide(1, load(0, 0)), Scaling::Keep);
+        }
+    }
+
+    #[test]
+    fn restarts() {
+        let recorder = FakeRecorder::for_metrics(&["extpy_processes_restarted_total"]);
+        let handle = recorder.handle();
+        let _guard = RecorderGuard::lock(recorder);
+
+        let metrics = Metrics::register();
+        metrics.increment_restarted(1);
+        metrics.increment_restarted(1);
+        metrics.increment_restarted(0);
+
+        assert_eq!(
+            handle.get_counter_value_by_label(METRIC_RESTARTED_PROCESSES, [("worker", "0")]),
+            1
+        );
+        assert_eq!(
+            handle.get_counter_value_by_label(METRIC_RESTARTED_PROCESSES, [("worker", "1")]),
+            2
+        );
+    }
+
+    #[test]
+    fn scaling_decisions() {
+        let recorder = FakeRecorder::for_metrics(&["extpy_scaling_decisions_total"]);
+        let handle = recorder.handle();
+        let _guard = RecorderGuard::lock(recorder);
+
+        let metrics = Metrics::register();
+        metrics.increment_scaling("up");
+        metrics.increment_scaling("up");
+        metrics.increment_scaling("down");
+
+        assert_eq!(
+            handle.get_counter_value_by_label(METRIC_SCALING_DECISIONS, [("direction", "up")]),
+            2
+        );
+        assert_eq!(
+            handle.get_counter_value_by_label(METRIC_SCALING_DECISIONS, [("direction", "down")]),
+            1
+        );
+    }
 }
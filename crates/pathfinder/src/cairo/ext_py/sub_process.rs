@@ -2,17 +2,21 @@
 
 use super::{
     de::{ChildResponse, OutputValue, RefinedChildResponse, Status},
+    queue::{CommandQueue, Queued},
     ser::{ChildCommand, CommonProperties},
-    CallFailure, Command, SharedReceiver, SubProcessEvent, SubprocessError, SubprocessExitReason,
+    CallFailure, Command, Limits, SubProcessEvent, SubprocessError, SubprocessExitReason,
 };
 use anyhow::Context;
 use std::path::PathBuf;
+use std::sync::Arc;
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tokio::process::{Child, ChildStdin, ChildStdout};
-use tokio::sync::{broadcast, mpsc};
+use tokio::sync::{broadcast, mpsc, oneshot};
 use tracing::{debug, error, info, trace, warn, Instrument};
 
-/// Launches a python subprocess, and executes calls on it until shutdown is initiated.
+/// Launches a python subprocess, and executes calls on it until shutdown is initiated, until it
+/// is due to be replaced according to the [`Limits`], or until it is retired by the pool scaling
+/// down.
 ///
 /// If the python process is killed, reaping it and restarting new one is handled by [`super::start`],
 /// similarly to spawning this as a task usually handled.
@@ -23,9 +27,11 @@ use tracing::{debug, error, info, trace, warn, Instrument};
 #[tracing::instrument(name = "subproc", skip_all, fields(pid))]
 pub(super) async fn launch_python(
     database_path: PathBuf,
-    commands: SharedReceiver<(Command, tracing::Span)>,
+    commands: Arc<CommandQueue>,
     status_updates: mpsc::Sender<SubProcessEvent>,
     mut shutdown_rx: broadcast::Receiver<()>,
+    retire_rx: oneshot::Receiver<()>,
+    limits: Limits,
 ) -> anyhow::Result<(u32, Option<std::process::ExitStatus>, SubprocessExitReason)> {
     let current_span = std::sync::Arc::new(std::sync::Mutex::new(tracing::Span::none()));
 
@@ -49,24 +55,20 @@ pub(super) async fn launch_python(
     info!("Subprocess launched");
 
     let mut command_buffer = Vec::new();
+    let mut completed = 0usize;
+
+    // fused, as the pool could drop the sender which would complete the receiver with an error
+    let mut retire_rx = futures::FutureExt::fuse(retire_rx);
 
     // TODO: Why not have an outer loop to respawn a process fast? The idea occured during review.
     // Currently the "policy" over respawning is controlled by the "service" in `super::start`.
     let exit_reason = loop {
-        let command = async {
-            let mut locked = commands.lock().await;
-            locked.recv().await
-        };
+        let command = commands.pop();
 
         tokio::pin!(command);
 
-        let (command, span) = tokio::select! {
-            // locking is not cancellation safe BUT if the race is lost we don't retry so no
-            // worries on that.
-            maybe_command = &mut command => match maybe_command {
-                Some(tuple) => tuple,
-                None => break SubprocessExitReason::Shutdown,
-            },
+        let Queued { command, span, .. } = tokio::select! {
+            queued = &mut command => queued,
             _ = child.wait() => {
                 // if the python process was killed while we were awaiting for new commands, it
                 // would be zombie until we notice it has died. The wait can be called many times,
@@ -76,6 +78,9 @@ pub(super) async fn launch_python(
             _ = shutdown_rx.recv() => {
                 break SubprocessExitReason::Shutdown;
             },
+            Ok(()) = &mut retire_rx => {
+                break SubprocessExitReason::ScaledDown;
+            }
         };
 
         if command.is_closed() {
@@ -86,6 +91,8 @@ pub(super) async fn launch_python(
         span.record("pid", &pid);
 
         {
+            let _busy = commands.busy();
+
             let op = process(
                 &current_span,
                 command,
@@ -93,22 +100,16 @@ pub(super) async fn launch_python(
                 &mut stdin,
                 &mut stdout,
                 &mut buffer,
+                limits.timeout,
             )
             .instrument(span);
 
-            tokio::pin!(op);
-
-            tokio::select! {
-                res = &mut op => {
-                    match res {
-                        Ok(_) => (),
-                        Err(None) => continue,
-                        Err(Some(e)) => break e,
-                    }
-                },
-                _ = shutdown_rx.recv() => {
-                    break SubprocessExitReason::Shutdown;
-                },
+            // the command is completed even if shutdown starts meanwhile, as its caller is still
+            // waiting for it. shutdown is noticed when waiting for the next command.
+            match op.await {
+                Ok(_) => completed += 1,
+                Err(None) => continue,
+                Err(Some(e)) => break e,
             }
         }
 
@@ -123,6 +124,25 @@ pub(super) async fn launch_python(
             // FIXME: log this, hasn't happened.
             break SubprocessExitReason::UnrecoverableIO;
         }
+
+        // cairo-lang caches keep growing, so the subprocess is replaced every now and then.
+        if limits
+            .max_requests
+            .map_or(false, |max| completed >= max.get())
+        {
+            debug!(%completed, "Recycling subprocess after maximum number of requests");
+            break SubprocessExitReason::Recycled;
+        }
+
+        if let Some(max_memory) = limits.max_memory {
+            match resident_memory(pid) {
+                Some(memory) if memory > max_memory => {
+                    debug!(%memory, "Recycling subprocess after exceeding maximum memory");
+                    break SubprocessExitReason::Recycled;
+                }
+                _ => {}
+            }
+        }
     };
 
     trace!(?exit_reason, "Starting to exit");
@@ -318,6 +338,7 @@ async fn process(
     stdin: &mut ChildStdin,
     stdout: &mut BufReader<ChildStdout>,
     buffer: &mut String,
+    timeout: Option<std::time::Duration>,
 ) -> Result<Status, Option<SubprocessExitReason>> {
     {
         let mut g = current_span.lock().unwrap_or_else(|e| e.into_inner());
@@ -386,8 +407,19 @@ async fn process(
         let rpc_op = rpc_round(command_buffer, stdin, stdout, buffer);
         tokio::pin!(rpc_op);
 
+        let deadline = async {
+            match timeout {
+                Some(timeout) => tokio::time::sleep(timeout).await,
+                None => std::future::pending().await,
+            }
+        };
+        tokio::pin!(deadline);
+
         tokio::select! {
             res = &mut rpc_op => res,
+            // like with cancellation, the python cannot be interrupted reliably, so it will be
+            // killed after failing the command.
+            _ = &mut deadline => Err(SubprocessError::Timeout),
             // no need to await for child dying here, because the event would close the childs
             // stdout and thus break our read_line and thus return a SubprocessError::IO and
             // we'd break out.
@@ -429,6 +461,11 @@ async fn process(
             // TODO: consider if we'd just retry; put this back into the queue?
             return Err(Some(SubprocessExitReason::UnrecoverableIO));
         }
+        Err(SubprocessError::Timeout) => {
+            warn!(?timeout, "Command timed out, killing subprocess");
+            let _ = command.fail(CallFailure::Timeout);
+            return Err(Some(SubprocessExitReason::Timeout));
+        }
     };
 
     // TODO: this could be pushed to Command but ...
@@ -484,3 +521,45 @@ async fn rpc_round<'a>(
 
     resp.refine()
 }
+
+/// Returns the resident memory of the process in bytes, or [None] if it cannot be determined,
+/// which is always the case outside of Linux.
+fn resident_memory(pid: u32) -> Option<u64> {
+    let status = std::fs::read_to_string(format!("/proc/{pid}/status")).ok()?;
+    parse_vm_rss(&status)
+}
+
+/// Parses the `VmRSS` line of `/proc/<pid>/status` into bytes.
+fn parse_vm_rss(status: &str) -> Option<u64> {
+    let kilobytes = status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmRSS:"))?
+        .trim()
+        .strip_suffix("kB")?
+        .trim()
+        .parse::<u64>()
+        .ok()?;
+
+    Some(kilobytes * 1024)
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn parse_vm_rss() {
+        let status = "Name:\tpython3\nVmPeak:\t  123456 kB\nVmRSS:\t   56789 kB\nThreads:\t1\n";
+        assert_eq!(super::parse_vm_rss(status), Some(56789 * 1024));
+
+        assert_eq!(super::parse_vm_rss("Name:\tpython3\n"), None);
+    }
+
+    #[test]
+    fn resident_memory_of_self() {
+        let memory = super::resident_memory(std::process::id());
+        if cfg!(target_os = "linux") {
+            assert!(memory.unwrap() > 0);
+        } else {
+            assert_eq!(memory, None);
+        }
+    }
+}
@@ -17,24 +17,72 @@ pub enum ConfigOption {
     EthereumHttpUrl,
     /// The Ethereum password.
     EthereumPassword,
+    /// How deep L1 state updates must be before they are trusted.
+    EthereumConfirmations,
     /// The HTTP-RPC listening socket address.
     HttpRpcAddress,
+    /// Size in MiB of the RPC response cache.
+    RpcCacheSize,
+    /// The `[rpc-auth]` section of the configuration file, as TOML.
+    RpcAuth,
+    /// Path to the PEM encoded TLS certificate chain of the HTTP-RPC server.
+    RpcTlsCert,
+    /// Path to the PEM encoded PKCS#8 TLS private key of the HTTP-RPC server.
+    RpcTlsKey,
+    /// Comma separated origins allowed to make CORS requests to the HTTP-RPC server.
+    RpcCorsOrigins,
+    /// Comma separated request headers allowed in CORS requests.
+    RpcCorsHeaders,
+    /// Maximum size in bytes of an HTTP-RPC request body.
+    RpcMaxRequestBodySize,
+    /// Maximum size in bytes of an HTTP-RPC response body.
+    RpcMaxResponseBodySize,
+    /// Maximum number of calls in an HTTP-RPC batch request.
+    RpcMaxBatchLength,
+    /// Maximum number of concurrent HTTP-RPC connections.
+    RpcMaxConnections,
+    /// Path of the Unix domain socket serving JSON-RPC over IPC.
+    RpcIpcPath,
+    /// Octal Unix permissions of the IPC sockets.
+    RpcIpcPermissions,
     /// Path to the node's data directory.
     DataDirectory,
     /// The Sequencer's HTTP URL.
     SequencerHttpUrl,
     /// Number of Python sub-processes to start.
     PythonSubprocesses,
+    /// Number of Python sub-processes the pool can scale up to.
+    PythonSubprocessesMax,
+    /// Seconds after which a Python sub-process command times out.
+    PythonSubprocessTimeout,
+    /// Number of commands after which a Python sub-process is replaced.
+    PythonSubprocessMaxRequests,
+    /// Resident memory in MiB above which a Python sub-process is replaced.
+    PythonSubprocessMaxMemory,
     /// Enable SQLite write-ahead logging.
     EnableSQLiteWriteAheadLogging,
     /// Enable pending polling.
     PollPending,
+    /// Treat block hash mismatches as fatal.
+    StrictBlockValidation,
     /// Enables and sets the monitoring endpoint
     MonitorAddress,
+    /// Enables and sets the feeder gateway endpoint
+    FeederGatewayAddress,
     /// Chooses Integration network instead of testnet.
     Integration,
     /// Chooses Testnet 2 network.
     Testnet2,
+    /// Path to a custom chain specification.
+    ChainSpec,
+    /// JSON-RPC URL of a node to sync from instead of the sequencer.
+    SyncRpcUrl,
+    /// Serve a database synced by another node, without syncing.
+    ReadOnly,
+    /// Maximum number of blocks behind the network while still reporting ready.
+    ReadyMaxLag,
+    /// Maximum age in seconds of the L1 head while still reporting ready.
+    ReadyMaxL1Age,
 }
 
 impl Display for ConfigOption {
@@ -42,17 +90,47 @@ impl Display for ConfigOption {
         match self {
             ConfigOption::EthereumHttpUrl => f.write_str("Ethereum HTTP URL"),
             ConfigOption::EthereumPassword => f.write_str("Ethereum password"),
+            ConfigOption::EthereumConfirmations => f.write_str("Ethereum confirmations"),
             ConfigOption::DataDirectory => f.write_str("Data directory"),
             ConfigOption::HttpRpcAddress => f.write_str("HTTP-RPC socket address"),
+            ConfigOption::RpcCacheSize => f.write_str("RPC response cache size"),
+            ConfigOption::RpcAuth => f.write_str("RPC access control"),
+            ConfigOption::RpcTlsCert => f.write_str("RPC TLS certificate"),
+            ConfigOption::RpcTlsKey => f.write_str("RPC TLS key"),
+            ConfigOption::RpcCorsOrigins => f.write_str("RPC CORS origins"),
+            ConfigOption::RpcCorsHeaders => f.write_str("RPC CORS headers"),
+            ConfigOption::RpcMaxRequestBodySize => f.write_str("RPC maximum request body size"),
+            ConfigOption::RpcMaxResponseBodySize => f.write_str("RPC maximum response body size"),
+            ConfigOption::RpcMaxBatchLength => f.write_str("RPC maximum batch length"),
+            ConfigOption::RpcMaxConnections => f.write_str("RPC maximum connections"),
+            ConfigOption::RpcIpcPath => f.write_str("RPC IPC socket path"),
+            ConfigOption::RpcIpcPermissions => f.write_str("RPC IPC socket permissions"),
             ConfigOption::SequencerHttpUrl => f.write_str("Sequencer HTTP URL"),
             ConfigOption::PythonSubprocesses => f.write_str("Number of Python subprocesses"),
+            ConfigOption::PythonSubprocessesMax => {
+                f.write_str("Maximum number of Python subprocesses")
+            }
+            ConfigOption::PythonSubprocessTimeout => f.write_str("Python subprocess timeout"),
+            ConfigOption::PythonSubprocessMaxRequests => {
+                f.write_str("Python subprocess maximum requests")
+            }
+            ConfigOption::PythonSubprocessMaxMemory => {
+                f.write_str("Python subprocess maximum memory")
+            }
             ConfigOption::EnableSQLiteWriteAheadLogging => {
                 f.write_str("Enable SQLite write-ahead logging")
             }
             ConfigOption::PollPending => f.write_str("Enable pending block polling"),
+            ConfigOption::StrictBlockValidation => f.write_str("Enable strict block validation"),
             ConfigOption::MonitorAddress => f.write_str("Pathfinder monitoring address"),
+            ConfigOption::FeederGatewayAddress => f.write_str("Feeder gateway address"),
             ConfigOption::Integration => f.write_str("Select integration network"),
             ConfigOption::Testnet2 => f.write_str("Select Testnet 2 network"),
+            ConfigOption::ChainSpec => f.write_str("Custom chain specification file"),
+            ConfigOption::SyncRpcUrl => f.write_str("Sync JSON-RPC URL"),
+            ConfigOption::ReadOnly => f.write_str("Read-only replica mode"),
+            ConfigOption::ReadyMaxLag => f.write_str("Maximum sync lag for readiness"),
+            ConfigOption::ReadyMaxL1Age => f.write_str("Maximum L1 head age for readiness"),
         }
     }
 }
@@ -64,6 +142,8 @@ pub struct EthereumConfig {
     pub url: Url,
     /// The optional Ethereum password.
     pub password: Option<String>,
+    /// How deep L1 state updates must be before they are trusted.
+    pub confirmation: crate::ethereum::Confirmation,
 }
 
 /// Node configuration options.
@@ -73,22 +153,46 @@ pub struct Configuration {
     pub ethereum: EthereumConfig,
     /// The HTTP-RPC listening address and port.
     pub http_rpc_addr: SocketAddr,
+    /// Size in bytes of the cache of historical RPC responses, zero disables it.
+    pub rpc_cache_size: usize,
+    /// Authentication, IP allow-list and rate limits of the HTTP-RPC server.
+    pub rpc_auth: Option<crate::rpc::access::AccessConfig>,
+    /// TLS, CORS and limits of the HTTP-RPC server.
+    pub rpc_http: crate::rpc::gateway::HttpOptions,
+    /// Serves JSON-RPC over a Unix domain socket as well.
+    pub rpc_ipc: Option<crate::rpc::ipc::IpcOptions>,
     /// The node's data directory.
     pub data_directory: PathBuf,
     /// The Sequencer's HTTP URL.
     pub sequencer_url: Option<Url>,
-    /// The number of Python subprocesses to start.
-    pub python_subprocesses: std::num::NonZeroUsize,
+    /// The number of Python subprocesses to start, and to scale up to under load.
+    pub python_subprocesses: crate::cairo::ext_py::PoolSize,
+    /// Timeout and recycling limits of the Python subprocesses.
+    pub python_limits: crate::cairo::ext_py::Limits,
     /// Enable SQLite write-ahead logging.
     pub sqlite_wal: bool,
     /// Enable pending polling.
     pub poll_pending: bool,
+    /// Abort sync on block hash mismatches instead of logging a warning.
+    pub strict_block_validation: bool,
     /// The node's monitoring address and port.
     pub monitoring_addr: Option<SocketAddr>,
+    /// The node's feeder gateway address and port.
+    pub feeder_gateway_addr: Option<SocketAddr>,
     /// Select integration network.
     pub integration: bool,
     /// Select testnet 2 network.
     pub testnet2: bool,
+    /// Path to a custom chain specification, used instead of the public networks.
+    pub chain_spec: Option<PathBuf>,
+    /// JSON-RPC URL of a node to sync from instead of the sequencer.
+    pub sync_rpc_url: Option<Url>,
+    /// Open the database read-only and follow the node writing to it instead of syncing.
+    pub read_only: bool,
+    /// Report not ready while more than this many blocks behind the network.
+    pub ready_max_lag: Option<u64>,
+    /// Report not ready while the block at the L1 head is older than this.
+    pub ready_max_l1_age: Option<std::time::Duration>,
 }
 
 impl Configuration {
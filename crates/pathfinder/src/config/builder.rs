@@ -61,6 +61,21 @@ Hint: Register your own account or run your own Ethereum node and put the real U
 
         // Optional parameters.
         let eth_password = self.take(ConfigOption::EthereumPassword);
+        let eth_confirmation = self
+            .take(ConfigOption::EthereumConfirmations)
+            .map(|confirmations| {
+                confirmations.parse().map_err(|err| {
+                    std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        format!(
+                            "Invalid Ethereum confirmations ({}): {}",
+                            confirmations, err
+                        ),
+                    )
+                })
+            })
+            .transpose()?
+            .unwrap_or_default();
         let sequencer_url = match self.take(ConfigOption::SequencerHttpUrl) {
             Some(url) => {
                 let url = url.parse::<Url>().map_err(|err| {
@@ -75,6 +90,59 @@ Hint: Register your own account or run your own Ethereum node and put the real U
             None => None,
         };
 
+        let sync_rpc_url = match self.take(ConfigOption::SyncRpcUrl) {
+            Some(url) => {
+                let url = url.parse::<Url>().map_err(|err| {
+                    std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        format!("Invalid sync JSON-RPC URL ({}): {}", url, err),
+                    )
+                })?;
+
+                Some(url)
+            }
+            None => None,
+        };
+
+        let read_only = self.take(ConfigOption::ReadOnly).is_some();
+        if read_only && sync_rpc_url.is_some() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "Cannot sync from a JSON-RPC node in read-only mode.".to_string(),
+            ));
+        }
+
+        let ready_max_lag = self
+            .take(ConfigOption::ReadyMaxLag)
+            .map(|lag| {
+                lag.parse::<u64>().map_err(|err| {
+                    std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        format!(
+                            "Invalid number of blocks for readiness lag ({}): {}",
+                            lag, err
+                        ),
+                    )
+                })
+            })
+            .transpose()?;
+        let ready_max_l1_age = self
+            .take(ConfigOption::ReadyMaxL1Age)
+            .map(|age| {
+                age.parse::<u64>()
+                    .map(std::time::Duration::from_secs)
+                    .map_err(|err| {
+                        std::io::Error::new(
+                            std::io::ErrorKind::InvalidInput,
+                            format!(
+                                "Invalid number of seconds for readiness L1 age ({}): {}",
+                                age, err
+                            ),
+                        )
+                    })
+            })
+            .transpose()?;
+
         let monitoring_addr = self
             .take(ConfigOption::MonitorAddress)
             .map(|addr| {
@@ -89,6 +157,20 @@ Hint: Register your own account or run your own Ethereum node and put the real U
                 })
             })
             .transpose()?;
+        let feeder_gateway_addr = self
+            .take(ConfigOption::FeederGatewayAddress)
+            .map(|addr| {
+                addr.parse::<SocketAddr>().map_err(|err| {
+                    std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        format!(
+                            "Invalid feeder gateway listening interface and port ({}): {}",
+                            addr, err
+                        ),
+                    )
+                })
+            })
+            .transpose()?;
         let integration = self.take(ConfigOption::Integration).is_some();
         let testnet2: bool = self.take(ConfigOption::Testnet2).is_some();
 
@@ -100,6 +182,16 @@ Hint: Register your own account or run your own Ethereum node and put the real U
             ));
         }
 
+        let chain_spec = self
+            .take(ConfigOption::ChainSpec)
+            .map(|s| PathBuf::from_str(&s).unwrap());
+        if chain_spec.is_some() && (integration || testnet2) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "Cannot use a chain spec together with integration or testnet 2.".to_string(),
+            ));
+        }
+
         // Optional parameters with defaults.
         let data_directory = self
             .take(ConfigOption::DataDirectory)
@@ -108,6 +200,196 @@ Hint: Register your own account or run your own Ethereum node and put the real U
         let http_rpc_addr = self
             .take(ConfigOption::HttpRpcAddress)
             .unwrap_or_else(|| DEFAULT_HTTP_RPC_ADDR.to_owned());
+        let rpc_cache_size = match self.take(ConfigOption::RpcCacheSize) {
+            Some(size) => size.parse::<usize>().map_err(|err| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!(
+                        "Invalid number of MiB for RPC cache size ({}): {}",
+                        size, err
+                    ),
+                )
+            })?,
+            None => 64,
+        };
+        let rpc_cache_size = rpc_cache_size * 1024 * 1024;
+        let rpc_auth = self
+            .take(ConfigOption::RpcAuth)
+            .map(|auth| {
+                toml::from_str::<crate::rpc::access::AccessConfig>(&auth).map_err(|err| {
+                    std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        format!("Invalid RPC access control section: {}", err),
+                    )
+                })
+            })
+            .transpose()?;
+        let rpc_tls = match (
+            self.take(ConfigOption::RpcTlsCert),
+            self.take(ConfigOption::RpcTlsKey),
+        ) {
+            (Some(cert), Some(key)) => Some(crate::rpc::gateway::TlsPaths {
+                cert: PathBuf::from(cert),
+                key: PathBuf::from(key),
+            }),
+            (None, None) => None,
+            _ => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    "RPC TLS certificate and key must be configured together".to_owned(),
+                ))
+            }
+        };
+        let rpc_cors_headers = self
+            .take(ConfigOption::RpcCorsHeaders)
+            .map(|headers| {
+                headers
+                    .split(',')
+                    .map(|header| {
+                        let header = header.trim();
+                        warp::http::HeaderName::from_str(header)
+                            .map(|_| header.to_owned())
+                            .map_err(|err| {
+                                std::io::Error::new(
+                                    std::io::ErrorKind::InvalidInput,
+                                    format!("Invalid RPC CORS header ({}): {}", header, err),
+                                )
+                            })
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .transpose()?;
+        let rpc_cors_origins = self
+            .take(ConfigOption::RpcCorsOrigins)
+            .map(|origins| {
+                origins
+                    .split(',')
+                    .map(|origin| {
+                        let origin = origin.trim();
+                        if origin == "*" {
+                            return Ok(origin.to_owned());
+                        }
+                        // An origin is a URL without a path, such as `https://example.com`.
+                        Url::parse(origin)
+                            .ok()
+                            .map(|url| url.origin().ascii_serialization())
+                            .filter(|serialized| serialized == origin)
+                            .ok_or_else(|| {
+                                std::io::Error::new(
+                                    std::io::ErrorKind::InvalidInput,
+                                    format!(
+                                        "Invalid RPC CORS origin ({}), expected e.g. https://example.com or *",
+                                        origin
+                                    ),
+                                )
+                            })
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .transpose()?;
+        let rpc_cors = match (rpc_cors_origins, rpc_cors_headers) {
+            (Some(origins), headers) => Some(crate::rpc::gateway::Cors {
+                origins,
+                headers: headers.unwrap_or_default(),
+            }),
+            (None, None) => None,
+            (None, Some(_)) => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    "RPC CORS headers require RPC CORS origins".to_owned(),
+                ))
+            }
+        };
+        let rpc_max_request_body_size = match self.take(ConfigOption::RpcMaxRequestBodySize) {
+            Some(size) => size.parse::<u32>().map_err(|err| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!(
+                        "Invalid number of bytes for RPC maximum request body size ({}): {}",
+                        size, err
+                    ),
+                )
+            })?,
+            None => crate::rpc::gateway::DEFAULT_MAX_BODY_SIZE,
+        };
+        let rpc_max_response_body_size = match self.take(ConfigOption::RpcMaxResponseBodySize) {
+            Some(size) => size.parse::<u32>().map_err(|err| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!(
+                        "Invalid number of bytes for RPC maximum response body size ({}): {}",
+                        size, err
+                    ),
+                )
+            })?,
+            None => crate::rpc::gateway::DEFAULT_MAX_BODY_SIZE,
+        };
+        let rpc_max_batch_len = self
+            .take(ConfigOption::RpcMaxBatchLength)
+            .map(|max| {
+                max.parse::<std::num::NonZeroUsize>().map_err(|err| {
+                    std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        format!(
+                            "Invalid number for RPC maximum batch length ({}): {}",
+                            max, err
+                        ),
+                    )
+                })
+            })
+            .transpose()?;
+        let rpc_max_connections = self
+            .take(ConfigOption::RpcMaxConnections)
+            .map(|max| {
+                max.parse::<std::num::NonZeroUsize>().map_err(|err| {
+                    std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        format!(
+                            "Invalid number for RPC maximum connections ({}): {}",
+                            max, err
+                        ),
+                    )
+                })
+            })
+            .transpose()?;
+        let rpc_http = crate::rpc::gateway::HttpOptions {
+            tls: rpc_tls,
+            cors: rpc_cors,
+            max_request_body_size: rpc_max_request_body_size,
+            max_response_body_size: rpc_max_response_body_size,
+            max_batch_len: rpc_max_batch_len,
+            max_connections: rpc_max_connections,
+        };
+        let rpc_ipc_permissions = self
+            .take(ConfigOption::RpcIpcPermissions)
+            .map(|permissions| {
+                u32::from_str_radix(&permissions, 8)
+                    .ok()
+                    .filter(|permissions| *permissions <= 0o777)
+                    .ok_or_else(|| {
+                        std::io::Error::new(
+                            std::io::ErrorKind::InvalidInput,
+                            format!(
+                                "Invalid octal permissions for RPC IPC socket ({}), expected e.g. 660",
+                                permissions
+                            ),
+                        )
+                    })
+            })
+            .transpose()?;
+        let rpc_ipc = match (self.take(ConfigOption::RpcIpcPath), rpc_ipc_permissions) {
+            (Some(path), permissions) => Some(crate::rpc::ipc::IpcOptions {
+                path: PathBuf::from(path),
+                permissions: permissions.unwrap_or(crate::rpc::ipc::DEFAULT_PERMISSIONS),
+            }),
+            (None, None) => None,
+            (None, Some(_)) => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    "RPC IPC permissions require an RPC IPC path".to_owned(),
+                ))
+            }
+        };
         let python_subprocesses = match self.take(ConfigOption::PythonSubprocesses) {
             Some(python_subprocesses) => {
                 let num: usize = python_subprocesses.parse().map_err(|err| {
@@ -128,6 +410,78 @@ Hint: Register your own account or run your own Ethereum node and put the real U
             }
             None => std::num::NonZeroUsize::new(2).unwrap(),
         };
+        let python_subprocesses = match self.take(ConfigOption::PythonSubprocessesMax) {
+            Some(max) => {
+                let max = max.parse::<std::num::NonZeroUsize>().map_err(|err| {
+                    std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        format!(
+                            "Invalid number for maximum Python subprocesses ({}): {}",
+                            max, err
+                        ),
+                    )
+                })?;
+                crate::cairo::ext_py::PoolSize::new(python_subprocesses, max).ok_or_else(|| {
+                    std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        format!(
+                            "Maximum number of Python subprocesses ({}) must not be less than the number of Python subprocesses ({})",
+                            max, python_subprocesses
+                        ),
+                    )
+                })?
+            }
+            // Without a maximum the pool does not scale.
+            None => crate::cairo::ext_py::PoolSize::fixed(python_subprocesses),
+        };
+        let python_timeout = match self.take(ConfigOption::PythonSubprocessTimeout) {
+            Some(timeout) => timeout.parse::<u64>().map_err(|err| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!(
+                        "Invalid number of seconds for Python subprocess timeout ({}): {}",
+                        timeout, err
+                    ),
+                )
+            })?,
+            None => 60,
+        };
+        let python_max_requests = self
+            .take(ConfigOption::PythonSubprocessMaxRequests)
+            .map(|max| {
+                max.parse::<std::num::NonZeroUsize>().map_err(|err| {
+                    std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        format!(
+                            "Invalid number for Python subprocess maximum requests ({}): {}",
+                            max, err
+                        ),
+                    )
+                })
+            })
+            .transpose()?;
+        let python_max_memory = self
+            .take(ConfigOption::PythonSubprocessMaxMemory)
+            .map(|max| {
+                max.parse::<u64>()
+                    .map(|mib| mib * 1024 * 1024)
+                    .map_err(|err| {
+                        std::io::Error::new(
+                            std::io::ErrorKind::InvalidInput,
+                            format!(
+                            "Invalid number of MiB for Python subprocess maximum memory ({}): {}",
+                            max, err
+                        ),
+                        )
+                    })
+            })
+            .transpose()?;
+        let python_limits = crate::cairo::ext_py::Limits {
+            // Zero disables the timeout.
+            timeout: (python_timeout > 0).then(|| std::time::Duration::from_secs(python_timeout)),
+            max_requests: python_max_requests,
+            max_memory: python_max_memory,
+        };
         let sqlite_wal = match self.take(ConfigOption::EnableSQLiteWriteAheadLogging) {
             Some(enable) => {
                 let enable = enable.to_lowercase();
@@ -172,20 +526,51 @@ Hint: Register your own account or run your own Ethereum node and put the real U
             None => Ok(false),
         }?;
 
+        let strict_block_validation = match self.take(ConfigOption::StrictBlockValidation) {
+            Some(enable) => {
+                let enable = enable.to_lowercase();
+                match enable.as_str() {
+                    "true" => Ok(true),
+                    "false" => Ok(false),
+                    _ => Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        format!(
+                            "Invalid value '{}' for strict block validation option, must be true|false",
+                            enable
+                        ),
+                    )),
+                }
+            }
+            None => Ok(true),
+        }?;
+
         Ok(Configuration {
             ethereum: EthereumConfig {
                 url: eth_url,
                 password: eth_password,
+                confirmation: eth_confirmation,
             },
             http_rpc_addr,
+            rpc_cache_size,
+            rpc_auth,
+            rpc_http,
+            rpc_ipc,
             data_directory,
             sequencer_url,
             python_subprocesses,
+            python_limits,
             sqlite_wal,
             poll_pending,
+            strict_block_validation,
             monitoring_addr,
+            feeder_gateway_addr,
             integration,
             testnet2,
+            chain_spec,
+            sync_rpc_url,
+            read_only,
+            ready_max_lag,
+            ready_max_l1_age,
         })
     }
 
@@ -348,7 +733,7 @@ mod tests {
         mod defaults {
             //! Tests that the correct default values are applied during `try_build`.
 
-            use super::builder_with_all_required;
+            use super::{builder_with_all_required, ConfigOption};
 
             #[test]
             fn data_directory() {
@@ -368,21 +753,160 @@ mod tests {
                 assert_eq!(config.http_rpc_addr, expected);
             }
 
+            #[test]
+            fn rpc_cache_size() {
+                let expected = 64 * 1024 * 1024;
+                let config = builder_with_all_required().try_build().unwrap();
+                assert_eq!(config.rpc_cache_size, expected);
+            }
+
+            #[test]
+            fn rpc_auth() {
+                let config = builder_with_all_required().try_build().unwrap();
+                assert_eq!(config.rpc_auth, None);
+            }
+
+            #[test]
+            fn rpc_http() {
+                let config = builder_with_all_required().try_build().unwrap();
+                assert_eq!(config.rpc_http, crate::rpc::gateway::HttpOptions::default());
+            }
+
+            #[test]
+            fn rpc_ipc() {
+                use crate::rpc::ipc::IpcOptions;
+
+                let config = builder_with_all_required().try_build().unwrap();
+                assert_eq!(config.rpc_ipc, None);
+
+                let config = builder_with_all_required()
+                    .with(ConfigOption::RpcIpcPath, Some("rpc.sock".to_owned()))
+                    .try_build()
+                    .unwrap();
+                assert_eq!(
+                    config.rpc_ipc,
+                    Some(IpcOptions {
+                        path: "rpc.sock".into(),
+                        permissions: 0o600,
+                    })
+                );
+
+                let config = builder_with_all_required()
+                    .with(ConfigOption::RpcIpcPath, Some("rpc.sock".to_owned()))
+                    .with(ConfigOption::RpcIpcPermissions, Some("660".to_owned()))
+                    .try_build()
+                    .unwrap();
+                assert_eq!(config.rpc_ipc.unwrap().permissions, 0o660);
+
+                for permissions in ["rw", "1777", "9"] {
+                    let builder = builder_with_all_required()
+                        .with(ConfigOption::RpcIpcPath, Some("rpc.sock".to_owned()))
+                        .with(
+                            ConfigOption::RpcIpcPermissions,
+                            Some(permissions.to_owned()),
+                        );
+                    assert!(builder.try_build().is_err(), "{permissions}");
+                }
+
+                let builder = builder_with_all_required()
+                    .with(ConfigOption::RpcIpcPermissions, Some("660".to_owned()));
+                assert!(builder.try_build().is_err());
+            }
+
+            #[test]
+            fn rpc_tls_requires_both_paths() {
+                let builder = builder_with_all_required()
+                    .with(ConfigOption::RpcTlsCert, Some("cert.pem".to_owned()));
+                assert!(builder.try_build().is_err());
+            }
+
+            #[test]
+            fn rpc_cors() {
+                use crate::rpc::gateway::Cors;
+
+                let config = builder_with_all_required()
+                    .with(
+                        ConfigOption::RpcCorsOrigins,
+                        Some("https://example.com, http://localhost:3000".to_owned()),
+                    )
+                    .with(ConfigOption::RpcCorsHeaders, Some("x-api-key".to_owned()))
+                    .try_build()
+                    .unwrap();
+                assert_eq!(
+                    config.rpc_http.cors,
+                    Some(Cors {
+                        origins: vec![
+                            "https://example.com".to_owned(),
+                            "http://localhost:3000".to_owned()
+                        ],
+                        headers: vec!["x-api-key".to_owned()],
+                    })
+                );
+
+                for origin in ["example.com", "https://example.com/path"] {
+                    let builder = builder_with_all_required()
+                        .with(ConfigOption::RpcCorsOrigins, Some(origin.to_owned()));
+                    assert!(builder.try_build().is_err(), "{origin}");
+                }
+
+                let builder = builder_with_all_required()
+                    .with(ConfigOption::RpcCorsHeaders, Some("x-api-key".to_owned()));
+                assert!(builder.try_build().is_err());
+            }
+
             #[test]
             fn python_subprocesses() {
+                use crate::cairo::ext_py::PoolSize;
                 use std::num::NonZeroUsize;
 
-                let expected = NonZeroUsize::new(2).unwrap();
+                let expected = PoolSize::fixed(NonZeroUsize::new(2).unwrap());
                 let config = builder_with_all_required().try_build().unwrap();
                 assert_eq!(config.python_subprocesses, expected);
             }
 
+            #[test]
+            fn python_subprocesses_max_below_min() {
+                let builder = builder_with_all_required()
+                    .with(ConfigOption::PythonSubprocesses, Some("4".to_owned()))
+                    .with(ConfigOption::PythonSubprocessesMax, Some("3".to_owned()));
+                assert!(builder.try_build().is_err());
+            }
+
+            #[test]
+            fn python_limits() {
+                use crate::cairo::ext_py::Limits;
+
+                let expected = Limits {
+                    timeout: Some(std::time::Duration::from_secs(60)),
+                    max_requests: None,
+                    max_memory: None,
+                };
+                let config = builder_with_all_required().try_build().unwrap();
+                assert_eq!(config.python_limits, expected);
+            }
+
             #[test]
             fn sqlite_wal() {
                 let expected = true;
                 let config = builder_with_all_required().try_build().unwrap();
                 assert_eq!(config.sqlite_wal, expected);
             }
+
+            #[test]
+            fn strict_block_validation() {
+                let expected = true;
+                let config = builder_with_all_required().try_build().unwrap();
+                assert_eq!(config.strict_block_validation, expected);
+            }
+
+            #[test]
+            fn ethereum_confirmation() {
+                use crate::ethereum::Confirmation;
+
+                let expected = Confirmation::Depth(0);
+                let config = builder_with_all_required().try_build().unwrap();
+                assert_eq!(config.ethereum.confirmation, expected);
+            }
         }
     }
 }
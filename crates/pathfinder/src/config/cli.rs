@@ -10,14 +10,37 @@ const CONFIG_KEY: &str = "config";
 const DATA_DIR_KEY: &str = "data-directory";
 const ETH_URL_KEY: &str = "ethereum.url";
 const ETH_PASS_KEY: &str = "ethereum.password";
+const ETH_CONFIRMATIONS_KEY: &str = "ethereum.confirmations";
 const HTTP_RPC_ADDR_KEY: &str = "http-rpc";
+const RPC_CACHE_SIZE_KEY: &str = "rpc-cache-size";
+const RPC_TLS_CERT_KEY: &str = "rpc-tls-cert";
+const RPC_TLS_KEY_KEY: &str = "rpc-tls-key";
+const RPC_CORS_ORIGINS_KEY: &str = "rpc-cors-origins";
+const RPC_CORS_HEADERS_KEY: &str = "rpc-cors-headers";
+const RPC_MAX_REQUEST_BODY_SIZE_KEY: &str = "rpc-max-request-body-size";
+const RPC_MAX_RESPONSE_BODY_SIZE_KEY: &str = "rpc-max-response-body-size";
+const RPC_MAX_BATCH_LENGTH_KEY: &str = "rpc-max-batch-length";
+const RPC_MAX_CONNECTIONS_KEY: &str = "rpc-max-connections";
+const RPC_IPC_PATH_KEY: &str = "rpc-ipc-path";
+const RPC_IPC_PERMISSIONS_KEY: &str = "rpc-ipc-permissions";
 const SEQ_URL_KEY: &str = "sequencer-url";
 const PYTHON_SUBPROCESSES_KEY: &str = "python-subprocesses";
+const PYTHON_SUBPROCESSES_MAX_KEY: &str = "python-subprocesses-max";
+const PYTHON_SUBPROCESS_TIMEOUT_KEY: &str = "python-subprocess-timeout";
+const PYTHON_SUBPROCESS_MAX_REQUESTS_KEY: &str = "python-subprocess-max-requests";
+const PYTHON_SUBPROCESS_MAX_MEMORY_KEY: &str = "python-subprocess-max-memory";
 const SQLITE_WAL: &str = "sqlite-wal";
 const POLL_PENDING: &str = "poll-pending";
+const STRICT_BLOCK_VALIDATION: &str = "strict-block-validation";
 const MONITOR_ADDRESS: &str = "monitor-address";
+const FEEDER_GATEWAY_ADDRESS: &str = "feeder-gateway-address";
 const INTEGRATION: &str = "integration";
 const TESTNET2: &str = "testnet2";
+const CHAIN_SPEC: &str = "chain-spec";
+const SYNC_RPC_URL: &str = "sync-rpc-url";
+const READ_ONLY: &str = "read-only";
+const READY_MAX_LAG: &str = "ready-max-lag";
+const READY_MAX_L1_AGE: &str = "ready-max-l1-age";
 
 /// Parses the cmd line arguments and returns the optional
 /// configuration file's path and the specified configuration options.
@@ -46,28 +69,103 @@ where
     let data_directory = args.value_of(DATA_DIR_KEY).map(|s| s.to_owned());
     let ethereum_url = args.value_of(ETH_URL_KEY).map(|s| s.to_owned());
     let ethereum_password = args.value_of(ETH_PASS_KEY).map(|s| s.to_owned());
+    let ethereum_confirmations = args.value_of(ETH_CONFIRMATIONS_KEY).map(|s| s.to_owned());
     let http_rpc_addr = args.value_of(HTTP_RPC_ADDR_KEY).map(|s| s.to_owned());
+    let rpc_cache_size = args.value_of(RPC_CACHE_SIZE_KEY).map(|s| s.to_owned());
+    let rpc_tls_cert = args.value_of(RPC_TLS_CERT_KEY).map(|s| s.to_owned());
+    let rpc_tls_key = args.value_of(RPC_TLS_KEY_KEY).map(|s| s.to_owned());
+    let rpc_cors_origins = args.value_of(RPC_CORS_ORIGINS_KEY).map(|s| s.to_owned());
+    let rpc_cors_headers = args.value_of(RPC_CORS_HEADERS_KEY).map(|s| s.to_owned());
+    let rpc_max_request_body_size = args
+        .value_of(RPC_MAX_REQUEST_BODY_SIZE_KEY)
+        .map(|s| s.to_owned());
+    let rpc_max_response_body_size = args
+        .value_of(RPC_MAX_RESPONSE_BODY_SIZE_KEY)
+        .map(|s| s.to_owned());
+    let rpc_max_batch_length = args
+        .value_of(RPC_MAX_BATCH_LENGTH_KEY)
+        .map(|s| s.to_owned());
+    let rpc_max_connections = args.value_of(RPC_MAX_CONNECTIONS_KEY).map(|s| s.to_owned());
+    let rpc_ipc_path = args.value_of(RPC_IPC_PATH_KEY).map(|s| s.to_owned());
+    let rpc_ipc_permissions = args.value_of(RPC_IPC_PERMISSIONS_KEY).map(|s| s.to_owned());
     let sequencer_url = args.value_of(SEQ_URL_KEY).map(|s| s.to_owned());
     let python_subprocesses = args.value_of(PYTHON_SUBPROCESSES_KEY).map(|s| s.to_owned());
+    let python_subprocesses_max = args
+        .value_of(PYTHON_SUBPROCESSES_MAX_KEY)
+        .map(|s| s.to_owned());
+    let python_subprocess_timeout = args
+        .value_of(PYTHON_SUBPROCESS_TIMEOUT_KEY)
+        .map(|s| s.to_owned());
+    let python_subprocess_max_requests = args
+        .value_of(PYTHON_SUBPROCESS_MAX_REQUESTS_KEY)
+        .map(|s| s.to_owned());
+    let python_subprocess_max_memory = args
+        .value_of(PYTHON_SUBPROCESS_MAX_MEMORY_KEY)
+        .map(|s| s.to_owned());
     let sqlite_wal = args.value_of(SQLITE_WAL).map(|s| s.to_owned());
     let poll_pending = args.value_of(POLL_PENDING).map(|s| s.to_owned());
+    let strict_block_validation = args.value_of(STRICT_BLOCK_VALIDATION).map(|s| s.to_owned());
     let monitor_address = args.value_of(MONITOR_ADDRESS).map(|s| s.to_owned());
+    let feeder_gateway_address = args.value_of(FEEDER_GATEWAY_ADDRESS).map(|s| s.to_owned());
     // Hack around our builder requiring Strings, but these args just needs to be present.
     let integration = args.is_present(INTEGRATION).then_some(String::new());
     let testnet2: Option<String> = args.is_present(TESTNET2).then_some(String::new());
+    let chain_spec = args.value_of(CHAIN_SPEC).map(|s| s.to_owned());
+    let sync_rpc_url = args.value_of(SYNC_RPC_URL).map(|s| s.to_owned());
+    let read_only = args.is_present(READ_ONLY).then_some(String::new());
+    let ready_max_lag = args.value_of(READY_MAX_LAG).map(|s| s.to_owned());
+    let ready_max_l1_age = args.value_of(READY_MAX_L1_AGE).map(|s| s.to_owned());
 
     let cfg = ConfigBuilder::default()
         .with(ConfigOption::EthereumHttpUrl, ethereum_url)
         .with(ConfigOption::EthereumPassword, ethereum_password)
+        .with(ConfigOption::EthereumConfirmations, ethereum_confirmations)
         .with(ConfigOption::HttpRpcAddress, http_rpc_addr)
+        .with(ConfigOption::RpcCacheSize, rpc_cache_size)
+        .with(ConfigOption::RpcTlsCert, rpc_tls_cert)
+        .with(ConfigOption::RpcTlsKey, rpc_tls_key)
+        .with(ConfigOption::RpcCorsOrigins, rpc_cors_origins)
+        .with(ConfigOption::RpcCorsHeaders, rpc_cors_headers)
+        .with(
+            ConfigOption::RpcMaxRequestBodySize,
+            rpc_max_request_body_size,
+        )
+        .with(
+            ConfigOption::RpcMaxResponseBodySize,
+            rpc_max_response_body_size,
+        )
+        .with(ConfigOption::RpcMaxBatchLength, rpc_max_batch_length)
+        .with(ConfigOption::RpcMaxConnections, rpc_max_connections)
+        .with(ConfigOption::RpcIpcPath, rpc_ipc_path)
+        .with(ConfigOption::RpcIpcPermissions, rpc_ipc_permissions)
         .with(ConfigOption::DataDirectory, data_directory)
         .with(ConfigOption::SequencerHttpUrl, sequencer_url)
         .with(ConfigOption::PythonSubprocesses, python_subprocesses)
+        .with(ConfigOption::PythonSubprocessesMax, python_subprocesses_max)
+        .with(
+            ConfigOption::PythonSubprocessTimeout,
+            python_subprocess_timeout,
+        )
+        .with(
+            ConfigOption::PythonSubprocessMaxRequests,
+            python_subprocess_max_requests,
+        )
+        .with(
+            ConfigOption::PythonSubprocessMaxMemory,
+            python_subprocess_max_memory,
+        )
         .with(ConfigOption::EnableSQLiteWriteAheadLogging, sqlite_wal)
         .with(ConfigOption::PollPending, poll_pending)
+        .with(ConfigOption::StrictBlockValidation, strict_block_validation)
         .with(ConfigOption::MonitorAddress, monitor_address)
+        .with(ConfigOption::FeederGatewayAddress, feeder_gateway_address)
         .with(ConfigOption::Integration, integration)
-        .with(ConfigOption::Testnet2, testnet2);
+        .with(ConfigOption::Testnet2, testnet2)
+        .with(ConfigOption::ChainSpec, chain_spec)
+        .with(ConfigOption::SyncRpcUrl, sync_rpc_url)
+        .with(ConfigOption::ReadOnly, read_only)
+        .with(ConfigOption::ReadyMaxLag, ready_max_lag)
+        .with(ConfigOption::ReadyMaxL1Age, ready_max_l1_age);
 
     Ok((config_filepath, cfg))
 }
@@ -86,6 +184,7 @@ fn clap_app() -> clap::Command<'static> {
     clap::Command::new("Pathfinder")
         .version(version)
         .about("A StarkNet node implemented by Equilibrium. Submit bug reports and issues at https://github.com/eqlabs/pathfinder.")
+        .after_help("Database maintenance commands are available via `pathfinder db --help`.")
         .arg(
             Arg::new(CONFIG_KEY)
                 .short('c')
@@ -113,6 +212,17 @@ fn clap_app() -> clap::Command<'static> {
 Examples:
     infura: https://goerli.infura.io/v3/<PROJECT_ID>
     geth:   https://localhost:8545"))
+        .arg(
+            Arg::new(ETH_CONFIRMATIONS_KEY)
+                .long(ETH_CONFIRMATIONS_KEY)
+                .help("L1 confirmations required before state updates are accepted")
+                .takes_value(true)
+                .value_name("BLOCKS|safe|finalized")
+                .env("PATHFINDER_ETHEREUM_CONFIRMATIONS")
+                .long_help(r"How deep an L1 state update must be before its StarkNet block is considered accepted on L1. Either a number of blocks below the L1 head, or one of the 'safe' and 'finalized' block tags which require a post-merge Ethereum endpoint.
+
+Defaults to 0, which accepts state updates as soon as they appear on L1."),
+        )
         .arg(
             Arg::new(HTTP_RPC_ADDR_KEY)
                 .long(HTTP_RPC_ADDR_KEY)
@@ -121,6 +231,94 @@ Examples:
                 .value_name("IP:PORT")
                 .env("PATHFINDER_HTTP_RPC_ADDRESS")
         )
+        .arg(
+            Arg::new(RPC_CACHE_SIZE_KEY)
+                .long(RPC_CACHE_SIZE_KEY)
+                .help("Size in MiB of the cache of historical JSON-RPC responses, 0 disables the cache")
+                .takes_value(true)
+                .value_name("MiB")
+                .env("PATHFINDER_RPC_CACHE_SIZE")
+        )
+        .arg(
+            Arg::new(RPC_TLS_CERT_KEY)
+                .long(RPC_TLS_CERT_KEY)
+                .help("Path to the PEM encoded TLS certificate chain of the HTTP-RPC server, which then serves HTTPS. Reloaded on SIGHUP")
+                .takes_value(true)
+                .value_name("PATH")
+                .env("PATHFINDER_RPC_TLS_CERT")
+        )
+        .arg(
+            Arg::new(RPC_TLS_KEY_KEY)
+                .long(RPC_TLS_KEY_KEY)
+                .help("Path to the PEM encoded PKCS#8 private key of the HTTP-RPC TLS certificate. Reloaded on SIGHUP")
+                .takes_value(true)
+                .value_name("PATH")
+                .env("PATHFINDER_RPC_TLS_KEY")
+        )
+        .arg(
+            Arg::new(RPC_CORS_ORIGINS_KEY)
+                .long(RPC_CORS_ORIGINS_KEY)
+                .help("Comma separated origins allowed to make CORS requests to the HTTP-RPC server, or * for any")
+                .takes_value(true)
+                .value_name("ORIGINS")
+                .env("PATHFINDER_RPC_CORS_ORIGINS")
+        )
+        .arg(
+            Arg::new(RPC_CORS_HEADERS_KEY)
+                .long(RPC_CORS_HEADERS_KEY)
+                .help("Comma separated request headers allowed in CORS requests, in addition to Content-Type")
+                .takes_value(true)
+                .value_name("HEADERS")
+                .env("PATHFINDER_RPC_CORS_HEADERS")
+        )
+        .arg(
+            Arg::new(RPC_MAX_REQUEST_BODY_SIZE_KEY)
+                .long(RPC_MAX_REQUEST_BODY_SIZE_KEY)
+                .help("Maximum size in bytes of an HTTP-RPC request body, defaults to 10 MiB")
+                .takes_value(true)
+                .value_name("BYTES")
+                .env("PATHFINDER_RPC_MAX_REQUEST_BODY_SIZE")
+        )
+        .arg(
+            Arg::new(RPC_MAX_RESPONSE_BODY_SIZE_KEY)
+                .long(RPC_MAX_RESPONSE_BODY_SIZE_KEY)
+                .help("Maximum size in bytes of an HTTP-RPC response body, defaults to 10 MiB")
+                .takes_value(true)
+                .value_name("BYTES")
+                .env("PATHFINDER_RPC_MAX_RESPONSE_BODY_SIZE")
+        )
+        .arg(
+            Arg::new(RPC_MAX_BATCH_LENGTH_KEY)
+                .long(RPC_MAX_BATCH_LENGTH_KEY)
+                .help("Maximum number of calls in an HTTP-RPC batch request, unlimited by default")
+                .takes_value(true)
+                .value_name("COUNT")
+                .env("PATHFINDER_RPC_MAX_BATCH_LENGTH")
+        )
+        .arg(
+            Arg::new(RPC_MAX_CONNECTIONS_KEY)
+                .long(RPC_MAX_CONNECTIONS_KEY)
+                .help("Maximum number of concurrent HTTP-RPC connections, further ones wait to be accepted. Unlimited by default")
+                .takes_value(true)
+                .value_name("COUNT")
+                .env("PATHFINDER_RPC_MAX_CONNECTIONS")
+        )
+        .arg(
+            Arg::new(RPC_IPC_PATH_KEY)
+                .long(RPC_IPC_PATH_KEY)
+                .help("Path of a Unix domain socket to serve the v0.2 and pathfinder JSON-RPC methods at, in addition to HTTP. The v0.1 methods are served at the same path with a .v0.1 suffix")
+                .takes_value(true)
+                .value_name("PATH")
+                .env("PATHFINDER_RPC_IPC_PATH")
+        )
+        .arg(
+            Arg::new(RPC_IPC_PERMISSIONS_KEY)
+                .long(RPC_IPC_PERMISSIONS_KEY)
+                .help("Octal Unix permissions of the IPC sockets, defaults to 600")
+                .takes_value(true)
+                .value_name("MODE")
+                .env("PATHFINDER_RPC_IPC_PERMISSIONS")
+        )
         .arg(
             Arg::new(DATA_DIR_KEY)
                 .long(DATA_DIR_KEY)
@@ -146,6 +344,38 @@ Examples:
                 .value_name("NUM")
                 .env("PATHFINDER_PYTHON_SUBPROCESSES")
         )
+        .arg(
+            Arg::new(PYTHON_SUBPROCESSES_MAX_KEY)
+                .long(PYTHON_SUBPROCESSES_MAX_KEY)
+                .help("Number of Python subprocesses the pool can scale up to under load, defaults to the number of Python subprocesses")
+                .takes_value(true)
+                .value_name("NUM")
+                .env("PATHFINDER_PYTHON_SUBPROCESSES_MAX")
+        )
+        .arg(
+            Arg::new(PYTHON_SUBPROCESS_TIMEOUT_KEY)
+                .long(PYTHON_SUBPROCESS_TIMEOUT_KEY)
+                .help("Seconds after which a call or fee estimation fails and its Python subprocess is replaced, 0 disables the timeout")
+                .takes_value(true)
+                .value_name("SECONDS")
+                .env("PATHFINDER_PYTHON_SUBPROCESS_TIMEOUT")
+        )
+        .arg(
+            Arg::new(PYTHON_SUBPROCESS_MAX_REQUESTS_KEY)
+                .long(PYTHON_SUBPROCESS_MAX_REQUESTS_KEY)
+                .help("Number of calls and fee estimations after which a Python subprocess is replaced")
+                .takes_value(true)
+                .value_name("NUM")
+                .env("PATHFINDER_PYTHON_SUBPROCESS_MAX_REQUESTS")
+        )
+        .arg(
+            Arg::new(PYTHON_SUBPROCESS_MAX_MEMORY_KEY)
+                .long(PYTHON_SUBPROCESS_MAX_MEMORY_KEY)
+                .help("Resident memory in MiB above which a Python subprocess is replaced")
+                .takes_value(true)
+                .value_name("MIB")
+                .env("PATHFINDER_PYTHON_SUBPROCESS_MAX_MEMORY")
+        )
         .arg(
             Arg::new(SQLITE_WAL)
                 .long(SQLITE_WAL)
@@ -162,6 +392,15 @@ Examples:
                 .value_name("TRUE/FALSE")
                 .env("PATHFINDER_POLL_PENDING")
         )
+        .arg(
+            Arg::new(STRICT_BLOCK_VALIDATION)
+                .long(STRICT_BLOCK_VALIDATION)
+                .help("Abort sync if a block hash does not match the block contents")
+                .long_help("Abort sync if a block hash or commitment does not match the block contents. Enabled by default. When disabled, such mismatches are only logged as a warning.")
+                .takes_value(true)
+                .value_name("TRUE/FALSE")
+                .env("PATHFINDER_STRICT_BLOCK_VALIDATION")
+        )
         .arg(
             Arg::new(MONITOR_ADDRESS)
                 .long(MONITOR_ADDRESS)
@@ -171,6 +410,15 @@ Examples:
                 .value_name("IP:PORT")
                 .env("PATHFINDER_MONITOR_ADDRESS")
         )
+        .arg(
+            Arg::new(FEEDER_GATEWAY_ADDRESS)
+                .long(FEEDER_GATEWAY_ADDRESS)
+                .help("Feeder gateway address")
+                .long_help("The address at which pathfinder will serve a sequencer feeder gateway compatible API from its own database, allowing other nodes to sync from this one.")
+                .takes_value(true)
+                .value_name("IP:PORT")
+                .env("PATHFINDER_FEEDER_GATEWAY_ADDRESS")
+        )
         .arg(
             Arg::new(INTEGRATION)
                 .long(INTEGRATION)
@@ -183,6 +431,49 @@ Examples:
             .help("Use Testnet 2 on Ethereum Goerli")
             .takes_value(false)
         )
+        .arg(
+            Arg::new(CHAIN_SPEC)
+                .long(CHAIN_SPEC)
+                .help("Path to a custom chain specification")
+                .long_help("Path to a TOML chain specification describing a private Starknet deployment. Replaces the built-in public networks.")
+                .takes_value(true)
+                .value_name("PATH")
+                .env("PATHFINDER_CHAIN_SPEC")
+        )
+        .arg(
+            Arg::new(SYNC_RPC_URL)
+                .long(SYNC_RPC_URL)
+                .help("Sync from another node's JSON-RPC API")
+                .long_help("The HTTP-RPC address of a trusted pathfinder node to sync from instead of the sequencer, e.g. http://127.0.0.1:9545. Block hashes are always verified in this mode.")
+                .takes_value(true)
+                .value_name("HTTP(s) URL")
+                .env("PATHFINDER_SYNC_RPC_URL")
+        )
+        .arg(
+            Arg::new(READ_ONLY)
+                .long(READ_ONLY)
+                .help("Serve the database of another node without syncing")
+                .long_help("Opens the database read-only and serves it without syncing, following the progress of the node writing to it. The database must be in WAL mode and migrated by that node.")
+                .takes_value(false)
+        )
+        .arg(
+            Arg::new(READY_MAX_LAG)
+                .long(READY_MAX_LAG)
+                .help("Maximum sync lag for readiness")
+                .long_help("The monitoring `/ready` endpoint reports not ready while the node is more than this many blocks behind the latest block of the network, or while the latest block is not yet known.")
+                .takes_value(true)
+                .value_name("BLOCKS")
+                .env("PATHFINDER_READY_MAX_LAG")
+        )
+        .arg(
+            Arg::new(READY_MAX_L1_AGE)
+                .long(READY_MAX_L1_AGE)
+                .help("Maximum L1 head age for readiness")
+                .long_help("The monitoring `/ready` endpoint reports not ready while the latest block accepted on L1 is older than this many seconds, or while no block is known to be accepted on L1.")
+                .takes_value(true)
+                .value_name("SECONDS")
+                .env("PATHFINDER_READY_MAX_L1_AGE")
+        )
 }
 
 #[cfg(test)]
@@ -200,13 +491,35 @@ mod tests {
     fn clear_environment() {
         env::remove_var("PATHFINDER_ETHEREUM_API_PASSWORD");
         env::remove_var("PATHFINDER_ETHEREUM_API_URL");
+        env::remove_var("PATHFINDER_ETHEREUM_CONFIRMATIONS");
         env::remove_var("PATHFINDER_HTTP_RPC_ADDRESS");
+        env::remove_var("PATHFINDER_RPC_CACHE_SIZE");
+        env::remove_var("PATHFINDER_RPC_TLS_CERT");
+        env::remove_var("PATHFINDER_RPC_TLS_KEY");
+        env::remove_var("PATHFINDER_RPC_CORS_ORIGINS");
+        env::remove_var("PATHFINDER_RPC_CORS_HEADERS");
+        env::remove_var("PATHFINDER_RPC_MAX_REQUEST_BODY_SIZE");
+        env::remove_var("PATHFINDER_RPC_MAX_RESPONSE_BODY_SIZE");
+        env::remove_var("PATHFINDER_RPC_MAX_BATCH_LENGTH");
+        env::remove_var("PATHFINDER_RPC_MAX_CONNECTIONS");
+        env::remove_var("PATHFINDER_RPC_IPC_PATH");
+        env::remove_var("PATHFINDER_RPC_IPC_PERMISSIONS");
         env::remove_var("PATHFINDER_DATA_DIRECTORY");
         env::remove_var("PATHFINDER_SEQUENCER_URL");
         env::remove_var("PATHFINDER_PYTHON_SUBPROCESSES");
+        env::remove_var("PATHFINDER_PYTHON_SUBPROCESSES_MAX");
+        env::remove_var("PATHFINDER_PYTHON_SUBPROCESS_TIMEOUT");
+        env::remove_var("PATHFINDER_PYTHON_SUBPROCESS_MAX_REQUESTS");
+        env::remove_var("PATHFINDER_PYTHON_SUBPROCESS_MAX_MEMORY");
         env::remove_var("PATHFINDER_SQLITE_WAL");
         env::remove_var("PATHFINDER_POLL_PENDING");
+        env::remove_var("PATHFINDER_STRICT_BLOCK_VALIDATION");
         env::remove_var("PATHFINDER_MONITOR_ADDRESS");
+        env::remove_var("PATHFINDER_FEEDER_GATEWAY_ADDRESS");
+        env::remove_var("PATHFINDER_CHAIN_SPEC");
+        env::remove_var("PATHFINDER_SYNC_RPC_URL");
+        env::remove_var("PATHFINDER_READY_MAX_LAG");
+        env::remove_var("PATHFINDER_READY_MAX_L1_AGE");
     }
 
     #[test]
@@ -251,6 +564,28 @@ mod tests {
         assert_eq!(cfg.take(ConfigOption::EthereumPassword), Some(value));
     }
 
+    #[test]
+    fn ethereum_confirmations_long() {
+        let _env_guard = ENV_VAR_MUTEX.lock().unwrap_or_else(|e| e.into_inner());
+        clear_environment();
+
+        let value = "12".to_owned();
+        let (_, mut cfg) =
+            parse_args(vec!["bin name", "--ethereum.confirmations", &value]).unwrap();
+        assert_eq!(cfg.take(ConfigOption::EthereumConfirmations), Some(value));
+    }
+
+    #[test]
+    fn ethereum_confirmations_environment_variable() {
+        let _env_guard = ENV_VAR_MUTEX.lock().unwrap_or_else(|e| e.into_inner());
+        clear_environment();
+
+        let value = "safe".to_owned();
+        env::set_var("PATHFINDER_ETHEREUM_CONFIRMATIONS", &value);
+        let (_, mut cfg) = parse_args(vec!["bin name"]).unwrap();
+        assert_eq!(cfg.take(ConfigOption::EthereumConfirmations), Some(value));
+    }
+
     #[test]
     fn config_filepath_short() {
         let _env_guard = ENV_VAR_MUTEX.lock().unwrap_or_else(|e| e.into_inner());
@@ -292,6 +627,137 @@ mod tests {
         assert_eq!(cfg.take(ConfigOption::HttpRpcAddress), Some(value));
     }
 
+    #[test]
+    fn rpc_cache_size_long() {
+        let _env_guard = ENV_VAR_MUTEX.lock().unwrap_or_else(|e| e.into_inner());
+        clear_environment();
+
+        let value = "value".to_owned();
+        let (_, mut cfg) = parse_args(vec!["bin name", "--rpc-cache-size", &value]).unwrap();
+        assert_eq!(cfg.take(ConfigOption::RpcCacheSize), Some(value));
+    }
+
+    #[test]
+    fn rpc_cache_size_environment_variable() {
+        let _env_guard = ENV_VAR_MUTEX.lock().unwrap_or_else(|e| e.into_inner());
+        clear_environment();
+
+        let value = "value".to_owned();
+        env::set_var("PATHFINDER_RPC_CACHE_SIZE", &value);
+        let (_, mut cfg) = parse_args(vec!["bin name"]).unwrap();
+        assert_eq!(cfg.take(ConfigOption::RpcCacheSize), Some(value));
+    }
+
+    #[test]
+    fn rpc_http_options_long() {
+        let _env_guard = ENV_VAR_MUTEX.lock().unwrap_or_else(|e| e.into_inner());
+        clear_environment();
+
+        let (_, mut cfg) = parse_args(vec![
+            "bin name",
+            "--rpc-tls-cert",
+            "cert.pem",
+            "--rpc-tls-key",
+            "key.pem",
+            "--rpc-cors-origins",
+            "https://example.com,http://localhost:3000",
+            "--rpc-cors-headers",
+            "x-api-key,authorization",
+            "--rpc-max-request-body-size",
+            "1048576",
+            "--rpc-max-response-body-size",
+            "1048576",
+            "--rpc-max-batch-length",
+            "100",
+            "--rpc-max-connections",
+            "256",
+        ])
+        .unwrap();
+        assert_eq!(
+            cfg.take(ConfigOption::RpcTlsCert),
+            Some("cert.pem".to_owned())
+        );
+        assert_eq!(
+            cfg.take(ConfigOption::RpcTlsKey),
+            Some("key.pem".to_owned())
+        );
+        assert_eq!(
+            cfg.take(ConfigOption::RpcCorsOrigins),
+            Some("https://example.com,http://localhost:3000".to_owned())
+        );
+        assert_eq!(
+            cfg.take(ConfigOption::RpcCorsHeaders),
+            Some("x-api-key,authorization".to_owned())
+        );
+        assert_eq!(
+            cfg.take(ConfigOption::RpcMaxRequestBodySize),
+            Some("1048576".to_owned())
+        );
+        assert_eq!(
+            cfg.take(ConfigOption::RpcMaxResponseBodySize),
+            Some("1048576".to_owned())
+        );
+        assert_eq!(
+            cfg.take(ConfigOption::RpcMaxBatchLength),
+            Some("100".to_owned())
+        );
+        assert_eq!(
+            cfg.take(ConfigOption::RpcMaxConnections),
+            Some("256".to_owned())
+        );
+    }
+
+    #[test]
+    fn rpc_http_options_environment_variable() {
+        let _env_guard = ENV_VAR_MUTEX.lock().unwrap_or_else(|e| e.into_inner());
+        clear_environment();
+
+        env::set_var("PATHFINDER_RPC_TLS_CERT", "cert.pem");
+        env::set_var("PATHFINDER_RPC_TLS_KEY", "key.pem");
+        env::set_var(
+            "PATHFINDER_RPC_CORS_ORIGINS",
+            "https://example.com,http://localhost:3000",
+        );
+        env::set_var("PATHFINDER_RPC_CORS_HEADERS", "x-api-key,authorization");
+        env::set_var("PATHFINDER_RPC_MAX_REQUEST_BODY_SIZE", "1048576");
+        env::set_var("PATHFINDER_RPC_MAX_RESPONSE_BODY_SIZE", "1048576");
+        env::set_var("PATHFINDER_RPC_MAX_BATCH_LENGTH", "100");
+        env::set_var("PATHFINDER_RPC_MAX_CONNECTIONS", "256");
+        let (_, mut cfg) = parse_args(vec!["bin name"]).unwrap();
+        assert_eq!(
+            cfg.take(ConfigOption::RpcTlsCert),
+            Some("cert.pem".to_owned())
+        );
+        assert_eq!(
+            cfg.take(ConfigOption::RpcTlsKey),
+            Some("key.pem".to_owned())
+        );
+        assert_eq!(
+            cfg.take(ConfigOption::RpcCorsOrigins),
+            Some("https://example.com,http://localhost:3000".to_owned())
+        );
+        assert_eq!(
+            cfg.take(ConfigOption::RpcCorsHeaders),
+            Some("x-api-key,authorization".to_owned())
+        );
+        assert_eq!(
+            cfg.take(ConfigOption::RpcMaxRequestBodySize),
+            Some("1048576".to_owned())
+        );
+        assert_eq!(
+            cfg.take(ConfigOption::RpcMaxResponseBodySize),
+            Some("1048576".to_owned())
+        );
+        assert_eq!(
+            cfg.take(ConfigOption::RpcMaxBatchLength),
+            Some("100".to_owned())
+        );
+        assert_eq!(
+            cfg.take(ConfigOption::RpcMaxConnections),
+            Some("256".to_owned())
+        );
+    }
+
     #[test]
     fn data_directory_long() {
         let _env_guard = ENV_VAR_MUTEX.lock().unwrap_or_else(|e| e.into_inner());
@@ -355,6 +821,121 @@ mod tests {
         assert_eq!(cfg.take(ConfigOption::PythonSubprocesses), Some(value));
     }
 
+    #[test]
+    fn python_subprocesses_max_long() {
+        let _env_guard = ENV_VAR_MUTEX.lock().unwrap_or_else(|e| e.into_inner());
+        clear_environment();
+
+        let value = "value".to_owned();
+        let (_, mut cfg) =
+            parse_args(vec!["bin name", "--python-subprocesses-max", &value]).unwrap();
+        assert_eq!(cfg.take(ConfigOption::PythonSubprocessesMax), Some(value));
+    }
+
+    #[test]
+    fn python_subprocesses_max_environment_variable() {
+        let _env_guard = ENV_VAR_MUTEX.lock().unwrap_or_else(|e| e.into_inner());
+        clear_environment();
+
+        let value = "value".to_owned();
+        env::set_var("PATHFINDER_PYTHON_SUBPROCESSES_MAX", &value);
+        let (_, mut cfg) = parse_args(vec!["bin name"]).unwrap();
+        assert_eq!(cfg.take(ConfigOption::PythonSubprocessesMax), Some(value));
+    }
+
+    #[test]
+    fn python_subprocess_limits_long() {
+        let _env_guard = ENV_VAR_MUTEX.lock().unwrap_or_else(|e| e.into_inner());
+        clear_environment();
+
+        let (_, mut cfg) = parse_args(vec![
+            "bin name",
+            "--python-subprocess-timeout",
+            "10",
+            "--python-subprocess-max-requests",
+            "1000",
+            "--python-subprocess-max-memory",
+            "512",
+        ])
+        .unwrap();
+        assert_eq!(
+            cfg.take(ConfigOption::PythonSubprocessTimeout),
+            Some("10".to_owned())
+        );
+        assert_eq!(
+            cfg.take(ConfigOption::PythonSubprocessMaxRequests),
+            Some("1000".to_owned())
+        );
+        assert_eq!(
+            cfg.take(ConfigOption::PythonSubprocessMaxMemory),
+            Some("512".to_owned())
+        );
+    }
+
+    #[test]
+    fn python_subprocess_limits_environment_variable() {
+        let _env_guard = ENV_VAR_MUTEX.lock().unwrap_or_else(|e| e.into_inner());
+        clear_environment();
+
+        env::set_var("PATHFINDER_PYTHON_SUBPROCESS_TIMEOUT", "10");
+        env::set_var("PATHFINDER_PYTHON_SUBPROCESS_MAX_REQUESTS", "1000");
+        env::set_var("PATHFINDER_PYTHON_SUBPROCESS_MAX_MEMORY", "512");
+        let (_, mut cfg) = parse_args(vec!["bin name"]).unwrap();
+        assert_eq!(
+            cfg.take(ConfigOption::PythonSubprocessTimeout),
+            Some("10".to_owned())
+        );
+        assert_eq!(
+            cfg.take(ConfigOption::PythonSubprocessMaxRequests),
+            Some("1000".to_owned())
+        );
+        assert_eq!(
+            cfg.take(ConfigOption::PythonSubprocessMaxMemory),
+            Some("512".to_owned())
+        );
+    }
+
+    #[test]
+    fn rpc_ipc_long() {
+        let _env_guard = ENV_VAR_MUTEX.lock().unwrap_or_else(|e| e.into_inner());
+        clear_environment();
+
+        let (_, mut cfg) = parse_args(vec![
+            "bin name",
+            "--rpc-ipc-path",
+            "rpc.sock",
+            "--rpc-ipc-permissions",
+            "660",
+        ])
+        .unwrap();
+        assert_eq!(
+            cfg.take(ConfigOption::RpcIpcPath),
+            Some("rpc.sock".to_owned())
+        );
+        assert_eq!(
+            cfg.take(ConfigOption::RpcIpcPermissions),
+            Some("660".to_owned())
+        );
+    }
+
+    #[test]
+    fn rpc_ipc_environment_variable() {
+        let _env_guard = ENV_VAR_MUTEX.lock().unwrap_or_else(|e| e.into_inner());
+        clear_environment();
+
+        env::set_var("PATHFINDER_RPC_IPC_PATH", "rpc.sock");
+        env::set_var("PATHFINDER_RPC_IPC_PERMISSIONS", "660");
+        let (_, mut cfg) = parse_args(vec!["bin name"]).unwrap();
+        assert_eq!(
+            cfg.take(ConfigOption::RpcIpcPath),
+            Some("rpc.sock".to_owned())
+        );
+        assert_eq!(
+            cfg.take(ConfigOption::RpcIpcPermissions),
+            Some("660".to_owned())
+        );
+    }
+
     #[test]
     fn sqlite_wal_long() {
         let _env_guard = ENV_VAR_MUTEX.lock().unwrap_or_else(|e| e.into_inner());
@@ -403,6 +984,28 @@ mod tests {
         assert_eq!(cfg.take(ConfigOption::PollPending), Some(value));
     }
 
+    #[test]
+    fn strict_block_validation_long() {
+        let _env_guard = ENV_VAR_MUTEX.lock().unwrap_or_else(|e| e.into_inner());
+        clear_environment();
+
+        let value = "value".to_owned();
+        let (_, mut cfg) =
+            parse_args(vec!["bin name", "--strict-block-validation", &value]).unwrap();
+        assert_eq!(cfg.take(ConfigOption::StrictBlockValidation), Some(value));
+    }
+
+    #[test]
+    fn strict_block_validation_environment_variable() {
+        let _env_guard = ENV_VAR_MUTEX.lock().unwrap_or_else(|e| e.into_inner());
+        clear_environment();
+
+        let value = "value".to_owned();
+        env::set_var("PATHFINDER_STRICT_BLOCK_VALIDATION", &value);
+        let (_, mut cfg) = parse_args(vec!["bin name"]).unwrap();
+        assert_eq!(cfg.take(ConfigOption::StrictBlockValidation), Some(value));
+    }
+
     #[test]
     fn monitor_address_long() {
         let _env_guard = ENV_VAR_MUTEX.lock().unwrap_or_else(|e| e.into_inner());
@@ -424,6 +1027,28 @@ mod tests {
         assert_eq!(cfg.take(ConfigOption::MonitorAddress), Some(value));
     }
 
+    #[test]
+    fn feeder_gateway_address_long() {
+        let _env_guard = ENV_VAR_MUTEX.lock().unwrap_or_else(|e| e.into_inner());
+        clear_environment();
+
+        let value = "value".to_owned();
+        let (_, mut cfg) =
+            parse_args(vec!["bin name", "--feeder-gateway-address", &value]).unwrap();
+        assert_eq!(cfg.take(ConfigOption::FeederGatewayAddress), Some(value));
+    }
+
+    #[test]
+    fn feeder_gateway_address_environment_variable() {
+        let _env_guard = ENV_VAR_MUTEX.lock().unwrap_or_else(|e| e.into_inner());
+        clear_environment();
+
+        let value = "value".to_owned();
+        env::set_var("PATHFINDER_FEEDER_GATEWAY_ADDRESS", &value);
+        let (_, mut cfg) = parse_args(vec!["bin name"]).unwrap();
+        assert_eq!(cfg.take(ConfigOption::FeederGatewayAddress), Some(value));
+    }
+
     #[test]
     fn testnet2_long() {
         let _env_guard = ENV_VAR_MUTEX.lock().unwrap_or_else(|e| e.into_inner());
@@ -433,6 +1058,15 @@ mod tests {
         assert_eq!(cfg.take(ConfigOption::Testnet2), Some("".to_owned()));
     }
 
+    #[test]
+    fn read_only_long() {
+        let _env_guard = ENV_VAR_MUTEX.lock().unwrap_or_else(|e| e.into_inner());
+        clear_environment();
+
+        let (_, mut cfg) = parse_args(vec!["bin name", "--read-only"]).unwrap();
+        assert_eq!(cfg.take(ConfigOption::ReadOnly), Some("".to_owned()));
+    }
+
     #[test]
     fn empty_config() {
         let _env_guard = ENV_VAR_MUTEX.lock().unwrap_or_else(|e| e.into_inner());
@@ -442,4 +1076,88 @@ mod tests {
         assert_eq!(filepath, None);
         assert_eq!(cfg, ConfigBuilder::default());
     }
+
+    #[test]
+    fn chain_spec_long() {
+        let _env_guard = ENV_VAR_MUTEX.lock().unwrap_or_else(|e| e.into_inner());
+        clear_environment();
+
+        let value = "value".to_owned();
+        let (_, mut cfg) = parse_args(vec!["bin name", "--chain-spec", &value]).unwrap();
+        assert_eq!(cfg.take(ConfigOption::ChainSpec), Some(value));
+    }
+
+    #[test]
+    fn chain_spec_environment_variable() {
+        let _env_guard = ENV_VAR_MUTEX.lock().unwrap_or_else(|e| e.into_inner());
+        clear_environment();
+
+        let value = "value".to_owned();
+        env::set_var("PATHFINDER_CHAIN_SPEC", &value);
+        let (_, mut cfg) = parse_args(vec!["bin name"]).unwrap();
+        assert_eq!(cfg.take(ConfigOption::ChainSpec), Some(value));
+    }
+
+    #[test]
+    fn sync_rpc_url_long() {
+        let _env_guard = ENV_VAR_MUTEX.lock().unwrap_or_else(|e| e.into_inner());
+        clear_environment();
+
+        let value = "value".to_owned();
+        let (_, mut cfg) = parse_args(vec!["bin name", "--sync-rpc-url", &value]).unwrap();
+        assert_eq!(cfg.take(ConfigOption::SyncRpcUrl), Some(value));
+    }
+
+    #[test]
+    fn sync_rpc_url_environment_variable() {
+        let _env_guard = ENV_VAR_MUTEX.lock().unwrap_or_else(|e| e.into_inner());
+        clear_environment();
+
+        let value = "value".to_owned();
+        env::set_var("PATHFINDER_SYNC_RPC_URL", &value);
+        let (_, mut cfg) = parse_args(vec!["bin name"]).unwrap();
+        assert_eq!(cfg.take(ConfigOption::SyncRpcUrl), Some(value));
+    }
+
+    #[test]
+    fn ready_max_lag_long() {
+        let _env_guard = ENV_VAR_MUTEX.lock().unwrap_or_else(|e| e.into_inner());
+        clear_environment();
+
+        let value = "value".to_owned();
+        let (_, mut cfg) = parse_args(vec!["bin name", "--ready-max-lag", &value]).unwrap();
+        assert_eq!(cfg.take(ConfigOption::ReadyMaxLag), Some(value));
+    }
+
+    #[test]
+    fn ready_max_lag_environment_variable() {
+        let _env_guard = ENV_VAR_MUTEX.lock().unwrap_or_else(|e| e.into_inner());
+        clear_environment();
+
+        let value = "value".to_owned();
+        env::set_var("PATHFINDER_READY_MAX_LAG", &value);
+        let (_, mut cfg) = parse_args(vec!["bin name"]).unwrap();
+        assert_eq!(cfg.take(ConfigOption::ReadyMaxLag), Some(value));
+    }
+
+    #[test]
+    fn ready_max_l1_age_long() {
+        let _env_guard = ENV_VAR_MUTEX.lock().unwrap_or_else(|e| e.into_inner());
+        clear_environment();
+
+        let value = "value".to_owned();
+        let (_, mut cfg) = parse_args(vec!["bin name", "--ready-max-l1-age", &value]).unwrap();
+        assert_eq!(cfg.take(ConfigOption::ReadyMaxL1Age), Some(value));
+    }
+
+    #[test]
+    fn ready_max_l1_age_environment_variable() {
+        let _env_guard = ENV_VAR_MUTEX.lock().unwrap_or_else(|e| e.into_inner());
+        clear_environment();
+
+        let value = "value".to_owned();
+        env::set_var("PATHFINDER_READY_MAX_L1_AGE", &value);
+        let (_, mut cfg) = parse_args(vec!["bin name"]).unwrap();
+        assert_eq!(cfg.take(ConfigOption::ReadyMaxL1Age), Some(value));
+    }
 }
@@ -7,6 +7,7 @@ use crate::config::builder::ConfigBuilder;
 struct EthereumConfig {
     url: Option<String>,
     password: Option<String>,
+    confirmations: Option<String>,
 }
 
 #[derive(Deserialize, Debug, PartialEq)]
@@ -14,18 +15,63 @@ struct FileConfig {
     ethereum: Option<EthereumConfig>,
     #[serde(rename = "http-rpc")]
     http_rpc: Option<String>,
+    #[serde(rename = "rpc-cache-size")]
+    rpc_cache_size: Option<String>,
+    #[serde(rename = "rpc-tls-cert")]
+    rpc_tls_cert: Option<String>,
+    #[serde(rename = "rpc-tls-key")]
+    rpc_tls_key: Option<String>,
+    #[serde(rename = "rpc-cors-origins")]
+    rpc_cors_origins: Option<String>,
+    #[serde(rename = "rpc-cors-headers")]
+    rpc_cors_headers: Option<String>,
+    #[serde(rename = "rpc-max-request-body-size")]
+    rpc_max_request_body_size: Option<String>,
+    #[serde(rename = "rpc-max-response-body-size")]
+    rpc_max_response_body_size: Option<String>,
+    #[serde(rename = "rpc-max-batch-length")]
+    rpc_max_batch_length: Option<String>,
+    #[serde(rename = "rpc-max-connections")]
+    rpc_max_connections: Option<String>,
+    #[serde(rename = "rpc-ipc-path")]
+    rpc_ipc_path: Option<String>,
+    #[serde(rename = "rpc-ipc-permissions")]
+    rpc_ipc_permissions: Option<String>,
+    /// Structured unlike the other options, passed on as TOML.
+    #[serde(rename = "rpc-auth")]
+    rpc_auth: Option<toml::Value>,
     #[serde(rename = "data-directory")]
     data_directory: Option<String>,
     #[serde(rename = "sequencer-url")]
     sequencer_url: Option<String>,
     #[serde(rename = "python-subprocesses")]
     python_subprocesses: Option<String>,
+    #[serde(rename = "python-subprocesses-max")]
+    python_subprocesses_max: Option<String>,
+    #[serde(rename = "python-subprocess-timeout")]
+    python_subprocess_timeout: Option<String>,
+    #[serde(rename = "python-subprocess-max-requests")]
+    python_subprocess_max_requests: Option<String>,
+    #[serde(rename = "python-subprocess-max-memory")]
+    python_subprocess_max_memory: Option<String>,
     #[serde(rename = "sqlite-wal")]
     sqlite_wal: Option<String>,
     #[serde(rename = "poll-pending")]
     poll_pending: Option<String>,
+    #[serde(rename = "strict-block-validation")]
+    strict_block_validation: Option<String>,
     #[serde(rename = "monitor-address")]
     monitor_address: Option<String>,
+    #[serde(rename = "feeder-gateway-address")]
+    feeder_gateway_address: Option<String>,
+    #[serde(rename = "chain-spec")]
+    chain_spec: Option<String>,
+    #[serde(rename = "sync-rpc-url")]
+    sync_rpc_url: Option<String>,
+    #[serde(rename = "ready-max-lag")]
+    ready_max_lag: Option<String>,
+    #[serde(rename = "ready-max-l1-age")]
+    ready_max_l1_age: Option<String>,
 }
 
 impl FileConfig {
@@ -34,16 +80,66 @@ impl FileConfig {
         match self.ethereum {
             Some(eth) => ConfigBuilder::default()
                 .with(ConfigOption::EthereumHttpUrl, eth.url)
-                .with(ConfigOption::EthereumPassword, eth.password),
+                .with(ConfigOption::EthereumPassword, eth.password)
+                .with(ConfigOption::EthereumConfirmations, eth.confirmations),
             None => ConfigBuilder::default(),
         }
         .with(ConfigOption::DataDirectory, self.data_directory)
         .with(ConfigOption::HttpRpcAddress, self.http_rpc)
+        .with(ConfigOption::RpcCacheSize, self.rpc_cache_size)
+        .with(ConfigOption::RpcTlsCert, self.rpc_tls_cert)
+        .with(ConfigOption::RpcTlsKey, self.rpc_tls_key)
+        .with(ConfigOption::RpcCorsOrigins, self.rpc_cors_origins)
+        .with(ConfigOption::RpcCorsHeaders, self.rpc_cors_headers)
+        .with(
+            ConfigOption::RpcMaxRequestBodySize,
+            self.rpc_max_request_body_size,
+        )
+        .with(
+            ConfigOption::RpcMaxResponseBodySize,
+            self.rpc_max_response_body_size,
+        )
+        .with(ConfigOption::RpcMaxBatchLength, self.rpc_max_batch_length)
+        .with(ConfigOption::RpcMaxConnections, self.rpc_max_connections)
+        .with(ConfigOption::RpcIpcPath, self.rpc_ipc_path)
+        .with(ConfigOption::RpcIpcPermissions, self.rpc_ipc_permissions)
+        .with(
+            ConfigOption::RpcAuth,
+            self.rpc_auth.map(|auth| auth.to_string()),
+        )
         .with(ConfigOption::SequencerHttpUrl, self.sequencer_url)
         .with(ConfigOption::PythonSubprocesses, self.python_subprocesses)
+        .with(
+            ConfigOption::PythonSubprocessesMax,
+            self.python_subprocesses_max,
+        )
+        .with(
+            ConfigOption::PythonSubprocessTimeout,
+            self.python_subprocess_timeout,
+        )
+        .with(
+            ConfigOption::PythonSubprocessMaxRequests,
+            self.python_subprocess_max_requests,
+        )
+        .with(
+            ConfigOption::PythonSubprocessMaxMemory,
+            self.python_subprocess_max_memory,
+        )
         .with(ConfigOption::EnableSQLiteWriteAheadLogging, self.sqlite_wal)
         .with(ConfigOption::PollPending, self.poll_pending)
+        .with(
+            ConfigOption::StrictBlockValidation,
+            self.strict_block_validation,
+        )
         .with(ConfigOption::MonitorAddress, self.monitor_address)
+        .with(
+            ConfigOption::FeederGatewayAddress,
+            self.feeder_gateway_address,
+        )
+        .with(ConfigOption::ChainSpec, self.chain_spec)
+        .with(ConfigOption::SyncRpcUrl, self.sync_rpc_url)
+        .with(ConfigOption::ReadyMaxLag, self.ready_max_lag)
+        .with(ConfigOption::ReadyMaxL1Age, self.ready_max_l1_age)
     }
 }
 
@@ -80,6 +176,14 @@ mod tests {
         assert_eq!(cfg.take(ConfigOption::EthereumPassword), Some(value));
     }
 
+    #[test]
+    fn ethereum_confirmations() {
+        let value = "finalized".to_owned();
+        let toml = format!(r#"ethereum.confirmations = "{}""#, value);
+        let mut cfg = config_from_str(&toml).unwrap();
+        assert_eq!(cfg.take(ConfigOption::EthereumConfirmations), Some(value));
+    }
+
     #[test]
     fn ethereum_section() {
         let url = "url".to_owned();
@@ -105,6 +209,92 @@ password = "{}""#,
         assert_eq!(cfg.take(ConfigOption::HttpRpcAddress), Some(value));
     }
 
+    #[test]
+    fn rpc_cache_size() {
+        let value = "128".to_owned();
+        let toml = format!(r#"rpc-cache-size = "{}""#, value);
+        let mut cfg = config_from_str(&toml).unwrap();
+        assert_eq!(cfg.take(ConfigOption::RpcCacheSize), Some(value));
+    }
+
+    #[test]
+    fn rpc_http_options() {
+        let toml = r#"rpc-tls-cert = "cert.pem"
+rpc-tls-key = "key.pem"
+rpc-cors-origins = "https://example.com"
+rpc-cors-headers = "x-api-key"
+rpc-max-request-body-size = "1048576"
+rpc-max-response-body-size = "1048576"
+rpc-max-batch-length = "100"
+rpc-max-connections = "256""#;
+        let mut cfg = config_from_str(toml).unwrap();
+        assert_eq!(
+            cfg.take(ConfigOption::RpcTlsCert),
+            Some("cert.pem".to_owned())
+        );
+        assert_eq!(
+            cfg.take(ConfigOption::RpcTlsKey),
+            Some("key.pem".to_owned())
+        );
+        assert_eq!(
+            cfg.take(ConfigOption::RpcCorsOrigins),
+            Some("https://example.com".to_owned())
+        );
+        assert_eq!(
+            cfg.take(ConfigOption::RpcCorsHeaders),
+            Some("x-api-key".to_owned())
+        );
+        assert_eq!(
+            cfg.take(ConfigOption::RpcMaxRequestBodySize),
+            Some("1048576".to_owned())
+        );
+        assert_eq!(
+            cfg.take(ConfigOption::RpcMaxResponseBodySize),
+            Some("1048576".to_owned())
+        );
+        assert_eq!(
+            cfg.take(ConfigOption::RpcMaxBatchLength),
+            Some("100".to_owned())
+        );
+        assert_eq!(
+            cfg.take(ConfigOption::RpcMaxConnections),
+            Some("256".to_owned())
+        );
+    }
+
+    #[test]
+    fn rpc_ipc() {
+        let toml = r#"rpc-ipc-path = "/run/pathfinder/rpc.sock"
+rpc-ipc-permissions = "660""#;
+        let mut cfg = config_from_str(toml).unwrap();
+        assert_eq!(
+            cfg.take(ConfigOption::RpcIpcPath),
+            Some("/run/pathfinder/rpc.sock".to_owned())
+        );
+        assert_eq!(
+            cfg.take(ConfigOption::RpcIpcPermissions),
+            Some("660".to_owned())
+        );
+    }
+
+    #[test]
+    fn rpc_auth() {
+        let toml = r#"[rpc-auth]
+allowed-ips = ["127.0.0.1"]
+
+[[rpc-auth.keys]]
+name = "read-only"
+key = "secret"
+deny = ["starknet_add*"]"#;
+        let mut cfg = config_from_str(toml).unwrap();
+        let auth = cfg.take(ConfigOption::RpcAuth).unwrap();
+
+        let auth = toml::from_str::<crate::rpc::access::AccessConfig>(&auth).unwrap();
+        assert_eq!(auth.allowed_ips, vec!["127.0.0.1".parse().unwrap()]);
+        assert_eq!(auth.keys[0].name, "read-only");
+        assert_eq!(auth.keys[0].deny, vec!["starknet_add*".to_owned()]);
+    }
+
     #[test]
     fn data_directory() {
         let value = "value".to_owned();
@@ -129,6 +319,34 @@ password = "{}""#,
         assert_eq!(cfg.take(ConfigOption::PythonSubprocesses), Some(value));
     }
 
+    #[test]
+    fn python_subprocesses_max() {
+        let value = "8".to_owned();
+        let toml = format!(r#"python-subprocesses-max = "{}""#, value);
+        let mut cfg = config_from_str(&toml).unwrap();
+        assert_eq!(cfg.take(ConfigOption::PythonSubprocessesMax), Some(value));
+    }
+
+    #[test]
+    fn python_subprocess_limits() {
+        let toml = r#"python-subprocess-timeout = "10"
+python-subprocess-max-requests = "1000"
+python-subprocess-max-memory = "512""#;
+        let mut cfg = config_from_str(toml).unwrap();
+        assert_eq!(
+            cfg.take(ConfigOption::PythonSubprocessTimeout),
+            Some("10".to_owned())
+        );
+        assert_eq!(
+            cfg.take(ConfigOption::PythonSubprocessMaxRequests),
+            Some("1000".to_owned())
+        );
+        assert_eq!(
+            cfg.take(ConfigOption::PythonSubprocessMaxMemory),
+            Some("512".to_owned())
+        );
+    }
+
     #[test]
     fn sqlite_wal() {
         let value = "true".to_owned();
@@ -148,6 +366,14 @@ password = "{}""#,
         assert_eq!(cfg.take(ConfigOption::PollPending), Some(value));
     }
 
+    #[test]
+    fn strict_block_validation() {
+        let value = "true".to_owned();
+        let toml = format!(r#"strict-block-validation = "{}""#, value);
+        let mut cfg = config_from_str(&toml).unwrap();
+        assert_eq!(cfg.take(ConfigOption::StrictBlockValidation), Some(value));
+    }
+
     #[test]
     fn monitor_address() {
         let value = "address".to_owned();
@@ -156,6 +382,46 @@ password = "{}""#,
         assert_eq!(cfg.take(ConfigOption::MonitorAddress), Some(value));
     }
 
+    #[test]
+    fn feeder_gateway_address() {
+        let value = "address".to_owned();
+        let toml = format!(r#"feeder-gateway-address = "{}""#, value);
+        let mut cfg = config_from_str(&toml).unwrap();
+        assert_eq!(cfg.take(ConfigOption::FeederGatewayAddress), Some(value));
+    }
+
+    #[test]
+    fn chain_spec() {
+        let value = "path".to_owned();
+        let toml = format!(r#"chain-spec = "{}""#, value);
+        let mut cfg = config_from_str(&toml).unwrap();
+        assert_eq!(cfg.take(ConfigOption::ChainSpec), Some(value));
+    }
+
+    #[test]
+    fn sync_rpc_url() {
+        let value = "value".to_owned();
+        let toml = format!(r#"sync-rpc-url = "{}""#, value);
+        let mut cfg = config_from_str(&toml).unwrap();
+        assert_eq!(cfg.take(ConfigOption::SyncRpcUrl), Some(value));
+    }
+
+    #[test]
+    fn ready_max_lag() {
+        let value = "value".to_owned();
+        let toml = format!(r#"ready-max-lag = "{}""#, value);
+        let mut cfg = config_from_str(&toml).unwrap();
+        assert_eq!(cfg.take(ConfigOption::ReadyMaxLag), Some(value));
+    }
+
+    #[test]
+    fn ready_max_l1_age() {
+        let value = "value".to_owned();
+        let toml = format!(r#"ready-max-l1-age = "{}""#, value);
+        let mut cfg = config_from_str(&toml).unwrap();
+        assert_eq!(cfg.take(ConfigOption::ReadyMaxL1Age), Some(value));
+    }
+
     #[test]
     fn empty_config() {
         let cfg = config_from_str("").unwrap();
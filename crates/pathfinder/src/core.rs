@@ -6,8 +6,11 @@ use serde::{Deserialize, Serialize};
 use stark_hash::StarkHash;
 use web3::types::{H128, H160, H256};
 
+pub mod chain_spec;
 mod macros;
 
+pub use chain_spec::ChainSpec;
+
 /// The address of a StarkNet contract.
 #[derive(Copy, Clone, PartialEq, Eq, Hash, Serialize, PartialOrd, Ord)]
 pub struct ContractAddress(StarkHash);
@@ -33,6 +36,10 @@ pub struct ContractAddressSalt(pub StarkHash);
 #[derive(Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, PartialOrd, Ord)]
 pub struct ClassHash(pub StarkHash);
 
+/// The hash of a compiled Sierra class, i.e. of its CASM representation.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, PartialOrd, Ord)]
+pub struct CasmHash(pub StarkHash);
+
 /// A StarkNet contract's state hash. This is the value stored
 /// in the global state tree.
 #[derive(Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -119,6 +126,15 @@ pub struct GlobalRoot(pub StarkHash);
 #[derive(Copy, Clone, PartialEq, Eq, Deserialize, Serialize)]
 pub struct StarknetBlockHash(pub StarkHash);
 
+/// The root of the commitment tree of a StarkNet block's transactions, including their
+/// signatures.
+#[derive(Copy, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct TransactionCommitment(pub StarkHash);
+
+/// The root of the commitment tree of the events emitted in a StarkNet block.
+#[derive(Copy, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct EventCommitment(pub StarkHash);
+
 /// A StarkNet block number.
 #[derive(Copy, Debug, Clone, PartialEq, Eq, PartialOrd)]
 pub struct StarknetBlockNumber(u64);
@@ -169,6 +185,46 @@ pub struct L1ToL2MessagePayloadElem(pub StarkHash);
 #[derive(Copy, Clone, PartialEq, Eq, Deserialize, Serialize)]
 pub struct L2ToL1MessagePayloadElem(pub StarkHash);
 
+/// The hash of a message between L1 and L2, as computed by the Starknet core contract.
+///
+/// Unlike most StarkNet hashes, this is a Keccak256 digest and not a field element.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub struct MessageHash(pub H256);
+
+impl MessageHash {
+    /// The hash of an L1 to L2 message, as computed by the core contract's `getL1ToL2MsgHash`.
+    pub fn l1_to_l2(
+        from_address: H256,
+        to_address: H256,
+        nonce: H256,
+        selector: H256,
+        payload: &[H256],
+    ) -> Self {
+        let length = H256::from_low_u64_be(payload.len() as u64);
+        Self::of_words(
+            [from_address, to_address, nonce, selector, length]
+                .iter()
+                .chain(payload),
+        )
+    }
+
+    /// The hash of an L2 to L1 message, which is the key the core contract uses to track it.
+    pub fn l2_to_l1(from_address: H256, to_address: H256, payload: &[H256]) -> Self {
+        let length = H256::from_low_u64_be(payload.len() as u64);
+        Self::of_words([from_address, to_address, length].iter().chain(payload))
+    }
+
+    fn of_words<'a>(words: impl Iterator<Item = &'a H256>) -> Self {
+        use sha3::{Digest, Keccak256};
+
+        let mut keccak = Keccak256::new();
+        for word in words {
+            keccak.update(word.as_bytes());
+        }
+        Self(H256(<[u8; 32]>::from(keccak.finalize())))
+    }
+}
+
 /// StarkNet transaction event data.
 #[derive(Copy, Clone, PartialEq, Eq, Deserialize, Serialize)]
 pub struct EventData(pub StarkHash);
@@ -233,6 +289,16 @@ impl TransactionVersion {
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize, Serialize)]
 pub struct EthereumAddress(pub H160);
 
+impl EthereumAddress {
+    /// The address as a left-padded 32 byte word, which is how the core contract's messages
+    /// encode it.
+    pub fn to_word(&self) -> H256 {
+        let mut word = H256::zero();
+        word.0[12..].copy_from_slice(self.0.as_bytes());
+        word
+    }
+}
+
 /// An Ethereum block hash.
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub struct EthereumBlockHash(pub H256);
@@ -400,6 +466,19 @@ impl From<StarknetBlockHash> for BlockId {
 pub enum EthereumChain {
     Mainnet,
     Goerli,
+    /// Any other chain, identified by its chain id. Only usable with a [ChainSpec].
+    Other(u64),
+}
+
+impl EthereumChain {
+    /// The Ethereum chain id.
+    pub fn id(&self) -> u64 {
+        match self {
+            EthereumChain::Mainnet => 1,
+            EthereumChain::Goerli => 5,
+            EthereumChain::Other(id) => *id,
+        }
+    }
 }
 
 /// Starknet chain.
@@ -409,10 +488,12 @@ pub enum Chain {
     Testnet,
     Integration,
     Testnet2,
+    /// A private deployment described by a [ChainSpec].
+    Custom(&'static ChainSpec),
 }
 
 impl Chain {
-    pub const fn starknet_chain_id(&self) -> StarkHash {
+    pub fn starknet_chain_id(&self) -> StarkHash {
         match self {
             // SN_MAIN
             Chain::Mainnet => StarkHash::from_u128(0x534e5f4d41494eu128),
@@ -422,6 +503,7 @@ impl Chain {
             Chain::Testnet2 => StarkHash::from_u128(0x534e5f474f45524c4932),
             // SN_INTEGRATION
             Chain::Integration => StarkHash::from_u128(0x534E5F494E544547524154494F4E),
+            Chain::Custom(spec) => spec.chain_id,
         }
     }
 }
@@ -433,6 +515,7 @@ impl std::fmt::Display for Chain {
             Chain::Testnet => f.write_str("Görli"),
             Chain::Testnet2 => f.write_str("Görli2"),
             Chain::Integration => f.write_str("Integration"),
+            Chain::Custom(spec) => f.write_str(&spec.name),
         }
     }
 }
@@ -449,6 +532,7 @@ macros::fmt::thin_debug!(ClassHash);
 macros::starkhash::to_from_sql!(ClassHash);
 
 macros::starkhash::common_newtype!(
+    CasmHash,
     ContractAddressSalt,
     ContractNonce,
     ContractStateHash,
@@ -463,6 +547,8 @@ macros::starkhash::common_newtype!(
     StorageValue,
     GlobalRoot,
     StarknetBlockHash,
+    TransactionCommitment,
+    EventCommitment,
     TransactionSignatureElem,
     L1ToL2MessageNonce,
     L1ToL2MessagePayloadElem,
@@ -512,4 +598,41 @@ mod tests {
             );
         }
     }
+
+    mod message_hash {
+        use super::super::MessageHash;
+        use web3::types::H256;
+
+        fn word(value: u64) -> H256 {
+            H256::from_low_u64_be(value)
+        }
+
+        #[test]
+        fn l1_to_l2() {
+            let hash = MessageHash::l1_to_l2(
+                word(0x1234),
+                word(0x5678),
+                word(7),
+                word(0x9abc),
+                &[word(1), word(2)],
+            );
+            assert_eq!(
+                hash.0,
+                H256(hex_literal::hex!(
+                    "6b0b99392fc1508b6d588a856c726398b333764605e4c9b794d8de60d180c32d"
+                ))
+            );
+        }
+
+        #[test]
+        fn l2_to_l1() {
+            let hash = MessageHash::l2_to_l1(word(0x5678), word(0x1234), &[word(3)]);
+            assert_eq!(
+                hash.0,
+                H256(hex_literal::hex!(
+                    "8bb5e08f4366c68b8f7810221fa90c8956965d29e58c754bc5e44ca5748f6e74"
+                ))
+            );
+        }
+    }
 }
@@ -0,0 +1,332 @@
+//! Chain specifications for Starknet deployments other than the public networks.
+//!
+//! A chain spec is a TOML file describing everything pathfinder otherwise hardcodes
+//! per [Chain](super::Chain):
+//!
+//! ```toml
+//! name = "my-appchain"
+//! chain-id = "SN_MY_APPCHAIN"
+//! feeder-gateway-url = "https://gateway.my-appchain.io/"
+//! ethereum-chain-id = 1337
+//! # Optional, defaults to 30 seconds.
+//! head-poll-interval = 10
+//!
+//! [genesis]
+//! block-hash = "0x47c3637b57c2b079b93c61539950c17e868a28f46cdef28f88521067f21e943"
+//! # The L1 block containing the genesis state update log.
+//! ethereum-block = 5854324
+//!
+//! [contracts]
+//! core = "0xde29d060D45901Fb19ED6C6e959EB22d8626708e"
+//! gps = "0x5EF3C980Bf970FcE5BbC217835743ea9f0388f4F"
+//! mempage = "0x743789ff2fF82Bfb907009C9911a7dA636D34FA7"
+//!
+//! # Optional, defaults to hashing every block with the Starknet 0.7+ algorithm.
+//! [block-hash]
+//! first-0-7-block = 0
+//! not-verifiable-range = [100, 200]
+//! fallback-sequencer-address = "0x46a89ae102987331d369645031b49c27738ed096f2789c24449966da4c6de6b"
+//! ```
+use std::ops::Range;
+use std::path::Path;
+use std::time::Duration;
+
+use anyhow::Context;
+use reqwest::Url;
+use serde::Deserialize;
+use stark_hash::StarkHash;
+use web3::types::H160;
+
+use super::{EthereumBlockNumber, SequencerAddress, StarknetBlockHash, StarknetBlockNumber};
+
+/// Describes a custom Starknet deployment, see the [module documentation](self) for the file format.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChainSpec {
+    /// Human readable name, also used to name the database file.
+    pub name: String,
+    /// The Starknet chain id, encoded from its short string.
+    pub chain_id: StarkHash,
+    /// Base URL of the sequencer's gateway and feeder gateway.
+    pub feeder_gateway_url: Url,
+    /// Chain id of the L1 the deployment settles on.
+    pub ethereum_chain_id: u64,
+    /// Interval at which to poll for new blocks when at the head of the chain.
+    pub head_poll_interval: Duration,
+    pub genesis: Genesis,
+    pub contracts: L1Contracts,
+    pub block_hash: BlockHashAlgorithm,
+}
+
+/// The genesis block of a [ChainSpec].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Genesis {
+    pub block_hash: StarknetBlockHash,
+    /// The Ethereum block containing the genesis state update log.
+    pub ethereum_block: EthereumBlockNumber,
+}
+
+/// The L1 Starknet contract addresses of a [ChainSpec].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct L1Contracts {
+    pub core: H160,
+    pub gps: H160,
+    pub mempage: H160,
+}
+
+/// The block hash algorithm switches of a [ChainSpec].
+///
+/// See [block_hash](crate::state::block_hash) for what these mean.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockHashAlgorithm {
+    /// The number of the first block that was hashed with the Starknet 0.7 hash algorithm.
+    pub first_0_7_block: StarknetBlockNumber,
+    /// The range of block numbers that can't be verified because of an unknown sequencer address.
+    pub not_verifiable_range: Option<Range<StarknetBlockNumber>>,
+    /// Fallback sequencer address to use for blocks that don't include the address.
+    pub fallback_sequencer_address: SequencerAddress,
+}
+
+impl ChainSpec {
+    const DEFAULT_HEAD_POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+    /// Reads and validates the chain spec at the given path.
+    pub fn from_file(path: &Path) -> anyhow::Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Reading chain spec from {}", path.display()))?;
+        Self::from_toml(&contents)
+    }
+
+    /// Reads the chain spec at the given path and leaks it, so that it can be used
+    /// in a [Chain::Custom](super::Chain::Custom) for the remainder of the process.
+    pub fn load(path: &Path) -> anyhow::Result<&'static Self> {
+        Ok(Box::leak(Box::new(Self::from_file(path)?)))
+    }
+
+    fn from_toml(s: &str) -> anyhow::Result<Self> {
+        let file = toml::from_str::<FileSpec>(s).context("Parsing chain spec")?;
+        file.try_into()
+    }
+
+    /// The identity the database of this chain is tagged with.
+    pub fn identity(&self) -> (StarkHash, StarknetBlockHash) {
+        (self.chain_id, self.genesis.block_hash)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+struct FileSpec {
+    name: String,
+    chain_id: String,
+    feeder_gateway_url: String,
+    ethereum_chain_id: u64,
+    head_poll_interval: Option<u64>,
+    genesis: FileGenesis,
+    contracts: FileContracts,
+    block_hash: Option<FileBlockHash>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+struct FileGenesis {
+    block_hash: String,
+    ethereum_block: u64,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FileContracts {
+    core: String,
+    gps: String,
+    mempage: String,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+struct FileBlockHash {
+    first_0_7_block: Option<u64>,
+    not_verifiable_range: Option<(u64, u64)>,
+    fallback_sequencer_address: Option<String>,
+}
+
+impl TryFrom<FileSpec> for ChainSpec {
+    type Error = anyhow::Error;
+
+    fn try_from(file: FileSpec) -> Result<Self, Self::Error> {
+        anyhow::ensure!(
+            !file.name.is_empty()
+                && file
+                    .name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'),
+            "Chain name must be non-empty and consist of ASCII alphanumerics, '-' or '_'"
+        );
+
+        let feeder_gateway_url =
+            Url::parse(&file.feeder_gateway_url).context("Parsing feeder-gateway-url")?;
+
+        let genesis = Genesis {
+            block_hash: StarknetBlockHash(
+                parse_hash(&file.genesis.block_hash).context("Parsing genesis block-hash")?,
+            ),
+            ethereum_block: EthereumBlockNumber(file.genesis.ethereum_block),
+        };
+
+        let contracts = L1Contracts {
+            core: parse_address(&file.contracts.core).context("Parsing core contract address")?,
+            gps: parse_address(&file.contracts.gps).context("Parsing gps contract address")?,
+            mempage: parse_address(&file.contracts.mempage)
+                .context("Parsing mempage contract address")?,
+        };
+
+        let block_hash = match file.block_hash {
+            Some(block_hash) => BlockHashAlgorithm {
+                first_0_7_block: block_number(block_hash.first_0_7_block.unwrap_or_default())?,
+                not_verifiable_range: block_hash
+                    .not_verifiable_range
+                    .map(|(start, end)| -> anyhow::Result<_> {
+                        Ok(block_number(start)?..block_number(end)?)
+                    })
+                    .transpose()?,
+                fallback_sequencer_address: match block_hash.fallback_sequencer_address {
+                    Some(address) => SequencerAddress(
+                        parse_hash(&address).context("Parsing fallback-sequencer-address")?,
+                    ),
+                    None => SequencerAddress(StarkHash::ZERO),
+                },
+            },
+            None => BlockHashAlgorithm {
+                first_0_7_block: StarknetBlockNumber::GENESIS,
+                not_verifiable_range: None,
+                fallback_sequencer_address: SequencerAddress(StarkHash::ZERO),
+            },
+        };
+
+        Ok(Self {
+            name: file.name,
+            chain_id: encode_short_string(&file.chain_id)?,
+            feeder_gateway_url,
+            ethereum_chain_id: file.ethereum_chain_id,
+            head_poll_interval: file
+                .head_poll_interval
+                .map(Duration::from_secs)
+                .unwrap_or(Self::DEFAULT_HEAD_POLL_INTERVAL),
+            genesis,
+            contracts,
+            block_hash,
+        })
+    }
+}
+
+/// Encodes a Cairo short string, i.e. at most 31 ASCII characters, as a [StarkHash].
+fn encode_short_string(s: &str) -> anyhow::Result<StarkHash> {
+    anyhow::ensure!(
+        s.is_ascii() && !s.is_empty() && s.len() <= 31,
+        "Chain id must be a short string of 1 to 31 ASCII characters"
+    );
+
+    Ok(StarkHash::from_be_slice(s.as_bytes()).expect("31 bytes cannot overflow"))
+}
+
+fn parse_hash(s: &str) -> anyhow::Result<StarkHash> {
+    Ok(StarkHash::from_hex_str(s)?)
+}
+
+fn parse_address(s: &str) -> anyhow::Result<H160> {
+    let s = s.strip_prefix("0x").unwrap_or(s);
+    let bytes = hex::decode(s)?;
+    anyhow::ensure!(bytes.len() == 20, "Expected 20 bytes, got {}", bytes.len());
+
+    Ok(H160::from_slice(&bytes))
+}
+
+fn block_number(number: u64) -> anyhow::Result<StarknetBlockNumber> {
+    StarknetBlockNumber::new(number).context("Block number out of range")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::starkhash;
+
+    const SPEC: &str = r#"
+name = "my-appchain"
+chain-id = "SN_GOERLI"
+feeder-gateway-url = "https://gateway.my-appchain.io/"
+ethereum-chain-id = 1337
+head-poll-interval = 10
+
+[genesis]
+block-hash = "0x7d328a71faf48c5c3857e99f20a77b18522480956d1cd5bff1ff2df3c8b427b"
+ethereum-block = 5854324
+
+[contracts]
+core = "0xde29d060D45901Fb19ED6C6e959EB22d8626708e"
+gps = "0x5EF3C980Bf970FcE5BbC217835743ea9f0388f4F"
+mempage = "0x743789ff2fF82Bfb907009C9911a7dA636D34FA7"
+
+[block-hash]
+first-0-7-block = 47028
+not-verifiable-range = [119802, 148428]
+fallback-sequencer-address = "0x46a89ae102987331d369645031b49c27738ed096f2789c24449966da4c6de6b"
+"#;
+
+    #[test]
+    fn parse() {
+        let spec = ChainSpec::from_toml(SPEC).unwrap();
+
+        assert_eq!(spec.name, "my-appchain");
+        assert_eq!(
+            spec.chain_id,
+            crate::core::Chain::Testnet.starknet_chain_id()
+        );
+        assert_eq!(spec.head_poll_interval, Duration::from_secs(10));
+        assert_eq!(spec.genesis.block_hash, crate::consts::TESTNET_GENESIS_HASH);
+        assert_eq!(
+            spec.contracts.core,
+            crate::ethereum::contract::addresses(crate::core::Chain::Testnet).core
+        );
+        assert_eq!(
+            spec.block_hash.not_verifiable_range,
+            Some(
+                StarknetBlockNumber::new_or_panic(119802)
+                    ..StarknetBlockNumber::new_or_panic(148428)
+            )
+        );
+        assert_eq!(
+            spec.block_hash.fallback_sequencer_address,
+            SequencerAddress(starkhash!(
+                "046a89ae102987331d369645031b49c27738ed096f2789c24449966da4c6de6b"
+            ))
+        );
+    }
+
+    #[test]
+    fn defaults() {
+        let spec = SPEC
+            .split("[block-hash]")
+            .next()
+            .unwrap()
+            .replace("head-poll-interval = 10\n", "");
+        let spec = ChainSpec::from_toml(&spec).unwrap();
+
+        assert_eq!(
+            spec.head_poll_interval,
+            ChainSpec::DEFAULT_HEAD_POLL_INTERVAL
+        );
+        assert_eq!(
+            spec.block_hash,
+            BlockHashAlgorithm {
+                first_0_7_block: StarknetBlockNumber::GENESIS,
+                not_verifiable_range: None,
+                fallback_sequencer_address: SequencerAddress(StarkHash::ZERO),
+            }
+        );
+    }
+
+    #[test]
+    fn chain_id_too_long() {
+        let spec = SPEC.replace("SN_GOERLI", &"A".repeat(32));
+        ChainSpec::from_toml(&spec).unwrap_err();
+    }
+}
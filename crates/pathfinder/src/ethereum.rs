@@ -133,8 +133,59 @@ impl TryFrom<&web3::types::Log> for EthOrigin {
     }
 }
 
+/// How deep an L1 log must be before it is trusted.
+///
+/// Logs in the unconfirmed window are not synced at all, which prevents states from
+/// being accepted on L1 only to be reverted by an L1 reorg shortly afterwards.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Confirmation {
+    /// The log must be at least this many blocks below the L1 head. Zero trusts all logs.
+    Depth(u64),
+    /// The log must be at or below the L1 `safe` block.
+    Safe,
+    /// The log must be at or below the L1 `finalized` block.
+    Finalized,
+}
+
+impl Default for Confirmation {
+    fn default() -> Self {
+        Self::Depth(0)
+    }
+}
+
+impl std::str::FromStr for Confirmation {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "safe" => Ok(Self::Safe),
+            "finalized" => Ok(Self::Finalized),
+            depth => depth.parse::<u64>().map(Self::Depth).with_context(|| {
+                format!("Expected 'safe', 'finalized' or a block count but got '{depth}'")
+            }),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    #[test]
+    fn parse_confirmation() {
+        use super::Confirmation;
+
+        assert_eq!("safe".parse::<Confirmation>().unwrap(), Confirmation::Safe);
+        assert_eq!(
+            "finalized".parse::<Confirmation>().unwrap(),
+            Confirmation::Finalized
+        );
+        assert_eq!(
+            "12".parse::<Confirmation>().unwrap(),
+            Confirmation::Depth(12)
+        );
+        "latest".parse::<Confirmation>().unwrap_err();
+        "-1".parse::<Confirmation>().unwrap_err();
+    }
+
     mod chain {
         use crate::core::{Chain, EthereumChain};
         use crate::ethereum::transport::{EthereumTransport, HttpTransport};
@@ -73,6 +73,11 @@ pub fn addresses(chain: Chain) -> ContractAddresses {
         Chain::Testnet => TESTNET_ADDRESSES,
         Chain::Testnet2 => TESTNET2_ADDRESSES,
         Chain::Integration => INTEGRATION_ADDRESSES,
+        Chain::Custom(spec) => ContractAddresses {
+            core: spec.contracts.core,
+            gps: spec.contracts.gps,
+            mempage: spec.contracts.mempage,
+        },
     }
 }
 
@@ -96,6 +101,12 @@ lazy_static::lazy_static!(
             .expect("LogStateUpdate event not found in core contract ABI").to_owned();
     pub static ref STATE_TRANSITION_FACT_EVENT: Event = core_contract().event("LogStateTransitionFact")
             .expect("LogStateTransitionFact event not found in core contract ABI").to_owned();
+    pub static ref MESSAGE_TO_L2_EVENT: Event = core_contract().event("LogMessageToL2")
+            .expect("LogMessageToL2 event not found in core contract ABI").to_owned();
+    pub static ref CONSUMED_MESSAGE_TO_L2_EVENT: Event = core_contract().event("ConsumedMessageToL2")
+            .expect("ConsumedMessageToL2 event not found in core contract ABI").to_owned();
+    pub static ref CONSUMED_MESSAGE_TO_L1_EVENT: Event = core_contract().event("ConsumedMessageToL1")
+            .expect("ConsumedMessageToL1 event not found in core contract ABI").to_owned();
     pub static ref MEMORY_PAGE_HASHES_EVENT: Event = gps_contract().event("LogMemoryPagesHashes")
             .expect("LogMemoryPagesHashes event not found in GPS contract ABI").to_owned();
     pub static ref MEMORY_PAGE_FACT_CONTINUOUS_EVENT: Event = mempage_contract().event("LogMemoryPageFactContinuous")
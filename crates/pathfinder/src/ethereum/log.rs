@@ -3,10 +3,10 @@ mod parse;
 
 pub use fetch::*;
 
-use web3::types::H256;
+use web3::types::{H256, U256};
 
 use crate::{
-    core::{GlobalRoot, StarknetBlockNumber},
+    core::{EthereumAddress, GlobalRoot, MessageHash, StarknetBlockNumber},
     ethereum::EthOrigin,
 };
 
@@ -53,3 +53,88 @@ pub struct MemoryPageFactContinuousLog {
     pub origin: EthOrigin,
     pub hash: H256,
 }
+
+/// A message sent from L1 to L2, which is consumed once the sequencer executes its L1 handler.
+///
+/// This is emitted by the Starknet core contract.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MessageToL2Log {
+    pub origin: EthOrigin,
+    pub hash: MessageHash,
+    pub from_address: EthereumAddress,
+    pub to_address: H256,
+    pub selector: H256,
+    pub payload: Vec<H256>,
+    pub nonce: H256,
+    pub fee: U256,
+}
+
+/// An L1 to L2 message was consumed by a state update.
+///
+/// This is emitted by the Starknet core contract.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConsumedMessageToL2Log {
+    pub origin: EthOrigin,
+    pub hash: MessageHash,
+    pub from_address: EthereumAddress,
+    pub to_address: H256,
+    pub selector: H256,
+    pub payload: Vec<H256>,
+    pub nonce: H256,
+}
+
+/// An L2 to L1 message was consumed, i.e. claimed by its recipient on L1.
+///
+/// This is emitted by the Starknet core contract.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConsumedMessageToL1Log {
+    pub origin: EthOrigin,
+    pub hash: MessageHash,
+    pub from_address: H256,
+    pub to_address: EthereumAddress,
+    pub payload: Vec<H256>,
+}
+
+/// One of the message logs of the Starknet core contract.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MessageLog {
+    ToL2(MessageToL2Log),
+    ConsumedToL2(ConsumedMessageToL2Log),
+    ConsumedToL1(ConsumedMessageToL1Log),
+}
+
+impl MessageLog {
+    pub fn origin(&self) -> &EthOrigin {
+        match self {
+            MessageLog::ToL2(log) => &log.origin,
+            MessageLog::ConsumedToL2(log) => &log.origin,
+            MessageLog::ConsumedToL1(log) => &log.origin,
+        }
+    }
+
+    pub fn hash(&self) -> MessageHash {
+        match self {
+            MessageLog::ToL2(log) => log.hash,
+            MessageLog::ConsumedToL2(log) => log.hash,
+            MessageLog::ConsumedToL1(log) => log.hash,
+        }
+    }
+}
+
+impl From<MessageToL2Log> for MessageLog {
+    fn from(log: MessageToL2Log) -> Self {
+        MessageLog::ToL2(log)
+    }
+}
+
+impl From<ConsumedMessageToL2Log> for MessageLog {
+    fn from(log: ConsumedMessageToL2Log) -> Self {
+        MessageLog::ConsumedToL2(log)
+    }
+}
+
+impl From<ConsumedMessageToL1Log> for MessageLog {
+    fn from(log: ConsumedMessageToL1Log) -> Self {
+        MessageLog::ConsumedToL1(log)
+    }
+}
@@ -5,7 +5,8 @@ use crate::{
     core::{Chain, EthereumBlockNumber},
     ethereum::{
         log::fetch::MetaLog,
-        transport::{EthereumTransport, LogsError},
+        transport::{BlockTag, EthereumTransport, LogsError},
+        Confirmation,
     },
 };
 
@@ -20,6 +21,7 @@ where
     genesis: EthereumBlockNumber,
     stride: u64,
     base_filter: FilterBuilder,
+    confirmation: Confirmation,
 }
 
 #[derive(Debug)]
@@ -54,9 +56,15 @@ where
             stride: 10_000,
             base_filter,
             genesis,
+            confirmation: Confirmation::default(),
         }
     }
 
+    /// Only fetch logs which have reached the given [Confirmation] on L1.
+    pub fn set_confirmation(&mut self, confirmation: Confirmation) {
+        self.confirmation = confirmation;
+    }
+
     pub fn set_head(&mut self, head: Option<T>) {
         self.head = head;
     }
@@ -88,6 +96,15 @@ where
             .as_ref()
             .map(|update| update.origin().block.number.0)
             .unwrap_or(self.genesis.0);
+
+        // Logs above the confirmed block are ignored until they are confirmed. The reorg
+        // handling below still applies, as a confirmation depth does not rule out reorgs.
+        let confirmed_block = confirmed_block(self.confirmation, &transport).await?;
+        if let Some(confirmed_block) = confirmed_block {
+            if from_block > confirmed_block {
+                return Ok(Vec::new());
+            }
+        }
         let base_filter = self
             .base_filter
             .clone()
@@ -105,6 +122,7 @@ where
 
         loop {
             let to_block = from_block.saturating_add(self.stride);
+            let to_block = confirmed_block.map_or(to_block, |c| to_block.min(c));
             let filter = base_filter
                 .clone()
                 .to_block(BlockNumber::Number(to_block.into()))
@@ -168,10 +186,13 @@ where
             // If there are no new logs, then either we have reached the end of L1,
             // or we need to increase our query range.
             if logs.is_empty() {
-                let chain_head = transport
-                    .block_number()
-                    .await
-                    .context("Get latest block number from L1")?;
+                let chain_head = match confirmed_block {
+                    Some(confirmed_block) => confirmed_block,
+                    None => transport
+                        .block_number()
+                        .await
+                        .context("Get latest block number from L1")?,
+                };
 
                 if to_block < chain_head {
                     match stride_cap {
@@ -191,6 +212,33 @@ where
     }
 }
 
+/// Returns the latest L1 block which satisfies the `confirmation`, or [None] if all
+/// blocks are trusted.
+async fn confirmed_block(
+    confirmation: Confirmation,
+    transport: &impl EthereumTransport,
+) -> anyhow::Result<Option<u64>> {
+    let tag = match confirmation {
+        Confirmation::Depth(0) => return Ok(None),
+        Confirmation::Depth(depth) => {
+            let chain_head = transport
+                .block_number()
+                .await
+                .context("Get latest block number from L1")?;
+            return Ok(Some(chain_head.saturating_sub(depth)));
+        }
+        Confirmation::Safe => BlockTag::Safe,
+        Confirmation::Finalized => BlockTag::Finalized,
+    };
+
+    let block = transport
+        .tagged_block_number(tag)
+        .await
+        .with_context(|| format!("Get {} block number from L1", tag.as_str()))?
+        .with_context(|| format!("L1 endpoint does not support the {} tag", tag.as_str()))?;
+    Ok(Some(block))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
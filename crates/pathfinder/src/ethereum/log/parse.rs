@@ -7,15 +7,16 @@ use web3::{
 };
 
 use crate::{
-    core::{GlobalRoot, StarknetBlockNumber},
+    core::{EthereumAddress, GlobalRoot, MessageHash, StarknetBlockNumber},
     ethereum::{
         contract::{
-            MEMORY_PAGE_FACT_CONTINUOUS_EVENT, MEMORY_PAGE_HASHES_EVENT,
+            CONSUMED_MESSAGE_TO_L1_EVENT, CONSUMED_MESSAGE_TO_L2_EVENT,
+            MEMORY_PAGE_FACT_CONTINUOUS_EVENT, MEMORY_PAGE_HASHES_EVENT, MESSAGE_TO_L2_EVENT,
             STATE_TRANSITION_FACT_EVENT, STATE_UPDATE_EVENT,
         },
         log::{
-            MemoryPageFactContinuousLog, MemoryPagesHashesLog, StateTransitionFactLog,
-            StateUpdateLog,
+            ConsumedMessageToL1Log, ConsumedMessageToL2Log, MemoryPageFactContinuousLog,
+            MemoryPagesHashesLog, MessageToL2Log, StateTransitionFactLog, StateUpdateLog,
         },
         EthOrigin,
     },
@@ -118,6 +119,103 @@ impl TryFrom<web3::types::Log> for MemoryPageFactContinuousLog {
     }
 }
 
+impl TryFrom<web3::types::Log> for MessageToL2Log {
+    type Error = anyhow::Error;
+
+    fn try_from(value: web3::types::Log) -> Result<Self, Self::Error> {
+        let (origin, raw_log) = parse_web3_log(value)?;
+
+        let log = MESSAGE_TO_L2_EVENT.parse_log(raw_log)?;
+
+        let from_address = get_address_param(&log, "fromAddress")?;
+        let to_address = get_word_param(&log, "toAddress")?;
+        let selector = get_word_param(&log, "selector")?;
+        let payload = get_words_param(&log, "payload")?;
+        let nonce = get_word_param(&log, "nonce")?;
+        let fee = get_log_param(&log, "fee")?
+            .value
+            .into_uint()
+            .context("fee could not be parsed")?;
+
+        let hash = MessageHash::l1_to_l2(
+            from_address.to_word(),
+            to_address,
+            nonce,
+            selector,
+            &payload,
+        );
+
+        Ok(Self {
+            origin,
+            hash,
+            from_address,
+            to_address,
+            selector,
+            payload,
+            nonce,
+            fee,
+        })
+    }
+}
+
+impl TryFrom<web3::types::Log> for ConsumedMessageToL2Log {
+    type Error = anyhow::Error;
+
+    fn try_from(value: web3::types::Log) -> Result<Self, Self::Error> {
+        let (origin, raw_log) = parse_web3_log(value)?;
+
+        let log = CONSUMED_MESSAGE_TO_L2_EVENT.parse_log(raw_log)?;
+
+        let from_address = get_address_param(&log, "fromAddress")?;
+        let to_address = get_word_param(&log, "toAddress")?;
+        let selector = get_word_param(&log, "selector")?;
+        let payload = get_words_param(&log, "payload")?;
+        let nonce = get_word_param(&log, "nonce")?;
+
+        let hash = MessageHash::l1_to_l2(
+            from_address.to_word(),
+            to_address,
+            nonce,
+            selector,
+            &payload,
+        );
+
+        Ok(Self {
+            origin,
+            hash,
+            from_address,
+            to_address,
+            selector,
+            payload,
+            nonce,
+        })
+    }
+}
+
+impl TryFrom<web3::types::Log> for ConsumedMessageToL1Log {
+    type Error = anyhow::Error;
+
+    fn try_from(value: web3::types::Log) -> Result<Self, Self::Error> {
+        let (origin, raw_log) = parse_web3_log(value)?;
+
+        let log = CONSUMED_MESSAGE_TO_L1_EVENT.parse_log(raw_log)?;
+
+        let from_address = get_word_param(&log, "fromAddress")?;
+        let to_address = get_address_param(&log, "toAddress")?;
+        let payload = get_words_param(&log, "payload")?;
+
+        let hash = MessageHash::l2_to_l1(from_address, to_address.to_word(), &payload);
+
+        Ok(Self {
+            origin,
+            hash,
+            from_address,
+            to_address,
+            payload,
+        })
+    }
+}
+
 /// Utility which extracts the [EthOrigin] and log index, and then converts to a [RawLog].
 fn parse_web3_log(log: web3::types::Log) -> anyhow::Result<(EthOrigin, RawLog)> {
     let origin = EthOrigin::try_from(&log)?;
@@ -139,6 +237,36 @@ fn get_log_param(log: &web3::ethabi::Log, param: &str) -> anyhow::Result<LogPara
         .with_context(|| format!("parameter {} not found", param))
 }
 
+/// Utility function to retrieve a named `address` parameter from a log.
+fn get_address_param(log: &web3::ethabi::Log, param: &str) -> anyhow::Result<EthereumAddress> {
+    get_log_param(log, param)?
+        .value
+        .into_address()
+        .map(EthereumAddress)
+        .with_context(|| format!("{} could not be parsed", param))
+}
+
+/// Utility function to retrieve a named `uint256` parameter from a log, as a 32 byte word.
+fn get_word_param(log: &web3::ethabi::Log, param: &str) -> anyhow::Result<H256> {
+    get_log_param(log, param)?
+        .value
+        .into_uint()
+        .map(|word| H256::from_uint(&word))
+        .with_context(|| format!("{} could not be parsed", param))
+}
+
+/// Utility function to retrieve a named `uint256[]` parameter from a log, as 32 byte words.
+fn get_words_param(log: &web3::ethabi::Log, param: &str) -> anyhow::Result<Vec<H256>> {
+    get_log_param(log, param)?
+        .value
+        .into_array()
+        .with_context(|| format!("{} could not be cast to array", param))?
+        .into_iter()
+        .map(|token| token.into_uint().map(|word| H256::from_uint(&word)))
+        .collect::<Option<Vec<_>>>()
+        .with_context(|| format!("{} could not be parsed", param))
+}
+
 #[cfg(test)]
 mod tests {
     use hex::FromHex;
@@ -495,4 +623,116 @@ mod tests {
             MemoryPageFactContinuousLog::try_from(log).unwrap_err();
         }
     }
+
+    mod messages {
+        use super::*;
+        use pretty_assertions::assert_eq;
+        use web3::ethabi::Token;
+
+        fn word(value: u64) -> H256 {
+            H256::from_low_u64_be(value)
+        }
+
+        fn uint(value: u64) -> Token {
+            Token::Uint(U256::from(value))
+        }
+
+        /// Creates a log with the given indexed topics and ABI encoded data.
+        fn message_log(signature: H256, topics: Vec<H256>, data: Vec<Token>) -> web3::types::Log {
+            let mut log = create_test_log(signature, web3::ethabi::encode(&data));
+            log.topics.extend(topics);
+            log
+        }
+
+        /// The hash is the one of L1 to L2 message from `0x1234` to `0x5678`, with selector
+        /// `0x9abc`, nonce 7 and payload `[1, 2]`.
+        fn l1_to_l2_hash() -> MessageHash {
+            MessageHash(H256(hex_literal::hex!(
+                "6b0b99392fc1508b6d588a856c726398b333764605e4c9b794d8de60d180c32d"
+            )))
+        }
+
+        #[test]
+        fn message_to_l2() {
+            let log = message_log(
+                MESSAGE_TO_L2_EVENT.signature(),
+                vec![word(0x1234), word(0x5678), word(0x9abc)],
+                vec![Token::Array(vec![uint(1), uint(2)]), uint(7), uint(1000)],
+            );
+            let origin = EthOrigin::try_from(&log).unwrap();
+
+            let result = MessageToL2Log::try_from(log).unwrap();
+            assert_eq!(
+                result,
+                MessageToL2Log {
+                    origin,
+                    hash: l1_to_l2_hash(),
+                    from_address: EthereumAddress(H160::from_low_u64_be(0x1234)),
+                    to_address: word(0x5678),
+                    selector: word(0x9abc),
+                    payload: vec![word(1), word(2)],
+                    nonce: word(7),
+                    fee: U256::from(1000),
+                }
+            );
+        }
+
+        #[test]
+        fn message_to_l2_missing_topic() {
+            let log = message_log(
+                MESSAGE_TO_L2_EVENT.signature(),
+                vec![word(0x1234), word(0x5678)],
+                vec![Token::Array(vec![uint(1), uint(2)]), uint(7), uint(1000)],
+            );
+            MessageToL2Log::try_from(log).unwrap_err();
+        }
+
+        #[test]
+        fn consumed_message_to_l2() {
+            let log = message_log(
+                CONSUMED_MESSAGE_TO_L2_EVENT.signature(),
+                vec![word(0x1234), word(0x5678), word(0x9abc)],
+                vec![Token::Array(vec![uint(1), uint(2)]), uint(7)],
+            );
+
+            let result = ConsumedMessageToL2Log::try_from(log).unwrap();
+            assert_eq!(result.hash, l1_to_l2_hash());
+            assert_eq!(result.nonce, word(7));
+        }
+
+        #[test]
+        fn consumed_message_to_l1() {
+            let log = message_log(
+                CONSUMED_MESSAGE_TO_L1_EVENT.signature(),
+                vec![word(0x5678), word(0x1234)],
+                vec![Token::Array(vec![uint(3)])],
+            );
+            let origin = EthOrigin::try_from(&log).unwrap();
+
+            let result = ConsumedMessageToL1Log::try_from(log).unwrap();
+            assert_eq!(
+                result,
+                ConsumedMessageToL1Log {
+                    origin,
+                    hash: MessageHash(H256(hex_literal::hex!(
+                        "8bb5e08f4366c68b8f7810221fa90c8956965d29e58c754bc5e44ca5748f6e74"
+                    ))),
+                    from_address: word(0x5678),
+                    to_address: EthereumAddress(H160::from_low_u64_be(0x1234)),
+                    payload: vec![word(3)],
+                }
+            );
+        }
+
+        #[test]
+        fn consumed_message_to_l1_bad_data() {
+            let mut log = message_log(
+                CONSUMED_MESSAGE_TO_L1_EVENT.signature(),
+                vec![word(0x5678), word(0x1234)],
+                vec![Token::Array(vec![uint(3)])],
+            );
+            log.data.0.pop();
+            ConsumedMessageToL1Log::try_from(log).unwrap_err();
+        }
+    }
 }
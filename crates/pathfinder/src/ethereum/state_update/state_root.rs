@@ -16,16 +16,21 @@ const TESTNET2_GENESIS: EthereumBlockNumber = EthereumBlockNumber(5_854_324);
 /// The Goerli Ethereum block containing the Starknet genesis [StateUpdateLog] for integration.
 const INTEGRATION_GENESIS: EthereumBlockNumber = EthereumBlockNumber(5_986_835);
 
+/// The Ethereum block containing the Starknet genesis [StateUpdateLog] of `chain`, i.e. the
+/// first block the Starknet core contract emitted logs in.
+pub fn genesis(chain: Chain) -> EthereumBlockNumber {
+    match chain {
+        Chain::Mainnet => MAINNET_GENESIS,
+        Chain::Testnet => TESTNET_GENESIS,
+        Chain::Testnet2 => TESTNET2_GENESIS,
+        Chain::Integration => INTEGRATION_GENESIS,
+        Chain::Custom(spec) => spec.genesis.ethereum_block,
+    }
+}
+
 impl StateRootFetcher {
     pub fn new(head: Option<StateUpdateLog>, chain: Chain) -> Self {
-        let genesis = match chain {
-            Chain::Mainnet => MAINNET_GENESIS,
-            Chain::Testnet => TESTNET_GENESIS,
-            Chain::Testnet2 => TESTNET2_GENESIS,
-            Chain::Integration => INTEGRATION_GENESIS,
-        };
-
-        let inner = LogFetcher::<StateUpdateLog>::new(head, chain, genesis);
+        let inner = LogFetcher::<StateUpdateLog>::new(head, chain, genesis(chain));
         Self(inner)
     }
 }
@@ -29,6 +29,24 @@ pub enum LogsError {
     Other(#[from] web3::Error),
 }
 
+/// Ethereum block tags which refer to blocks that are unlikely to be reorged, see [EIP-1898].
+///
+/// [EIP-1898]: https://eips.ethereum.org/EIPS/eip-1898
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockTag {
+    Safe,
+    Finalized,
+}
+
+impl BlockTag {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BlockTag::Safe => "safe",
+            BlockTag::Finalized => "finalized",
+        }
+    }
+}
+
 /// Contains only those functions from [`Web3::eth()`](https://docs.rs/web3/latest/web3/api/struct.Eth.html)
 /// that [the ethereum module](super) uses.
 #[async_trait::async_trait]
@@ -39,6 +57,9 @@ pub trait EthereumTransport {
     async fn logs(&self, filter: Filter) -> std::result::Result<Vec<Log>, LogsError>;
     async fn transaction(&self, id: TransactionId) -> web3::Result<Option<Transaction>>;
     async fn gas_price(&self) -> web3::Result<U256>;
+    /// Returns the number of the block with the given tag, or [None] if the endpoint
+    /// does not support the tag.
+    async fn tagged_block_number(&self, tag: BlockTag) -> web3::Result<Option<u64>>;
 }
 
 /// An implementation of [`EthereumTransport`] which uses [`Web3::eth()`](https://docs.rs/web3/latest/web3/api/struct.Eth.html)
@@ -99,6 +120,7 @@ impl HttpTransport {
             Chain::Testnet | Chain::Testnet2 | Chain::Integration => {
                 "PATHFINDER_ETHEREUM_HTTP_GOERLI"
             }
+            Chain::Custom(_) => "PATHFINDER_ETHEREUM_HTTP_CUSTOM",
         };
 
         let url_key = format!("{}_URL", key_prefix);
@@ -137,13 +159,14 @@ impl EthereumTransport for HttpTransport {
 
     /// Identifies the [EthereumChain] behind the given Ethereum transport.
     ///
-    /// Will error if it's not one of the valid Starknet [EthereumChain] variants.
+    /// Chains other than Mainnet and Goerli are returned as [EthereumChain::Other].
     /// Internaly wraps [`Web3::chain_id()`](https://docs.rs/web3/latest/web3/api/struct.Eth.html#method.chain_id)
     /// into exponential retry on __all__ errors.
     async fn chain(&self) -> anyhow::Result<EthereumChain> {
         match retry(|| self.0.eth().chain_id(), log_and_always_retry).await? {
             id if id == U256::from(1u32) => Ok(EthereumChain::Mainnet),
             id if id == U256::from(5u32) => Ok(EthereumChain::Goerli),
+            id if id <= U256::from(u64::MAX) => Ok(EthereumChain::Other(id.as_u64())),
             other => anyhow::bail!("Unsupported chain ID: {}", other),
         }
     }
@@ -221,6 +244,41 @@ impl EthereumTransport for HttpTransport {
     async fn gas_price(&self) -> web3::Result<U256> {
         retry(|| self.0.eth().gas_price(), log_and_always_retry).await
     }
+
+    /// Queries `eth_getBlockByNumber` using the block tag, as [`Web3::eth().block()`](https://docs.rs/web3/latest/web3/api/struct.Eth.html#method.block)
+    /// does not support the `safe` and `finalized` tags. Retries on all errors except RPC errors,
+    /// which are returned by nodes that do not know the tag.
+    async fn tagged_block_number(&self, tag: BlockTag) -> web3::Result<Option<u64>> {
+        use web3::Transport;
+
+        let params = vec![
+            web3::helpers::serialize(&tag.as_str()),
+            web3::helpers::serialize(&false),
+        ];
+        let block = retry(
+            || {
+                web3::helpers::CallFuture::<Option<Block<H256>>, _>::new(
+                    self.0
+                        .transport()
+                        .execute("eth_getBlockByNumber", params.clone()),
+                )
+            },
+            |error| match error {
+                Error::Rpc(_) => false,
+                other => log_and_always_retry(other),
+            },
+        )
+        .await;
+
+        match block {
+            Ok(block) => Ok(block.and_then(|b| b.number).map(|n| n.as_u64())),
+            Err(Error::Rpc(error)) => {
+                debug!(reason=%error, tag=%tag.as_str(), "L1 block tag is not supported");
+                Ok(None)
+            }
+            Err(other) => Err(other),
+        }
+    }
 }
 
 /// A helper function to keep the backoff strategy consistent across different Web3 Eth API calls.
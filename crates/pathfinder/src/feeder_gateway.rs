@@ -0,0 +1,1147 @@
+//! A sequencer feeder gateway compatible REST API, served from pathfinder's own database.
+//!
+//! This lets other nodes and tooling which talk to the feeder gateway use this node instead of
+//! the sequencer, e.g. another pathfinder instance started with `--sequencer-url`. Replies are
+//! serialized from the [sequencer reply](crate::sequencer::reply) types, so that a
+//! [sequencer::Client](crate::sequencer::Client) pointed at this server works as usual.
+//!
+//! Only the read-only `feeder_gateway` endpoints are served, transactions have to be submitted
+//! to the sequencer's gateway.
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use anyhow::Context as _;
+use rusqlite::Transaction;
+use stark_hash::StarkHash;
+use warp::http::StatusCode;
+use warp::{Filter, Reply};
+
+use crate::core::{
+    BlockId, Chain, ClassHash, ContractAddress, ContractNonce, ContractStateHash, EthereumAddress,
+    GasPrice, GlobalRoot, SequencerAddress, StarknetBlockHash, StarknetBlockNumber,
+    StarknetTransactionHash, StorageAddress, StorageValue,
+};
+use crate::sequencer::error::{StarknetError, StarknetErrorCode};
+use crate::sequencer::reply;
+use crate::state::state_tree::{ContractsStateTree, GlobalStateTree};
+use crate::state::PendingData;
+use crate::storage::{
+    ContractCodeTable, ContractsStateTable, RefsTable, StarknetBlocksBlockId, StarknetBlocksTable,
+    StarknetStateUpdatesTable, StarknetTransactionsTable, Storage,
+};
+
+/// The data the feeder gateway API is served from.
+#[derive(Clone)]
+pub struct Context {
+    storage: Storage,
+    chain: Chain,
+    pending_data: PendingData,
+}
+
+impl Context {
+    pub fn new(storage: Storage, chain: Chain, pending_data: PendingData) -> Self {
+        Self {
+            storage,
+            chain,
+            pending_data,
+        }
+    }
+}
+
+/// Spawns a server which hosts the feeder gateway API at `/feeder_gateway/<method>`.
+///
+/// The server stops accepting connections once `shutdown` resolves, and its task ends once the
+/// requests in progress have been served.
+///
+/// Returns the server's task handle and the address it is bound to.
+pub async fn spawn_server(
+    addr: std::net::SocketAddr,
+    context: Context,
+    shutdown: impl std::future::Future<Output = ()> + Send + 'static,
+) -> anyhow::Result<(tokio::task::JoinHandle<()>, std::net::SocketAddr)> {
+    let (local_addr, server) = warp::serve(routes(context))
+        .try_bind_with_graceful_shutdown(addr, shutdown)
+        .context("Binding feeder gateway address")?;
+
+    Ok((tokio::spawn(server), local_addr))
+}
+
+fn routes(
+    context: Context,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+    warp::get()
+        .and(warp::path!("feeder_gateway" / String))
+        .and(warp::query::<HashMap<String, String>>())
+        .and_then(move |method: String, params: HashMap<String, String>| {
+            let context = context.clone();
+            async move { handle(context, &method, &params).await }
+        })
+}
+
+async fn handle(
+    context: Context,
+    method: &str,
+    params: &HashMap<String, String>,
+) -> Result<warp::reply::Response, warp::Rejection> {
+    let result = match method {
+        "get_block" => get_block(context, params).await,
+        "get_state_update" => get_state_update(context, params).await,
+        "get_class_by_hash" => get_class_by_hash(context, params).await,
+        "get_compiled_class_by_class_hash" => {
+            get_compiled_class_by_class_hash(context, params).await
+        }
+        "get_full_contract" => get_full_contract(context, params).await,
+        "get_class_hash_at" => get_class_hash_at(context, params).await,
+        "get_storage_at" => get_storage_at(context, params).await,
+        "get_transaction" => get_transaction(context, params).await,
+        "get_transaction_status" => get_transaction_status(context, params).await,
+        "get_contract_addresses" => get_contract_addresses(context),
+        _ => return Err(warp::reject::not_found()),
+    };
+
+    let response = match result {
+        Ok(body) => {
+            warp::reply::with_header(body, "content-type", "application/json").into_response()
+        }
+        // This is how the sequencer reports errors, and what the sequencer client expects.
+        Err(Error::Starknet(error)) => {
+            warp::reply::with_status(warp::reply::json(&error), StatusCode::INTERNAL_SERVER_ERROR)
+                .into_response()
+        }
+        Err(Error::Internal(error)) => {
+            tracing::error!(%method, reason=?error, "Feeder gateway request failed");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    };
+
+    Ok(response)
+}
+
+pub(crate) enum Error {
+    /// Reported to the client in the sequencer's error format.
+    Starknet(StarknetError),
+    Internal(anyhow::Error),
+}
+
+impl From<anyhow::Error> for Error {
+    fn from(e: anyhow::Error) -> Self {
+        Self::Internal(e)
+    }
+}
+
+impl Error {
+    fn starknet(code: StarknetErrorCode, message: impl Into<String>) -> Self {
+        Self::Starknet(StarknetError {
+            code,
+            message: message.into(),
+        })
+    }
+
+    fn malformed(message: impl Into<String>) -> Self {
+        Self::starknet(StarknetErrorCode::MalformedRequest, message)
+    }
+
+    /// Uses the sequencer's messages, which are relied upon when mapping errors to RPC errors.
+    fn block_not_found(block: BlockId) -> Self {
+        let message = match block {
+            BlockId::Number(number) => format!("Block number {} was not found.", number.get()),
+            BlockId::Hash(hash) => format!("Block hash {} does not exist.", hash.0),
+            BlockId::Latest | BlockId::Pending => "No blocks have been synced yet.".to_owned(),
+        };
+        Self::starknet(StarknetErrorCode::BlockNotFound, message)
+    }
+
+    fn undeclared_class(class_hash: ClassHash) -> Self {
+        Self::starknet(
+            StarknetErrorCode::UndeclaredClass,
+            format!("Class with hash {} is not declared.", class_hash.0),
+        )
+    }
+
+    fn uninitialized_contract(address: ContractAddress) -> Self {
+        Self::starknet(
+            StarknetErrorCode::UninitializedContract,
+            format!(
+                "Requested contract address {} is not deployed.",
+                address.get()
+            ),
+        )
+    }
+}
+
+async fn get_block(context: Context, params: &HashMap<String, String>) -> Result<Vec<u8>, Error> {
+    block(&context, block_id(params)?).await
+}
+
+/// Serializes the block as the sequencer's `get_block` does.
+pub(crate) async fn block(context: &Context, block: BlockId) -> Result<Vec<u8>, Error> {
+    if block == BlockId::Pending {
+        if let Some((pending, _)) = pending_data(context).await? {
+            return to_json(&*pending);
+        }
+    }
+
+    let block = with_db(&context.storage, move |tx| block_from_db(tx, block)).await?;
+
+    to_json(&block)
+}
+
+async fn get_state_update(
+    context: Context,
+    params: &HashMap<String, String>,
+) -> Result<Vec<u8>, Error> {
+    state_update(&context, block_id(params)?).await
+}
+
+/// Serializes the block's state update as the sequencer's `get_state_update` does.
+pub(crate) async fn state_update(context: &Context, block: BlockId) -> Result<Vec<u8>, Error> {
+    if block == BlockId::Pending {
+        if let Some((_, state_update)) = pending_data(context).await? {
+            return to_json(&*state_update);
+        }
+    }
+
+    let state_update = with_db(&context.storage, move |tx| {
+        let block_hash = StarknetBlocksTable::get(tx, storage_block_id(block))
+            .context("Reading block")?
+            .ok_or_else(|| Error::block_not_found(block))?
+            .hash;
+        let state_update = StarknetStateUpdatesTable::get(tx, block_hash)
+            .context("Reading state update")?
+            .context("State update missing from database")?;
+
+        state_update_from_db(tx, block_hash, state_update)
+    })
+    .await?;
+
+    to_json(&state_update)
+}
+
+async fn get_class_by_hash(
+    context: Context,
+    params: &HashMap<String, String>,
+) -> Result<Vec<u8>, Error> {
+    class_definition(&context, ClassHash(hash_param(params, "classHash")?)).await
+}
+
+/// Returns the class definition as it was declared.
+pub(crate) async fn class_definition(
+    context: &Context,
+    class_hash: ClassHash,
+) -> Result<Vec<u8>, Error> {
+    with_db(&context.storage, move |tx| {
+        ContractCodeTable::get_definition(tx, class_hash)
+            .context("Reading class definition")?
+            .ok_or_else(|| Error::undeclared_class(class_hash))
+    })
+    .await
+}
+
+async fn get_compiled_class_by_class_hash(
+    context: Context,
+    params: &HashMap<String, String>,
+) -> Result<Vec<u8>, Error> {
+    compiled_class_definition(&context, ClassHash(hash_param(params, "classHash")?)).await
+}
+
+/// Returns the compiled class definition of a Sierra class.
+pub(crate) async fn compiled_class_definition(
+    context: &Context,
+    class_hash: ClassHash,
+) -> Result<Vec<u8>, Error> {
+    with_db(&context.storage, move |tx| {
+        ContractCodeTable::get_casm_definition(tx, class_hash)
+            .context("Reading compiled class definition")?
+            .ok_or_else(|| Error::undeclared_class(class_hash))
+    })
+    .await
+}
+
+async fn get_full_contract(
+    context: Context,
+    params: &HashMap<String, String>,
+) -> Result<Vec<u8>, Error> {
+    let address = contract_address_param(params)?;
+    let block = block_id(params)?;
+
+    let class_hash = class_hash_at(&context, block, address).await?;
+
+    with_db(&context.storage, move |tx| {
+        ContractCodeTable::get_definition(tx, class_hash)
+            .context("Reading class definition")?
+            .context("Class definition missing from database")
+            .map_err(Into::into)
+    })
+    .await
+}
+
+async fn get_class_hash_at(
+    context: Context,
+    params: &HashMap<String, String>,
+) -> Result<Vec<u8>, Error> {
+    let address = contract_address_param(params)?;
+    let block = block_id(params)?;
+
+    let class_hash = class_hash_at(&context, block, address).await?;
+
+    to_json(&class_hash)
+}
+
+async fn get_storage_at(
+    context: Context,
+    params: &HashMap<String, String>,
+) -> Result<Vec<u8>, Error> {
+    let address = contract_address_param(params)?;
+    let key = params
+        .get("key")
+        .ok_or_else(|| Error::malformed("Missing key"))?;
+    let key = crate::rpc::serde::starkhash_from_dec_str(key)
+        .ok()
+        .and_then(StorageAddress::new)
+        .ok_or_else(|| Error::malformed(format!("Invalid key: {key}")))?;
+    let block = block_id(params)?;
+
+    if block == BlockId::Pending {
+        if let Some((_, state_update)) = pending_data(&context).await? {
+            let pending_value = state_update
+                .state_diff
+                .storage_diffs
+                .get(&address)
+                .and_then(|diffs| {
+                    diffs
+                        .iter()
+                        .find_map(|diff| (diff.key == key).then_some(diff.value))
+                });
+
+            if let Some(value) = pending_value {
+                return to_json(&value);
+            }
+        }
+    }
+
+    let value = with_db(&context.storage, move |tx| {
+        // The sequencer reports zero for contracts which are not deployed.
+        let state_hash = match contract_state_hash(tx, block, address)? {
+            Some(state_hash) => state_hash,
+            None => return Ok(StorageValue(StarkHash::ZERO)),
+        };
+
+        let root = ContractsStateTable::get_root(tx, state_hash)
+            .context("Reading contract state root")?
+            .context("Contract state missing from database")?;
+        let tree = ContractsStateTree::load(tx, root).context("Loading contract state tree")?;
+        let value = tree
+            .get(key)
+            .context("Reading storage value")?
+            .unwrap_or(StorageValue(StarkHash::ZERO));
+
+        Ok(value)
+    })
+    .await?;
+
+    to_json(&value)
+}
+
+async fn get_transaction(
+    context: Context,
+    params: &HashMap<String, String>,
+) -> Result<Vec<u8>, Error> {
+    let hash = StarknetTransactionHash(hash_param(params, "transactionHash")?);
+
+    let transaction = transaction(&context, hash).await?;
+
+    to_json(&transaction)
+}
+
+async fn get_transaction_status(
+    context: Context,
+    params: &HashMap<String, String>,
+) -> Result<Vec<u8>, Error> {
+    let hash = StarknetTransactionHash(hash_param(params, "transactionHash")?);
+
+    let transaction = transaction(&context, hash).await?;
+
+    to_json(&reply::TransactionStatus {
+        block_hash: transaction.block_hash,
+        tx_status: transaction.status,
+    })
+}
+
+fn get_contract_addresses(context: Context) -> Result<Vec<u8>, Error> {
+    let addresses = crate::ethereum::contract::addresses(context.chain);
+
+    to_json(&reply::EthContractAddresses {
+        starknet: EthereumAddress(addresses.core),
+        gps_statement_verifier: EthereumAddress(addresses.gps),
+    })
+}
+
+/// Parses the block from the `blockNumber` or `blockHash` parameters.
+fn block_id(params: &HashMap<String, String>) -> Result<BlockId, Error> {
+    match (params.get("blockNumber"), params.get("blockHash")) {
+        (Some(_), Some(_)) => Err(Error::malformed(
+            "Only one of blockNumber and blockHash may be specified",
+        )),
+        (Some(number), None) => match number.as_str() {
+            "latest" => Ok(BlockId::Latest),
+            "pending" => Ok(BlockId::Pending),
+            number => number
+                .parse::<u64>()
+                .ok()
+                .and_then(StarknetBlockNumber::new)
+                .map(BlockId::Number)
+                .ok_or_else(|| Error::malformed(format!("Invalid blockNumber: {number}"))),
+        },
+        (None, Some(_)) => Ok(BlockId::Hash(StarknetBlockHash(hash_param(
+            params,
+            "blockHash",
+        )?))),
+        // Same default as the sequencer.
+        (None, None) => Ok(BlockId::Pending),
+    }
+}
+
+fn hash_param(params: &HashMap<String, String>, name: &str) -> Result<StarkHash, Error> {
+    let value = params
+        .get(name)
+        .ok_or_else(|| Error::malformed(format!("Missing {name}")))?;
+
+    StarkHash::from_hex_str(value).map_err(|_| Error::malformed(format!("Invalid {name}: {value}")))
+}
+
+fn contract_address_param(params: &HashMap<String, String>) -> Result<ContractAddress, Error> {
+    let address = hash_param(params, "contractAddress")?;
+
+    ContractAddress::new(address).ok_or_else(|| {
+        Error::starknet(
+            StarknetErrorCode::OutOfRangeContractAddress,
+            format!("Contract address {} is out of range", address),
+        )
+    })
+}
+
+/// Pending blocks are served from [PendingData], everything else from the database's latest block.
+fn storage_block_id(block: BlockId) -> StarknetBlocksBlockId {
+    match block {
+        BlockId::Number(number) => number.into(),
+        BlockId::Hash(hash) => hash.into(),
+        BlockId::Latest | BlockId::Pending => StarknetBlocksBlockId::Latest,
+    }
+}
+
+fn to_json<T: serde::Serialize>(value: &T) -> Result<Vec<u8>, Error> {
+    Ok(serde_json::to_vec(value).context("Serializing reply")?)
+}
+
+/// Runs `f` with a database transaction on a blocking task.
+async fn with_db<T, F>(storage: &Storage, f: F) -> Result<T, Error>
+where
+    T: Send + 'static,
+    F: FnOnce(&Transaction<'_>) -> Result<T, Error> + Send + 'static,
+{
+    let storage = storage.clone();
+    let span = tracing::Span::current();
+
+    tokio::task::spawn_blocking(move || {
+        let _g = span.enter();
+        let mut db = storage
+            .connection()
+            .context("Opening database connection")?;
+        let tx = db.transaction().context("Creating database transaction")?;
+
+        f(&tx)
+    })
+    .await
+    .context("Database read panic or shutting down")?
+}
+
+/// Returns the pending block and state update, if they build on top of our latest block.
+async fn pending_data(
+    context: &Context,
+) -> Result<Option<(Arc<reply::PendingBlock>, Arc<reply::StateUpdate>)>, Error> {
+    let (block, state_update) = match (
+        context.pending_data.block().await,
+        context.pending_data.state_update().await,
+    ) {
+        (Some(block), Some(state_update)) => (block, state_update),
+        _ => return Ok(None),
+    };
+
+    let latest = with_db(&context.storage, |tx| {
+        Ok(StarknetBlocksTable::get_latest_hash_and_number(tx)?)
+    })
+    .await?;
+
+    Ok(latest.and_then(|(hash, _)| (hash == block.parent_hash).then_some((block, state_update))))
+}
+
+/// Returns the [ClassHash] of the contract deployed at `address`, preferring the pending
+/// state update for [BlockId::Pending].
+async fn class_hash_at(
+    context: &Context,
+    block: BlockId,
+    address: ContractAddress,
+) -> Result<ClassHash, Error> {
+    if block == BlockId::Pending {
+        if let Some((_, state_update)) = pending_data(context).await? {
+            let pending_class = state_update
+                .state_diff
+                .deployed_contracts
+                .iter()
+                .find_map(|contract| (contract.address == address).then_some(contract.class_hash));
+
+            if let Some(class_hash) = pending_class {
+                return Ok(class_hash);
+            }
+        }
+    }
+
+    with_db(&context.storage, move |tx| {
+        let state_hash = contract_state_hash(tx, block, address)?
+            .ok_or_else(|| Error::uninitialized_contract(address))?;
+
+        ContractsStateTable::get_class_hash(tx, state_hash)
+            .context("Reading class hash")?
+            .context("Contract state missing from database")
+            .map_err(Into::into)
+    })
+    .await
+}
+
+/// Looks the transaction up in the pending block first, then in the database.
+///
+/// Unknown transactions are reported as [reply::Status::NotReceived], like the sequencer does.
+async fn transaction(
+    context: &Context,
+    hash: StarknetTransactionHash,
+) -> Result<reply::Transaction, Error> {
+    if let Some((block, _)) = pending_data(context).await? {
+        let pending = block
+            .transactions
+            .iter()
+            .zip(&block.transaction_receipts)
+            .find(|(transaction, _)| transaction.hash() == hash);
+
+        if let Some((transaction, receipt)) = pending {
+            return Ok(reply::Transaction {
+                block_hash: None,
+                block_number: None,
+                status: reply::Status::Pending,
+                transaction: Some(transaction.clone()),
+                transaction_index: Some(receipt.transaction_index.get()),
+            });
+        }
+    }
+
+    with_db(&context.storage, move |tx| {
+        let (transaction, receipt, block_hash) =
+            match StarknetTransactionsTable::get_transaction_with_receipt(tx, hash)
+                .context("Reading transaction")?
+            {
+                Some(found) => found,
+                None => {
+                    return Ok(reply::Transaction {
+                        block_hash: None,
+                        block_number: None,
+                        status: reply::Status::NotReceived,
+                        transaction: None,
+                        transaction_index: None,
+                    })
+                }
+            };
+
+        let block_number = StarknetBlocksTable::get_number(tx, block_hash)
+            .context("Reading block number")?
+            .context("Transaction's block missing from database")?;
+
+        Ok(reply::Transaction {
+            block_hash: Some(block_hash),
+            block_number: Some(block_number),
+            status: block_status(tx, block_number)?,
+            transaction: Some(transaction),
+            transaction_index: Some(receipt.transaction_index.get()),
+        })
+    })
+    .await
+}
+
+/// Reads the contract's state hash from the global state tree of the given block.
+fn contract_state_hash(
+    tx: &Transaction<'_>,
+    block: BlockId,
+    address: ContractAddress,
+) -> Result<Option<ContractStateHash>, Error> {
+    let root = StarknetBlocksTable::get_root(tx, storage_block_id(block))
+        .context("Reading global root")?
+        .ok_or_else(|| Error::block_not_found(block))?;
+    let tree = GlobalStateTree::load(tx, root).context("Loading global state tree")?;
+
+    Ok(tree.get(address).context("Reading contract state hash")?)
+}
+
+/// All stored blocks are accepted on L2, and on L1 up to the L1-L2 head.
+fn block_status(tx: &Transaction<'_>, number: StarknetBlockNumber) -> Result<reply::Status, Error> {
+    let l1_l2_head = RefsTable::get_l1_l2_head(tx).context("Reading L1-L2 head")?;
+
+    Ok(match l1_l2_head {
+        Some(head) if head >= number => reply::Status::AcceptedOnL1,
+        _ => reply::Status::AcceptedOnL2,
+    })
+}
+
+fn block_from_db(tx: &Transaction<'_>, block_id: BlockId) -> Result<reply::Block, Error> {
+    let block = StarknetBlocksTable::get(tx, storage_block_id(block_id))
+        .context("Reading block")?
+        .ok_or_else(|| Error::block_not_found(block_id))?;
+
+    let parent_block_hash = if block.number == StarknetBlockNumber::GENESIS {
+        StarknetBlockHash(StarkHash::ZERO)
+    } else {
+        StarknetBlocksTable::get_hash(tx, (block.number - 1).into())
+            .context("Reading parent block hash")?
+            .context("Parent block missing from database")?
+    };
+
+    let (transactions, transaction_receipts) =
+        StarknetTransactionsTable::get_transaction_data_for_block(tx, block.hash.into())
+            .context("Reading transactions")?
+            .into_iter()
+            .unzip();
+
+    let starknet_version = StarknetBlocksTable::get_starknet_version(tx, block.number)
+        .context("Reading starknet version")?;
+    let commitments =
+        StarknetBlocksTable::get_commitments(tx, block.number).context("Reading commitments")?;
+
+    Ok(reply::Block {
+        block_hash: block.hash,
+        block_number: block.number,
+        // Blocks without a gas price or sequencer address are stored with zeros instead.
+        gas_price: (block.gas_price != GasPrice::ZERO).then_some(block.gas_price),
+        parent_block_hash,
+        sequencer_address: (block.sequencer_address != SequencerAddress(StarkHash::ZERO))
+            .then_some(block.sequencer_address),
+        state_root: block.root,
+        status: block_status(tx, block.number)?,
+        timestamp: block.timestamp,
+        transaction_receipts,
+        transactions,
+        starknet_version,
+        transaction_commitment: commitments.map(|(transaction, _)| transaction),
+        event_commitment: commitments.map(|(_, event)| event),
+    })
+}
+
+/// Converts the state update format we store back into the sequencer's.
+fn state_update_from_db(
+    tx: &Transaction<'_>,
+    block_hash: StarknetBlockHash,
+    state_update: crate::rpc::v01::types::reply::StateUpdate,
+) -> Result<reply::StateUpdate, Error> {
+    use reply::state_update::{DeclaredSierraClass, DeployedContract, StateDiff, StorageDiff};
+
+    let diff = state_update.state_diff;
+
+    let mut storage_diffs: HashMap<ContractAddress, Vec<StorageDiff>> = HashMap::new();
+    for storage_diff in diff.storage_diffs {
+        storage_diffs
+            .entry(storage_diff.address)
+            .or_default()
+            .push(StorageDiff {
+                key: storage_diff.key,
+                value: storage_diff.value,
+            });
+    }
+
+    // Cairo 0.x and Sierra classes are stored together, only the latter have a compiled class.
+    let mut declared_contracts = Vec::new();
+    let mut declared_classes = Vec::new();
+    for class in diff.declared_contracts {
+        match ContractCodeTable::get_compiled_class_hash(tx, class.class_hash)
+            .context("Reading compiled class hash")?
+        {
+            Some(compiled_class_hash) => declared_classes.push(DeclaredSierraClass {
+                class_hash: class.class_hash,
+                compiled_class_hash,
+            }),
+            None => declared_contracts.push(class.class_hash),
+        }
+    }
+
+    let deployed_contracts = diff
+        .deployed_contracts
+        .into_iter()
+        .map(|contract| DeployedContract {
+            address: contract.address,
+            class_hash: contract.class_hash,
+        })
+        .collect::<Vec<_>>();
+
+    let touched = storage_diffs
+        .keys()
+        .copied()
+        .chain(deployed_contracts.iter().map(|contract| contract.address));
+    let nonces = nonce_updates(
+        tx,
+        block_hash,
+        state_update.old_root,
+        state_update.new_root,
+        touched,
+    )?;
+
+    Ok(reply::StateUpdate {
+        block_hash: state_update.block_hash,
+        new_root: state_update.new_root,
+        old_root: state_update.old_root,
+        state_diff: StateDiff {
+            storage_diffs,
+            deployed_contracts,
+            declared_contracts,
+            declared_classes,
+            nonces,
+        },
+    })
+}
+
+/// Reads the nonces a block updated by comparing the global state before and after it.
+///
+/// Stored state updates do not include nonces. Only the senders of account transactions have
+/// their nonce updated, which are checked along with the `touched` contracts.
+fn nonce_updates(
+    tx: &Transaction<'_>,
+    block_hash: StarknetBlockHash,
+    old_root: GlobalRoot,
+    new_root: GlobalRoot,
+    touched: impl Iterator<Item = ContractAddress>,
+) -> Result<HashMap<ContractAddress, ContractNonce>, Error> {
+    use reply::transaction::{InvokeTransaction, Transaction as T};
+
+    let senders = StarknetTransactionsTable::get_transaction_data_for_block(tx, block_hash.into())
+        .context("Reading transactions")?
+        .into_iter()
+        .filter_map(|(transaction, _)| match transaction {
+            T::Declare(declare) => Some(declare.sender_address),
+            T::DeployAccount(deploy) => Some(deploy.contract_address),
+            T::Invoke(InvokeTransaction::V1(invoke)) => Some(invoke.sender_address),
+            T::Deploy(_) | T::Invoke(InvokeTransaction::V0(_)) | T::L1Handler(_) => None,
+        });
+    let candidates = senders.chain(touched).collect::<HashSet<_>>();
+
+    let old_tree = GlobalStateTree::load(tx, old_root).context("Loading global state tree")?;
+    let new_tree = GlobalStateTree::load(tx, new_root).context("Loading global state tree")?;
+    // Contracts which are not deployed have a zero nonce.
+    let nonce =
+        |tree: &GlobalStateTree<_>, address: ContractAddress| -> anyhow::Result<ContractNonce> {
+            let state_hash = match tree.get(address).context("Reading contract state hash")? {
+                Some(state_hash) => state_hash,
+                None => return Ok(ContractNonce::ZERO),
+            };
+            ContractsStateTable::get_nonce(tx, state_hash)
+                .context("Reading nonce")?
+                .context("Contract state missing from database")
+        };
+
+    let mut nonces = HashMap::new();
+    for address in candidates {
+        let new_nonce = nonce(&new_tree, address)?;
+        if new_nonce != nonce(&old_tree, address)? {
+            nonces.insert(address, new_nonce);
+        }
+    }
+
+    Ok(nonces)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::CasmHash;
+    use crate::rpc::tests::{create_pending_data, setup_storage};
+    use crate::rpc::v01::types::{BlockHashOrTag, Tag};
+    use crate::sequencer::error::SequencerError;
+    use crate::sequencer::{self, ClientApi};
+    use crate::{starkhash, starkhash_bytes};
+    use assert_matches::assert_matches;
+
+    /// Serves the [setup_storage] fixture, with a state update for the latest block and
+    /// pending data on top of it, and returns a sequencer client pointed at it.
+    async fn setup() -> (tokio::task::JoinHandle<()>, sequencer::Client) {
+        let storage = setup_storage();
+
+        let db = storage.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut connection = db.connection().unwrap();
+            let tx = connection.transaction().unwrap();
+
+            let compress = |data: &[u8]| zstd::bulk::compress(data, 10).unwrap();
+            let sierra = crate::state::CompressedContract {
+                abi: compress(b"abi"),
+                bytecode: compress(b"sierra program"),
+                definition: compress(b"sierra definition"),
+                hash: ClassHash(starkhash_bytes!(b"sierra class")),
+                casm: Some(crate::state::CompressedCasmClass {
+                    definition: compress(b"casm definition"),
+                    hash: CasmHash(starkhash_bytes!(b"casm hash")),
+                }),
+            };
+            ContractCodeTable::insert_compressed(&tx, &sierra).unwrap();
+
+            let root = |number| {
+                StarknetBlocksTable::get_root(&tx, StarknetBlockNumber::new_or_panic(number).into())
+                    .unwrap()
+                    .unwrap()
+            };
+            StarknetStateUpdatesTable::insert(
+                &tx,
+                StarknetBlockHash(starkhash_bytes!(b"latest")),
+                &stored_state_update(root(1), root(2)),
+            )
+            .unwrap();
+
+            tx.commit().unwrap();
+        })
+        .await
+        .unwrap();
+
+        let pending_data = create_pending_data(storage.clone()).await;
+        let context = Context::new(storage, Chain::Testnet, pending_data);
+        let (handle, addr) =
+            spawn_server(([127, 0, 0, 1], 0).into(), context, std::future::pending())
+                .await
+                .unwrap();
+
+        let url = format!("http://{addr}/").parse().unwrap();
+        (handle, sequencer::Client::with_url(url).unwrap())
+    }
+
+    /// The state update of the latest block, which sets the nonce of `contract 1`. Like the ones
+    /// sync stores, it has no nonces.
+    fn stored_state_update(
+        old_root: GlobalRoot,
+        new_root: GlobalRoot,
+    ) -> crate::rpc::v01::types::reply::StateUpdate {
+        use crate::rpc::v01::types::reply::state_update::{
+            DeclaredContract, DeployedContract, StateDiff, StorageDiff,
+        };
+
+        crate::rpc::v01::types::reply::StateUpdate {
+            block_hash: Some(StarknetBlockHash(starkhash_bytes!(b"latest"))),
+            new_root,
+            old_root,
+            state_diff: StateDiff {
+                storage_diffs: vec![
+                    StorageDiff {
+                        address: ContractAddress::new_or_panic(starkhash_bytes!(b"contract 1")),
+                        key: StorageAddress::new_or_panic(starkhash!("01")),
+                        value: StorageValue(starkhash!("02")),
+                    },
+                    StorageDiff {
+                        address: ContractAddress::new_or_panic(starkhash_bytes!(b"contract 1")),
+                        key: StorageAddress::new_or_panic(starkhash!("03")),
+                        value: StorageValue(starkhash!("04")),
+                    },
+                ],
+                declared_contracts: vec![
+                    DeclaredContract {
+                        class_hash: ClassHash(starkhash_bytes!(b"class 0 hash")),
+                    },
+                    DeclaredContract {
+                        class_hash: ClassHash(starkhash_bytes!(b"sierra class")),
+                    },
+                ],
+                deployed_contracts: vec![DeployedContract {
+                    address: ContractAddress::new_or_panic(starkhash_bytes!(b"contract 1")),
+                    class_hash: ClassHash(starkhash_bytes!(b"class 1 hash")),
+                }],
+                nonces: vec![],
+            },
+        }
+    }
+
+    #[tokio::test]
+    async fn block() {
+        let (_handle, client) = setup().await;
+
+        let latest = client.block(BlockId::Latest).await.unwrap();
+        let latest = assert_matches!(latest, reply::MaybePendingBlock::Block(block) => block);
+        assert_eq!(
+            latest.block_hash,
+            StarknetBlockHash(starkhash_bytes!(b"latest"))
+        );
+        assert_eq!(
+            latest.parent_block_hash,
+            StarknetBlockHash(starkhash_bytes!(b"block 1"))
+        );
+        assert_eq!(latest.status, reply::Status::AcceptedOnL2);
+        assert_eq!(latest.gas_price, Some(GasPrice::from(2)));
+        assert_eq!(latest.transactions.len(), 3);
+        assert_eq!(latest.transaction_receipts.len(), 3);
+
+        let by_hash = client
+            .block(BlockId::Hash(latest.block_hash))
+            .await
+            .unwrap();
+        assert_eq!(by_hash, reply::MaybePendingBlock::Block(latest));
+
+        let genesis = client
+            .block(BlockId::Number(StarknetBlockNumber::GENESIS))
+            .await
+            .unwrap();
+        let genesis = assert_matches!(genesis, reply::MaybePendingBlock::Block(block) => block);
+        assert_eq!(
+            genesis.parent_block_hash,
+            StarknetBlockHash(StarkHash::ZERO)
+        );
+        assert_eq!(genesis.gas_price, None);
+        assert_eq!(genesis.sequencer_address, None);
+        assert_eq!(genesis.transactions.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn pending_block() {
+        let (_handle, client) = setup().await;
+
+        let pending = client.block(BlockId::Pending).await.unwrap();
+        let pending = assert_matches!(pending, reply::MaybePendingBlock::Pending(block) => block);
+        assert_eq!(
+            pending.parent_hash,
+            StarknetBlockHash(starkhash_bytes!(b"latest"))
+        );
+        assert_eq!(pending.transactions.len(), 2);
+
+        let state_update = client.state_update(BlockId::Pending).await.unwrap();
+        assert_eq!(state_update.block_hash, None);
+        assert_eq!(state_update.state_diff.deployed_contracts.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn block_not_found() {
+        let (_handle, client) = setup().await;
+
+        let error = client
+            .block(BlockId::Number(StarknetBlockNumber::new_or_panic(99)))
+            .await
+            .unwrap_err();
+        assert_matches!(
+            error,
+            SequencerError::StarknetError(e) => assert_eq!(e.code, StarknetErrorCode::BlockNotFound)
+        );
+    }
+
+    #[tokio::test]
+    async fn state_update() {
+        use reply::state_update::{DeclaredSierraClass, DeployedContract, StorageDiff};
+
+        let (_handle, client) = setup().await;
+
+        let state_update = client.state_update(BlockId::Latest).await.unwrap();
+
+        let contract = ContractAddress::new_or_panic(starkhash_bytes!(b"contract 1"));
+        assert_eq!(
+            state_update.block_hash,
+            Some(StarknetBlockHash(starkhash_bytes!(b"latest")))
+        );
+        assert_eq!(
+            state_update.state_diff.storage_diffs,
+            [(
+                contract,
+                vec![
+                    StorageDiff {
+                        key: StorageAddress::new_or_panic(starkhash!("01")),
+                        value: StorageValue(starkhash!("02")),
+                    },
+                    StorageDiff {
+                        key: StorageAddress::new_or_panic(starkhash!("03")),
+                        value: StorageValue(starkhash!("04")),
+                    },
+                ]
+            )]
+            .into_iter()
+            .collect()
+        );
+        assert_eq!(
+            state_update.state_diff.declared_contracts,
+            vec![ClassHash(starkhash_bytes!(b"class 0 hash"))]
+        );
+        assert_eq!(
+            state_update.state_diff.declared_classes,
+            vec![DeclaredSierraClass {
+                class_hash: ClassHash(starkhash_bytes!(b"sierra class")),
+                compiled_class_hash: CasmHash(starkhash_bytes!(b"casm hash")),
+            }]
+        );
+        assert_eq!(
+            state_update.state_diff.deployed_contracts,
+            vec![DeployedContract {
+                address: contract,
+                class_hash: ClassHash(starkhash_bytes!(b"class 1 hash")),
+            }]
+        );
+        assert_eq!(
+            state_update.state_diff.nonces,
+            [(contract, ContractNonce(starkhash!("10")))]
+                .into_iter()
+                .collect()
+        );
+    }
+
+    #[tokio::test]
+    async fn classes() {
+        let (_handle, client) = setup().await;
+
+        let expected =
+            zstd::decode_all(&include_bytes!("../fixtures/contract_definition.json.zst")[..])
+                .unwrap();
+
+        let class = client
+            .class_by_hash(ClassHash(starkhash_bytes!(b"class 0 hash")))
+            .await
+            .unwrap();
+        assert_eq!(class, expected);
+
+        let contract = ContractAddress::new_or_panic(starkhash_bytes!(b"contract 0"));
+        let class = client.full_contract(contract).await.unwrap();
+        assert_eq!(class, expected);
+
+        let class_hash = client.class_hash_at(contract).await.unwrap();
+        assert_eq!(class_hash, ClassHash(starkhash_bytes!(b"class 0 hash")));
+
+        let casm = client
+            .compiled_class_by_hash(ClassHash(starkhash_bytes!(b"sierra class")))
+            .await
+            .unwrap();
+        assert_eq!(casm, &b"casm definition"[..]);
+
+        let error = client
+            .class_by_hash(ClassHash(starkhash_bytes!(b"unknown")))
+            .await
+            .unwrap_err();
+        assert_matches!(
+            error,
+            SequencerError::StarknetError(e) => assert_eq!(e.code, StarknetErrorCode::UndeclaredClass)
+        );
+
+        let error = client
+            .class_hash_at(ContractAddress::new_or_panic(starkhash_bytes!(b"unknown")))
+            .await
+            .unwrap_err();
+        assert_matches!(
+            error,
+            SequencerError::StarknetError(e) => assert_eq!(e.code, StarknetErrorCode::UninitializedContract)
+        );
+    }
+
+    #[tokio::test]
+    async fn storage() {
+        let (_handle, client) = setup().await;
+
+        let contract = ContractAddress::new_or_panic(starkhash_bytes!(b"contract 1"));
+        let key = StorageAddress::new_or_panic(starkhash_bytes!(b"storage addr 0"));
+
+        let value = client
+            .storage(contract, key, BlockHashOrTag::Tag(Tag::Latest))
+            .await
+            .unwrap();
+        assert_eq!(value, StorageValue(starkhash_bytes!(b"storage value 2")));
+
+        let value = client
+            .storage(
+                contract,
+                key,
+                BlockHashOrTag::Hash(StarknetBlockHash(starkhash_bytes!(b"block 1"))),
+            )
+            .await
+            .unwrap();
+        assert_eq!(value, StorageValue(starkhash_bytes!(b"storage value 1")));
+
+        let unknown = ContractAddress::new_or_panic(starkhash_bytes!(b"unknown"));
+        let value = client
+            .storage(unknown, key, BlockHashOrTag::Tag(Tag::Latest))
+            .await
+            .unwrap();
+        assert_eq!(value, StorageValue(StarkHash::ZERO));
+
+        // Served from the pending state update.
+        let pending_contract =
+            ContractAddress::new_or_panic(starkhash_bytes!(b"pending contract 1 address"));
+        let pending_key = StorageAddress::new_or_panic(starkhash_bytes!(b"pending storage key 0"));
+        let value = client
+            .storage(
+                pending_contract,
+                pending_key,
+                BlockHashOrTag::Tag(Tag::Pending),
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            value,
+            StorageValue(starkhash_bytes!(b"pending storage value 0"))
+        );
+    }
+
+    #[tokio::test]
+    async fn transaction() {
+        let (_handle, client) = setup().await;
+
+        let hash = StarknetTransactionHash(starkhash_bytes!(b"txn 1"));
+        let transaction = client.transaction(hash).await.unwrap();
+        assert_eq!(
+            transaction.block_hash,
+            Some(StarknetBlockHash(starkhash_bytes!(b"block 1")))
+        );
+        assert_eq!(
+            transaction.block_number,
+            Some(StarknetBlockNumber::new_or_panic(1))
+        );
+        assert_eq!(transaction.status, reply::Status::AcceptedOnL2);
+        assert_eq!(transaction.transaction.unwrap().hash(), hash);
+
+        let status = client.transaction_status(hash).await.unwrap();
+        assert_eq!(status.tx_status, reply::Status::AcceptedOnL2);
+        assert_eq!(
+            status.block_hash,
+            Some(StarknetBlockHash(starkhash_bytes!(b"block 1")))
+        );
+
+        let pending = StarknetTransactionHash(starkhash_bytes!(b"pending tx hash 0"));
+        let transaction = client.transaction(pending).await.unwrap();
+        assert_eq!(transaction.status, reply::Status::Pending);
+        assert_eq!(transaction.block_hash, None);
+
+        let unknown = StarknetTransactionHash(starkhash_bytes!(b"unknown"));
+        let transaction = client.transaction(unknown).await.unwrap();
+        assert_eq!(transaction.status, reply::Status::NotReceived);
+        assert_eq!(transaction.transaction, None);
+    }
+
+    #[tokio::test]
+    async fn contract_addresses() {
+        let (_handle, client) = setup().await;
+
+        let addresses = client.eth_contract_addresses().await.unwrap();
+        let expected = crate::ethereum::contract::addresses(Chain::Testnet);
+        assert_eq!(addresses.starknet.0, expected.core);
+        assert_eq!(addresses.gps_statement_verifier.0, expected.gps);
+    }
+
+    #[tokio::test]
+    async fn malformed_request() {
+        let storage = Storage::in_memory().unwrap();
+        let context = Context::new(storage, Chain::Testnet, PendingData::default());
+        let filter = routes(context);
+
+        let response = warp::test::request()
+            .path("/feeder_gateway/get_block?blockNumber=abc")
+            .reply(&filter)
+            .await;
+        assert_eq!(response.status(), http::StatusCode::INTERNAL_SERVER_ERROR);
+        let error = serde_json::from_slice::<StarknetError>(response.body()).unwrap();
+        assert_eq!(error.code, StarknetErrorCode::MalformedRequest);
+
+        let response = warp::test::request()
+            .path("/feeder_gateway/unknown_method")
+            .reply(&filter)
+            .await;
+        assert_eq!(response.status(), http::StatusCode::NOT_FOUND);
+    }
+}
@@ -5,6 +5,7 @@ pub mod config;
 pub mod consts;
 pub mod core;
 pub mod ethereum;
+pub mod feeder_gateway;
 pub mod monitoring;
 pub mod retry;
 pub mod rpc;
@@ -1,14 +1,23 @@
 pub mod metrics;
 
-use std::sync::atomic::AtomicBool;
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
 
+use anyhow::Context;
 use metrics_exporter_prometheus::PrometheusHandle;
+use serde::Serialize;
 use warp::Filter;
 
-/// Spawns a server which hosts a `/health` endpoint.
+use crate::cairo::ext_py;
+use crate::rpc::v01::types::reply::Syncing;
+use crate::state::{PendingData, SyncState};
+use crate::storage::{RefsTable, StarknetBlocksBlockId, StarknetBlocksTable, Storage};
+
+/// Spawns a server which hosts the `/health`, `/ready`, `/status` and `/metrics` endpoints.
 pub async fn spawn_server(
     addr: impl Into<std::net::SocketAddr> + 'static,
-    readiness: std::sync::Arc<AtomicBool>,
+    readiness: Readiness,
     prometheus_handle: PrometheusHandle,
 ) -> tokio::task::JoinHandle<()> {
     let server = warp::serve(routes(readiness, prometheus_handle));
@@ -17,13 +26,263 @@ pub async fn spawn_server(
     tokio::spawn(async move { server.await })
 }
 
+/// Conditions the node has to meet, in addition to having started, to be reported ready.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ReadinessPolicy {
+    /// Not ready while more than this many blocks behind the network, or while the network's
+    /// latest block is not known.
+    pub max_lag: Option<u64>,
+    /// Not ready while the latest block accepted on L1 is older than this, or while there is no
+    /// such block.
+    pub max_l1_age: Option<Duration>,
+}
+
+/// The parts of a started node reported on by `/ready` and `/status`.
+#[derive(Clone)]
+pub struct Node {
+    pub storage: Storage,
+    pub sync_state: Arc<SyncState>,
+    /// [None] unless pending blocks are polled.
+    pub pending_data: Option<PendingData>,
+    pub call_handle: Option<ext_py::Handle>,
+    /// The configured bounds for the number of Python subprocesses.
+    pub python_subprocesses: ext_py::PoolSize,
+}
+
+/// Shared between the node and the monitoring server, tracks whether the node has started.
+#[derive(Clone, Default)]
+pub struct Readiness {
+    policy: ReadinessPolicy,
+    node: Arc<RwLock<Option<Node>>>,
+}
+
+impl Readiness {
+    pub fn new(policy: ReadinessPolicy) -> Self {
+        Self {
+            policy,
+            node: Default::default(),
+        }
+    }
+
+    /// Marks the node as started, after which it is ready as long as the policy is met.
+    pub fn started(&self, node: Node) {
+        *self.node.write().unwrap_or_else(|e| e.into_inner()) = Some(node);
+    }
+
+    /// Marks the node as no longer ready, because it is stopping or one of its tasks failed.
+    pub fn stopped(&self) {
+        *self.node.write().unwrap_or_else(|e| e.into_inner()) = None;
+    }
+
+    fn node(&self) -> Option<Node> {
+        self.node.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    async fn is_ready(&self) -> anyhow::Result<bool> {
+        let node = match self.node() {
+            Some(node) => node,
+            None => return Ok(false),
+        };
+
+        if self.policy == ReadinessPolicy::default() {
+            return Ok(true);
+        }
+
+        let status = Status::of(&node, true).await?;
+        Ok(self.policy.is_met_by(&status))
+    }
+}
+
+impl ReadinessPolicy {
+    fn is_met_by(&self, status: &Status) -> bool {
+        let lag = match self.max_lag {
+            Some(max_lag) => matches!(status.lag, Some(lag) if lag <= max_lag),
+            None => true,
+        };
+        let l1_age = match self.max_l1_age {
+            Some(max_age) => {
+                matches!(&status.l1_head, Some(head) if head.age <= max_age.as_secs())
+            }
+            None => true,
+        };
+
+        lag && l1_age
+    }
+}
+
+/// The body of `/status`.
+#[derive(Debug, Serialize)]
+struct Status {
+    ready: bool,
+    /// The latest block accepted on L1.
+    l1_head: Option<Head>,
+    /// The latest block in the database.
+    l2_head: Option<Head>,
+    /// The latest block of the network, once known.
+    highest_block: Option<u64>,
+    /// Number of blocks the database is behind the network.
+    lag: Option<u64>,
+    pending: PendingStatus,
+    python_subprocesses: Option<PythonStatus>,
+    /// Size of the database file in bytes.
+    database_size: Option<u64>,
+    /// Size of the database's WAL file in bytes, zero if there is none.
+    wal_size: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+struct Head {
+    number: u64,
+    hash: crate::core::StarknetBlockHash,
+    timestamp: u64,
+    /// Seconds since `timestamp`.
+    age: u64,
+}
+
+#[derive(Debug, Serialize)]
+struct PendingStatus {
+    enabled: bool,
+    /// Whether a pending block is currently available.
+    available: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct PythonStatus {
+    running: usize,
+    /// The minimum number of subprocesses.
+    configured: usize,
+    /// The number of subprocesses the pool can scale up to.
+    max: usize,
+}
+
+impl Status {
+    /// Collects the status of `node`, with `ready` set to the given value.
+    async fn of(node: &Node, ready: bool) -> anyhow::Result<Self> {
+        let storage = node.storage.clone();
+        let (l1_head, l2_head, file_sizes) = tokio::task::spawn_blocking(move || {
+            let mut connection = storage
+                .connection()
+                .context("Opening database connection")?;
+            let tx = connection
+                .transaction()
+                .context("Creating database transaction")?;
+
+            let l2_head = StarknetBlocksTable::get(&tx, StarknetBlocksBlockId::Latest)
+                .context("Reading latest block")?;
+            let l1_head = match RefsTable::get_l1_l2_head(&tx).context("Reading L1 head")? {
+                Some(number) => {
+                    StarknetBlocksTable::get(&tx, number.into()).context("Reading L1 head block")?
+                }
+                None => None,
+            };
+
+            Ok::<_, anyhow::Error>((l1_head, l2_head, database_file_sizes(storage.path())))
+        })
+        .await
+        .context("Joining blocking task")??;
+
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let head = |block: crate::storage::StarknetBlock| Head {
+            number: block.number.get(),
+            hash: block.hash,
+            timestamp: block.timestamp.get(),
+            age: now.saturating_sub(block.timestamp.get()),
+        };
+        let l1_head = l1_head.map(head);
+        let l2_head = l2_head.map(head);
+
+        let highest_block = match &*node.sync_state.status.read().await {
+            Syncing::Status(status) => Some(status.highest.number.get()),
+            Syncing::False(_) => None,
+        };
+        let lag = highest_block.map(|highest| match &l2_head {
+            Some(head) => highest.saturating_sub(head.number),
+            None => highest + 1,
+        });
+
+        let pending = match &node.pending_data {
+            Some(pending_data) => PendingStatus {
+                enabled: true,
+                available: pending_data.block().await.is_some(),
+            },
+            None => PendingStatus {
+                enabled: false,
+                available: false,
+            },
+        };
+
+        let python_subprocesses = node.call_handle.as_ref().map(|handle| PythonStatus {
+            running: handle.running_workers(),
+            configured: node.python_subprocesses.min().get(),
+            max: node.python_subprocesses.max().get(),
+        });
+
+        Ok(Self {
+            ready,
+            l1_head,
+            l2_head,
+            highest_block,
+            lag,
+            pending,
+            python_subprocesses,
+            database_size: file_sizes.map(|(database, _)| database),
+            wal_size: file_sizes.map(|(_, wal)| wal),
+        })
+    }
+
+    /// Sets the gauges which are updated when `/metrics` is scraped.
+    fn record_metrics(&self) {
+        if let Some(head) = &self.l1_head {
+            ::metrics::gauge!(METRIC_L1_HEAD, head.number as f64);
+        }
+        if let Some(head) = &self.l2_head {
+            ::metrics::gauge!(METRIC_L2_HEAD, head.number as f64);
+        }
+        if let Some(highest) = self.highest_block {
+            ::metrics::gauge!(METRIC_HIGHEST_BLOCK, highest as f64);
+        }
+        if let (Some(l1_head), Some(l2_head)) = (&self.l1_head, &self.l2_head) {
+            let gap = l2_head.number.saturating_sub(l1_head.number);
+            ::metrics::gauge!(METRIC_L1_L2_GAP, gap as f64);
+        }
+        if let Some(size) = self.database_size {
+            ::metrics::gauge!(METRIC_DATABASE_SIZE, size as f64);
+        }
+        if let Some(size) = self.wal_size {
+            ::metrics::gauge!(METRIC_WAL_SIZE, size as f64);
+        }
+    }
+}
+
+const METRIC_L1_HEAD: &str = "sync_l1_head";
+const METRIC_L2_HEAD: &str = "sync_l2_head";
+const METRIC_HIGHEST_BLOCK: &str = "sync_highest_block";
+const METRIC_L1_L2_GAP: &str = "sync_l1_l2_gap";
+const METRIC_DATABASE_SIZE: &str = "storage_database_size_bytes";
+const METRIC_WAL_SIZE: &str = "storage_wal_size_bytes";
+
+/// Sizes of the database file and its WAL file, which is zero if it does not exist.
+fn database_file_sizes(path: &Path) -> Option<(u64, u64)> {
+    let size = std::fs::metadata(path).ok()?.len();
+
+    let mut wal = path.as_os_str().to_owned();
+    wal.push("-wal");
+    let wal_size = std::fs::metadata(wal).map(|m| m.len()).unwrap_or(0);
+
+    Some((size, wal_size))
+}
+
 fn routes(
-    readiness: std::sync::Arc<AtomicBool>,
+    readiness: Readiness,
     prometheus_handle: PrometheusHandle,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
     health_route()
-        .or(ready_route(readiness))
-        .or(metrics_route(prometheus_handle))
+        .or(ready_route(readiness.clone()))
+        .or(status_route(readiness.clone()))
+        .or(metrics_route(readiness, prometheus_handle))
 }
 
 /// Always returns `Ok(200)` at `/health`.
@@ -31,45 +290,155 @@ fn health_route() -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Re
     warp::get().and(warp::path!("health")).map(warp::reply)
 }
 
-/// Returns `Ok` if `readiness == true`, or `SERVICE_UNAVAILABLE` otherwise.
+/// Returns `Ok` at `/ready` once the node has started and while it meets the [ReadinessPolicy],
+/// or `SERVICE_UNAVAILABLE` otherwise.
 fn ready_route(
-    readiness: std::sync::Arc<AtomicBool>,
+    readiness: Readiness,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
     warp::get()
         .and(warp::path!("ready"))
-        .map(move || -> std::sync::Arc<AtomicBool> { readiness.clone() })
-        .and_then(|readiness: std::sync::Arc<AtomicBool>| async move {
-            match readiness.load(std::sync::atomic::Ordering::Relaxed) {
-                true => Ok::<_, std::convert::Infallible>(warp::http::StatusCode::OK),
-                false => Ok(warp::http::StatusCode::SERVICE_UNAVAILABLE),
+        .map(move || -> Readiness { readiness.clone() })
+        .and_then(|readiness: Readiness| async move {
+            match readiness.is_ready().await {
+                Ok(true) => Ok::<_, std::convert::Infallible>(warp::http::StatusCode::OK),
+                Ok(false) => Ok(warp::http::StatusCode::SERVICE_UNAVAILABLE),
+                Err(err) => {
+                    tracing::warn!(error=?err, "Checking readiness failed");
+                    Ok(warp::http::StatusCode::SERVICE_UNAVAILABLE)
+                }
             }
         })
 }
 
-/// Returns Prometheus merics snapshot at `/metrics`.
+/// Returns the node's [Status] as JSON at `/status`, or `SERVICE_UNAVAILABLE` until the node has
+/// started.
+fn status_route(
+    readiness: Readiness,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+    use warp::Reply;
+
+    warp::get()
+        .and(warp::path!("status"))
+        .map(move || -> Readiness { readiness.clone() })
+        .and_then(|readiness: Readiness| async move {
+            let node = match readiness.node() {
+                Some(node) => node,
+                None => {
+                    return Ok::<_, std::convert::Infallible>(
+                        warp::http::StatusCode::SERVICE_UNAVAILABLE.into_response(),
+                    )
+                }
+            };
+
+            let status = match Status::of(&node, false).await {
+                Ok(mut status) => {
+                    status.ready = readiness.policy.is_met_by(&status);
+                    status
+                }
+                Err(err) => {
+                    tracing::warn!(error=?err, "Collecting status failed");
+                    return Ok(warp::http::StatusCode::INTERNAL_SERVER_ERROR.into_response());
+                }
+            };
+
+            Ok(warp::reply::json(&status).into_response())
+        })
+}
+
+/// Returns Prometheus merics snapshot at `/metrics`, after updating the gauges which reflect the
+/// node's [Status] once it has started.
 fn metrics_route(
+    readiness: Readiness,
     handle: PrometheusHandle,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
     warp::get()
         .and(warp::path!("metrics"))
-        .map(move || -> PrometheusHandle { handle.clone() })
-        .and_then(|handle: PrometheusHandle| async move {
-            Ok::<_, std::convert::Infallible>(warp::http::Response::builder().body(handle.render()))
-        })
+        .map(move || -> (Readiness, PrometheusHandle) { (readiness.clone(), handle.clone()) })
+        .and_then(
+            |(readiness, handle): (Readiness, PrometheusHandle)| async move {
+                if let Some(node) = readiness.node() {
+                    match Status::of(&node, false).await {
+                        Ok(status) => status.record_metrics(),
+                        Err(err) => tracing::warn!(error=?err, "Collecting status failed"),
+                    }
+                }
+
+                Ok::<_, std::convert::Infallible>(
+                    warp::http::Response::builder().body(handle.render()),
+                )
+            },
+        )
 }
 
 #[cfg(test)]
 mod tests {
+    use super::{Node, Readiness, ReadinessPolicy};
+    use crate::core::{
+        GasPrice, GlobalRoot, SequencerAddress, StarknetBlockHash, StarknetBlockNumber,
+        StarknetBlockTimestamp,
+    };
+    use crate::rpc::v01::types::reply::{syncing, Syncing};
+    use crate::state::SyncState;
+    use crate::storage::{RefsTable, StarknetBlock, StarknetBlocksTable, Storage};
     use metrics_exporter_prometheus::PrometheusBuilder;
-    use std::sync::atomic::AtomicBool;
+    use stark_hash::StarkHash;
     use std::sync::Arc;
+    use std::time::Duration;
+
+    /// A started node with blocks `0..count`, of which the last one is accepted on L1 and has
+    /// the current time as its timestamp.
+    fn node(count: u64) -> Node {
+        let storage = Storage::in_memory().unwrap();
+        let mut connection = storage.connection().unwrap();
+        let tx = connection.transaction().unwrap();
+
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        for number in 0..count {
+            let block = StarknetBlock {
+                number: StarknetBlockNumber::new_or_panic(number),
+                hash: StarknetBlockHash(StarkHash::from_u64(number)),
+                root: GlobalRoot(StarkHash::ZERO),
+                timestamp: StarknetBlockTimestamp::new_or_panic(now - (count - number - 1)),
+                gas_price: GasPrice::ZERO,
+                sequencer_address: SequencerAddress(StarkHash::ZERO),
+            };
+            StarknetBlocksTable::insert(&tx, &block, None).unwrap();
+        }
+        if count > 0 {
+            RefsTable::set_l1_l2_head(&tx, Some(StarknetBlockNumber::new_or_panic(count - 1)))
+                .unwrap();
+        }
+        tx.commit().unwrap();
+
+        Node {
+            storage,
+            sync_state: Arc::new(SyncState::default()),
+            pending_data: None,
+            call_handle: None,
+            python_subprocesses: ext_py::PoolSize::fixed(std::num::NonZeroUsize::new(2).unwrap()),
+        }
+    }
+
+    async fn set_highest(sync_state: &SyncState, number: u64) {
+        let block = syncing::NumberedBlock::from((
+            StarknetBlockHash(StarkHash::from_u64(number)),
+            StarknetBlockNumber::new_or_panic(number),
+        ));
+        *sync_state.status.write().await = Syncing::Status(syncing::Status {
+            starting: block,
+            current: block,
+            highest: block,
+        });
+    }
 
     #[tokio::test]
     async fn health() {
         let recorder = PrometheusBuilder::new().build_recorder();
         let handle = recorder.handle();
-        let readiness = Arc::new(AtomicBool::new(false));
-        let filter = super::routes(readiness, handle);
+        let filter = super::routes(Readiness::default(), handle);
         let response = warp::test::request().path("/health").reply(&filter).await;
 
         assert_eq!(response.status(), http::StatusCode::OK);
@@ -79,16 +448,126 @@ mod tests {
     async fn ready() {
         let recorder = PrometheusBuilder::new().build_recorder();
         let handle = recorder.handle();
-        let readiness = Arc::new(AtomicBool::new(false));
+        let readiness = Readiness::default();
+        let filter = super::routes(readiness.clone(), handle);
+        let response = warp::test::request().path("/ready").reply(&filter).await;
+        assert_eq!(response.status(), http::StatusCode::SERVICE_UNAVAILABLE);
+
+        readiness.started(node(0));
+        let response = warp::test::request().path("/ready").reply(&filter).await;
+        assert_eq!(response.status(), http::StatusCode::OK);
+
+        readiness.stopped();
+        let response = warp::test::request().path("/ready").reply(&filter).await;
+        assert_eq!(response.status(), http::StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[tokio::test]
+    async fn ready_max_lag() {
+        let recorder = PrometheusBuilder::new().build_recorder();
+        let handle = recorder.handle();
+        let readiness = Readiness::new(ReadinessPolicy {
+            max_lag: Some(2),
+            max_l1_age: None,
+        });
+        let filter = super::routes(readiness.clone(), handle);
+
+        let node = node(5);
+        let sync_state = node.sync_state.clone();
+        readiness.started(node);
+
+        // The network's latest block is not known yet.
+        let response = warp::test::request().path("/ready").reply(&filter).await;
+        assert_eq!(response.status(), http::StatusCode::SERVICE_UNAVAILABLE);
+
+        set_highest(&sync_state, 6).await;
+        let response = warp::test::request().path("/ready").reply(&filter).await;
+        assert_eq!(response.status(), http::StatusCode::OK);
+
+        set_highest(&sync_state, 7).await;
+        let response = warp::test::request().path("/ready").reply(&filter).await;
+        assert_eq!(response.status(), http::StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[tokio::test]
+    async fn ready_max_l1_age() {
+        let recorder = PrometheusBuilder::new().build_recorder();
+        let handle = recorder.handle();
+        let readiness = Readiness::new(ReadinessPolicy {
+            max_lag: None,
+            max_l1_age: Some(Duration::from_secs(60)),
+        });
         let filter = super::routes(readiness.clone(), handle);
+
+        // Nothing has been accepted on L1 yet.
+        readiness.started(node(0));
         let response = warp::test::request().path("/ready").reply(&filter).await;
         assert_eq!(response.status(), http::StatusCode::SERVICE_UNAVAILABLE);
 
-        readiness.store(true, std::sync::atomic::Ordering::Relaxed);
+        readiness.started(node(3));
         let response = warp::test::request().path("/ready").reply(&filter).await;
         assert_eq!(response.status(), http::StatusCode::OK);
     }
 
+    #[tokio::test]
+    async fn status() {
+        let recorder = PrometheusBuilder::new().build_recorder();
+        let handle = recorder.handle();
+        let readiness = Readiness::new(ReadinessPolicy {
+            max_lag: Some(0),
+            max_l1_age: None,
+        });
+        let filter = super::routes(readiness.clone(), handle);
+
+        let response = warp::test::request().path("/status").reply(&filter).await;
+        assert_eq!(response.status(), http::StatusCode::SERVICE_UNAVAILABLE);
+
+        let node = node(3);
+        set_highest(&node.sync_state, 4).await;
+        readiness.started(node);
+
+        let response = warp::test::request().path("/status").reply(&filter).await;
+        assert_eq!(response.status(), http::StatusCode::OK);
+
+        let status: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(status["ready"], false);
+        assert_eq!(status["l1_head"]["number"], 2);
+        assert_eq!(status["l2_head"]["number"], 2);
+        assert_eq!(status["highest_block"], 4);
+        assert_eq!(status["lag"], 2);
+        assert_eq!(status["pending"]["enabled"], false);
+        assert_eq!(status["python_subprocesses"], serde_json::Value::Null);
+    }
+
+    #[tokio::test]
+    async fn status_metrics() {
+        use super::metrics::test::{FakeRecorder, RecorderGuard};
+        use super::*;
+
+        let recorder = FakeRecorder::for_metrics(&[
+            METRIC_L1_HEAD,
+            METRIC_L2_HEAD,
+            METRIC_HIGHEST_BLOCK,
+            METRIC_L1_L2_GAP,
+        ]);
+        let handle = recorder.handle();
+        let _guard = RecorderGuard::lock(recorder);
+
+        let node = node(3);
+        set_highest(&node.sync_state, 4).await;
+        let mut status = Status::of(&node, true).await.unwrap();
+        status.l1_head.as_mut().unwrap().number = 1;
+        status.record_metrics();
+
+        assert_eq!(handle.get_gauge_value_by_label(METRIC_L1_HEAD, []), 1.0);
+        assert_eq!(handle.get_gauge_value_by_label(METRIC_L2_HEAD, []), 2.0);
+        assert_eq!(
+            handle.get_gauge_value_by_label(METRIC_HIGHEST_BLOCK, []),
+            4.0
+        );
+        assert_eq!(handle.get_gauge_value_by_label(METRIC_L1_L2_GAP, []), 1.0);
+    }
+
     #[tokio::test]
     async fn metrics() {
         use super::metrics::test::RecorderGuard;
@@ -101,8 +580,7 @@ mod tests {
         let counter = metrics::register_counter!("x");
         counter.increment(123);
 
-        let readiness = Arc::new(AtomicBool::new(false));
-        let filter = super::routes(readiness.clone(), handle);
+        let filter = super::routes(Readiness::default(), handle);
         let response = warp::test::request().path("/metrics").reply(&filter).await;
         assert_eq!(response.status(), http::StatusCode::OK);
         assert_eq!(response.body(), "# TYPE x counter\nx 123\n\n");
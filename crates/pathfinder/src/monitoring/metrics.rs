@@ -5,15 +5,22 @@ pub mod middleware {
     pub struct RpcMetricsMiddleware;
 
     impl Middleware for RpcMetricsMiddleware {
-        type Instant = ();
+        type Instant = std::time::Instant;
 
-        fn on_request(&self) -> Self::Instant {}
+        fn on_request(&self) -> Self::Instant {
+            std::time::Instant::now()
+        }
 
         fn on_call(&self, name: &str) {
             metrics::increment_counter!("rpc_method_calls_total", "method" => name.to_owned());
         }
 
-        fn on_result(&self, name: &str, success: bool, _started_at: Self::Instant) {
+        fn on_result(&self, name: &str, success: bool, started_at: Self::Instant) {
+            metrics::histogram!(
+                "rpc_method_call_duration_seconds",
+                started_at.elapsed().as_secs_f64(),
+                "method" => name.to_owned()
+            );
             if !success {
                 metrics::increment_counter!("rpc_method_calls_failed_total", "method" => name.to_owned());
             }
@@ -27,9 +34,11 @@ pub mod middleware {
     }
 
     impl jsonrpsee::core::middleware::Middleware for MaybeRpcMetricsMiddleware {
-        type Instant = ();
+        type Instant = std::time::Instant;
 
-        fn on_request(&self) -> Self::Instant {}
+        fn on_request(&self) -> Self::Instant {
+            std::time::Instant::now()
+        }
 
         fn on_call(&self, name: &str) {
             match self {
@@ -50,11 +59,12 @@ pub mod middleware {
 #[cfg(test)]
 pub mod test {
     use metrics::{
-        Counter, CounterFn, Gauge, Histogram, Key, KeyName, Label, Recorder, SharedString, Unit,
+        Counter, CounterFn, Gauge, GaugeFn, Histogram, HistogramFn, Key, KeyName, Label, Recorder,
+        SharedString, Unit,
     };
     use std::collections::HashMap;
     use std::sync::atomic::{AtomicU64, Ordering};
-    use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+    use std::sync::{Arc, Mutex, RwLock, RwLockReadGuard, RwLockWriteGuard};
 
     // The flag means if the current recorder is a noop one
     static RECORDER_LOCK: RwLock<()> = RwLock::new(());
@@ -135,19 +145,46 @@ pub mod test {
 
     #[derive(Debug)]
     /// Mocks a [recorder](`metrics::Recorder`) only for specified [labels](`metrics::Label`)
-    /// treating the rest of registered metrics as _no-op_
+    /// or metric names, treating the rest of registered metrics as _no-op_
     pub struct FakeRecorder(FakeRecorderHandle);
 
     #[derive(Debug, Clone)]
-    /// Handle to the [`FakeRecorder`], which allows to get the current value of counters.
+    /// Handle to the [`FakeRecorder`], which allows to get the current value of counters, gauges
+    /// and histograms.
     pub struct FakeRecorderHandle {
         counters: Arc<RwLock<HashMap<Key, Arc<FakeCounterFn>>>>,
+        gauges: Arc<RwLock<HashMap<Key, Arc<FakeGaugeFn>>>>,
+        histograms: Arc<RwLock<HashMap<Key, Arc<FakeHistogramFn>>>>,
         methods: &'static [&'static str],
+        names: &'static [&'static str],
     }
 
     #[derive(Debug, Default)]
     struct FakeCounterFn(AtomicU64);
 
+    /// Holds the bits of an `f64`.
+    #[derive(Debug, Default)]
+    struct FakeGaugeFn(AtomicU64);
+
+    /// Holds all the recorded values.
+    #[derive(Debug, Default)]
+    struct FakeHistogramFn(Mutex<Vec<f64>>);
+
+    /// Returns the metric registered for `key`, registering it first if needed.
+    fn get_or_register<T: Default>(metrics: &RwLock<HashMap<Key, Arc<T>>>, key: &Key) -> Arc<T> {
+        let read_guard = metrics.read().unwrap();
+        if let Some(metric) = read_guard.get(key) {
+            return metric.clone();
+        }
+        drop(read_guard);
+
+        let mut write_guard = metrics.write().unwrap();
+        write_guard
+            .entry(key.clone())
+            .or_insert_with(Arc::default)
+            .clone()
+    }
+
     impl Recorder for FakeRecorder {
         fn describe_counter(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
         fn describe_gauge(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
@@ -168,31 +205,31 @@ pub mod test {
         /// which means just get the exiting counter instance asap.
         fn register_counter(&self, key: &Key) -> Counter {
             if self.is_key_used(key) {
-                // Check if the counter is already registered
-                let read_guard = self.0.counters.read().unwrap();
-                if let Some(counter) = read_guard.get(key) {
-                    // Do nothing, it's already there
-                    return Counter::from_arc(counter.clone());
-                }
-                drop(read_guard);
-                // We could still be having some contention on write >here<, but let's assume most of the time
-                // the `read()` above does its job
-                let mut write_guard = self.0.counters.write().unwrap();
-                // Put it there
-                // let counter = write_guard.entry(key.clone()).or_default();
-                let counter = write_guard.entry(key.clone()).or_insert_with(Arc::default);
-                Counter::from_arc(counter.clone())
+                // We could still be having some contention on write when registering, but let's
+                // assume most of the time the counter is already there
+                Counter::from_arc(get_or_register(&self.0.counters, key))
             } else {
                 // We don't care
                 Counter::noop()
             }
         }
 
-        fn register_gauge(&self, _: &Key) -> Gauge {
-            unimplemented!()
+        /// Registers a gauge under the same conditions as [`Self::register_counter`].
+        fn register_gauge(&self, key: &Key) -> Gauge {
+            if self.is_key_used(key) {
+                Gauge::from_arc(get_or_register(&self.0.gauges, key))
+            } else {
+                Gauge::noop()
+            }
         }
-        fn register_histogram(&self, _: &Key) -> Histogram {
-            unimplemented!()
+
+        /// Registers a histogram under the same conditions as [`Self::register_counter`].
+        fn register_histogram(&self, key: &Key) -> Histogram {
+            if self.is_key_used(key) {
+                Histogram::from_arc(get_or_register(&self.0.histograms, key))
+            } else {
+                Histogram::noop()
+            }
         }
     }
 
@@ -203,7 +240,22 @@ pub mod test {
         pub fn new(methods: &'static [&'static str]) -> Self {
             Self(FakeRecorderHandle {
                 counters: Arc::default(),
+                gauges: Arc::default(),
+                histograms: Arc::default(),
                 methods,
+                names: &[],
+            })
+        }
+
+        /// Creates a [`FakeRecorder`] which only holds the values of the metrics called `names`,
+        /// regardless of their labels.
+        pub fn for_metrics(names: &'static [&'static str]) -> Self {
+            Self(FakeRecorderHandle {
+                counters: Arc::default(),
+                gauges: Arc::default(),
+                histograms: Arc::default(),
+                methods: &[],
+                names,
             })
         }
 
@@ -213,10 +265,11 @@ pub mod test {
         }
 
         fn is_key_used(&self, key: &Key) -> bool {
-            key.labels().into_iter().any(|label| {
-                label.key() == "method"
-                    && self.0.methods.iter().any(|&method| method == label.value())
-            })
+            self.0.names.iter().any(|&name| name == key.name())
+                || key.labels().into_iter().any(|label| {
+                    label.key() == "method"
+                        && self.0.methods.iter().any(|&method| method == label.value())
+                })
         }
     }
 
@@ -267,6 +320,52 @@ pub mod test {
         }
     }
 
+    impl FakeRecorderHandle {
+        /// Panics if `gauge_name` with `labels` was not registered via [`metrics::register_gauge`]
+        pub fn get_gauge_value_by_label<const N: usize>(
+            &self,
+            gauge_name: &'static str,
+            labels: [(&'static str, &'static str); N],
+        ) -> f64 {
+            let read_guard = self.gauges.read().unwrap();
+            let bits = read_guard
+                .get(&key(gauge_name, labels))
+                .unwrap()
+                .0
+                .load(Ordering::Relaxed);
+            f64::from_bits(bits)
+        }
+
+        /// Returns all values recorded so far, in order.
+        ///
+        /// Panics if `histogram_name` with `labels` was not registered via
+        /// [`metrics::register_histogram`]
+        pub fn get_histogram_values_by_label<const N: usize>(
+            &self,
+            histogram_name: &'static str,
+            labels: [(&'static str, &'static str); N],
+        ) -> Vec<f64> {
+            let read_guard = self.histograms.read().unwrap();
+            read_guard
+                .get(&key(histogram_name, labels))
+                .unwrap()
+                .0
+                .lock()
+                .unwrap()
+                .clone()
+        }
+    }
+
+    fn key<const N: usize>(name: &'static str, labels: [(&'static str, &'static str); N]) -> Key {
+        Key::from_parts(
+            name,
+            labels
+                .iter()
+                .map(|&(key, val)| Label::new(key, val))
+                .collect::<Vec<_>>(),
+        )
+    }
+
     impl CounterFn for FakeCounterFn {
         fn increment(&self, val: u64) {
             self.0.fetch_add(val, Ordering::Relaxed);
@@ -275,4 +374,32 @@ pub mod test {
             unimplemented!()
         }
     }
+
+    impl GaugeFn for FakeGaugeFn {
+        fn increment(&self, value: f64) {
+            self.update(|current| current + value)
+        }
+        fn decrement(&self, value: f64) {
+            self.update(|current| current - value)
+        }
+        fn set(&self, value: f64) {
+            self.0.store(value.to_bits(), Ordering::Relaxed);
+        }
+    }
+
+    impl FakeGaugeFn {
+        fn update(&self, f: impl Fn(f64) -> f64) {
+            let _ = self
+                .0
+                .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |bits| {
+                    Some(f(f64::from_bits(bits)).to_bits())
+                });
+        }
+    }
+
+    impl HistogramFn for FakeHistogramFn {
+        fn record(&self, value: f64) {
+            self.0.lock().unwrap().push(value);
+        }
+    }
 }
@@ -1,6 +1,11 @@
 //! StarkNet node JSON-RPC related modules.
+pub mod access;
+pub mod cache;
+mod dispatch;
 mod error;
 pub mod gas_price;
+pub mod gateway;
+pub mod ipc;
 mod pathfinder;
 pub mod serde;
 #[cfg(test)]
@@ -11,18 +16,23 @@ pub mod v01;
 pub mod v02;
 
 use crate::monitoring::metrics::middleware::{MaybeRpcMetricsMiddleware, RpcMetricsMiddleware};
+use anyhow::Context;
+use futures::FutureExt;
 use jsonrpsee::{
     core::server::rpc_module::Methods,
     http_server::{HttpServerBuilder, HttpServerHandle, RpcModule},
 };
 
-use std::{net::SocketAddr, result::Result};
+use std::{future::Future, net::SocketAddr, pin::Pin, result::Result, task::Poll};
 use v01::api::RpcApi;
 
 pub struct RpcServer {
     addr: SocketAddr,
     api: RpcApi,
     middleware: MaybeRpcMetricsMiddleware,
+    access: Option<access::AccessControl>,
+    http: gateway::HttpOptions,
+    ipc: Option<ipc::IpcOptions>,
 }
 
 impl RpcServer {
@@ -31,6 +41,33 @@ impl RpcServer {
             addr,
             api,
             middleware: MaybeRpcMetricsMiddleware::NoOp,
+            access: None,
+            http: Default::default(),
+            ipc: None,
+        }
+    }
+
+    /// Enforces `access` in the [gateway], see [access].
+    pub fn with_access_control(self, access: access::AccessControl) -> Self {
+        Self {
+            access: Some(access),
+            ..self
+        }
+    }
+
+    /// Applies the HTTP `options`, see [gateway].
+    pub fn with_http_options(self, options: gateway::HttpOptions) -> Self {
+        Self {
+            http: options,
+            ..self
+        }
+    }
+
+    /// Also serves the API over IPC, see [ipc].
+    pub fn with_ipc(self, options: ipc::IpcOptions) -> Self {
+        Self {
+            ipc: Some(options),
+            ..self
         }
     }
 
@@ -41,59 +78,128 @@ impl RpcServer {
         }
     }
 
-    /// Starts the HTTP-RPC server.
-    pub async fn run(self) -> Result<(HttpServerHandle, SocketAddr), anyhow::Error> {
+    /// Starts the HTTP-RPC server, and the IPC one if configured.
+    pub async fn run(self) -> Result<(RpcHandle, SocketAddr), anyhow::Error> {
+        // The server must only be reachable through the gateway, which tells callers apart.
         let server = HttpServerBuilder::default()
-            .set_middleware(self.middleware)
-            .build(self.addr)
-            .await
-            .map_err(|e| match e {
-                jsonrpsee::core::Error::Transport(_) => {
-                    use std::error::Error;
-
-                    if let Some(inner) = e.source().and_then(|inner| inner.downcast_ref::<std::io::Error>()) {
-                        if let std::io::ErrorKind::AddrInUse = inner.kind() {
-                            return anyhow::Error::new(e)
-                                .context(format!("RPC address is already in use: {}.
-
-Hint: This usually means you are already running another instance of pathfinder.
-Hint: If this happens when upgrading, make sure to shut down the first one first.
-Hint: If you are looking to run two instances of pathfinder, you must configure them with different http rpc addresses.", self.addr));
-                        }
-                    }
-
-                    anyhow::Error::new(e)
-                }
-                _ => anyhow::Error::new(e),
-            })?;
+            .max_request_body_size(self.http.max_request_body_size)
+            .max_response_body_size(self.http.max_response_body_size)
+            .set_middleware(self.middleware.clone())
+            .build(SocketAddr::from(([127, 0, 0, 1], 0)))
+            .await?;
         let local_addr = server.local_addr()?;
 
-        let context_v02 = (&self.api).into();
+        let context_v02: v02::RpcContext = (&self.api).into();
 
         let mut module_v01 = v01::RpcModuleWrapper::new(RpcModule::new(self.api));
         v01::register_all_methods(&mut module_v01)?;
         let module_v01: Methods = module_v01.into_inner().into();
 
-        let mut module_v02 = RpcModule::new(context_v02);
+        let mut module_v02 = RpcModule::new(context_v02.clone());
         v02::register_all_methods(&mut module_v02)?;
-        let module_v02 = module_v02.into();
+        let module_v02: Methods = module_v02.into();
 
-        let mut pathfinder_module = RpcModule::new(());
+        let mut pathfinder_module = RpcModule::new(context_v02);
         pathfinder::register_all_methods(&mut pathfinder_module)?;
-        let pathfinder_module = pathfinder_module.into();
-
-        Ok(server
-            .start_with_paths([
-                (vec!["/rpc/v0.1"], module_v01),
-                (vec!["/", "/rpc/v0.2"], module_v02),
-                (vec!["/rpc/pathfinder/v0.1"], pathfinder_module),
-            ])
-            .map(|handle| (handle, local_addr))?)
+        let pathfinder_module: Methods = pathfinder_module.into();
+
+        let gate = dispatch::Gate::default();
+
+        let ipc = match &self.ipc {
+            Some(options) => {
+                let mut methods = module_v02.clone();
+                methods.merge(pathfinder_module.clone())?;
+                Some(ipc::spawn(
+                    options,
+                    module_v01.clone(),
+                    methods,
+                    self.middleware.clone(),
+                    gate.clone(),
+                    self.http.max_request_body_size,
+                )?)
+            }
+            None => None,
+        };
+
+        let paths = [
+            (vec!["/rpc/v0.1"], module_v01),
+            (vec!["/", "/rpc/v0.2"], module_v02),
+            (vec!["/rpc/pathfinder/v0.1"], pathfinder_module),
+        ];
+
+        let methods = paths
+            .iter()
+            .flat_map(|(served_at, methods)| {
+                served_at.iter().map(move |path| (*path, methods.clone()))
+            })
+            .collect();
+        let local_addr = gateway::spawn(
+            self.addr,
+            local_addr,
+            &self.http,
+            self.access,
+            methods,
+            self.middleware.clone(),
+            gate.clone(),
+        )
+        .await
+        .map_err(|e| {
+            let in_use = e
+                .chain()
+                .filter_map(|e| e.downcast_ref::<std::io::Error>())
+                .any(|e| e.kind() == std::io::ErrorKind::AddrInUse);
+            if in_use {
+                e.context(format!("RPC address is already in use: {}.
+
+Hint: This usually means you are already running another instance of pathfinder.
+Hint: If this happens when upgrading, make sure to shut down the first one first.
+Hint: If you are looking to run two instances of pathfinder, you must configure them with different http rpc addresses.", self.addr))
+            } else {
+                e
+            }
+        })?;
+
+        let http = server.start_with_paths(paths)?;
+
+        Ok((RpcHandle { http, gate, ipc }, local_addr))
+    }
+}
+
+/// Handle of the running RPC servers, which completes if the HTTP-RPC server stops unexpectedly.
+/// Dropping it stops the HTTP-RPC server without waiting for the requests in progress, while the
+/// IPC server lives on.
+pub struct RpcHandle {
+    http: HttpServerHandle,
+    gate: dispatch::Gate,
+    /// Completes once the IPC sockets have been removed.
+    ipc: Option<tokio::task::JoinHandle<()>>,
+}
+
+impl RpcHandle {
+    /// Stops accepting requests and connections, and completes once the requests in progress
+    /// have and the IPC sockets have been removed.
+    pub async fn stop(self) -> anyhow::Result<()> {
+        let http = self.http.stop();
+        self.gate.close().await;
+        if let Some(ipc) = self.ipc {
+            ipc.await.context("Joining the IPC server")?;
+        }
+        http.context("Stopping the HTTP-RPC server")?
+            .await
+            .context("Joining the HTTP-RPC server")
+    }
+}
+
+impl Future for RpcHandle {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut std::task::Context<'_>) -> Poll<Self::Output> {
+        self.http.poll_unpin(cx)
     }
 }
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use crate::{
         core::{
             ClassHash, ContractAddress, ContractAddressSalt, EntryPoint, EventData, EventKey,
@@ -117,7 +223,7 @@ mod tests {
             StarknetBlocksTable, StarknetTransactionsTable, Storage,
         },
     };
-    use jsonrpsee::{http_server::HttpServerHandle, types::ParamsSer};
+    use jsonrpsee::types::ParamsSer;
 
     use stark_hash::StarkHash;
     use std::{
@@ -131,7 +237,7 @@ mod tests {
     pub async fn run_server(
         addr: SocketAddr,
         api: super::v01::api::RpcApi,
-    ) -> Result<(HttpServerHandle, SocketAddr), anyhow::Error> {
+    ) -> Result<(super::RpcHandle, SocketAddr), anyhow::Error> {
         RpcServer::new(addr, api).run().await
     }
 
@@ -191,6 +297,7 @@ mod tests {
             bytecode: zstd_magic,
             definition: contract_definition,
             hash: class0_hash,
+            casm: None,
         };
         let mut contract1_code = contract0_code.clone();
         contract1_code.hash = class1_hash;
@@ -513,6 +620,7 @@ mod tests {
             storage_diffs,
             deployed_contracts,
             declared_contracts: Vec::new(),
+            declared_classes: Vec::new(),
             nonces: std::collections::HashMap::new(),
         };
 
@@ -532,6 +640,7 @@ mod tests {
                     bytecode: zstd_magic.clone(),
                     definition: compressed_definition.to_vec(),
                     hash: deployed.class_hash,
+                    casm: None,
                 };
                 ContractCodeTable::insert_compressed(&tx, &contract).unwrap();
             }
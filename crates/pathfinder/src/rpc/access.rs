@@ -0,0 +1,617 @@
+//! Optional access control in front of the JSON-RPC server.
+//!
+//! The JSON-RPC server cannot inspect request headers or peer addresses, so access control is
+//! enforced by the [gateway](super::gateway) in front of it, which
+//!
+//! - refuses peers outside of the IP allow-list,
+//! - requires one of the configured API keys, either as an `Authorization: Bearer <key>` or as an
+//!   `X-Api-Key: <key>` header,
+//! - checks every method of a request, or of a batch, against the key's allow and deny lists,
+//! - rate limits each peer IP address and each key with a token bucket, where every call of a
+//!   batch costs one token.
+//!
+//! Refused requests are answered with a JSON-RPC error for each of their calls, see [Denial].
+use std::collections::{HashMap, HashSet};
+use std::net::IpAddr;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use anyhow::Context;
+use serde::Deserialize;
+use warp::http::{HeaderMap, StatusCode};
+
+static METRIC_CALLS: &str = "rpc_key_calls_total";
+static METRIC_DENIED: &str = "rpc_access_denied_total";
+
+/// Key label of requests without an API key.
+const ANONYMOUS: &str = "anonymous";
+/// Method label of methods the server does not have.
+const UNKNOWN_METHOD: &str = "unknown";
+/// Number of peers tracked for rate limiting above which refilled buckets are dropped.
+const MAX_TRACKED_IPS: usize = 10_000;
+
+/// The `[rpc-auth]` section of the configuration file.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct AccessConfig {
+    /// Addresses or CIDR ranges which may connect, any address if empty.
+    #[serde(default)]
+    pub allowed_ips: Vec<IpRange>,
+    /// Rate limit of each peer IP address.
+    #[serde(default)]
+    pub ip_rate_limit: Option<RateLimit>,
+    /// API keys of which one is required, unless there are none.
+    #[serde(default)]
+    pub keys: Vec<KeyConfig>,
+}
+
+/// A token bucket refilled with `per-second` tokens per second, holding up to `burst` tokens.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct RateLimit {
+    pub per_second: u32,
+    /// Defaults to `per-second`.
+    #[serde(default)]
+    pub burst: Option<u32>,
+}
+
+impl RateLimit {
+    fn burst(&self) -> f64 {
+        self.burst.unwrap_or(self.per_second) as f64
+    }
+}
+
+/// An API key and what it may do.
+#[derive(Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct KeyConfig {
+    /// Identifies the key in logs and metrics.
+    pub name: String,
+    pub key: String,
+    /// Methods the key may call, all methods if empty. A trailing `*` matches any suffix.
+    #[serde(default)]
+    pub allow: Vec<String>,
+    /// Methods the key may not call, even if allowed. A trailing `*` matches any suffix.
+    #[serde(default)]
+    pub deny: Vec<String>,
+    #[serde(default)]
+    pub rate_limit: Option<RateLimit>,
+}
+
+impl std::fmt::Debug for KeyConfig {
+    // The key itself is a secret, and configurations are logged.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("KeyConfig")
+            .field("name", &self.name)
+            .field("key", &"<redacted>")
+            .field("allow", &self.allow)
+            .field("deny", &self.deny)
+            .field("rate_limit", &self.rate_limit)
+            .finish()
+    }
+}
+
+impl KeyConfig {
+    fn permits(&self, method: &str) -> bool {
+        let matches = |pattern: &String| match pattern.strip_suffix('*') {
+            Some(prefix) => method.starts_with(prefix),
+            None => method == pattern,
+        };
+
+        (self.allow.is_empty() || self.allow.iter().any(matches)) && !self.deny.iter().any(matches)
+    }
+}
+
+/// An IP address, or a range of them in CIDR notation such as `10.0.0.0/8`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct IpRange {
+    addr: IpAddr,
+    prefix: u8,
+}
+
+impl IpRange {
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        match (self.addr, canonical(ip)) {
+            (IpAddr::V4(range), IpAddr::V4(ip)) => {
+                let mask = u32::MAX.checked_shl(32 - self.prefix as u32).unwrap_or(0);
+                u32::from(range) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(range), IpAddr::V6(ip)) => {
+                let mask = u128::MAX.checked_shl(128 - self.prefix as u32).unwrap_or(0);
+                u128::from(range) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Treats IPv4-mapped IPv6 addresses, as seen on dual-stack sockets, as the IPv4 address.
+fn canonical(ip: IpAddr) -> IpAddr {
+    match ip {
+        IpAddr::V6(v6) => match v6.segments() {
+            [0, 0, 0, 0, 0, 0xffff, ..] => IpAddr::V4(v6.to_ipv4().expect("Is IPv4-mapped")),
+            _ => ip,
+        },
+        v4 => v4,
+    }
+}
+
+impl FromStr for IpRange {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (addr, prefix) = match s.split_once('/') {
+            Some((addr, prefix)) => (addr, Some(prefix)),
+            None => (s, None),
+        };
+        let addr = canonical(
+            addr.parse::<IpAddr>()
+                .with_context(|| format!("Invalid IP address {addr}"))?,
+        );
+        let max = if addr.is_ipv4() { 32 } else { 128 };
+        let prefix = match prefix {
+            Some(prefix) => prefix
+                .parse::<u8>()
+                .ok()
+                .filter(|prefix| *prefix <= max)
+                .with_context(|| format!("Invalid prefix length {prefix}"))?,
+            None => max,
+        };
+
+        Ok(Self { addr, prefix })
+    }
+}
+
+impl TryFrom<String> for IpRange {
+    type Error = anyhow::Error;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+/// Why a request was refused.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Denial {
+    /// A key is required, and none or an unknown one was given.
+    Unauthorized,
+    /// The peer is not on the IP allow-list.
+    ForbiddenIp,
+    /// The key may not call this method.
+    ForbiddenMethod(String),
+    /// The peer or the key ran out of tokens.
+    RateLimited,
+}
+
+impl Denial {
+    /// Within the range JSON-RPC reserves for implementation-defined server errors, next to the
+    /// execution timeout error of the methods.
+    pub fn code(&self) -> i32 {
+        match self {
+            Denial::Unauthorized => -32002,
+            Denial::ForbiddenIp | Denial::ForbiddenMethod(_) => -32003,
+            Denial::RateLimited => -32005,
+        }
+    }
+
+    pub fn message(&self) -> String {
+        match self {
+            Denial::Unauthorized => "Unauthorized".to_owned(),
+            Denial::ForbiddenIp => "Forbidden".to_owned(),
+            Denial::ForbiddenMethod(method) => format!("Method not allowed: {method}"),
+            Denial::RateLimited => "Rate limit exceeded".to_owned(),
+        }
+    }
+
+    pub fn status(&self) -> StatusCode {
+        match self {
+            Denial::Unauthorized => StatusCode::UNAUTHORIZED,
+            Denial::ForbiddenIp | Denial::ForbiddenMethod(_) => StatusCode::FORBIDDEN,
+            Denial::RateLimited => StatusCode::TOO_MANY_REQUESTS,
+        }
+    }
+
+    fn reason(&self) -> &'static str {
+        match self {
+            Denial::Unauthorized => "unauthorized",
+            Denial::ForbiddenIp => "forbidden_ip",
+            Denial::ForbiddenMethod(_) => "forbidden_method",
+            Denial::RateLimited => "rate_limited",
+        }
+    }
+}
+
+struct TokenBucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl TokenBucket {
+    fn full(limit: &RateLimit, now: Instant) -> Self {
+        Self {
+            tokens: limit.burst(),
+            updated: now,
+        }
+    }
+
+    fn refill(&mut self, limit: &RateLimit, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * limit.per_second as f64).min(limit.burst());
+        self.updated = now;
+    }
+
+    /// Takes `cost` tokens if there are enough. A cost above the burst size takes all of them
+    /// instead, so that large batches are not refused forever.
+    fn take(&mut self, limit: &RateLimit, cost: f64, now: Instant) -> bool {
+        self.refill(limit, now);
+        let cost = cost.min(limit.burst());
+        if self.tokens >= cost {
+            self.tokens -= cost;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// Decides which requests may reach the JSON-RPC server, shared by the gateway's connections.
+#[derive(Clone)]
+pub struct AccessControl(Arc<Inner>);
+
+struct Inner {
+    config: AccessConfig,
+    ip_buckets: Mutex<HashMap<IpAddr, TokenBucket>>,
+    /// By the index of the key in the configuration.
+    key_buckets: Mutex<HashMap<usize, TokenBucket>>,
+}
+
+impl AccessControl {
+    pub fn new(config: AccessConfig) -> Self {
+        metrics::describe_counter!(
+            METRIC_CALLS,
+            metrics::Unit::Count,
+            "number of calls let through, by API `key` and `method`."
+        );
+        metrics::describe_counter!(
+            METRIC_DENIED,
+            metrics::Unit::Count,
+            "number of requests refused, by API `key` and `reason`."
+        );
+
+        Self(Arc::new(Inner {
+            config,
+            ip_buckets: Default::default(),
+            key_buckets: Default::default(),
+        }))
+    }
+
+    /// Checks a request for `methods` from `ip` with the `credential` taken from its headers,
+    /// returning the name of its key if it has one.
+    pub fn check(
+        &self,
+        ip: IpAddr,
+        credential: Option<&str>,
+        methods: &[&str],
+    ) -> Result<Option<&str>, Denial> {
+        self.check_at(ip, credential, methods, Instant::now())
+    }
+
+    fn check_at(
+        &self,
+        ip: IpAddr,
+        credential: Option<&str>,
+        methods: &[&str],
+        now: Instant,
+    ) -> Result<Option<&str>, Denial> {
+        let config = &self.0.config;
+
+        if !config.allowed_ips.is_empty()
+            && !config.allowed_ips.iter().any(|range| range.contains(ip))
+        {
+            return Err(Denial::ForbiddenIp);
+        }
+
+        let key = match self.key(credential) {
+            Some(key) => Some(key),
+            None if config.keys.is_empty() => None,
+            None => return Err(Denial::Unauthorized),
+        };
+
+        if let Some((_, key)) = key {
+            if let Some(method) = methods.iter().find(|method| !key.permits(method)) {
+                return Err(Denial::ForbiddenMethod(method.to_string()));
+            }
+        }
+
+        let cost = methods.len().max(1) as f64;
+
+        if let Some(limit) = &config.ip_rate_limit {
+            let mut buckets = self.0.ip_buckets.lock().unwrap_or_else(|e| e.into_inner());
+            if buckets.len() >= MAX_TRACKED_IPS {
+                // A full bucket is no different from a new one.
+                buckets.retain(|_, bucket| {
+                    bucket.refill(limit, now);
+                    bucket.tokens < limit.burst()
+                });
+            }
+            let bucket = buckets
+                .entry(canonical(ip))
+                .or_insert_with(|| TokenBucket::full(limit, now));
+            if !bucket.take(limit, cost, now) {
+                return Err(Denial::RateLimited);
+            }
+        }
+
+        if let Some((index, key)) = key {
+            if let Some(limit) = &key.rate_limit {
+                let mut buckets = self.0.key_buckets.lock().unwrap_or_else(|e| e.into_inner());
+                let bucket = buckets
+                    .entry(index)
+                    .or_insert_with(|| TokenBucket::full(limit, now));
+                if !bucket.take(limit, cost, now) {
+                    return Err(Denial::RateLimited);
+                }
+            }
+        }
+
+        Ok(key.map(|(_, key)| key.name.as_str()))
+    }
+
+    /// The index and configuration of the key matching `credential`.
+    fn key(&self, credential: Option<&str>) -> Option<(usize, &KeyConfig)> {
+        let credential = credential?;
+        self.0
+            .config
+            .keys
+            .iter()
+            .enumerate()
+            .find(|(_, key)| key.key == credential)
+    }
+
+    /// Checks a request for `methods` from `ip` and records the outcome in the metrics, where
+    /// methods which are not `known` are counted as `unknown`. Returns the name of the request's
+    /// key, if it has one.
+    pub fn authorize(
+        &self,
+        ip: IpAddr,
+        headers: &HeaderMap,
+        methods: &[&str],
+        known: &HashSet<&'static str>,
+    ) -> Result<Option<&str>, Denial> {
+        let credential = credential(headers);
+
+        let name = match self.check(ip, credential, methods) {
+            Ok(name) => name,
+            Err(denial) => {
+                let key = self
+                    .key(credential)
+                    .map_or(ANONYMOUS, |(_, key)| key.name.as_str());
+                metrics::increment_counter!(METRIC_DENIED, "key" => key.to_owned(), "reason" => denial.reason());
+                tracing::debug!(%ip, ?denial, "Refused RPC request");
+                return Err(denial);
+            }
+        };
+
+        let key = name.unwrap_or(ANONYMOUS);
+        for method in methods {
+            let method = known.get(method).copied().unwrap_or(UNKNOWN_METHOD);
+            metrics::increment_counter!(METRIC_CALLS, "key" => key.to_owned(), "method" => method);
+        }
+
+        Ok(name)
+    }
+}
+
+/// The API key of a request, from either its `Authorization: Bearer` or `X-Api-Key` header.
+fn credential(headers: &HeaderMap) -> Option<&str> {
+    let bearer = headers
+        .get("authorization")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    let api_key = || {
+        headers
+            .get("x-api-key")
+            .and_then(|value| value.to_str().ok())
+    };
+
+    bearer.or_else(api_key).map(str::trim)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    fn key(name: &str) -> KeyConfig {
+        KeyConfig {
+            name: name.to_owned(),
+            key: format!("{name} secret"),
+            allow: vec![],
+            deny: vec![],
+            rate_limit: None,
+        }
+    }
+
+    #[test]
+    fn ip_range() {
+        let range = "10.1.0.0/16".parse::<IpRange>().unwrap();
+        assert!(range.contains(ip("10.1.2.3")));
+        assert!(range.contains(ip("::ffff:10.1.2.3")));
+        assert!(!range.contains(ip("10.2.0.1")));
+        assert!(!range.contains(ip("::1")));
+
+        let single = "::1".parse::<IpRange>().unwrap();
+        assert!(single.contains(ip("::1")));
+        assert!(!single.contains(ip("::2")));
+
+        let any = "0.0.0.0/0".parse::<IpRange>().unwrap();
+        assert!(any.contains(ip("192.168.0.1")));
+
+        assert!("10.0.0.0/33".parse::<IpRange>().is_err());
+        assert!("localhost".parse::<IpRange>().is_err());
+    }
+
+    #[test]
+    fn config_from_toml() {
+        let config: AccessConfig = toml::from_str(
+            r#"allowed-ips = ["127.0.0.1", "10.0.0.0/8"]
+ip-rate-limit = { per-second = 10, burst = 20 }
+
+[[keys]]
+name = "read-only"
+key = "secret"
+deny = ["starknet_add*"]
+rate-limit = { per-second = 5 }"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.allowed_ips.len(), 2);
+        assert_eq!(
+            config.ip_rate_limit,
+            Some(RateLimit {
+                per_second: 10,
+                burst: Some(20)
+            })
+        );
+        assert_eq!(config.keys[0].deny, vec!["starknet_add*".to_owned()]);
+        assert!(!format!("{config:?}").contains("secret"));
+    }
+
+    #[test]
+    fn allowed_ips() {
+        let access = AccessControl::new(AccessConfig {
+            allowed_ips: vec!["127.0.0.1".parse().unwrap()],
+            ..Default::default()
+        });
+
+        assert_eq!(access.check(ip("127.0.0.1"), None, &[]), Ok(None));
+        assert_eq!(
+            access.check(ip("127.0.0.2"), None, &[]),
+            Err(Denial::ForbiddenIp)
+        );
+    }
+
+    #[test]
+    fn keys() {
+        let access = AccessControl::new(AccessConfig {
+            keys: vec![key("a"), key("b")],
+            ..Default::default()
+        });
+        let localhost = ip("127.0.0.1");
+
+        assert_eq!(
+            access.check(localhost, Some("b secret"), &[]),
+            Ok(Some("b"))
+        );
+        assert_eq!(
+            access.check(localhost, None, &[]),
+            Err(Denial::Unauthorized)
+        );
+        assert_eq!(
+            access.check(localhost, Some("c secret"), &[]),
+            Err(Denial::Unauthorized)
+        );
+    }
+
+    #[test]
+    fn methods() {
+        let mut read_only = key("read-only");
+        read_only.deny = vec!["starknet_add*".to_owned()];
+        let mut calls = key("calls");
+        calls.allow = vec!["starknet_call".to_owned(), "starknet_get*".to_owned()];
+        calls.deny = vec!["starknet_getEvents".to_owned()];
+
+        let access = AccessControl::new(AccessConfig {
+            keys: vec![read_only, calls],
+            ..Default::default()
+        });
+        let check = |key, methods: &[&str]| access.check(ip("127.0.0.1"), Some(key), methods);
+
+        assert!(check("read-only secret", &["starknet_call", "starknet_chainId"]).is_ok());
+        assert_eq!(
+            check(
+                "read-only secret",
+                &["starknet_call", "starknet_addInvokeTransaction"]
+            ),
+            Err(Denial::ForbiddenMethod(
+                "starknet_addInvokeTransaction".to_owned()
+            ))
+        );
+
+        assert!(check(
+            "calls secret",
+            &["starknet_call", "starknet_getBlockWithTxs"]
+        )
+        .is_ok());
+        assert!(check("calls secret", &["starknet_chainId"]).is_err());
+        assert!(check("calls secret", &["starknet_getEvents"]).is_err());
+    }
+
+    #[test]
+    fn rate_limits() {
+        let limit = RateLimit {
+            per_second: 1,
+            burst: Some(2),
+        };
+        let mut limited = key("limited");
+        limited.rate_limit = Some(limit);
+
+        let access = AccessControl::new(AccessConfig {
+            ip_rate_limit: Some(RateLimit {
+                per_second: 10,
+                burst: None,
+            }),
+            keys: vec![limited, key("unlimited")],
+            ..Default::default()
+        });
+        let start = Instant::now();
+        let check = |key, ip_: &str, calls: usize, secs: u64| {
+            access.check_at(
+                ip(ip_),
+                Some(key),
+                &vec!["starknet_chainId"; calls],
+                start + Duration::from_secs(secs),
+            )
+        };
+
+        // per key
+        assert!(check("limited secret", "10.0.0.1", 1, 0).is_ok());
+        assert!(check("limited secret", "10.0.0.2", 1, 0).is_ok());
+        assert_eq!(
+            check("limited secret", "10.0.0.3", 1, 0),
+            Err(Denial::RateLimited)
+        );
+        assert!(check("unlimited secret", "10.0.0.3", 1, 0).is_ok());
+        assert!(check("limited secret", "10.0.0.3", 1, 1).is_ok());
+
+        // per IP, with batches costing a token per call
+        assert!(check("unlimited secret", "10.0.0.4", 9, 1).is_ok());
+        assert_eq!(
+            check("unlimited secret", "10.0.0.4", 2, 1),
+            Err(Denial::RateLimited)
+        );
+        assert!(check("unlimited secret", "10.0.0.4", 1, 1).is_ok());
+
+        // batches larger than the burst take all tokens
+        assert!(check("unlimited secret", "10.0.0.5", 100, 1).is_ok());
+        assert!(check("unlimited secret", "10.0.0.5", 1, 1).is_err());
+    }
+
+    #[test]
+    fn credentials() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(credential(&headers), None);
+
+        headers.insert("x-api-key", "key".parse().unwrap());
+        assert_eq!(credential(&headers), Some("key"));
+
+        headers.insert("authorization", "Bearer token".parse().unwrap());
+        assert_eq!(credential(&headers), Some("token"));
+    }
+}
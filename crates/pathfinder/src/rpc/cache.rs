@@ -0,0 +1,398 @@
+//! In-memory cache of RPC responses which can no longer change.
+//!
+//! Responses about a specific historical block, a transaction in one, or a class or a call at
+//! one, only change if the block is reorged away. They are cached by method and normalized
+//! parameters along with the number of that block, so that a reorg can drop the affected ones with
+//! [`ResponseCache::invalidate_from`].
+use std::any::Any;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Debug;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+
+use crate::core::StarknetBlockNumber;
+
+static METRIC_HITS: &str = "rpc_cache_hits_total";
+static METRIC_MISSES: &str = "rpc_cache_misses_total";
+static METRIC_SIZE: &str = "rpc_cache_size_bytes";
+
+/// Least recently used cache of responses, bounded by the size of their JSON.
+///
+/// Cloneable and shareable between the RPC methods and the sync which invalidates it. The
+/// [`Default`] cache is disabled, and caches nothing.
+#[derive(Clone, Default)]
+pub struct ResponseCache(Option<Arc<Mutex<Lru>>>);
+
+/// Cache key of a method and its parameters.
+type Key = (&'static str, String);
+
+struct Lru {
+    entries: HashMap<Key, Entry>,
+    /// Keys by the tick they were last used on, the least recently used first.
+    recency: BTreeMap<u64, Key>,
+    tick: u64,
+    /// Sum of the entry sizes in bytes.
+    size: usize,
+    capacity: usize,
+    /// Incremented on each invalidation, so that responses read before it are not cached after
+    /// it.
+    generation: u64,
+}
+
+struct Entry {
+    response: Arc<dyn Any + Send + Sync>,
+    size: usize,
+    block: StarknetBlockNumber,
+    tick: u64,
+}
+
+impl ResponseCache {
+    /// Creates a cache holding up to `capacity` bytes of responses, or a disabled one if
+    /// `capacity` is zero.
+    pub fn new(capacity: usize) -> Self {
+        if capacity == 0 {
+            return Self::default();
+        }
+
+        metrics::describe_counter!(
+            METRIC_HITS,
+            metrics::Unit::Count,
+            "number of responses served from the cache, by `method`."
+        );
+        metrics::describe_counter!(
+            METRIC_MISSES,
+            metrics::Unit::Count,
+            "number of cacheable responses not found in the cache, by `method`."
+        );
+        metrics::register_gauge!(METRIC_SIZE);
+        metrics::describe_gauge!(
+            METRIC_SIZE,
+            metrics::Unit::Bytes,
+            "approximate size of the cached responses."
+        );
+
+        Self(Some(Arc::new(Mutex::new(Lru {
+            entries: HashMap::new(),
+            recency: BTreeMap::new(),
+            tick: 0,
+            size: 0,
+            capacity,
+            generation: 0,
+        }))))
+    }
+
+    /// Returns the cached response of `method` for `params`, or executes it with `f`.
+    ///
+    /// `f` returns the response along with the block it belongs to, which is [None] if the
+    /// response can still change and may not be cached. Passing [None] as `params` bypasses the
+    /// cache, which is meant for parameters such as `latest` or `pending`. `params` are normalized
+    /// by using the [Debug] representation of the parsed parameters as the key.
+    pub async fn get_or_insert_with<P, T, E, F, Fut>(
+        &self,
+        method: &'static str,
+        params: Option<P>,
+        f: F,
+    ) -> Result<T, E>
+    where
+        P: Debug,
+        T: Clone + serde::Serialize + Send + Sync + 'static,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<(T, Option<StarknetBlockNumber>), E>>,
+    {
+        let (lru, params) = match (&self.0, params) {
+            (Some(lru), Some(params)) => (lru, params),
+            _ => return f().await.map(|(response, _)| response),
+        };
+
+        let key = (method, format!("{params:?}"));
+
+        let generation = {
+            let mut lru = lru.lock().unwrap_or_else(|e| e.into_inner());
+            if let Some(response) = lru.get::<T>(&key) {
+                metrics::increment_counter!(METRIC_HITS, "method" => method);
+                return Ok(response);
+            }
+            lru.generation
+        };
+        metrics::increment_counter!(METRIC_MISSES, "method" => method);
+
+        let (response, block) = f().await?;
+
+        if let Some(block) = block {
+            // Serializing again is the price of bounding the cache by the size of the responses.
+            let size = serde_json::to_vec(&response)
+                .map(|json| json.len() + key.1.len())
+                .unwrap_or(usize::MAX);
+
+            let mut lru = lru.lock().unwrap_or_else(|e| e.into_inner());
+            if lru.generation == generation {
+                lru.insert(key, Arc::new(response.clone()), size, block);
+            }
+        }
+
+        Ok(response)
+    }
+
+    /// Drops the responses of `reorg_tail` and the blocks after it.
+    pub fn invalidate_from(&self, reorg_tail: StarknetBlockNumber) {
+        if let Some(lru) = &self.0 {
+            let mut lru = lru.lock().unwrap_or_else(|e| e.into_inner());
+            lru.retain(|entry| entry.block < reorg_tail);
+        }
+    }
+
+    /// Drops all of the responses.
+    pub fn clear(&self) {
+        if let Some(lru) = &self.0 {
+            let mut lru = lru.lock().unwrap_or_else(|e| e.into_inner());
+            lru.retain(|_| false);
+        }
+    }
+}
+
+impl Lru {
+    fn get<T: Clone + 'static>(&mut self, key: &Key) -> Option<T> {
+        self.tick += 1;
+        let tick = self.tick;
+
+        let entry = self.entries.get_mut(key)?;
+        let response = entry.response.downcast_ref::<T>()?.clone();
+
+        let key = self
+            .recency
+            .remove(&entry.tick)
+            .expect("Entries are tracked by their tick");
+        entry.tick = tick;
+        self.recency.insert(tick, key);
+
+        Some(response)
+    }
+
+    fn insert(
+        &mut self,
+        key: Key,
+        response: Arc<dyn Any + Send + Sync>,
+        size: usize,
+        block: StarknetBlockNumber,
+    ) {
+        if size > self.capacity {
+            return;
+        }
+
+        if let Some(existing) = self.entries.remove(&key) {
+            self.recency.remove(&existing.tick);
+            self.size -= existing.size;
+        }
+
+        while self.size + size > self.capacity {
+            let oldest = match self.recency.keys().next() {
+                Some(&oldest) => oldest,
+                None => break,
+            };
+            let evicted = self.recency.remove(&oldest).expect("Key was just found");
+            if let Some(evicted) = self.entries.remove(&evicted) {
+                self.size -= evicted.size;
+            }
+        }
+
+        self.tick += 1;
+        self.recency.insert(self.tick, key.clone());
+        self.entries.insert(
+            key,
+            Entry {
+                response,
+                size,
+                block,
+                tick: self.tick,
+            },
+        );
+        self.size += size;
+
+        metrics::gauge!(METRIC_SIZE, self.size as f64);
+    }
+
+    fn retain(&mut self, keep: impl Fn(&Entry) -> bool) {
+        let Self {
+            entries,
+            recency,
+            size,
+            ..
+        } = self;
+        entries.retain(|_, entry| {
+            let retained = keep(entry);
+            if !retained {
+                recency.remove(&entry.tick);
+                *size -= entry.size;
+            }
+            retained
+        });
+        self.generation += 1;
+
+        metrics::gauge!(METRIC_SIZE, self.size as f64);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::monitoring::metrics::test::{FakeRecorder, RecorderGuard};
+
+    /// Caches `response` at `block` for the `params`, returning whether `f` was executed.
+    async fn cache(
+        cache: &ResponseCache,
+        params: Option<u64>,
+        response: &str,
+        block: Option<u64>,
+    ) -> (String, bool) {
+        let mut executed = false;
+        let result = cache
+            .get_or_insert_with("method", params, || async {
+                executed = true;
+                Ok::<_, ()>((
+                    response.to_owned(),
+                    block.map(StarknetBlockNumber::new_or_panic),
+                ))
+            })
+            .await
+            .unwrap();
+        (result, executed)
+    }
+
+    #[tokio::test]
+    async fn hit() {
+        let _guard = RecorderGuard::lock_as_noop();
+        let lru = ResponseCache::new(1024);
+
+        assert_eq!(
+            cache(&lru, Some(1), "a", Some(1)).await,
+            ("a".to_owned(), true)
+        );
+        assert_eq!(
+            cache(&lru, Some(1), "b", Some(1)).await,
+            ("a".to_owned(), false)
+        );
+        assert_eq!(
+            cache(&lru, Some(2), "b", Some(1)).await,
+            ("b".to_owned(), true)
+        );
+    }
+
+    #[tokio::test]
+    async fn uncacheable() {
+        let _guard = RecorderGuard::lock_as_noop();
+        let lru = ResponseCache::new(1024);
+
+        // bypassed
+        cache(&lru, None, "a", Some(1)).await;
+        assert_eq!(
+            cache(&lru, None, "b", Some(1)).await,
+            ("b".to_owned(), true)
+        );
+
+        // can still change
+        cache(&lru, Some(1), "a", None).await;
+        assert_eq!(
+            cache(&lru, Some(1), "b", None).await,
+            ("b".to_owned(), true)
+        );
+
+        // disabled
+        let disabled = ResponseCache::new(0);
+        cache(&disabled, Some(1), "a", Some(1)).await;
+        assert_eq!(
+            cache(&disabled, Some(1), "b", Some(1)).await,
+            ("b".to_owned(), true)
+        );
+    }
+
+    #[tokio::test]
+    async fn evicts_least_recently_used() {
+        let _guard = RecorderGuard::lock_as_noop();
+        // "\"a\"" with a single digit key takes 4 bytes, so three fit.
+        let lru = ResponseCache::new(12);
+
+        cache(&lru, Some(1), "a", Some(1)).await;
+        cache(&lru, Some(2), "a", Some(1)).await;
+        cache(&lru, Some(3), "a", Some(1)).await;
+        // 1 is now used more recently than 2
+        cache(&lru, Some(1), "b", Some(1)).await;
+        cache(&lru, Some(4), "a", Some(1)).await;
+
+        assert_eq!(
+            cache(&lru, Some(1), "b", Some(1)).await,
+            ("a".to_owned(), false)
+        );
+        assert_eq!(
+            cache(&lru, Some(2), "b", Some(1)).await,
+            ("b".to_owned(), true)
+        );
+
+        // too large to be cached at all
+        cache(&lru, Some(5), "much too large", Some(1)).await;
+        assert_eq!(
+            cache(&lru, Some(5), "b", Some(1)).await,
+            ("b".to_owned(), true)
+        );
+    }
+
+    #[tokio::test]
+    async fn invalidate_from() {
+        let _guard = RecorderGuard::lock_as_noop();
+        let lru = ResponseCache::new(1024);
+
+        cache(&lru, Some(1), "a", Some(1)).await;
+        cache(&lru, Some(2), "a", Some(2)).await;
+        cache(&lru, Some(3), "a", Some(3)).await;
+
+        lru.invalidate_from(StarknetBlockNumber::new_or_panic(2));
+
+        assert_eq!(
+            cache(&lru, Some(1), "b", Some(1)).await,
+            ("a".to_owned(), false)
+        );
+        assert_eq!(
+            cache(&lru, Some(2), "b", Some(2)).await,
+            ("b".to_owned(), true)
+        );
+        assert_eq!(
+            cache(&lru, Some(3), "b", Some(3)).await,
+            ("b".to_owned(), true)
+        );
+    }
+
+    #[tokio::test]
+    async fn not_cached_across_invalidation() {
+        let _guard = RecorderGuard::lock_as_noop();
+        let lru = ResponseCache::new(1024);
+
+        // the response was read before a reorg, and could be from the reorged block
+        lru.get_or_insert_with("method", Some(1), || async {
+            lru.invalidate_from(StarknetBlockNumber::GENESIS);
+            Ok::<_, ()>(("a".to_owned(), Some(StarknetBlockNumber::GENESIS)))
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(
+            cache(&lru, Some(1), "b", Some(0)).await,
+            ("b".to_owned(), true)
+        );
+    }
+
+    #[tokio::test]
+    async fn metrics() {
+        let recorder = FakeRecorder::new(&["method"]);
+        let handle = recorder.handle();
+        let _guard = RecorderGuard::lock(recorder);
+
+        let lru = ResponseCache::new(1024);
+        cache(&lru, Some(1), "a", Some(1)).await;
+        cache(&lru, Some(1), "a", Some(1)).await;
+        cache(&lru, Some(1), "a", Some(1)).await;
+        // bypassing the cache is neither
+        cache(&lru, None, "a", Some(1)).await;
+
+        assert_eq!(handle.get_counter_value(METRIC_HITS, "method"), 2);
+        assert_eq!(handle.get_counter_value(METRIC_MISSES, "method"), 1);
+    }
+}
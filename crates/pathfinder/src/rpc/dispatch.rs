@@ -0,0 +1,190 @@
+//! Executes JSON-RPC requests in-process, for the [gateway](super::gateway) and [IPC](super::ipc).
+//!
+//! Unlike a request forwarded to the JSON-RPC server, the calls are executed in the task of the
+//! connection, which lets them tell the [python subprocesses](crate::cairo::ext_py) their caller.
+//! They are recorded by the same middleware as the server's, and are stopped by the [Gate] of the
+//! server.
+use std::borrow::Cow;
+use std::sync::Arc;
+
+use jsonrpsee::core::middleware::Middleware;
+use jsonrpsee::core::server::rpc_module::Methods;
+use serde::Deserialize;
+use serde_json::value::RawValue;
+use serde_json::Value;
+use tokio::sync::watch;
+
+use crate::cairo::ext_py::{with_caller, Caller};
+use crate::monitoring::metrics::middleware::MaybeRpcMetricsMiddleware;
+
+/// Stops the in-process calls along with the JSON-RPC server.
+#[derive(Clone)]
+pub(super) struct Gate {
+    closed: Arc<tokio::sync::RwLock<bool>>,
+    closing: Arc<watch::Sender<bool>>,
+    closing_rx: watch::Receiver<bool>,
+}
+
+impl Default for Gate {
+    fn default() -> Self {
+        let (closing, closing_rx) = watch::channel(false);
+        Self {
+            closed: Default::default(),
+            closing: Arc::new(closing),
+            closing_rx,
+        }
+    }
+}
+
+impl Gate {
+    /// Runs `f` unless the gate is closed, returning [None] if it is.
+    pub async fn pass<F: std::future::Future>(&self, f: F) -> Option<F::Output> {
+        let closed = self.closed.read().await;
+        if *closed {
+            return None;
+        }
+        Some(f.await)
+    }
+
+    /// Completes once the gate starts closing, for the servers to stop accepting connections.
+    pub async fn closing(&self) {
+        let mut closing = self.closing_rx.clone();
+        // The sender lives as long as the gate does.
+        while !*closing.borrow() {
+            if closing.changed().await.is_err() {
+                return;
+            }
+        }
+    }
+
+    /// Closes the gate once the futures passing it have completed. Futures reaching it meanwhile
+    /// wait, and are then refused.
+    pub async fn close(&self) {
+        let _ = self.closing.send(true);
+        *self.closed.write().await = true;
+    }
+}
+
+/// Executes a request or batch for `caller`, returning its response. Blank requests are ignored.
+pub(super) async fn handle(
+    methods: &Methods,
+    middleware: &MaybeRpcMetricsMiddleware,
+    caller: &Caller,
+    request: &[u8],
+) -> Option<String> {
+    let request = match std::str::from_utf8(request) {
+        Ok(request) => request.trim(),
+        Err(_) => return Some(error(-32700, "Parse error", None)),
+    };
+    if request.is_empty() {
+        return None;
+    }
+
+    let started_at = middleware.on_request();
+
+    if !request.starts_with('[') {
+        return Some(call(methods, middleware, caller, request, started_at).await);
+    }
+
+    let batch = match serde_json::from_str::<Vec<&RawValue>>(request) {
+        Ok(batch) if batch.is_empty() => return Some(error(-32600, "Invalid request", None)),
+        Ok(batch) => batch,
+        Err(_) => return Some(error(-32700, "Parse error", None)),
+    };
+
+    let mut responses = Vec::with_capacity(batch.len());
+    for request in batch {
+        responses.push(call(methods, middleware, caller, request.get(), started_at).await);
+    }
+
+    Some(format!("[{}]", responses.join(",")))
+}
+
+/// The fields of a call needed to report on it without parsing its parameters.
+#[derive(Deserialize)]
+struct Call<'a> {
+    #[serde(borrow)]
+    method: Option<Cow<'a, str>>,
+    #[serde(borrow)]
+    id: Option<&'a RawValue>,
+}
+
+/// Whether a response is an error, without parsing its result.
+#[derive(Deserialize)]
+struct Outcome {
+    error: Option<serde::de::IgnoredAny>,
+}
+
+async fn call(
+    methods: &Methods,
+    middleware: &MaybeRpcMetricsMiddleware,
+    caller: &Caller,
+    request: &str,
+    started_at: std::time::Instant,
+) -> String {
+    let parsed = match serde_json::from_str::<Call<'_>>(request) {
+        Ok(parsed) => parsed,
+        Err(_) => return error(-32700, "Parse error", None),
+    };
+
+    if let Some(method) = &parsed.method {
+        middleware.on_call(method);
+    }
+
+    let response = match with_caller(caller.clone(), methods.raw_json_request(request)).await {
+        Ok((response, _subscription)) => response,
+        Err(_) => error(-32600, "Invalid request", parsed.id),
+    };
+
+    if let Some(method) = &parsed.method {
+        let success = serde_json::from_str::<Outcome>(&response)
+            .map(|outcome| outcome.error.is_none())
+            .unwrap_or(false);
+        middleware.on_result(method, success, started_at);
+    }
+
+    response
+}
+
+/// A JSON-RPC error response.
+pub(super) fn error(code: i32, message: &str, id: Option<&RawValue>) -> String {
+    serde_json::json!({
+        "jsonrpc": "2.0",
+        "error": { "code": code, "message": message },
+        "id": id.map_or(Value::Null, |id| serde_json::from_str(id.get()).unwrap_or(Value::Null)),
+    })
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn gate_waits_for_passing_futures() {
+        let gate = Gate::default();
+        let (release_tx, release_rx) = tokio::sync::oneshot::channel::<()>();
+
+        let passing = tokio::spawn({
+            let gate = gate.clone();
+            async move { gate.pass(release_rx).await }
+        });
+        // let it enter
+        tokio::time::sleep(Duration::from_millis(10)).await;
+
+        let mut close = tokio::spawn({
+            let gate = gate.clone();
+            async move { gate.close().await }
+        });
+        gate.closing().await;
+        let waited = tokio::time::timeout(Duration::from_millis(10), &mut close).await;
+        assert!(waited.is_err(), "closed while a future was passing");
+
+        release_tx.send(()).unwrap();
+        assert_eq!(passing.await.unwrap(), Some(Ok(())));
+        close.await.unwrap();
+
+        assert_eq!(gate.pass(async { 1 }).await, None);
+    }
+}
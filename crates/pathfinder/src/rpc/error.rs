@@ -33,6 +33,12 @@ pub enum RpcError {
     ContractError,
     #[error("Invalid contract class")]
     InvalidContractClass,
+    /// Pathfinder specific, not part of the specification.
+    #[error("Execution timed out")]
+    ExecutionTimeout,
+    /// Pathfinder specific, not part of the specification.
+    #[error("Too many calls are waiting to be executed")]
+    Overloaded,
     #[error(transparent)]
     Internal(anyhow::Error),
 }
@@ -53,6 +59,9 @@ impl RpcError {
             RpcError::InvalidContinuationToken => 33,
             RpcError::ContractError => 40,
             RpcError::InvalidContractClass => 50,
+            // Within the range JSON-RPC reserves for implementation-defined server errors.
+            RpcError::ExecutionTimeout => -32001,
+            RpcError::Overloaded => -32004,
             RpcError::Internal(_) => jsonrpsee::types::error::ErrorCode::InternalError.code(),
         }
     }
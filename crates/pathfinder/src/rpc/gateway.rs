@@ -0,0 +1,807 @@
+//! HTTP gateway in front of the JSON-RPC server.
+//!
+//! The JSON-RPC server only speaks plain HTTP and cannot inspect request headers or peer
+//! addresses. It is bound to an ephemeral loopback address instead, and the gateway serving the
+//! configured address handles TLS, CORS, batch and connection limits and
+//! [access control](super::access) for the requests it lets through.
+//!
+//! JSON-RPC requests are [dispatched](super::dispatch) in-process, so that the python subprocesses
+//! can tell callers apart: each API key, or each peer address for requests without one, waits for
+//! them in a lane of its own. Any other requests are forwarded to the JSON-RPC server.
+//!
+//! The TLS certificate and key are read again on `SIGHUP`, so that a renewed certificate can be
+//! used without restarting. If they cannot be read, the previous ones are kept.
+use std::collections::{HashMap, HashSet};
+use std::net::{IpAddr, SocketAddr};
+use std::num::NonZeroUsize;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use anyhow::Context;
+use bytes::Buf;
+use futures::{Stream, StreamExt};
+use jsonrpsee::core::server::rpc_module::Methods;
+use serde_json::Value;
+use tokio::net::TcpListener;
+use tokio::sync::Semaphore;
+use tokio_native_tls::TlsAcceptor;
+use warp::filters::BoxedFilter;
+use warp::http::{HeaderMap, Method, StatusCode};
+use warp::hyper::body::Bytes;
+use warp::hyper::server::conn::Http;
+use warp::reply::Response;
+use warp::{Filter, Reply};
+
+use super::access::AccessControl;
+use super::dispatch::{self, Gate};
+use crate::cairo::ext_py::Caller;
+use crate::monitoring::metrics::middleware::MaybeRpcMetricsMiddleware;
+
+/// Default limit of request and response bodies, the same as the JSON-RPC server's.
+pub const DEFAULT_MAX_BODY_SIZE: u32 = 10 * 1024 * 1024;
+
+/// Time allowed for a client to complete the TLS handshake.
+const TLS_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Options of the HTTP server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpOptions {
+    /// Serves HTTPS instead of HTTP.
+    pub tls: Option<TlsPaths>,
+    /// Answers CORS requests, which are refused otherwise.
+    pub cors: Option<Cors>,
+    /// Limit of request bodies in bytes.
+    pub max_request_body_size: u32,
+    /// Limit of response bodies in bytes.
+    pub max_response_body_size: u32,
+    /// Maximum number of calls in a batch request.
+    pub max_batch_len: Option<NonZeroUsize>,
+    /// Maximum number of concurrent connections, further ones wait to be accepted.
+    pub max_connections: Option<NonZeroUsize>,
+}
+
+impl Default for HttpOptions {
+    fn default() -> Self {
+        Self {
+            tls: None,
+            cors: None,
+            max_request_body_size: DEFAULT_MAX_BODY_SIZE,
+            max_response_body_size: DEFAULT_MAX_BODY_SIZE,
+            max_batch_len: None,
+            max_connections: None,
+        }
+    }
+}
+
+/// Paths of the PEM encoded TLS certificate chain and its PKCS#8 private key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TlsPaths {
+    pub cert: PathBuf,
+    pub key: PathBuf,
+}
+
+/// Allowed CORS origins and request headers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cors {
+    /// Origins such as `https://example.com`, or `*` for any.
+    pub origins: Vec<String>,
+    /// Request headers allowed in addition to `Content-Type`.
+    pub headers: Vec<String>,
+}
+
+/// Spawns the gateway serving `addr`, which executes the JSON-RPC requests it lets through with the
+/// `methods` of their path, and forwards other requests to the JSON-RPC server at `upstream`.
+///
+/// Methods the server does not have are counted as `unknown` in the access control metrics. The
+/// gateway lives as long as the process, requests reaching it after the JSON-RPC server stopped
+/// and `gate` closed fail with `503 Service Unavailable`.
+///
+/// Returns the address the gateway is bound to.
+pub(super) async fn spawn(
+    addr: SocketAddr,
+    upstream: SocketAddr,
+    options: &HttpOptions,
+    access: Option<AccessControl>,
+    methods: HashMap<&'static str, Methods>,
+    middleware: MaybeRpcMetricsMiddleware,
+    gate: Gate,
+) -> anyhow::Result<SocketAddr> {
+    let tls = match &options.tls {
+        Some(paths) => {
+            let tls = Arc::new(RwLock::new(load_tls(paths)?));
+            #[cfg(unix)]
+            reload_on_sighup(paths.clone(), tls.clone())?;
+            Some(tls)
+        }
+        None => None,
+    };
+
+    let cors = options.cors.as_ref().map(|cors| {
+        let builder = warp::cors()
+            .allow_methods([Method::POST])
+            .allow_header("content-type")
+            .allow_headers(cors.headers.iter().map(String::as_str));
+        let builder = if cors.origins.iter().any(|origin| origin == "*") {
+            builder.allow_any_origin()
+        } else {
+            builder.allow_origins(cors.origins.iter().map(String::as_str))
+        };
+        builder.build()
+    });
+
+    // The server is on the loopback interface, never behind a proxy.
+    let client = reqwest::Client::builder()
+        .no_proxy()
+        .build()
+        .context("Creating HTTP client")?;
+
+    let method_names = methods
+        .values()
+        .flat_map(|methods| methods.method_names())
+        .collect();
+
+    let gateway = Arc::new(Gateway {
+        upstream,
+        client,
+        access,
+        methods,
+        method_names,
+        middleware,
+        gate,
+        cors,
+        max_request_body_size: options.max_request_body_size,
+        max_response_body_size: options.max_response_body_size,
+        max_batch_len: options.max_batch_len,
+    });
+
+    let listener = TcpListener::bind(addr)
+        .await
+        .with_context(|| format!("Binding RPC address {addr}"))?;
+    let local_addr = listener.local_addr().context("Reading RPC address")?;
+
+    let connections = options
+        .max_connections
+        .map(|max| Arc::new(Semaphore::new(max.get())));
+
+    tokio::spawn(async move {
+        loop {
+            let permit = match &connections {
+                Some(connections) => Some(
+                    connections
+                        .clone()
+                        .acquire_owned()
+                        .await
+                        .expect("Semaphore is never closed"),
+                ),
+                None => None,
+            };
+
+            let (stream, remote) = match listener.accept().await {
+                Ok(accepted) => accepted,
+                Err(e) => {
+                    // Most likely out of file descriptors, which takes a while to resolve.
+                    tracing::debug!(reason=%e, "Accepting RPC connection failed");
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                    continue;
+                }
+            };
+
+            let tls = tls
+                .as_ref()
+                .map(|tls| tls.read().unwrap_or_else(|e| e.into_inner()).clone());
+            let service = warp::service(routes(gateway.clone(), remote.ip()));
+
+            tokio::spawn(async move {
+                let _permit = permit;
+
+                let result = match tls {
+                    Some(tls) => {
+                        match tokio::time::timeout(TLS_HANDSHAKE_TIMEOUT, tls.accept(stream)).await
+                        {
+                            Ok(Ok(stream)) => Http::new()
+                                .serve_connection(stream, service)
+                                .await
+                                .context("Serving connection"),
+                            Ok(Err(e)) => Err(anyhow::Error::new(e).context("TLS handshake")),
+                            Err(_) => Err(anyhow::anyhow!("TLS handshake timed out")),
+                        }
+                    }
+                    None => Http::new()
+                        .serve_connection(stream, service)
+                        .await
+                        .context("Serving connection"),
+                };
+
+                if let Err(e) = result {
+                    tracing::debug!(%remote, reason=?e, "RPC connection failed");
+                }
+            });
+        }
+    });
+
+    Ok(local_addr)
+}
+
+/// Reads the TLS certificate chain and private key.
+fn load_tls(paths: &TlsPaths) -> anyhow::Result<TlsAcceptor> {
+    let cert = std::fs::read(&paths.cert)
+        .with_context(|| format!("Reading TLS certificate from {}", paths.cert.display()))?;
+    let key = std::fs::read(&paths.key)
+        .with_context(|| format!("Reading TLS key from {}", paths.key.display()))?;
+
+    let identity = native_tls::Identity::from_pkcs8(&cert, &key)
+        .context("Parsing TLS certificate and PKCS#8 key")?;
+    let acceptor = native_tls::TlsAcceptor::new(identity).context("Creating TLS acceptor")?;
+
+    Ok(acceptor.into())
+}
+
+#[cfg(unix)]
+fn reload_on_sighup(paths: TlsPaths, tls: Arc<RwLock<TlsAcceptor>>) -> anyhow::Result<()> {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut hangups = signal(SignalKind::hangup()).context("Listening for SIGHUP")?;
+
+    tokio::spawn(async move {
+        while hangups.recv().await.is_some() {
+            match load_tls(&paths) {
+                Ok(acceptor) => {
+                    *tls.write().unwrap_or_else(|e| e.into_inner()) = acceptor;
+                    tracing::info!("Reloaded RPC TLS certificate");
+                }
+                Err(e) => {
+                    tracing::warn!(reason=?e, "Reloading RPC TLS certificate failed, keeping the previous one");
+                }
+            }
+        }
+    });
+
+    Ok(())
+}
+
+struct Gateway {
+    upstream: SocketAddr,
+    client: reqwest::Client,
+    access: Option<AccessControl>,
+    /// The methods served at each path.
+    methods: HashMap<&'static str, Methods>,
+    method_names: HashSet<&'static str>,
+    middleware: MaybeRpcMetricsMiddleware,
+    gate: Gate,
+    cors: Option<warp::cors::Cors>,
+    max_request_body_size: u32,
+    max_response_body_size: u32,
+    max_batch_len: Option<NonZeroUsize>,
+}
+
+struct Request {
+    method: Method,
+    path: String,
+    headers: HeaderMap,
+    body: Bytes,
+}
+
+/// The routes of a connection from `ip`.
+fn routes(gateway: Arc<Gateway>, ip: IpAddr) -> BoxedFilter<(Response,)> {
+    let cors = gateway.cors.clone();
+
+    let route = warp::method()
+        .and(warp::path::full())
+        .and(warp::header::headers_cloned())
+        .and(warp::body::stream())
+        .then(
+            move |method: Method, path: warp::path::FullPath, headers: HeaderMap, body| {
+                let gateway = gateway.clone();
+                async move {
+                    let body = match read_body(body, gateway.max_request_body_size).await {
+                        Ok(body) => body,
+                        Err(status) => return status.into_response(),
+                    };
+                    let request = Request {
+                        method,
+                        path: path.as_str().to_owned(),
+                        headers,
+                        body,
+                    };
+                    gateway.handle(ip, request).await
+                }
+            },
+        );
+
+    match cors {
+        Some(cors) => route.with(cors).map(Reply::into_response).boxed(),
+        None => route.boxed(),
+    }
+}
+
+/// Reads a body of up to `limit` bytes.
+async fn read_body<S, B>(body: S, limit: u32) -> Result<Bytes, StatusCode>
+where
+    S: Stream<Item = Result<B, warp::Error>>,
+    B: Buf,
+{
+    let mut body = Box::pin(body);
+
+    let mut bytes = Vec::new();
+    while let Some(chunk) = body.next().await {
+        let mut chunk = chunk.map_err(|_| StatusCode::BAD_REQUEST)?;
+        if bytes.len() + chunk.remaining() > limit as usize {
+            return Err(StatusCode::PAYLOAD_TOO_LARGE);
+        }
+        while chunk.has_remaining() {
+            let len = chunk.chunk().len();
+            bytes.extend_from_slice(chunk.chunk());
+            chunk.advance(len);
+        }
+    }
+
+    Ok(bytes.into())
+}
+
+impl Gateway {
+    async fn handle(&self, ip: IpAddr, request: Request) -> Response {
+        let parsed = Parsed::new(&request.body);
+
+        if let (Some(len), Some(max)) = (parsed.batch_len, self.max_batch_len) {
+            if len > max.get() {
+                return batch_too_long(max);
+            }
+        }
+
+        let caller = match &self.access {
+            Some(access) => {
+                let methods = parsed
+                    .calls
+                    .iter()
+                    .map(|(_, method)| method.as_str())
+                    .collect::<Vec<_>>();
+                match access.authorize(ip, &request.headers, &methods, &self.method_names) {
+                    Ok(Some(key)) => Caller::Key(key.to_owned()),
+                    Ok(None) => Caller::Peer(ip),
+                    Err(denial) => {
+                        return parsed.error_response(
+                            denial.code(),
+                            &denial.message(),
+                            denial.status(),
+                        )
+                    }
+                }
+            }
+            None => Caller::Peer(ip),
+        };
+
+        if request.method == Method::POST && is_json(&request.headers) {
+            if let Some(methods) = self.methods.get(request.path.as_str()) {
+                return match self
+                    .gate
+                    .pass(self.dispatch(methods, &caller, &request.body))
+                    .await
+                {
+                    Some(response) => response,
+                    None => StatusCode::SERVICE_UNAVAILABLE.into_response(),
+                };
+            }
+        }
+
+        match self.forward(request).await {
+            Ok(response) => response,
+            Err(e) => {
+                tracing::debug!(reason=?e, "Forwarding RPC request failed");
+                StatusCode::SERVICE_UNAVAILABLE.into_response()
+            }
+        }
+    }
+
+    /// Executes a request in-process, with the body limit of the JSON-RPC server.
+    async fn dispatch(&self, methods: &Methods, caller: &Caller, body: &[u8]) -> Response {
+        let response = dispatch::handle(methods, &self.middleware, caller, body)
+            .await
+            .unwrap_or_else(|| dispatch::error(-32700, "Parse error", None));
+        let response = if response.len() > self.max_response_body_size as usize {
+            dispatch::error(-32702, "Response is too big", None)
+        } else {
+            response
+        };
+
+        warp::http::Response::builder()
+            .header("content-type", "application/json; charset=utf-8")
+            .body(response.into())
+            .expect("Header is valid")
+    }
+
+    async fn forward(&self, request: Request) -> anyhow::Result<Response> {
+        let mut forwarded = self
+            .client
+            .request(
+                request.method,
+                format!("http://{}{}", self.upstream, request.path),
+            )
+            .body(request.body);
+        if let Some(content_type) = request.headers.get("content-type") {
+            forwarded = forwarded.header("content-type", content_type);
+        }
+
+        let response = forwarded.send().await.context("Sending request")?;
+        let status = response.status();
+        let content_type = response.headers().get("content-type").cloned();
+        let body = response.bytes().await.context("Reading response")?;
+
+        let mut reply = warp::http::Response::new(body.into());
+        *reply.status_mut() = status;
+        if let Some(content_type) = content_type {
+            reply.headers_mut().insert("content-type", content_type);
+        }
+
+        Ok(reply)
+    }
+}
+
+/// Whether the body is declared as JSON, as the JSON-RPC server requires.
+fn is_json(headers: &HeaderMap) -> bool {
+    headers
+        .get("content-type")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(';').next())
+        .map_or(false, |mime| {
+            mime.trim().eq_ignore_ascii_case("application/json")
+        })
+}
+
+/// Refuses a batch of more than `max` calls as a whole.
+fn batch_too_long(max: NonZeroUsize) -> Response {
+    let body = serde_json::json!({
+        "jsonrpc": "2.0",
+        "error": {
+            "code": -32600,
+            "message": format!("Batch too long, at most {max} calls are allowed"),
+        },
+        "id": Value::Null,
+    });
+
+    warp::reply::json(&body).into_response()
+}
+
+/// The calls of a single or batch request.
+#[derive(Debug, PartialEq)]
+struct Parsed {
+    /// The number of requests in a batch, [None] if it is not one.
+    batch_len: Option<usize>,
+    /// The ids and methods of the calls, empty if the request is not valid JSON-RPC, which is
+    /// left for the server to report.
+    calls: Vec<(Value, String)>,
+}
+
+impl Parsed {
+    fn new(body: &[u8]) -> Self {
+        let call = |call: &Value| {
+            let method = call.get("method")?.as_str()?.to_owned();
+            let id = call.get("id").cloned().unwrap_or(Value::Null);
+            Some((id, method))
+        };
+
+        match serde_json::from_slice::<Value>(body) {
+            Ok(Value::Array(batch)) => Self {
+                batch_len: Some(batch.len()),
+                calls: batch.iter().filter_map(call).collect(),
+            },
+            Ok(single) => Self {
+                batch_len: None,
+                calls: call(&single).into_iter().collect(),
+            },
+            Err(_) => Self {
+                batch_len: None,
+                calls: Vec::new(),
+            },
+        }
+    }
+
+    /// Answers each of the calls with the same error.
+    fn error_response(&self, code: i32, message: &str, status: StatusCode) -> Response {
+        let error = |id: &Value| {
+            serde_json::json!({
+                "jsonrpc": "2.0",
+                "error": { "code": code, "message": message },
+                "id": id,
+            })
+        };
+
+        let body = match (self.batch_len, self.calls.first()) {
+            (Some(_), _) => Value::Array(self.calls.iter().map(|(id, _)| error(id)).collect()),
+            (None, Some((id, _))) => error(id),
+            (None, None) => error(&Value::Null),
+        };
+
+        warp::reply::with_status(warp::reply::json(&body), status).into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::Chain;
+    use crate::rpc::access::{AccessConfig, KeyConfig};
+    use crate::rpc::v01::api::RpcApi;
+    use crate::rpc::RpcServer;
+    use crate::state::SyncState;
+    use crate::storage::Storage;
+
+    fn fixture(name: &str) -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("fixtures/tls")
+            .join(name)
+    }
+
+    async fn serve(
+        options: HttpOptions,
+        access: Option<AccessControl>,
+    ) -> (crate::rpc::RpcHandle, SocketAddr) {
+        let api = RpcApi::new(
+            Storage::in_memory().unwrap(),
+            crate::sequencer::Client::new(Chain::Testnet).unwrap(),
+            Chain::Testnet,
+            Arc::new(SyncState::default()),
+        );
+        let server = RpcServer::new(([127, 0, 0, 1], 0).into(), api).with_http_options(options);
+        let server = match access {
+            Some(access) => server.with_access_control(access),
+            None => server,
+        };
+        server.run().await.unwrap()
+    }
+
+    fn chain_id(id: u64) -> Value {
+        serde_json::json!({"jsonrpc": "2.0", "id": id, "method": "starknet_chainId"})
+    }
+
+    #[test]
+    fn parse() {
+        let single = br#"{"jsonrpc":"2.0","id":7,"method":"a"}"#;
+        assert_eq!(
+            Parsed::new(single),
+            Parsed {
+                batch_len: None,
+                calls: vec![(Value::from(7), "a".to_owned())]
+            }
+        );
+
+        let batch =
+            br#"[{"jsonrpc":"2.0","id":1,"method":"a"},{"jsonrpc":"2.0","id":"x","method":"b"},1]"#;
+        let parsed = Parsed::new(batch);
+        assert_eq!(parsed.batch_len, Some(3));
+        assert_eq!(parsed.calls.len(), 2);
+
+        let response = parsed.error_response(-32005, "error", StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+
+        assert_eq!(
+            Parsed::new(b"not json"),
+            Parsed {
+                batch_len: None,
+                calls: vec![]
+            }
+        );
+    }
+
+    #[test]
+    fn load_tls_errors() {
+        let paths = TlsPaths {
+            cert: fixture("cert.pem"),
+            key: fixture("missing.pem"),
+        };
+        let error = format!("{:#}", load_tls(&paths).unwrap_err());
+        assert!(error.contains("Reading TLS key"), "{error}");
+
+        // the certificate is not a key
+        let paths = TlsPaths {
+            cert: fixture("cert.pem"),
+            key: fixture("cert.pem"),
+        };
+        assert!(load_tls(&paths).is_err());
+    }
+
+    #[tokio::test]
+    async fn access_control() {
+        let access = AccessControl::new(AccessConfig {
+            keys: vec![KeyConfig {
+                name: "read-only".to_owned(),
+                key: "secret".to_owned(),
+                allow: vec![],
+                deny: vec!["starknet_add*".to_owned()],
+                rate_limit: None,
+            }],
+            ..Default::default()
+        });
+        let (_handle, addr) = serve(HttpOptions::default(), Some(access)).await;
+
+        let client = reqwest::Client::new();
+        let request = |method: &str, key: Option<&str>| {
+            let request = client
+                .post(format!("http://{addr}/rpc/v0.2"))
+                .json(&serde_json::json!({"jsonrpc": "2.0", "id": 1, "method": method}));
+            let request = match key {
+                Some(key) => request.bearer_auth(key),
+                None => request,
+            };
+            async move {
+                let response = request.send().await.unwrap();
+                let status = response.status();
+                (status, response.json::<Value>().await.unwrap())
+            }
+        };
+
+        let (status, body) = request("starknet_chainId", Some("secret")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["result"], "0x534e5f474f45524c49");
+
+        let (status, body) = request("starknet_chainId", None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(body["error"]["code"], -32002);
+
+        let (status, body) = request("starknet_addInvokeTransaction", Some("secret")).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert_eq!(body["error"]["code"], -32003);
+        assert_eq!(body["id"], 1);
+    }
+
+    #[tokio::test]
+    async fn limits() {
+        let options = HttpOptions {
+            max_request_body_size: 1024,
+            max_batch_len: NonZeroUsize::new(2),
+            max_connections: NonZeroUsize::new(1),
+            ..Default::default()
+        };
+        let (_handle, addr) = serve(options, None).await;
+        let client = reqwest::Client::new();
+        let url = format!("http://{addr}/rpc/v0.2");
+
+        let response = client
+            .post(&url)
+            .json(&[chain_id(1), chain_id(2)])
+            .send()
+            .await
+            .unwrap();
+        let body = response.json::<Value>().await.unwrap();
+        assert_eq!(body[1]["result"], "0x534e5f474f45524c49");
+
+        let response = client
+            .post(&url)
+            .json(&[chain_id(1), chain_id(2), chain_id(3)])
+            .send()
+            .await
+            .unwrap();
+        let body = response.json::<Value>().await.unwrap();
+        assert_eq!(body["error"]["code"], -32600);
+        assert_eq!(body["id"], Value::Null);
+
+        let response = client
+            .post(&url)
+            .header("content-type", "application/json")
+            .body(vec![b' '; 1025])
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    #[tokio::test]
+    async fn oversized_response() {
+        let options = HttpOptions {
+            max_response_body_size: 64,
+            // only for the requests to go through the gateway
+            max_batch_len: NonZeroUsize::new(8),
+            ..Default::default()
+        };
+        let (_handle, addr) = serve(options, None).await;
+        let client = reqwest::Client::new();
+        let url = format!("http://{addr}/rpc/v0.2");
+
+        let response = client.post(&url).json(&chain_id(1)).send().await.unwrap();
+        let body = response.json::<Value>().await.unwrap();
+        assert_eq!(body["result"], "0x534e5f474f45524c49");
+
+        let response = client
+            .post(&url)
+            .json(&[chain_id(1), chain_id(2)])
+            .send()
+            .await
+            .unwrap();
+        let body = response.json::<Value>().await.unwrap();
+        assert_eq!(body["error"]["code"], -32702);
+    }
+
+    #[test]
+    fn json_content_type() {
+        let headers = |value: &'static str| {
+            let mut headers = HeaderMap::new();
+            headers.insert("content-type", value.parse().unwrap());
+            headers
+        };
+        assert!(is_json(&headers("application/json")));
+        assert!(is_json(&headers("Application/JSON; charset=utf-8")));
+        assert!(!is_json(&headers("text/plain")));
+        assert!(!is_json(&HeaderMap::new()));
+    }
+
+    #[tokio::test]
+    async fn cors() {
+        let options = HttpOptions {
+            cors: Some(Cors {
+                origins: vec!["https://example.com".to_owned()],
+                headers: vec!["x-api-key".to_owned()],
+            }),
+            ..Default::default()
+        };
+        let (_handle, addr) = serve(options, None).await;
+        let client = reqwest::Client::new();
+        let url = format!("http://{addr}/rpc/v0.2");
+
+        let preflight = |origin: &str| {
+            client
+                .request(Method::OPTIONS, &url)
+                .header("origin", origin)
+                .header("access-control-request-method", "POST")
+                .header("access-control-request-headers", "content-type, x-api-key")
+                .send()
+        };
+
+        let response = preflight("https://example.com").await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()["access-control-allow-origin"],
+            "https://example.com"
+        );
+
+        let response = preflight("https://example.org").await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let response = client
+            .post(&url)
+            .header("origin", "https://example.com")
+            .json(&chain_id(1))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(
+            response.headers()["access-control-allow-origin"],
+            "https://example.com"
+        );
+        let body = response.json::<Value>().await.unwrap();
+        assert_eq!(body["result"], "0x534e5f474f45524c49");
+    }
+
+    #[tokio::test]
+    async fn tls() {
+        let options = HttpOptions {
+            tls: Some(TlsPaths {
+                cert: fixture("cert.pem"),
+                key: fixture("key.pem"),
+            }),
+            ..Default::default()
+        };
+        let (_handle, addr) = serve(options, None).await;
+
+        // the fixture is self-signed
+        let client = reqwest::Client::builder()
+            .danger_accept_invalid_certs(true)
+            .build()
+            .unwrap();
+
+        let response = client
+            .post(format!("https://{addr}/rpc/v0.2"))
+            .json(&chain_id(1))
+            .send()
+            .await
+            .unwrap();
+        let body = response.json::<Value>().await.unwrap();
+        assert_eq!(body["result"], "0x534e5f474f45524c49");
+
+        let plain = reqwest::Client::new()
+            .post(format!("http://{addr}/rpc/v0.2"))
+            .json(&chain_id(1))
+            .send()
+            .await;
+        assert!(plain.is_err());
+    }
+}
@@ -0,0 +1,437 @@
+//! JSON-RPC over Unix domain sockets, for services running on the same host.
+//!
+//! Each line a client writes is a JSON-RPC request or batch, and is answered with a line holding
+//! its response, in order. The socket at the configured path serves the v0.2 and pathfinder
+//! methods. The v0.1 methods share their names with v0.2, and are served by a second socket at the
+//! same path with a `.v0.1` suffix.
+//!
+//! Calls are [dispatched](super::dispatch) in-process and recorded by the same middleware as HTTP
+//! ones, and each connection waits for the python subprocesses in a lane of its own. Access is
+//! controlled by the file permissions of the sockets, not by the [access](super::access)
+//! configuration.
+//!
+//! The sockets are served until the RPC server is stopped, which lets the calls in progress
+//! complete and then removes the socket files.
+use std::path::PathBuf;
+
+/// Default permissions of the sockets, read and write for the owner only.
+pub const DEFAULT_PERMISSIONS: u32 = 0o600;
+
+/// Where to serve the JSON-RPC API over IPC.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IpcOptions {
+    /// Path of the socket serving the v0.2 and pathfinder methods.
+    pub path: PathBuf,
+    /// Unix permissions of the sockets, such as `0o660` to allow the group.
+    pub permissions: u32,
+}
+
+impl IpcOptions {
+    /// Path of the socket serving the v0.1 methods.
+    pub fn v01_path(&self) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(".v0.1");
+        path.into()
+    }
+}
+
+#[cfg(unix)]
+pub(super) use server::spawn;
+
+#[cfg(not(unix))]
+pub(super) fn spawn(
+    options: &IpcOptions,
+    _v01: jsonrpsee::core::server::rpc_module::Methods,
+    _v02: jsonrpsee::core::server::rpc_module::Methods,
+    _middleware: crate::monitoring::metrics::middleware::MaybeRpcMetricsMiddleware,
+    _gate: super::dispatch::Gate,
+    _max_request_size: u32,
+) -> anyhow::Result<tokio::task::JoinHandle<()>> {
+    anyhow::bail!(
+        "IPC is only supported on Unix, cannot serve {}",
+        options.path.display()
+    )
+}
+
+#[cfg(unix)]
+mod server {
+    use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+    use std::path::{Path, PathBuf};
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::time::Duration;
+
+    use anyhow::Context;
+    use jsonrpsee::core::server::rpc_module::Methods;
+    use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::{UnixListener, UnixStream};
+
+    use super::IpcOptions;
+    use crate::cairo::ext_py::Caller;
+    use crate::monitoring::metrics::middleware::MaybeRpcMetricsMiddleware;
+    use crate::rpc::dispatch::Gate;
+
+    /// Binds the sockets of `options` and serves `v01` and `v02` on them until `gate` closes.
+    /// Requests longer than `max_request_size` bytes close their connection.
+    ///
+    /// The returned task completes once both sockets have been removed.
+    pub fn spawn(
+        options: &IpcOptions,
+        v01: Methods,
+        v02: Methods,
+        middleware: MaybeRpcMetricsMiddleware,
+        gate: Gate,
+        max_request_size: u32,
+    ) -> anyhow::Result<tokio::task::JoinHandle<()>> {
+        let v02_listener = bind(&options.path, options.permissions)?;
+        let v01_listener = bind(&options.v01_path(), options.permissions)?;
+
+        let v02 = serve(
+            v02_listener,
+            options.path.clone(),
+            v02,
+            middleware.clone(),
+            gate.clone(),
+            max_request_size,
+        );
+        let v01 = serve(
+            v01_listener,
+            options.v01_path(),
+            v01,
+            middleware,
+            gate,
+            max_request_size,
+        );
+
+        Ok(tokio::spawn(async move {
+            tokio::join!(v02, v01);
+        }))
+    }
+
+    /// Binds a socket at `path`, replacing one left behind by a previous run.
+    fn bind(path: &Path, permissions: u32) -> anyhow::Result<UnixListener> {
+        match std::fs::symlink_metadata(path) {
+            Ok(metadata) if metadata.file_type().is_socket() => {
+                if std::os::unix::net::UnixStream::connect(path).is_ok() {
+                    anyhow::bail!("IPC socket {} is already in use", path.display());
+                }
+                std::fs::remove_file(path)
+                    .with_context(|| format!("Removing stale IPC socket {}", path.display()))?;
+            }
+            Ok(_) => anyhow::bail!("IPC path {} exists and is not a socket", path.display()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => {
+                return Err(e).with_context(|| format!("Inspecting IPC path {}", path.display()))
+            }
+        }
+
+        // Bound under a temporary name and only moved into place once its permissions are set,
+        // so that it is never reachable with the default ones.
+        let file_name = path
+            .file_name()
+            .with_context(|| format!("IPC path {} has no file name", path.display()))?;
+        let mut temporary = std::ffi::OsString::from(".");
+        temporary.push(file_name);
+        temporary.push(format!(".{}", std::process::id()));
+        let temporary = path.with_file_name(temporary);
+        let _ = std::fs::remove_file(&temporary);
+
+        let listener = UnixListener::bind(&temporary)
+            .with_context(|| format!("Binding IPC socket {}", path.display()))?;
+        std::fs::set_permissions(&temporary, std::fs::Permissions::from_mode(permissions))
+            .with_context(|| format!("Setting permissions of IPC socket {}", path.display()))?;
+        std::fs::rename(&temporary, path)
+            .with_context(|| format!("Moving IPC socket into place at {}", path.display()))?;
+
+        Ok(listener)
+    }
+
+    /// Numbers the connections of both sockets, which are each a [Caller] of their own.
+    static CONNECTIONS: AtomicU64 = AtomicU64::new(0);
+
+    /// Accepts connections to the socket at `path` until `gate` starts closing, and then removes
+    /// the socket.
+    async fn serve(
+        listener: UnixListener,
+        path: PathBuf,
+        methods: Methods,
+        middleware: MaybeRpcMetricsMiddleware,
+        gate: Gate,
+        max_request_size: u32,
+    ) {
+        loop {
+            let accepted = tokio::select! {
+                accepted = listener.accept() => accepted,
+                _ = gate.closing() => break,
+            };
+            let stream = match accepted {
+                Ok((stream, _)) => stream,
+                Err(e) => {
+                    tracing::debug!(reason=%e, "Accepting IPC connection failed");
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                    continue;
+                }
+            };
+
+            let caller = Caller::Ipc(CONNECTIONS.fetch_add(1, Ordering::Relaxed));
+            let methods = methods.clone();
+            let middleware = middleware.clone();
+            let gate = gate.clone();
+            tokio::spawn(async move {
+                if let Err(e) = serve_connection(
+                    stream,
+                    &methods,
+                    &middleware,
+                    &caller,
+                    &gate,
+                    max_request_size,
+                )
+                .await
+                {
+                    tracing::debug!(reason=?e, "IPC connection failed");
+                }
+            });
+        }
+
+        drop(listener);
+        if let Err(e) = std::fs::remove_file(&path) {
+            tracing::warn!(path=%path.display(), reason=%e, "Removing IPC socket failed");
+        }
+    }
+
+    async fn serve_connection(
+        stream: UnixStream,
+        methods: &Methods,
+        middleware: &MaybeRpcMetricsMiddleware,
+        caller: &Caller,
+        gate: &Gate,
+        max_request_size: u32,
+    ) -> anyhow::Result<()> {
+        let (reader, mut writer) = stream.into_split();
+        let mut reader = BufReader::new(reader);
+        let mut line = Vec::new();
+
+        loop {
+            line.clear();
+            // Idle connections are closed along with the sockets.
+            let read = tokio::select! {
+                read = (&mut reader)
+                    .take(u64::from(max_request_size) + 1)
+                    .read_until(b'\n', &mut line) => read.context("Reading request")?,
+                _ = gate.closing() => return Ok(()),
+            };
+            if read == 0 {
+                return Ok(());
+            }
+            if line.last() != Some(&b'\n') && read > max_request_size as usize {
+                anyhow::bail!("Request exceeds {max_request_size} bytes");
+            }
+
+            let response = gate
+                .pass(crate::rpc::dispatch::handle(
+                    methods, middleware, caller, &line,
+                ))
+                .await;
+            let response = match response {
+                Some(response) => response,
+                // Stopped while the request was read.
+                None => return Ok(()),
+            };
+            if let Some(mut response) = response {
+                response.push('\n');
+                writer
+                    .write_all(response.as_bytes())
+                    .await
+                    .context("Writing response")?;
+            }
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::core::Chain;
+        use crate::monitoring::metrics::middleware::RpcMetricsMiddleware;
+        use crate::monitoring::metrics::test::{FakeRecorder, RecorderGuard};
+        use crate::rpc::v01::api::RpcApi;
+        use crate::rpc::RpcServer;
+        use crate::state::SyncState;
+        use crate::storage::Storage;
+        use serde_json::Value;
+        use std::sync::Arc;
+
+        async fn start(options: IpcOptions) -> crate::rpc::RpcHandle {
+            let api = RpcApi::new(
+                Storage::in_memory().unwrap(),
+                crate::sequencer::Client::new(Chain::Testnet).unwrap(),
+                Chain::Testnet,
+                Arc::new(SyncState::default()),
+            );
+            let (handle, _) = RpcServer::new(([127, 0, 0, 1], 0).into(), api)
+                .with_middleware(RpcMetricsMiddleware)
+                .with_ipc(options)
+                .run()
+                .await
+                .unwrap();
+            handle
+        }
+
+        fn options(dir: &tempfile::TempDir) -> IpcOptions {
+            IpcOptions {
+                path: dir.path().join("rpc.sock"),
+                permissions: crate::rpc::ipc::DEFAULT_PERMISSIONS,
+            }
+        }
+
+        /// Writes each of `requests` as a line, returning the response lines.
+        async fn requests(path: &Path, requests: &[&str]) -> Vec<Value> {
+            let stream = UnixStream::connect(path).await.unwrap();
+            let (reader, mut writer) = stream.into_split();
+            let mut lines = BufReader::new(reader).lines();
+
+            let mut responses = Vec::new();
+            for request in requests {
+                writer
+                    .write_all(format!("{request}\n").as_bytes())
+                    .await
+                    .unwrap();
+                let line = lines.next_line().await.unwrap().unwrap();
+                responses.push(serde_json::from_str(&line).unwrap());
+            }
+            responses
+        }
+
+        #[tokio::test]
+        async fn serves_modules() {
+            let _guard = RecorderGuard::lock_as_noop();
+            let dir = tempfile::tempdir().unwrap();
+            let options = options(&dir);
+            let _handle = start(options.clone()).await;
+
+            let responses = requests(
+                &options.path,
+                &[
+                    r#"{"jsonrpc":"2.0","id":1,"method":"starknet_chainId"}"#,
+                    r#"[{"jsonrpc":"2.0","id":2,"method":"pathfinder_version"},{"jsonrpc":"2.0","id":3,"method":"unknown"}]"#,
+                    r#"{"jsonrpc":"2.0","id":4,"#,
+                    "[]",
+                ],
+            )
+            .await;
+            assert_eq!(responses[0]["result"], "0x534e5f474f45524c49");
+            assert_eq!(responses[0]["id"], 1);
+            assert!(responses[1][0]["result"].is_string());
+            assert_eq!(responses[1][1]["error"]["code"], -32601);
+            assert_eq!(responses[1][1]["id"], 3);
+            assert_eq!(responses[2]["error"]["code"], -32700);
+            assert_eq!(responses[3]["error"]["code"], -32600);
+
+            let responses = requests(
+                &options.v01_path(),
+                &[r#"{"jsonrpc":"2.0","id":1,"method":"starknet_chainId"}"#],
+            )
+            .await;
+            assert_eq!(responses[0]["result"], "0x534e5f474f45524c49");
+        }
+
+        #[tokio::test]
+        async fn socket_files() {
+            let _guard = RecorderGuard::lock_as_noop();
+            let dir = tempfile::tempdir().unwrap();
+            let options = IpcOptions {
+                permissions: 0o660,
+                ..options(&dir)
+            };
+
+            // left behind by a previous run
+            drop(std::os::unix::net::UnixListener::bind(&options.path).unwrap());
+            let _handle = start(options.clone()).await;
+
+            let mode = std::fs::metadata(&options.path)
+                .unwrap()
+                .permissions()
+                .mode();
+            assert_eq!(mode & 0o777, 0o660);
+
+            // in use by this one
+            let error = bind(&options.path, options.permissions).unwrap_err();
+            assert!(error.to_string().contains("already in use"), "{error}");
+
+            let file = dir.path().join("file");
+            std::fs::write(&file, b"").unwrap();
+            assert!(bind(&file, options.permissions).is_err());
+        }
+
+        #[tokio::test]
+        async fn stop_removes_sockets() {
+            let _guard = RecorderGuard::lock_as_noop();
+            let dir = tempfile::tempdir().unwrap();
+            let options = options(&dir);
+            let handle = start(options.clone()).await;
+
+            // an idle connection does not hold up the stop
+            let idle = UnixStream::connect(&options.path).await.unwrap();
+            requests(
+                &options.path,
+                &[r#"{"jsonrpc":"2.0","id":1,"method":"starknet_chainId"}"#],
+            )
+            .await;
+
+            handle.stop().await.unwrap();
+            assert!(!options.path.exists());
+            assert!(!options.v01_path().exists());
+            assert!(UnixStream::connect(&options.path).await.is_err());
+
+            let mut response = Vec::new();
+            let (mut reader, _writer) = idle.into_split();
+            reader.read_to_end(&mut response).await.unwrap();
+            assert!(response.is_empty());
+        }
+
+        #[tokio::test]
+        async fn oversized_request_closes_connection() {
+            let _guard = RecorderGuard::lock_as_noop();
+            let dir = tempfile::tempdir().unwrap();
+            let options = options(&dir);
+            let listener = bind(&options.path, options.permissions).unwrap();
+            tokio::spawn(serve(
+                listener,
+                options.path.clone(),
+                Methods::new(),
+                MaybeRpcMetricsMiddleware::NoOp,
+                Gate::default(),
+                16,
+            ));
+
+            let mut stream = UnixStream::connect(&options.path).await.unwrap();
+            stream.write_all(&[b' '; 32]).await.unwrap();
+            let mut response = Vec::new();
+            stream.read_to_end(&mut response).await.unwrap();
+            assert!(response.is_empty());
+        }
+
+        #[tokio::test]
+        async fn metrics() {
+            let recorder = FakeRecorder::new(&["starknet_chainId"]);
+            let handle = recorder.handle();
+            let _guard = RecorderGuard::lock(recorder);
+
+            let dir = tempfile::tempdir().unwrap();
+            let options = options(&dir);
+            let _handle = start(options.clone()).await;
+
+            requests(
+                &options.path,
+                &[
+                    r#"{"jsonrpc":"2.0","id":1,"method":"starknet_chainId"}"#,
+                    r#"[{"jsonrpc":"2.0","id":2,"method":"starknet_chainId"}]"#,
+                ],
+            )
+            .await;
+
+            assert_eq!(
+                handle.get_counter_value("rpc_method_calls_total", "starknet_chainId"),
+                2
+            );
+        }
+    }
+}
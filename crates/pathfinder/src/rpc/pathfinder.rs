@@ -1,4 +1,10 @@
-pub fn register_all_methods(module: &mut jsonrpsee::RpcModule<()>) -> anyhow::Result<()> {
+mod l1_acceptance;
+mod messages;
+mod sequencer;
+
+use crate::rpc::v02::{register_method, RpcContext};
+
+pub fn register_all_methods(module: &mut jsonrpsee::RpcModule<RpcContext>) -> anyhow::Result<()> {
     use anyhow::Context;
 
     module
@@ -6,6 +12,41 @@ pub fn register_all_methods(module: &mut jsonrpsee::RpcModule<()>) -> anyhow::Re
             Ok(env!("VERGEN_GIT_SEMVER_LIGHTWEIGHT"))
         })
         .with_context(|| "Registering pathfinder_version".to_string())?;
+    register_method(
+        module,
+        "pathfinder_getMessageStatus",
+        messages::get_message_status,
+    )?;
+    register_method(
+        module,
+        "pathfinder_getMessagesByAddress",
+        messages::get_messages_by_address,
+    )?;
+    register_method(
+        module,
+        "pathfinder_getL1Acceptance",
+        l1_acceptance::get_l1_acceptance,
+    )?;
+    register_method(
+        module,
+        "pathfinder_getSequencerBlock",
+        sequencer::get_sequencer_block,
+    )?;
+    register_method(
+        module,
+        "pathfinder_getSequencerStateUpdate",
+        sequencer::get_sequencer_state_update,
+    )?;
+    register_method(
+        module,
+        "pathfinder_getClassDefinition",
+        sequencer::get_class_definition,
+    )?;
+    register_method(
+        module,
+        "pathfinder_getCompiledClassDefinition",
+        sequencer::get_compiled_class_definition,
+    )?;
 
     Ok(())
 }
@@ -0,0 +1,207 @@
+use anyhow::Context;
+
+use crate::core::{BlockId, StarknetBlockHash, StarknetBlockNumber, StarknetTransactionHash};
+use crate::rpc::v02::common::get_l1_reference;
+use crate::rpc::v02::openrpc::{Object, Schema};
+use crate::rpc::v02::types::reply::L1Reference;
+use crate::rpc::v02::RpcContext;
+use crate::storage::{
+    RefsTable, StarknetBlocksBlockId, StarknetBlocksTable, StarknetTransactionsTable,
+};
+
+crate::rpc::error::generate_rpc_error_subset!(GetL1AcceptanceError: BlockNotFound, TxnHashNotFound);
+
+/// The StarkNet block to look up, either directly or as the block containing a transaction.
+#[derive(serde::Deserialize, Debug, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum GetL1AcceptanceInput {
+    Block {
+        block_id: BlockId,
+    },
+    Transaction {
+        transaction_hash: StarknetTransactionHash,
+    },
+}
+
+#[derive(serde::Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct L1Acceptance {
+    pub block_hash: StarknetBlockHash,
+    pub block_number: StarknetBlockNumber,
+    /// The L1 state update which accepted the block, `null` until the block is accepted on L1.
+    pub l1_reference: Option<L1Reference>,
+    /// The latest StarkNet block accepted on L1, `null` if there is none yet.
+    pub latest_accepted_block_number: Option<StarknetBlockNumber>,
+}
+
+impl Schema for L1Acceptance {
+    fn schema() -> serde_json::Value {
+        // the options are serialized as null rather than skipped
+        Object::new::<Self>()
+            .field::<StarknetBlockHash>("block_hash")
+            .field::<StarknetBlockNumber>("block_number")
+            .field_as("l1_reference", Option::<L1Reference>::schema(), true)
+            .field_as(
+                "latest_accepted_block_number",
+                Option::<StarknetBlockNumber>::schema(),
+                true,
+            )
+            .build()
+    }
+}
+
+/// Returns where on L1 a StarkNet block, or the block containing a transaction, was accepted.
+pub async fn get_l1_acceptance(
+    context: RpcContext,
+    input: GetL1AcceptanceInput,
+) -> Result<L1Acceptance, GetL1AcceptanceError> {
+    let span = tracing::Span::current();
+    let jh = tokio::task::spawn_blocking(move || -> Result<_, GetL1AcceptanceError> {
+        let _g = span.enter();
+        let mut db = context
+            .storage
+            .connection()
+            .context("Opening database connection")?;
+        let tx = db.transaction().context("Creating database transaction")?;
+
+        let block_id = match input {
+            GetL1AcceptanceInput::Block { block_id } => match block_id {
+                BlockId::Hash(hash) => hash.into(),
+                BlockId::Number(number) => number.into(),
+                BlockId::Latest => StarknetBlocksBlockId::Latest,
+                // Pending blocks are never accepted on L1.
+                BlockId::Pending => return Err(GetL1AcceptanceError::BlockNotFound),
+            },
+            GetL1AcceptanceInput::Transaction { transaction_hash } => {
+                let (_, _, block_hash) =
+                    StarknetTransactionsTable::get_transaction_with_receipt(&tx, transaction_hash)
+                        .context("Reading transaction from database")?
+                        .ok_or(GetL1AcceptanceError::TxnHashNotFound)?;
+                block_hash.into()
+            }
+        };
+
+        let block = StarknetBlocksTable::get(&tx, block_id)
+            .context("Reading block from database")?
+            .ok_or(GetL1AcceptanceError::BlockNotFound)?;
+        let l1_reference = get_l1_reference(&tx, block.number)?;
+        let latest_accepted_block_number =
+            RefsTable::get_l1_l2_head(&tx).context("Reading L1-L2 head from database")?;
+
+        Ok(L1Acceptance {
+            block_hash: block.hash,
+            block_number: block.number,
+            l1_reference,
+            latest_accepted_block_number,
+        })
+    });
+
+    jh.await.context("Database read panic or shutting down")?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{
+        EthereumBlockHash, EthereumBlockNumber, EthereumLogIndex, EthereumTransactionHash,
+        EthereumTransactionIndex,
+    };
+    use crate::ethereum::{log::StateUpdateLog, BlockOrigin, EthOrigin, TransactionOrigin};
+    use crate::rpc::v02::types::reply::L1Finality;
+    use crate::starkhash_bytes;
+    use crate::storage::L1StateTable;
+    use assert_matches::assert_matches;
+    use jsonrpsee::types::Params;
+    use web3::types::H256;
+
+    #[test]
+    fn parsing() {
+        let block = Params::new(Some(r#"{"block_id": "latest"}"#));
+        assert_eq!(
+            block.parse::<GetL1AcceptanceInput>().unwrap(),
+            GetL1AcceptanceInput::Block {
+                block_id: BlockId::Latest
+            }
+        );
+
+        let transaction = Params::new(Some(r#"{"transaction_hash": "0xabc"}"#));
+        assert_eq!(
+            transaction.parse::<GetL1AcceptanceInput>().unwrap(),
+            GetL1AcceptanceInput::Transaction {
+                transaction_hash: StarknetTransactionHash(crate::starkhash!("0abc"))
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn accepted() {
+        let context = RpcContext::for_tests();
+        let origin = EthOrigin {
+            block: BlockOrigin {
+                hash: EthereumBlockHash(H256::from_low_u64_be(1)),
+                number: EthereumBlockNumber(100),
+            },
+            transaction: TransactionOrigin {
+                hash: EthereumTransactionHash(H256::from_low_u64_be(2)),
+                index: EthereumTransactionIndex(3),
+            },
+            log_index: EthereumLogIndex(4),
+        };
+
+        let mut connection = context.storage.connection().unwrap();
+        let tx = connection.transaction().unwrap();
+        let genesis = StarknetBlocksTable::get(&tx, StarknetBlockNumber::GENESIS.into())
+            .unwrap()
+            .unwrap();
+        L1StateTable::upsert(
+            &tx,
+            &StateUpdateLog {
+                origin: origin.clone(),
+                global_root: genesis.root,
+                block_number: genesis.number,
+            },
+        )
+        .unwrap();
+        tx.commit().unwrap();
+
+        // The update is only trusted once the L1-L2 head reaches the block.
+        let input = || GetL1AcceptanceInput::Transaction {
+            transaction_hash: StarknetTransactionHash(starkhash_bytes!(b"txn 0")),
+        };
+        let result = get_l1_acceptance(context.clone(), input()).await.unwrap();
+        assert_eq!(result.block_hash, genesis.hash);
+        assert_eq!(result.l1_reference, None);
+        assert_eq!(result.latest_accepted_block_number, None);
+
+        let tx = connection.transaction().unwrap();
+        RefsTable::set_l1_l2_head(&tx, Some(StarknetBlockNumber::GENESIS)).unwrap();
+        tx.commit().unwrap();
+
+        let result = get_l1_acceptance(context, input()).await.unwrap();
+        assert_eq!(
+            result,
+            L1Acceptance {
+                block_hash: genesis.hash,
+                block_number: StarknetBlockNumber::GENESIS,
+                l1_reference: Some(L1Reference::new(&origin, L1Finality::Unconfirmed)),
+                latest_accepted_block_number: Some(StarknetBlockNumber::GENESIS),
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn not_found() {
+        let context = RpcContext::for_tests();
+
+        let input = GetL1AcceptanceInput::Block {
+            block_id: BlockId::Pending,
+        };
+        let result = get_l1_acceptance(context.clone(), input).await;
+        assert_matches!(result, Err(GetL1AcceptanceError::BlockNotFound));
+
+        let input = GetL1AcceptanceInput::Transaction {
+            transaction_hash: StarknetTransactionHash(starkhash_bytes!(b"invalid")),
+        };
+        let result = get_l1_acceptance(context, input).await;
+        assert_matches!(result, Err(GetL1AcceptanceError::TxnHashNotFound));
+    }
+}
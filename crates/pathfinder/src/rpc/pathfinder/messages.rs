@@ -0,0 +1,427 @@
+use anyhow::Context;
+use rusqlite::Transaction;
+use stark_hash::StarkHash;
+use web3::types::H256;
+
+use crate::core::{MessageHash, StarknetBlockNumber, StarknetTransactionHash};
+use crate::ethereum::log::MessageLog;
+use crate::rpc::v02::openrpc::{enumeration, hex, Object, Schema};
+use crate::rpc::v02::RpcContext;
+use crate::storage::{L1MessagesTable, L2Message, L2MessagesTable, MessageDirection, RefsTable};
+
+crate::rpc::error::generate_rpc_error_subset!(GetMessageStatusError:);
+crate::rpc::error::generate_rpc_error_subset!(GetMessagesByAddressError: PageSizeTooBig);
+
+/// The number of messages returned by `pathfinder_getMessagesByAddress` if no limit is given.
+const DEFAULT_LIMIT: usize = 100;
+/// The maximum number of messages returned by `pathfinder_getMessagesByAddress`.
+const MAX_LIMIT: usize = 1000;
+
+#[derive(serde::Serialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum Direction {
+    L1ToL2,
+    L2ToL1,
+}
+
+impl Schema for Direction {
+    fn schema() -> serde_json::Value {
+        enumeration::<Self>(&["L1_TO_L2", "L2_TO_L1"])
+    }
+}
+
+/// The progress of a message, in the order a message goes through them.
+///
+/// L1 to L2 messages are `SENT_ON_L1`, `CONSUMED_ON_L2` once their L1 handler is executed and
+/// `ACCEPTED_ON_L1` once the state update consuming them is accepted on L1.
+///
+/// L2 to L1 messages are `SENT_ON_L2`, `ACCEPTED_ON_L1` once their block is accepted on L1, at
+/// which point they can be consumed, and `CONSUMED_ON_L1` once the recipient consumed them.
+#[derive(serde::Serialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum Status {
+    SentOnL1,
+    ConsumedOnL2,
+    SentOnL2,
+    AcceptedOnL1,
+    ConsumedOnL1,
+}
+
+impl Schema for Status {
+    fn schema() -> serde_json::Value {
+        enumeration::<Self>(&[
+            "SENT_ON_L1",
+            "CONSUMED_ON_L2",
+            "SENT_ON_L2",
+            "ACCEPTED_ON_L1",
+            "CONSUMED_ON_L1",
+        ])
+    }
+}
+
+#[derive(serde::Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct MessageStatus {
+    pub message_hash: MessageHash,
+    pub direction: Direction,
+    pub status: Status,
+    pub from_address: H256,
+    pub to_address: H256,
+    /// The L1 transaction which sent (L1 to L2) or consumed (L2 to L1) the message.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub l1_transaction_hash: Option<H256>,
+    /// The L2 transaction which consumed (L1 to L2) or sent (L2 to L1) the message.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub l2_transaction_hash: Option<StarknetTransactionHash>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub l2_block_number: Option<StarknetBlockNumber>,
+}
+
+impl Schema for MessageStatus {
+    fn schema() -> serde_json::Value {
+        Object::new::<Self>()
+            .field::<MessageHash>("message_hash")
+            .field::<Direction>("direction")
+            .field::<Status>("status")
+            .field_as("from_address", hex(), true)
+            .field_as("to_address", hex(), true)
+            .field_as("l1_transaction_hash", hex(), false)
+            .field::<Option<StarknetTransactionHash>>("l2_transaction_hash")
+            .field::<Option<StarknetBlockNumber>>("l2_block_number")
+            .build()
+    }
+}
+
+#[derive(serde::Deserialize, Debug, PartialEq, Eq)]
+pub struct GetMessageStatusInput {
+    message_hash: MessageHash,
+}
+
+/// Returns the status of the message, or [None] if it is unknown.
+pub async fn get_message_status(
+    context: RpcContext,
+    input: GetMessageStatusInput,
+) -> Result<Option<MessageStatus>, GetMessageStatusError> {
+    let span = tracing::Span::current();
+    let jh = tokio::task::spawn_blocking(move || -> Result<_, GetMessageStatusError> {
+        let _g = span.enter();
+        let mut db = context
+            .storage
+            .connection()
+            .context("Opening database connection")?;
+        let tx = db.transaction().context("Creating database transaction")?;
+
+        read_status(&tx, input.message_hash).map_err(Into::into)
+    });
+
+    jh.await.context("Database read panic or shutting down")?
+}
+
+#[derive(serde::Deserialize, Debug, PartialEq, Eq)]
+pub struct GetMessagesByAddressInput {
+    /// A StarkNet contract or an Ethereum address.
+    address: StarkHash,
+    limit: Option<usize>,
+}
+
+/// Returns the status of the most recent messages sent or received by the address, newest
+/// first.
+pub async fn get_messages_by_address(
+    context: RpcContext,
+    input: GetMessagesByAddressInput,
+) -> Result<Vec<MessageStatus>, GetMessagesByAddressError> {
+    let limit = input.limit.unwrap_or(DEFAULT_LIMIT);
+    if limit > MAX_LIMIT {
+        return Err(GetMessagesByAddressError::PageSizeTooBig);
+    }
+    let address = H256(input.address.to_be_bytes());
+
+    let span = tracing::Span::current();
+    let jh = tokio::task::spawn_blocking(move || -> Result<_, GetMessagesByAddressError> {
+        let _g = span.enter();
+        let mut db = context
+            .storage
+            .connection()
+            .context("Opening database connection")?;
+        let tx = db.transaction().context("Creating database transaction")?;
+
+        let mut hashes =
+            L2MessagesTable::get_by_address(&tx, address, limit).context("Reading L2 messages")?;
+        // Messages from L1 which were not consumed yet are only known on L1.
+        for hash in L1MessagesTable::get_sent_by_address(&tx, address, limit)
+            .context("Reading L1 messages")?
+        {
+            if !hashes.contains(&hash) {
+                hashes.push(hash);
+            }
+        }
+        hashes.truncate(limit);
+
+        let mut statuses = Vec::with_capacity(hashes.len());
+        for hash in hashes {
+            if let Some(status) = read_status(&tx, hash)? {
+                statuses.push(status);
+            }
+        }
+        Ok(statuses)
+    });
+
+    jh.await.context("Database read panic or shutting down")?
+}
+
+fn read_status(tx: &Transaction<'_>, hash: MessageHash) -> anyhow::Result<Option<MessageStatus>> {
+    let logs = L1MessagesTable::get_by_hash(tx, hash).context("Reading L1 messages")?;
+    let messages = L2MessagesTable::get_by_hash(tx, hash).context("Reading L2 messages")?;
+    let l1_l2_head = RefsTable::get_l1_l2_head(tx).context("Reading L1-L2 head")?;
+
+    Ok(status(hash, &logs, &messages, l1_l2_head))
+}
+
+/// Determines the status of a message from its L1 logs and L2 occurences.
+///
+/// Identical L2 to L1 messages share a hash, in which case the oldest one is reported.
+fn status(
+    hash: MessageHash,
+    logs: &[MessageLog],
+    messages: &[L2Message],
+    l1_l2_head: Option<StarknetBlockNumber>,
+) -> Option<MessageStatus> {
+    let message = messages.first();
+    let direction = match (message, logs.first()) {
+        (Some(message), _) => match message.direction {
+            MessageDirection::L1ToL2 => Direction::L1ToL2,
+            MessageDirection::L2ToL1 => Direction::L2ToL1,
+        },
+        (None, Some(MessageLog::ConsumedToL1(_))) => Direction::L2ToL1,
+        (None, Some(_)) => Direction::L1ToL2,
+        (None, None) => return None,
+    };
+
+    let mut status = MessageStatus {
+        message_hash: hash,
+        direction,
+        status: Status::SentOnL1,
+        from_address: H256::zero(),
+        to_address: H256::zero(),
+        l1_transaction_hash: None,
+        l2_transaction_hash: message.map(|message| message.transaction_hash),
+        l2_block_number: message.map(|message| message.block_number),
+    };
+    if let Some(message) = message {
+        status.from_address = message.from_address;
+        status.to_address = message.to_address;
+    }
+
+    match direction {
+        Direction::L1ToL2 => {
+            let sent = logs.iter().find_map(|log| match log {
+                MessageLog::ToL2(log) => Some(log),
+                _ => None,
+            });
+            if let Some(sent) = sent {
+                status.from_address = sent.from_address.to_word();
+                status.to_address = sent.to_address;
+                status.l1_transaction_hash = Some(sent.origin.transaction.hash.0);
+            } else if let (None, Some(MessageLog::ConsumedToL2(consumed))) = (message, logs.first())
+            {
+                status.from_address = consumed.from_address.to_word();
+                status.to_address = consumed.to_address;
+            }
+
+            let consumed = logs
+                .iter()
+                .any(|log| matches!(log, MessageLog::ConsumedToL2(_)));
+            status.status = match (consumed, message) {
+                (true, _) => Status::AcceptedOnL1,
+                (false, Some(_)) => Status::ConsumedOnL2,
+                (false, None) => Status::SentOnL1,
+            };
+        }
+        Direction::L2ToL1 => {
+            let consumed = logs.iter().find_map(|log| match log {
+                MessageLog::ConsumedToL1(log) => Some(log),
+                _ => None,
+            });
+            status.status = match consumed {
+                Some(consumed) => {
+                    status.from_address = consumed.from_address;
+                    status.to_address = consumed.to_address.to_word();
+                    status.l1_transaction_hash = Some(consumed.origin.transaction.hash.0);
+                    Status::ConsumedOnL1
+                }
+                None => match (message, l1_l2_head) {
+                    (Some(message), Some(head)) if message.block_number <= head => {
+                        Status::AcceptedOnL1
+                    }
+                    _ => Status::SentOnL2,
+                },
+            };
+        }
+    }
+
+    Some(status)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{
+        EthereumAddress, EthereumBlockHash, EthereumBlockNumber, EthereumLogIndex,
+        EthereumTransactionHash, EthereumTransactionIndex,
+    };
+    use crate::ethereum::log::{ConsumedMessageToL1Log, ConsumedMessageToL2Log, MessageToL2Log};
+    use crate::ethereum::{BlockOrigin, EthOrigin, TransactionOrigin};
+    use assert_matches::assert_matches;
+    use web3::types::{H160, U256};
+
+    const HASH: MessageHash = MessageHash(H256([7u8; 32]));
+
+    fn origin(transaction: u64) -> EthOrigin {
+        EthOrigin {
+            block: BlockOrigin {
+                hash: EthereumBlockHash(H256::from_low_u64_be(1)),
+                number: EthereumBlockNumber(1),
+            },
+            transaction: TransactionOrigin {
+                hash: EthereumTransactionHash(H256::from_low_u64_be(transaction)),
+                index: EthereumTransactionIndex(0),
+            },
+            log_index: EthereumLogIndex(0),
+        }
+    }
+
+    fn l2_message(direction: MessageDirection, block_number: u64) -> L2Message {
+        L2Message {
+            hash: HASH,
+            direction,
+            from_address: H256::from_low_u64_be(0x10),
+            to_address: H256::from_low_u64_be(0x20),
+            payload: vec![],
+            block_number: StarknetBlockNumber::new_or_panic(block_number),
+            transaction_hash: StarknetTransactionHash(StarkHash::from_u64(0x30)),
+        }
+    }
+
+    fn sent_to_l2() -> MessageLog {
+        MessageToL2Log {
+            origin: origin(0x40),
+            hash: HASH,
+            from_address: EthereumAddress(H160::from_low_u64_be(0x10)),
+            to_address: H256::from_low_u64_be(0x20),
+            selector: H256::zero(),
+            payload: vec![],
+            nonce: H256::zero(),
+            fee: U256::zero(),
+        }
+        .into()
+    }
+
+    fn consumed_on_l1() -> MessageLog {
+        ConsumedMessageToL1Log {
+            origin: origin(0x50),
+            hash: HASH,
+            from_address: H256::from_low_u64_be(0x20),
+            to_address: EthereumAddress(H160::from_low_u64_be(0x10)),
+            payload: vec![],
+        }
+        .into()
+    }
+
+    #[test]
+    fn unknown() {
+        assert_eq!(status(HASH, &[], &[], None), None);
+    }
+
+    #[test]
+    fn l1_to_l2() {
+        let logs = [sent_to_l2()];
+        let result = status(HASH, &logs, &[], None).unwrap();
+        assert_eq!(result.direction, Direction::L1ToL2);
+        assert_eq!(result.status, Status::SentOnL1);
+        assert_eq!(result.from_address, H256::from_low_u64_be(0x10));
+        assert_eq!(
+            result.l1_transaction_hash,
+            Some(H256::from_low_u64_be(0x40))
+        );
+        assert_eq!(result.l2_transaction_hash, None);
+
+        let messages = [l2_message(MessageDirection::L1ToL2, 3)];
+        let result = status(HASH, &logs, &messages, None).unwrap();
+        assert_eq!(result.status, Status::ConsumedOnL2);
+        assert_eq!(
+            result.l2_block_number,
+            Some(StarknetBlockNumber::new_or_panic(3))
+        );
+
+        let logs = [
+            sent_to_l2(),
+            ConsumedMessageToL2Log {
+                origin: origin(0x60),
+                hash: HASH,
+                from_address: EthereumAddress(H160::from_low_u64_be(0x10)),
+                to_address: H256::from_low_u64_be(0x20),
+                selector: H256::zero(),
+                payload: vec![],
+                nonce: H256::zero(),
+            }
+            .into(),
+        ];
+        let result = status(HASH, &logs, &messages, None).unwrap();
+        assert_eq!(result.status, Status::AcceptedOnL1);
+        assert_eq!(
+            result.l1_transaction_hash,
+            Some(H256::from_low_u64_be(0x40))
+        );
+    }
+
+    #[test]
+    fn l2_to_l1() {
+        let messages = [l2_message(MessageDirection::L2ToL1, 3)];
+        let result = status(HASH, &[], &messages, None).unwrap();
+        assert_eq!(result.direction, Direction::L2ToL1);
+        assert_eq!(result.status, Status::SentOnL2);
+
+        let head = Some(StarknetBlockNumber::new_or_panic(2));
+        let result = status(HASH, &[], &messages, head).unwrap();
+        assert_eq!(result.status, Status::SentOnL2);
+
+        let head = Some(StarknetBlockNumber::new_or_panic(3));
+        let result = status(HASH, &[], &messages, head).unwrap();
+        assert_eq!(result.status, Status::AcceptedOnL1);
+
+        let result = status(HASH, &[consumed_on_l1()], &messages, head).unwrap();
+        assert_eq!(result.status, Status::ConsumedOnL1);
+        assert_eq!(
+            result.l1_transaction_hash,
+            Some(H256::from_low_u64_be(0x50))
+        );
+        assert_eq!(result.to_address, H256::from_low_u64_be(0x10));
+    }
+
+    #[test]
+    fn serialization() {
+        let result = status(HASH, &[sent_to_l2()], &[], None).unwrap();
+        let json = serde_json::to_value(&result).unwrap();
+        assert_eq!(json["direction"], "L1_TO_L2");
+        assert_eq!(json["status"], "SENT_ON_L1");
+        assert!(json.get("l2_transaction_hash").is_none());
+    }
+
+    #[tokio::test]
+    async fn unknown_message() {
+        let context = RpcContext::for_tests();
+        let input = GetMessageStatusInput { message_hash: HASH };
+        let result = get_message_status(context, input).await.unwrap();
+        assert_eq!(result, None);
+    }
+
+    #[tokio::test]
+    async fn limit_too_big() {
+        let context = RpcContext::for_tests();
+        let input = GetMessagesByAddressInput {
+            address: StarkHash::from_u64(0x10),
+            limit: Some(MAX_LIMIT + 1),
+        };
+        let result = get_messages_by_address(context, input).await;
+        assert_matches!(result, Err(GetMessagesByAddressError::PageSizeTooBig));
+    }
+}
@@ -0,0 +1,142 @@
+//! Blocks, state updates and classes in the sequencer's format, as served by the
+//! [feeder gateway API](crate::feeder_gateway).
+//!
+//! The v0.2 API leaves out data which is needed to verify and store blocks, which is why another
+//! node syncing from this one uses these methods, see
+//! [JsonRpcClient](crate::sequencer::JsonRpcClient).
+use serde_json::value::RawValue;
+
+use crate::core::{BlockId, ClassHash};
+use crate::feeder_gateway::{self, Error};
+use crate::rpc::v02::openrpc::Schema;
+use crate::rpc::v02::RpcContext;
+use crate::sequencer::error::StarknetErrorCode;
+
+crate::rpc::error::generate_rpc_error_subset!(GetSequencerBlockError: BlockNotFound);
+crate::rpc::error::generate_rpc_error_subset!(GetClassDefinitionError: ClassHashNotFound);
+
+#[derive(serde::Deserialize, Debug, PartialEq, Eq)]
+pub struct GetSequencerBlockInput {
+    block_id: BlockId,
+}
+
+#[derive(serde::Deserialize, Debug, PartialEq, Eq)]
+pub struct GetClassDefinitionInput {
+    class_hash: ClassHash,
+}
+
+/// A reply of the sequencer's feeder gateway, passed on as is.
+#[derive(serde::Serialize, Debug)]
+#[serde(transparent)]
+pub struct SequencerReply(Box<RawValue>);
+
+impl Schema for SequencerReply {
+    fn schema() -> serde_json::Value {
+        serde_json::json!({
+            "title": "SEQUENCER_REPLY",
+            "description": "The reply of the sequencer's feeder gateway",
+            "type": "object",
+        })
+    }
+}
+
+impl SequencerReply {
+    fn from_json(json: Vec<u8>) -> anyhow::Result<Self> {
+        let json = String::from_utf8(json)?;
+
+        Ok(Self(RawValue::from_string(json)?))
+    }
+}
+
+fn feeder_gateway_context(context: RpcContext) -> feeder_gateway::Context {
+    feeder_gateway::Context::new(
+        context.storage,
+        context.chain,
+        context.pending_data.unwrap_or_default(),
+    )
+}
+
+/// Maps the error reported with `code` to `mapped`, and all others to internal errors.
+fn map_error<E: From<anyhow::Error>>(error: Error, code: StarknetErrorCode, mapped: E) -> E {
+    match error {
+        Error::Starknet(e) if e.code == code => mapped,
+        Error::Starknet(e) => anyhow::anyhow!("{:?}: {}", e.code, e.message).into(),
+        Error::Internal(e) => e.into(),
+    }
+}
+
+/// Returns the block like the sequencer's `get_block`, including its transaction receipts.
+pub async fn get_sequencer_block(
+    context: RpcContext,
+    input: GetSequencerBlockInput,
+) -> Result<SequencerReply, GetSequencerBlockError> {
+    let context = feeder_gateway_context(context);
+    let block = feeder_gateway::block(&context, input.block_id)
+        .await
+        .map_err(|e| {
+            map_error(
+                e,
+                StarknetErrorCode::BlockNotFound,
+                GetSequencerBlockError::BlockNotFound,
+            )
+        })?;
+
+    Ok(SequencerReply::from_json(block)?)
+}
+
+/// Returns the block's state update like the sequencer's `get_state_update`.
+pub async fn get_sequencer_state_update(
+    context: RpcContext,
+    input: GetSequencerBlockInput,
+) -> Result<SequencerReply, GetSequencerBlockError> {
+    let context = feeder_gateway_context(context);
+    let state_update = feeder_gateway::state_update(&context, input.block_id)
+        .await
+        .map_err(|e| {
+            map_error(
+                e,
+                StarknetErrorCode::BlockNotFound,
+                GetSequencerBlockError::BlockNotFound,
+            )
+        })?;
+
+    Ok(SequencerReply::from_json(state_update)?)
+}
+
+/// Returns the class definition as it was declared, unlike `starknet_getClass`.
+pub async fn get_class_definition(
+    context: RpcContext,
+    input: GetClassDefinitionInput,
+) -> Result<SequencerReply, GetClassDefinitionError> {
+    let context = feeder_gateway_context(context);
+    let definition = feeder_gateway::class_definition(&context, input.class_hash)
+        .await
+        .map_err(|e| {
+            map_error(
+                e,
+                StarknetErrorCode::UndeclaredClass,
+                GetClassDefinitionError::ClassHashNotFound,
+            )
+        })?;
+
+    Ok(SequencerReply::from_json(definition)?)
+}
+
+/// Returns the compiled class definition of a Sierra class.
+pub async fn get_compiled_class_definition(
+    context: RpcContext,
+    input: GetClassDefinitionInput,
+) -> Result<SequencerReply, GetClassDefinitionError> {
+    let context = feeder_gateway_context(context);
+    let definition = feeder_gateway::compiled_class_definition(&context, input.class_hash)
+        .await
+        .map_err(|e| {
+            map_error(
+                e,
+                StarknetErrorCode::UndeclaredClass,
+                GetClassDefinitionError::ClassHashNotFound,
+            )
+        })?;
+
+    Ok(SequencerReply::from_json(definition)?)
+}
@@ -308,7 +308,7 @@ pub(crate) fn starkhash_to_dec_str(h: &StarkHash) -> String {
 }
 
 /// A helper conversion function. Only use with __sequencer API related types__.
-fn starkhash_from_dec_str(s: &str) -> Result<StarkHash, anyhow::Error> {
+pub(crate) fn starkhash_from_dec_str(s: &str) -> Result<StarkHash, anyhow::Error> {
     // TODO remove fallback to hex string representation once mainnet moves to cairo-0.8.0
     match BigUint::from_str(s) {
         Ok(b) => {
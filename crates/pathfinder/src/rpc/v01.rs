@@ -39,6 +39,7 @@ impl<Context: Send + Sync + 'static> RpcModuleWrapper<Context> {
 
         metrics::register_counter!("rpc_method_calls_total", "method" => method_name);
         metrics::register_counter!("rpc_method_calls_failed_total", "method" => method_name);
+        metrics::register_histogram!("rpc_method_call_duration_seconds", "method" => method_name);
 
         self.0.register_async_method(method_name, move |p, c| {
             // why info here? it's the same used in warp tracing filter for example.
@@ -2994,5 +2995,11 @@ mod tests {
 
         assert_eq!(get_all(), 3);
         assert_eq!(get_failed(), 1);
+
+        let durations = handle.get_histogram_values_by_label(
+            "rpc_method_call_duration_seconds",
+            [("method", "starknet_getBlockWithTxHashes")],
+        );
+        assert_eq!(durations.len(), 3);
     }
 }
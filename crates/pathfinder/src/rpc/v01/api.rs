@@ -21,7 +21,7 @@ use crate::{
         StarknetTransactionHash, StarknetTransactionIndex, StorageAddress, StorageValue,
         TransactionNonce, TransactionSignatureElem, TransactionVersion,
     },
-    rpc::gas_price,
+    rpc::{cache::ResponseCache, gas_price},
     sequencer::{self, request::add_transaction::ContractDefinition, ClientApi},
     state::{state_tree::GlobalStateTree, PendingData, SyncState},
     storage::{
@@ -51,6 +51,8 @@ pub struct RpcApi {
     pub shared_gas_price: Option<gas_price::Cached>,
     pub sync_state: Arc<SyncState>,
     pub pending_data: Option<PendingData>,
+    /// Only used by the v0.2 methods.
+    pub response_cache: ResponseCache,
 }
 
 #[derive(Debug)]
@@ -89,6 +91,7 @@ impl RpcApi {
             shared_gas_price: None,
             sync_state,
             pending_data: None,
+            response_cache: ResponseCache::default(),
         }
     }
 
@@ -113,6 +116,13 @@ impl RpcApi {
         }
     }
 
+    pub fn with_response_cache(self, response_cache: ResponseCache) -> Self {
+        Self {
+            response_cache,
+            ..self
+        }
+    }
+
     /// Returns [PendingData]; errors if [RpcApi] was not configured with one.
     ///
     /// This is useful for queries to access pending data or return an error via `?` if it
@@ -1345,9 +1355,10 @@ impl RpcApi {
                 max_fee,
                 signature,
                 nonce,
-                contract_class,
+                contract_class.into(),
                 // actual address dumped from a `starknet declare` call
                 sender_address,
+                None,
                 token,
             )
             .await?;
@@ -1499,6 +1510,9 @@ impl From<ext_py::CallFailure> for jsonrpsee::core::Error {
             NoSuchContract => Error::from(ErrorCode::ContractNotFound),
             InvalidEntryPoint => Error::from(ErrorCode::InvalidMessageSelector),
             ExecutionFailed(e) => internal_server_error(e),
+            Timeout => crate::rpc::error::RpcError::ExecutionTimeout.into(),
+            Overloaded => crate::rpc::error::RpcError::Overloaded.into(),
+            UnsupportedChain => internal_server_error("Calls are not supported on custom chains"),
             // Intentionally hide the message under Internal
             Internal(_) | Shutdown => static_internal_server_error(),
         }
@@ -512,9 +512,12 @@ pub mod reply {
                             })
                         })
                         .collect(),
+                    // Sierra classes are listed alongside Cairo 0.x classes, their compiled
+                    // class hashes are stored with the classes themselves.
                     declared_contracts: x
                         .declared_contracts
                         .into_iter()
+                        .chain(x.declared_classes.into_iter().map(|class| class.class_hash))
                         .map(|class_hash| DeclaredContract { class_hash })
                         .collect(),
                     deployed_contracts: x
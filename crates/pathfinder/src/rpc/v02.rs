@@ -1,13 +1,17 @@
 use std::sync::Arc;
 
+use super::cache::ResponseCache;
 use super::error::RpcError;
 use crate::cairo::ext_py;
 use crate::rpc::gas_price;
 use crate::{core::Chain, state::SyncState};
 use crate::{state::PendingData, storage::Storage};
 
-mod common;
+pub(crate) mod common;
+#[cfg(test)]
+mod conformance;
 pub mod method;
+pub(crate) mod openrpc;
 pub mod types;
 
 type SequencerClient = crate::sequencer::Client;
@@ -21,6 +25,7 @@ pub struct RpcContext {
     pub call_handle: Option<ext_py::Handle>,
     pub eth_gas_price: Option<gas_price::Cached>,
     pub sequencer: SequencerClient,
+    pub cache: ResponseCache,
 }
 
 impl RpcContext {
@@ -38,6 +43,7 @@ impl RpcContext {
             call_handle: None,
             eth_gas_price: None,
             sequencer,
+            cache: ResponseCache::default(),
         }
     }
 
@@ -89,6 +95,10 @@ impl RpcContext {
             ..self
         }
     }
+
+    pub fn with_response_cache(self, cache: ResponseCache) -> Self {
+        Self { cache, ..self }
+    }
 }
 
 // FIXME
@@ -104,24 +114,26 @@ impl From<&super::v01::api::RpcApi> for RpcContext {
             call_handle: v01.call_handle.clone(),
             eth_gas_price: v01.shared_gas_price.clone(),
             sequencer: v01.sequencer.clone(),
+            cache: v01.response_cache.clone(),
         }
     }
 }
 
-/// Registers a JSON-RPC method with the [RpcModule<RpcContext>](jsonrpsee::RpcModule).
+/// Registers a JSON-RPC method with the [RpcModule<RpcContext>](jsonrpsee::RpcModule), returning
+/// its description for `rpc.discover`.
 ///
 /// An example signature for `method` is:
 /// ```ignore
 /// async fn method(context: RpcContext, input: Input) -> Result<Ouput, Error>
 /// ```
-fn register_method<Input, Output, Error, MethodFuture, Method>(
+pub(crate) fn register_method<Input, Output, Error, MethodFuture, Method>(
     module: &mut jsonrpsee::RpcModule<RpcContext>,
     method_name: &'static str,
     method: Method,
-) -> anyhow::Result<()>
+) -> anyhow::Result<openrpc::Method>
 where
     Input: ::serde::de::DeserializeOwned + Send + Sync,
-    Output: 'static + ::serde::Serialize + Send + Sync,
+    Output: 'static + ::serde::Serialize + openrpc::Schema + Send + Sync,
     Error: Into<RpcError>,
     MethodFuture: std::future::Future<Output = Result<Output, Error>> + Send,
     Method: (Fn(RpcContext, Input) -> MethodFuture) + Copy + Send + Sync + 'static,
@@ -131,6 +143,7 @@ where
     use tracing::Instrument;
 
     metrics::register_counter!("rpc_method_calls_total", "method" => method_name);
+    metrics::register_histogram!("rpc_method_call_duration_seconds", "method" => method_name);
 
     let method_callback = move |params: Params<'static>, context: Arc<RpcContext>| {
         // why info here? it's the same used in warp tracing filter for example.
@@ -149,10 +162,11 @@ where
         .register_async_method(method_name, method_callback)
         .with_context(|| format!("Registering {method_name}"))?;
 
-    Ok(())
+    Ok(openrpc::Method::new::<Input, Output>(method_name))
 }
 
-/// Registers a JSON-RPC method with the [RpcModule<RpcContext>](jsonrpsee::RpcModule).
+/// Registers a JSON-RPC method with the [RpcModule<RpcContext>](jsonrpsee::RpcModule), returning
+/// its description for `rpc.discover`.
 ///
 /// An example signature for `method` is:
 /// ```ignore
@@ -162,9 +176,9 @@ fn register_method_with_no_input<Output, Error, MethodFuture, Method>(
     module: &mut jsonrpsee::RpcModule<RpcContext>,
     method_name: &'static str,
     method: Method,
-) -> anyhow::Result<()>
+) -> anyhow::Result<openrpc::Method>
 where
-    Output: 'static + ::serde::Serialize + Send + Sync,
+    Output: 'static + ::serde::Serialize + openrpc::Schema + Send + Sync,
     Error: Into<RpcError>,
     MethodFuture: std::future::Future<Output = Result<Output, Error>> + Send,
     Method: (Fn(RpcContext) -> MethodFuture) + Copy + Send + Sync + 'static,
@@ -173,6 +187,7 @@ where
     use tracing::Instrument;
 
     metrics::register_counter!("rpc_method_calls_total", "method" => method_name);
+    metrics::register_histogram!("rpc_method_call_duration_seconds", "method" => method_name);
 
     let method_callback = move |_params, context: Arc<RpcContext>| {
         // why info here? it's the same used in warp tracing filter for example.
@@ -190,110 +205,111 @@ where
         .register_async_method(method_name, method_callback)
         .with_context(|| format!("Registering {method_name}"))?;
 
-    Ok(())
+    Ok(openrpc::Method::without_params::<Output>(method_name))
 }
 
 // Registers all methods for the v0.2 API
 pub fn register_all_methods(module: &mut jsonrpsee::RpcModule<RpcContext>) -> anyhow::Result<()> {
-    register_method(module, "starknet_call", method::call::call)?;
-    register_method_with_no_input(module, "starknet_chainId", method::chain_id::chain_id)?;
-    register_method(
-        module,
-        "starknet_getBlockWithTxHashes",
-        method::get_block::get_block_with_tx_hashes,
-    )?;
-    register_method(
-        module,
-        "starknet_getBlockWithTxs",
-        method::get_block::get_block_with_txs,
-    )?;
-    register_method(module, "starknet_getClass", method::get_class::get_class)?;
-    register_method(
-        module,
-        "starknet_getClassAt",
-        method::get_class_at::get_class_at,
-    )?;
-    register_method(
-        module,
-        "starknet_getClassHashAt",
-        method::get_class_hash_at::get_class_hash_at,
-    )?;
-    register_method(module, "starknet_getEvents", method::get_events::get_events)?;
-    register_method(
-        module,
-        "starknet_estimateFee",
-        method::estimate_fee::estimate_fee,
-    )?;
-    register_method(module, "starknet_getNonce", method::get_nonce::get_nonce)?;
-    register_method_with_no_input(
-        module,
-        "starknet_pendingTransactions",
-        method::pending_transactions::pending_transactions,
-    )?;
-    register_method(
-        module,
-        "starknet_getStateUpdate",
-        method::get_state_update::get_state_update,
-    )?;
-    register_method(
-        module,
-        "starknet_getStorageAt",
-        method::get_storage_at::get_storage_at,
-    )?;
-    register_method(
-        module,
-        "starknet_getTransactionByHash",
-        method::get_transaction_by_hash::get_transaction_by_hash,
-    )?;
-    register_method(
-        module,
-        "starknet_getTransactionByBlockIdAndIndex",
-        method::get_transaction_by_block_id_and_index::get_transaction_by_block_id_and_index,
-    )?;
-    register_method(
-        module,
-        "starknet_getTransactionReceipt",
-        method::get_transaction_receipt::get_transaction_receipt,
-    )?;
-    register_method_with_no_input(module, "starknet_syncing", method::syncing::syncing)?;
-    register_method(
-        module,
-        "starknet_getBlockTransactionCount",
-        method::get_block_transaction_count::get_block_transaction_count,
-    )?;
-    register_method_with_no_input(
-        module,
-        "starknet_blockHashAndNumber",
-        method::block_hash_and_number::block_hash_and_number,
-    )?;
-    register_method_with_no_input(
-        module,
-        "starknet_blockNumber",
-        method::block_hash_and_number::block_number,
-    )?;
-
-    register_method(
-        module,
-        "starknet_addInvokeTransaction",
-        method::add_invoke_transaction::add_invoke_transaction,
-    )?;
-    register_method(
-        module,
-        "starknet_addDeclareTransaction",
-        method::add_declare_transaction::add_declare_transaction,
-    )?;
-    register_method(
-        module,
-        "starknet_addDeployTransaction",
-        method::add_deploy_transaction::add_deploy_transaction,
-    )?;
-    register_method(
-        module,
-        "starknet_addDeployAccountTransaction",
-        method::add_deploy_account_transaction::add_deploy_account_transaction,
-    )?;
+    let methods = vec![
+        register_method(module, "starknet_call", method::call::call)?,
+        register_method_with_no_input(module, "starknet_chainId", method::chain_id::chain_id)?,
+        register_method(
+            module,
+            "starknet_getBlockWithTxHashes",
+            method::get_block::get_block_with_tx_hashes,
+        )?,
+        register_method(
+            module,
+            "starknet_getBlockWithTxs",
+            method::get_block::get_block_with_txs,
+        )?,
+        register_method(module, "starknet_getClass", method::get_class::get_class)?,
+        register_method(
+            module,
+            "starknet_getClassAt",
+            method::get_class_at::get_class_at,
+        )?,
+        register_method(
+            module,
+            "starknet_getClassHashAt",
+            method::get_class_hash_at::get_class_hash_at,
+        )?,
+        register_method(module, "starknet_getEvents", method::get_events::get_events)?,
+        register_method(
+            module,
+            "starknet_estimateFee",
+            method::estimate_fee::estimate_fee,
+        )?,
+        register_method(module, "starknet_getNonce", method::get_nonce::get_nonce)?,
+        register_method_with_no_input(
+            module,
+            "starknet_pendingTransactions",
+            method::pending_transactions::pending_transactions,
+        )?,
+        register_method(
+            module,
+            "starknet_getStateUpdate",
+            method::get_state_update::get_state_update,
+        )?,
+        register_method(
+            module,
+            "starknet_getStorageAt",
+            method::get_storage_at::get_storage_at,
+        )?,
+        register_method(
+            module,
+            "starknet_getTransactionByHash",
+            method::get_transaction_by_hash::get_transaction_by_hash,
+        )?,
+        register_method(
+            module,
+            "starknet_getTransactionByBlockIdAndIndex",
+            method::get_transaction_by_block_id_and_index::get_transaction_by_block_id_and_index,
+        )?,
+        register_method(
+            module,
+            "starknet_getTransactionReceipt",
+            method::get_transaction_receipt::get_transaction_receipt,
+        )?,
+        register_method_with_no_input(module, "starknet_syncing", method::syncing::syncing)?,
+        register_method(
+            module,
+            "starknet_getBlockTransactionCount",
+            method::get_block_transaction_count::get_block_transaction_count,
+        )?,
+        register_method_with_no_input(
+            module,
+            "starknet_blockHashAndNumber",
+            method::block_hash_and_number::block_hash_and_number,
+        )?,
+        register_method_with_no_input(
+            module,
+            "starknet_blockNumber",
+            method::block_hash_and_number::block_number,
+        )?,
+        register_method(
+            module,
+            "starknet_addInvokeTransaction",
+            method::add_invoke_transaction::add_invoke_transaction,
+        )?,
+        register_method(
+            module,
+            "starknet_addDeclareTransaction",
+            method::add_declare_transaction::add_declare_transaction,
+        )?,
+        register_method(
+            module,
+            "starknet_addDeployTransaction",
+            method::add_deploy_transaction::add_deploy_transaction,
+        )?,
+        register_method(
+            module,
+            "starknet_addDeployAccountTransaction",
+            method::add_deploy_account_transaction::add_deploy_account_transaction,
+        )?,
+    ];
 
-    Ok(())
+    openrpc::register_discover(module, "StarkNet Node API", "0.2.1-rc1", methods)
 }
 
 #[cfg(test)]
@@ -1,9 +1,9 @@
 //! Common utilities shared among [`v02`](super) methods.
 use anyhow::Context;
 
-use crate::core::StarknetBlockNumber;
-use crate::rpc::v02::types::reply::BlockStatus;
-use crate::storage::RefsTable;
+use crate::core::{BlockId, StarknetBlockNumber};
+use crate::rpc::v02::types::reply::{BlockStatus, L1Finality, L1Reference};
+use crate::storage::{L1StateTable, RefsTable, StarknetBlocksTable};
 
 /// Determines block status based on the current L1-L2 stored in the DB.
 pub fn get_block_status(
@@ -20,3 +20,73 @@ pub fn get_block_status(
 
     Ok(block_status)
 }
+
+/// Returns the L1 state update which accepted the block, or [None] if the block is not yet
+/// accepted on L1.
+pub fn get_l1_reference(
+    db_tx: &rusqlite::Transaction<'_>,
+    block_number: StarknetBlockNumber,
+) -> anyhow::Result<Option<L1Reference>> {
+    // An L1 update only accepts the block if its root matches ours, which is tracked by the L1-L2 head.
+    let l1_l2_head =
+        RefsTable::get_l1_l2_head(db_tx).context("Read latest L1 head from database")?;
+    match l1_l2_head {
+        Some(number) if number >= block_number => {}
+        _ => return Ok(None),
+    }
+
+    let update = L1StateTable::get(db_tx, block_number.into())
+        .context("Read L1 state update from database")?;
+    let update = match update {
+        Some(update) => update,
+        None => return Ok(None),
+    };
+
+    // Both are tracked by `track_finality`, and stay unknown if the L1 endpoint cannot tell.
+    let finalized = RefsTable::get_l1_finalized_block(db_tx)
+        .context("Read finalized L1 block from database")?;
+    let safe = RefsTable::get_l1_safe_block(db_tx).context("Read safe L1 block from database")?;
+    let number = update.origin.block.number.0;
+    let finality = match (finalized, safe) {
+        (Some(finalized), _) if number <= finalized.0 => L1Finality::Finalized,
+        (_, Some(safe)) if number <= safe.0 => L1Finality::Safe,
+        _ => L1Finality::Unconfirmed,
+    };
+
+    Ok(Some(L1Reference::new(&update.origin, finality)))
+}
+
+/// Whether `block_id` refers to a specific block rather than `latest` or `pending`, which makes
+/// responses about it cacheable, see [ResponseCache](crate::rpc::cache::ResponseCache).
+pub fn is_pinned(block_id: &BlockId) -> bool {
+    matches!(block_id, BlockId::Hash(_) | BlockId::Number(_))
+}
+
+/// Returns the number of the block a pinned `block_id` refers to, or [None] for `latest`,
+/// `pending` and missing blocks.
+pub fn get_pinned_block_number(
+    db_tx: &rusqlite::Transaction<'_>,
+    block_id: BlockId,
+) -> anyhow::Result<Option<StarknetBlockNumber>> {
+    match block_id {
+        BlockId::Number(number) => Ok(Some(number)),
+        BlockId::Hash(hash) => {
+            StarknetBlocksTable::get_number(db_tx, hash).context("Read block number from database")
+        }
+        BlockId::Latest | BlockId::Pending => Ok(None),
+    }
+}
+
+/// Whether a block or receipt with this L1 reference can no longer change, short of an L2 reorg.
+///
+/// This is never the case if the L1 endpoint does not support the `finalized` block tag, as
+/// [track_finality](crate::state::l1::track_finality) cannot tell which L1 blocks are final then.
+pub fn is_final(l1_reference: &Option<L1Reference>) -> bool {
+    matches!(
+        l1_reference,
+        Some(L1Reference {
+            finality: L1Finality::Finalized,
+            ..
+        })
+    )
+}
@@ -0,0 +1,484 @@
+//! Conformance of the v0.2 API to the StarkNet JSON-RPC specification.
+//!
+//! Each method is called with the [cases] against the
+//! [setup_storage](crate::rpc::tests::setup_storage) and its pending data. Results are validated
+//! against the result schemas of the specification files vendored into [SPEC_DIR] by
+//! `resources/specs/fetch.sh`, and errors against the errors specified for the method. That test
+//! is ignored until the files are committed. Results are also validated against the schemas
+//! served by `rpc.discover`. The validator supports the subset of JSON schema used by both.
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use anyhow::Context;
+use serde_json::{json, Value};
+
+use super::{register_all_methods, RpcContext};
+use crate::starkhash_bytes;
+
+/// Directory of the vendored specification files.
+const SPEC_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/resources/specs/v0.2.1-rc1");
+const SPEC_FILES: [&str; 2] = ["starknet_api_openrpc.json", "starknet_write_api.json"];
+
+/// Methods without [cases], and why.
+const UNCOVERED: [(&str, &str); 7] = [
+    ("starknet_call", "needs the Python subprocesses"),
+    ("starknet_estimateFee", "needs the Python subprocesses"),
+    (
+        "starknet_addInvokeTransaction",
+        "is forwarded to the sequencer",
+    ),
+    (
+        "starknet_addDeclareTransaction",
+        "is forwarded to the sequencer",
+    ),
+    (
+        "starknet_addDeployTransaction",
+        "is forwarded to the sequencer",
+    ),
+    (
+        "starknet_addDeployAccountTransaction",
+        "is forwarded to the sequencer",
+    ),
+    ("rpc.discover", "is not part of the specification"),
+];
+
+/// Methods and the parameters they are called with, some of which fail on purpose.
+fn cases() -> Vec<(&'static str, Value)> {
+    let genesis = starkhash_bytes!(b"genesis");
+    let contract0 = starkhash_bytes!(b"contract 0");
+    let contract1 = starkhash_bytes!(b"contract 1");
+    let class0 = starkhash_bytes!(b"class 0 hash");
+    let storage_key = starkhash_bytes!(b"storage addr 0");
+    let txn0 = starkhash_bytes!(b"txn 0");
+    let pending_txn0 = starkhash_bytes!(b"pending tx hash 0");
+    let unknown = starkhash_bytes!(b"unknown");
+
+    let mut cases = vec![
+        ("starknet_chainId", json!([])),
+        ("starknet_syncing", json!([])),
+        ("starknet_blockNumber", json!([])),
+        ("starknet_blockHashAndNumber", json!([])),
+        ("starknet_pendingTransactions", json!([])),
+        (
+            "starknet_getStorageAt",
+            json!([contract1, storage_key, "latest"]),
+        ),
+        ("starknet_getNonce", json!(["latest", contract0])),
+        ("starknet_getNonce", json!(["latest", unknown])),
+        ("starknet_getClass", json!(["latest", class0])),
+        ("starknet_getClass", json!(["latest", unknown])),
+        ("starknet_getClassAt", json!(["latest", contract0])),
+        ("starknet_getClassHashAt", json!(["latest", contract0])),
+        ("starknet_getClassHashAt", json!(["pending", unknown])),
+        ("starknet_getTransactionByHash", json!([txn0])),
+        ("starknet_getTransactionByHash", json!([pending_txn0])),
+        ("starknet_getTransactionByHash", json!([unknown])),
+        ("starknet_getTransactionReceipt", json!([txn0])),
+        ("starknet_getTransactionReceipt", json!([pending_txn0])),
+        ("starknet_getTransactionReceipt", json!([unknown])),
+        (
+            "starknet_getEvents",
+            json!([{
+                "from_block": { "block_number": 0 },
+                "to_block": "pending",
+                "chunk_size": 2,
+            }]),
+        ),
+        (
+            "starknet_getEvents",
+            json!([{ "chunk_size": 10, "continuation_token": "invalid" }]),
+        ),
+    ];
+
+    for block_id in [
+        json!("latest"),
+        json!("pending"),
+        json!({ "block_number": 0 }),
+        json!({ "block_hash": genesis }),
+        json!({ "block_number": 100 }),
+    ] {
+        cases.extend([
+            ("starknet_getBlockWithTxHashes", json!([block_id])),
+            ("starknet_getBlockWithTxs", json!([block_id])),
+            ("starknet_getBlockTransactionCount", json!([block_id])),
+            ("starknet_getStateUpdate", json!([block_id])),
+            (
+                "starknet_getTransactionByBlockIdAndIndex",
+                json!([block_id, 0]),
+            ),
+        ]);
+    }
+    cases.push((
+        "starknet_getTransactionByBlockIdAndIndex",
+        json!(["latest", 100]),
+    ));
+
+    cases
+}
+
+/// The specification files, by their file name.
+struct Spec {
+    files: HashMap<String, Value>,
+}
+
+impl Spec {
+    fn load(dir: &Path) -> anyhow::Result<Self> {
+        let files = SPEC_FILES
+            .iter()
+            .map(|&name| {
+                let path = dir.join(name);
+                let file =
+                    std::fs::read(&path).with_context(|| format!("Reading {}", path.display()))?;
+                let spec = serde_json::from_slice(&file)
+                    .with_context(|| format!("Parsing {}", path.display()))?;
+                Ok((name.to_owned(), spec))
+            })
+            .collect::<anyhow::Result<_>>()?;
+
+        Ok(Self { files })
+    }
+
+    /// Returns the specification of method `name`, and the file it is in.
+    fn method(&self, name: &str) -> Option<(&str, &Value)> {
+        self.files.iter().find_map(|(file, spec)| {
+            spec["methods"]
+                .as_array()?
+                .iter()
+                .find(|method| method["name"] == name)
+                .map(|method| (file.as_str(), method))
+        })
+    }
+
+    /// Returns the codes of the errors specified for `method` in `file`.
+    fn error_codes(&self, file: &str, method: &Value) -> anyhow::Result<Vec<Value>> {
+        let errors = match method["errors"].as_array() {
+            Some(errors) => errors,
+            None => return Ok(Vec::new()),
+        };
+
+        errors
+            .iter()
+            .map(|error| {
+                let (_, error) = match error["$ref"].as_str() {
+                    Some(reference) => self.resolve(file, reference)?,
+                    None => (file, error),
+                };
+                Ok(error["code"].clone())
+            })
+            .collect()
+    }
+
+    /// Resolves `reference` made in `file` to the schema it references, and the file it is in.
+    fn resolve<'a>(
+        &'a self,
+        file: &'a str,
+        reference: &str,
+    ) -> anyhow::Result<(&'a str, &'a Value)> {
+        let (path, pointer) = reference.split_once('#').unwrap_or((reference, ""));
+        let file = match path.rsplit('/').next() {
+            Some(name) if !name.is_empty() => name,
+            _ => file,
+        };
+        let (file, spec) = self
+            .files
+            .get_key_value(file)
+            .with_context(|| format!("Unknown file of {reference}"))?;
+        let schema = spec
+            .pointer(pointer)
+            .with_context(|| format!("Unresolved {reference}"))?;
+
+        Ok((file.as_str(), schema))
+    }
+
+    /// Validates `value` against `schema` in `file`, adding the violations found at `path` to
+    /// `violations`.
+    fn validate(
+        &self,
+        file: &str,
+        schema: &Value,
+        value: &Value,
+        path: &str,
+        violations: &mut Vec<String>,
+    ) {
+        if let Some(reference) = schema["$ref"].as_str() {
+            match self.resolve(file, reference) {
+                Ok((file, schema)) => self.validate(file, schema, value, path, violations),
+                Err(e) => violations.push(format!("{path}: {e:#}")),
+            }
+            return;
+        }
+
+        if let Some(schemas) = schema["allOf"].as_array() {
+            for schema in schemas {
+                self.validate(file, schema, value, path, violations);
+            }
+        }
+        // Variants in the specification overlap, so that `oneOf` is checked like `anyOf`.
+        for keyword in ["oneOf", "anyOf"] {
+            if let Some(schemas) = schema[keyword].as_array() {
+                if !schemas
+                    .iter()
+                    .any(|schema| self.is_valid(file, schema, value))
+                {
+                    violations.push(format!("{path}: {value} matches none of {keyword}"));
+                }
+            }
+        }
+
+        let has_type = match &schema["type"] {
+            Value::String(ty) => has_type(value, ty),
+            Value::Array(types) => types
+                .iter()
+                .filter_map(Value::as_str)
+                .any(|ty| has_type(value, ty)),
+            _ => true,
+        };
+        if !has_type {
+            violations.push(format!("{path}: {value} is not of type {}", schema["type"]));
+        }
+
+        if let Some(variants) = schema["enum"].as_array() {
+            if !variants.contains(value) {
+                violations.push(format!("{path}: {value} is not in {}", schema["enum"]));
+            }
+        }
+
+        if let (Some(pattern), Some(string)) = (schema["pattern"].as_str(), value.as_str()) {
+            match regex::Regex::new(pattern) {
+                Ok(regex) if regex.is_match(string) => {}
+                Ok(_) => violations.push(format!("{path}: {string} does not match {pattern}")),
+                Err(e) => violations.push(format!("{path}: invalid pattern {pattern}: {e}")),
+            }
+        }
+
+        if let Some(object) = value.as_object() {
+            for field in schema["required"].as_array().into_iter().flatten() {
+                if let Some(field) = field.as_str() {
+                    if !object.contains_key(field) {
+                        violations.push(format!("{path}: {field} is missing"));
+                    }
+                }
+            }
+
+            let closed = schema["additionalProperties"] == Value::Bool(false);
+            for (field, value) in object {
+                match schema["properties"].get(field) {
+                    Some(schema) => {
+                        self.validate(file, schema, value, &format!("{path}.{field}"), violations)
+                    }
+                    None if closed => violations.push(format!("{path}: {field} is not allowed")),
+                    None => {}
+                }
+            }
+        }
+
+        if let (Some(items), Some(array)) = (schema.get("items"), value.as_array()) {
+            for (i, item) in array.iter().enumerate() {
+                self.validate(file, items, item, &format!("{path}[{i}]"), violations);
+            }
+        }
+    }
+
+    fn is_valid(&self, file: &str, schema: &Value, value: &Value) -> bool {
+        let mut violations = Vec::new();
+        self.validate(file, schema, value, "", &mut violations);
+        violations.is_empty()
+    }
+}
+
+fn has_type(value: &Value, ty: &str) -> bool {
+    match ty {
+        "null" => value.is_null(),
+        "boolean" => value.is_boolean(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "number" => value.is_number(),
+        "string" => value.is_string(),
+        "array" => value.is_array(),
+        "object" => value.is_object(),
+        _ => true,
+    }
+}
+
+async fn module() -> jsonrpsee::RpcModule<RpcContext> {
+    let mut module = jsonrpsee::RpcModule::new(RpcContext::for_tests_with_pending().await);
+    register_all_methods(&mut module).unwrap();
+    module
+}
+
+/// Calls `method` with `params`, returning the response.
+async fn call(module: &jsonrpsee::RpcModule<RpcContext>, method: &str, params: &Value) -> Value {
+    let request = json!({ "jsonrpc": "2.0", "id": 0, "method": method, "params": params });
+    let (response, _) = module.raw_json_request(&request.to_string()).await.unwrap();
+    serde_json::from_str(&response).unwrap()
+}
+
+#[tokio::test]
+async fn every_method_is_covered() {
+    let module = module().await;
+
+    let covered = cases()
+        .into_iter()
+        .map(|(method, _)| method)
+        .chain(UNCOVERED.iter().map(|&(method, _)| method))
+        .collect::<HashSet<_>>();
+    let registered = module.method_names().collect::<HashSet<_>>();
+
+    assert_eq!(covered, registered);
+}
+
+#[tokio::test]
+#[ignore = "needs the specification files vendored into resources/specs/v0.2.1-rc1 by fetch.sh"]
+async fn conforms_to_specification() {
+    let spec = Spec::load(Path::new(SPEC_DIR))
+        .context("Loading the specification files, which are fetched by resources/specs/fetch.sh")
+        .unwrap();
+    let module = module().await;
+
+    let mut violations = Vec::new();
+    for (method, params) in cases() {
+        let case = format!("{method}({params})");
+        let (file, specified) = match spec.method(method) {
+            Some(specified) => specified,
+            None => {
+                violations.push(format!("{case}: method is not specified"));
+                continue;
+            }
+        };
+
+        let response = call(&module, method, &params).await;
+
+        match response.get("error") {
+            None => spec.validate(
+                file,
+                &specified["result"]["schema"],
+                &response["result"],
+                &case,
+                &mut violations,
+            ),
+            Some(error) => {
+                let codes = spec.error_codes(file, specified).unwrap();
+                if !codes.contains(&error["code"]) {
+                    violations.push(format!("{case}: unspecified error {error}"));
+                }
+            }
+        }
+    }
+
+    assert!(violations.is_empty(), "{}", violations.join("\n"));
+}
+
+#[tokio::test]
+async fn conforms_to_discovery() {
+    let module = module().await;
+    let mut document = call(&module, "rpc.discover", &json!([])).await;
+    let spec = Spec {
+        files: HashMap::from([("discover.json".to_owned(), document["result"].take())]),
+    };
+
+    let mut violations = Vec::new();
+    let cases = cases()
+        .into_iter()
+        .chain(std::iter::once(("rpc.discover", json!([]))));
+    for (method, params) in cases {
+        let case = format!("{method}({params})");
+        let (file, described) = spec.method(method).unwrap();
+
+        let response = call(&module, method, &params).await;
+        if response.get("error").is_none() {
+            spec.validate(
+                file,
+                &described["result"]["schema"],
+                &response["result"],
+                &case,
+                &mut violations,
+            );
+        }
+    }
+
+    assert!(violations.is_empty(), "{}", violations.join("\n"));
+}
+
+#[test]
+fn validate() {
+    let spec = Spec {
+        files: HashMap::from([
+            (
+                "api.json".to_owned(),
+                json!({
+                    "components": {
+                        "schemas": {
+                            "FELT": { "type": "string", "pattern": "^0x0[a-fA-F0-9]{1,63}$" },
+                            "BLOCK": {
+                                "type": "object",
+                                "properties": {
+                                    "status": { "enum": ["PENDING", "ACCEPTED_ON_L2"] },
+                                    "hash": { "$ref": "#/components/schemas/FELT" },
+                                    "number": { "type": "integer" },
+                                },
+                                "required": ["status", "number"],
+                            },
+                        },
+                    },
+                }),
+            ),
+            (
+                "write.json".to_owned(),
+                json!({
+                    "schema": {
+                        "oneOf": [
+                            {
+                                "type": "array",
+                                "items": { "$ref": "./api/api.json#/components/schemas/FELT" },
+                            },
+                            { "$ref": "./api/api.json#/components/schemas/BLOCK" },
+                        ],
+                    },
+                }),
+            ),
+        ]),
+    };
+
+    let violations = |value: Value| {
+        let mut violations = Vec::new();
+        spec.validate(
+            "write.json",
+            &json!({ "$ref": "#/schema" }),
+            &value,
+            "value",
+            &mut violations,
+        );
+        violations
+    };
+
+    assert!(violations(json!(["0x01", "0x0abc"])).is_empty());
+    assert!(violations(json!({ "status": "PENDING", "hash": "0x01", "number": 1 })).is_empty());
+    assert_eq!(violations(json!(["0x1"])).len(), 1);
+    assert_eq!(violations(json!({ "status": "PENDING" })).len(), 1);
+    assert_eq!(
+        violations(json!({ "status": "REJECTED", "number": 1 })).len(),
+        1
+    );
+    assert_eq!(
+        violations(json!({ "status": "PENDING", "number": "1" })).len(),
+        1
+    );
+    assert_eq!(violations(json!(1)).len(), 1);
+
+    // violations are reported where they are found
+    let mut violations = Vec::new();
+    spec.validate(
+        "api.json",
+        &json!({ "$ref": "#/components/schemas/BLOCK" }),
+        &json!({ "status": "REJECTED", "hash": "0x1", "number": 1.5 }),
+        "block",
+        &mut violations,
+    );
+    assert_eq!(
+        violations,
+        vec![
+            "block.hash: 0x1 does not match ^0x0[a-fA-F0-9]{1,63}$",
+            "block.number: 1.5 is not of type \"integer\"",
+            r#"block.status: "REJECTED" is not in ["PENDING","ACCEPTED_ON_L2"]"#,
+        ]
+    );
+}
@@ -1,17 +1,20 @@
 use crate::core::{ClassHash, StarknetTransactionHash};
+use crate::rpc::v02::openrpc::{Object, Schema};
 use crate::rpc::v02::types::request::BroadcastedDeclareTransaction;
 use crate::rpc::v02::RpcContext;
 use crate::sequencer::error::SequencerError;
-use crate::sequencer::request::add_transaction::ContractDefinition;
+use crate::sequencer::request::add_transaction::ClassDefinition;
 use crate::sequencer::ClientApi;
 
 crate::rpc::error::generate_rpc_error_subset!(AddDeclareTransactionError: InvalidContractClass);
 
 impl From<SequencerError> for AddDeclareTransactionError {
     fn from(e: SequencerError) -> Self {
-        use crate::sequencer::error::StarknetErrorCode::InvalidProgram;
+        use crate::sequencer::error::StarknetErrorCode::{CompilationFailed, InvalidProgram};
         match e {
-            SequencerError::StarknetError(e) if e.code == InvalidProgram => {
+            SequencerError::StarknetError(e)
+                if e.code == InvalidProgram || e.code == CompilationFailed =>
+            {
                 Self::InvalidContractClass
             }
             _ => Self::Internal(e.into()),
@@ -41,12 +44,21 @@ pub struct AddDeclareTransactionOutput {
     class_hash: ClassHash,
 }
 
+impl Schema for AddDeclareTransactionOutput {
+    fn schema() -> serde_json::Value {
+        Object::new::<Self>()
+            .field::<StarknetTransactionHash>("transaction_hash")
+            .field::<ClassHash>("class_hash")
+            .build()
+    }
+}
+
 pub async fn add_declare_transaction(
     context: RpcContext,
     input: AddDeclareTransactionInput,
 ) -> Result<AddDeclareTransactionOutput, AddDeclareTransactionError> {
     let Transaction::Declare(tx) = input.declare_transaction;
-    let contract_definition: ContractDefinition = tx
+    let contract_definition: ClassDefinition = tx
         .contract_class
         .try_into()
         .map_err(|e| anyhow::anyhow!("Failed to convert contract definition: {}", e))?;
@@ -60,6 +72,7 @@ pub async fn add_declare_transaction(
             tx.nonce,
             contract_definition,
             tx.sender_address,
+            tx.compiled_class_hash,
             input.token,
         )
         .await?;
@@ -76,7 +89,7 @@ mod tests {
 
     use crate::core::{ContractAddress, Fee, TransactionNonce, TransactionVersion};
     use crate::rpc::v02::types::request::BroadcastedDeclareTransaction;
-    use crate::rpc::v02::types::ContractClass;
+    use crate::rpc::v02::types::{CairoContractClass, ContractClass};
     use crate::starkhash;
 
     use super::*;
@@ -87,8 +100,8 @@ mod tests {
             zstd::decode_all(std::io::Cursor::new(compressed_json)).unwrap()
         };
 
-        pub static ref CONTRACT_CLASS: ContractClass = {
-            ContractClass::from_definition_bytes(&CONTRACT_DEFINITION_JSON).unwrap()
+        pub static ref CONTRACT_CLASS: CairoContractClass = {
+            CairoContractClass::from_definition_bytes(&CONTRACT_DEFINITION_JSON).unwrap()
         };
 
         pub static ref CONTRACT_CLASS_JSON: String = {
@@ -105,8 +118,9 @@ mod tests {
                 version: TransactionVersion::ZERO,
                 signature: vec![],
                 nonce: TransactionNonce(StarkHash::ZERO),
-                contract_class: CONTRACT_CLASS.clone(),
+                contract_class: CONTRACT_CLASS.clone().into(),
                 sender_address: ContractAddress::new_or_panic(StarkHash::from_u64(1)),
+                compiled_class_hash: None,
             })
         }
 
@@ -166,6 +180,65 @@ mod tests {
             };
             assert_eq!(input, expected);
         }
+
+        #[test]
+        fn sierra_class() {
+            use crate::core::CasmHash;
+            use crate::rpc::v02::types::{
+                SierraContractClass, SierraEntryPoint, SierraEntryPoints,
+            };
+            use jsonrpsee::types::Params;
+
+            let positional = r#"[
+                {
+                    "type": "DECLARE",
+                    "version": "0x2",
+                    "max_fee": "0x1",
+                    "signature": [],
+                    "nonce": "0x0",
+                    "contract_class": {
+                        "sierra_program": ["0x1", "0x2"],
+                        "contract_class_version": "0.1.0",
+                        "entry_points_by_type": {
+                            "CONSTRUCTOR": [],
+                            "EXTERNAL": [{"selector": "0x3", "function_idx": 0}],
+                            "L1_HANDLER": []
+                        },
+                        "abi": "[]"
+                    },
+                    "sender_address": "0x1",
+                    "compiled_class_hash": "0x4"
+                }
+            ]"#;
+            let positional = Params::new(Some(positional));
+
+            let input = positional.parse::<AddDeclareTransactionInput>().unwrap();
+            let expected = AddDeclareTransactionInput {
+                declare_transaction: Transaction::Declare(BroadcastedDeclareTransaction {
+                    max_fee: Fee(web3::types::H128::from_low_u64_be(1)),
+                    version: TransactionVersion(web3::types::H256::from_low_u64_be(2)),
+                    signature: vec![],
+                    nonce: TransactionNonce(StarkHash::ZERO),
+                    contract_class: ContractClass::Sierra(SierraContractClass {
+                        sierra_program: vec![StarkHash::from_u64(1), StarkHash::from_u64(2)],
+                        contract_class_version: "0.1.0".to_owned(),
+                        entry_points_by_type: SierraEntryPoints {
+                            constructor: vec![],
+                            external: vec![SierraEntryPoint {
+                                selector: StarkHash::from_u64(3),
+                                function_idx: 0,
+                            }],
+                            l1_handler: vec![],
+                        },
+                        abi: "[]".to_owned(),
+                    }),
+                    sender_address: ContractAddress::new_or_panic(StarkHash::from_u64(1)),
+                    compiled_class_hash: Some(CasmHash(StarkHash::from_u64(4))),
+                }),
+                token: None,
+            };
+            assert_eq!(input, expected);
+        }
     }
 
     #[test_log::test(tokio::test)]
@@ -173,7 +246,7 @@ mod tests {
     async fn invalid_contract_definition() {
         let context = RpcContext::for_tests();
 
-        let invalid_contract_class = ContractClass {
+        let invalid_contract_class = CairoContractClass {
             program: "".to_owned(),
             ..CONTRACT_CLASS.clone()
         };
@@ -183,8 +256,9 @@ mod tests {
             max_fee: Fee(Default::default()),
             signature: vec![],
             nonce: TransactionNonce(Default::default()),
-            contract_class: invalid_contract_class,
+            contract_class: invalid_contract_class.into(),
             sender_address: ContractAddress::new_or_panic(StarkHash::from_u64(1)),
+            compiled_class_hash: None,
         });
 
         let input = AddDeclareTransactionInput {
@@ -205,8 +279,9 @@ mod tests {
             max_fee: Fee(Default::default()),
             signature: vec![],
             nonce: TransactionNonce(Default::default()),
-            contract_class: CONTRACT_CLASS.clone(),
+            contract_class: CONTRACT_CLASS.clone().into(),
             sender_address: ContractAddress::new_or_panic(StarkHash::from_u64(1)),
+            compiled_class_hash: None,
         });
 
         let input = AddDeclareTransactionInput {
@@ -2,7 +2,11 @@ use anyhow::Context;
 
 use crate::{
     core::{ContractAddress, StarknetTransactionHash},
-    rpc::v02::{types::request::BroadcastedDeployAccountTransaction, RpcContext},
+    rpc::v02::{
+        openrpc::{Object, Schema},
+        types::request::BroadcastedDeployAccountTransaction,
+        RpcContext,
+    },
     sequencer::ClientApi,
 };
 
@@ -24,6 +28,15 @@ pub struct AddDeployAccountTransactionOutput {
     contract_address: ContractAddress,
 }
 
+impl Schema for AddDeployAccountTransactionOutput {
+    fn schema() -> serde_json::Value {
+        Object::new::<Self>()
+            .field::<StarknetTransactionHash>("transaction_hash")
+            .field::<ContractAddress>("contract_address")
+            .build()
+    }
+}
+
 crate::rpc::error::generate_rpc_error_subset!(AddDeployAccountTransactionError: ClassHashNotFound);
 
 pub async fn add_deploy_account_transaction(
@@ -1,4 +1,5 @@
 use crate::core::{ContractAddress, StarknetTransactionHash};
+use crate::rpc::v02::openrpc::{Object, Schema};
 use crate::rpc::v02::types::request::BroadcastedDeployTransaction;
 use crate::rpc::v02::RpcContext;
 use crate::sequencer::error::SequencerError;
@@ -41,6 +42,15 @@ pub struct AddDeployTransactionOutput {
     contract_address: ContractAddress,
 }
 
+impl Schema for AddDeployTransactionOutput {
+    fn schema() -> serde_json::Value {
+        Object::new::<Self>()
+            .field::<StarknetTransactionHash>("transaction_hash")
+            .field::<ContractAddress>("contract_address")
+            .build()
+    }
+}
+
 pub async fn add_deploy_transaction(
     context: RpcContext,
     input: AddDeployTransactionInput,
@@ -71,7 +81,7 @@ pub async fn add_deploy_transaction(
 #[cfg(test)]
 mod tests {
     use crate::core::{ContractAddressSalt, TransactionVersion};
-    use crate::rpc::v02::types::ContractClass;
+    use crate::rpc::v02::types::CairoContractClass;
     use crate::starkhash;
 
     use super::*;
@@ -82,8 +92,8 @@ mod tests {
             zstd::decode_all(std::io::Cursor::new(compressed_json)).unwrap()
         };
 
-        pub static ref CONTRACT_CLASS: ContractClass = {
-            ContractClass::from_definition_bytes(&CONTRACT_DEFINITION_JSON).unwrap()
+        pub static ref CONTRACT_CLASS: CairoContractClass = {
+            CairoContractClass::from_definition_bytes(&CONTRACT_DEFINITION_JSON).unwrap()
         };
 
         pub static ref CONTRACT_CLASS_JSON: String = {
@@ -162,7 +172,7 @@ mod tests {
     async fn invalid_contract_definition() {
         let context = RpcContext::for_tests();
 
-        let invalid_contract_class = ContractClass {
+        let invalid_contract_class = CairoContractClass {
             program: "".to_owned(),
             ..CONTRACT_CLASS.clone()
         };
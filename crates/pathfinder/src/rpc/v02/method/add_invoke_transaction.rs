@@ -1,6 +1,7 @@
 use anyhow::Context;
 
 use crate::core::StarknetTransactionHash;
+use crate::rpc::v02::openrpc::{Object, Schema};
 use crate::rpc::v02::types::request::BroadcastedInvokeTransaction;
 use crate::rpc::v02::RpcContext;
 use crate::sequencer::ClientApi;
@@ -24,6 +25,14 @@ pub struct AddInvokeTransactionOutput {
     transaction_hash: StarknetTransactionHash,
 }
 
+impl Schema for AddInvokeTransactionOutput {
+    fn schema() -> serde_json::Value {
+        Object::new::<Self>()
+            .field::<StarknetTransactionHash>("transaction_hash")
+            .build()
+    }
+}
+
 pub async fn add_invoke_transaction(
     context: RpcContext,
     input: AddInvokeTransactionInput,
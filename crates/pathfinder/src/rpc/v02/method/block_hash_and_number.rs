@@ -2,7 +2,10 @@ use anyhow::Context;
 
 use crate::{
     core::{StarknetBlockHash, StarknetBlockNumber},
-    rpc::v02::RpcContext,
+    rpc::v02::{
+        openrpc::{Object, Schema},
+        RpcContext,
+    },
     storage::StarknetBlocksTable,
 };
 
@@ -12,6 +15,15 @@ pub struct BlockHashAndNumber {
     pub block_number: StarknetBlockNumber,
 }
 
+impl Schema for BlockHashAndNumber {
+    fn schema() -> serde_json::Value {
+        Object::new::<Self>()
+            .field::<StarknetBlockHash>("block_hash")
+            .field::<StarknetBlockNumber>("block_number")
+            .build()
+    }
+}
+
 crate::rpc::error::generate_rpc_error_subset!(BlockNumberError: NoBlocks);
 
 pub async fn block_hash_and_number(
@@ -1,5 +1,8 @@
+use anyhow::Context;
+
 use crate::{
     core::{BlockId, CallParam, CallResultValue, ContractAddress, EntryPoint},
+    rpc::v02::common::{get_pinned_block_number, is_pinned},
     rpc::v02::RpcContext,
 };
 
@@ -8,7 +11,9 @@ crate::rpc::error::generate_rpc_error_subset!(
     ContractNotFound,
     InvalidMessageSelector,
     InvalidCallData,
-    ContractError
+    ContractError,
+    ExecutionTimeout,
+    Overloaded
 );
 
 impl From<crate::cairo::ext_py::CallFailure> for CallError {
@@ -19,6 +24,11 @@ impl From<crate::cairo::ext_py::CallFailure> for CallError {
             NoSuchContract => Self::ContractNotFound,
             InvalidEntryPoint => Self::InvalidMessageSelector,
             ExecutionFailed(e) => Self::Internal(anyhow::anyhow!("Internal error: {}", e)),
+            Timeout => Self::ExecutionTimeout,
+            Overloaded => Self::Overloaded,
+            UnsupportedChain => {
+                Self::Internal(anyhow::anyhow!("Calls are not supported on custom chains"))
+            }
             // Intentionally hide the message under Internal
             Internal(_) | Shutdown => Self::Internal(anyhow::anyhow!("Internal error")),
         }
@@ -68,11 +78,38 @@ pub async fn call(
         super::estimate_fee::base_block_and_pending_for_call(input.block_id, &context.pending_data)
             .await?;
 
-    let result = handle
-        .call(input.request.into(), when, pending_update)
-        .await?;
+    let storage = context.storage.clone();
+    let block_id = input.block_id;
+
+    let params = is_pinned(&block_id).then(|| format!("{input:?}"));
+    context
+        .cache
+        .get_or_insert_with("starknet_call", params, || async move {
+            let result = handle
+                .call(input.request.into(), when, pending_update)
+                .await?;
+
+            // Calls at `latest` or `pending` are not cached, and need no block.
+            let block_number = if is_pinned(&block_id) {
+                let span = tracing::Span::current();
+                tokio::task::spawn_blocking(move || {
+                    let _g = span.enter();
+                    let mut db = storage
+                        .connection()
+                        .context("Opening database connection")?;
+                    let tx = db.transaction().context("Creating database transaction")?;
+
+                    get_pinned_block_number(&tx, block_id)
+                })
+                .await
+                .context("Database read panic or shutting down")??
+            } else {
+                None
+            };
 
-    Ok(result)
+            Ok::<_, CallError>((result, block_number))
+        })
+        .await
 }
 
 #[cfg(test)]
@@ -171,7 +208,8 @@ mod tests {
             let sync_state = Arc::new(crate::state::SyncState::default());
             let (call_handle, cairo_handle) = crate::cairo::ext_py::start(
                 storage.path().into(),
-                std::num::NonZeroUsize::try_from(2).unwrap(),
+                crate::cairo::ext_py::PoolSize::fixed(std::num::NonZeroUsize::try_from(2).unwrap()),
+                crate::cairo::ext_py::Limits::default(),
                 futures::future::pending(),
                 Chain::Mainnet,
             )
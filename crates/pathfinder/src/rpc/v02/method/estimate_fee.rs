@@ -6,6 +6,7 @@ use serde_with::serde_as;
 use crate::{
     cairo::ext_py::{BlockHashNumberOrLatest, GasPriceSource},
     core::BlockId,
+    rpc::v02::openrpc::{hex, Object, Schema},
     rpc::v02::types::request::BroadcastedTransaction,
     rpc::v02::RpcContext,
     state::PendingData,
@@ -22,7 +23,9 @@ crate::rpc::error::generate_rpc_error_subset!(
     ContractNotFound,
     ContractError,
     InvalidMessageSelector,
-    InvalidCallData
+    InvalidCallData,
+    ExecutionTimeout,
+    Overloaded
 );
 
 impl From<crate::cairo::ext_py::CallFailure> for EstimateFeeError {
@@ -33,6 +36,11 @@ impl From<crate::cairo::ext_py::CallFailure> for EstimateFeeError {
             NoSuchContract => Self::ContractNotFound,
             InvalidEntryPoint => Self::InvalidMessageSelector,
             ExecutionFailed(e) => Self::Internal(anyhow::anyhow!("Internal error: {}", e)),
+            Timeout => Self::ExecutionTimeout,
+            Overloaded => Self::Overloaded,
+            UnsupportedChain => {
+                Self::Internal(anyhow::anyhow!("Calls are not supported on custom chains"))
+            }
             // Intentionally hide the message under Internal
             Internal(_) | Shutdown => Self::Internal(anyhow::anyhow!("Internal error")),
         }
@@ -137,6 +145,16 @@ pub struct FeeEstimate {
     pub overall_fee: web3::types::H256,
 }
 
+impl Schema for FeeEstimate {
+    fn schema() -> serde_json::Value {
+        Object::new::<Self>()
+            .field_as("gas_consumed", hex(), true)
+            .field_as("gas_price", hex(), true)
+            .field_as("overall_fee", hex(), true)
+            .build()
+    }
+}
+
 impl From<crate::rpc::v01::types::reply::FeeEstimate> for FeeEstimate {
     fn from(v01: crate::rpc::v01::types::reply::FeeEstimate) -> Self {
         Self {
@@ -250,7 +268,7 @@ mod tests {
             BroadcastedDeclareTransaction, BroadcastedDeployTransaction,
             BroadcastedInvokeTransactionV0,
         };
-        use crate::rpc::v02::types::ContractClass;
+        use crate::rpc::v02::types::CairoContractClass;
         use crate::starkhash_bytes;
 
         use super::*;
@@ -298,7 +316,8 @@ mod tests {
             let sync_state = Arc::new(crate::state::SyncState::default());
             let (call_handle, cairo_handle) = crate::cairo::ext_py::start(
                 storage.path().into(),
-                std::num::NonZeroUsize::try_from(2).unwrap(),
+                crate::cairo::ext_py::PoolSize::fixed(std::num::NonZeroUsize::try_from(2).unwrap()),
+                crate::cairo::ext_py::Limits::default(),
                 futures::future::pending(),
                 Chain::Mainnet,
             )
@@ -380,10 +399,10 @@ mod tests {
         }
 
         lazy_static::lazy_static! {
-            pub static ref CONTRACT_CLASS: ContractClass = {
+            pub static ref CONTRACT_CLASS: CairoContractClass = {
                 let compressed_json = include_bytes!("../../../../fixtures/contract_definition.json.zst");
                 let json = zstd::decode_all(std::io::Cursor::new(compressed_json)).unwrap();
-                ContractClass::from_definition_bytes(&json).unwrap()
+                CairoContractClass::from_definition_bytes(&json).unwrap()
             };
         }
 
@@ -397,10 +416,11 @@ mod tests {
                     max_fee: Fee(Default::default()),
                     signature: vec![],
                     nonce: TransactionNonce(Default::default()),
-                    contract_class: CONTRACT_CLASS.clone(),
+                    contract_class: CONTRACT_CLASS.clone().into(),
                     sender_address: ContractAddress::new_or_panic(starkhash!(
                         "020cfa74ee3564b4cd5435cdace0f9c4d43b939620e4a0bb5076105df0a626c6"
                     )),
+                    compiled_class_hash: None,
                 });
 
             let input = EstimateFeeInput {
@@ -3,7 +3,7 @@ use serde::Deserialize;
 use stark_hash::StarkHash;
 
 use crate::core::{BlockId, GlobalRoot, StarknetBlockHash, StarknetBlockNumber};
-use crate::rpc::v02::common::get_block_status;
+use crate::rpc::v02::common::{get_block_status, get_l1_reference, is_final, is_pinned};
 use crate::rpc::v02::RpcContext;
 use crate::storage::{StarknetBlocksBlockId, StarknetBlocksTable, StarknetTransactionsTable};
 
@@ -47,6 +47,12 @@ async fn get_block(
     block_id: BlockId,
     scope: types::BlockResponseScope,
 ) -> Result<types::Block, GetBlockError> {
+    let method = match scope {
+        types::BlockResponseScope::TransactionHashes => "starknet_getBlockWithTxHashes",
+        types::BlockResponseScope::FullTransactions => "starknet_getBlockWithTxs",
+    };
+    let params = is_pinned(&block_id).then_some(block_id);
+
     let block_id = match block_id {
         BlockId::Pending => {
             match context
@@ -72,25 +78,32 @@ async fn get_block(
     let storage = context.storage.clone();
     let span = tracing::Span::current();
 
-    tokio::task::spawn_blocking(move || {
-        let _g = span.enter();
-        let mut connection = storage
-            .connection()
-            .context("Opening database connection")?;
-
-        let transaction = connection
-            .transaction()
-            .context("Creating database transaction")?;
-
-        // Need to get the block status. This also tests that the block hash is valid.
-        let block = get_raw_block(&transaction, block_id)?;
-
-        let transactions = get_block_transactions(&transaction, block.number, scope)?;
-
-        Ok(types::Block::from_raw(block, transactions))
-    })
-    .await
-    .context("Database read panic or shutting down")?
+    context
+        .cache
+        .get_or_insert_with(method, params, || async move {
+            tokio::task::spawn_blocking(move || -> Result<_, GetBlockError> {
+                let _g = span.enter();
+                let mut connection = storage
+                    .connection()
+                    .context("Opening database connection")?;
+
+                let transaction = connection
+                    .transaction()
+                    .context("Creating database transaction")?;
+
+                // Need to get the block status. This also tests that the block hash is valid.
+                let block = get_raw_block(&transaction, block_id)?;
+                // The status and L1 reference no longer change once finalized on L1.
+                let cacheable = is_final(&block.l1_reference).then_some(block.number);
+
+                let transactions = get_block_transactions(&transaction, block.number, scope)?;
+
+                Ok((types::Block::from_raw(block, transactions), cacheable))
+            })
+            .await
+            .context("Database read panic or shutting down")?
+        })
+        .await
 }
 
 /// Fetches a [RawBlock](types::RawBlock) from storage.
@@ -103,6 +116,10 @@ fn get_raw_block(
         .ok_or(GetBlockError::BlockNotFound)?;
 
     let block_status = get_block_status(transaction, block.number)?;
+    let l1_reference = get_l1_reference(transaction, block.number)?;
+
+    let commitments = StarknetBlocksTable::get_commitments(transaction, block.number)
+        .context("Read block commitments from database")?;
 
     let (parent_hash, parent_root) = match block.number {
         StarknetBlockNumber::GENESIS => (
@@ -128,6 +145,8 @@ fn get_raw_block(
         status: block_status,
         gas_price: block.gas_price,
         sequencer: block.sequencer_address,
+        commitments,
+        l1_reference,
     };
 
     Ok(block)
@@ -161,10 +180,12 @@ fn get_block_transactions(
 
 mod types {
     use crate::core::{
-        GasPrice, GlobalRoot, SequencerAddress, StarknetBlockHash, StarknetBlockNumber,
-        StarknetBlockTimestamp, StarknetTransactionHash,
+        EventCommitment, GasPrice, GlobalRoot, SequencerAddress, StarknetBlockHash,
+        StarknetBlockNumber, StarknetBlockTimestamp, StarknetTransactionHash,
+        TransactionCommitment,
     };
-    use crate::rpc::v02::types::reply::{BlockStatus, Transaction};
+    use crate::rpc::v02::openrpc::{untagged, Object, Schema};
+    use crate::rpc::v02::types::reply::{BlockStatus, L1Reference, Transaction};
     use crate::sequencer;
     use serde::Serialize;
     use serde_with::{serde_as, skip_serializing_none};
@@ -187,6 +208,15 @@ mod types {
         HashesOnly(Vec<StarknetTransactionHash>),
     }
 
+    impl Schema for Transactions {
+        fn schema() -> serde_json::Value {
+            untagged::<Self>(vec![
+                Vec::<Transaction>::schema(),
+                Vec::<StarknetTransactionHash>::schema(),
+            ])
+        }
+    }
+
     /// L2 Block as returned by the RPC API.
     #[serde_as]
     #[skip_serializing_none]
@@ -201,6 +231,30 @@ mod types {
         pub timestamp: StarknetBlockTimestamp,
         pub sequencer_address: SequencerAddress,
         pub transactions: Transactions,
+        /// Not available for pending blocks, nor for blocks synced before commitments were stored.
+        pub transaction_commitment: Option<TransactionCommitment>,
+        /// Not available for pending blocks, nor for blocks synced before commitments were stored.
+        pub event_commitment: Option<EventCommitment>,
+        /// The L1 state update which accepted the block, if it is accepted on L1.
+        pub l1_reference: Option<L1Reference>,
+    }
+
+    impl Schema for Block {
+        fn schema() -> serde_json::Value {
+            Object::new::<Self>()
+                .field::<BlockStatus>("status")
+                .field::<Option<StarknetBlockHash>>("block_hash")
+                .field::<StarknetBlockHash>("parent_hash")
+                .field::<Option<StarknetBlockNumber>>("block_number")
+                .field::<Option<GlobalRoot>>("new_root")
+                .field::<StarknetBlockTimestamp>("timestamp")
+                .field::<SequencerAddress>("sequencer_address")
+                .field::<Transactions>("transactions")
+                .field::<Option<TransactionCommitment>>("transaction_commitment")
+                .field::<Option<EventCommitment>>("event_commitment")
+                .field::<Option<L1Reference>>("l1_reference")
+                .build()
+        }
     }
 
     /// Convenience type for DB manipulation.
@@ -215,6 +269,8 @@ mod types {
         pub status: BlockStatus,
         pub sequencer: SequencerAddress,
         pub gas_price: GasPrice,
+        pub commitments: Option<(TransactionCommitment, EventCommitment)>,
+        pub l1_reference: Option<L1Reference>,
     }
 
     impl Block {
@@ -229,6 +285,9 @@ mod types {
                 timestamp: block.timestamp,
                 sequencer_address: block.sequencer,
                 transactions,
+                transaction_commitment: block.commitments.map(|c| c.0),
+                event_commitment: block.commitments.map(|c| c.1),
+                l1_reference: block.l1_reference,
             }
         }
 
@@ -263,6 +322,9 @@ mod types {
                         // Default value for cairo <0.8.0 is 0
                         .unwrap_or(SequencerAddress(StarkHash::ZERO)),
                     transactions,
+                    transaction_commitment: block.transaction_commitment,
+                    event_commitment: block.event_commitment,
+                    l1_reference: None,
                 },
                 MaybePendingBlock::Pending(pending) => Self {
                     status: pending.status.into(),
@@ -273,6 +335,9 @@ mod types {
                     timestamp: pending.timestamp,
                     sequencer_address: pending.sequencer_address,
                     transactions,
+                    transaction_commitment: None,
+                    event_commitment: None,
+                    l1_reference: None,
                 },
             }
         }
@@ -429,4 +494,135 @@ mod tests {
             check(i, test_case).await;
         }
     }
+
+    #[tokio::test]
+    async fn commitments() {
+        use crate::core::{EventCommitment, TransactionCommitment};
+
+        let context = RpcContext::for_tests();
+        let commitments = (
+            TransactionCommitment(starkhash!("01")),
+            EventCommitment(starkhash!("02")),
+        );
+
+        let mut connection = context.storage.connection().unwrap();
+        let tx = connection.transaction().unwrap();
+        StarknetBlocksTable::update_commitments(
+            &tx,
+            StarknetBlockNumber::GENESIS,
+            commitments.0,
+            commitments.1,
+        )
+        .unwrap();
+        tx.commit().unwrap();
+
+        let block = get_block_with_tx_hashes(
+            context.clone(),
+            GetBlockInput {
+                block_id: BlockId::Number(StarknetBlockNumber::GENESIS),
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(block.transaction_commitment, Some(commitments.0));
+        assert_eq!(block.event_commitment, Some(commitments.1));
+
+        // Blocks synced without commitments don't report any.
+        let block = get_block_with_tx_hashes(
+            context,
+            GetBlockInput {
+                block_id: BlockId::Latest,
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(block.transaction_commitment, None);
+        assert_eq!(block.event_commitment, None);
+    }
+
+    #[tokio::test]
+    async fn l1_reference() {
+        use crate::core::{
+            EthereumBlockHash, EthereumBlockNumber, EthereumLogIndex, EthereumTransactionHash,
+            EthereumTransactionIndex,
+        };
+        use crate::ethereum::{log::StateUpdateLog, BlockOrigin, EthOrigin, TransactionOrigin};
+        use crate::rpc::v02::types::reply::{L1Finality, L1Reference};
+        use crate::storage::{L1StateTable, RefsTable};
+        use web3::types::H256;
+
+        let context = RpcContext::for_tests();
+        let origin = EthOrigin {
+            block: BlockOrigin {
+                hash: EthereumBlockHash(H256::from_low_u64_be(1)),
+                number: EthereumBlockNumber(100),
+            },
+            transaction: TransactionOrigin {
+                hash: EthereumTransactionHash(H256::from_low_u64_be(2)),
+                index: EthereumTransactionIndex(3),
+            },
+            log_index: EthereumLogIndex(4),
+        };
+        let input = GetBlockInput {
+            block_id: BlockId::Number(StarknetBlockNumber::GENESIS),
+        };
+
+        let mut connection = context.storage.connection().unwrap();
+        let tx = connection.transaction().unwrap();
+        let genesis = StarknetBlocksTable::get(&tx, StarknetBlockNumber::GENESIS.into())
+            .unwrap()
+            .unwrap();
+        L1StateTable::upsert(
+            &tx,
+            &StateUpdateLog {
+                origin: origin.clone(),
+                global_root: genesis.root,
+                block_number: genesis.number,
+            },
+        )
+        .unwrap();
+        tx.commit().unwrap();
+
+        // Not accepted on L1 until the L1-L2 head reaches the block.
+        let block = get_block_with_tx_hashes(context.clone(), input)
+            .await
+            .unwrap();
+        assert_eq!(block.l1_reference, None);
+
+        let tx = connection.transaction().unwrap();
+        RefsTable::set_l1_l2_head(&tx, Some(StarknetBlockNumber::GENESIS)).unwrap();
+        tx.commit().unwrap();
+
+        let block = get_block_with_tx_hashes(context.clone(), input)
+            .await
+            .unwrap();
+        assert_eq!(
+            block.l1_reference,
+            Some(L1Reference::new(&origin, L1Finality::Unconfirmed))
+        );
+
+        // Safe once the safe L1 block reaches the update.
+        let tx = connection.transaction().unwrap();
+        RefsTable::set_l1_safe_block(&tx, origin.block.number).unwrap();
+        tx.commit().unwrap();
+
+        let block = get_block_with_tx_hashes(context.clone(), input)
+            .await
+            .unwrap();
+        assert_eq!(
+            block.l1_reference,
+            Some(L1Reference::new(&origin, L1Finality::Safe))
+        );
+
+        // Finalized once the finalized L1 block reaches the update.
+        let tx = connection.transaction().unwrap();
+        RefsTable::set_l1_finalized_block(&tx, origin.block.number).unwrap();
+        tx.commit().unwrap();
+
+        let block = get_block_with_tx_hashes(context, input).await.unwrap();
+        assert_eq!(
+            block.l1_reference,
+            Some(L1Reference::new(&origin, L1Finality::Finalized))
+        );
+    }
 }
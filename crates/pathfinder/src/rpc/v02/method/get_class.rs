@@ -1,4 +1,5 @@
 use crate::core::{BlockId, ClassHash};
+use crate::rpc::v02::common::{get_pinned_block_number, is_pinned};
 use crate::rpc::v02::types::ContractClass;
 use crate::rpc::v02::RpcContext;
 
@@ -28,31 +29,40 @@ pub async fn get_class(
         other => other,
     };
 
+    let storage = context.storage.clone();
     let span = tracing::Span::current();
-    let jh = tokio::task::spawn_blocking(move || -> Result<ContractClass, GetClassError> {
-        let _g = span.enter();
-        let mut db = context
-            .storage
-            .connection()
-            .context("Opening database connection")?;
-        let tx = db.transaction().context("Creating database transaction")?;
-
-        let definition = match block {
-            BlockId::Pending => read_pending(&tx, input.class_hash),
-            BlockId::Number(number) => read_at_number(&tx, input.class_hash, number),
-            BlockId::Hash(hash) => read_at_hash(&tx, input.class_hash, hash),
-            BlockId::Latest => read_latest(&tx, input.class_hash),
-        }?;
-
-        let definition =
-            zstd::decode_all(&*definition).context("Decompressing class definition")?;
-        let class = ContractClass::from_definition_bytes(&definition)
-            .context("Parsing class definition")?;
-
-        Ok(class)
-    });
-
-    jh.await.context("Reading class from database")?
+
+    let params = is_pinned(&block).then_some((block, input.class_hash));
+    context
+        .cache
+        .get_or_insert_with("starknet_getClass", params, || async move {
+            let jh = tokio::task::spawn_blocking(move || -> Result<_, GetClassError> {
+                let _g = span.enter();
+                let mut db = storage
+                    .connection()
+                    .context("Opening database connection")?;
+                let tx = db.transaction().context("Creating database transaction")?;
+
+                let definition = match block {
+                    BlockId::Pending => read_pending(&tx, input.class_hash),
+                    BlockId::Number(number) => read_at_number(&tx, input.class_hash, number),
+                    BlockId::Hash(hash) => read_at_hash(&tx, input.class_hash, hash),
+                    BlockId::Latest => read_latest(&tx, input.class_hash),
+                }?;
+
+                let definition =
+                    zstd::decode_all(&*definition).context("Decompressing class definition")?;
+                let class = ContractClass::from_definition_bytes(&definition)
+                    .context("Parsing class definition")?;
+
+                let block_number = get_pinned_block_number(&tx, block)?;
+
+                Ok((class, block_number))
+            });
+
+            jh.await.context("Reading class from database")?
+        })
+        .await
 }
 
 /// Returns the class definition data.
@@ -169,13 +179,21 @@ async fn is_pending_class(pending: &Option<crate::state::PendingData>, hash: Cla
     };
 
     let declared = state_diff.state_diff.declared_contracts.iter().cloned();
+    let declared_sierra = state_diff
+        .state_diff
+        .declared_classes
+        .iter()
+        .map(|class| class.class_hash);
     let deployed = state_diff
         .state_diff
         .deployed_contracts
         .iter()
         .map(|contract| contract.class_hash);
 
-    deployed.chain(declared).any(|item| item == hash)
+    deployed
+        .chain(declared)
+        .chain(declared_sierra)
+        .any(|item| item == hash)
 }
 
 #[cfg(test)]
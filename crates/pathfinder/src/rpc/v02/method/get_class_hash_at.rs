@@ -1,9 +1,9 @@
 use anyhow::Context;
 
-use crate::core::{BlockId, ClassHash, ContractAddress, ContractStateHash};
+use crate::core::{BlockId, ClassHash, ContractAddress};
 use crate::rpc::v02::RpcContext;
 use crate::state::state_tree::GlobalStateTree;
-use crate::storage::{StarknetBlocksBlockId, StarknetBlocksTable};
+use crate::storage::{StarknetBlocksBlockId, StorageBackend};
 
 crate::rpc::error::generate_rpc_error_subset!(GetClassHashAtError: BlockNotFound, ContractNotFound);
 
@@ -39,34 +39,49 @@ pub async fn get_class_hash_at(
 
         let tx = db.transaction().context("Creating database transaction")?;
 
-        // Read the class hash via the state tree. This involves:
-        //  1. Reading the state_hash for this contract from the global tree
-        //  2. Fetching the class hash from the `contract_states` table
-        //
-        // (2) can also be achieved by fetching it directly from the `contracts` table,
-        // but it felt more "correct" to continue using the global state mechanism.
-        let global_root = StarknetBlocksTable::get_root(&tx, block_id)
-            .context("Reading global root from database")?
-            .ok_or(GetClassHashAtError::BlockNotFound)?;
-
-        let tree = GlobalStateTree::load(&tx, global_root).context("Loading global state tree")?;
-        let state_hash = tree
-            .get(input.contract_address)
-            .context("Fetching contract leaf in global tree")?
-            .ok_or(GetClassHashAtError::ContractNotFound)?;
-
-        read_class_hash(&tx, state_hash)
-            .context("Reading class hash from state table")?
-            // Class hash should not be None at this stage since we have a valid block and non-zero contract state_hash.
-            .ok_or_else(|| {
-                tracing::error!(%state_hash, "Class hash is missing in `contract_states` table");
-                anyhow::anyhow!("State table missing row for state_hash={}", state_hash).into()
-            })
+        read_class_hash_at(&tx, block_id, input.contract_address)
     });
 
     jh.await.context("Database read panic or shutting down")?
 }
 
+/// Reads the contract's class hash from the given block's state.
+fn read_class_hash_at<S: StorageBackend>(
+    storage: S,
+    block_id: StarknetBlocksBlockId,
+    contract_address: ContractAddress,
+) -> Result<ClassHash, GetClassHashAtError> {
+    // Read the class hash via the state tree. This involves:
+    //  1. Reading the state_hash for this contract from the global tree
+    //  2. Fetching the class hash from the `contract_states` table
+    //
+    // (2) can also be achieved by fetching it directly from the `contracts` table,
+    // but it felt more "correct" to continue using the global state mechanism.
+    let global_root = storage
+        .block_root(block_id)
+        .context("Reading global root from database")?
+        .ok_or(GetClassHashAtError::BlockNotFound)?;
+
+    let nodes = storage
+        .global_tree_nodes()
+        .context("Opening global state tree storage")?;
+    let tree =
+        GlobalStateTree::with_storage(nodes, global_root).context("Loading global state tree")?;
+    let state_hash = tree
+        .get(contract_address)
+        .context("Fetching contract leaf in global tree")?
+        .ok_or(GetClassHashAtError::ContractNotFound)?;
+
+    storage
+        .contract_state_class_hash(state_hash)
+        .context("Reading class hash from state table")?
+        // Class hash should not be None at this stage since we have a valid block and non-zero contract state_hash.
+        .ok_or_else(|| {
+            tracing::error!(%state_hash, "Class hash is missing in `contract_states` table");
+            anyhow::anyhow!("State table missing row for state_hash={}", state_hash).into()
+        })
+}
+
 /// Returns the [ClassHash] of the given [ContractAddress] if any is defined in the pending data.
 async fn get_pending_class_hash(
     pending: Option<crate::state::PendingData>,
@@ -81,22 +96,6 @@ async fn get_pending_class_hash(
     })
 }
 
-/// Returns the [ClassHash] for the given [ContractStateHash] from the database.
-fn read_class_hash(
-    tx: &rusqlite::Transaction<'_>,
-    state_hash: ContractStateHash,
-) -> anyhow::Result<Option<ClassHash>> {
-    use rusqlite::OptionalExtension;
-
-    tx.query_row(
-        "SELECT hash FROM contract_states WHERE state_hash=?",
-        [state_hash],
-        |row| row.get(0),
-    )
-    .optional()
-    .map_err(|e| e.into())
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
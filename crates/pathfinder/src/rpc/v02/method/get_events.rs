@@ -340,6 +340,7 @@ mod types {
         ContractAddress, EventData, EventKey, StarknetBlockHash, StarknetBlockNumber,
         StarknetTransactionHash,
     };
+    use crate::rpc::v02::openrpc::{Object, Schema};
     use serde::Serialize;
 
     /// Describes an emitted event returned by starknet_getEvents
@@ -356,6 +357,24 @@ mod types {
         pub transaction_hash: StarknetTransactionHash,
     }
 
+    impl Schema for EmittedEvent {
+        fn schema() -> serde_json::Value {
+            Object::new::<Self>()
+                .field::<Vec<EventData>>("data")
+                .field::<Vec<EventKey>>("keys")
+                .field::<ContractAddress>("from_address")
+                // serialized as null rather than skipped
+                .field_as("block_hash", Option::<StarknetBlockHash>::schema(), true)
+                .field_as(
+                    "block_number",
+                    Option::<StarknetBlockNumber>::schema(),
+                    true,
+                )
+                .field::<StarknetTransactionHash>("transaction_hash")
+                .build()
+        }
+    }
+
     impl From<crate::storage::StarknetEmittedEvent> for EmittedEvent {
         fn from(event: crate::storage::StarknetEmittedEvent) -> Self {
             Self {
@@ -378,6 +397,15 @@ mod types {
         /// Offset, measured in events, which points to the chunk that follows currenty requested chunk (`events`)
         pub continuation_token: Option<String>,
     }
+
+    impl Schema for GetEventsResult {
+        fn schema() -> serde_json::Value {
+            Object::new::<Self>()
+                .field::<Vec<EmittedEvent>>("events")
+                .field::<Option<String>>("continuation_token")
+                .build()
+        }
+    }
 }
 
 #[cfg(test)]
@@ -3,6 +3,8 @@ use anyhow::Context;
 use crate::{
     core::{BlockId, ContractAddress, ContractNonce},
     rpc::v02::RpcContext,
+    state::state_tree::GlobalStateTree,
+    storage::{StarknetBlocksBlockId, StorageBackend},
 };
 
 #[derive(serde::Deserialize, Debug, PartialEq, Eq)]
@@ -18,9 +20,6 @@ pub async fn get_nonce(
     context: RpcContext,
     input: GetNonceInput,
 ) -> Result<ContractNonce, GetNonceError> {
-    use crate::state::state_tree::GlobalStateTree;
-    use crate::storage::{StarknetBlocksBlockId, StarknetBlocksTable};
-
     // We can potentially read the nonce from pending without having to reach out to the database.
     let block_id = match input.block_id {
         BlockId::Pending => {
@@ -43,28 +42,42 @@ pub async fn get_nonce(
             .context("Opening database connection")?;
         let tx = db.transaction().context("Creating database transaction")?;
 
-        let global_root = StarknetBlocksTable::get_root(&tx, block_id)
-            .context("Fetching global root")?
-            .ok_or(GetNonceError::BlockNotFound)?;
-
-        let global_state_tree =
-            GlobalStateTree::load(&tx, global_root).context("Loading global state tree")?;
-
-        let state_hash = global_state_tree
-            .get(input.contract_address)
-            .context("Get contract state hash from global state tree")?
-            .ok_or(GetNonceError::ContractNotFound)?;
-
-        let nonce = crate::storage::ContractsStateTable::get_nonce(&tx, state_hash)
-            .context("Reading contract nonce")?
-            // Since the contract does exist, the nonce should not be missing.
-            .context("Contract nonce is missing from database")?;
-
-        Ok(nonce)
+        read_nonce(&tx, block_id, input.contract_address)
     });
     jh.await.context("Database read panic or shutting down")?
 }
 
+/// Reads the contract's nonce from the given block's state.
+fn read_nonce<S: StorageBackend>(
+    storage: S,
+    block_id: StarknetBlocksBlockId,
+    contract_address: ContractAddress,
+) -> Result<ContractNonce, GetNonceError> {
+    let global_root = storage
+        .block_root(block_id)
+        .context("Fetching global root")?
+        .ok_or(GetNonceError::BlockNotFound)?;
+
+    let nodes = storage
+        .global_tree_nodes()
+        .context("Opening global state tree storage")?;
+    let global_state_tree =
+        GlobalStateTree::with_storage(nodes, global_root).context("Loading global state tree")?;
+
+    let state_hash = global_state_tree
+        .get(contract_address)
+        .context("Get contract state hash from global state tree")?
+        .ok_or(GetNonceError::ContractNotFound)?;
+
+    let (_, nonce) = storage
+        .contract_root_and_nonce(state_hash)
+        .context("Reading contract nonce")?
+        // Since the contract does exist, the nonce should not be missing.
+        .context("Contract nonce is missing from database")?;
+
+    Ok(nonce)
+}
+
 /// Returns the contract's pending nonce.
 async fn get_pending_nonce(
     pending: &Option<crate::state::PendingData>,
@@ -217,6 +230,61 @@ mod tests {
         assert_eq!(nonce, ContractNonce::ZERO);
     }
 
+    #[test]
+    fn in_memory_storage() {
+        use super::read_nonce;
+        use crate::core::{
+            ClassHash, GasPrice, GlobalRoot, SequencerAddress, StarknetBlockTimestamp,
+            StorageAddress, StorageValue,
+        };
+        use crate::sequencer::reply::state_update::StorageDiff;
+        use crate::state::{state_tree::GlobalStateTree, update_contract_state};
+        use crate::storage::{
+            InMemoryStorage, StarknetBlock, StarknetBlocksBlockId, StorageBackend,
+        };
+
+        let storage = InMemoryStorage::default();
+        let address = ContractAddress::new_or_panic(starkhash_bytes!(b"contract"));
+        let nonce = ContractNonce(starkhash_bytes!(b"nonce"));
+        storage
+            .upsert_contract(address, ClassHash(starkhash_bytes!(b"class")))
+            .unwrap();
+
+        let nodes = storage.global_tree_nodes().unwrap();
+        let mut tree =
+            GlobalStateTree::with_storage(nodes, GlobalRoot(stark_hash::StarkHash::ZERO)).unwrap();
+        let diff = StorageDiff {
+            key: StorageAddress::new_or_panic(starkhash_bytes!(b"key")),
+            value: StorageValue(starkhash_bytes!(b"value")),
+        };
+        let state_hash =
+            update_contract_state(address, &[diff], Some(nonce), &tree, &storage).unwrap();
+        tree.set(address, state_hash).unwrap();
+        let block = StarknetBlock {
+            number: StarknetBlockNumber::GENESIS,
+            hash: StarknetBlockHash(starkhash_bytes!(b"genesis")),
+            root: tree.apply().unwrap(),
+            timestamp: StarknetBlockTimestamp::new_or_panic(0),
+            gas_price: GasPrice::ZERO,
+            sequencer_address: SequencerAddress(stark_hash::StarkHash::ZERO),
+        };
+        storage.insert_block(&block, None).unwrap();
+
+        let result = read_nonce(&storage, StarknetBlocksBlockId::Latest, address).unwrap();
+        assert_eq!(result, nonce);
+
+        let unknown = ContractAddress::new_or_panic(starkhash_bytes!(b"unknown"));
+        let result = read_nonce(&storage, StarknetBlocksBlockId::Latest, unknown);
+        assert_matches::assert_matches!(result, Err(GetNonceError::ContractNotFound));
+
+        let result = read_nonce(
+            &storage,
+            StarknetBlockNumber::new_or_panic(1).into(),
+            address,
+        );
+        assert_matches::assert_matches!(result, Err(GetNonceError::BlockNotFound));
+    }
+
     #[tokio::test]
     async fn pending() {
         use super::get_pending_nonce;
@@ -251,6 +319,7 @@ mod tests {
                 storage_diffs: std::collections::HashMap::new(),
                 deployed_contracts: Vec::new(),
                 declared_contracts: Vec::new(),
+                declared_classes: Vec::new(),
                 nonces: [(valid_1, nonce_1), (valid_2, nonce_2)]
                     .into_iter()
                     .collect(),
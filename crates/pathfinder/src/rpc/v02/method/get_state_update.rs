@@ -77,6 +77,7 @@ mod types {
         ClassHash, ContractAddress, ContractNonce, GlobalRoot, StarknetBlockHash, StorageAddress,
         StorageValue,
     };
+    use crate::rpc::v02::openrpc::{Object, Schema};
 
     #[skip_serializing_none]
     #[derive(Clone, Debug, Serialize, PartialEq, Eq)]
@@ -91,6 +92,17 @@ mod types {
         pub state_diff: StateDiff,
     }
 
+    impl Schema for StateUpdate {
+        fn schema() -> serde_json::Value {
+            Object::new::<Self>()
+                .field::<Option<StarknetBlockHash>>("block_hash")
+                .field::<GlobalRoot>("new_root")
+                .field::<GlobalRoot>("old_root")
+                .field::<StateDiff>("state_diff")
+                .build()
+        }
+    }
+
     impl From<crate::sequencer::reply::StateUpdate> for StateUpdate {
         fn from(x: crate::sequencer::reply::StateUpdate) -> Self {
             Self {
@@ -124,6 +136,17 @@ mod types {
         pub nonces: Vec<Nonce>,
     }
 
+    impl Schema for StateDiff {
+        fn schema() -> serde_json::Value {
+            Object::new::<Self>()
+                .field::<Vec<StorageDiff>>("storage_diffs")
+                .field::<Vec<ClassHash>>("declared_contract_hashes")
+                .field::<Vec<DeployedContract>>("deployed_contracts")
+                .field::<Vec<Nonce>>("nonces")
+                .build()
+        }
+    }
+
     impl From<crate::sequencer::reply::state_update::StateDiff> for StateDiff {
         fn from(state_diff: crate::sequencer::reply::state_update::StateDiff) -> Self {
             let storage_diffs: Vec<StorageDiff> = state_diff
@@ -136,7 +159,16 @@ mod types {
                 .collect();
             Self {
                 storage_diffs,
-                declared_contract_hashes: state_diff.declared_contracts,
+                declared_contract_hashes: state_diff
+                    .declared_contracts
+                    .into_iter()
+                    .chain(
+                        state_diff
+                            .declared_classes
+                            .into_iter()
+                            .map(|class| class.class_hash),
+                    )
+                    .collect(),
                 deployed_contracts: state_diff
                     .deployed_contracts
                     .into_iter()
@@ -213,6 +245,15 @@ mod types {
         pub storage_entries: Vec<StorageEntry>,
     }
 
+    impl Schema for StorageDiff {
+        fn schema() -> serde_json::Value {
+            Object::new::<Self>()
+                .field::<ContractAddress>("address")
+                .field::<Vec<StorageEntry>>("storage_entries")
+                .build()
+        }
+    }
+
     /// A key-value entry of a storage diff.
     #[derive(Clone, Debug, Serialize, PartialEq, Eq)]
     #[cfg_attr(any(test, feature = "rpc-full-serde"), derive(serde::Deserialize))]
@@ -222,6 +263,15 @@ mod types {
         pub value: StorageValue,
     }
 
+    impl Schema for StorageEntry {
+        fn schema() -> serde_json::Value {
+            Object::new::<Self>()
+                .field::<StorageAddress>("key")
+                .field::<StorageValue>("value")
+                .build()
+        }
+    }
+
     impl From<crate::sequencer::reply::state_update::StorageDiff> for StorageEntry {
         fn from(diff: crate::sequencer::reply::state_update::StorageDiff) -> Self {
             Self {
@@ -240,6 +290,15 @@ mod types {
         pub class_hash: ClassHash,
     }
 
+    impl Schema for DeployedContract {
+        fn schema() -> serde_json::Value {
+            Object::new::<Self>()
+                .field::<ContractAddress>("address")
+                .field::<ClassHash>("class_hash")
+                .build()
+        }
+    }
+
     impl From<crate::sequencer::reply::state_update::DeployedContract> for DeployedContract {
         fn from(d: crate::sequencer::reply::state_update::DeployedContract) -> Self {
             Self {
@@ -267,6 +326,15 @@ mod types {
         pub nonce: ContractNonce,
     }
 
+    impl Schema for Nonce {
+        fn schema() -> serde_json::Value {
+            Object::new::<Self>()
+                .field::<ContractAddress>("contract_address")
+                .field::<ContractNonce>("nonce")
+                .build()
+        }
+    }
+
     impl From<crate::rpc::v01::types::reply::state_update::Nonce> for Nonce {
         fn from(n: crate::rpc::v01::types::reply::state_update::Nonce) -> Self {
             Self {
@@ -5,7 +5,7 @@ use stark_hash::StarkHash;
 use crate::core::{BlockId, ContractAddress, StorageAddress, StorageValue};
 use crate::rpc::v02::RpcContext;
 use crate::state::state_tree::{ContractsStateTree, GlobalStateTree};
-use crate::storage::{ContractsStateTable, StarknetBlocksBlockId, StarknetBlocksTable};
+use crate::storage::{StarknetBlocksBlockId, StorageBackend};
 
 #[derive(Deserialize, Debug, PartialEq, Eq)]
 pub struct GetStorageAtInput {
@@ -64,43 +64,61 @@ pub async fn get_storage_at(
 
         let tx = db.transaction().context("Creating database transaction")?;
 
-        // Use internal error to indicate that the process of querying for a particular block failed,
-        // which is not the same as being sure that the block is not in the db.
-        let global_root = StarknetBlocksTable::get_root(&tx, block_id)
-            .context("Get global root for block")?
-            // Since the db query succeeded in execution, we can now report if the block hash was indeed not found
-            // by using a dedicated error code from the RPC API spec
-            .ok_or(GetStorageAtError::BlockNotFound)?;
+        read_storage(&tx, block_id, input.contract_address, input.key)
+    });
 
-        let global_state_tree =
-            GlobalStateTree::load(&tx, global_root).context("Global state tree")?;
+    jh.await.context("Database read panic or shutting down")?
+}
 
-        let contract_state_hash = global_state_tree
-            .get(input.contract_address)
-            .context("Get contract state hash from global state tree")?
-            .ok_or(GetStorageAtError::ContractNotFound)?;
+/// Reads the value at `key` of the contract's storage from the given block's state.
+fn read_storage<S: StorageBackend>(
+    storage: S,
+    block_id: StarknetBlocksBlockId,
+    contract_address: ContractAddress,
+    key: StorageAddress,
+) -> Result<StorageValue, GetStorageAtError> {
+    // Use internal error to indicate that the process of querying for a particular block failed,
+    // which is not the same as being sure that the block is not in the db.
+    let global_root = storage
+        .block_root(block_id)
+        .context("Get global root for block")?
+        // Since the db query succeeded in execution, we can now report if the block hash was indeed not found
+        // by using a dedicated error code from the RPC API spec
+        .ok_or(GetStorageAtError::BlockNotFound)?;
 
-        let contract_state_root = ContractsStateTable::get_root(&tx, contract_state_hash)
-            .context("Get contract state root")?
-            .ok_or_else(|| {
-                anyhow::anyhow!(
-                    "Contract state root not found for contract state hash {}",
-                    contract_state_hash.0
-                )
-            })?;
+    let nodes = storage
+        .global_tree_nodes()
+        .context("Open global state tree storage")?;
+    let global_state_tree =
+        GlobalStateTree::with_storage(nodes, global_root).context("Global state tree")?;
 
-        let contract_state_tree = ContractsStateTree::load(&tx, contract_state_root)
-            .context("Load contract state tree")?;
+    let contract_state_hash = global_state_tree
+        .get(contract_address)
+        .context("Get contract state hash from global state tree")?
+        .ok_or(GetStorageAtError::ContractNotFound)?;
 
-        let storage_val = contract_state_tree
-            .get(input.key)
-            .context("Get value from contract state tree")?
-            .unwrap_or(StorageValue(StarkHash::ZERO));
+    let (contract_state_root, _) = storage
+        .contract_root_and_nonce(contract_state_hash)
+        .context("Get contract state root")?
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "Contract state root not found for contract state hash {}",
+                contract_state_hash.0
+            )
+        })?;
 
-        Ok(storage_val)
-    });
+    let nodes = storage
+        .contract_tree_nodes()
+        .context("Open contract state tree storage")?;
+    let contract_state_tree = ContractsStateTree::with_storage(nodes, contract_state_root)
+        .context("Load contract state tree")?;
 
-    jh.await.context("Database read panic or shutting down")?
+    let storage_val = contract_state_tree
+        .get(key)
+        .context("Get value from contract state tree")?
+        .unwrap_or(StorageValue(StarkHash::ZERO));
+
+    Ok(storage_val)
 }
 
 #[cfg(test)]
@@ -1,6 +1,7 @@
 use anyhow::Context;
 
 use crate::core::{BlockId, StarknetTransactionIndex};
+use crate::rpc::v02::common::{get_pinned_block_number, is_pinned};
 use crate::rpc::v02::types::reply::Transaction;
 use crate::rpc::v02::RpcContext;
 use crate::storage::{StarknetBlocksBlockId, StarknetBlocksTable, StarknetTransactionsTable};
@@ -39,35 +40,55 @@ pub async fn get_transaction_by_block_id_and_index(
     let storage = context.storage.clone();
     let span = tracing::Span::current();
 
-    let jh = tokio::task::spawn_blocking(move || {
-        let _g = span.enter();
-        let mut db = storage
-            .connection()
-            .context("Opening database connection")?;
-
-        let db_tx = db.transaction().context("Creating database transaction")?;
-
-        // Get the transaction from storage.
-        match StarknetTransactionsTable::get_transaction_at_block(&db_tx, block_id, index)
-            .context("Reading transaction from database")?
-        {
-            Some(transaction) => Ok(transaction.into()),
-            None => {
-                // We now need to check whether it was the block hash or transaction index which were invalid. We do this by checking if the block exists
-                // at all. If no, then the block hash is invalid. If yes, then the index is invalid.
-                //
-                // get_root is cheaper than querying the full block.
-                match StarknetBlocksTable::get_root(&db_tx, block_id)
-                    .context("Reading block from database")?
-                {
-                    Some(_) => Err(GetTransactionByBlockIdAndIndexError::InvalidTxnIndex),
-                    None => Err(GetTransactionByBlockIdAndIndexError::BlockNotFound),
-                }
+    let method = "starknet_getTransactionByBlockIdAndIndex";
+    let params = is_pinned(&input.block_id).then_some((input.block_id, index));
+    context
+        .cache
+        .get_or_insert_with(method, params, || async move {
+            let jh = tokio::task::spawn_blocking(
+                move || -> Result<_, GetTransactionByBlockIdAndIndexError> {
+                    let _g = span.enter();
+                    let mut db = storage
+                        .connection()
+                        .context("Opening database connection")?;
+
+                    let db_tx = db.transaction().context("Creating database transaction")?;
+
+                    let transaction = read_transaction(&db_tx, block_id, index)?;
+                    let block_number = get_pinned_block_number(&db_tx, input.block_id)?;
+
+                    Ok((transaction, block_number))
+                },
+            );
+
+            jh.await.context("Database read panic or shutting down")?
+        })
+        .await
+}
+
+fn read_transaction(
+    db_tx: &rusqlite::Transaction<'_>,
+    block_id: StarknetBlocksBlockId,
+    index: usize,
+) -> Result<Transaction, GetTransactionByBlockIdAndIndexError> {
+    // Get the transaction from storage.
+    match StarknetTransactionsTable::get_transaction_at_block(db_tx, block_id, index)
+        .context("Reading transaction from database")?
+    {
+        Some(transaction) => Ok(transaction.into()),
+        None => {
+            // We now need to check whether it was the block hash or transaction index which were invalid. We do this by checking if the block exists
+            // at all. If no, then the block hash is invalid. If yes, then the index is invalid.
+            //
+            // get_root is cheaper than querying the full block.
+            match StarknetBlocksTable::get_root(db_tx, block_id)
+                .context("Reading block from database")?
+            {
+                Some(_) => Err(GetTransactionByBlockIdAndIndexError::InvalidTxnIndex),
+                None => Err(GetTransactionByBlockIdAndIndexError::BlockNotFound),
             }
         }
-    });
-
-    jh.await.context("Database read panic or shutting down")?
+    }
 }
 
 async fn get_transaction_from_pending(
@@ -3,7 +3,7 @@ use anyhow::Context;
 use crate::core::StarknetTransactionHash;
 use crate::rpc::v02::types::reply::Transaction;
 use crate::rpc::v02::RpcContext;
-use crate::storage::StarknetTransactionsTable;
+use crate::storage::{StarknetBlocksTable, StarknetTransactionsTable};
 
 #[derive(serde::Deserialize, Debug, PartialEq, Eq)]
 pub struct GetTransactionByHashInput {
@@ -34,22 +34,41 @@ pub async fn get_transaction_by_hash(
     let storage = context.storage.clone();
     let span = tracing::Span::current();
 
-    let jh = tokio::task::spawn_blocking(move || {
-        let _g = span.enter();
-        let mut db = storage
-            .connection()
-            .context("Opening database connection")?;
-
-        let db_tx = db.transaction().context("Creating database transaction")?;
-
-        // Get the transaction from storage.
-        StarknetTransactionsTable::get_transaction(&db_tx, input.transaction_hash)
-            .context("Reading transaction from database")?
-            .ok_or(GetTransactionByHashError::TxnHashNotFound)
-            .map(|tx| tx.into())
-    });
-
-    jh.await.context("Database read panic or shutting down")?
+    let method = "starknet_getTransactionByHash";
+    context
+        .cache
+        .get_or_insert_with(method, Some(input.transaction_hash), || async move {
+            let jh =
+                tokio::task::spawn_blocking(move || -> Result<_, GetTransactionByHashError> {
+                    let _g = span.enter();
+                    let mut db = storage
+                        .connection()
+                        .context("Opening database connection")?;
+
+                    let db_tx = db.transaction().context("Creating database transaction")?;
+
+                    // Get the transaction from storage.
+                    let transaction =
+                        StarknetTransactionsTable::get_transaction(&db_tx, input.transaction_hash)
+                            .context("Reading transaction from database")?
+                            .ok_or(GetTransactionByHashError::TxnHashNotFound)?;
+
+                    // The transaction only changes if its block is reorged away.
+                    let block_hash =
+                        StarknetTransactionsTable::get_block_hash(&db_tx, input.transaction_hash)
+                            .context("Reading transaction block from database")?;
+                    let block_number = match block_hash {
+                        Some(hash) => StarknetBlocksTable::get_number(&db_tx, hash)
+                            .context("Reading block number from database")?,
+                        None => None,
+                    };
+
+                    Ok((transaction.into(), block_number))
+                });
+
+            jh.await.context("Database read panic or shutting down")?
+        })
+        .await
 }
 
 #[cfg(test)]
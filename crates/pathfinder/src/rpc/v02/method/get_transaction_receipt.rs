@@ -1,7 +1,7 @@
 use anyhow::Context;
 
 use crate::core::StarknetTransactionHash;
-use crate::rpc::v02::common::get_block_status;
+use crate::rpc::v02::common::{get_block_status, get_l1_reference, is_final};
 use crate::rpc::v02::RpcContext;
 use crate::storage::{StarknetBlocksTable, StarknetTransactionsTable};
 
@@ -40,49 +40,64 @@ pub async fn get_transaction_receipt(
     let storage = context.storage.clone();
     let span = tracing::Span::current();
 
-    let jh = tokio::task::spawn_blocking(move || {
-        let _g = span.enter();
-        let mut db = storage
-            .connection()
-            .context("Opening database connection")?;
-
-        let db_tx = db.transaction().context("Creating database transaction")?;
-
-        match StarknetTransactionsTable::get_transaction_with_receipt(
-            &db_tx,
-            input.transaction_hash,
-        )
-        .context("Reading transaction receipt from database")?
-        {
-            Some((transaction, receipt, block_hash)) => {
-                // We require the block status here as well..
-                let block_number = StarknetBlocksTable::get_number(&db_tx, block_hash)
-                    .context("Reading block from database")?
-                    .context("Block missing from database")?;
-                let block_status = get_block_status(&db_tx, block_number)?;
-
-                Ok(types::MaybePendingTransactionReceipt::Normal(
-                    types::TransactionReceipt::with_block_data(
-                        receipt,
-                        block_status,
-                        block_hash,
-                        block_number,
-                        transaction,
-                    ),
-                ))
-            }
-            None => Err(GetTransactionReceiptError::TxnHashNotFound),
-        }
-    });
+    let method = "starknet_getTransactionReceipt";
+    context
+        .cache
+        .get_or_insert_with(method, Some(input.transaction_hash), || async move {
+            let jh =
+                tokio::task::spawn_blocking(move || -> Result<_, GetTransactionReceiptError> {
+                    let _g = span.enter();
+                    let mut db = storage
+                        .connection()
+                        .context("Opening database connection")?;
+
+                    let db_tx = db.transaction().context("Creating database transaction")?;
+
+                    match StarknetTransactionsTable::get_transaction_with_receipt(
+                        &db_tx,
+                        input.transaction_hash,
+                    )
+                    .context("Reading transaction receipt from database")?
+                    {
+                        Some((transaction, receipt, block_hash)) => {
+                            // We require the block status here as well..
+                            let block_number = StarknetBlocksTable::get_number(&db_tx, block_hash)
+                                .context("Reading block from database")?
+                                .context("Block missing from database")?;
+                            let block_status = get_block_status(&db_tx, block_number)?;
+                            let l1_reference = get_l1_reference(&db_tx, block_number)?;
+
+                            // The status changes until the block is finalized on L1.
+                            let cacheable = is_final(&l1_reference).then_some(block_number);
+
+                            let receipt = types::MaybePendingTransactionReceipt::Normal(
+                                types::TransactionReceipt::with_block_data(
+                                    receipt,
+                                    block_status,
+                                    block_hash,
+                                    block_number,
+                                    transaction,
+                                    l1_reference,
+                                ),
+                            );
+
+                            Ok((receipt, cacheable))
+                        }
+                        None => Err(GetTransactionReceiptError::TxnHashNotFound),
+                    }
+                });
 
-    jh.await.context("Database read panic or shutting down")?
+            jh.await.context("Database read panic or shutting down")?
+        })
+        .await
 }
 
 mod types {
     use serde::Serialize;
     use serde_with::serde_as;
 
-    use crate::rpc::v02::types::reply::BlockStatus;
+    use crate::rpc::v02::openrpc::{enumeration, hex, tagged, untagged, Object, Schema};
+    use crate::rpc::v02::types::reply::{BlockStatus, L1Reference};
     use crate::{
         core::{
             ContractAddress, EthereumAddress, EventData, EventKey, Fee, L1ToL2MessagePayloadElem,
@@ -102,6 +117,15 @@ mod types {
         Pending(PendingTransactionReceipt),
     }
 
+    impl Schema for MaybePendingTransactionReceipt {
+        fn schema() -> serde_json::Value {
+            untagged::<Self>(vec![
+                TransactionReceipt::schema(),
+                PendingTransactionReceipt::schema(),
+            ])
+        }
+    }
+
     /// Non-pending L2 transaction receipt as returned by the RPC API.
     #[derive(Clone, Debug, Serialize, PartialEq, Eq)]
     #[cfg_attr(any(test, feature = "rpc-full-serde"), derive(serde::Deserialize))]
@@ -119,6 +143,21 @@ mod types {
         DeployAccount(DeployAccountTransactionReceipt),
     }
 
+    impl Schema for TransactionReceipt {
+        fn schema() -> serde_json::Value {
+            tagged::<Self>(
+                "type",
+                vec![
+                    ("INVOKE", InvokeTransactionReceipt::schema()),
+                    ("DECLARE", DeclareTransactionReceipt::schema()),
+                    ("L1_HANDLER", L1HandlerTransactionReceipt::schema()),
+                    ("DEPLOY", DeployTransactionReceipt::schema()),
+                    ("DEPLOY_ACCOUNT", DeployAccountTransactionReceipt::schema()),
+                ],
+            )
+        }
+    }
+
     #[derive(Clone, Debug, Serialize, PartialEq, Eq)]
     #[cfg_attr(any(test, feature = "rpc-full-serde"), derive(serde::Deserialize))]
     pub struct InvokeTransactionReceipt {
@@ -126,6 +165,14 @@ mod types {
         pub common: CommonTransactionReceiptProperties,
     }
 
+    impl Schema for InvokeTransactionReceipt {
+        fn schema() -> serde_json::Value {
+            Object::new::<Self>()
+                .flatten::<CommonTransactionReceiptProperties>()
+                .build()
+        }
+    }
+
     #[serde_as]
     #[derive(Clone, Debug, Serialize, PartialEq, Eq)]
     #[cfg_attr(any(test, feature = "rpc-full-serde"), derive(serde::Deserialize))]
@@ -138,6 +185,24 @@ mod types {
         pub block_number: StarknetBlockNumber,
         pub messages_sent: Vec<MessageToL1>,
         pub events: Vec<Event>,
+        /// The L1 state update which accepted the block, if it is accepted on L1.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub l1_reference: Option<L1Reference>,
+    }
+
+    impl Schema for CommonTransactionReceiptProperties {
+        fn schema() -> serde_json::Value {
+            Object::new::<Self>()
+                .field::<StarknetTransactionHash>("transaction_hash")
+                .field_as("actual_fee", hex(), true)
+                .field::<TransactionStatus>("status")
+                .field::<StarknetBlockHash>("block_hash")
+                .field::<StarknetBlockNumber>("block_number")
+                .field::<Vec<MessageToL1>>("messages_sent")
+                .field::<Vec<Event>>("events")
+                .field::<Option<L1Reference>>("l1_reference")
+                .build()
+        }
     }
 
     #[derive(Clone, Debug, Serialize, PartialEq, Eq)]
@@ -147,6 +212,14 @@ mod types {
         pub common: CommonTransactionReceiptProperties,
     }
 
+    impl Schema for L1HandlerTransactionReceipt {
+        fn schema() -> serde_json::Value {
+            Object::new::<Self>()
+                .flatten::<CommonTransactionReceiptProperties>()
+                .build()
+        }
+    }
+
     #[derive(Clone, Debug, Serialize, PartialEq, Eq)]
     #[cfg_attr(any(test, feature = "rpc-full-serde"), derive(serde::Deserialize))]
     pub struct DeployTransactionReceipt {
@@ -156,6 +229,15 @@ mod types {
         pub contract_address: ContractAddress,
     }
 
+    impl Schema for DeployTransactionReceipt {
+        fn schema() -> serde_json::Value {
+            Object::new::<Self>()
+                .flatten::<CommonTransactionReceiptProperties>()
+                .field::<ContractAddress>("contract_address")
+                .build()
+        }
+    }
+
     #[derive(Clone, Debug, Serialize, PartialEq, Eq)]
     #[cfg_attr(any(test, feature = "rpc-full-serde"), derive(serde::Deserialize))]
     pub struct DeployAccountTransactionReceipt {
@@ -165,6 +247,15 @@ mod types {
         pub contract_address: ContractAddress,
     }
 
+    impl Schema for DeployAccountTransactionReceipt {
+        fn schema() -> serde_json::Value {
+            Object::new::<Self>()
+                .flatten::<CommonTransactionReceiptProperties>()
+                .field::<ContractAddress>("contract_address")
+                .build()
+        }
+    }
+
     #[derive(Clone, Debug, Serialize, PartialEq, Eq)]
     #[cfg_attr(any(test, feature = "rpc-full-serde"), derive(serde::Deserialize))]
     pub struct DeclareTransactionReceipt {
@@ -172,6 +263,14 @@ mod types {
         pub common: CommonTransactionReceiptProperties,
     }
 
+    impl Schema for DeclareTransactionReceipt {
+        fn schema() -> serde_json::Value {
+            Object::new::<Self>()
+                .flatten::<CommonTransactionReceiptProperties>()
+                .build()
+        }
+    }
+
     impl TransactionReceipt {
         pub fn with_block_data(
             receipt: crate::sequencer::reply::transaction::Receipt,
@@ -179,6 +278,7 @@ mod types {
             block_hash: StarknetBlockHash,
             block_number: StarknetBlockNumber,
             transaction: crate::sequencer::reply::transaction::Transaction,
+            l1_reference: Option<L1Reference>,
         ) -> Self {
             let common = CommonTransactionReceiptProperties {
                 transaction_hash: receipt.transaction_hash,
@@ -194,6 +294,7 @@ mod types {
                     .map(MessageToL1::from)
                     .collect(),
                 events: receipt.events.into_iter().map(Event::from).collect(),
+                l1_reference,
             };
 
             use crate::sequencer::reply::transaction::Transaction::*;
@@ -233,12 +334,38 @@ mod types {
         L1Handler(PendingL1HandlerTransactionReceipt),
     }
 
+    impl Schema for PendingTransactionReceipt {
+        fn schema() -> serde_json::Value {
+            tagged::<Self>(
+                "type",
+                vec![
+                    ("INVOKE", PendingInvokeTransactionReceipt::schema()),
+                    ("DECLARE", PendingDeclareTransactionReceipt::schema()),
+                    ("DEPLOY", PendingDeployTransactionReceipt::schema()),
+                    (
+                        "DEPLOY_ACCOUNT",
+                        PendingDeployAccountTransactionReceipt::schema(),
+                    ),
+                    ("L1_HANDLER", PendingL1HandlerTransactionReceipt::schema()),
+                ],
+            )
+        }
+    }
+
     #[derive(Clone, Debug, Serialize, PartialEq, Eq)]
     #[cfg_attr(any(test, feature = "rpc-full-serde"), derive(serde::Deserialize))]
     pub struct PendingInvokeTransactionReceipt {
         #[serde(flatten)]
         pub common: CommonPendingTransactionReceiptProperties,
     }
+
+    impl Schema for PendingInvokeTransactionReceipt {
+        fn schema() -> serde_json::Value {
+            Object::new::<Self>()
+                .flatten::<CommonPendingTransactionReceiptProperties>()
+                .build()
+        }
+    }
     #[serde_as]
     #[derive(Clone, Debug, Serialize, PartialEq, Eq)]
     #[cfg_attr(any(test, feature = "rpc-full-serde"), derive(serde::Deserialize))]
@@ -250,6 +377,17 @@ mod types {
         pub events: Vec<Event>,
     }
 
+    impl Schema for CommonPendingTransactionReceiptProperties {
+        fn schema() -> serde_json::Value {
+            Object::new::<Self>()
+                .field::<StarknetTransactionHash>("transaction_hash")
+                .field_as("actual_fee", hex(), true)
+                .field::<Vec<MessageToL1>>("messages_sent")
+                .field::<Vec<Event>>("events")
+                .build()
+        }
+    }
+
     #[derive(Clone, Debug, Serialize, PartialEq, Eq)]
     #[cfg_attr(any(test, feature = "rpc-full-serde"), derive(serde::Deserialize))]
     pub struct PendingDeclareTransactionReceipt {
@@ -257,6 +395,14 @@ mod types {
         pub common: CommonPendingTransactionReceiptProperties,
     }
 
+    impl Schema for PendingDeclareTransactionReceipt {
+        fn schema() -> serde_json::Value {
+            Object::new::<Self>()
+                .flatten::<CommonPendingTransactionReceiptProperties>()
+                .build()
+        }
+    }
+
     #[derive(Clone, Debug, Serialize, PartialEq, Eq)]
     #[cfg_attr(any(test, feature = "rpc-full-serde"), derive(serde::Deserialize))]
     pub struct PendingDeployTransactionReceipt {
@@ -266,6 +412,15 @@ mod types {
         pub contract_address: ContractAddress,
     }
 
+    impl Schema for PendingDeployTransactionReceipt {
+        fn schema() -> serde_json::Value {
+            Object::new::<Self>()
+                .flatten::<CommonPendingTransactionReceiptProperties>()
+                .field::<ContractAddress>("contract_address")
+                .build()
+        }
+    }
+
     #[derive(Clone, Debug, Serialize, PartialEq, Eq)]
     #[cfg_attr(any(test, feature = "rpc-full-serde"), derive(serde::Deserialize))]
     pub struct PendingDeployAccountTransactionReceipt {
@@ -275,6 +430,15 @@ mod types {
         pub contract_address: ContractAddress,
     }
 
+    impl Schema for PendingDeployAccountTransactionReceipt {
+        fn schema() -> serde_json::Value {
+            Object::new::<Self>()
+                .flatten::<CommonPendingTransactionReceiptProperties>()
+                .field::<ContractAddress>("contract_address")
+                .build()
+        }
+    }
+
     #[derive(Clone, Debug, Serialize, PartialEq, Eq)]
     #[cfg_attr(any(test, feature = "rpc-full-serde"), derive(serde::Deserialize))]
     pub struct PendingL1HandlerTransactionReceipt {
@@ -282,6 +446,14 @@ mod types {
         pub common: CommonPendingTransactionReceiptProperties,
     }
 
+    impl Schema for PendingL1HandlerTransactionReceipt {
+        fn schema() -> serde_json::Value {
+            Object::new::<Self>()
+                .flatten::<CommonPendingTransactionReceiptProperties>()
+                .build()
+        }
+    }
+
     impl PendingTransactionReceipt {
         pub fn from(
             receipt: crate::sequencer::reply::transaction::Receipt,
@@ -328,6 +500,15 @@ mod types {
         pub payload: Vec<L2ToL1MessagePayloadElem>,
     }
 
+    impl Schema for MessageToL1 {
+        fn schema() -> serde_json::Value {
+            Object::new::<Self>()
+                .field_as("to_address", hex(), true)
+                .field::<Vec<L2ToL1MessagePayloadElem>>("payload")
+                .build()
+        }
+    }
+
     impl From<L2ToL1Message> for MessageToL1 {
         fn from(msg: L2ToL1Message) -> Self {
             Self {
@@ -367,6 +548,16 @@ mod types {
         pub data: Vec<EventData>,
     }
 
+    impl Schema for Event {
+        fn schema() -> serde_json::Value {
+            Object::new::<Self>()
+                .field::<ContractAddress>("from_address")
+                .field::<Vec<EventKey>>("keys")
+                .field::<Vec<EventData>>("data")
+                .build()
+        }
+    }
+
     impl From<crate::sequencer::reply::transaction::Event> for Event {
         fn from(e: crate::sequencer::reply::transaction::Event) -> Self {
             Self {
@@ -392,6 +583,12 @@ mod types {
         Rejected,
     }
 
+    impl Schema for TransactionStatus {
+        fn schema() -> serde_json::Value {
+            enumeration::<Self>(&["PENDING", "ACCEPTED_ON_L2", "ACCEPTED_ON_L1", "REJECTED"])
+        }
+    }
+
     impl From<BlockStatus> for TransactionStatus {
         fn from(status: BlockStatus) -> Self {
             match status {
@@ -433,6 +630,7 @@ mod types {
                             keys: vec![EventKey(starkhash!("e7"))],
                             data: vec![EventData(starkhash!("e8"))],
                         }],
+                        l1_reference: None,
                     }
                 }
             }
@@ -631,6 +829,7 @@ mod tests {
                             )),
                             keys: vec![EventKey(starkhash_bytes!(b"event 0 key"))],
                         }],
+                        l1_reference: None,
                     }
                 }
             ))
@@ -1,6 +1,12 @@
 use crate::{
     core::{StarknetBlockHash, StarknetBlockNumber},
-    rpc::{serde::StarknetBlockNumberAsHexStr, v02::RpcContext},
+    rpc::{
+        serde::StarknetBlockNumberAsHexStr,
+        v02::{
+            openrpc::{hex, untagged, Object, Schema},
+            RpcContext,
+        },
+    },
 };
 
 use serde::Serialize;
@@ -50,6 +56,15 @@ impl Serialize for SyncingOuput {
     }
 }
 
+impl Schema for SyncingOuput {
+    fn schema() -> serde_json::Value {
+        untagged::<Self>(vec![
+            serde_json::json!({ "enum": ["false"] }),
+            SyncingStatus::schema(),
+        ])
+    }
+}
+
 #[serde_with::serde_as]
 #[derive(Clone, Copy, Debug, Serialize, PartialEq, Eq)]
 #[cfg_attr(any(test, feature = "rpc-full-serde"), derive(serde::Deserialize))]
@@ -65,6 +80,19 @@ pub struct SyncingStatus {
     highest_block_hash: StarknetBlockHash,
 }
 
+impl Schema for SyncingStatus {
+    fn schema() -> serde_json::Value {
+        Object::new::<Self>()
+            .field_as("starting_block_num", hex(), true)
+            .field_as("current_block_num", hex(), true)
+            .field_as("highest_block_num", hex(), true)
+            .field::<StarknetBlockHash>("starting_block_hash")
+            .field::<StarknetBlockHash>("current_block_hash")
+            .field::<StarknetBlockHash>("highest_block_hash")
+            .build()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::SyncingOuput;
@@ -0,0 +1,1188 @@
+//! [OpenRPC](https://spec.open-rpc.org) document of the v0.2 API, served by `rpc.discover`.
+//!
+//! The parameters of a method are described from its input type. A probing [Deserializer] walks
+//! into the type along a path of field names, as its [Deserialize](serde::Deserialize)
+//! implementation asks for them, and records the shape of what it finds at the end of the path.
+//! Whether a field defaults when it is missing is found by feeding its struct a sequence of
+//! [Sample] values which ends right before the field.
+//!
+//! Outputs can only be serialized, which cannot be probed, so results are described by the
+//! [Schema] implementations written next to their types.
+use std::cell::{Cell, RefCell};
+
+use serde::de::{self, DeserializeOwned, DeserializeSeed, Deserializer, Visitor};
+use serde_json::{json, Value};
+
+use super::RpcContext;
+
+const OPENRPC_VERSION: &str = "1.2.6";
+/// Path element of the items of a sequence.
+const ITEMS: &str = "[]";
+/// Limits how deep types are described, which stops recursive types from recursing forever.
+const MAX_DEPTH: usize = 8;
+/// Pattern of hex encoded numbers, such as felts.
+const HEX_PATTERN: &str = "^0x[a-fA-F0-9]+$";
+
+/// Description of a registered method.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Method {
+    name: &'static str,
+    params: Vec<Param>,
+    result: Value,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Param {
+    name: &'static str,
+    required: bool,
+    schema: Value,
+}
+
+impl Method {
+    /// Describes method `name` taking the fields of `Input` as its parameters.
+    pub fn new<Input: DeserializeOwned, Output: Schema>(name: &'static str) -> Self {
+        let fields = match probe::<Input>(&[]).shape {
+            Some(Shape::Struct { fields, .. }) => fields,
+            _ => &[],
+        };
+        let mut path = Vec::new();
+        let params = fields
+            .iter()
+            .enumerate()
+            .map(|(index, &field)| {
+                let (schema, optional) = nested::<Input>(&mut path, field);
+                Param {
+                    name: field,
+                    required: !optional && !defaulted::<Input>(&path, index),
+                    schema,
+                }
+            })
+            .collect();
+
+        Self {
+            name,
+            params,
+            result: Output::schema(),
+        }
+    }
+
+    /// Describes method `name` taking no parameters.
+    pub fn without_params<Output: Schema>(name: &'static str) -> Self {
+        Self {
+            name,
+            params: Vec::new(),
+            result: Output::schema(),
+        }
+    }
+
+    fn to_json(&self) -> Value {
+        let params = self
+            .params
+            .iter()
+            .map(|param| {
+                json!({
+                    "name": param.name,
+                    "required": param.required,
+                    "schema": param.schema,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        json!({
+            "name": self.name,
+            "params": params,
+            "result": {
+                "name": "result",
+                "schema": self.result,
+            },
+        })
+    }
+}
+
+/// Returns the OpenRPC document of `methods`.
+pub fn document(title: &str, version: &str, methods: &[Method]) -> Value {
+    json!({
+        "openrpc": OPENRPC_VERSION,
+        "info": {
+            "title": title,
+            "version": version,
+        },
+        "methods": methods.iter().map(Method::to_json).collect::<Vec<_>>(),
+    })
+}
+
+/// Registers `rpc.discover`, which returns the OpenRPC document of `methods` and itself.
+pub fn register_discover(
+    module: &mut jsonrpsee::RpcModule<RpcContext>,
+    title: &str,
+    version: &str,
+    mut methods: Vec<Method>,
+) -> anyhow::Result<()> {
+    use anyhow::Context;
+
+    methods.push(Method {
+        name: "rpc.discover",
+        params: Vec::new(),
+        result: Object::new::<Document>()
+            .field_as("openrpc", json!({ "type": "string" }), true)
+            .field_as("info", json!({ "type": "object" }), true)
+            .field_as("methods", json!({ "type": "array" }), true)
+            .build(),
+    });
+    let document = document(title, version, &methods);
+
+    module
+        .register_method("rpc.discover", move |_, _| Ok(document.clone()))
+        .context("Registering rpc.discover")?;
+
+    Ok(())
+}
+
+/// The OpenRPC document, which is only named for its schema.
+struct Document;
+
+/// JSON schema of an output, describing how it is serialized.
+pub trait Schema {
+    /// Whether fields of this type may be missing, which is the case for [Option]s as they are
+    /// usually skipped when [None].
+    const OPTIONAL: bool = false;
+
+    fn schema() -> Value;
+}
+
+/// Schema of hex encoded numbers, such as felts.
+pub fn hex() -> Value {
+    json!({ "type": "string", "pattern": HEX_PATTERN })
+}
+
+/// Schema of `T`, a unit-only enum serialized as the names of its variants.
+pub fn enumeration<T>(variants: &[&str]) -> Value {
+    json!({ "title": title::<T>(), "enum": variants })
+}
+
+/// Schema of `T`, an untagged enum serialized as the first of its `variants` matching its value,
+/// which may match others as well.
+pub fn untagged<T>(variants: Vec<Value>) -> Value {
+    json!({ "title": title::<T>(), "anyOf": variants })
+}
+
+/// Schema of `T`, an internally tagged enum whose variants are objects naming them in `tag`.
+pub fn tagged<T>(tag: &str, variants: Vec<(&str, Value)>) -> Value {
+    let variants = variants
+        .into_iter()
+        .map(|(name, schema)| with_tag(schema, tag, name))
+        .collect::<Vec<_>>();
+    json!({ "title": title::<T>(), "oneOf": variants })
+}
+
+/// Adds `tag` naming the variant to the object `schema`, or to each of its own variants.
+fn with_tag(mut schema: Value, tag: &str, name: &str) -> Value {
+    for key in ["oneOf", "anyOf"] {
+        if let Some(Value::Array(variants)) = schema.get_mut(key) {
+            for variant in variants {
+                *variant = with_tag(variant.take(), tag, name);
+            }
+            return schema;
+        }
+    }
+
+    schema["properties"][tag] = json!({ "enum": [name] });
+    if let Some(required) = schema["required"].as_array_mut() {
+        required.push(json!(tag));
+    }
+    schema
+}
+
+/// Builds the schema of a struct serialized as an object.
+pub struct Object {
+    title: String,
+    properties: serde_json::Map<String, Value>,
+    required: Vec<Value>,
+}
+
+impl Object {
+    pub fn new<T>() -> Self {
+        Self {
+            title: title::<T>(),
+            properties: Default::default(),
+            required: Vec::new(),
+        }
+    }
+
+    /// Adds `field`, serialized as its type `F`.
+    pub fn field<F: Schema>(self, field: &str) -> Self {
+        self.field_as(field, F::schema(), !F::OPTIONAL)
+    }
+
+    /// Adds `field`, serialized as `schema` instead of as its type, such as through `serde_as`.
+    pub fn field_as(mut self, field: &str, schema: Value, required: bool) -> Self {
+        self.properties.insert(field.to_owned(), schema);
+        if required {
+            self.required.push(json!(field));
+        }
+        self
+    }
+
+    /// Adds the fields of `F`, which is flattened into the object.
+    pub fn flatten<F: Schema>(mut self) -> Self {
+        let mut schema = F::schema();
+        if let Value::Object(properties) = schema["properties"].take() {
+            self.properties.extend(properties);
+        }
+        if let Value::Array(required) = schema["required"].take() {
+            self.required.extend(required);
+        }
+        self
+    }
+
+    pub fn build(self) -> Value {
+        json!({
+            "title": self.title,
+            "type": "object",
+            "properties": self.properties,
+            "required": self.required,
+            "additionalProperties": false,
+        })
+    }
+}
+
+impl Schema for String {
+    fn schema() -> Value {
+        json!({ "type": "string" })
+    }
+}
+
+impl Schema for u64 {
+    fn schema() -> Value {
+        json!({ "type": "integer", "minimum": 0 })
+    }
+}
+
+impl<T: Schema> Schema for Vec<T> {
+    fn schema() -> Value {
+        json!({ "type": "array", "items": T::schema() })
+    }
+}
+
+impl<T: Schema> Schema for Option<T> {
+    const OPTIONAL: bool = true;
+
+    fn schema() -> Value {
+        json!({ "anyOf": [T::schema(), { "type": "null" }] })
+    }
+}
+
+/// Implements [Schema] for types serialized as hex encoded numbers.
+macro_rules! hex_schema {
+    ($($ty:ty),* $(,)?) => {
+        $(
+            impl Schema for $ty {
+                fn schema() -> Value {
+                    json!({ "title": title::<$ty>(), "type": "string", "pattern": HEX_PATTERN })
+                }
+            }
+        )*
+    };
+}
+
+hex_schema! {
+    stark_hash::StarkHash,
+    crate::core::CallParam,
+    crate::core::CallResultValue,
+    crate::core::ClassHash,
+    crate::core::ConstructorParam,
+    crate::core::ContractAddress,
+    crate::core::ContractAddressSalt,
+    crate::core::ContractNonce,
+    crate::core::EntryPoint,
+    crate::core::EventCommitment,
+    crate::core::EventData,
+    crate::core::EventKey,
+    crate::core::GlobalRoot,
+    crate::core::L1ToL2MessagePayloadElem,
+    crate::core::L2ToL1MessagePayloadElem,
+    crate::core::MessageHash,
+    crate::core::SequencerAddress,
+    crate::core::StarknetBlockHash,
+    crate::core::StarknetTransactionHash,
+    crate::core::StorageAddress,
+    crate::core::StorageValue,
+    crate::core::TransactionCommitment,
+    crate::core::TransactionNonce,
+    crate::core::TransactionSignatureElem,
+}
+
+impl Schema for crate::core::StarknetBlockNumber {
+    fn schema() -> Value {
+        json!({ "title": title::<Self>(), "type": "integer", "minimum": 0 })
+    }
+}
+
+impl Schema for crate::core::StarknetBlockTimestamp {
+    fn schema() -> Value {
+        json!({ "title": title::<Self>(), "type": "integer", "minimum": 0 })
+    }
+}
+
+/// Title of `T` in its schema.
+fn title<T>() -> String {
+    short_type_name(std::any::type_name::<T>())
+}
+
+/// Shape of a type, as requested from the [Deserializer] by its implementation.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Shape {
+    Boolean,
+    Integer,
+    Number,
+    String,
+    Null,
+    Seq,
+    Map,
+    Struct {
+        name: &'static str,
+        fields: &'static [&'static str],
+    },
+    Enum {
+        name: &'static str,
+        variants: &'static [&'static str],
+    },
+    UnitVariant,
+    /// Self-describing, such as internally tagged or untagged enums.
+    Any,
+}
+
+/// What [Probe] found at the end of its path.
+struct Probed {
+    /// [None] if the path does not exist.
+    shape: Option<Shape>,
+    /// Whether the value at the path is an [Option].
+    optional: bool,
+}
+
+/// Returns what is found at `path` in `T`.
+fn probe<T: DeserializeOwned>(path: &[&'static str]) -> Probed {
+    let shape = RefCell::new(None);
+    let optional = Cell::new(false);
+
+    // Probing always fails, after having recorded the shape at the end of the path.
+    let _ = T::deserialize(Probe {
+        path,
+        shape: &shape,
+        optional: &optional,
+        missing: None,
+        defaulted: &Cell::new(false),
+    });
+
+    Probed {
+        shape: shape.into_inner(),
+        optional: optional.get(),
+    }
+}
+
+/// Returns whether the field at `index` of the struct at `path` in `T` defaults when missing.
+///
+/// The struct is fed a sequence of [Sample] values which ends right before the field, which fails
+/// with the field's index unless it defaults. Fields are reported as not defaulting when the
+/// sequence fails otherwise, such as when the preceding fields cannot be sampled.
+fn defaulted<T: DeserializeOwned>(path: &[&'static str], index: usize) -> bool {
+    let defaulted = Cell::new(false);
+
+    let _ = T::deserialize(Probe {
+        path,
+        shape: &RefCell::new(None),
+        optional: &Cell::new(false),
+        missing: Some(index),
+        defaulted: &defaulted,
+    });
+
+    defaulted.get()
+}
+
+/// Returns the JSON schema of `step` within `path` in `T`, and whether it is optional.
+fn nested<T: DeserializeOwned>(path: &mut Vec<&'static str>, step: &'static str) -> (Value, bool) {
+    path.push(step);
+    let probed = probe::<T>(path);
+    let schema = match probed.shape {
+        Some(shape) if path.len() <= MAX_DEPTH => schema::<T>(path, shape),
+        _ => json!({}),
+    };
+    path.pop();
+
+    (schema, probed.optional)
+}
+
+/// Returns the JSON schema of the `shape` at `path` in `T`.
+fn schema<T: DeserializeOwned>(path: &mut Vec<&'static str>, shape: Shape) -> Value {
+    match shape {
+        Shape::Boolean => json!({ "type": "boolean" }),
+        Shape::Integer => json!({ "type": "integer" }),
+        Shape::Number => json!({ "type": "number" }),
+        Shape::String => json!({ "type": "string" }),
+        Shape::Null | Shape::UnitVariant => json!({ "type": "null" }),
+        Shape::Seq => {
+            let (items, _) = nested::<T>(path, ITEMS);
+            json!({ "type": "array", "items": items })
+        }
+        Shape::Map => json!({ "type": "object" }),
+        Shape::Struct { name, fields } => {
+            let mut properties = serde_json::Map::new();
+            let mut required = Vec::new();
+            for (index, &field) in fields.iter().enumerate() {
+                let (schema, optional) = nested::<T>(path, field);
+                if !optional && !defaulted::<T>(path, index) {
+                    required.push(field);
+                }
+                properties.insert(field.to_owned(), schema);
+            }
+
+            json!({
+                "title": name,
+                "type": "object",
+                "properties": properties,
+                "required": required,
+            })
+        }
+        Shape::Enum { name, variants } => {
+            // Externally tagged: unit variants by their name, others as an object keyed by it.
+            let variants = variants
+                .iter()
+                .map(|&variant| {
+                    path.push(variant);
+                    let shape = probe::<T>(path).shape;
+                    path.pop();
+
+                    match shape {
+                        Some(Shape::UnitVariant) => json!({ "enum": [variant] }),
+                        _ => {
+                            let (schema, _) = nested::<T>(path, variant);
+                            json!({
+                                "type": "object",
+                                "properties": { variant: schema },
+                                "required": [variant],
+                            })
+                        }
+                    }
+                })
+                .collect::<Vec<_>>();
+
+            json!({ "title": name, "oneOf": variants })
+        }
+        Shape::Any => json!({}),
+    }
+}
+
+/// Strips the module paths from a [type name](std::any::type_name).
+fn short_type_name(name: &str) -> String {
+    let mut short = String::with_capacity(name.len());
+    // Start of the path being read.
+    let mut start = 0;
+    for c in name.chars() {
+        match c {
+            ':' => short.truncate(start),
+            c if c.is_alphanumeric() || c == '_' => short.push(c),
+            c => {
+                short.push(c);
+                start = short.len();
+            }
+        }
+    }
+    short
+}
+
+/// Error ending a probe, or a [Sample] sequence which is too short.
+#[derive(Debug, Default)]
+struct Abort {
+    /// Length of the sequence which was too short.
+    invalid_length: Option<usize>,
+}
+
+impl std::fmt::Display for Abort {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Probe ended")
+    }
+}
+
+impl std::error::Error for Abort {}
+
+impl de::Error for Abort {
+    fn custom<T: std::fmt::Display>(_: T) -> Self {
+        Abort::default()
+    }
+
+    fn invalid_length(len: usize, _: &dyn de::Expected) -> Self {
+        Abort {
+            invalid_length: Some(len),
+        }
+    }
+}
+
+/// [Deserializer] walking along `path`, which records the [Shape] at its end.
+#[derive(Clone, Copy)]
+struct Probe<'a> {
+    path: &'a [&'static str],
+    shape: &'a RefCell<Option<Shape>>,
+    optional: &'a Cell<bool>,
+    /// Index of the field missing from the struct at the end of the path, to find whether it
+    /// defaults instead of recording the struct's shape.
+    missing: Option<usize>,
+    defaulted: &'a Cell<bool>,
+}
+
+impl<'a> Probe<'a> {
+    fn at(self, path: &'a [&'static str]) -> Self {
+        Self { path, ..self }
+    }
+
+    fn end<T>(self, shape: Shape) -> Result<T, Abort> {
+        if self.path.is_empty() {
+            self.shape.borrow_mut().get_or_insert(shape);
+        }
+        Err(Abort::default())
+    }
+
+    fn seq<'de, V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Abort> {
+        match self.path.split_first() {
+            None => self.end(Shape::Seq),
+            Some((&ITEMS, rest)) => visitor.visit_seq(Element(Some(self.at(rest)))),
+            Some(_) => Err(Abort::default()),
+        }
+    }
+}
+
+macro_rules! leaves {
+    ($($method:ident => $shape:ident),* $(,)?) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, _: V) -> Result<V::Value, Abort> {
+                self.end(Shape::$shape)
+            }
+        )*
+    };
+}
+
+impl<'de, 'a> Deserializer<'de> for Probe<'a> {
+    type Error = Abort;
+
+    leaves! {
+        deserialize_any => Any,
+        deserialize_ignored_any => Any,
+        deserialize_bool => Boolean,
+        deserialize_i8 => Integer,
+        deserialize_i16 => Integer,
+        deserialize_i32 => Integer,
+        deserialize_i64 => Integer,
+        deserialize_i128 => Integer,
+        deserialize_u8 => Integer,
+        deserialize_u16 => Integer,
+        deserialize_u32 => Integer,
+        deserialize_u64 => Integer,
+        deserialize_u128 => Integer,
+        deserialize_f32 => Number,
+        deserialize_f64 => Number,
+        deserialize_char => String,
+        deserialize_str => String,
+        deserialize_string => String,
+        deserialize_bytes => String,
+        deserialize_byte_buf => String,
+        deserialize_identifier => String,
+        deserialize_unit => Null,
+        deserialize_map => Map,
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Abort> {
+        if self.path.is_empty() {
+            self.optional.set(true);
+        }
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _: V,
+    ) -> Result<V::Value, Abort> {
+        self.end(Shape::Null)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Abort> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Abort> {
+        self.seq(visitor)
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Abort> {
+        self.seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Abort> {
+        self.seq(visitor)
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Abort> {
+        match (self.path.split_first(), self.missing) {
+            (None, Some(missing)) => {
+                // Once the field is missing, the ones after it are too, so the sequence fails at
+                // a later index if the field defaults, or succeeds if they all do.
+                let defaulted = match visitor.visit_seq(Samples::new(missing)) {
+                    Ok(_) => true,
+                    Err(Abort { invalid_length }) => {
+                        matches!(invalid_length, Some(at) if at > missing)
+                    }
+                };
+                self.defaulted.set(defaulted);
+                Err(Abort::default())
+            }
+            (None, None) => self.end(Shape::Struct { name, fields }),
+            (Some((&field, rest)), _) => visitor.visit_map(Entry(Some(field), self.at(rest))),
+        }
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Abort> {
+        match self.path.split_first() {
+            None => self.end(Shape::Enum { name, variants }),
+            Some((&variant, rest)) => visitor.visit_enum(Variant(variant, self.at(rest))),
+        }
+    }
+}
+
+/// Map of a single entry, whose value is probed.
+struct Entry<'a>(Option<&'static str>, Probe<'a>);
+
+impl<'de, 'a> de::MapAccess<'de> for Entry<'a> {
+    type Error = Abort;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Abort> {
+        match self.0.take() {
+            Some(key) => seed
+                .deserialize(de::value::StrDeserializer::new(key))
+                .map(Some),
+            None => Err(Abort::default()),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Abort> {
+        seed.deserialize(self.1)
+    }
+}
+
+/// Sequence of a single element, which is probed.
+struct Element<'a>(Option<Probe<'a>>);
+
+impl<'de, 'a> de::SeqAccess<'de> for Element<'a> {
+    type Error = Abort;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, Abort> {
+        match self.0.take() {
+            Some(probe) => seed.deserialize(probe).map(Some),
+            None => Err(Abort::default()),
+        }
+    }
+}
+
+/// Variant of an enum, whose content is probed.
+struct Variant<'a>(&'static str, Probe<'a>);
+
+impl<'de, 'a> de::EnumAccess<'de> for Variant<'a> {
+    type Error = Abort;
+    type Variant = Probe<'a>;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, Probe<'a>), Abort> {
+        let variant = seed.deserialize(de::value::StrDeserializer::new(self.0))?;
+        Ok((variant, self.1))
+    }
+}
+
+impl<'de, 'a> de::VariantAccess<'de> for Probe<'a> {
+    type Error = Abort;
+
+    fn unit_variant(self) -> Result<(), Abort> {
+        self.end(Shape::UnitVariant)
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, Abort> {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, Abort> {
+        self.seq(visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Abort> {
+        self.deserialize_struct("", fields, visitor)
+    }
+}
+
+/// Limits how deep [Sample] values are built, which stops recursive types from recursing forever.
+const MAX_SAMPLE_DEPTH: usize = 8;
+
+/// [Deserializer] of a plain value of any type, such as zero or an empty sequence, to fill in the
+/// fields preceding a missing one.
+#[derive(Clone, Copy)]
+struct Sample {
+    depth: usize,
+}
+
+impl Sample {
+    fn nested(self) -> Result<Self, Abort> {
+        match self.depth {
+            depth if depth < MAX_SAMPLE_DEPTH => Ok(Self { depth: depth + 1 }),
+            _ => Err(Abort::default()),
+        }
+    }
+}
+
+macro_rules! samples {
+    ($($method:ident => $visit:ident($($value:expr)?)),* $(,)?) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Abort> {
+                visitor.$visit($($value)?)
+            }
+        )*
+    };
+}
+
+impl<'de> Deserializer<'de> for Sample {
+    type Error = Abort;
+
+    samples! {
+        // Strings are sampled as a hex encoded zero, which is also a valid felt.
+        deserialize_any => visit_str("0x0"),
+        deserialize_ignored_any => visit_unit(),
+        deserialize_bool => visit_bool(false),
+        deserialize_i8 => visit_u64(0),
+        deserialize_i16 => visit_u64(0),
+        deserialize_i32 => visit_u64(0),
+        deserialize_i64 => visit_u64(0),
+        deserialize_i128 => visit_u64(0),
+        deserialize_u8 => visit_u64(0),
+        deserialize_u16 => visit_u64(0),
+        deserialize_u32 => visit_u64(0),
+        deserialize_u64 => visit_u64(0),
+        deserialize_u128 => visit_u64(0),
+        deserialize_f32 => visit_f64(0.0),
+        deserialize_f64 => visit_f64(0.0),
+        deserialize_char => visit_char('0'),
+        deserialize_str => visit_str("0x0"),
+        deserialize_string => visit_str("0x0"),
+        deserialize_bytes => visit_bytes(&[]),
+        deserialize_byte_buf => visit_bytes(&[]),
+        deserialize_identifier => visit_str("0x0"),
+        deserialize_unit => visit_unit(),
+        deserialize_option => visit_none(),
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Abort> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Abort> {
+        visitor.visit_newtype_struct(self.nested()?)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Abort> {
+        visitor.visit_seq(Samples::complete(0, self.nested()?))
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, len: usize, visitor: V) -> Result<V::Value, Abort> {
+        visitor.visit_seq(Samples::complete(len, self.nested()?))
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Abort> {
+        self.deserialize_tuple(len, visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Abort> {
+        visitor.visit_map(SampleFields(&[], self.nested()?))
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Abort> {
+        visitor.visit_map(SampleFields(fields, self.nested()?))
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Abort> {
+        match variants.first() {
+            Some(&variant) => visitor.visit_enum(SampleVariant(variant, self.nested()?)),
+            None => Err(Abort::default()),
+        }
+    }
+}
+
+/// Sequence of [Sample] values, which ends at `end`.
+struct Samples {
+    next: usize,
+    end: usize,
+    sample: Sample,
+}
+
+impl Samples {
+    /// Samples the fields of a struct preceding the one at `missing`.
+    fn new(missing: usize) -> Self {
+        Self::complete(missing, Sample { depth: 0 })
+    }
+
+    fn complete(len: usize, sample: Sample) -> Self {
+        Self {
+            next: 0,
+            end: len,
+            sample,
+        }
+    }
+}
+
+impl<'de> de::SeqAccess<'de> for Samples {
+    type Error = Abort;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, Abort> {
+        if self.next == self.end {
+            return Ok(None);
+        }
+        self.next += 1;
+        seed.deserialize(self.sample).map(Some)
+    }
+}
+
+/// Map of the fields of a struct, whose values are [Sample]s.
+struct SampleFields(&'static [&'static str], Sample);
+
+impl<'de> de::MapAccess<'de> for SampleFields {
+    type Error = Abort;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Abort> {
+        match self.0.split_first() {
+            Some((&field, rest)) => {
+                self.0 = rest;
+                seed.deserialize(de::value::StrDeserializer::new(field))
+                    .map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Abort> {
+        seed.deserialize(self.1)
+    }
+}
+
+/// First variant of an enum, whose content is sampled.
+struct SampleVariant(&'static str, Sample);
+
+impl<'de> de::EnumAccess<'de> for SampleVariant {
+    type Error = Abort;
+    type Variant = Sample;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, Sample), Abort> {
+        let variant = seed.deserialize(de::value::StrDeserializer::new(self.0))?;
+        Ok((variant, self.1))
+    }
+}
+
+impl<'de> de::VariantAccess<'de> for Sample {
+    type Error = Abort;
+
+    fn unit_variant(self) -> Result<(), Abort> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, Abort> {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, len: usize, visitor: V) -> Result<V::Value, Abort> {
+        self.deserialize_tuple(len, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Abort> {
+        self.deserialize_struct("", fields, visitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[allow(dead_code)]
+    #[derive(Deserialize)]
+    struct Input {
+        id: Id,
+        #[serde(default)]
+        filter: Option<Filter>,
+        values: Vec<u64>,
+    }
+
+    #[allow(dead_code)]
+    #[derive(Deserialize)]
+    #[serde(deny_unknown_fields)]
+    struct Filter {
+        keys: Vec<String>,
+        #[serde(rename = "limit")]
+        chunk_size: Option<usize>,
+        #[serde(default)]
+        enabled: bool,
+    }
+
+    #[allow(dead_code)]
+    #[derive(Deserialize)]
+    enum Id {
+        #[serde(rename = "number")]
+        Number(u64),
+        #[serde(rename = "latest")]
+        Latest,
+    }
+
+    #[test]
+    fn params() {
+        let method = Method::new::<Input, Vec<u64>>("method");
+
+        assert_eq!(
+            method.result,
+            json!({ "type": "array", "items": { "type": "integer", "minimum": 0 } })
+        );
+        assert_eq!(
+            method.to_json(),
+            json!({
+                "name": "method",
+                "params": [
+                    {
+                        "name": "id",
+                        "required": true,
+                        "schema": {
+                            "title": "Id",
+                            "oneOf": [
+                                {
+                                    "type": "object",
+                                    "properties": { "number": { "type": "integer" } },
+                                    "required": ["number"],
+                                },
+                                { "enum": ["latest"] },
+                            ],
+                        },
+                    },
+                    {
+                        "name": "filter",
+                        "required": false,
+                        "schema": {
+                            "title": "Filter",
+                            "type": "object",
+                            "properties": {
+                                "keys": { "type": "array", "items": { "type": "string" } },
+                                "limit": { "type": "integer" },
+                                "enabled": { "type": "boolean" },
+                            },
+                            "required": ["keys"],
+                        },
+                    },
+                    {
+                        "name": "values",
+                        "required": true,
+                        "schema": { "type": "array", "items": { "type": "integer" } },
+                    },
+                ],
+                "result": {
+                    "name": "result",
+                    "schema": { "type": "array", "items": { "type": "integer", "minimum": 0 } },
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn defaulted_fields() {
+        #[allow(dead_code)]
+        #[derive(Deserialize)]
+        struct Defaults {
+            required: Id,
+            #[serde(default)]
+            defaulted: Vec<u64>,
+            #[serde(default = "one")]
+            defaulted_with: u64,
+            last: String,
+        }
+
+        fn one() -> u64 {
+            1
+        }
+
+        assert!(!defaulted::<Defaults>(&[], 0));
+        assert!(defaulted::<Defaults>(&[], 1));
+        assert!(defaulted::<Defaults>(&[], 2));
+        assert!(!defaulted::<Defaults>(&[], 3));
+        // within a nested struct
+        assert!(!defaulted::<Input>(&["filter"], 0));
+        assert!(defaulted::<Input>(&["filter"], 2));
+    }
+
+    #[test]
+    fn result_schemas() {
+        use crate::rpc::v02::types::reply::Transaction;
+
+        let schema = Transaction::schema();
+        assert_eq!(schema["title"], "Transaction");
+        let variants = schema["oneOf"].as_array().unwrap();
+
+        // the versions of invoke transactions are nested in their variant
+        let invoke = variants
+            .iter()
+            .find(|variant| variant["oneOf"].is_array())
+            .unwrap();
+        let v0 = &invoke["oneOf"][0];
+        assert_eq!(v0["title"], "InvokeTransactionV0");
+        assert_eq!(v0["properties"]["type"], json!({ "enum": ["INVOKE"] }));
+        assert_eq!(v0["properties"]["version"], json!({ "enum": ["0x0"] }));
+        // flattened fields
+        let required = v0["required"].as_array().unwrap();
+        assert!(required.contains(&json!("transaction_hash")));
+        assert!(required.contains(&json!("contract_address")));
+        assert!(required.contains(&json!("type")));
+        assert_eq!(v0["additionalProperties"], false);
+
+        assert_eq!(
+            Option::<crate::core::ClassHash>::schema(),
+            json!({
+                "anyOf": [
+                    { "title": "ClassHash", "type": "string", "pattern": HEX_PATTERN },
+                    { "type": "null" },
+                ],
+            })
+        );
+    }
+
+    #[test]
+    fn short_type_names() {
+        assert_eq!(short_type_name("u64"), "u64");
+        assert_eq!(
+            short_type_name("pathfinder_lib::rpc::v02::types::reply::Block"),
+            "Block"
+        );
+        assert_eq!(
+            short_type_name("alloc::vec::Vec<pathfinder_lib::core::ClassHash>"),
+            "Vec<ClassHash>"
+        );
+    }
+
+    #[tokio::test]
+    async fn discover() {
+        use crate::rpc::v02::{register_all_methods, RpcContext};
+
+        let mut module = jsonrpsee::RpcModule::new(RpcContext::for_tests());
+        register_all_methods(&mut module).unwrap();
+
+        let (response, _) = module
+            .raw_json_request(r#"{"jsonrpc":"2.0","id":0,"method":"rpc.discover"}"#)
+            .await
+            .unwrap();
+        let response: Value = serde_json::from_str(&response).unwrap();
+        let methods = response["result"]["methods"].as_array().unwrap();
+
+        // every registered method is described
+        let described = methods
+            .iter()
+            .map(|method| method["name"].as_str().unwrap())
+            .collect::<std::collections::HashSet<_>>();
+        let registered = module
+            .method_names()
+            .collect::<std::collections::HashSet<_>>();
+        assert_eq!(described, registered);
+
+        let get_events = methods
+            .iter()
+            .find(|method| method["name"] == "starknet_getEvents")
+            .unwrap();
+        let filter = &get_events["params"][0];
+        assert_eq!(filter["name"], "filter");
+        assert_eq!(filter["schema"]["title"], "EventFilter");
+        let required = filter["schema"]["required"].as_array().unwrap();
+        assert!(required.contains(&json!("chunk_size")));
+        assert!(!required.contains(&json!("from_block")));
+        // defaults without being an Option
+        assert!(!required.contains(&json!("keys")));
+        assert_eq!(get_events["result"]["schema"]["title"], "GetEventsResult");
+        assert_eq!(
+            get_events["result"]["schema"]["required"],
+            json!(["events"])
+        );
+
+        let declare = methods
+            .iter()
+            .find(|method| method["name"] == "starknet_addDeclareTransaction")
+            .unwrap();
+        assert_eq!(
+            declare["params"],
+            json!([
+                { "name": "declare_transaction", "required": true, "schema": {} },
+                { "name": "token", "required": false, "schema": { "type": "string" } },
+            ])
+        );
+    }
+}
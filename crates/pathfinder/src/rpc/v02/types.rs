@@ -7,7 +7,7 @@ pub use class::*;
 pub mod request {
     use crate::{
         core::{
-            CallParam, ClassHash, ConstructorParam, ContractAddress, ContractAddressSalt,
+            CallParam, CasmHash, ClassHash, ConstructorParam, ContractAddress, ContractAddressSalt,
             EntryPoint, Fee, TransactionNonce, TransactionSignatureElem, TransactionVersion,
         },
         rpc::serde::{FeeAsHexStr, TransactionVersionAsHexStr},
@@ -51,6 +51,9 @@ pub mod request {
 
         pub contract_class: super::ContractClass,
         pub sender_address: ContractAddress,
+        /// The hash of the compiled CASM class, required when declaring a Sierra class.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub compiled_class_hash: Option<CasmHash>,
     }
 
     #[serde_as]
@@ -64,7 +67,7 @@ pub mod request {
         pub constructor_calldata: Vec<ConstructorParam>,
 
         /// The class of the contract that will be deployed.
-        pub contract_class: super::ContractClass,
+        pub contract_class: super::CairoContractClass,
     }
 
     #[serde_as]
@@ -196,14 +199,14 @@ pub mod request {
         mod serde {
             use super::super::*;
             use crate::{
-                rpc::v02::types::{ContractClass, ContractEntryPoints},
+                rpc::v02::types::{CairoContractClass, ContractEntryPoints},
                 starkhash,
             };
             use pretty_assertions::assert_eq;
 
             #[test]
             fn broadcasted_transaction() {
-                let contract_class = ContractClass {
+                let contract_class = CairoContractClass {
                     program: "program".to_owned(),
                     entry_points_by_type: ContractEntryPoints {
                         constructor: vec![],
@@ -218,8 +221,9 @@ pub mod request {
                         version: TransactionVersion(web3::types::H256::from_low_u64_be(0x0)),
                         signature: vec![TransactionSignatureElem(starkhash!("07"))],
                         nonce: TransactionNonce(starkhash!("08")),
-                        contract_class: contract_class.clone(),
+                        contract_class: contract_class.clone().into(),
                         sender_address: ContractAddress::new_or_panic(starkhash!("0a")),
+                        compiled_class_hash: None,
                     }),
                     BroadcastedTransaction::Deploy(BroadcastedDeployTransaction {
                         version: TransactionVersion(web3::types::H256::from_low_u64_be(0x0)),
@@ -284,6 +288,7 @@ pub mod reply {
             TransactionVersion,
         },
         rpc::serde::{FeeAsHexStr, TransactionVersionAsHexStr},
+        rpc::v02::openrpc::{enumeration, hex, tagged, Object, Schema},
         sequencer,
     };
     use serde::Serialize;
@@ -308,6 +313,21 @@ pub mod reply {
         L1Handler(L1HandlerTransaction),
     }
 
+    impl Schema for Transaction {
+        fn schema() -> serde_json::Value {
+            tagged::<Self>(
+                "type",
+                vec![
+                    ("DECLARE", DeclareTransaction::schema()),
+                    ("INVOKE", InvokeTransaction::schema()),
+                    ("DEPLOY", DeployTransaction::schema()),
+                    ("DEPLOY_ACCOUNT", DeployAccountTransaction::schema()),
+                    ("L1_HANDLER", L1HandlerTransaction::schema()),
+                ],
+            )
+        }
+    }
+
     impl Transaction {
         pub fn hash(&self) -> StarknetTransactionHash {
             match self {
@@ -335,6 +355,18 @@ pub mod reply {
         pub nonce: TransactionNonce,
     }
 
+    impl Schema for CommonTransactionProperties {
+        fn schema() -> serde_json::Value {
+            Object::new::<Self>()
+                .field::<StarknetTransactionHash>("transaction_hash")
+                .field_as("max_fee", hex(), true)
+                .field_as("version", hex(), true)
+                .field::<Vec<TransactionSignatureElem>>("signature")
+                .field::<TransactionNonce>("nonce")
+                .build()
+        }
+    }
+
     #[serde_as]
     #[derive(Clone, Debug, Serialize, PartialEq, Eq)]
     #[cfg_attr(any(test, feature = "rpc-full-serde"), derive(serde::Deserialize))]
@@ -347,6 +379,16 @@ pub mod reply {
         pub sender_address: ContractAddress,
     }
 
+    impl Schema for DeclareTransaction {
+        fn schema() -> serde_json::Value {
+            Object::new::<Self>()
+                .flatten::<CommonTransactionProperties>()
+                .field::<ClassHash>("class_hash")
+                .field::<ContractAddress>("sender_address")
+                .build()
+        }
+    }
+
     #[serde_as]
     #[derive(Clone, Debug, Serialize, PartialEq, Eq)]
     #[cfg_attr(any(test, feature = "rpc-full-serde"), derive(serde::Deserialize))]
@@ -360,6 +402,17 @@ pub mod reply {
         pub class_hash: ClassHash,
     }
 
+    impl Schema for DeployAccountTransaction {
+        fn schema() -> serde_json::Value {
+            Object::new::<Self>()
+                .flatten::<CommonTransactionProperties>()
+                .field::<ContractAddressSalt>("contract_address_salt")
+                .field::<Vec<CallParam>>("constructor_calldata")
+                .field::<ClassHash>("class_hash")
+                .build()
+        }
+    }
+
     #[derive(Clone, Debug, Serialize, PartialEq, Eq)]
     #[serde(tag = "version")]
     pub enum InvokeTransaction {
@@ -369,6 +422,18 @@ pub mod reply {
         V1(InvokeTransactionV1),
     }
 
+    impl Schema for InvokeTransaction {
+        fn schema() -> serde_json::Value {
+            tagged::<Self>(
+                "version",
+                vec![
+                    ("0x0", InvokeTransactionV0::schema()),
+                    ("0x1", InvokeTransactionV1::schema()),
+                ],
+            )
+        }
+    }
+
     #[cfg(any(test, feature = "rpc-full-serde"))]
     impl<'de> serde::Deserialize<'de> for InvokeTransaction {
         fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
@@ -422,6 +487,17 @@ pub mod reply {
         pub calldata: Vec<CallParam>,
     }
 
+    impl Schema for InvokeTransactionV0 {
+        fn schema() -> serde_json::Value {
+            Object::new::<Self>()
+                .flatten::<CommonInvokeTransactionProperties>()
+                .field::<ContractAddress>("contract_address")
+                .field::<EntryPoint>("entry_point_selector")
+                .field::<Vec<CallParam>>("calldata")
+                .build()
+        }
+    }
+
     #[serde_as]
     #[derive(Clone, Debug, Serialize, PartialEq, Eq)]
     #[cfg_attr(any(test, feature = "rpc-full-serde"), derive(serde::Deserialize))]
@@ -434,6 +510,16 @@ pub mod reply {
         pub calldata: Vec<CallParam>,
     }
 
+    impl Schema for InvokeTransactionV1 {
+        fn schema() -> serde_json::Value {
+            Object::new::<Self>()
+                .flatten::<CommonInvokeTransactionProperties>()
+                .field::<ContractAddress>("sender_address")
+                .field::<Vec<CallParam>>("calldata")
+                .build()
+        }
+    }
+
     #[serde_as]
     #[derive(Clone, Debug, Serialize, PartialEq, Eq)]
     #[cfg_attr(any(test, feature = "rpc-full-serde"), derive(serde::Deserialize))]
@@ -449,6 +535,17 @@ pub mod reply {
         pub nonce: TransactionNonce,
     }
 
+    impl Schema for CommonInvokeTransactionProperties {
+        fn schema() -> serde_json::Value {
+            Object::new::<Self>()
+                .field::<StarknetTransactionHash>("transaction_hash")
+                .field_as("max_fee", hex(), true)
+                .field::<Vec<TransactionSignatureElem>>("signature")
+                .field::<TransactionNonce>("nonce")
+                .build()
+        }
+    }
+
     #[serde_as]
     #[derive(Clone, Debug, Serialize, PartialEq, Eq)]
     #[cfg_attr(any(test, feature = "rpc-full-serde"), derive(serde::Deserialize))]
@@ -465,6 +562,18 @@ pub mod reply {
         pub constructor_calldata: Vec<ConstructorParam>,
     }
 
+    impl Schema for DeployTransaction {
+        fn schema() -> serde_json::Value {
+            Object::new::<Self>()
+                .field::<StarknetTransactionHash>("transaction_hash")
+                .field::<ClassHash>("class_hash")
+                .field_as("version", hex(), true)
+                .field::<ContractAddressSalt>("contract_address_salt")
+                .field::<Vec<ConstructorParam>>("constructor_calldata")
+                .build()
+        }
+    }
+
     #[serde_as]
     #[derive(Clone, Debug, Serialize, PartialEq, Eq)]
     #[cfg_attr(any(test, feature = "rpc-full-serde"), derive(serde::Deserialize))]
@@ -482,6 +591,19 @@ pub mod reply {
         pub calldata: Vec<CallParam>,
     }
 
+    impl Schema for L1HandlerTransaction {
+        fn schema() -> serde_json::Value {
+            Object::new::<Self>()
+                .field::<StarknetTransactionHash>("transaction_hash")
+                .field_as("version", hex(), true)
+                .field::<TransactionNonce>("nonce")
+                .field::<ContractAddress>("contract_address")
+                .field::<EntryPoint>("entry_point_selector")
+                .field::<Vec<CallParam>>("calldata")
+                .build()
+        }
+    }
+
     impl TryFrom<sequencer::reply::Transaction> for Transaction {
         type Error = anyhow::Error;
 
@@ -598,6 +720,12 @@ pub mod reply {
         Rejected,
     }
 
+    impl Schema for BlockStatus {
+        fn schema() -> serde_json::Value {
+            enumeration::<Self>(&["PENDING", "ACCEPTED_ON_L2", "ACCEPTED_ON_L1", "REJECTED"])
+        }
+    }
+
     impl From<sequencer::reply::Status> for BlockStatus {
         fn from(status: sequencer::reply::Status) -> Self {
             match status {
@@ -614,6 +742,65 @@ pub mod reply {
         }
     }
 
+    /// The Ethereum state update log which accepted a StarkNet block on L1.
+    #[derive(Clone, Debug, Serialize, PartialEq, Eq)]
+    #[cfg_attr(any(test, feature = "rpc-full-serde"), derive(serde::Deserialize))]
+    #[serde(deny_unknown_fields)]
+    pub struct L1Reference {
+        pub block_hash: web3::types::H256,
+        pub block_number: u64,
+        pub transaction_hash: web3::types::H256,
+        pub log_index: u64,
+        pub finality: L1Finality,
+    }
+
+    impl Schema for L1Reference {
+        fn schema() -> serde_json::Value {
+            Object::new::<Self>()
+                .field_as("block_hash", hex(), true)
+                .field::<u64>("block_number")
+                .field_as("transaction_hash", hex(), true)
+                .field::<u64>("log_index")
+                .field::<L1Finality>("finality")
+                .build()
+        }
+    }
+
+    impl L1Reference {
+        pub fn new(origin: &crate::ethereum::EthOrigin, finality: L1Finality) -> Self {
+            Self {
+                block_hash: origin.block.hash.0,
+                block_number: origin.block.number.0,
+                transaction_hash: origin.transaction.hash.0,
+                log_index: origin.log_index.0,
+                finality,
+            }
+        }
+    }
+
+    /// Whether the L1 block of an [L1Reference] can still be reorged.
+    #[derive(Copy, Clone, Debug, Serialize, PartialEq, Eq)]
+    #[cfg_attr(any(test, feature = "rpc-full-serde"), derive(serde::Deserialize))]
+    pub enum L1Finality {
+        /// The L1 block is neither at or below the `safe` block nor the configured number of
+        /// confirmations deep, and could still be reorged.
+        #[serde(rename = "UNCONFIRMED")]
+        Unconfirmed,
+        /// The L1 block is at or below the `safe` block or the configured number of confirmations
+        /// deep, but is not yet finalized.
+        #[serde(rename = "SAFE")]
+        Safe,
+        /// The L1 block is finalized.
+        #[serde(rename = "FINALIZED")]
+        Finalized,
+    }
+
+    impl Schema for L1Finality {
+        fn schema() -> serde_json::Value {
+            enumeration::<Self>(&["UNCONFIRMED", "SAFE", "FINALIZED"])
+        }
+    }
+
     #[cfg(test)]
     mod tests {
         macro_rules! fixture {
@@ -1,11 +1,47 @@
 use crate::rpc::serde::U64AsHexStr;
+use crate::rpc::v02::openrpc::{enumeration, hex, untagged, Object, Schema};
+use crate::state::ClassFormat;
 
 use anyhow::Context;
 use serde::{Deserialize, Serialize};
 use stark_hash::StarkHash;
 
+/// A class definition as exposed over RPC, either a Sierra or a Cairo 0.x class.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum ContractClass {
+    Sierra(SierraContractClass),
+    Cairo(CairoContractClass),
+}
+
+impl Schema for ContractClass {
+    fn schema() -> serde_json::Value {
+        untagged::<Self>(vec![
+            SierraContractClass::schema(),
+            CairoContractClass::schema(),
+        ])
+    }
+}
+
 impl ContractClass {
     pub fn from_definition_bytes(data: &[u8]) -> anyhow::Result<ContractClass> {
+        match ClassFormat::of_definition(data)? {
+            ClassFormat::Cairo => CairoContractClass::from_definition_bytes(data).map(Self::Cairo),
+            ClassFormat::Sierra => {
+                SierraContractClass::from_definition_bytes(data).map(Self::Sierra)
+            }
+        }
+    }
+}
+
+impl From<CairoContractClass> for ContractClass {
+    fn from(class: CairoContractClass) -> Self {
+        Self::Cairo(class)
+    }
+}
+
+impl CairoContractClass {
+    pub fn from_definition_bytes(data: &[u8]) -> anyhow::Result<CairoContractClass> {
         let mut json = serde_json::from_slice::<serde_json::Value>(data).context("Parsing json")?;
         let json_obj = json
             .as_object_mut()
@@ -40,7 +76,7 @@ impl ContractClass {
         let encoded_program = base64::encode(compressed_program);
         let program = encoded_program;
 
-        Ok(ContractClass {
+        Ok(CairoContractClass {
             program,
             entry_points_by_type: entry,
             abi,
@@ -49,12 +85,85 @@ impl ContractClass {
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
-pub struct ContractClass {
+pub struct CairoContractClass {
     pub program: String,
     pub entry_points_by_type: ContractEntryPoints,
     pub abi: Option<Vec<ContractAbiEntry>>,
 }
 
+impl Schema for CairoContractClass {
+    fn schema() -> serde_json::Value {
+        Object::new::<Self>()
+            .field::<String>("program")
+            .field::<ContractEntryPoints>("entry_points_by_type")
+            // serialized as null rather than skipped
+            .field_as("abi", Option::<Vec<ContractAbiEntry>>::schema(), true)
+            .build()
+    }
+}
+
+impl SierraContractClass {
+    pub fn from_definition_bytes(data: &[u8]) -> anyhow::Result<SierraContractClass> {
+        serde_json::from_slice(data).context("Parsing Sierra class definition")
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct SierraContractClass {
+    pub sierra_program: Vec<StarkHash>,
+    pub contract_class_version: String,
+    pub entry_points_by_type: SierraEntryPoints,
+    /// Unlike Cairo 0.x classes, the ABI of a Sierra class is an opaque string.
+    pub abi: String,
+}
+
+impl Schema for SierraContractClass {
+    fn schema() -> serde_json::Value {
+        Object::new::<Self>()
+            .field::<Vec<StarkHash>>("sierra_program")
+            .field::<String>("contract_class_version")
+            .field::<SierraEntryPoints>("entry_points_by_type")
+            .field::<String>("abi")
+            .build()
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+#[serde(deny_unknown_fields)]
+pub struct SierraEntryPoints {
+    pub constructor: Vec<SierraEntryPoint>,
+    pub external: Vec<SierraEntryPoint>,
+    pub l1_handler: Vec<SierraEntryPoint>,
+}
+
+impl Schema for SierraEntryPoints {
+    fn schema() -> serde_json::Value {
+        Object::new::<Self>()
+            .field::<Vec<SierraEntryPoint>>("CONSTRUCTOR")
+            .field::<Vec<SierraEntryPoint>>("EXTERNAL")
+            .field::<Vec<SierraEntryPoint>>("L1_HANDLER")
+            .build()
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct SierraEntryPoint {
+    pub selector: StarkHash,
+    pub function_idx: u64,
+}
+
+impl Schema for SierraEntryPoint {
+    fn schema() -> serde_json::Value {
+        Object::new::<Self>()
+            .field::<StarkHash>("selector")
+            .field::<u64>("function_idx")
+            .build()
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 #[serde(deny_unknown_fields)]
@@ -64,6 +173,16 @@ pub struct ContractEntryPoints {
     pub l1_handler: Vec<ContractEntryPoint>,
 }
 
+impl Schema for ContractEntryPoints {
+    fn schema() -> serde_json::Value {
+        Object::new::<Self>()
+            .field::<Vec<ContractEntryPoint>>("CONSTRUCTOR")
+            .field::<Vec<ContractEntryPoint>>("EXTERNAL")
+            .field::<Vec<ContractEntryPoint>>("L1_HANDLER")
+            .build()
+    }
+}
+
 #[serde_with::serde_as]
 #[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
 #[serde(deny_unknown_fields)]
@@ -73,6 +192,15 @@ pub struct ContractEntryPoint {
     pub selector: StarkHash,
 }
 
+impl Schema for ContractEntryPoint {
+    fn schema() -> serde_json::Value {
+        Object::new::<Self>()
+            .field_as("offset", hex(), true)
+            .field::<StarkHash>("selector")
+            .build()
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
 #[serde(untagged)]
 #[serde(deny_unknown_fields)]
@@ -82,6 +210,16 @@ pub enum ContractAbiEntry {
     Struct(StructAbiEntry),
 }
 
+impl Schema for ContractAbiEntry {
+    fn schema() -> serde_json::Value {
+        untagged::<Self>(vec![
+            FunctionAbiEntry::schema(),
+            EventAbiEntry::schema(),
+            StructAbiEntry::schema(),
+        ])
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
 #[serde(rename_all = "lowercase")]
 #[serde(deny_unknown_fields)]
@@ -89,6 +227,12 @@ pub enum StructAbiType {
     Struct,
 }
 
+impl Schema for StructAbiType {
+    fn schema() -> serde_json::Value {
+        enumeration::<Self>(&["struct"])
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
 #[serde(rename_all = "lowercase")]
 #[serde(deny_unknown_fields)]
@@ -96,6 +240,12 @@ pub enum EventAbiType {
     Event,
 }
 
+impl Schema for EventAbiType {
+    fn schema() -> serde_json::Value {
+        enumeration::<Self>(&["event"])
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
 #[serde(deny_unknown_fields)]
@@ -108,6 +258,12 @@ pub enum FunctionAbiType {
     Constructor,
 }
 
+impl Schema for FunctionAbiType {
+    fn schema() -> serde_json::Value {
+        enumeration::<Self>(&["function", "l1_handler", "constructor"])
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
 #[serde(deny_unknown_fields)]
 pub struct StructAbiEntry {
@@ -117,6 +273,17 @@ pub struct StructAbiEntry {
     members: Vec<StructMember>,
 }
 
+impl Schema for StructAbiEntry {
+    fn schema() -> serde_json::Value {
+        Object::new::<Self>()
+            .field::<StructAbiType>("type")
+            .field::<String>("name")
+            .field::<u64>("size")
+            .field::<Vec<StructMember>>("members")
+            .build()
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
 #[serde(deny_unknown_fields)]
 pub struct StructMember {
@@ -129,6 +296,16 @@ pub struct StructMember {
     offset: u64,
 }
 
+impl Schema for StructMember {
+    fn schema() -> serde_json::Value {
+        Object::new::<Self>()
+            .field::<String>("name")
+            .field::<String>("type")
+            .field::<u64>("offset")
+            .build()
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
 #[serde(deny_unknown_fields)]
 pub struct EventAbiEntry {
@@ -154,6 +331,17 @@ pub struct EventAbiEntry {
     _outputs: Option<Vec<TypedParameter>>,
 }
 
+impl Schema for EventAbiEntry {
+    fn schema() -> serde_json::Value {
+        Object::new::<Self>()
+            .field::<EventAbiType>("type")
+            .field::<String>("name")
+            .field::<Option<Vec<TypedParameter>>>("keys")
+            .field::<Option<Vec<TypedParameter>>>("data")
+            .build()
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
 #[serde(deny_unknown_fields)]
 pub struct FunctionAbiEntry {
@@ -173,9 +361,29 @@ pub struct FunctionAbiEntry {
     _state_mutability: Option<String>,
 }
 
+impl Schema for FunctionAbiEntry {
+    fn schema() -> serde_json::Value {
+        Object::new::<Self>()
+            .field::<FunctionAbiType>("type")
+            .field::<String>("name")
+            .field::<Option<Vec<TypedParameter>>>("inputs")
+            .field::<Option<Vec<TypedParameter>>>("outputs")
+            .build()
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
 #[serde(deny_unknown_fields)]
 pub struct TypedParameter {
     name: String,
     r#type: String,
 }
+
+impl Schema for TypedParameter {
+    fn schema() -> serde_json::Value {
+        Object::new::<Self>()
+            .field::<String>("name")
+            .field::<String>("type")
+            .build()
+    }
+}
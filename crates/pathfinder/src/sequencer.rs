@@ -1,20 +1,24 @@
 //! StarkNet L2 sequencer client.
 mod builder;
 pub mod error;
+mod json_rpc;
 mod metrics;
 pub mod reply;
 pub mod request;
 
-use self::request::add_transaction::ContractDefinition;
+use self::request::add_transaction::{ClassDefinition, ContractDefinition};
 use crate::{
     core::{
-        BlockId, CallParam, Chain, ClassHash, ConstructorParam, ContractAddress,
-        ContractAddressSalt, EntryPoint, Fee, StarknetTransactionHash, StorageAddress,
-        StorageValue, TransactionNonce, TransactionSignatureElem, TransactionVersion,
+        BlockId, CallParam, CasmHash, Chain, ClassHash, ConstructorParam, ContractAddress,
+        ContractAddressSalt, EntryPoint, Fee, StarknetBlockHash, StarknetTransactionHash,
+        StorageAddress, StorageValue, TransactionNonce, TransactionSignatureElem,
+        TransactionVersion,
     },
     rpc::v01::types::BlockHashOrTag,
     sequencer::error::SequencerError,
 };
+pub use json_rpc::JsonRpcClient;
+
 use reqwest::Url;
 use std::{fmt::Debug, result::Result, time::Duration};
 
@@ -30,6 +34,11 @@ pub trait ClientApi {
 
     async fn class_by_hash(&self, class_hash: ClassHash) -> Result<bytes::Bytes, SequencerError>;
 
+    async fn compiled_class_by_hash(
+        &self,
+        class_hash: ClassHash,
+    ) -> Result<bytes::Bytes, SequencerError>;
+
     async fn class_hash_at(
         &self,
         contract_address: ContractAddress,
@@ -75,8 +84,9 @@ pub trait ClientApi {
         max_fee: Fee,
         signature: Vec<TransactionSignatureElem>,
         nonce: TransactionNonce,
-        contract_definition: ContractDefinition,
+        contract_definition: ClassDefinition,
         sender_address: ContractAddress,
+        compiled_class_hash: Option<CasmHash>,
         token: Option<String>,
     ) -> Result<reply::add_transaction::DeclareResponse, SequencerError>;
 
@@ -133,6 +143,7 @@ impl Client {
             Chain::Testnet => Url::parse("https://alpha4.starknet.io/").unwrap(),
             Chain::Testnet2 => Url::parse("https://alpha4-2.starknet.io/").unwrap(),
             Chain::Integration => Url::parse("https://external.integration.starknet.io").unwrap(),
+            Chain::Custom(spec) => spec.feeder_gateway_url.clone(),
         };
 
         Self::with_url(url)
@@ -161,6 +172,18 @@ impl Client {
             INTEGRATION_GENESIS_HASH, MAINNET_GENESIS_HASH, TESTNET2_GENESIS_HASH,
             TESTNET_GENESIS_HASH,
         };
+
+        match self.genesis_hash().await? {
+            testnet if testnet == TESTNET_GENESIS_HASH => Ok(Chain::Testnet),
+            testnet2 if testnet2 == TESTNET2_GENESIS_HASH => Ok(Chain::Testnet2),
+            mainnet if mainnet == MAINNET_GENESIS_HASH => Ok(Chain::Mainnet),
+            integration if integration == INTEGRATION_GENESIS_HASH => Ok(Chain::Integration),
+            other => Err(anyhow::anyhow!("Unknown genesis block hash: {}", other.0)),
+        }
+    }
+
+    /// Returns the hash of the genesis block of the chain this client is operating on.
+    pub async fn genesis_hash(&self) -> anyhow::Result<StarknetBlockHash> {
         use crate::core::StarknetBlockNumber;
 
         // unwrap is safe as `block_hash` is always present for non-pending blocks.
@@ -171,13 +194,7 @@ impl Client {
             .expect("Genesis block should not be pending")
             .block_hash;
 
-        match genesis_hash {
-            testnet if testnet == TESTNET_GENESIS_HASH => Ok(Chain::Testnet),
-            testnet2 if testnet2 == TESTNET2_GENESIS_HASH => Ok(Chain::Testnet2),
-            mainnet if mainnet == MAINNET_GENESIS_HASH => Ok(Chain::Mainnet),
-            integration if integration == INTEGRATION_GENESIS_HASH => Ok(Chain::Integration),
-            other => Err(anyhow::anyhow!("Unknown genesis block hash: {}", other.0)),
-        }
+        Ok(genesis_hash)
     }
 }
 
@@ -221,6 +238,21 @@ impl ClientApi for Client {
             .await
     }
 
+    /// Gets the compiled CASM definition of a Sierra class.
+    #[tracing::instrument(skip(self))]
+    async fn compiled_class_by_hash(
+        &self,
+        class_hash: ClassHash,
+    ) -> Result<bytes::Bytes, SequencerError> {
+        self.request()
+            .feeder_gateway()
+            .get_compiled_class_by_class_hash()
+            .with_class_hash(class_hash)
+            .with_retry(Self::RETRY)
+            .get_as_bytes()
+            .await
+    }
+
     /// Gets class hash for a particular contract address.
     #[tracing::instrument(skip(self))]
     async fn class_hash_at(
@@ -351,8 +383,9 @@ impl ClientApi for Client {
         max_fee: Fee,
         signature: Vec<TransactionSignatureElem>,
         nonce: TransactionNonce,
-        contract_definition: ContractDefinition,
+        contract_definition: ClassDefinition,
         sender_address: ContractAddress,
+        compiled_class_hash: Option<CasmHash>,
         token: Option<String>,
     ) -> Result<reply::add_transaction::DeclareResponse, SequencerError> {
         let req =
@@ -363,6 +396,7 @@ impl ClientApi for Client {
                 signature,
                 nonce,
                 version,
+                compiled_class_hash,
             });
 
         // Note that we don't do retries here.
@@ -758,6 +792,8 @@ mod tests {
                     transaction_receipts: vec![],
                     transactions: vec![],
                     starknet_version: None,
+                    transaction_commitment: None,
+                    event_commitment: None,
                 }))
             },
         );
@@ -1622,10 +1658,11 @@ mod tests {
                     Fee(0u128.to_be_bytes().into()),
                     vec![],
                     TransactionNonce(StarkHash::ZERO),
-                    contract_class,
+                    contract_class.into(),
                     // actual address dumped from a `starknet declare` call
                     ContractAddress::new_or_panic(starkhash!("01")),
                     None,
+                    None,
                 )
                 .await
                 .unwrap();
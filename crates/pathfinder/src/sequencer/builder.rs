@@ -46,6 +46,7 @@ pub mod stage {
     /// - [get_block](super::Request::get_block)
     /// - [get_full_contract](super::Request::get_full_contract)
     /// - [get_class_by_hash](super::Request::get_class_by_hash)
+    /// - [get_compiled_class_by_class_hash](super::Request::get_compiled_class_by_class_hash)
     /// - [get_class_hash_at](super::Request::get_class_hash_at)
     /// - [get_storage_at](super::Request::get_storage_at)
     /// - [get_transaction](super::Request::get_transaction)
@@ -172,6 +173,7 @@ impl<'a> Request<'a, stage::Method> {
         get_block,
         get_full_contract,
         get_class_by_hash,
+        get_compiled_class_by_class_hash,
         get_class_hash_at,
         get_storage_at,
         get_transaction,
@@ -403,7 +405,7 @@ async fn parse_raw(response: reqwest::Response) -> Result<reqwest::Response, Seq
 pub trait RequestState {}
 
 /// Wrapper function to allow retrying sequencer queries in an exponential manner.
-async fn retry0<T, Fut, FutureFactory, Ret>(
+pub(super) async fn retry0<T, Fut, FutureFactory, Ret>(
     future_factory: FutureFactory,
     retry_condition: Ret,
 ) -> Result<T, SequencerError>
@@ -423,7 +425,7 @@ where
 }
 
 /// Determines if an error is retryable or not.
-fn retry_condition(e: &SequencerError) -> bool {
+pub(super) fn retry_condition(e: &SequencerError) -> bool {
     use reqwest::StatusCode;
     use tracing::{debug, error, info, warn};
 
@@ -456,7 +458,7 @@ fn retry_condition(e: &SequencerError) -> bool {
 
             true
         }
-        SequencerError::StarknetError(_) => false,
+        SequencerError::StarknetError(_) | SequencerError::JsonRpcError(_) => false,
         SequencerError::InvalidStarknetErrorVariant => {
             error!(reason=%e, "Request failed, retrying");
             true
@@ -16,6 +16,9 @@ pub enum SequencerError {
     /// not informative enough or bloated
     #[error("error decoding response body: invalid error variant")]
     InvalidStarknetErrorVariant,
+    /// Errors reported by a JSON-RPC node, or replies from it which could not be used.
+    #[error("JSON-RPC error: {0}")]
+    JsonRpcError(String),
 }
 
 impl From<SequencerError> for Error {
@@ -23,6 +26,7 @@ impl From<SequencerError> for Error {
         match e {
             SequencerError::ReqwestError(e) => Error::Call(CallError::Failed(e.into())),
             SequencerError::InvalidStarknetErrorVariant => Error::Call(CallError::Failed(e.into())),
+            SequencerError::JsonRpcError(_) => Error::Call(CallError::Failed(e.into())),
             SequencerError::StarknetError(e) => match e.code {
                 StarknetErrorCode::OutOfRangeBlockHash | StarknetErrorCode::BlockNotFound
                     if e.message.contains("Block hash") =>
@@ -119,4 +123,8 @@ pub enum StarknetErrorCode {
     InvalidTransactionVersion,
     #[serde(rename = "StarknetErrorCode.INVALID_PROGRAM")]
     InvalidProgram,
+    #[serde(rename = "StarknetErrorCode.COMPILATION_FAILED")]
+    CompilationFailed,
+    #[serde(rename = "StarknetErrorCode.INVALID_COMPILED_CLASS_HASH")]
+    InvalidCompiledClassHash,
 }
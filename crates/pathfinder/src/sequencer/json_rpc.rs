@@ -0,0 +1,482 @@
+//! A [ClientApi] implementation backed by another pathfinder node's JSON-RPC API.
+//!
+//! This lets a node sync from a trusted pathfinder instead of the sequencer. Blocks, state updates
+//! and class definitions are fetched in the sequencer's format through the `pathfinder_*` methods
+//! which serve them, since the v0.2 API leaves out data sync needs, such as execution resources
+//! and the original class definitions. Contract storage and class hashes are read through the
+//! v0.2 API.
+//!
+//! The node cannot submit transactions, nor query transactions or the L1 contract addresses.
+use std::time::Duration;
+
+use reqwest::Url;
+use serde::de::DeserializeOwned;
+use serde_json::json;
+use serde_json::value::RawValue;
+use stark_hash::StarkHash;
+
+use super::error::{SequencerError, StarknetError, StarknetErrorCode};
+use super::request::add_transaction::{ClassDefinition, ContractDefinition};
+use super::{builder, reply, ClientApi};
+use crate::core::{
+    BlockId, CallParam, CasmHash, ClassHash, ConstructorParam, ContractAddress,
+    ContractAddressSalt, EntryPoint, Fee, StarknetTransactionHash, StorageAddress, StorageValue,
+    TransactionNonce, TransactionSignatureElem, TransactionVersion,
+};
+use crate::rpc::v01::types::BlockHashOrTag;
+
+/// StarkNet JSON-RPC client, see the [module documentation](self).
+///
+/// Requests are retried like the [sequencer client's](super::Client).
+#[derive(Debug, Clone)]
+pub struct JsonRpcClient {
+    /// This client is internally refcounted
+    inner: reqwest::Client,
+    /// URL of the node's v0.2 JSON-RPC API.
+    v02: Url,
+    /// URL of the node's pathfinder specific JSON-RPC API.
+    pathfinder: Url,
+}
+
+impl JsonRpcClient {
+    #[cfg(not(test))]
+    const RETRY: builder::Retry = builder::Retry::Enabled;
+    #[cfg(test)]
+    const RETRY: builder::Retry = builder::Retry::Disabled;
+
+    /// Creates a client for the node whose HTTP-RPC server is at the given [Url], e.g.
+    /// `http://127.0.0.1:9545`.
+    pub fn new(mut url: Url) -> anyhow::Result<Self> {
+        use anyhow::Context;
+
+        // Otherwise joining replaces the last path segment.
+        if !url.path().ends_with('/') {
+            url.set_path(&format!("{}/", url.path()));
+        }
+
+        Ok(Self {
+            inner: reqwest::Client::builder()
+                .timeout(Duration::from_secs(120))
+                .user_agent(crate::consts::USER_AGENT)
+                .build()?,
+            v02: url.join("rpc/v0.2").context("Building v0.2 API URL")?,
+            pathfinder: url
+                .join("rpc/pathfinder/v0.1")
+                .context("Building pathfinder API URL")?,
+        })
+    }
+
+    /// Returns the Starknet chain id of the node.
+    pub async fn chain_id(&self) -> Result<StarkHash, SequencerError> {
+        let chain_id = self
+            .call::<String>(&self.v02, "starknet_chainId", json!([]))
+            .await?;
+
+        StarkHash::from_hex_str(&chain_id).map_err(invalid_reply)
+    }
+
+    async fn call<T: DeserializeOwned>(
+        &self,
+        url: &Url,
+        method: &'static str,
+        params: serde_json::Value,
+    ) -> Result<T, SequencerError> {
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 0,
+            "method": method,
+            "params": params,
+        });
+
+        let send = || async {
+            let response = self
+                .inner
+                .post(url.clone())
+                .json(&request)
+                .send()
+                .await?
+                .error_for_status()?;
+            Result::<_, SequencerError>::Ok(response.json::<dto::Response<T>>().await?)
+        };
+
+        let response = match Self::RETRY {
+            builder::Retry::Disabled => send().await,
+            builder::Retry::Enabled => builder::retry0(send, builder::retry_condition).await,
+        }?;
+
+        response.into_result()
+    }
+
+    /// Downloads a class definition as it was declared.
+    async fn class(
+        &self,
+        method: &'static str,
+        class_hash: ClassHash,
+    ) -> Result<bytes::Bytes, SequencerError> {
+        let class = self
+            .call::<Box<RawValue>>(
+                &self.pathfinder,
+                method,
+                json!({ "class_hash": class_hash }),
+            )
+            .await?;
+
+        Ok(bytes::Bytes::from(class.get().to_owned()))
+    }
+}
+
+/// For replies which can't be converted to their sequencer representation.
+fn invalid_reply(reason: impl std::fmt::Display) -> SequencerError {
+    SequencerError::JsonRpcError(format!("Invalid reply: {reason}"))
+}
+
+fn unsupported(operation: &str) -> SequencerError {
+    SequencerError::JsonRpcError(format!("{operation} is not available over JSON-RPC"))
+}
+
+fn block_id(block: BlockId) -> serde_json::Value {
+    match block {
+        BlockId::Number(number) => json!({ "block_number": number.get() }),
+        BlockId::Hash(hash) => json!({ "block_hash": hash }),
+        BlockId::Latest => json!("latest"),
+        BlockId::Pending => json!("pending"),
+    }
+}
+
+#[async_trait::async_trait]
+impl ClientApi for JsonRpcClient {
+    #[tracing::instrument(skip(self))]
+    async fn block(&self, block: BlockId) -> Result<reply::MaybePendingBlock, SequencerError> {
+        self.call(
+            &self.pathfinder,
+            "pathfinder_getSequencerBlock",
+            json!({ "block_id": block_id(block) }),
+        )
+        .await
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn full_contract(
+        &self,
+        contract_addr: ContractAddress,
+    ) -> Result<bytes::Bytes, SequencerError> {
+        let class_hash = self.class_hash_at(contract_addr).await?;
+
+        self.class_by_hash(class_hash).await
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn class_by_hash(&self, class_hash: ClassHash) -> Result<bytes::Bytes, SequencerError> {
+        self.class("pathfinder_getClassDefinition", class_hash)
+            .await
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn compiled_class_by_hash(
+        &self,
+        class_hash: ClassHash,
+    ) -> Result<bytes::Bytes, SequencerError> {
+        self.class("pathfinder_getCompiledClassDefinition", class_hash)
+            .await
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn class_hash_at(
+        &self,
+        contract_address: ContractAddress,
+    ) -> Result<ClassHash, SequencerError> {
+        self.call(
+            &self.v02,
+            "starknet_getClassHashAt",
+            json!({ "block_id": "pending", "contract_address": contract_address }),
+        )
+        .await
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn storage(
+        &self,
+        contract_addr: ContractAddress,
+        key: StorageAddress,
+        block_hash: BlockHashOrTag,
+    ) -> Result<StorageValue, SequencerError> {
+        self.call(
+            &self.v02,
+            "starknet_getStorageAt",
+            json!({
+                "contract_address": contract_addr,
+                "key": key,
+                "block_id": block_id(block_hash.into()),
+            }),
+        )
+        .await
+    }
+
+    async fn transaction(
+        &self,
+        _transaction_hash: StarknetTransactionHash,
+    ) -> Result<reply::Transaction, SequencerError> {
+        Err(unsupported("Querying transactions"))
+    }
+
+    async fn transaction_status(
+        &self,
+        _transaction_hash: StarknetTransactionHash,
+    ) -> Result<reply::TransactionStatus, SequencerError> {
+        Err(unsupported("Querying transaction status"))
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn state_update(&self, block: BlockId) -> Result<reply::StateUpdate, SequencerError> {
+        self.call(
+            &self.pathfinder,
+            "pathfinder_getSequencerStateUpdate",
+            json!({ "block_id": block_id(block) }),
+        )
+        .await
+    }
+
+    async fn eth_contract_addresses(&self) -> Result<reply::EthContractAddresses, SequencerError> {
+        Err(unsupported("Querying the L1 contract addresses"))
+    }
+
+    async fn add_invoke_transaction(
+        &self,
+        _version: TransactionVersion,
+        _max_fee: Fee,
+        _signature: Vec<TransactionSignatureElem>,
+        _nonce: Option<TransactionNonce>,
+        _contract_address: ContractAddress,
+        _entry_point_selector: Option<EntryPoint>,
+        _calldata: Vec<CallParam>,
+    ) -> Result<reply::add_transaction::InvokeResponse, SequencerError> {
+        Err(unsupported("Submitting transactions"))
+    }
+
+    async fn add_declare_transaction(
+        &self,
+        _version: TransactionVersion,
+        _max_fee: Fee,
+        _signature: Vec<TransactionSignatureElem>,
+        _nonce: TransactionNonce,
+        _contract_definition: ClassDefinition,
+        _sender_address: ContractAddress,
+        _compiled_class_hash: Option<CasmHash>,
+        _token: Option<String>,
+    ) -> Result<reply::add_transaction::DeclareResponse, SequencerError> {
+        Err(unsupported("Submitting transactions"))
+    }
+
+    async fn add_deploy_transaction(
+        &self,
+        _version: TransactionVersion,
+        _contract_address_salt: ContractAddressSalt,
+        _constructor_calldata: Vec<ConstructorParam>,
+        _contract_definition: ContractDefinition,
+        _token: Option<String>,
+    ) -> Result<reply::add_transaction::DeployResponse, SequencerError> {
+        Err(unsupported("Submitting transactions"))
+    }
+
+    async fn add_deploy_account(
+        &self,
+        _version: TransactionVersion,
+        _max_fee: Fee,
+        _signature: Vec<TransactionSignatureElem>,
+        _nonce: TransactionNonce,
+        _contract_address_salt: ContractAddressSalt,
+        _class_hash: ClassHash,
+        _calldata: Vec<CallParam>,
+    ) -> Result<reply::add_transaction::DeployAccountResponse, SequencerError> {
+        Err(unsupported("Submitting transactions"))
+    }
+}
+
+/// The JSON-RPC envelope, and the conversion of its errors to the sequencer's.
+mod dto {
+    use serde::Deserialize;
+
+    use super::{invalid_reply, StarknetError, StarknetErrorCode};
+    use crate::sequencer::error::SequencerError;
+
+    #[derive(Deserialize)]
+    pub struct Response<T> {
+        pub result: Option<T>,
+        pub error: Option<Error>,
+    }
+
+    impl<T> Response<T> {
+        pub fn into_result(self) -> Result<T, SequencerError> {
+            match (self.result, self.error) {
+                (_, Some(error)) => Err(error.into()),
+                (Some(result), None) => Ok(result),
+                (None, None) => Err(invalid_reply("Response has neither result nor error")),
+            }
+        }
+    }
+
+    #[derive(Deserialize)]
+    pub struct Error {
+        pub code: i64,
+        pub message: String,
+    }
+
+    impl From<Error> for SequencerError {
+        /// Maps the errors the sync process handles to their sequencer equivalents.
+        fn from(error: Error) -> Self {
+            let code = match error.code {
+                20 => StarknetErrorCode::UninitializedContract,
+                24 => StarknetErrorCode::BlockNotFound,
+                28 => StarknetErrorCode::UndeclaredClass,
+                code => return SequencerError::JsonRpcError(format!("{} ({code})", error.message)),
+            };
+
+            SequencerError::StarknetError(StarknetError {
+                code,
+                message: error.message,
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{Chain, StarknetBlockHash, StarknetBlockNumber};
+    use crate::rpc::tests::setup_storage;
+    use crate::rpc::v01::api::RpcApi;
+    use crate::rpc::v01::types::Tag;
+    use crate::rpc::RpcServer;
+    use crate::starkhash_bytes;
+    use crate::storage::{StarknetBlocksBlockId, StarknetTransactionsTable, Storage};
+    use assert_matches::assert_matches;
+    use std::sync::Arc;
+
+    async fn setup() -> (Storage, crate::rpc::RpcHandle, JsonRpcClient) {
+        let storage = setup_storage();
+        let sequencer = super::super::Client::new(Chain::Testnet).unwrap();
+        let api = RpcApi::new(
+            storage.clone(),
+            sequencer,
+            Chain::Testnet,
+            Arc::new(crate::state::SyncState::default()),
+        );
+        let (handle, addr) = RpcServer::new(([127, 0, 0, 1], 0).into(), api)
+            .run()
+            .await
+            .unwrap();
+
+        let url = format!("http://{addr}").parse().unwrap();
+        (storage, handle, JsonRpcClient::new(url).unwrap())
+    }
+
+    #[tokio::test]
+    async fn block() {
+        let (storage, _handle, client) = setup().await;
+
+        let block = client
+            .block(StarknetBlockNumber::new_or_panic(1).into())
+            .await
+            .unwrap();
+        let block = assert_matches!(block, reply::MaybePendingBlock::Block(block) => block);
+
+        assert_eq!(
+            block.block_hash,
+            StarknetBlockHash(starkhash_bytes!(b"block 1"))
+        );
+        assert_eq!(
+            block.parent_block_hash,
+            StarknetBlockHash(starkhash_bytes!(b"genesis"))
+        );
+
+        let mut connection = storage.connection().unwrap();
+        let tx = connection.transaction().unwrap();
+        let expected = StarknetTransactionsTable::get_transaction_data_for_block(
+            &tx,
+            StarknetBlocksBlockId::Number(block.block_number),
+        )
+        .unwrap();
+
+        assert_eq!(block.transactions.len(), expected.len());
+        for ((transaction, receipt), (expected_tx, expected_receipt)) in block
+            .transactions
+            .iter()
+            .zip(&block.transaction_receipts)
+            .zip(&expected)
+        {
+            assert_eq!(transaction, expected_tx);
+            assert_eq!(receipt, expected_receipt);
+        }
+    }
+
+    #[tokio::test]
+    async fn block_not_found() {
+        let (_storage, _handle, client) = setup().await;
+
+        let error = client
+            .block(StarknetBlockNumber::new_or_panic(99).into())
+            .await
+            .unwrap_err();
+        assert_matches!(
+            error,
+            SequencerError::StarknetError(e) => assert_eq!(e.code, StarknetErrorCode::BlockNotFound)
+        );
+    }
+
+    #[tokio::test]
+    async fn class_definition() {
+        let (_storage, _handle, client) = setup().await;
+
+        let definition = client
+            .class_by_hash(ClassHash(starkhash_bytes!(b"class 0 hash")))
+            .await
+            .unwrap();
+
+        let expected =
+            zstd::decode_all(&include_bytes!("../../fixtures/contract_definition.json.zst")[..])
+                .unwrap();
+        assert_eq!(definition, expected);
+
+        let error = client
+            .class_by_hash(ClassHash(starkhash_bytes!(b"unknown")))
+            .await
+            .unwrap_err();
+        assert_matches!(
+            error,
+            SequencerError::StarknetError(e) => assert_eq!(e.code, StarknetErrorCode::UndeclaredClass)
+        );
+
+        // Cairo 0.x classes have no compiled class.
+        let error = client
+            .compiled_class_by_hash(ClassHash(starkhash_bytes!(b"class 0 hash")))
+            .await
+            .unwrap_err();
+        assert_matches!(
+            error,
+            SequencerError::StarknetError(e) => assert_eq!(e.code, StarknetErrorCode::UndeclaredClass)
+        );
+    }
+
+    #[tokio::test]
+    async fn storage() {
+        let (_storage, _handle, client) = setup().await;
+
+        let value = client
+            .storage(
+                ContractAddress::new_or_panic(starkhash_bytes!(b"contract 1")),
+                StorageAddress::new_or_panic(starkhash_bytes!(b"storage addr 0")),
+                BlockHashOrTag::Tag(Tag::Latest),
+            )
+            .await
+            .unwrap();
+        assert_eq!(value, StorageValue(starkhash_bytes!(b"storage value 2")));
+    }
+
+    #[tokio::test]
+    async fn chain_id() {
+        let (_storage, _handle, client) = setup().await;
+
+        let chain_id = client.chain_id().await.unwrap();
+        assert_eq!(chain_id, Chain::Testnet.starknet_chain_id());
+    }
+}
@@ -163,7 +163,7 @@ pub async fn with_metrics<T>(
             {
                 increment_failed(meta, REASON_RATE_LIMITING);
             }
-            SequencerError::ReqwestError(_) => {}
+            SequencerError::ReqwestError(_) | SequencerError::JsonRpcError(_) => {}
         }
 
         e
@@ -1,8 +1,8 @@
 //! Structures used for deserializing replies from Starkware's sequencer REST API.
 use crate::{
     core::{
-        EthereumAddress, GasPrice, GlobalRoot, SequencerAddress, StarknetBlockHash,
-        StarknetBlockNumber, StarknetBlockTimestamp,
+        EthereumAddress, EventCommitment, GasPrice, GlobalRoot, SequencerAddress,
+        StarknetBlockHash, StarknetBlockNumber, StarknetBlockTimestamp, TransactionCommitment,
     },
     rpc::serde::{EthereumAddressAsHexStr, GasPriceAsHexStr},
 };
@@ -18,11 +18,11 @@ pub struct Block {
     pub block_number: StarknetBlockNumber,
     /// Excluded in blocks prior to StarkNet 0.9
     #[serde_as(as = "Option<GasPriceAsHexStr>")]
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub gas_price: Option<GasPrice>,
     pub parent_block_hash: StarknetBlockHash,
     /// Excluded in blocks prior to StarkNet 0.8
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub sequencer_address: Option<SequencerAddress>,
     pub state_root: GlobalRoot,
     pub status: Status,
@@ -30,13 +30,18 @@ pub struct Block {
     pub transaction_receipts: Vec<transaction::Receipt>,
     pub transactions: Vec<transaction::Transaction>,
     /// Version metadata introduced in 0.9.1, older blocks will not have it.
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub starknet_version: Option<String>,
+    /// Only reported by newer sequencer versions.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transaction_commitment: Option<TransactionCommitment>,
+    /// Only reported by newer sequencer versions.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub event_commitment: Option<EventCommitment>,
 }
 
 #[serde_as]
-#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
-#[cfg_attr(test, derive(serde::Serialize))]
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, serde::Serialize)]
 pub struct PendingBlock {
     #[serde_as(as = "GasPriceAsHexStr")]
     pub gas_price: GasPrice,
@@ -48,11 +53,11 @@ pub struct PendingBlock {
     pub transaction_receipts: Vec<transaction::Receipt>,
     pub transactions: Vec<transaction::Transaction>,
     /// Version metadata introduced in 0.9.1, older blocks will not have it.
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub starknet_version: Option<String>,
 }
 
-#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, serde::Serialize)]
 #[serde(untagged)]
 pub enum MaybePendingBlock {
     Block(Block),
@@ -157,26 +162,26 @@ pub mod call {
 
 /// Used to deserialize replies to [ClientApi::transaction](crate::sequencer::ClientApi::transaction).
 #[serde_as]
-#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, serde::Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct Transaction {
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub block_hash: Option<StarknetBlockHash>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub block_number: Option<StarknetBlockNumber>,
     pub status: Status,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub transaction: Option<transaction::Transaction>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub transaction_index: Option<u64>,
 }
 
 /// Used to deserialize replies to [ClientApi::transaction_status](crate::sequencer::ClientApi::transaction_status).
 #[serde_as]
-#[derive(Copy, Clone, Debug, Deserialize, PartialEq, Eq)]
+#[derive(Copy, Clone, Debug, Deserialize, PartialEq, Eq, serde::Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct TransactionStatus {
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub block_hash: Option<StarknetBlockHash>,
     pub tx_status: Status,
 }
@@ -185,11 +190,11 @@ pub struct TransactionStatus {
 pub mod transaction {
     use crate::{
         core::{
-            CallParam, ClassHash, ConstructorParam, ContractAddress, ContractAddressSalt,
+            CallParam, CasmHash, ClassHash, ConstructorParam, ContractAddress, ContractAddressSalt,
             EntryPoint, EthereumAddress, EventData, EventKey, Fee, L1ToL2MessageNonce,
-            L1ToL2MessagePayloadElem, L2ToL1MessagePayloadElem, StarknetTransactionHash,
-            StarknetTransactionIndex, TransactionNonce, TransactionSignatureElem,
-            TransactionVersion,
+            L1ToL2MessagePayloadElem, L2ToL1MessagePayloadElem, MessageHash,
+            StarknetTransactionHash, StarknetTransactionIndex, TransactionNonce,
+            TransactionSignatureElem, TransactionVersion,
         },
         rpc::serde::{
             CallParamAsDecimalStr, ConstructorParamAsDecimalStr, EthereumAddressAsHexStr,
@@ -200,6 +205,7 @@ pub mod transaction {
     };
     use serde::{Deserialize, Serialize};
     use serde_with::serde_as;
+    use web3::types::H256;
 
     /// Represents deserialized L2 transaction entry point values.
     #[derive(Copy, Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
@@ -263,6 +269,27 @@ pub mod transaction {
         pub nonce: Option<L1ToL2MessageNonce>,
     }
 
+    impl L1ToL2Message {
+        /// The [hash](MessageHash::l1_to_l2) of the message, or [None] for messages sent before
+        /// nonces were introduced.
+        pub fn hash(&self) -> Option<MessageHash> {
+            let nonce = self.nonce?;
+            let payload = self
+                .payload
+                .iter()
+                .map(|elem| H256(elem.0.to_be_bytes()))
+                .collect::<Vec<_>>();
+
+            Some(MessageHash::l1_to_l2(
+                self.from_address.to_word(),
+                H256(self.to_address.get().to_be_bytes()),
+                H256(nonce.0.to_be_bytes()),
+                H256(self.selector.0.to_be_bytes()),
+                &payload,
+            ))
+        }
+    }
+
     /// Represents deserialized L2 to L1 message.
     #[serde_as]
     #[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
@@ -275,6 +302,23 @@ pub mod transaction {
         pub to_address: EthereumAddress,
     }
 
+    impl L2ToL1Message {
+        /// The [hash](MessageHash::l2_to_l1) of the message.
+        pub fn hash(&self) -> MessageHash {
+            let payload = self
+                .payload
+                .iter()
+                .map(|elem| H256(elem.0.to_be_bytes()))
+                .collect::<Vec<_>>();
+
+            MessageHash::l2_to_l1(
+                H256(self.from_address.get().to_be_bytes()),
+                self.to_address.to_word(),
+                &payload,
+            )
+        }
+    }
+
     /// Represents deserialized L2 transaction receipt data.
     #[serde_as]
     #[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
@@ -356,6 +400,9 @@ pub mod transaction {
     #[serde(deny_unknown_fields)]
     pub struct DeclareTransaction {
         pub class_hash: ClassHash,
+        /// Only present for version 2 declarations, which declare Sierra classes.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub compiled_class_hash: Option<CasmHash>,
         #[serde_as(as = "FeeAsHexStr")]
         pub max_fee: Fee,
         pub nonce: TransactionNonce,
@@ -578,10 +625,11 @@ pub mod transaction {
 
 /// Used to deserialize a reply from
 /// [ClientApi::state_update](crate::sequencer::ClientApi::state_update).
-#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, serde::Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct StateUpdate {
     /// This field is absent for a `pending` state update
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub block_hash: Option<StarknetBlockHash>,
     pub new_root: GlobalRoot,
     pub old_root: GlobalRoot,
@@ -590,27 +638,44 @@ pub struct StateUpdate {
 
 /// Types used when deserializing state update related data.
 pub mod state_update {
-    use crate::core::{ClassHash, ContractAddress, ContractNonce, StorageAddress, StorageValue};
-    use serde::Deserialize;
+    use crate::core::{
+        CasmHash, ClassHash, ContractAddress, ContractNonce, StorageAddress, StorageValue,
+    };
+    use serde::{Deserialize, Serialize};
     use serde_with::serde_as;
     use std::collections::HashMap;
 
     /// L2 state diff.
     #[serde_as]
-    #[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+    #[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
     #[serde(deny_unknown_fields)]
     pub struct StateDiff {
         #[serde_as(as = "HashMap<_, Vec<_>>")]
         pub storage_diffs: HashMap<ContractAddress, Vec<StorageDiff>>,
         pub deployed_contracts: Vec<DeployedContract>,
+        /// Cairo 0.x classes declared in this block.
+        ///
+        /// `old_declared_contracts` is the field name once Sierra classes were introduced.
+        #[serde(alias = "old_declared_contracts")]
         pub declared_contracts: Vec<ClassHash>,
+        /// Sierra classes declared in this block.
+        #[serde(default)]
+        pub declared_classes: Vec<DeclaredSierraClass>,
         /// FIXME(0.10): drop the default once 0.10 hits mainnet
         #[serde(default)]
         pub nonces: HashMap<ContractAddress, ContractNonce>,
     }
 
+    /// A Sierra class declared in a state diff, along with the hash of its compiled CASM.
+    #[derive(Copy, Clone, Debug, Deserialize, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+    #[serde(deny_unknown_fields)]
+    pub struct DeclaredSierraClass {
+        pub class_hash: ClassHash,
+        pub compiled_class_hash: CasmHash,
+    }
+
     /// L2 storage diff.
-    #[derive(Clone, Debug, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+    #[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq, PartialOrd, Ord)]
     #[serde(deny_unknown_fields)]
     pub struct StorageDiff {
         pub key: StorageAddress,
@@ -618,7 +683,7 @@ pub mod state_update {
     }
 
     /// L2 contract data within state diff.
-    #[derive(Copy, Clone, Debug, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+    #[derive(Copy, Clone, Debug, Deserialize, Serialize, PartialEq, Eq, PartialOrd, Ord)]
     #[serde(deny_unknown_fields)]
     pub struct DeployedContract {
         pub address: ContractAddress,
@@ -662,7 +727,7 @@ pub mod state_update {
 
 /// Used to deserialize a reply from [ClientApi::eth_contract_addresses](crate::sequencer::ClientApi::eth_contract_addresses).
 #[serde_as]
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, Deserialize, serde::Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct EthContractAddresses {
     #[serde(rename = "Starknet")]
@@ -51,7 +51,8 @@ pub mod add_transaction {
     use std::collections::HashMap;
 
     use crate::core::{
-        ClassHash, ConstructorParam, ContractAddressSalt, TransactionNonce, TransactionVersion,
+        CasmHash, ClassHash, ConstructorParam, ContractAddressSalt, TransactionNonce,
+        TransactionVersion,
     };
     use crate::rpc::serde::{
         CallParamAsDecimalStr, ConstructorParamAsDecimalStr, FeeAsHexStr,
@@ -76,10 +77,10 @@ pub mod add_transaction {
         pub abi: Option<serde_json::Value>,
     }
 
-    impl TryFrom<crate::rpc::v02::types::ContractClass> for ContractDefinition {
+    impl TryFrom<crate::rpc::v02::types::CairoContractClass> for ContractDefinition {
         type Error = serde_json::Error;
 
-        fn try_from(c: crate::rpc::v02::types::ContractClass) -> Result<Self, Self::Error> {
+        fn try_from(c: crate::rpc::v02::types::CairoContractClass) -> Result<Self, Self::Error> {
             let abi = match c.abi {
                 Some(abi) => Some(serde_json::to_value(abi)?),
                 None => None,
@@ -119,6 +120,97 @@ pub mod add_transaction {
         }
     }
 
+    /// Definition of a Sierra class.
+    ///
+    /// Like the program of a [ContractDefinition], the Sierra program is sent as a gzip + base64
+    /// encoded string via the API.
+    #[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+    pub struct SierraContractDefinition {
+        // gzip + base64 encoded JSON of the Sierra program
+        pub sierra_program: String,
+        pub contract_class_version: String,
+        pub entry_points_by_type: HashMap<EntryPointType, Vec<SierraEntryPoint>>,
+        pub abi: String,
+    }
+
+    #[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+    pub struct SierraEntryPoint {
+        pub selector: EntryPoint,
+        pub function_idx: u64,
+    }
+
+    impl TryFrom<crate::rpc::v02::types::SierraContractClass> for SierraContractDefinition {
+        type Error = std::io::Error;
+
+        fn try_from(c: crate::rpc::v02::types::SierraContractClass) -> Result<Self, Self::Error> {
+            // Program is expected to be a gzip-compressed then base64 encoded representation of the JSON.
+            let mut gzip_encoder =
+                flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+            serde_json::to_writer(&mut gzip_encoder, &c.sierra_program)?;
+            let sierra_program = base64::encode(gzip_encoder.finish()?);
+
+            let into_entry_points =
+                |entry_points: Vec<crate::rpc::v02::types::SierraEntryPoint>| -> Vec<_> {
+                    entry_points
+                        .into_iter()
+                        .map(|entry_point| SierraEntryPoint {
+                            selector: EntryPoint(entry_point.selector),
+                            function_idx: entry_point.function_idx,
+                        })
+                        .collect()
+                };
+
+            let entry_points = HashMap::from([
+                (
+                    EntryPointType::Constructor,
+                    into_entry_points(c.entry_points_by_type.constructor),
+                ),
+                (
+                    EntryPointType::External,
+                    into_entry_points(c.entry_points_by_type.external),
+                ),
+                (
+                    EntryPointType::L1Handler,
+                    into_entry_points(c.entry_points_by_type.l1_handler),
+                ),
+            ]);
+
+            Ok(Self {
+                sierra_program,
+                contract_class_version: c.contract_class_version,
+                entry_points_by_type: entry_points,
+                abi: c.abi,
+            })
+        }
+    }
+
+    /// The definition of a class being declared, either a Cairo 0.x or a Sierra class.
+    #[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+    #[serde(untagged)]
+    pub enum ClassDefinition {
+        Sierra(SierraContractDefinition),
+        Cairo(ContractDefinition),
+    }
+
+    impl From<ContractDefinition> for ClassDefinition {
+        fn from(definition: ContractDefinition) -> Self {
+            Self::Cairo(definition)
+        }
+    }
+
+    impl TryFrom<crate::rpc::v02::types::ContractClass> for ClassDefinition {
+        type Error = anyhow::Error;
+
+        fn try_from(c: crate::rpc::v02::types::ContractClass) -> Result<Self, Self::Error> {
+            use crate::rpc::v02::types::ContractClass;
+
+            Ok(match c {
+                ContractClass::Cairo(c) => Self::Cairo(c.try_into()?),
+                ContractClass::Sierra(c) => Self::Sierra(c.try_into()?),
+            })
+        }
+    }
+
     /// Contract deployment transaction details.
     #[serde_as]
     #[derive(Debug, serde::Deserialize, serde::Serialize)]
@@ -188,9 +280,13 @@ pub mod add_transaction {
         #[serde_as(as = "Vec<TransactionSignatureElemAsDecimalStr>")]
         pub signature: Vec<TransactionSignatureElem>,
 
-        pub contract_class: ContractDefinition,
+        pub contract_class: ClassDefinition,
         pub sender_address: ContractAddress,
         pub nonce: TransactionNonce,
+
+        // Only present for version 2 declarations, which declare Sierra classes.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub compiled_class_hash: Option<CasmHash>,
     }
 
     /// Add transaction API operation.
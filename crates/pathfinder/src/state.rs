@@ -1,12 +1,11 @@
 use anyhow::Context;
-use rusqlite::Transaction;
 use stark_hash::{stark_hash, StarkHash};
 
 use crate::{
-    core::{ClassHash, ContractAddress, ContractNonce, ContractRoot, ContractStateHash},
+    core::{CasmHash, ClassHash, ContractAddress, ContractNonce, ContractRoot, ContractStateHash},
     sequencer::reply::state_update::StorageDiff,
     state::state_tree::{ContractsStateTree, GlobalStateTree},
-    storage::{ContractsStateTable, ContractsTable},
+    storage::StorageBackend,
 };
 
 pub mod block_hash;
@@ -16,8 +15,8 @@ pub mod merkle_tree;
 pub mod state_tree;
 mod sync;
 
-pub use class_hash::compute_class_hash;
-pub use sync::{l1, l2, sync, PendingData, State as SyncState};
+pub use class_hash::{compute_casm_class_hash, compute_class_hash, ClassFormat};
+pub use sync::{l1, l2, messages, repair, replica, sync, PendingData, State as SyncState};
 
 #[derive(Clone, PartialEq, Eq)]
 pub struct CompressedContract {
@@ -25,15 +24,36 @@ pub struct CompressedContract {
     pub bytecode: Vec<u8>,
     pub definition: Vec<u8>,
     pub hash: ClassHash,
+    /// The compiled class of a Sierra class, [None] for Cairo 0.x classes.
+    pub casm: Option<CompressedCasmClass>,
+}
+
+/// The compressed CASM definition of a Sierra class, along with its compiled class hash.
+#[derive(Clone, PartialEq, Eq)]
+pub struct CompressedCasmClass {
+    pub definition: Vec<u8>,
+    pub hash: CasmHash,
+}
+
+impl CompressedContract {
+    pub fn format(&self) -> ClassFormat {
+        match self.casm {
+            Some(_) => ClassFormat::Sierra,
+            None => ClassFormat::Cairo,
+        }
+    }
 }
 
 impl std::fmt::Debug for CompressedContract {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "CompressedContract {{ sizes: {:?}, hash: {} }}",
+            "CompressedContract {{ sizes: {:?}, hash: {}, casm: {:?} }}",
             (self.abi.len(), self.bytecode.len(), self.definition.len()),
-            self.hash.0
+            self.hash.0,
+            self.casm
+                .as_ref()
+                .map(|casm| (casm.definition.len(), casm.hash.0))
         )
     }
 }
@@ -41,13 +61,14 @@ impl std::fmt::Debug for CompressedContract {
 /// Updates a contract's state with the given [`StorageDiff`]. It returns the
 /// [ContractStateHash] of the new state.
 ///
-/// Specifically, it updates the [ContractsStateTree] and [ContractsStateTable].
-pub(crate) fn update_contract_state(
+/// Specifically, it updates the [ContractsStateTree] and the
+/// [ContractsStateTable](crate::storage::ContractsStateTable).
+pub(crate) fn update_contract_state<S: StorageBackend>(
     contract_address: ContractAddress,
     updates: &[StorageDiff],
     new_nonce: Option<ContractNonce>,
-    global_tree: &GlobalStateTree<'_, '_>,
-    db: &Transaction<'_>,
+    global_tree: &GlobalStateTree<S::Nodes>,
+    storage: S,
 ) -> anyhow::Result<ContractStateHash> {
     // Update the contract state tree.
     let state_hash = global_tree
@@ -59,7 +80,8 @@ pub(crate) fn update_contract_state(
     //
     // Contract root defaults to ZERO because that is the default merkle tree value.
     // Contract nonce defaults to ZERO because that is its historical value before being added in 0.10.
-    let (old_root, old_nonce) = ContractsStateTable::get_root_and_nonce(db, state_hash)
+    let (old_root, old_nonce) = storage
+        .contract_root_and_nonce(state_hash)
         .context("Read contract root and nonce from contracts state table")?
         .unwrap_or((ContractRoot::ZERO, ContractNonce::ZERO));
 
@@ -67,8 +89,11 @@ pub(crate) fn update_contract_state(
 
     // Load the contract tree and insert the updates.
     let new_root = if !updates.is_empty() {
-        let mut contract_tree =
-            ContractsStateTree::load(db, old_root).context("Load contract state tree")?;
+        let nodes = storage
+            .contract_tree_nodes()
+            .context("Open contract state tree storage")?;
+        let mut contract_tree = ContractsStateTree::with_storage(nodes, old_root)
+            .context("Load contract state tree")?;
         for storage_diff in updates {
             contract_tree
                 .set(storage_diff.key, storage_diff.value)
@@ -82,12 +107,14 @@ pub(crate) fn update_contract_state(
     };
 
     // Calculate contract state hash, update global state tree and persist pre-image.
-    let class_hash = ContractsTable::get_hash(db, contract_address)
+    let class_hash = storage
+        .contract_class_hash(contract_address)
         .context("Read class hash from contracts table")?
         .context("Class hash is missing from contracts table")?;
     let contract_state_hash = calculate_contract_state_hash(class_hash, new_root, new_nonce);
 
-    ContractsStateTable::upsert(db, contract_state_hash, class_hash, new_root, new_nonce)
+    storage
+        .upsert_contract_state(contract_state_hash, class_hash, new_root, new_nonce)
         .context("Insert constract state hash into contracts state table")?;
 
     Ok(contract_state_hash)
@@ -490,10 +517,15 @@ mod tests {
             chain,
             sequencer,
             state,
-            sync::l1::sync,
+            |tx, transport, chain, head| {
+                sync::l1::sync(tx, transport, chain, head, Default::default())
+            },
             sync::l2::sync,
             sync::PendingData::default(),
+            crate::rpc::cache::ResponseCache::default(),
             None,
+            sync::l2::BlockValidationMode::Strict,
+            std::future::pending(),
         )
         .await
         .unwrap();
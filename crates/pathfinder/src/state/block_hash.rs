@@ -1,10 +1,10 @@
 use anyhow::{Context, Error, Result};
 use bitvec::prelude::BitView;
-use stark_hash::{stark_hash, HashChain, StarkHash};
+use stark_hash::{stark_hash_batch, HashChain, StarkHash};
 
 use crate::core::{
-    Chain, GlobalRoot, SequencerAddress, StarknetBlockHash, StarknetBlockNumber,
-    StarknetBlockTimestamp,
+    Chain, EventCommitment, GlobalRoot, SequencerAddress, StarknetBlockHash, StarknetBlockNumber,
+    StarknetBlockTimestamp, TransactionCommitment,
 };
 use crate::sequencer::reply::{
     transaction::{Event, Receipt, Transaction},
@@ -14,9 +14,13 @@ use crate::state::merkle_tree::MerkleTree;
 
 #[derive(Debug, PartialEq, Eq)]
 pub enum VerifyResult {
-    Match,
+    /// The block hash matches, the commitments are the ones we computed from the block contents.
+    Match((TransactionCommitment, EventCommitment)),
     Mismatch,
-    NotVerifiable,
+    /// The block hash cannot be recomputed for this block, or it does not cover the events and the
+    /// sequencer reported no event commitment. The commitments are computed from the block
+    /// contents but could only be checked against the sequencer's values, if it reported any.
+    NotVerifiable((TransactionCommitment, EventCommitment)),
 }
 
 /// Verify the block hash value.
@@ -29,6 +33,13 @@ pub enum VerifyResult {
 /// hashes, but with a hash computed from the transaction hash and the signature
 /// values (for invoke transactions).
 ///
+/// The event commitment is computed for all blocks, even though pre-0.7 block
+/// hashes do not include it. For those it is checked against the one reported by
+/// the sequencer instead, and the block is not verifiable if there is none. For
+/// blocks whose hash cannot be recomputed, because the
+/// sequencer address hashed into it is unknown, we fall back to comparing both
+/// commitments against the ones reported by the sequencer.
+///
 /// See the `compute_block_hash.py` helper script that uses the cairo-lang
 /// Python implementation to compute the block hash for details.
 pub fn verify_block_hash(
@@ -37,9 +48,6 @@ pub fn verify_block_hash(
     expected_block_hash: StarknetBlockHash,
 ) -> Result<VerifyResult> {
     let meta_info = meta::for_chain(chain);
-    if !meta_info.can_verify(block.block_number) {
-        return Ok(VerifyResult::NotVerifiable);
-    }
 
     let num_transactions: u64 = block
         .transactions
@@ -47,8 +55,20 @@ pub fn verify_block_hash(
         .try_into()
         .expect("too many transactions in block");
     let transaction_commitment = calculate_transaction_commitment(&block.transactions)?;
+    let num_events = number_of_events_in_block(block);
+    let num_events: u64 = num_events.try_into().expect("too many events in block");
+    let event_commitment = calculate_event_commitment(&block.transaction_receipts)?;
+
+    let commitments = (
+        TransactionCommitment(transaction_commitment),
+        EventCommitment(event_commitment),
+    );
 
-    let verified = if meta_info.uses_pre_0_7_hash_algorithm(block.block_number) {
+    if !meta_info.can_verify(block.block_number) {
+        return Ok(verify_reported_commitments(block, commitments));
+    }
+
+    if meta_info.uses_pre_0_7_hash_algorithm(block.block_number) {
         let block_hash = compute_final_hash_pre_0_7(
             block.block_number,
             block.state_root,
@@ -57,42 +77,72 @@ pub fn verify_block_hash(
             block.parent_block_hash,
             chain.starknet_chain_id(),
         );
+        if block_hash != expected_block_hash {
+            return Ok(VerifyResult::Mismatch);
+        }
+
+        return Ok(match verify_reported_commitments(block, commitments) {
+            VerifyResult::Mismatch => VerifyResult::Mismatch,
+            _ if block.event_commitment.is_none() => VerifyResult::NotVerifiable(commitments),
+            _ => VerifyResult::Match(commitments),
+        });
+    }
+
+    let block_sequencer_address = block
+        .sequencer_address
+        .unwrap_or(SequencerAddress(StarkHash::ZERO));
+
+    let sequencer_addresses_to_try = &[
+        &block_sequencer_address,
+        &meta_info.fallback_sequencer_address,
+    ];
+    let verified = sequencer_addresses_to_try.iter().any(|address| {
+        let block_hash = compute_final_hash(
+            block.block_number,
+            block.state_root,
+            address,
+            block.timestamp,
+            num_transactions,
+            transaction_commitment,
+            num_events,
+            event_commitment,
+            block.parent_block_hash,
+        );
         block_hash == expected_block_hash
-    } else {
-        let num_events = number_of_events_in_block(block);
-        let num_events: u64 = num_events.try_into().expect("too many events in block");
-        let event_commitment = calculate_event_commitment(&block.transaction_receipts)?;
-
-        let block_sequencer_address = block
-            .sequencer_address
-            .unwrap_or(SequencerAddress(StarkHash::ZERO));
-
-        let sequencer_addresses_to_try = &[
-            &block_sequencer_address,
-            &meta_info.fallback_sequencer_address,
-        ];
-        sequencer_addresses_to_try.iter().any(|address| {
-            let block_hash = compute_final_hash(
-                block.block_number,
-                block.state_root,
-                address,
-                block.timestamp,
-                num_transactions,
-                transaction_commitment,
-                num_events,
-                event_commitment,
-                block.parent_block_hash,
-            );
-            block_hash == expected_block_hash
-        })
-    };
+    });
 
     Ok(match verified {
         false => VerifyResult::Mismatch,
-        true => VerifyResult::Match,
+        true => VerifyResult::Match(commitments),
     })
 }
 
+/// Checks the commitments computed from the block contents against the ones reported
+/// by the sequencer.
+///
+/// Used for blocks where the block hash itself cannot be recomputed. Missing values
+/// leave the block [not verifiable](VerifyResult::NotVerifiable).
+fn verify_reported_commitments(
+    block: &Block,
+    commitments: (TransactionCommitment, EventCommitment),
+) -> VerifyResult {
+    let (transaction_commitment, event_commitment) = commitments;
+
+    let transaction_mismatch = block
+        .transaction_commitment
+        .map(|reported| reported != transaction_commitment)
+        .unwrap_or_default();
+    let event_mismatch = block
+        .event_commitment
+        .map(|reported| reported != event_commitment)
+        .unwrap_or_default();
+
+    match transaction_mismatch || event_mismatch {
+        true => VerifyResult::Mismatch,
+        false => VerifyResult::NotVerifiable(commitments),
+    }
+}
+
 mod meta {
     use std::ops::Range;
 
@@ -178,12 +228,17 @@ mod meta {
         )),
     };
 
-    pub fn for_chain(chain: Chain) -> &'static BlockHashMetaInfo {
+    pub fn for_chain(chain: Chain) -> BlockHashMetaInfo {
         match chain {
-            Chain::Mainnet => &MAINNET_METAINFO,
-            Chain::Testnet => &TESTNET_METAINFO,
-            Chain::Testnet2 => &TESTNET2_METAINFO,
-            Chain::Integration => &INTEGRATION_METAINFO,
+            Chain::Mainnet => MAINNET_METAINFO,
+            Chain::Testnet => TESTNET_METAINFO,
+            Chain::Testnet2 => TESTNET2_METAINFO,
+            Chain::Integration => INTEGRATION_METAINFO,
+            Chain::Custom(spec) => BlockHashMetaInfo {
+                first_0_7_block: spec.block_hash.first_0_7_block,
+                not_verifiable_range: spec.block_hash.not_verifiable_range.clone(),
+                fallback_sequencer_address: spec.block_hash.fallback_sequencer_address,
+            },
         }
     }
 }
@@ -314,14 +369,13 @@ impl CommitmentTree {
 fn calculate_transaction_commitment(transactions: &[Transaction]) -> Result<StarkHash> {
     let mut tree = CommitmentTree::default();
 
-    transactions
-        .iter()
+    calculate_transaction_hashes_with_signature(transactions)
+        .into_iter()
         .enumerate()
-        .try_for_each(|(idx, tx)| {
+        .try_for_each(|(idx, final_hash)| {
             let idx: u64 = idx
                 .try_into()
                 .expect("too many transactions while calculating commitment");
-            let final_hash = calculate_transaction_hash_with_signature(tx);
             tree.set(idx, final_hash)?;
             Result::<_, Error>::Ok(())
         })
@@ -330,7 +384,7 @@ fn calculate_transaction_commitment(transactions: &[Transaction]) -> Result<Star
     tree.commit()
 }
 
-/// Compute the combined hash of the transaction hash and the signature.
+/// Compute the combined hash of the transaction hash and the signature for each transaction.
 ///
 /// Since the transaction hash doesn't take the signature values as its input
 /// computing the transaction commitent uses a hash value that combines
@@ -339,26 +393,33 @@ fn calculate_transaction_commitment(transactions: &[Transaction]) -> Result<Star
 /// Note that for non-invoke transactions we don't actually have signatures. The
 /// cairo-lang uses an empty list (whose hash is not the ZERO value!) in that
 /// case.
-fn calculate_transaction_hash_with_signature(tx: &Transaction) -> StarkHash {
+fn calculate_transaction_hashes_with_signature(transactions: &[Transaction]) -> Vec<StarkHash> {
     lazy_static::lazy_static!(
         static ref HASH_OF_EMPTY_LIST: StarkHash = HashChain::default().finalize();
     );
 
-    let signature_hash = match tx {
-        Transaction::Invoke(tx) => {
-            let mut hash = HashChain::default();
-            for signature in tx.signature() {
-                hash.update(signature.0);
-            }
-            hash.finalize()
-        }
-        Transaction::Declare(_)
-        | Transaction::Deploy(_)
-        | Transaction::DeployAccount(_)
-        | Transaction::L1Handler(_) => *HASH_OF_EMPTY_LIST,
-    };
+    let inputs = transactions
+        .iter()
+        .map(|tx| {
+            let signature_hash = match tx {
+                Transaction::Invoke(tx) => {
+                    let mut hash = HashChain::default();
+                    for signature in tx.signature() {
+                        hash.update(signature.0);
+                    }
+                    hash.finalize()
+                }
+                Transaction::Declare(_)
+                | Transaction::Deploy(_)
+                | Transaction::DeployAccount(_)
+                | Transaction::L1Handler(_) => *HASH_OF_EMPTY_LIST,
+            };
+
+            (tx.hash().0, signature_hash)
+        })
+        .collect::<Vec<_>>();
 
-    stark_hash(tx.hash().0, signature_hash)
+    stark_hash_batch(&inputs)
 }
 
 /// Calculate event commitment hash value.
@@ -422,6 +483,8 @@ fn number_of_events_in_block(block: &Block) -> usize {
 
 #[cfg(test)]
 mod tests {
+    use assert_matches::assert_matches;
+
     use crate::{
         core::{EntryPoint, Fee},
         sequencer::reply::transaction::{EntryPointType, InvokeTransaction, InvokeTransactionV0},
@@ -482,8 +545,9 @@ mod tests {
             "0x259c3bd5a1951eafb2f41e0b783eab92cfe4e108b2b1f071e3736f06b909431",
         )
         .unwrap();
-        let calculated_final_hash = calculate_transaction_hash_with_signature(&transaction);
-        assert_eq!(expected_final_hash, calculated_final_hash);
+        let calculated_final_hash =
+            calculate_transaction_hashes_with_signature(std::slice::from_ref(&transaction));
+        assert_eq!(vec![expected_final_hash], calculated_final_hash);
     }
 
     #[test]
@@ -525,9 +589,9 @@ mod tests {
         let json = include_bytes!("../../fixtures/sequencer/0.9.0/block/90000.json");
         let block: Block = serde_json::from_slice(json).unwrap();
 
-        assert_eq!(
+        assert_matches!(
             verify_block_hash(&block, Chain::Testnet, block.block_hash).unwrap(),
-            VerifyResult::Match
+            VerifyResult::Match(_)
         );
     }
 
@@ -540,9 +604,9 @@ mod tests {
         let json = include_bytes!("../../fixtures/sequencer/0.9.0/block/231579.json");
         let block: Block = serde_json::from_slice(json).unwrap();
 
-        assert_eq!(
+        assert_matches!(
             verify_block_hash(&block, Chain::Testnet, block.block_hash).unwrap(),
-            VerifyResult::Match
+            VerifyResult::Match(_)
         );
     }
 
@@ -556,9 +620,9 @@ mod tests {
         let json = include_bytes!("../../fixtures/sequencer/0.9.0/block/156000.json");
         let block: Block = serde_json::from_slice(json).unwrap();
 
-        assert_eq!(
-            verify_block_hash(&block, Chain::Testnet, block.block_hash,).unwrap(),
-            VerifyResult::Match
+        assert_matches!(
+            verify_block_hash(&block, Chain::Testnet, block.block_hash).unwrap(),
+            VerifyResult::Match(_)
         );
     }
 
@@ -567,13 +631,73 @@ mod tests {
         use crate::sequencer::reply::Block;
 
         // This tests with a pre-0.7 block where the chain ID was hashed into
-        // the block hash.
+        // the block hash. Its events are not, and the block has no reported
+        // event commitment to check them against.
         let json = include_bytes!("../../fixtures/sequencer/0.9.0/block/genesis.json");
         let block: Block = serde_json::from_slice(json).unwrap();
 
+        assert_matches!(
+            verify_block_hash(&block, Chain::Testnet, block.block_hash).unwrap(),
+            VerifyResult::NotVerifiable(_)
+        );
+        assert_eq!(
+            verify_block_hash(&block, Chain::Testnet, StarknetBlockHash(starkhash!("01"))).unwrap(),
+            VerifyResult::Mismatch
+        );
+    }
+
+    #[test]
+    fn test_block_hash_pre_0_7_checks_reported_event_commitment() {
+        use crate::sequencer::reply::Block;
+
+        // Pre-0.7 block hashes do not include the events.
+        let json = include_bytes!("../../fixtures/sequencer/0.9.0/block/genesis.json");
+        let mut block: Block = serde_json::from_slice(json).unwrap();
+
+        let commitments = assert_matches!(
+            verify_block_hash(&block, Chain::Testnet, block.block_hash).unwrap(),
+            VerifyResult::NotVerifiable(commitments) => commitments
+        );
+
+        block.event_commitment = Some(commitments.1);
+        assert_eq!(
+            verify_block_hash(&block, Chain::Testnet, block.block_hash).unwrap(),
+            VerifyResult::Match(commitments)
+        );
+
+        block.event_commitment = Some(EventCommitment(starkhash!("01")));
+        assert_eq!(
+            verify_block_hash(&block, Chain::Testnet, block.block_hash).unwrap(),
+            VerifyResult::Mismatch
+        );
+    }
+
+    #[test]
+    fn test_block_hash_not_verifiable_checks_reported_commitments() {
+        use crate::sequencer::reply::Block;
+
+        // Move a block into the range where the sequencer address used for the block
+        // hash is unknown, so that only the commitments can be checked.
+        let json = include_bytes!("../../fixtures/sequencer/0.9.0/block/90000.json");
+        let mut block: Block = serde_json::from_slice(json).unwrap();
+        block.block_number = StarknetBlockNumber::new_or_panic(120000);
+
+        let commitments = assert_matches!(
+            verify_block_hash(&block, Chain::Testnet, block.block_hash).unwrap(),
+            VerifyResult::NotVerifiable(commitments) => commitments
+        );
+
+        block.transaction_commitment = Some(commitments.0);
+        block.event_commitment = Some(commitments.1);
+        assert_eq!(
+            verify_block_hash(&block, Chain::Testnet, block.block_hash).unwrap(),
+            VerifyResult::NotVerifiable(commitments)
+        );
+
+        block.event_commitment = Some(EventCommitment(StarkHash::ZERO));
         assert_eq!(
             verify_block_hash(&block, Chain::Testnet, block.block_hash).unwrap(),
-            VerifyResult::Match
+            VerifyResult::Mismatch
         );
     }
 }
@@ -1,9 +1,9 @@
 use anyhow::{Context, Error, Result};
 use serde::Serialize;
 use sha3::Digest;
-use stark_hash::{HashChain, StarkHash};
+use stark_hash::{poseidon_hash_many, HashChain, PoseidonHasher, StarkHash};
 
-use crate::core::ClassHash;
+use crate::core::{CasmHash, ClassHash};
 use crate::sequencer::request::contract::EntryPointType;
 
 /// Computes the starknet class hash for given class definition json blob.
@@ -56,6 +56,160 @@ pub fn extract_abi_code_hash(
     Ok((abi, code, hash))
 }
 
+/// The format of a class definition.
+///
+/// The discriminant is the value stored in the `contract_code.class_format` column.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ClassFormat {
+    /// A Cairo 0.x class, with the program inlined in the definition.
+    Cairo = 0,
+    /// A Cairo 1.0 class, with the program given as Sierra and compiled separately to CASM.
+    Sierra = 1,
+}
+
+impl ClassFormat {
+    /// Determines the format of the given class definition json blob.
+    pub fn of_definition(contract_definition_dump: &[u8]) -> Result<Self> {
+        #[derive(serde::Deserialize)]
+        struct Probe {
+            #[serde(default)]
+            sierra_program: Option<serde::de::IgnoredAny>,
+        }
+
+        let probe = serde_json::from_slice::<Probe>(contract_definition_dump)
+            .context("Failed to parse contract_definition")?;
+
+        Ok(match probe.sierra_program {
+            Some(_) => Self::Sierra,
+            None => Self::Cairo,
+        })
+    }
+}
+
+/// Like [extract_abi_code_hash], but accepts both Cairo 0.x and Sierra class definitions.
+///
+/// For Sierra classes the ABI is the ABI string as given in the definition and the code is the
+/// Sierra program.
+pub fn extract_class_abi_code_hash(
+    contract_definition_dump: &[u8],
+) -> Result<(ClassFormat, Vec<u8>, Vec<u8>, ClassHash)> {
+    match ClassFormat::of_definition(contract_definition_dump)? {
+        ClassFormat::Cairo => {
+            let (abi, code, hash) = extract_abi_code_hash(contract_definition_dump)?;
+            Ok((ClassFormat::Cairo, abi, code, hash))
+        }
+        ClassFormat::Sierra => {
+            let definition = serde_json::from_slice::<json::SierraContractDefinition<'_>>(
+                contract_definition_dump,
+            )
+            .context("Failed to parse Sierra contract_definition")?;
+
+            let abi = definition.abi.as_bytes().to_vec();
+            let code = serde_json::to_vec(&definition.sierra_program)
+                .context("Serialize contract_definition.sierra_program")?;
+
+            let hash = compute_sierra_class_hash(definition).context("Compute class hash")?;
+
+            Ok((ClassFormat::Sierra, abi, code, hash))
+        }
+    }
+}
+
+/// Computes the class hash of a Sierra class definition.
+///
+/// Unlike the Cairo 0.x [class hash](compute_class_hash) this is a Poseidon hash over the class
+/// version, the entry points, a truncated Keccak256 hash of the ABI string and the Sierra program.
+/// See the [cairo-lang implementation][cairo-compute].
+///
+/// [cairo-compute]: https://github.com/starkware-libs/cairo-lang/blob/v0.11.0/src/starkware/starknet/core/os/contract_class/class_hash.py
+fn compute_sierra_class_hash(definition: json::SierraContractDefinition<'_>) -> Result<ClassHash> {
+    use EntryPointType::*;
+
+    let version = format!("CONTRACT_CLASS_V{}", definition.contract_class_version);
+    let version = StarkHash::from_be_slice(version.as_bytes())
+        .context("Contract class version is too long")?;
+
+    let mut outer = PoseidonHasher::default();
+    outer.update(version);
+
+    [External, L1Handler, Constructor]
+        .iter()
+        .map(|key| {
+            definition
+                .entry_points_by_type
+                .get(key)
+                .unwrap_or(&Vec::new())
+                .iter()
+                // flatten each entry point to get a list of (selector, function_idx, ...)
+                .flat_map(|x| [x.selector, StarkHash::from_u64(x.function_idx)].into_iter())
+                .fold(PoseidonHasher::default(), |mut hasher, next| {
+                    hasher.update(next);
+                    hasher
+                })
+        })
+        .for_each(|x| outer.update(x.finalize()));
+
+    let abi_hash = truncated_keccak(<[u8; 32]>::from(sha3::Keccak256::digest(
+        definition.abi.as_bytes(),
+    )));
+    outer.update(abi_hash);
+
+    outer.update(poseidon_hash_many(&definition.sierra_program));
+
+    Ok(ClassHash(outer.finalize()))
+}
+
+/// Computes the compiled class hash of a CASM class definition json blob, which is what the
+/// compiled class hash of a Sierra class declaration commits to.
+///
+/// See the [cairo-lang implementation][cairo-compute].
+///
+/// [cairo-compute]: https://github.com/starkware-libs/cairo-lang/blob/v0.11.0/src/starkware/starknet/core/os/contract_class/compiled_class_hash.py
+pub fn compute_casm_class_hash(casm_definition_dump: &[u8]) -> Result<CasmHash> {
+    use EntryPointType::*;
+
+    const COMPILED_CLASS_VERSION: &[u8] = b"COMPILED_CLASS_V1";
+
+    let definition = serde_json::from_slice::<json::CasmContractDefinition>(casm_definition_dump)
+        .context("Failed to parse CASM contract_definition")?;
+
+    let mut outer = PoseidonHasher::default();
+    outer.update(
+        StarkHash::from_be_slice(COMPILED_CLASS_VERSION).expect("Version fits into a felt"),
+    );
+
+    for key in [External, L1Handler, Constructor] {
+        let mut entry_points = PoseidonHasher::default();
+
+        for entry_point in definition
+            .entry_points_by_type
+            .get(&key)
+            .unwrap_or(&Vec::new())
+        {
+            let builtins = entry_point
+                .builtins
+                .iter()
+                .enumerate()
+                .map(|(i, s)| {
+                    StarkHash::from_be_slice(s.as_bytes())
+                        .with_context(|| format!("Invalid builtin at index {i}"))
+                })
+                .collect::<Result<Vec<_>>>()
+                .with_context(|| format!("Failed to process {key} entry point builtins"))?;
+
+            entry_points.update(entry_point.selector);
+            entry_points.update(StarkHash::from_u64(entry_point.offset));
+            entry_points.update(poseidon_hash_many(&builtins));
+        }
+
+        outer.update(entry_points.finalize());
+    }
+
+    outer.update(poseidon_hash_many(&definition.bytecode));
+
+    Ok(CasmHash(outer.finalize()))
+}
+
 /// Extract JSON representation of program and entry points from the contract definition.
 pub(crate) fn extract_program_and_entry_points_by_type(
     contract_definition_dump: &[u8],
@@ -321,6 +475,8 @@ mod json {
     use std::borrow::Cow;
     use std::collections::{BTreeMap, HashMap};
 
+    use stark_hash::StarkHash;
+
     use crate::sequencer::request::contract::{EntryPointType, SelectorAndOffset};
 
     /// Our version of the cairo contract definition used to deserialize and re-serialize a
@@ -391,6 +547,50 @@ mod json {
         pub reference_manager: serde_json::Value,
     }
 
+    /// A Sierra class definition, as served by the feeder gateway.
+    ///
+    /// Unlike the Cairo 0.x definition nothing is hashed in its serialized form, so everything
+    /// can be parsed up front.
+    #[derive(serde::Deserialize)]
+    #[serde(deny_unknown_fields)]
+    pub struct SierraContractDefinition<'a> {
+        /// The contract ABI, which is a string rather than json.
+        #[serde(borrow)]
+        pub abi: Cow<'a, str>,
+
+        pub sierra_program: Vec<StarkHash>,
+
+        #[serde(borrow)]
+        pub contract_class_version: Cow<'a, str>,
+
+        pub entry_points_by_type: HashMap<EntryPointType, Vec<SierraEntryPoint>>,
+    }
+
+    #[derive(serde::Deserialize)]
+    #[serde(deny_unknown_fields)]
+    pub struct SierraEntryPoint {
+        pub selector: StarkHash,
+        pub function_idx: u64,
+    }
+
+    /// The parts of a CASM class definition which contribute to the compiled class hash.
+    ///
+    /// Hints and the compiler version are not part of the hash, so unknown fields are allowed.
+    #[derive(serde::Deserialize)]
+    pub struct CasmContractDefinition {
+        pub bytecode: Vec<StarkHash>,
+
+        pub entry_points_by_type: HashMap<EntryPointType, Vec<CasmEntryPoint>>,
+    }
+
+    #[derive(serde::Deserialize)]
+    #[serde(deny_unknown_fields)]
+    pub struct CasmEntryPoint {
+        pub selector: StarkHash,
+        pub offset: u64,
+        pub builtins: Vec<String>,
+    }
+
     #[cfg(test)]
     mod roundtrip_tests {
         // FIXME: we should have many test cases utilizing this.
@@ -631,4 +831,65 @@ mod tests {
             starkhash!("01c584056064687e149968cbab758a3376d22aedc6a55823d1b3ecbee81b8fb9")
         );
     }
+
+    // The expected hashes match the cairo-lang Python implementation of `compute_class_hash` and
+    // `compute_compiled_class_hash`.
+    const SIERRA_DEFINITION: &str = r#"{"abi": "[{\"type\": \"function\", \"name\": \"increase_balance\", \"inputs\": [{\"name\": \"amount\", \"type\": \"core::felt252\"}], \"outputs\": [], \"state_mutability\": \"external\"}]", "contract_class_version": "0.1.0", "entry_points_by_type": {"CONSTRUCTOR": [], "EXTERNAL": [{"selector": "0x362398bec32bc0ebb411203221a35a0301193a96f317ebe5e40be9f60d15320", "function_idx": 0}, {"selector": "0x39e11d48192e4333233c7eb19d10ad67c362bb28580c604d67884c85da39695", "function_idx": 1}], "L1_HANDLER": []}, "sierra_program": ["0x1", "0x2", "0x3", "0xdeadbeef", "0x800000000000011000000000000000000000000000000000000000000000000"]}"#;
+
+    const CASM_DEFINITION: &str = r#"{"prime": "0x800000000000011000000000000000000000000000000000000000000000001", "compiler_version": "1.0.0", "bytecode": ["0xa0680017fff8000", "0x7", "0x482680017ffd8000", "0x800000000000011000000000000000000000000000000000000000000000000"], "hints": [[0, [{"TestLessThanOrEqual": {}}]]], "entry_points_by_type": {"EXTERNAL": [{"selector": "0x362398bec32bc0ebb411203221a35a0301193a96f317ebe5e40be9f60d15320", "offset": 0, "builtins": ["range_check"]}, {"selector": "0x39e11d48192e4333233c7eb19d10ad67c362bb28580c604d67884c85da39695", "offset": 2, "builtins": []}], "L1_HANDLER": [], "CONSTRUCTOR": [{"selector": "0x28ffe4ff0f226a9107253e17a904099aa4f63a02a5621de0576e5aa71bc5194", "offset": 3, "builtins": ["pedersen", "range_check"]}]}}"#;
+
+    #[test]
+    fn sierra_class_hash() {
+        use super::{extract_class_abi_code_hash, ClassFormat};
+        use crate::core::ClassHash;
+        use crate::starkhash;
+
+        let (format, abi, code, hash) =
+            extract_class_abi_code_hash(SIERRA_DEFINITION.as_bytes()).unwrap();
+
+        assert_eq!(format, ClassFormat::Sierra);
+        assert!(abi.starts_with(br#"[{"type": "function""#));
+        assert_eq!(
+            serde_json::from_slice::<Vec<stark_hash::StarkHash>>(&code)
+                .unwrap()
+                .len(),
+            5
+        );
+        assert_eq!(
+            hash,
+            ClassHash(starkhash!(
+                "0132bb676fec3fc594e1127b67766ddf0543dfca4f08c97026fe638c4a309b00"
+            ))
+        );
+    }
+
+    #[test]
+    fn cairo_class_format() {
+        use super::{extract_class_abi_code_hash, ClassFormat};
+
+        let definition =
+            zstd::decode_all(&include_bytes!("../../fixtures/contract_definition.json.zst")[..])
+                .unwrap();
+
+        let (format, _, _, hash) = extract_class_abi_code_hash(&definition).unwrap();
+
+        assert_eq!(format, ClassFormat::Cairo);
+        assert_eq!(hash, super::compute_class_hash(&definition).unwrap());
+    }
+
+    #[test]
+    fn casm_class_hash() {
+        use super::compute_casm_class_hash;
+        use crate::core::CasmHash;
+        use crate::starkhash;
+
+        let hash = compute_casm_class_hash(CASM_DEFINITION.as_bytes()).unwrap();
+
+        assert_eq!(
+            hash,
+            CasmHash(starkhash!(
+                "0430c1f1114ac49ed04c40f03e8cd2ea6a80d2c09985c2c66f37658020d7b66d"
+            ))
+        );
+    }
 }
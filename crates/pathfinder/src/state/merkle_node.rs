@@ -125,6 +125,14 @@ impl BinaryNode {
             return;
         }
 
+        let (left, right) = self.hash_inputs();
+        self.hash = Some(stark_hash(left, right));
+    }
+
+    /// Returns the hashes of the left and right child, whose Pedersen hash is this node's hash.
+    ///
+    /// Both children have to be committed first.
+    pub(crate) fn hash_inputs(&self) -> (StarkHash, StarkHash) {
         let left = match self.left.borrow().hash() {
             Some(hash) => hash,
             None => unreachable!("subtrees have to be commited first"),
@@ -135,7 +143,7 @@ impl BinaryNode {
             None => unreachable!("subtrees have to be commited first"),
         };
 
-        self.hash = Some(stark_hash(left, right));
+        (left, right)
     }
 }
 
@@ -221,19 +229,32 @@ impl EdgeNode {
             return;
         }
 
+        let (child, path) = self.hash_inputs();
+        self.set_hash(stark_hash(child, path));
+    }
+
+    /// Returns the hash of the child and the path, whose Pedersen hash makes up this node's hash.
+    ///
+    /// The child has to be committed first.
+    pub(crate) fn hash_inputs(&self) -> (StarkHash, StarkHash) {
         let child = match self.child.borrow().hash() {
             Some(hash) => hash,
             None => unreachable!("subtree has to be commited before"),
         };
 
         let path = StarkHash::from_bits(&self.path).unwrap();
+
+        (child, path)
+    }
+
+    /// Sets this node's hash from the Pedersen hash of its [inputs](Self::hash_inputs).
+    pub(crate) fn set_hash(&mut self, inputs_hash: StarkHash) {
         let mut length = [0; 32];
         // Safe as len() is guaranteed to be <= 251
         length[31] = self.path.len() as u8;
 
         let length = StarkHash::from_be_bytes(length).unwrap();
-        let hash = stark_hash(child, path) + length;
-        self.hash = Some(hash);
+        self.hash = Some(inputs_hash + length);
     }
 }
 
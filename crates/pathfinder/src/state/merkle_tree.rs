@@ -56,12 +56,13 @@ use crate::storage::merkle_tree::{
     PersistedBinaryNode, PersistedEdgeNode, PersistedNode, RcNodeStorage,
 };
 
-use stark_hash::StarkHash;
+use stark_hash::{stark_hash_batch, StarkHash};
 
 /// Backing storage for [`MerkleTree`].
 ///
-/// Default implementation and persistent implementation is the `RcNodeStorage`. Testing/future
-/// implementations include [`HashMap`](std::collections::HashMap) and `()` based implementations
+/// Default implementation and persistent implementation is the `RcNodeStorage`. The
+/// `InMemoryNodeStorage` is a reference counted equivalent without a database, for tests and
+/// benchmarks. [`HashMap`](std::collections::HashMap) and `()` based implementations are used
 /// where the backing storage is not persistent, or doesn't exist at all. The nodes will still be
 /// visitable in-memory.
 pub trait NodeStorage {
@@ -115,7 +116,7 @@ impl<'tx, 'queries> MerkleTree<RcNodeStorage<'tx, 'queries>> {
         root: StarkHash,
     ) -> anyhow::Result<Self> {
         let storage = RcNodeStorage::open(table, transaction)?;
-        Self::new(storage, root, 251)
+        Self::with_storage(storage, root)
     }
 }
 
@@ -159,6 +160,13 @@ impl<T: NodeStorage> MerkleTree<T> {
         Ok(tree)
     }
 
+    /// Loads an existing tree of height 251 from the given storage, or creates a new one if the
+    /// root is [StarkHash::ZERO]. See [`MerkleTree::<RcNodeStorage>::load`] for the database
+    /// backed equivalent.
+    pub fn with_storage(storage: T, root: StarkHash) -> anyhow::Result<Self> {
+        Self::new(storage, root, 251)
+    }
+
     pub fn empty(storage: T, max_height: u8) -> Self {
         Self::new(storage, StarkHash::ZERO, max_height).expect(
             "Since called with ZERO as root, there should not have been a query, and therefore no error",
@@ -177,7 +185,7 @@ impl<T: NodeStorage> MerkleTree<T> {
         // Go through tree, collect dirty nodes, calculate their hashes and
         // persist them. Take care to increment ref counts of child nodes. So in order
         // to do this correctly, will have to start back-to-front.
-        self.commit_subtree(&mut self.root.borrow_mut())?;
+        self.commit_subtree(&self.root)?;
         // unwrap is safe as `commit_subtree` will set the hash.
         let root = self.root.borrow().hash().unwrap();
         self.storage.increment_ref_count(root)?;
@@ -189,53 +197,92 @@ impl<T: NodeStorage> MerkleTree<T> {
 
     /// Persists any changes in this subtree to storage.
     ///
-    /// This necessitates recursively calculating the hash of, and
-    /// in turn persisting, any changed child nodes. This is necessary
-    /// as the parent node's hash relies on its childrens hashes.
+    /// This necessitates calculating the hash of, and in turn persisting, any
+    /// changed child nodes. This is necessary as the parent node's hash relies
+    /// on its childrens hashes.
+    ///
+    /// Dirty nodes are hashed layer by layer, starting from the bottom, so that
+    /// each layer can be hashed as a single batch.
     ///
     /// In effect, the entire subtree gets persisted.
-    fn commit_subtree(&self, node: &mut Node) -> anyhow::Result<()> {
-        use Node::*;
-        match node {
-            Unresolved(_) => { /* Unresolved nodes are already persisted. */ }
-            Leaf(_) => { /* storage wouldn't persist these even if we asked. */ }
-            Binary(binary) if binary.hash.is_some() => { /* not dirty, already persisted */ }
-            Edge(edge) if edge.hash.is_some() => { /* not dirty, already persisted */ }
-
-            Binary(binary) => {
-                self.commit_subtree(&mut binary.left.borrow_mut())?;
-                self.commit_subtree(&mut binary.right.borrow_mut())?;
-                // This will succeed as `commit_subtree` will set the child hashes.
-                binary.calculate_hash();
-                // unwrap is safe as `commit_subtree` will set the hashes.
-                let left = binary.left.borrow().hash().unwrap();
-                let right = binary.right.borrow().hash().unwrap();
-                let persisted_node = PersistedNode::Binary(PersistedBinaryNode { left, right });
-                // unwrap is safe as we just set the hash.
-                self.storage
-                    .upsert(binary.hash.unwrap(), persisted_node)
-                    .context("Failed to insert binary node")?;
+    fn commit_subtree(&self, node: &Rc<RefCell<Node>>) -> anyhow::Result<()> {
+        let mut layers = Vec::new();
+        Self::collect_dirty_nodes(node, &mut layers);
+
+        for layer in layers {
+            let inputs = layer
+                .iter()
+                .map(|node| match &*node.borrow() {
+                    Node::Binary(binary) => binary.hash_inputs(),
+                    Node::Edge(edge) => edge.hash_inputs(),
+                    _ => unreachable!("only binary and edge nodes can be dirty"),
+                })
+                .collect::<Vec<_>>();
+            let hashes = stark_hash_batch(&inputs);
+
+            for (node, hash) in layer.iter().zip(hashes) {
+                match &mut *node.borrow_mut() {
+                    Node::Binary(binary) => {
+                        binary.hash = Some(hash);
+                        // unwrap is safe as the children were committed in a previous layer.
+                        let left = binary.left.borrow().hash().unwrap();
+                        let right = binary.right.borrow().hash().unwrap();
+                        let persisted_node =
+                            PersistedNode::Binary(PersistedBinaryNode { left, right });
+                        self.storage
+                            .upsert(hash, persisted_node)
+                            .context("Failed to insert binary node")?;
+                    }
+                    Node::Edge(edge) => {
+                        edge.set_hash(hash);
+                        // unwrap is safe as the child was committed in a previous layer.
+                        let child = edge.child.borrow().hash().unwrap();
+                        let persisted_node = PersistedNode::Edge(PersistedEdgeNode {
+                            path: edge.path.clone(),
+                            child,
+                        });
+                        // unwrap is safe as we just set the hash.
+                        self.storage
+                            .upsert(edge.hash.unwrap(), persisted_node)
+                            .context("Failed to insert edge node")?;
+                    }
+                    _ => unreachable!("only binary and edge nodes can be dirty"),
+                }
             }
+        }
 
-            Edge(edge) => {
-                self.commit_subtree(&mut edge.child.borrow_mut())?;
-                // This will succeed as `commit_subtree` will set the child's hash.
-                edge.calculate_hash();
+        Ok(())
+    }
 
-                // unwrap is safe as `commit_subtree` will set the hash.
-                let child = edge.child.borrow().hash().unwrap();
-                let persisted_node = PersistedNode::Edge(PersistedEdgeNode {
-                    path: edge.path.clone(),
-                    child,
-                });
-                // unwrap is safe as we just set the hash.
-                self.storage
-                    .upsert(edge.hash.unwrap(), persisted_node)
-                    .context("Failed to insert edge node")?;
+    /// Collects the dirty nodes of this subtree into `layers`, where a node's layer is one
+    /// above that of its highest dirty child.
+    ///
+    /// Returns the layer of `node`, or [None] if it is not dirty.
+    fn collect_dirty_nodes(
+        node: &Rc<RefCell<Node>>,
+        layers: &mut Vec<Vec<Rc<RefCell<Node>>>>,
+    ) -> Option<usize> {
+        use Node::*;
+        let layer = match &*node.borrow() {
+            Binary(binary) if binary.hash.is_none() => {
+                let left = Self::collect_dirty_nodes(&binary.left, layers);
+                let right = Self::collect_dirty_nodes(&binary.right, layers);
+                left.max(right).map_or(0, |layer| layer + 1)
+            }
+            Edge(edge) if edge.hash.is_none() => {
+                Self::collect_dirty_nodes(&edge.child, layers).map_or(0, |layer| layer + 1)
             }
+            // Unresolved nodes are already persisted, storage wouldn't persist leaves even if we
+            // asked and binary and edge nodes with a hash are not dirty.
+            Unresolved(_) | Leaf(_) | Binary(_) | Edge(_) => return None,
+        };
+
+        if layers.len() <= layer {
+            layers.resize_with(layer + 1, Vec::new);
         }
+        layers[layer].push(node.clone());
 
-        Ok(())
+        Some(layer)
     }
 
     /// Sets the value of a key. To delete a key, set the value to [StarkHash::ZERO].
@@ -11,7 +11,7 @@ use crate::{
     core::{
         ContractAddress, ContractRoot, ContractStateHash, GlobalRoot, StorageAddress, StorageValue,
     },
-    state::merkle_tree::{MerkleTree, Visit},
+    state::merkle_tree::{MerkleTree, NodeStorage, Visit},
     storage::merkle_tree::RcNodeStorage,
 };
 
@@ -19,17 +19,27 @@ use super::merkle_node::Node;
 
 /// A Binary Merkle-Patricia Tree which contains
 /// the storage state of all StarkNet contracts.
-pub struct ContractsStateTree<'tx, 'queries> {
-    tree: MerkleTree<RcNodeStorage<'tx, 'queries>>,
+///
+/// Backed by the database unless loaded [with another storage](Self::with_storage).
+pub struct ContractsStateTree<S> {
+    tree: MerkleTree<S>,
 }
 
-impl<'tx> ContractsStateTree<'tx, '_> {
+impl<'tx> ContractsStateTree<RcNodeStorage<'tx, 'static>> {
     pub fn load(transaction: &'tx Transaction<'tx>, root: ContractRoot) -> anyhow::Result<Self> {
         // TODO: move the string into storage.
         let tree = MerkleTree::load("tree_contracts", transaction, root.0)?;
 
         Ok(Self { tree })
     }
+}
+
+impl<S: NodeStorage> ContractsStateTree<S> {
+    pub fn with_storage(storage: S, root: ContractRoot) -> anyhow::Result<Self> {
+        let tree = MerkleTree::with_storage(storage, root.0)?;
+
+        Ok(Self { tree })
+    }
 
     #[allow(dead_code)]
     pub fn get(&self, address: StorageAddress) -> anyhow::Result<Option<StorageValue>> {
@@ -58,17 +68,27 @@ impl<'tx> ContractsStateTree<'tx, '_> {
 
 /// A Binary Merkle-Patricia Tree which contains
 /// the global state of StarkNet.
-pub struct GlobalStateTree<'tx, 'queries> {
-    tree: MerkleTree<RcNodeStorage<'tx, 'queries>>,
+///
+/// Backed by the database unless loaded [with another storage](Self::with_storage).
+pub struct GlobalStateTree<S> {
+    tree: MerkleTree<S>,
 }
 
-impl<'tx> GlobalStateTree<'tx, '_> {
+impl<'tx> GlobalStateTree<RcNodeStorage<'tx, 'static>> {
     pub fn load(transaction: &'tx Transaction<'tx>, root: GlobalRoot) -> anyhow::Result<Self> {
         // TODO: move the string into storage.
         let tree = MerkleTree::load("tree_global", transaction, root.0)?;
 
         Ok(Self { tree })
     }
+}
+
+impl<S: NodeStorage> GlobalStateTree<S> {
+    pub fn with_storage(storage: S, root: GlobalRoot) -> anyhow::Result<Self> {
+        let tree = MerkleTree::with_storage(storage, root.0)?;
+
+        Ok(Self { tree })
+    }
 
     pub fn get(&self, address: ContractAddress) -> anyhow::Result<Option<ContractStateHash>> {
         let value = self.tree.get(address.view_bits())?;
@@ -97,3 +117,48 @@ impl<'tx> GlobalStateTree<'tx, '_> {
         self.tree.dfs(f)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::starkhash_bytes;
+    use crate::storage::merkle_tree::InMemoryNodeStorage;
+
+    #[test]
+    fn in_memory_matches_database() {
+        let updates = [
+            (b"contract 0", b"state 0"),
+            (b"contract 1", b"state 1"),
+            (b"contract 2", b"state 2"),
+        ]
+        .map(|(address, state)| {
+            (
+                ContractAddress::new_or_panic(starkhash_bytes!(address)),
+                ContractStateHash(starkhash_bytes!(state)),
+            )
+        });
+
+        let mut conn = rusqlite::Connection::open_in_memory().unwrap();
+        let transaction = conn.transaction().unwrap();
+        let mut tree =
+            GlobalStateTree::load(&transaction, GlobalRoot(stark_hash::StarkHash::ZERO)).unwrap();
+        let storage = InMemoryNodeStorage::default();
+        let mut in_memory =
+            GlobalStateTree::with_storage(storage.clone(), GlobalRoot(stark_hash::StarkHash::ZERO))
+                .unwrap();
+
+        for (address, state) in updates {
+            tree.set(address, state).unwrap();
+            in_memory.set(address, state).unwrap();
+        }
+
+        let root = tree.apply().unwrap();
+        assert_eq!(in_memory.apply().unwrap(), root);
+
+        // The committed tree can be loaded again from a clone of the storage.
+        let in_memory = GlobalStateTree::with_storage(storage, root).unwrap();
+        for (address, state) in updates {
+            assert_eq!(in_memory.get(address).unwrap(), Some(state));
+        }
+    }
+}
@@ -1,33 +1,39 @@
 pub mod l1;
 pub mod l2;
+pub mod messages;
+mod metrics;
 mod pending;
+pub mod repair;
+pub mod replica;
 
+use std::collections::HashMap;
 use std::future::Future;
 use std::sync::Arc;
 
 use crate::{
     core::{
-        Chain, ClassHash, ContractRoot, GasPrice, GlobalRoot, SequencerAddress, StarknetBlockHash,
-        StarknetBlockNumber,
+        CasmHash, Chain, ClassHash, ContractRoot, EventCommitment, GasPrice, GlobalRoot,
+        SequencerAddress, StarknetBlockHash, StarknetBlockNumber, TransactionCommitment,
     },
     ethereum::{log::StateUpdateLog, transport::EthereumTransport},
+    rpc::cache::ResponseCache,
     rpc::v01::types::reply::{syncing, syncing::NumberedBlock, Syncing as SyncStatus},
     sequencer::{
         self,
-        reply::{Block, MaybePendingBlock, PendingBlock, StateUpdate},
+        reply::{transaction, Block, MaybePendingBlock, PendingBlock, StateUpdate},
     },
     state::{calculate_contract_state_hash, state_tree::GlobalStateTree, update_contract_state},
     storage::{
-        ContractCodeTable, ContractsStateTable, ContractsTable, L1StateTable, L1TableBlockId,
-        RefsTable, StarknetBlock, StarknetBlocksBlockId, StarknetBlocksTable,
-        StarknetStateUpdatesTable, StarknetTransactionsTable, Storage,
+        ContractCodeTable, L1StateTable, L1TableBlockId, L2Message, MessageDirection,
+        StarknetBlock, StarknetBlocksBlockId, StarknetBlocksTable, Storage, StorageBackend,
     },
 };
 
 use anyhow::Context;
-use rusqlite::{Connection, Transaction, TransactionBehavior};
+use rusqlite::{Connection, TransactionBehavior};
 use stark_hash::StarkHash;
 use tokio::sync::{mpsc, RwLock};
+use web3::types::H256;
 
 pub struct State {
     pub status: RwLock<SyncStatus>,
@@ -94,6 +100,8 @@ impl PendingData {
 }
 
 /// Implements the main sync loop, where L1 and L2 sync results are combined.
+///
+/// Returns once `shutdown` resolves, after the database update in progress has completed.
 #[allow(clippy::too_many_arguments)]
 pub async fn sync<Transport, SequencerClient, F1, F2, L1Sync, L2Sync>(
     storage: Storage,
@@ -104,7 +112,10 @@ pub async fn sync<Transport, SequencerClient, F1, F2, L1Sync, L2Sync>(
     mut l1_sync: L1Sync,
     l2_sync: L2Sync,
     pending_data: PendingData,
+    response_cache: ResponseCache,
     pending_poll_interval: Option<std::time::Duration>,
+    block_validation_mode: l2::BlockValidationMode,
+    shutdown: impl Future<Output = ()> + Send,
 ) -> anyhow::Result<()>
 where
     Transport: EthereumTransport + Clone,
@@ -118,9 +129,12 @@ where
             Option<(StarknetBlockNumber, StarknetBlockHash, GlobalRoot)>,
             Chain,
             Option<std::time::Duration>,
+            l2::BlockValidationMode,
         ) -> F2
         + Copy,
 {
+    metrics::register();
+
     // TODO: should this be owning a Storage, or just take in a Connection?
     let mut db_conn = storage
         .connection()
@@ -162,6 +176,7 @@ where
         l2_head,
         chain,
         pending_poll_interval,
+        block_validation_mode,
     ));
 
     let mut existed = (0, 0);
@@ -174,8 +189,17 @@ where
     #[cfg(not(test))]
     const RESET_DELAY_ON_FAILURE: std::time::Duration = std::time::Duration::from_secs(60);
 
+    tokio::pin!(shutdown);
+
     loop {
         tokio::select! {
+            // Updates are only interrupted between events, so the current one always completes.
+            _ = &mut shutdown => {
+                l1_handle.abort();
+                l2_handle.abort();
+                tracing::info!("Sync stopped");
+                return Ok(());
+            }
             l1_event = rx_l1.recv() => match l1_event {
                 Some(l1::Event::Update(updates)) => {
                     let first = updates.first().map(|u| u.block_number.get());
@@ -200,9 +224,11 @@ where
                     }
                 }
                 Some(l1::Event::Reorg(reorg_tail)) => {
-                    l1_reorg(&mut db_conn, reorg_tail)
+                    let depth = l1_reorg(&mut db_conn, reorg_tail)
                         .await
                         .with_context(|| format!("Reorg L1 state to block {}", reorg_tail))?;
+                    response_cache.invalidate_from(reorg_tail);
+                    metrics::record_reorg(metrics::Layer::L1, depth);
 
                     let new_head = match reorg_tail {
                         StarknetBlockNumber::GENESIS => None,
@@ -258,34 +284,25 @@ where
                 },
             },
             l2_event = rx_l2.recv() => match l2_event {
-                Some(l2::Event::Update(block, state_update, timings)) => {
+                Some(l2::Event::Update((block, commitments), state_update, timings)) => {
                     pending_data.clear().await;
 
                     let block_number = block.block_number;
                     let block_hash = block.block_hash;
                     let storage_updates: usize = state_update.state_diff.storage_diffs.iter().map(|(_, storage_diffs)| storage_diffs.len()).sum();
                     let update_t = std::time::Instant::now();
-                    l2_update(&mut db_conn, *block, *state_update)
+                    let tree_t = l2_update(&mut db_conn, *block, commitments, *state_update)
                         .await
                         .with_context(|| format!("Update L2 state to {}", block_number))?;
                     let block_time = last_block_start.elapsed();
                     let update_t = update_t.elapsed();
+                    metrics::record_block(&timings, tree_t, update_t);
                     last_block_start = std::time::Instant::now();
 
                     block_time_avg = block_time_avg.mul_f32(1.0 - BLOCK_TIME_WEIGHT)
                         + block_time.mul_f32(BLOCK_TIME_WEIGHT);
 
-                    // Update sync status
-                    match &mut *state.status.write().await {
-                        SyncStatus::False(_) => {}
-                        SyncStatus::Status(status) => {
-                            status.current = NumberedBlock::from((block_hash, block_number));
-
-                            if status.highest.number <= block_number {
-                                status.highest = status.current;
-                            }
-                        }
-                    }
+                    update_sync_status_current(&state, block_hash, block_number).await;
 
                     // Give a simple log under INFO level, and a more verbose log
                     // with timing information under DEBUG+ level.
@@ -316,9 +333,11 @@ where
                 Some(l2::Event::Reorg(reorg_tail)) => {
                     pending_data.clear().await;
 
-                    l2_reorg(&mut db_conn, reorg_tail)
+                    let depth = l2_reorg(&mut db_conn, reorg_tail)
                         .await
                         .with_context(|| format!("Reorg L2 state to {:?}", reorg_tail))?;
+                    response_cache.invalidate_from(reorg_tail);
+                    metrics::record_reorg(metrics::Layer::L2, depth);
 
                     let new_head = match reorg_tail {
                         StarknetBlockNumber::GENESIS => None,
@@ -374,6 +393,7 @@ where
                 Some(l2::Event::Pending(block, state_update)) => {
                     let deployed_classes = state_update.state_diff.deployed_contracts.iter().map(|x| x.class_hash);
                     let declared_classes = state_update.state_diff.declared_contracts.iter().cloned();
+                    let declared_sierra_classes = state_update.state_diff.declared_classes.iter().map(|x| x.class_hash);
                     let declared_classes_block = block
                         .transactions
                         .iter()
@@ -386,8 +406,16 @@ where
                         });
                     let classes = deployed_classes
                         .chain(declared_classes)
+                        .chain(declared_sierra_classes)
                         .chain(declared_classes_block);
-                    download_verify_and_insert_missing_classes(sequencer.clone(), &mut db_conn, classes)
+                    // Sierra classes must compile to the CASM they were declared with.
+                    let compiled_class_hashes = state_update
+                        .state_diff
+                        .declared_classes
+                        .iter()
+                        .map(|x| (x.class_hash, x.compiled_class_hash))
+                        .collect::<HashMap<_, _>>();
+                    download_verify_and_insert_missing_classes(sequencer.clone(), &mut db_conn, classes, &compiled_class_hashes)
                         .await
                         .context("Downloading missing classes for pending block")?;
 
@@ -441,7 +469,14 @@ where
                     let (new_tx, new_rx) = mpsc::channel(1);
                     rx_l2 = new_rx;
 
-                    let fut = l2_sync(new_tx, sequencer.clone(), l2_head, chain, pending_poll_interval);
+                    let fut = l2_sync(
+                        new_tx,
+                        sequencer.clone(),
+                        l2_head,
+                        chain,
+                        pending_poll_interval,
+                        block_validation_mode,
+                    );
 
                     l2_handle = tokio::spawn(async move {
                         #[cfg(not(test))]
@@ -455,6 +490,24 @@ where
     }
 }
 
+/// Updates sync state with the block the local state is at.
+async fn update_sync_status_current(
+    state: &State,
+    block_hash: StarknetBlockHash,
+    block_number: StarknetBlockNumber,
+) {
+    match &mut *state.status.write().await {
+        SyncStatus::False(_) => {}
+        SyncStatus::Status(status) => {
+            status.current = NumberedBlock::from((block_hash, block_number));
+
+            if status.highest.number <= block_number {
+                status.highest = status.current;
+            }
+        }
+    }
+}
+
 /// Periodically updates sync state with the latest block height.
 async fn update_sync_status_latest(
     state: Arc<State>,
@@ -521,225 +574,339 @@ async fn l1_update(connection: &mut Connection, updates: &[StateUpdateLog]) -> a
             .transaction_with_behavior(TransactionBehavior::Immediate)
             .context("Create database transaction")?;
 
-        for update in updates {
-            L1StateTable::upsert(&transaction, update).context("Insert update")?;
-        }
+        insert_l1_updates(&transaction, updates)?;
 
-        // Track combined L1 and L2 state.
-        let l1_l2_head = RefsTable::get_l1_l2_head(&transaction).context("Query L1-L2 head")?;
-        let expected_next = l1_l2_head
-            .map(|head| head + 1)
-            .unwrap_or(StarknetBlockNumber::GENESIS);
-
-        match updates.first() {
-            Some(update) if update.block_number == expected_next => {
-                let mut next_head = None;
-                for update in updates {
-                    let l2_root =
-                        StarknetBlocksTable::get(&transaction, update.block_number.into())
-                            .context("Query L2 root")?
-                            .map(|block| block.root);
-
-                    match l2_root {
-                        Some(l2_root) if l2_root == update.global_root => {
-                            next_head = Some(update.block_number);
-                        }
-                        _ => break,
+        transaction.commit().context("Commit database transaction")
+    })
+}
+
+/// Stores the L1 updates, and advances the L1-L2 head over the blocks they confirm.
+fn insert_l1_updates<S: StorageBackend>(
+    storage: S,
+    updates: &[StateUpdateLog],
+) -> anyhow::Result<()> {
+    for update in updates {
+        storage.upsert_l1_state(update).context("Insert update")?;
+    }
+
+    // Track combined L1 and L2 state.
+    let l1_l2_head = storage.l1_l2_head().context("Query L1-L2 head")?;
+    let expected_next = l1_l2_head
+        .map(|head| head + 1)
+        .unwrap_or(StarknetBlockNumber::GENESIS);
+
+    match updates.first() {
+        Some(update) if update.block_number == expected_next => {
+            let mut next_head = None;
+            for update in updates {
+                let l2_root = storage
+                    .block_root(update.block_number.into())
+                    .context("Query L2 root")?;
+
+                match l2_root {
+                    Some(l2_root) if l2_root == update.global_root => {
+                        next_head = Some(update.block_number);
                     }
+                    _ => break,
                 }
+            }
 
-                if let Some(next_head) = next_head {
-                    RefsTable::set_l1_l2_head(&transaction, Some(next_head))
-                        .context("Update L1-L2 head")?;
-                }
+            if let Some(next_head) = next_head {
+                storage
+                    .set_l1_l2_head(Some(next_head))
+                    .context("Update L1-L2 head")?;
             }
-            _ => {}
         }
+        _ => {}
+    }
 
-        transaction.commit().context("Commit database transaction")
-    })
+    Ok(())
 }
 
+/// Returns the number of removed blocks.
 async fn l1_reorg(
     connection: &mut Connection,
     reorg_tail: StarknetBlockNumber,
-) -> anyhow::Result<()> {
+) -> anyhow::Result<u64> {
     tokio::task::block_in_place(move || {
         let transaction = connection
             .transaction_with_behavior(TransactionBehavior::Immediate)
             .context("Create database transaction")?;
 
-        L1StateTable::reorg(&transaction, reorg_tail).context("Delete L1 state from database")?;
+        let head = L1StateTable::get(&transaction, L1TableBlockId::Latest)
+            .context("Query L1 head")?
+            .map(|update| update.block_number);
 
-        // Track combined L1 and L2 state.
-        let l1_l2_head = RefsTable::get_l1_l2_head(&transaction).context("Query L1-L2 head")?;
-        match l1_l2_head {
-            Some(head) if head >= reorg_tail => {
-                let new_head = match reorg_tail {
-                    StarknetBlockNumber::GENESIS => None,
-                    other => Some(other - 1),
-                };
-                RefsTable::set_l1_l2_head(&transaction, new_head).context("Update L1-L2 head")?;
-            }
-            _ => {}
-        }
+        reorg_l1_state(&transaction, reorg_tail)?;
 
-        transaction.commit().context("Commit database transaction")
+        transaction
+            .commit()
+            .context("Commit database transaction")?;
+        Ok(reorg_depth(head, reorg_tail))
     })
 }
 
-/// Returns the new [GlobalRoot] after the update.
+/// Deletes the L1 state of all blocks from `reorg_tail` onwards.
+pub(crate) fn reorg_l1_state<S: StorageBackend>(
+    storage: S,
+    reorg_tail: StarknetBlockNumber,
+) -> anyhow::Result<()> {
+    storage
+        .reorg_l1_state(reorg_tail)
+        .context("Delete L1 state from database")?;
+
+    reorg_l1_l2_head(storage, reorg_tail)
+}
+
+/// Returns the time spent updating the state trees.
 async fn l2_update(
     connection: &mut Connection,
     block: Block,
+    commitments: Option<(TransactionCommitment, EventCommitment)>,
     state_update: StateUpdate,
-) -> anyhow::Result<()> {
-    use crate::storage::CanonicalBlocksTable;
-
+) -> anyhow::Result<std::time::Duration> {
     tokio::task::block_in_place(move || {
         let transaction = connection
             .transaction_with_behavior(TransactionBehavior::Immediate)
             .context("Create database transaction")?;
 
-        let new_root = update_starknet_state(&transaction, &state_update)
-            .context("Updating Starknet state")?;
-
-        // Ensure that roots match.. what should we do if it doesn't? For now the whole sync process ends..
-        anyhow::ensure!(new_root == block.state_root, "State root mismatch");
-
-        // Update L2 database. These types shouldn't be options at this level,
-        // but for now the unwraps are "safe" in that these should only ever be
-        // None for pending queries to the sequencer, but we aren't using those here.
-        let starknet_block = StarknetBlock {
-            number: block.block_number,
-            hash: block.block_hash,
-            root: block.state_root,
-            timestamp: block.timestamp,
-            // Default value for cairo <0.8.2 is 0
-            gas_price: block.gas_price.unwrap_or(GasPrice::ZERO),
-            sequencer_address: block
-                .sequencer_address
-                .unwrap_or(SequencerAddress(StarkHash::ZERO)),
-        };
-        StarknetBlocksTable::insert(
-            &transaction,
-            &starknet_block,
-            block.starknet_version.as_deref(),
-        )
+        let tree_t = insert_block(&transaction, block, commitments, state_update)?;
+
+        transaction
+            .commit()
+            .context("Commit database transaction")?;
+        Ok(tree_t)
+    })
+}
+
+/// Applies the state update and stores the block. Returns the time spent updating the state trees.
+fn insert_block<S: StorageBackend>(
+    storage: S,
+    block: Block,
+    commitments: Option<(TransactionCommitment, EventCommitment)>,
+    state_update: StateUpdate,
+) -> anyhow::Result<std::time::Duration> {
+    let tree_t = std::time::Instant::now();
+    let new_root =
+        update_starknet_state(storage, &state_update).context("Updating Starknet state")?;
+    let tree_t = tree_t.elapsed();
+
+    // Ensure that roots match.. what should we do if it doesn't? For now the whole sync process ends..
+    anyhow::ensure!(new_root == block.state_root, "State root mismatch");
+
+    // Update L2 database. These types shouldn't be options at this level,
+    // but for now the unwraps are "safe" in that these should only ever be
+    // None for pending queries to the sequencer, but we aren't using those here.
+    let starknet_block = StarknetBlock {
+        number: block.block_number,
+        hash: block.block_hash,
+        root: block.state_root,
+        timestamp: block.timestamp,
+        // Default value for cairo <0.8.2 is 0
+        gas_price: block.gas_price.unwrap_or(GasPrice::ZERO),
+        sequencer_address: block
+            .sequencer_address
+            .unwrap_or(SequencerAddress(StarkHash::ZERO)),
+    };
+    storage
+        .insert_block(&starknet_block, block.starknet_version.as_deref())
         .context("Insert block into database")?;
 
-        let rpc_state_update = state_update.into();
-        StarknetStateUpdatesTable::insert(&transaction, block.block_hash, &rpc_state_update)
-            .context("Insert state update into database")?;
+    if let Some((transaction_commitment, event_commitment)) = commitments {
+        storage
+            .update_block_commitments(block.block_number, transaction_commitment, event_commitment)
+            .context("Insert block commitments into database")?;
+    }
 
-        CanonicalBlocksTable::insert(&transaction, block.block_number, block.block_hash)
-            .context("Inserting canonical block into database")?;
+    let rpc_state_update = state_update.into();
+    storage
+        .insert_state_update(block.block_hash, &rpc_state_update)
+        .context("Insert state update into database")?;
 
-        for class in rpc_state_update.state_diff.declared_contracts {
-            ContractCodeTable::update_declared_on_if_null(
-                &transaction,
-                class.class_hash,
-                block.block_hash,
-            )
+    storage
+        .insert_canonical_block(block.block_number, block.block_hash)
+        .context("Inserting canonical block into database")?;
+
+    for class in rpc_state_update.state_diff.declared_contracts {
+        storage
+            .update_class_declared_on_if_null(class.class_hash, block.block_hash)
             .with_context(|| format!("Setting declared_on for class={:?}", class.class_hash))?;
-        }
-        for class in rpc_state_update.state_diff.deployed_contracts {
-            ContractCodeTable::update_declared_on_if_null(
-                &transaction,
-                class.class_hash,
-                block.block_hash,
-            )
+    }
+    for class in rpc_state_update.state_diff.deployed_contracts {
+        storage
+            .update_class_declared_on_if_null(class.class_hash, block.block_hash)
             .with_context(|| format!("Setting declared_on for class={:?}", class.class_hash))?;
-        }
+    }
 
-        // Insert the transactions.
-        anyhow::ensure!(
-            block.transactions.len() == block.transaction_receipts.len(),
-            "Transactions and receipts mismatch. There were {} transactions and {} receipts.",
-            block.transactions.len(),
-            block.transaction_receipts.len()
-        );
-        let transaction_data = block
-            .transactions
-            .into_iter()
-            .zip(block.transaction_receipts.into_iter())
-            .collect::<Vec<_>>();
-        StarknetTransactionsTable::upsert(
-            &transaction,
+    // Insert the transactions.
+    anyhow::ensure!(
+        block.transactions.len() == block.transaction_receipts.len(),
+        "Transactions and receipts mismatch. There were {} transactions and {} receipts.",
+        block.transactions.len(),
+        block.transaction_receipts.len()
+    );
+    let transaction_data = block
+        .transactions
+        .into_iter()
+        .zip(block.transaction_receipts.into_iter())
+        .collect::<Vec<_>>();
+    storage
+        .upsert_transactions(
             starknet_block.hash,
             starknet_block.number,
             &transaction_data,
         )
         .context("Insert transaction data into database")?;
 
-        // Track combined L1 and L2 state.
-        let l1_l2_head = RefsTable::get_l1_l2_head(&transaction).context("Query L1-L2 head")?;
-        let expected_next = l1_l2_head
-            .map(|head| head + 1)
-            .unwrap_or(StarknetBlockNumber::GENESIS);
-
-        if expected_next == starknet_block.number {
-            let l1_root = L1StateTable::get_root(&transaction, starknet_block.number.into())
-                .context("Query L1 root")?;
-            if l1_root == Some(starknet_block.root) {
-                RefsTable::set_l1_l2_head(&transaction, Some(starknet_block.number))
-                    .context("Update L1-L2 head")?;
-            }
+    for (_, receipt) in &transaction_data {
+        for message in receipt_messages(starknet_block.number, receipt) {
+            storage
+                .insert_l2_message(&message)
+                .context("Insert message into database")?;
         }
+    }
 
-        transaction.commit().context("Commit database transaction")
-    })
+    // Track combined L1 and L2 state.
+    let l1_l2_head = storage.l1_l2_head().context("Query L1-L2 head")?;
+    let expected_next = l1_l2_head
+        .map(|head| head + 1)
+        .unwrap_or(StarknetBlockNumber::GENESIS);
+
+    if expected_next == starknet_block.number {
+        let l1_root = storage
+            .l1_root(starknet_block.number)
+            .context("Query L1 root")?;
+        if l1_root == Some(starknet_block.root) {
+            storage
+                .set_l1_l2_head(Some(starknet_block.number))
+                .context("Update L1-L2 head")?;
+        }
+    }
+
+    Ok(tree_t)
+}
+
+/// The messages consumed and sent by a transaction. Consumed messages without a nonce are
+/// skipped, as their hash is unknown.
+fn receipt_messages(
+    block_number: StarknetBlockNumber,
+    receipt: &transaction::Receipt,
+) -> Vec<L2Message> {
+    let consumed = receipt
+        .l1_to_l2_consumed_message
+        .iter()
+        .filter_map(|message| {
+            Some(L2Message {
+                hash: message.hash()?,
+                direction: MessageDirection::L1ToL2,
+                from_address: message.from_address.to_word(),
+                to_address: H256(message.to_address.get().to_be_bytes()),
+                payload: message
+                    .payload
+                    .iter()
+                    .map(|elem| H256(elem.0.to_be_bytes()))
+                    .collect(),
+                block_number,
+                transaction_hash: receipt.transaction_hash,
+            })
+        });
+    let sent = receipt.l2_to_l1_messages.iter().map(|message| L2Message {
+        hash: message.hash(),
+        direction: MessageDirection::L2ToL1,
+        from_address: H256(message.from_address.get().to_be_bytes()),
+        to_address: message.to_address.to_word(),
+        payload: message
+            .payload
+            .iter()
+            .map(|elem| H256(elem.0.to_be_bytes()))
+            .collect(),
+        block_number,
+        transaction_hash: receipt.transaction_hash,
+    });
+
+    consumed.chain(sent).collect()
 }
 
+/// Returns the number of removed blocks.
 async fn l2_reorg(
     connection: &mut Connection,
     reorg_tail: StarknetBlockNumber,
-) -> anyhow::Result<()> {
-    use crate::storage::CanonicalBlocksTable;
-
+) -> anyhow::Result<u64> {
     tokio::task::block_in_place(move || {
         let transaction = connection
             .transaction_with_behavior(TransactionBehavior::Immediate)
             .context("Create database transaction")?;
 
-        // TODO: clean up state tree's as well...
+        let head = StarknetBlocksTable::get_latest_number(&transaction).context("Query L2 head")?;
 
-        CanonicalBlocksTable::reorg(&transaction, reorg_tail)
-            .context("Delete canonical blocks from database")?;
+        reorg_l2_state(&transaction, reorg_tail)?;
 
-        StarknetBlocksTable::reorg(&transaction, reorg_tail)
-            .context("Delete L2 blocks from database")?;
+        transaction
+            .commit()
+            .context("Commit database transaction")?;
+        Ok(reorg_depth(head, reorg_tail))
+    })
+}
 
-        // Track combined L1 and L2 state.
-        let l1_l2_head = RefsTable::get_l1_l2_head(&transaction).context("Query L1-L2 head")?;
-        match l1_l2_head {
-            Some(head) if head >= reorg_tail => {
-                let new_head = match reorg_tail {
-                    StarknetBlockNumber::GENESIS => None,
-                    other => Some(other - 1),
-                };
-                RefsTable::set_l1_l2_head(&transaction, new_head).context("Update L1-L2 head")?;
-            }
-            _ => {}
+/// Number of blocks from `reorg_tail` up to and including `head`.
+fn reorg_depth(head: Option<StarknetBlockNumber>, reorg_tail: StarknetBlockNumber) -> u64 {
+    match head {
+        Some(head) if head >= reorg_tail => head.get() - reorg_tail.get() + 1,
+        _ => 0,
+    }
+}
+
+/// Deletes all blocks from `reorg_tail` onwards, along with their transactions, events and
+/// state updates.
+pub(crate) fn reorg_l2_state<S: StorageBackend>(
+    storage: S,
+    reorg_tail: StarknetBlockNumber,
+) -> anyhow::Result<()> {
+    // TODO: clean up state tree's as well...
+
+    storage.reorg_blocks(reorg_tail)?;
+
+    reorg_l1_l2_head(storage, reorg_tail)
+}
+
+/// Track combined L1 and L2 state.
+fn reorg_l1_l2_head<S: StorageBackend>(
+    storage: S,
+    reorg_tail: StarknetBlockNumber,
+) -> anyhow::Result<()> {
+    let l1_l2_head = storage.l1_l2_head().context("Query L1-L2 head")?;
+    match l1_l2_head {
+        Some(head) if head >= reorg_tail => {
+            let new_head = match reorg_tail {
+                StarknetBlockNumber::GENESIS => None,
+                other => Some(other - 1),
+            };
+            storage
+                .set_l1_l2_head(new_head)
+                .context("Update L1-L2 head")?;
         }
+        _ => {}
+    }
 
-        transaction.commit().context("Commit database transaction")
-    })
+    Ok(())
 }
 
-fn update_starknet_state(
-    transaction: &Transaction<'_>,
+fn update_starknet_state<S: StorageBackend>(
+    storage: S,
     state_update: &StateUpdate,
 ) -> anyhow::Result<GlobalRoot> {
-    let global_root = StarknetBlocksTable::get(transaction, StarknetBlocksBlockId::Latest)
+    let global_root = storage
+        .block_root(StarknetBlocksBlockId::Latest)
         .context("Query latest state root")?
-        .map(|block| block.root)
         .unwrap_or(GlobalRoot(StarkHash::ZERO));
+    let nodes = storage
+        .global_tree_nodes()
+        .context("Open global state tree storage")?;
     let mut global_tree =
-        GlobalStateTree::load(transaction, global_root).context("Loading global state tree")?;
+        GlobalStateTree::with_storage(nodes, global_root).context("Loading global state tree")?;
 
     for contract in &state_update.state_diff.deployed_contracts {
-        deploy_contract(transaction, &mut global_tree, contract).context("Deploying contract")?;
+        deploy_contract(storage, &mut global_tree, contract).context("Deploying contract")?;
     }
 
     // Copied so we can mutate the map. This lets us remove used nonces from the list.
@@ -751,7 +918,7 @@ fn update_starknet_state(
         let nonce = nonces.remove(contract_address);
 
         let contract_state_hash =
-            update_contract_state(*contract_address, updates, nonce, &global_tree, transaction)
+            update_contract_state(*contract_address, updates, nonce, &global_tree, storage)
                 .context("Update contract state")?;
 
         // Update the global state tree.
@@ -762,14 +929,9 @@ fn update_starknet_state(
 
     // Apply all remaining nonces (without storage updates).
     for (contract_address, nonce) in nonces {
-        let contract_state_hash = update_contract_state(
-            contract_address,
-            &[],
-            Some(nonce),
-            &global_tree,
-            transaction,
-        )
-        .context("Update contract nonce")?;
+        let contract_state_hash =
+            update_contract_state(contract_address, &[], Some(nonce), &global_tree, storage)
+                .context("Update contract nonce")?;
 
         // Update the global state tree.
         global_tree
@@ -783,9 +945,9 @@ fn update_starknet_state(
         .context("Apply global state tree updates")
 }
 
-fn deploy_contract(
-    transaction: &Transaction<'_>,
-    global_tree: &mut GlobalStateTree<'_, '_>,
+fn deploy_contract<S: StorageBackend>(
+    storage: S,
+    global_tree: &mut GlobalStateTree<S::Nodes>,
     contract: &sequencer::reply::state_update::DeployedContract,
 ) -> anyhow::Result<()> {
     // Add a new contract to global tree, the contract root is initialized to ZERO.
@@ -799,15 +961,11 @@ fn deploy_contract(
     global_tree
         .set(contract.address, state_hash)
         .context("Adding deployed contract to global state tree")?;
-    ContractsStateTable::upsert(
-        transaction,
-        state_hash,
-        class_hash,
-        contract_root,
-        contract_nonce,
-    )
-    .context("Insert constract state hash into contracts state table")?;
-    ContractsTable::upsert(transaction, contract.address, class_hash)
+    storage
+        .upsert_contract_state(state_hash, class_hash, contract_root, contract_nonce)
+        .context("Insert constract state hash into contracts state table")?;
+    storage
+        .upsert_contract(contract.address, class_hash)
         .context("Inserting class hash into contracts table")
 }
 
@@ -820,9 +978,8 @@ async fn download_verify_and_insert_missing_classes<
     sequencer: SequencerClient,
     connection: &mut Connection,
     classes: ClassIter,
+    compiled_class_hashes: &HashMap<ClassHash, CasmHash>,
 ) -> anyhow::Result<()> {
-    use crate::state::class_hash::extract_abi_code_hash;
-
     // Make list unique.
     let classes = classes
         .collect::<std::collections::HashSet<_>>()
@@ -848,50 +1005,13 @@ async fn download_verify_and_insert_missing_classes<
 
     // For each missing, download, verify and insert definition.
     for class_hash in missing {
-        let definition = sequencer
-            .class_by_hash(class_hash)
+        let compressed = l2::download_and_compress_class(class_hash, &sequencer)
             .await
             .with_context(|| format!("Downloading class {}", class_hash.0))?;
 
-        // Parse the contract definition for ABI, code and calculate the class hash. This can
-        // be expensive, so perform in a blocking task.
-        let extract = tokio::task::spawn_blocking(move || -> anyhow::Result<_> {
-            let (abi, bytecode, hash) = extract_abi_code_hash(&definition)?;
-            Ok((definition, abi, bytecode, hash))
-        });
-        let (definition, abi, bytecode, hash) = extract
-            .await
-            .context("Parse class definition and compute hash")??;
-
-        // Sanity check.
-        anyhow::ensure!(
-            class_hash == hash,
-            "Class hash mismatch, {} instead of {}",
-            hash.0,
-            class_hash.0
-        );
-
-        let compress = tokio::task::spawn_blocking(move || -> anyhow::Result<_> {
-            let mut compressor =
-                zstd::bulk::Compressor::new(10).context("Create zstd compressor")?;
-
-            let abi = compressor.compress(&abi).context("Compress ABI")?;
-            let bytecode = compressor
-                .compress(&bytecode)
-                .context("Compress bytecode")?;
-            let definition = compressor
-                .compress(&definition)
-                .context("Compress definition")?;
-
-            Ok((abi, bytecode, definition))
-        });
-        let (abi, bytecode, definition) = compress.await.context("Compress class")??;
-        let compressed = crate::state::CompressedContract {
-            abi,
-            bytecode,
-            definition,
-            hash,
-        };
+        if let Some(expected) = compiled_class_hashes.get(&class_hash) {
+            l2::verify_compiled_class_hash(&compressed, *expected)?;
+        }
 
         tokio::task::block_in_place(|| {
             let transaction =
@@ -923,6 +1043,7 @@ pub fn head_poll_interval(chain: crate::core::Chain) -> std::time::Duration {
         Mainnet => Duration::from_secs(60 * 5),
         // 30 second interval for a 2 minute block time.
         Testnet | Testnet2 | Integration => Duration::from_secs(30),
+        Custom(spec) => spec.head_poll_interval,
     }
 }
 
@@ -931,17 +1052,21 @@ mod tests {
     use super::{l1, l2};
     use crate::{
         core::{
-            CallParam, Chain, ClassHash, ConstructorParam, ContractAddress, ContractAddressSalt,
-            EntryPoint, EthereumBlockHash, EthereumBlockNumber, EthereumLogIndex,
-            EthereumTransactionHash, EthereumTransactionIndex, Fee, GasPrice, GlobalRoot,
-            SequencerAddress, StarknetBlockHash, StarknetBlockNumber, StarknetBlockTimestamp,
-            StarknetTransactionHash, StorageAddress, StorageValue, TransactionNonce,
-            TransactionSignatureElem, TransactionVersion,
+            CallParam, CasmHash, Chain, ClassHash, ConstructorParam, ContractAddress,
+            ContractAddressSalt, EntryPoint, EthereumBlockHash, EthereumBlockNumber,
+            EthereumLogIndex, EthereumTransactionHash, EthereumTransactionIndex, Fee, GasPrice,
+            GlobalRoot, SequencerAddress, StarknetBlockHash, StarknetBlockNumber,
+            StarknetBlockTimestamp, StarknetTransactionHash, StorageAddress, StorageValue,
+            TransactionNonce, TransactionSignatureElem, TransactionVersion,
         },
         ethereum,
+        rpc::cache::ResponseCache,
         rpc::v01::types::BlockHashOrTag,
         sequencer::{
-            self, error::SequencerError, reply, request::add_transaction::ContractDefinition,
+            self,
+            error::SequencerError,
+            reply,
+            request::add_transaction::{ClassDefinition, ContractDefinition},
         },
         state::{self, sync::PendingData},
         storage::{self, L1StateTable, RefsTable, StarknetBlocksTable, Storage},
@@ -989,6 +1114,13 @@ mod tests {
         async fn gas_price(&self) -> web3::Result<web3::types::U256> {
             unimplemented!()
         }
+
+        async fn tagged_block_number(
+            &self,
+            _: ethereum::transport::BlockTag,
+        ) -> web3::Result<Option<u64>> {
+            unimplemented!()
+        }
     }
 
     // We need a simple clonable mock here. Satisfies the sync() internals,
@@ -1019,6 +1151,13 @@ mod tests {
             unimplemented!()
         }
 
+        async fn compiled_class_by_hash(
+            &self,
+            _: ClassHash,
+        ) -> Result<bytes::Bytes, SequencerError> {
+            unimplemented!()
+        }
+
         async fn class_hash_at(&self, _: ContractAddress) -> Result<ClassHash, SequencerError> {
             unimplemented!()
         }
@@ -1078,8 +1217,9 @@ mod tests {
             _: Fee,
             _: Vec<TransactionSignatureElem>,
             _: TransactionNonce,
-            _: ContractDefinition,
+            _: ClassDefinition,
             _: ContractAddress,
+            _: Option<CasmHash>,
             _: Option<String>,
         ) -> Result<reply::add_transaction::DeclareResponse, SequencerError> {
             unimplemented!()
@@ -1127,6 +1267,7 @@ mod tests {
         _: Option<(StarknetBlockNumber, StarknetBlockHash, GlobalRoot)>,
         _: Chain,
         _: Option<std::time::Duration>,
+        _: l2::BlockValidationMode,
     ) -> anyhow::Result<()> {
         // Avoid being restarted all the time by the outer sync() loop
         std::future::pending::<()>().await;
@@ -1170,6 +1311,8 @@ mod tests {
             transaction_receipts: vec![],
             transactions: vec![],
             starknet_version: None,
+            transaction_commitment: None,
+            event_commitment: None,
         };
         pub static ref BLOCK1: reply::Block = reply::Block {
             block_hash: StarknetBlockHash(*B),
@@ -1183,6 +1326,8 @@ mod tests {
             transaction_receipts: vec![],
             transactions: vec![],
             starknet_version: None,
+            transaction_commitment: None,
+            event_commitment: None,
         };
         pub static ref STORAGE_BLOCK0: storage::StarknetBlock = storage::StarknetBlock {
             number: StarknetBlockNumber::GENESIS,
@@ -1209,6 +1354,7 @@ mod tests {
                 storage_diffs: std::collections::HashMap::new(),
                 deployed_contracts: vec![],
                 declared_contracts: vec![],
+                declared_classes: vec![],
                 nonces: std::collections::HashMap::new(),
             },
         };
@@ -1270,7 +1416,10 @@ mod tests {
                 l1,
                 l2_noop,
                 PendingData::default(),
+                ResponseCache::default(),
                 None,
+                l2::BlockValidationMode::AllowMismatch,
+                std::future::pending(),
             ));
 
             // TODO Find a better way to figure out that the DB update has already been performed
@@ -1299,6 +1448,9 @@ mod tests {
 
     #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
     async fn l1_reorg() {
+        // Other concurrent tests could be checking the reorg and block metrics
+        let _guard = crate::monitoring::metrics::test::RecorderGuard::lock_as_noop();
+
         let results = [
             // Case 0: single block in L1, reorg on genesis
             (vec![STATE_UPDATE_LOG0.clone()], 0),
@@ -1343,7 +1495,10 @@ mod tests {
                 l1,
                 l2_noop,
                 PendingData::default(),
+                ResponseCache::default(),
                 None,
+                l2::BlockValidationMode::AllowMismatch,
+                std::future::pending(),
             ));
 
             // TODO Find a better way to figure out that the DB update has already been performed
@@ -1412,7 +1567,10 @@ mod tests {
             l1,
             l2_noop,
             PendingData::default(),
+            ResponseCache::default(),
             None,
+            l2::BlockValidationMode::AllowMismatch,
+            std::future::pending(),
         ));
 
         tokio::time::sleep(Duration::from_millis(10)).await;
@@ -1447,7 +1605,10 @@ mod tests {
             l1,
             l2_noop,
             PendingData::default(),
+            ResponseCache::default(),
             None,
+            l2::BlockValidationMode::AllowMismatch,
+            std::future::pending(),
         ));
 
         let timeout = std::time::Duration::from_secs(1);
@@ -1467,6 +1628,9 @@ mod tests {
 
     #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
     async fn l2_update() {
+        // Other concurrent tests could be checking the reorg and block metrics
+        let _guard = crate::monitoring::metrics::test::RecorderGuard::lock_as_noop();
+
         let chain = Chain::Testnet;
         let sync_state = Arc::new(state::SyncState::default());
 
@@ -1481,9 +1645,9 @@ mod tests {
         };
 
         // A simple L2 sync task
-        let l2 = move |tx: mpsc::Sender<l2::Event>, _, _, _, _| async move {
+        let l2 = move |tx: mpsc::Sender<l2::Event>, _, _, _, _, _| async move {
             tx.send(l2::Event::Update(
-                Box::new(block()),
+                (Box::new(block()), None),
                 Box::new(state_update()),
                 timings,
             ))
@@ -1521,7 +1685,10 @@ mod tests {
                 l1_noop,
                 l2,
                 PendingData::default(),
+                ResponseCache::default(),
                 None,
+                l2::BlockValidationMode::AllowMismatch,
+                std::future::pending(),
             ));
 
             // TODO Find a better way to figure out that the DB update has already been performed
@@ -1548,6 +1715,9 @@ mod tests {
 
     #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
     async fn l2_reorg() {
+        // Other concurrent tests could be checking the reorg and block metrics
+        let _guard = crate::monitoring::metrics::test::RecorderGuard::lock_as_noop();
+
         let results = [
             // Case 0: single block in L2, reorg on genesis
             (vec![STORAGE_BLOCK0.clone()], 0),
@@ -1561,7 +1731,7 @@ mod tests {
             let tx = connection.transaction().unwrap();
 
             // A simple L2 sync task
-            let l2 = move |tx: mpsc::Sender<l2::Event>, _, _, _, _| async move {
+            let l2 = move |tx: mpsc::Sender<l2::Event>, _, _, _, _, _| async move {
                 tx.send(l2::Event::Reorg(StarknetBlockNumber::new_or_panic(
                     reorg_on_block,
                 )))
@@ -1589,7 +1759,10 @@ mod tests {
                 l1_noop,
                 l2,
                 PendingData::default(),
+                ResponseCache::default(),
                 None,
+                l2::BlockValidationMode::AllowMismatch,
+                std::future::pending(),
             ));
 
             // TODO Find a better way to figure out that the DB update has already been performed
@@ -1627,13 +1800,14 @@ mod tests {
         let connection = storage.connection().unwrap();
 
         // A simple L2 sync task
-        let l2 = |tx: mpsc::Sender<l2::Event>, _, _, _, _| async move {
+        let l2 = |tx: mpsc::Sender<l2::Event>, _, _, _, _, _| async move {
             let zstd_magic = vec![0x28, 0xb5, 0x2f, 0xfd];
             tx.send(l2::Event::NewContract(state::CompressedContract {
                 abi: zstd_magic.clone(),
                 bytecode: zstd_magic.clone(),
                 definition: zstd_magic,
                 hash: ClassHash(*A),
+                casm: None,
             }))
             .await
             .unwrap();
@@ -1652,7 +1826,10 @@ mod tests {
             l1_noop,
             l2,
             PendingData::default(),
+            ResponseCache::default(),
             None,
+            l2::BlockValidationMode::AllowMismatch,
+            std::future::pending(),
         ));
 
         // TODO Find a better way to figure out that the DB update has already been performed
@@ -1674,7 +1851,7 @@ mod tests {
         StarknetBlocksTable::insert(&tx, &STORAGE_BLOCK0, None).unwrap();
 
         // A simple L2 sync task which does the request and checks he result
-        let l2 = |tx: mpsc::Sender<l2::Event>, _, _, _, _| async move {
+        let l2 = |tx: mpsc::Sender<l2::Event>, _, _, _, _, _| async move {
             let (tx1, rx1) = tokio::sync::oneshot::channel();
 
             tx.send(l2::Event::QueryBlock(StarknetBlockNumber::GENESIS, tx1))
@@ -1699,7 +1876,10 @@ mod tests {
             l1_noop,
             l2,
             PendingData::default(),
+            ResponseCache::default(),
             None,
+            l2::BlockValidationMode::AllowMismatch,
+            std::future::pending(),
         ));
     }
 
@@ -1717,12 +1897,13 @@ mod tests {
                 bytecode: zstd_magic.clone(),
                 definition: zstd_magic,
                 hash: ClassHash(*A),
+                casm: None,
             },
         )
         .unwrap();
 
         // A simple L2 sync task which does the request and checks he result
-        let l2 = |tx: mpsc::Sender<l2::Event>, _, _, _, _| async move {
+        let l2 = |tx: mpsc::Sender<l2::Event>, _, _, _, _, _| async move {
             let (tx1, rx1) = tokio::sync::oneshot::channel::<Vec<bool>>();
 
             tx.send(l2::Event::QueryContractExistance(vec![ClassHash(*A)], tx1))
@@ -1746,7 +1927,10 @@ mod tests {
             l1_noop,
             l2,
             PendingData::default(),
+            ResponseCache::default(),
             None,
+            l2::BlockValidationMode::AllowMismatch,
+            std::future::pending(),
         ));
     }
 
@@ -1759,7 +1943,7 @@ mod tests {
         static CNT: AtomicUsize = AtomicUsize::new(0);
 
         // A simple L2 sync task
-        let l2 = move |_, _, _, _, _| async move {
+        let l2 = move |_, _, _, _, _, _| async move {
             CNT.fetch_add(1, Ordering::Relaxed);
             Ok(())
         };
@@ -1774,11 +1958,182 @@ mod tests {
             l1_noop,
             l2,
             PendingData::default(),
+            ResponseCache::default(),
             None,
+            l2::BlockValidationMode::AllowMismatch,
+            std::future::pending(),
         ));
 
         tokio::time::sleep(Duration::from_millis(5)).await;
 
         assert!(CNT.load(Ordering::Relaxed) > 1);
     }
+
+    #[test]
+    fn in_memory_storage_matches_database() {
+        use crate::core::{ContractNonce, ContractStateHash};
+        use crate::starkhash_bytes;
+        use crate::storage::{InMemoryStorage, StarknetBlocksBlockId, StorageBackend};
+
+        let address = ContractAddress::new_or_panic(starkhash_bytes!(b"contract"));
+        let class_hash = ClassHash(starkhash_bytes!(b"class"));
+        let mut state_update = STATE_UPDATE0.clone();
+        state_update
+            .state_diff
+            .deployed_contracts
+            .push(reply::state_update::DeployedContract {
+                address,
+                class_hash,
+            });
+        state_update.state_diff.storage_diffs.insert(
+            address,
+            vec![reply::state_update::StorageDiff {
+                key: StorageAddress::new_or_panic(starkhash_bytes!(b"key")),
+                value: StorageValue(starkhash_bytes!(b"value")),
+            }],
+        );
+        state_update
+            .state_diff
+            .nonces
+            .insert(address, ContractNonce(starkhash_bytes!(b"nonce")));
+
+        // The state root is only known once the update has been applied.
+        let root =
+            super::update_starknet_state(&InMemoryStorage::default(), &state_update).unwrap();
+        let mut block = BLOCK0.clone();
+        block.state_root = root;
+        let l1_update = ethereum::log::StateUpdateLog {
+            global_root: root,
+            ..STATE_UPDATE_LOG0.clone()
+        };
+
+        // Reads back everything the block touched.
+        fn read<S: StorageBackend>(
+            storage: S,
+        ) -> (
+            Option<storage::StarknetBlock>,
+            Option<StarknetBlockNumber>,
+            Option<(crate::core::ContractRoot, ContractNonce)>,
+            Option<ClassHash>,
+        ) {
+            let block = storage.block(StarknetBlocksBlockId::Latest).unwrap();
+            let nodes = storage.global_tree_nodes().unwrap();
+            let tree = state::state_tree::GlobalStateTree::with_storage(
+                nodes,
+                block
+                    .as_ref()
+                    .map(|block| block.root)
+                    .unwrap_or(GlobalRoot(StarkHash::ZERO)),
+            )
+            .unwrap();
+            let address = ContractAddress::new_or_panic(starkhash_bytes!(b"contract"));
+            let state_hash = tree
+                .get(address)
+                .unwrap()
+                .unwrap_or(ContractStateHash(StarkHash::ZERO));
+
+            (
+                block,
+                storage.l1_l2_head().unwrap(),
+                storage.contract_root_and_nonce(state_hash).unwrap(),
+                storage.contract_state_class_hash(state_hash).unwrap(),
+            )
+        }
+
+        let in_memory = InMemoryStorage::default();
+        super::insert_l1_updates(&in_memory, &[l1_update.clone()]).unwrap();
+        super::insert_block(&in_memory, block.clone(), None, state_update.clone()).unwrap();
+
+        let database = Storage::in_memory().unwrap();
+        let mut connection = database.connection().unwrap();
+        let tx = connection.transaction().unwrap();
+        // Unlike in memory, the class must have been downloaded before the contract is deployed.
+        let zstd_magic = vec![0x28, 0xb5, 0x2f, 0xfd];
+        storage::ContractCodeTable::insert_compressed(
+            &tx,
+            &state::CompressedContract {
+                abi: zstd_magic.clone(),
+                bytecode: zstd_magic.clone(),
+                definition: zstd_magic,
+                hash: class_hash,
+                casm: None,
+            },
+        )
+        .unwrap();
+        super::insert_l1_updates(&tx, &[l1_update]).unwrap();
+        super::insert_block(&tx, block, None, state_update).unwrap();
+
+        let expected = read(&tx);
+        assert_eq!(expected.0.as_ref().unwrap().root, root);
+        assert_eq!(expected.1, Some(StarknetBlockNumber::GENESIS));
+        assert_eq!(
+            expected.2.unwrap().1,
+            ContractNonce(starkhash_bytes!(b"nonce"))
+        );
+        assert_eq!(expected.3, Some(class_hash));
+        assert_eq!(read(&in_memory), expected);
+
+        super::reorg_l2_state(&in_memory, StarknetBlockNumber::GENESIS).unwrap();
+        super::reorg_l2_state(&tx, StarknetBlockNumber::GENESIS).unwrap();
+        assert_eq!(read(&in_memory), read(&tx));
+        assert_eq!(read(&tx).0, None);
+    }
+
+    #[test]
+    fn receipt_messages() {
+        use crate::core::{
+            EthereumAddress, L1ToL2MessageNonce, L1ToL2MessagePayloadElem,
+            L2ToL1MessagePayloadElem, MessageHash, StarknetTransactionIndex,
+        };
+        use crate::sequencer::reply::transaction::{L1ToL2Message, L2ToL1Message, Receipt};
+        use crate::storage::MessageDirection;
+
+        let mut receipt = Receipt {
+            actual_fee: None,
+            events: vec![],
+            execution_resources: None,
+            l1_to_l2_consumed_message: Some(L1ToL2Message {
+                from_address: EthereumAddress(web3::types::H160::from_low_u64_be(0x1234)),
+                payload: vec![
+                    L1ToL2MessagePayloadElem(StarkHash::from_u64(1)),
+                    L1ToL2MessagePayloadElem(StarkHash::from_u64(2)),
+                ],
+                selector: EntryPoint(StarkHash::from_u64(0x9abc)),
+                to_address: ContractAddress::new_or_panic(StarkHash::from_u64(0x5678)),
+                nonce: Some(L1ToL2MessageNonce(StarkHash::from_u64(7))),
+            }),
+            l2_to_l1_messages: vec![L2ToL1Message {
+                from_address: ContractAddress::new_or_panic(StarkHash::from_u64(0x5678)),
+                payload: vec![L2ToL1MessagePayloadElem(StarkHash::from_u64(3))],
+                to_address: EthereumAddress(web3::types::H160::from_low_u64_be(0x1234)),
+            }],
+            transaction_hash: StarknetTransactionHash(StarkHash::from_u64(1)),
+            transaction_index: StarknetTransactionIndex::new_or_panic(0),
+        };
+
+        let messages = super::receipt_messages(StarknetBlockNumber::GENESIS, &receipt);
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].direction, MessageDirection::L1ToL2);
+        assert_eq!(
+            messages[0].hash,
+            MessageHash(H256(hex_literal::hex!(
+                "6b0b99392fc1508b6d588a856c726398b333764605e4c9b794d8de60d180c32d"
+            )))
+        );
+        assert_eq!(messages[0].from_address, H256::from_low_u64_be(0x1234));
+        assert_eq!(messages[1].direction, MessageDirection::L2ToL1);
+        assert_eq!(
+            messages[1].hash,
+            MessageHash(H256(hex_literal::hex!(
+                "8bb5e08f4366c68b8f7810221fa90c8956965d29e58c754bc5e44ca5748f6e74"
+            )))
+        );
+        assert_eq!(messages[1].to_address, H256::from_low_u64_be(0x1234));
+
+        // Without a nonce the consumed message's hash is unknown.
+        receipt.l1_to_l2_consumed_message.as_mut().unwrap().nonce = None;
+        let messages = super::receipt_messages(StarknetBlockNumber::GENESIS, &receipt);
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].direction, MessageDirection::L2ToL1);
+    }
 }
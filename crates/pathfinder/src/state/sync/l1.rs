@@ -9,9 +9,11 @@ use crate::{
     ethereum::{
         log::{FetchError, StateUpdateLog},
         state_update::state_root::StateRootFetcher,
-        transport::EthereumTransport,
+        transport::{BlockTag, EthereumTransport},
+        Confirmation,
     },
     retry::Retry,
+    storage::{RefsTable, Storage},
 };
 
 /// Events and queries emitted by L1 sync process.
@@ -32,17 +34,23 @@ pub enum Event {
 
 /// Syncs L1 state update logs. Emits [sync events](Event) which should be handled
 /// to update storage and respond to queries.
+///
+/// Only logs which have reached the `confirmation` are emitted.
 pub async fn sync<T>(
     tx_event: mpsc::Sender<Event>,
     transport: T,
     chain: Chain,
     head: Option<StateUpdateLog>,
+    confirmation: Confirmation,
 ) -> anyhow::Result<()>
 where
     T: EthereumTransport + Send + Sync + Clone,
 {
+    let mut logs = StateRootFetcher::new(head, chain);
+    logs.set_confirmation(confirmation);
+
     let eth_api = EthereumImpl {
-        logs: Arc::new(RwLock::new(StateRootFetcher::new(head, chain))),
+        logs: Arc::new(RwLock::new(logs)),
         transport,
     };
 
@@ -261,6 +269,94 @@ async fn sync_impl(
     }
 }
 
+/// Keeps the latest finalized and safe L1 blocks in the [RefsTable] up to date, which are used to
+/// tell apart finalized and safe L1 state updates from those which could still be reorged.
+///
+/// An L1 block is safe if it is at or below the `safe` block, or at least the `confirmation` depth
+/// below the L1 head. Without support for the `finalized` tag no L1 block is ever considered
+/// finalized, as a depth can not guarantee it. Responses referring to L1 state updates are then
+/// never cached, see [is_final](crate::rpc::v02::common::is_final).
+///
+/// Returns if the L1 endpoint supports neither tag and there is no `confirmation` depth, as
+/// nothing is safe then.
+pub async fn track_finality<T>(
+    storage: Storage,
+    transport: T,
+    chain: Chain,
+    confirmation: Confirmation,
+) -> anyhow::Result<()>
+where
+    T: EthereumTransport + Send + Sync,
+{
+    let poll_interval = crate::state::sync::head_poll_interval(chain);
+    let mut db_conn = storage
+        .connection()
+        .context("Creating database connection")?;
+
+    let depth = match confirmation {
+        Confirmation::Depth(depth) => NonZeroU64::new(depth),
+        Confirmation::Safe | Confirmation::Finalized => None,
+    };
+
+    let mut stored = (None, None);
+    loop {
+        let blocks = async {
+            let finalized = transport
+                .tagged_block_number(BlockTag::Finalized)
+                .await
+                .context("Get finalized block number from L1")?;
+            let safe = transport
+                .tagged_block_number(BlockTag::Safe)
+                .await
+                .context("Get safe block number from L1")?;
+            let confirmed = match depth {
+                Some(depth) => {
+                    let head = transport
+                        .block_number()
+                        .await
+                        .context("Get latest block number from L1")?;
+                    Some(head.saturating_sub(depth.get()))
+                }
+                None => None,
+            };
+
+            // A finalized block is also safe.
+            Ok::<_, anyhow::Error>((finalized, safe.max(confirmed).max(finalized)))
+        };
+
+        match blocks.await {
+            Ok((None, None)) => {
+                tracing::info!(
+                    "L1 endpoint supports neither the finalized nor the safe tag, finality is not tracked"
+                );
+                return Ok(());
+            }
+            Ok(blocks) if blocks != stored => {
+                let (finalized, safe) = blocks;
+                tokio::task::block_in_place(|| -> anyhow::Result<()> {
+                    let tx = db_conn
+                        .transaction()
+                        .context("Create database transaction")?;
+                    if let Some(finalized) = finalized {
+                        RefsTable::set_l1_finalized_block(&tx, EthereumBlockNumber(finalized))
+                            .context("Update finalized L1 block")?;
+                    }
+                    if let Some(safe) = safe {
+                        RefsTable::set_l1_safe_block(&tx, EthereumBlockNumber(safe))
+                            .context("Update safe L1 block")?;
+                    }
+                    tx.commit().context("Commit database transaction")
+                })?;
+                stored = blocks;
+            }
+            Ok(_) => {}
+            Err(error) => tracing::warn!(reason=?error, "Failed fetching L1 finality"),
+        }
+
+        tokio::time::sleep(poll_interval).await;
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -679,4 +775,98 @@ mod tests {
             }
         }
     }
+
+    mod track_finality {
+        use super::*;
+        use crate::ethereum::transport::LogsError;
+        use web3::types::{Block, BlockId, Filter, Log, Transaction, TransactionId, H256, U256};
+
+        /// An L1 endpoint which supports neither the `safe` nor the `finalized` tag.
+        #[derive(Debug, Clone)]
+        struct UntaggedTransport {
+            head: u64,
+        }
+
+        #[async_trait::async_trait]
+        impl EthereumTransport for UntaggedTransport {
+            async fn block(&self, _: BlockId) -> web3::Result<Option<Block<H256>>> {
+                unimplemented!()
+            }
+
+            async fn block_number(&self) -> web3::Result<u64> {
+                Ok(self.head)
+            }
+
+            async fn chain(&self) -> anyhow::Result<crate::core::EthereumChain> {
+                unimplemented!()
+            }
+
+            async fn logs(&self, _: Filter) -> std::result::Result<Vec<Log>, LogsError> {
+                unimplemented!()
+            }
+
+            async fn transaction(&self, _: TransactionId) -> web3::Result<Option<Transaction>> {
+                unimplemented!()
+            }
+
+            async fn gas_price(&self) -> web3::Result<U256> {
+                unimplemented!()
+            }
+
+            async fn tagged_block_number(&self, _: BlockTag) -> web3::Result<Option<u64>> {
+                Ok(None)
+            }
+        }
+
+        #[tokio::test(flavor = "multi_thread")]
+        async fn untagged_endpoint_tracks_depth_only() {
+            let storage = Storage::in_memory().unwrap();
+            let transport = UntaggedTransport { head: 100 };
+
+            let tracking = tokio::spawn(track_finality(
+                storage.clone(),
+                transport,
+                Chain::Testnet,
+                Confirmation::Depth(10),
+            ));
+
+            let mut connection = storage.connection().unwrap();
+            let (safe, finalized) = loop {
+                let tx = connection.transaction().unwrap();
+                let safe = RefsTable::get_l1_safe_block(&tx).unwrap();
+                let finalized = RefsTable::get_l1_finalized_block(&tx).unwrap();
+                drop(tx);
+
+                if safe.is_some() {
+                    break (safe, finalized);
+                }
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            };
+            tracking.abort();
+
+            assert_eq!(safe, Some(EthereumBlockNumber(90)));
+            // Nothing is final without the tag, so responses are never cached.
+            assert_eq!(finalized, None);
+        }
+
+        #[tokio::test(flavor = "multi_thread")]
+        async fn untagged_endpoint_without_depth_stops() {
+            let storage = Storage::in_memory().unwrap();
+            let transport = UntaggedTransport { head: 100 };
+
+            track_finality(
+                storage.clone(),
+                transport,
+                Chain::Testnet,
+                Confirmation::Depth(0),
+            )
+            .await
+            .unwrap();
+
+            let mut connection = storage.connection().unwrap();
+            let tx = connection.transaction().unwrap();
+            assert_eq!(RefsTable::get_l1_safe_block(&tx).unwrap(), None);
+            assert_eq!(RefsTable::get_l1_finalized_block(&tx).unwrap(), None);
+        }
+    }
 }
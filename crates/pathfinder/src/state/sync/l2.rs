@@ -1,5 +1,8 @@
 use std::time::Duration;
-use std::{collections::HashSet, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
 
 use anyhow::{anyhow, Context};
 use tokio::sync::{mpsc, oneshot};
@@ -9,11 +12,16 @@ use crate::sequencer;
 use crate::sequencer::error::SequencerError;
 use crate::sequencer::reply::state_update::{DeployedContract, StateDiff};
 use crate::sequencer::reply::{Block, Status};
-use crate::state::block_hash::verify_block_hash;
-use crate::state::class_hash::extract_abi_code_hash;
-use crate::state::CompressedContract;
+use crate::state::block_hash::{verify_block_hash, VerifyResult};
+use crate::state::class_hash::{
+    compute_casm_class_hash, extract_abi_code_hash, extract_class_abi_code_hash, ClassFormat,
+};
+use crate::state::{CompressedCasmClass, CompressedContract};
 use crate::{
-    core::{Chain, ClassHash, StarknetBlockHash, StarknetBlockNumber},
+    core::{
+        CasmHash, Chain, ClassHash, EventCommitment, StarknetBlockHash, StarknetBlockNumber,
+        TransactionCommitment,
+    },
     sequencer::reply::{PendingBlock, StateUpdate},
 };
 
@@ -25,11 +33,25 @@ pub struct Timings {
     pub class_declaration: Duration,
 }
 
+/// How to handle blocks which fail block hash verification.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockValidationMode {
+    /// A block hash or commitment mismatch is fatal.
+    Strict,
+    /// A block hash or commitment mismatch is logged as a warning and the block is accepted.
+    AllowMismatch,
+}
+
 /// Events and queries emitted by L2 sync process.
 #[derive(Debug)]
 pub enum Event {
-    /// New L2 [block update](StateUpdate) found.
-    Update(Box<Block>, Box<StateUpdate>, Timings),
+    /// New L2 [block update](StateUpdate) found, along with the block's commitments
+    /// if they could be computed for a block whose hash did not mismatch.
+    Update(
+        (Box<Block>, Option<(TransactionCommitment, EventCommitment)>),
+        Box<StateUpdate>,
+        Timings,
+    ),
     /// An L2 reorg was detected, contains the reorg-tail which
     /// indicates the oldest block which is now invalid
     /// i.e. reorg-tail + 1 should be the new head.
@@ -58,6 +80,7 @@ pub async fn sync(
     mut head: Option<(StarknetBlockNumber, StarknetBlockHash, GlobalRoot)>,
     chain: Chain,
     pending_poll_interval: Option<Duration>,
+    block_validation_mode: BlockValidationMode,
 ) -> anyhow::Result<()> {
     use crate::state::sync::head_poll_interval;
 
@@ -69,9 +92,17 @@ pub async fn sync(
         };
         let t_block = std::time::Instant::now();
 
-        let block = loop {
-            match download_block(next, chain, head_meta.map(|h| h.1), &sequencer).await? {
-                DownloadBlock::Block(block) => break block,
+        let (block, commitments) = loop {
+            match download_block(
+                next,
+                chain,
+                head_meta.map(|h| h.1),
+                &sequencer,
+                block_validation_mode,
+            )
+            .await?
+            {
+                DownloadBlock::Block(block, commitments) => break (block, commitments),
                 DownloadBlock::AtHead => {
                     // Poll pending if it is enabled, otherwise just wait to poll head again.
                     match pending_poll_interval {
@@ -97,9 +128,15 @@ pub async fn sync(
                 }
                 DownloadBlock::Reorg => {
                     let some_head = head.unwrap();
-                    head = reorg(some_head, chain, &tx_event, &sequencer)
-                        .await
-                        .context("L2 reorg")?;
+                    head = reorg(
+                        some_head,
+                        chain,
+                        &tx_event,
+                        &sequencer,
+                        block_validation_mode,
+                    )
+                    .await
+                    .context("L2 reorg")?;
 
                     continue 'outer;
                 }
@@ -109,9 +146,15 @@ pub async fn sync(
 
         if let Some(some_head) = head {
             if some_head.1 != block.parent_block_hash {
-                head = reorg(some_head, chain, &tx_event, &sequencer)
-                    .await
-                    .context("L2 reorg")?;
+                head = reorg(
+                    some_head,
+                    chain,
+                    &tx_event,
+                    &sequencer,
+                    block_validation_mode,
+                )
+                .await
+                .context("L2 reorg")?;
 
                 continue 'outer;
             }
@@ -158,7 +201,11 @@ pub async fn sync(
         };
 
         tx_event
-            .send(Event::Update(block, Box::new(state_update), timings))
+            .send(Event::Update(
+                (block, commitments),
+                Box::new(state_update),
+                timings,
+            ))
             .await
             .context("Event channel closed")?;
     }
@@ -173,20 +220,20 @@ async fn declare_classes(
     sequencer: &impl sequencer::ClientApi,
     tx_event: &mpsc::Sender<Event>,
 ) -> Result<(), anyhow::Error> {
-    let declared_classes = block
+    // Sierra class declarations also commit to the hash of the compiled class.
+    let compiled_class_hashes = block
         .transactions
         .iter()
         .filter_map(|tx| {
             use crate::sequencer::reply::transaction::Transaction::*;
             match tx {
-                Declare(tx) => Some(tx.class_hash),
+                Declare(tx) => Some((tx.class_hash, tx.compiled_class_hash)),
                 Deploy(_) | DeployAccount(_) | Invoke(_) | L1Handler(_) => None,
             }
         })
-        // Get unique class hashes only. Its unlikely they would have dupes here, but rather safe than sorry.
-        .collect::<HashSet<_>>()
-        .into_iter()
-        .collect::<Vec<_>>();
+        .collect::<HashMap<ClassHash, Option<CasmHash>>>();
+    // Get unique class hashes only. Its unlikely they would have dupes here, but rather safe than sorry.
+    let declared_classes = compiled_class_hashes.keys().copied().collect::<Vec<_>>();
 
     if declared_classes.is_empty() {
         return Ok(());
@@ -221,6 +268,10 @@ async fn declare_classes(
             .await
             .with_context(|| format!("Downloading class {}", class_hash.0))?;
 
+        if let Some(Some(expected)) = compiled_class_hashes.get(&class_hash) {
+            verify_compiled_class_hash(&class, *expected)?;
+        }
+
         tx_event
             .send(Event::NewContract(class))
             .await
@@ -235,8 +286,24 @@ async fn declare_classes(
     Ok(())
 }
 
+/// Checks that a downloaded Sierra class was compiled to the CASM it was declared with.
+pub(super) fn verify_compiled_class_hash(
+    class: &CompressedContract,
+    expected: CasmHash,
+) -> anyhow::Result<()> {
+    let casm_hash = class.casm.as_ref().map(|casm| casm.hash);
+    anyhow::ensure!(
+        casm_hash == Some(expected),
+        "Compiled class hash mismatch for class {}, {:?} instead of {}",
+        class.hash.0,
+        casm_hash.map(|hash| hash.0),
+        expected.0
+    );
+    Ok(())
+}
+
 enum DownloadBlock {
-    Block(Box<Block>),
+    Block(Box<Block>, Option<(TransactionCommitment, EventCommitment)>),
     AtHead,
     Reorg,
 }
@@ -246,6 +313,7 @@ async fn download_block(
     chain: Chain,
     prev_block_hash: Option<StarknetBlockHash>,
     sequencer: &impl sequencer::ClientApi,
+    mode: BlockValidationMode,
 ) -> anyhow::Result<DownloadBlock> {
     use crate::core::BlockId;
     use sequencer::error::StarknetErrorCode::BlockNotFound;
@@ -264,16 +332,29 @@ async fn download_block(
                     .with_context(move || format!("Verify block {}", block_number))?;
                 Ok((block, verify_result))
             });
-            #[allow(unused_variables)]
             let (block, verify_result) = verify_hash.await.context("Verify block hash")??;
-            // FIXME: test block hashes aren't correct so this error breaks tests.
-            #[cfg(not(test))]
-            anyhow::ensure!(
-                verify_result != crate::state::block_hash::VerifyResult::Mismatch,
-                "Block hash mismatch"
-            );
+            let commitments = match (verify_result, mode) {
+                (VerifyResult::Match(commitments), _) => Some(commitments),
+                (VerifyResult::NotVerifiable(commitments), _) => {
+                    tracing::debug!(number=%block.block_number, "Block hash or event commitment cannot be verified");
+                    Some(commitments)
+                }
+                (VerifyResult::Mismatch, BlockValidationMode::Strict) => {
+                    anyhow::bail!("Block hash mismatch for block {}", block.block_number)
+                }
+                (VerifyResult::Mismatch, BlockValidationMode::AllowMismatch) => {
+                    tracing::warn!(
+                        number=%block.block_number,
+                        hash=%block.block_hash.0,
+                        "Block hash mismatch"
+                    );
+                    None
+                }
+            };
             match block.status {
-                Status::AcceptedOnL1 | Status::AcceptedOnL2 => Ok(DownloadBlock::Block(block)),
+                Status::AcceptedOnL1 | Status::AcceptedOnL2 => {
+                    Ok(DownloadBlock::Block(block, commitments))
+                }
                 _ => Err(anyhow!(
                     "Rejecting block as its status is {}, and only accepted blocks are allowed",
                     block.status
@@ -319,6 +400,7 @@ async fn reorg(
     chain: Chain,
     tx_event: &mpsc::Sender<Event>,
     sequencer: &impl sequencer::ClientApi,
+    mode: BlockValidationMode,
 ) -> anyhow::Result<Option<(StarknetBlockNumber, StarknetBlockHash, GlobalRoot)>> {
     // Go back in history until we find an L2 block that does still exist.
     // We already know the current head is invalid.
@@ -342,11 +424,17 @@ async fn reorg(
             None => break None,
         };
 
-        match download_block(previous_block_number, chain, Some(previous.0), sequencer)
-            .await
-            .with_context(|| format!("Download block {} from sequencer", previous_block_number))?
+        match download_block(
+            previous_block_number,
+            chain,
+            Some(previous.0),
+            sequencer,
+            mode,
+        )
+        .await
+        .with_context(|| format!("Download block {} from sequencer", previous_block_number))?
         {
-            DownloadBlock::Block(block) if block.block_hash == previous.0 => {
+            DownloadBlock::Block(block, _) if block.block_hash == previous.0 => {
                 break Some((previous_block_number, previous.0, previous.1));
             }
             _ => {}
@@ -429,10 +517,12 @@ async fn deploy_contracts(
     Ok(())
 }
 
-/// A copy of [download_and_compress_contract] that uses the new `class_by_hash` API.
+/// Downloads a class definition using the `class_by_hash` API, verifies its class hash and
+/// compresses it.
 ///
-/// These should eventually be deduplicated, but right now we are just aiming at functional.
-async fn download_and_compress_class(
+/// Sierra classes are downloaded along with their compiled CASM definition. Verifying the
+/// compiled class hash against the declaration is left to the caller.
+pub(super) async fn download_and_compress_class(
     class_hash: ClassHash,
     sequencer: &impl sequencer::ClientApi,
 ) -> anyhow::Result<CompressedContract> {
@@ -444,10 +534,10 @@ async fn download_and_compress_class(
     // Parse the contract definition for ABI, code and calculate the class hash. This can
     // be expensive, so perform in a blocking task.
     let extract = tokio::task::spawn_blocking(move || -> anyhow::Result<_> {
-        let (abi, bytecode, hash) = extract_abi_code_hash(&definition)?;
-        Ok((definition, abi, bytecode, hash))
+        let (format, abi, bytecode, hash) = extract_class_abi_code_hash(&definition)?;
+        Ok((format, definition, abi, bytecode, hash))
     });
-    let (definition, abi, bytecode, hash) = extract
+    let (format, definition, abi, bytecode, hash) = extract
         .await
         .context("Parse class definition and compute hash")??;
 
@@ -459,6 +549,24 @@ async fn download_and_compress_class(
         class_hash.0
     );
 
+    let casm = match format {
+        ClassFormat::Cairo => None,
+        ClassFormat::Sierra => {
+            let casm = sequencer
+                .compiled_class_by_hash(class_hash)
+                .await
+                .context("Downloading compiled class from sequencer")?;
+
+            let compute = tokio::task::spawn_blocking(move || -> anyhow::Result<_> {
+                let hash = compute_casm_class_hash(&casm)?;
+                Ok((casm, hash))
+            });
+            let casm = compute.await.context("Compute compiled class hash")??;
+
+            Some(casm)
+        }
+    };
+
     let compress = tokio::task::spawn_blocking(move || -> anyhow::Result<_> {
         let mut compressor = zstd::bulk::Compressor::new(10).context("Create zstd compressor")?;
 
@@ -469,16 +577,25 @@ async fn download_and_compress_class(
         let definition = compressor
             .compress(&definition)
             .context("Compress definition")?;
+        let casm = casm
+            .map(|(definition, hash)| -> anyhow::Result<_> {
+                let definition = compressor
+                    .compress(&definition)
+                    .context("Compress compiled class definition")?;
+                Ok(CompressedCasmClass { definition, hash })
+            })
+            .transpose()?;
 
-        Ok((abi, bytecode, definition))
+        Ok((abi, bytecode, definition, casm))
     });
-    let (abi, bytecode, definition) = compress.await.context("Compress contract")??;
+    let (abi, bytecode, definition, casm) = compress.await.context("Compress contract")??;
 
     Ok(CompressedContract {
         abi,
         bytecode,
         definition,
         hash,
+        casm,
     })
 }
 
@@ -528,13 +645,14 @@ async fn download_and_compress_contract(
         bytecode,
         definition,
         hash,
+        casm: None,
     })
 }
 
 #[cfg(test)]
 mod tests {
     mod sync {
-        use super::super::{sync, Event};
+        use super::super::{sync, BlockValidationMode, Event};
         use crate::{
             core::{
                 BlockId, ClassHash, ContractAddress, GasPrice, GlobalRoot, SequencerAddress,
@@ -635,6 +753,8 @@ mod tests {
                 transaction_receipts: vec![],
                 transactions: vec![],
                 starknet_version: None,
+                transaction_commitment: None,
+                event_commitment: None,
             };
             static ref BLOCK0_V2: reply::Block = reply::Block {
                 block_hash: *BLOCK0_HASH_V2,
@@ -648,6 +768,8 @@ mod tests {
                 transaction_receipts: vec![],
                 transactions: vec![],
                 starknet_version: Some("0.9.1".into()),
+                transaction_commitment: None,
+                event_commitment: None,
             };
             static ref BLOCK1: reply::Block = reply::Block {
                 block_hash: *BLOCK1_HASH,
@@ -661,6 +783,8 @@ mod tests {
                 transaction_receipts: vec![],
                 transactions: vec![],
                 starknet_version: Some("0.9.1".into()),
+                transaction_commitment: None,
+                event_commitment: None,
             };
             static ref BLOCK2: reply::Block = reply::Block {
                 block_hash: *BLOCK2_HASH,
@@ -674,6 +798,8 @@ mod tests {
                 transaction_receipts: vec![],
                 transactions: vec![],
                 starknet_version: Some("0.9.2".into()),
+                transaction_commitment: None,
+                event_commitment: None,
             };
 
             static ref STATE_UPDATE0: reply::StateUpdate = reply::StateUpdate {
@@ -693,6 +819,7 @@ mod tests {
                         }],
                     )]),
                     declared_contracts: Vec::new(),
+                    declared_classes: Vec::new(),
                     nonces: std::collections::HashMap::new(),
                 },
             };
@@ -707,6 +834,7 @@ mod tests {
                     }],
                     storage_diffs: HashMap::new(),
                     declared_contracts: Vec::new(),
+                    declared_classes: Vec::new(),
                     nonces: std::collections::HashMap::new(),
                 },
             };
@@ -736,6 +864,7 @@ mod tests {
                         ),
                     ]),
                     declared_contracts: Vec::new(),
+                    declared_classes: Vec::new(),
                     nonces: std::collections::HashMap::new(),
                 },
             };
@@ -747,6 +876,7 @@ mod tests {
                     deployed_contracts: vec![],
                     storage_diffs: HashMap::new(),
                     declared_contracts: Vec::new(),
+                    declared_classes: Vec::new(),
                     nonces: std::collections::HashMap::new(),
                 },
             };
@@ -758,6 +888,7 @@ mod tests {
                     deployed_contracts: vec![],
                     storage_diffs: HashMap::new(),
                     declared_contracts: Vec::new(),
+                    declared_classes: Vec::new(),
                     nonces: std::collections::HashMap::new(),
                 },
             };
@@ -769,6 +900,7 @@ mod tests {
                     deployed_contracts: vec![],
                     storage_diffs: HashMap::new(),
                     declared_contracts: Vec::new(),
+                    declared_classes: Vec::new(),
                     nonces: std::collections::HashMap::new(),
                 },
             };
@@ -780,6 +912,7 @@ mod tests {
                     deployed_contracts: vec![],
                     storage_diffs: HashMap::new(),
                     declared_contracts: Vec::new(),
+                    declared_classes: Vec::new(),
                     nonces: std::collections::HashMap::new(),
                 },
             };
@@ -903,7 +1036,14 @@ mod tests {
                 );
 
                 // Let's run the UUT
-                let _jh = tokio::spawn(sync(tx_event, mock, None, Chain::Testnet, None));
+                let _jh = tokio::spawn(sync(
+                    tx_event,
+                    mock,
+                    None,
+                    Chain::Testnet,
+                    None,
+                    BlockValidationMode::AllowMismatch,
+                ));
 
                 let zstd_magic = vec![0x28, 0xb5, 0x2f, 0xfd];
 
@@ -919,7 +1059,7 @@ mod tests {
                         assert_eq!(compressed_contract.definition[..4], zstd_magic);
                         assert_eq!(compressed_contract.hash, *CONTRACT0_HASH);
                 });
-                assert_matches!(rx_event.recv().await.unwrap(), Event::Update(block, state_update, _) => {
+                assert_matches!(rx_event.recv().await.unwrap(), Event::Update((block, _), state_update, _) => {
                     assert_eq!(*block, *BLOCK0);
                     assert_eq!(*state_update, *STATE_UPDATE0);
                 });
@@ -935,7 +1075,7 @@ mod tests {
                         assert_eq!(compressed_contract.definition[..4], zstd_magic);
                         assert_eq!(compressed_contract.hash, *CONTRACT1_HASH);
                 });
-                assert_matches!(rx_event.recv().await.unwrap(), Event::Update(block, state_update, _) => {
+                assert_matches!(rx_event.recv().await.unwrap(), Event::Update((block, _), state_update, _) => {
                     assert_eq!(*block, *BLOCK1);
                     assert_eq!(*state_update, *STATE_UPDATE1);
                 });
@@ -988,6 +1128,7 @@ mod tests {
                     Some((BLOCK0_NUMBER, *BLOCK0_HASH, *GLOBAL_ROOT0)),
                     Chain::Testnet,
                     None,
+                    BlockValidationMode::AllowMismatch,
                 ));
 
                 let zstd_magic = vec![0x28, 0xb5, 0x2f, 0xfd];
@@ -1004,7 +1145,7 @@ mod tests {
                         assert_eq!(compressed_contract.definition[..4], zstd_magic);
                         assert_eq!(compressed_contract.hash, *CONTRACT1_HASH);
                 });
-                assert_matches!(rx_event.recv().await.unwrap(), Event::Update(block, state_update, _) => {
+                assert_matches!(rx_event.recv().await.unwrap(), Event::Update((block, _), state_update, _) => {
                     assert_eq!(*block, *BLOCK1);
                     assert_eq!(*state_update, *STATE_UPDATE1);
                 });
@@ -1027,7 +1168,14 @@ mod tests {
                 block.status = Status::Reverted;
                 expect_block(&mut mock, &mut seq, BLOCK0_NUMBER.into(), Ok(block.into()));
 
-                let jh = tokio::spawn(sync(tx_event, mock, None, Chain::Testnet, None));
+                let jh = tokio::spawn(sync(
+                    tx_event,
+                    mock,
+                    None,
+                    Chain::Testnet,
+                    None,
+                    BlockValidationMode::AllowMismatch,
+                ));
                 let error = jh.await.unwrap().unwrap_err();
                 assert_eq!(
                     &error.to_string(),
@@ -1130,7 +1278,14 @@ mod tests {
                 );
 
                 // Let's run the UUT
-                let _jh = tokio::spawn(sync(tx_event, mock, None, Chain::Testnet, None));
+                let _jh = tokio::spawn(sync(
+                    tx_event,
+                    mock,
+                    None,
+                    Chain::Testnet,
+                    None,
+                    BlockValidationMode::AllowMismatch,
+                ));
 
                 let zstd_magic = vec![0x28, 0xb5, 0x2f, 0xfd];
 
@@ -1146,7 +1301,7 @@ mod tests {
                         assert_eq!(compressed_contract.definition[..4], zstd_magic);
                         assert_eq!(compressed_contract.hash, *CONTRACT0_HASH);
                 });
-                assert_matches!(rx_event.recv().await.unwrap(), Event::Update(block, state_update, _) => {
+                assert_matches!(rx_event.recv().await.unwrap(), Event::Update((block, _), state_update, _) => {
                     assert_eq!(*block, *BLOCK0);
                     assert_eq!(*state_update, *STATE_UPDATE0);
                 });
@@ -1166,7 +1321,7 @@ mod tests {
                         assert_eq!(compressed_contract.definition[..4], zstd_magic);
                         assert_eq!(compressed_contract.hash, *CONTRACT0_HASH_V2);
                 });
-                assert_matches!(rx_event.recv().await.unwrap(), Event::Update(block, state_update, _) => {
+                assert_matches!(rx_event.recv().await.unwrap(), Event::Update((block, _), state_update, _) => {
                     assert_eq!(*block, *BLOCK0_V2);
                     assert_eq!(*state_update, *STATE_UPDATE0_V2);
                 });
@@ -1200,6 +1355,8 @@ mod tests {
                     transaction_receipts: vec![],
                     transactions: vec![],
                     starknet_version: None,
+                    transaction_commitment: None,
+                    event_commitment: None,
                 };
 
                 // Fetch the genesis block with respective state update and contracts
@@ -1335,7 +1492,14 @@ mod tests {
                 );
 
                 // Run the UUT
-                let _jh = tokio::spawn(sync(tx_event, mock, None, Chain::Testnet, None));
+                let _jh = tokio::spawn(sync(
+                    tx_event,
+                    mock,
+                    None,
+                    Chain::Testnet,
+                    None,
+                    BlockValidationMode::AllowMismatch,
+                ));
 
                 let zstd_magic = vec![0x28, 0xb5, 0x2f, 0xfd];
 
@@ -1351,7 +1515,7 @@ mod tests {
                         assert_eq!(compressed_contract.definition[..4], zstd_magic);
                         assert_eq!(compressed_contract.hash, *CONTRACT0_HASH);
                 });
-                assert_matches!(rx_event.recv().await.unwrap(), Event::Update(block, state_update, _) => {
+                assert_matches!(rx_event.recv().await.unwrap(), Event::Update((block, _), state_update, _) => {
                     assert_eq!(*block, *BLOCK0);
                     assert_eq!(*state_update, *STATE_UPDATE0);
                 });
@@ -1367,11 +1531,11 @@ mod tests {
                         assert_eq!(compressed_contract.definition[..4], zstd_magic);
                         assert_eq!(compressed_contract.hash, *CONTRACT1_HASH);
                 });
-                assert_matches!(rx_event.recv().await.unwrap(), Event::Update(block, state_update, _) => {
+                assert_matches!(rx_event.recv().await.unwrap(), Event::Update((block, _), state_update, _) => {
                     assert_eq!(*block, *BLOCK1);
                     assert_eq!(*state_update, *STATE_UPDATE1);
                 });
-                assert_matches!(rx_event.recv().await.unwrap(), Event::Update(block, state_update, _) => {
+                assert_matches!(rx_event.recv().await.unwrap(), Event::Update((block, _), state_update, _) => {
                     assert_eq!(*block, *BLOCK2);
                     assert_eq!(*state_update, *STATE_UPDATE2);
                 });
@@ -1399,11 +1563,11 @@ mod tests {
                         assert_eq!(compressed_contract.definition[..4], zstd_magic);
                         assert_eq!(compressed_contract.hash, *CONTRACT0_HASH_V2);
                 });
-                assert_matches!(rx_event.recv().await.unwrap(), Event::Update(block, state_update, _) => {
+                assert_matches!(rx_event.recv().await.unwrap(), Event::Update((block, _), state_update, _) => {
                     assert_eq!(*block, *BLOCK0_V2);
                     assert_eq!(*state_update, *STATE_UPDATE0_V2);
                 });
-                assert_matches!(rx_event.recv().await.unwrap(), Event::Update(block, state_update, _) => {
+                assert_matches!(rx_event.recv().await.unwrap(), Event::Update((block, _), state_update, _) => {
                     assert_eq!(*block, block1_v2);
                     assert!(state_update.state_diff.deployed_contracts.is_empty());
                     assert!(state_update.state_diff.storage_diffs.is_empty());
@@ -1439,6 +1603,8 @@ mod tests {
                     transaction_receipts: vec![],
                     transactions: vec![],
                     starknet_version: None,
+                    transaction_commitment: None,
+                    event_commitment: None,
                 };
                 let block2_v2 = reply::Block {
                     block_hash: *BLOCK2_HASH_V2,
@@ -1454,6 +1620,8 @@ mod tests {
                     transaction_receipts: vec![],
                     transactions: vec![],
                     starknet_version: None,
+                    transaction_commitment: None,
+                    event_commitment: None,
                 };
                 let block3 = reply::Block {
                     block_hash: *BLOCK3_HASH,
@@ -1469,6 +1637,8 @@ mod tests {
                     transaction_receipts: vec![],
                     transactions: vec![],
                     starknet_version: None,
+                    transaction_commitment: None,
+                    event_commitment: None,
                 };
 
                 // Fetch the genesis block with respective state update and contracts
@@ -1615,7 +1785,14 @@ mod tests {
                 );
 
                 // Run the UUT
-                let _jh = tokio::spawn(sync(tx_event, mock, None, Chain::Testnet, None));
+                let _jh = tokio::spawn(sync(
+                    tx_event,
+                    mock,
+                    None,
+                    Chain::Testnet,
+                    None,
+                    BlockValidationMode::AllowMismatch,
+                ));
 
                 let zstd_magic = vec![0x28, 0xb5, 0x2f, 0xfd];
 
@@ -1631,7 +1808,7 @@ mod tests {
                         assert_eq!(compressed_contract.definition[..4], zstd_magic);
                         assert_eq!(compressed_contract.hash, *CONTRACT0_HASH);
                 });
-                assert_matches!(rx_event.recv().await.unwrap(), Event::Update(block, state_update, _) => {
+                assert_matches!(rx_event.recv().await.unwrap(), Event::Update((block, _), state_update, _) => {
                     assert_eq!(*block, *BLOCK0);
                     assert_eq!(*state_update, *STATE_UPDATE0);
                 });
@@ -1647,15 +1824,15 @@ mod tests {
                         assert_eq!(compressed_contract.definition[..4], zstd_magic);
                         assert_eq!(compressed_contract.hash, *CONTRACT1_HASH);
                 });
-                assert_matches!(rx_event.recv().await.unwrap(), Event::Update(block, state_update, _) => {
+                assert_matches!(rx_event.recv().await.unwrap(), Event::Update((block, _), state_update, _) => {
                     assert_eq!(*block, *BLOCK1);
                     assert_eq!(*state_update, *STATE_UPDATE1);
                 });
-                assert_matches!(rx_event.recv().await.unwrap(), Event::Update(block, state_update, _) => {
+                assert_matches!(rx_event.recv().await.unwrap(), Event::Update((block, _), state_update, _) => {
                     assert_eq!(*block, *BLOCK2);
                     assert_eq!(*state_update, *STATE_UPDATE2);
                 });
-                assert_matches!(rx_event.recv().await.unwrap(), Event::Update(block, state_update, _) => {
+                assert_matches!(rx_event.recv().await.unwrap(), Event::Update((block, _), state_update, _) => {
                     assert_eq!(*block, block3);
                     assert_eq!(*state_update, *STATE_UPDATE3);
                 });
@@ -1675,11 +1852,11 @@ mod tests {
                 assert_matches!(rx_event.recv().await.unwrap(), Event::Reorg(tail) => {
                     assert_eq!(tail, BLOCK1_NUMBER);
                 });
-                assert_matches!(rx_event.recv().await.unwrap(), Event::Update(block, state_update, _) => {
+                assert_matches!(rx_event.recv().await.unwrap(), Event::Update((block, _), state_update, _) => {
                     assert_eq!(*block, block1_v2);
                     assert_eq!(*state_update, *STATE_UPDATE1_V2);
                 });
-                assert_matches!(rx_event.recv().await.unwrap(), Event::Update(block, state_update, _) => {
+                assert_matches!(rx_event.recv().await.unwrap(), Event::Update((block, _), state_update, _) => {
                     assert_eq!(*block, block2_v2);
                     assert_eq!(*state_update, *STATE_UPDATE2_V2);
                 });
@@ -1714,6 +1891,8 @@ mod tests {
                     transaction_receipts: vec![],
                     transactions: vec![],
                     starknet_version: None,
+                    transaction_commitment: None,
+                    event_commitment: None,
                 };
 
                 // Fetch the genesis block with respective state update and contracts
@@ -1822,7 +2001,14 @@ mod tests {
                 );
 
                 // Run the UUT
-                let _jh = tokio::spawn(sync(tx_event, mock, None, Chain::Testnet, None));
+                let _jh = tokio::spawn(sync(
+                    tx_event,
+                    mock,
+                    None,
+                    Chain::Testnet,
+                    None,
+                    BlockValidationMode::AllowMismatch,
+                ));
 
                 let zstd_magic = vec![0x28, 0xb5, 0x2f, 0xfd];
 
@@ -1838,7 +2024,7 @@ mod tests {
                         assert_eq!(compressed_contract.definition[..4], zstd_magic);
                         assert_eq!(compressed_contract.hash, *CONTRACT0_HASH);
                 });
-                assert_matches!(rx_event.recv().await.unwrap(), Event::Update(block, state_update, _) => {
+                assert_matches!(rx_event.recv().await.unwrap(), Event::Update((block, _), state_update, _) => {
                     assert_eq!(*block, *BLOCK0);
                     assert_eq!(*state_update, *STATE_UPDATE0);
                 });
@@ -1854,11 +2040,11 @@ mod tests {
                         assert_eq!(compressed_contract.definition[..4], zstd_magic);
                         assert_eq!(compressed_contract.hash, *CONTRACT1_HASH);
                 });
-                assert_matches!(rx_event.recv().await.unwrap(), Event::Update(block, state_update, _) => {
+                assert_matches!(rx_event.recv().await.unwrap(), Event::Update((block, _), state_update, _) => {
                     assert_eq!(*block, *BLOCK1);
                     assert_eq!(*state_update, *STATE_UPDATE1);
                 });
-                assert_matches!(rx_event.recv().await.unwrap(), Event::Update(block, state_update, _) => {
+                assert_matches!(rx_event.recv().await.unwrap(), Event::Update((block, _), state_update, _) => {
                     assert_eq!(*block, *BLOCK2);
                     assert_eq!(*state_update, *STATE_UPDATE2);
                 });
@@ -1870,7 +2056,7 @@ mod tests {
                 assert_matches!(rx_event.recv().await.unwrap(), Event::Reorg(tail) => {
                     assert_eq!(tail, BLOCK2_NUMBER);
                 });
-                assert_matches!(rx_event.recv().await.unwrap(), Event::Update(block, state_update, _) => {
+                assert_matches!(rx_event.recv().await.unwrap(), Event::Update((block, _), state_update, _) => {
                     assert_eq!(*block, block2_v2);
                     assert_eq!(*state_update, *STATE_UPDATE2_V2);
                 });
@@ -1903,6 +2089,8 @@ mod tests {
                     transaction_receipts: vec![],
                     transactions: vec![],
                     starknet_version: None,
+                    transaction_commitment: None,
+                    event_commitment: None,
                 };
                 let block2 = reply::Block {
                     block_hash: *BLOCK2_HASH,
@@ -1918,6 +2106,8 @@ mod tests {
                     transaction_receipts: vec![],
                     transactions: vec![],
                     starknet_version: None,
+                    transaction_commitment: None,
+                    event_commitment: None,
                 };
 
                 // Fetch the genesis block with respective state update and contracts
@@ -2018,7 +2208,14 @@ mod tests {
                 );
 
                 // Run the UUT
-                let _jh = tokio::spawn(sync(tx_event, mock, None, Chain::Testnet, None));
+                let _jh = tokio::spawn(sync(
+                    tx_event,
+                    mock,
+                    None,
+                    Chain::Testnet,
+                    None,
+                    BlockValidationMode::AllowMismatch,
+                ));
 
                 let zstd_magic = vec![0x28, 0xb5, 0x2f, 0xfd];
 
@@ -2034,7 +2231,7 @@ mod tests {
                         assert_eq!(compressed_contract.definition[..4], zstd_magic);
                         assert_eq!(compressed_contract.hash, *CONTRACT0_HASH);
                 });
-                assert_matches!(rx_event.recv().await.unwrap(), Event::Update(block, state_update, _) => {
+                assert_matches!(rx_event.recv().await.unwrap(), Event::Update((block, _), state_update, _) => {
                     assert_eq!(*block, *BLOCK0);
                     assert_eq!(*state_update, *STATE_UPDATE0);
                 });
@@ -2050,7 +2247,7 @@ mod tests {
                         assert_eq!(compressed_contract.definition[..4], zstd_magic);
                         assert_eq!(compressed_contract.hash, *CONTRACT1_HASH);
                 });
-                assert_matches!(rx_event.recv().await.unwrap(), Event::Update(block, state_update, _) => {
+                assert_matches!(rx_event.recv().await.unwrap(), Event::Update((block, _), state_update, _) => {
                     assert_eq!(*block, *BLOCK1);
                     assert_eq!(*state_update, *STATE_UPDATE1);
                 });
@@ -2062,11 +2259,11 @@ mod tests {
                 assert_matches!(rx_event.recv().await.unwrap(), Event::Reorg(tail) => {
                     assert_eq!(tail, BLOCK1_NUMBER);
                 });
-                assert_matches!(rx_event.recv().await.unwrap(), Event::Update(block, state_update, _) => {
+                assert_matches!(rx_event.recv().await.unwrap(), Event::Update((block, _), state_update, _) => {
                     assert_eq!(*block, block1_v2);
                     assert_eq!(*state_update, *STATE_UPDATE1_V2);
                 });
-                assert_matches!(rx_event.recv().await.unwrap(), Event::Update(block, state_update, _) => {
+                assert_matches!(rx_event.recv().await.unwrap(), Event::Update((block, _), state_update, _) => {
                     assert_eq!(*block, block2);
                     assert_eq!(*state_update, *STATE_UPDATE2);
                 });
@@ -2095,7 +2292,14 @@ mod tests {
                 );
 
                 // Run the UUT
-                let jh = tokio::spawn(sync(tx_event, mock, None, Chain::Testnet, None));
+                let jh = tokio::spawn(sync(
+                    tx_event,
+                    mock,
+                    None,
+                    Chain::Testnet,
+                    None,
+                    BlockValidationMode::AllowMismatch,
+                ));
 
                 // Wrap this in a timeout so we don't wait forever in case of test failure.
                 // Right now closing the channel causes an error.
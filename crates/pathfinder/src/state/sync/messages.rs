@@ -0,0 +1,175 @@
+//! Indexes the message logs of the Starknet core contract.
+//!
+//! Unlike the L1 state update sync, this runs as its own task as the logs are only used to
+//! serve message statuses and do not affect the state.
+
+use std::time::Duration;
+
+use anyhow::Context;
+
+use crate::{
+    core::Chain,
+    ethereum::{
+        log::{
+            ConsumedMessageToL1Log, ConsumedMessageToL2Log, FetchError, LogFetcher, MessageLog,
+            MessageToL2Log, MetaLog,
+        },
+        state_update::state_root,
+        transport::EthereumTransport,
+    },
+    storage::{L1MessageKind, L1MessagesTable, Storage},
+};
+
+/// The delay before retrying after failing to fetch logs.
+const RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// Maps a message log type to its stored [kind](L1MessageKind).
+trait StoredLog: MetaLog + PartialEq + std::fmt::Debug + Clone + Into<MessageLog> {
+    const KIND: L1MessageKind;
+
+    fn from_message_log(log: MessageLog) -> Option<Self>;
+}
+
+impl StoredLog for MessageToL2Log {
+    const KIND: L1MessageKind = L1MessageKind::ToL2;
+
+    fn from_message_log(log: MessageLog) -> Option<Self> {
+        match log {
+            MessageLog::ToL2(log) => Some(log),
+            _ => None,
+        }
+    }
+}
+
+impl StoredLog for ConsumedMessageToL2Log {
+    const KIND: L1MessageKind = L1MessageKind::ConsumedToL2;
+
+    fn from_message_log(log: MessageLog) -> Option<Self> {
+        match log {
+            MessageLog::ConsumedToL2(log) => Some(log),
+            _ => None,
+        }
+    }
+}
+
+impl StoredLog for ConsumedMessageToL1Log {
+    const KIND: L1MessageKind = L1MessageKind::ConsumedToL1;
+
+    fn from_message_log(log: MessageLog) -> Option<Self> {
+        match log {
+            MessageLog::ConsumedToL1(log) => Some(log),
+            _ => None,
+        }
+    }
+}
+
+/// Syncs the [message logs](MessageLog) of the Starknet core contract into the
+/// [L1MessagesTable], resuming from the latest stored log of each kind.
+///
+/// Runs until an unrecoverable database error occurs.
+pub async fn sync<T>(storage: Storage, transport: T, chain: Chain) -> anyhow::Result<()>
+where
+    T: EthereumTransport + Send + Sync + Clone,
+{
+    let mut db_conn = storage
+        .connection()
+        .context("Creating database connection")?;
+
+    let mut to_l2 = fetcher::<MessageToL2Log>(&mut db_conn, chain)?;
+    let mut consumed_to_l2 = fetcher::<ConsumedMessageToL2Log>(&mut db_conn, chain)?;
+    let mut consumed_to_l1 = fetcher::<ConsumedMessageToL1Log>(&mut db_conn, chain)?;
+
+    loop {
+        let mut found = false;
+        let mut failed = false;
+
+        for result in [
+            step(&mut to_l2, &mut db_conn, transport.clone()).await,
+            step(&mut consumed_to_l2, &mut db_conn, transport.clone()).await,
+            step(&mut consumed_to_l1, &mut db_conn, transport.clone()).await,
+        ] {
+            match result? {
+                Some(0) => {}
+                Some(_) => found = true,
+                None => failed = true,
+            }
+        }
+
+        if failed {
+            tokio::time::sleep(RETRY_DELAY).await;
+        } else if !found {
+            tokio::time::sleep(super::head_poll_interval(chain)).await;
+        }
+    }
+}
+
+fn fetcher<L: StoredLog>(
+    db_conn: &mut rusqlite::Connection,
+    chain: Chain,
+) -> anyhow::Result<LogFetcher<L>> {
+    let head = latest::<L>(db_conn)?;
+    Ok(LogFetcher::new(head, chain, state_root::genesis(chain)))
+}
+
+fn latest<L: StoredLog>(db_conn: &mut rusqlite::Connection) -> anyhow::Result<Option<L>> {
+    tokio::task::block_in_place(|| {
+        let tx = db_conn
+            .transaction()
+            .context("Create database transaction")?;
+        let latest = L1MessagesTable::get_latest(&tx, L::KIND).context("Query latest log")?;
+        Ok(latest.and_then(L::from_message_log))
+    })
+}
+
+/// Fetches and stores the next batch of logs of one kind.
+///
+/// Returns the number of logs stored, or [None] if fetching failed and should be retried later.
+/// Only database errors are returned as they are not recoverable.
+async fn step<L: StoredLog>(
+    fetcher: &mut LogFetcher<L>,
+    db_conn: &mut rusqlite::Connection,
+    transport: impl EthereumTransport + Send + Sync,
+) -> anyhow::Result<Option<usize>> {
+    match fetcher.fetch(transport).await {
+        Ok(logs) => {
+            tokio::task::block_in_place(|| -> anyhow::Result<()> {
+                let tx = db_conn
+                    .transaction()
+                    .context("Create database transaction")?;
+                for log in &logs {
+                    L1MessagesTable::insert(&tx, &log.clone().into())
+                        .context("Insert message log")?;
+                }
+                tx.commit().context("Commit database transaction")
+            })?;
+
+            Ok(Some(logs.len()))
+        }
+        Err(FetchError::Reorg) => {
+            let head = fetcher
+                .head()
+                .as_ref()
+                .map(|head| head.origin().block.number);
+            tracing::info!(kind=?L::KIND, ?head, "L1 reorg detected while syncing message logs");
+
+            if let Some(head) = head {
+                tokio::task::block_in_place(|| -> anyhow::Result<()> {
+                    let tx = db_conn
+                        .transaction()
+                        .context("Create database transaction")?;
+                    L1MessagesTable::reorg(&tx, L::KIND, head)
+                        .context("Delete reorged message logs")?;
+                    tx.commit().context("Commit database transaction")
+                })?;
+            }
+
+            let head = latest::<L>(db_conn)?;
+            fetcher.set_head(head);
+            Ok(Some(0))
+        }
+        Err(FetchError::Other(error)) => {
+            tracing::warn!(kind=?L::KIND, reason=?error, "Failed fetching message logs");
+            Ok(None)
+        }
+    }
+}
@@ -0,0 +1,135 @@
+//! Block processing and reorg metrics of the sync process
+use std::time::Duration;
+
+use super::l2::Timings;
+
+const METRIC_BLOCK_PROCESSING_DURATION: &str = "sync_block_processing_duration_seconds";
+const METRIC_REORGS: &str = "sync_reorgs_total";
+const METRIC_REORG_DEPTH: &str = "sync_reorg_depth";
+
+/// The phases of processing a block, in order.
+const PHASES: [&str; 6] = [
+    "block_download",
+    "state_diff_download",
+    "contract_deployment",
+    "class_declaration",
+    "tree_commit",
+    "database_update",
+];
+
+/// The layer a reorg happened on.
+#[derive(Debug, Clone, Copy)]
+pub(super) enum Layer {
+    L1,
+    L2,
+}
+
+impl Layer {
+    fn as_label(&self) -> &'static str {
+        match self {
+            Layer::L1 => "l1",
+            Layer::L2 => "l2",
+        }
+    }
+}
+
+pub(super) fn register() {
+    for phase in PHASES {
+        metrics::register_histogram!(METRIC_BLOCK_PROCESSING_DURATION, "phase" => phase);
+    }
+    metrics::describe_histogram!(
+        METRIC_BLOCK_PROCESSING_DURATION,
+        metrics::Unit::Seconds,
+        "time spent on each phase of processing a block."
+    );
+
+    for layer in [Layer::L1, Layer::L2] {
+        metrics::register_counter!(METRIC_REORGS, "layer" => layer.as_label());
+        metrics::register_histogram!(METRIC_REORG_DEPTH, "layer" => layer.as_label());
+    }
+    metrics::describe_counter!(
+        METRIC_REORGS,
+        metrics::Unit::Count,
+        "number of reorgs on L1 and L2."
+    );
+    metrics::describe_histogram!(
+        METRIC_REORG_DEPTH,
+        metrics::Unit::Count,
+        "number of blocks removed by a reorg."
+    );
+}
+
+/// Records the duration of each phase of processing a block. The update of the state trees is
+/// part of the database update, and is excluded from the `database_update` phase.
+pub(super) fn record_block(timings: &Timings, tree_commit: Duration, database_update: Duration) {
+    let durations = [
+        timings.block_download,
+        timings.state_diff_download,
+        timings.contract_deployment,
+        timings.class_declaration,
+        tree_commit,
+        database_update.saturating_sub(tree_commit),
+    ];
+
+    for (phase, duration) in PHASES.into_iter().zip(durations) {
+        metrics::histogram!(METRIC_BLOCK_PROCESSING_DURATION, duration.as_secs_f64(), "phase" => phase);
+    }
+}
+
+pub(super) fn record_reorg(layer: Layer, depth: u64) {
+    metrics::increment_counter!(METRIC_REORGS, "layer" => layer.as_label());
+    metrics::histogram!(METRIC_REORG_DEPTH, depth as f64, "layer" => layer.as_label());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::monitoring::metrics::test::{FakeRecorder, RecorderGuard};
+
+    #[test]
+    fn block() {
+        let recorder = FakeRecorder::for_metrics(&[METRIC_BLOCK_PROCESSING_DURATION]);
+        let handle = recorder.handle();
+        let _guard = RecorderGuard::lock(recorder);
+
+        let timings = Timings {
+            block_download: Duration::from_secs(1),
+            state_diff_download: Duration::from_secs(2),
+            contract_deployment: Duration::from_secs(3),
+            class_declaration: Duration::from_secs(4),
+        };
+        record_block(&timings, Duration::from_secs(5), Duration::from_secs(11));
+
+        for (phase, expected) in PHASES.into_iter().zip([1.0, 2.0, 3.0, 4.0, 5.0, 6.0]) {
+            let values = handle.get_histogram_values_by_label(
+                METRIC_BLOCK_PROCESSING_DURATION,
+                [("phase", phase)],
+            );
+            assert_eq!(values, vec![expected], "{}", phase);
+        }
+    }
+
+    #[test]
+    fn reorg() {
+        let recorder = FakeRecorder::for_metrics(&[METRIC_REORGS, METRIC_REORG_DEPTH]);
+        let handle = recorder.handle();
+        let _guard = RecorderGuard::lock(recorder);
+
+        register();
+        record_reorg(Layer::L2, 3);
+        record_reorg(Layer::L2, 1);
+
+        assert_eq!(
+            handle.get_counter_value_by_label(METRIC_REORGS, [("layer", "l2")]),
+            2
+        );
+        assert_eq!(
+            handle.get_counter_value_by_label(METRIC_REORGS, [("layer", "l1")]),
+            0
+        );
+        assert_eq!(
+            handle.get_histogram_values_by_label(METRIC_REORG_DEPTH, [("layer", "l2")]),
+            vec![3.0, 1.0]
+        );
+    }
+}
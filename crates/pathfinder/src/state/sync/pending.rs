@@ -99,6 +99,8 @@ mod tests {
             transaction_receipts: Vec::new(),
             transactions: Vec::new(),
             starknet_version: None,
+            transaction_commitment: None,
+            event_commitment: None,
         };
 
         pub static ref PENDING_DIFF: sequencer::reply::StateUpdate = sequencer::reply::StateUpdate {
@@ -109,6 +111,7 @@ mod tests {
                 storage_diffs: std::collections::HashMap::new(),
                 deployed_contracts: Vec::new(),
                 declared_contracts: Vec::new(),
+                declared_classes: Vec::new(),
                 nonces: std::collections::HashMap::new(),
             }
         };
@@ -0,0 +1,448 @@
+//! Offline repair of the database, used by `pathfinder db rollback` and `pathfinder db resync`.
+//!
+//! The node must not be running, see [maintenance](crate::storage::maintenance).
+use anyhow::Context;
+use rusqlite::{Connection, Transaction};
+
+use super::{reorg_l1_state, reorg_l2_state};
+use crate::core::{GasPrice, SequencerAddress, StarknetBlockNumber};
+use crate::rpc::v01::types::reply::StateUpdate as StoredStateUpdate;
+use crate::sequencer::{
+    self,
+    reply::{Block, MaybePendingBlock, StateUpdate},
+};
+use crate::storage::{
+    ContractCodeTable, StarknetBlocksBlockId, StarknetBlocksTable, StarknetStateUpdatesTable,
+    StarknetTransactionsTable,
+};
+use stark_hash::StarkHash;
+
+/// Removes all blocks after `to`, or all blocks if `to` is [None], exactly like an L1 and L2 reorg
+/// to that block would.
+///
+/// The removed blocks are synced again when the node is started.
+pub fn rollback(
+    transaction: &Transaction<'_>,
+    to: Option<StarknetBlockNumber>,
+) -> anyhow::Result<()> {
+    let reorg_tail = match to {
+        Some(to) => to + 1,
+        None => StarknetBlockNumber::GENESIS,
+    };
+
+    ContractCodeTable::reorg_declared_on(transaction, reorg_tail)
+        .context("Clearing declaring blocks of classes")?;
+    reorg_l2_state(transaction, reorg_tail)?;
+    reorg_l1_state(transaction, reorg_tail)
+}
+
+/// A difference between a stored block and the sequencer's version of it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mismatch {
+    pub block: StarknetBlockNumber,
+    pub reason: String,
+}
+
+/// Downloads the blocks `from..=to` again and compares them with the stored blocks.
+///
+/// Returns the differences, ordered by block number.
+pub async fn resync(
+    connection: &mut Connection,
+    sequencer: &impl sequencer::ClientApi,
+    from: StarknetBlockNumber,
+    to: StarknetBlockNumber,
+) -> anyhow::Result<Vec<Mismatch>> {
+    let mut mismatches = Vec::new();
+
+    let mut number = from;
+    while number <= to {
+        let block = match sequencer
+            .block(number.into())
+            .await
+            .with_context(|| format!("Downloading block {}", number))?
+        {
+            MaybePendingBlock::Block(block) => block,
+            MaybePendingBlock::Pending(_) => anyhow::bail!("Sequencer returned a pending block"),
+        };
+        let state_update = sequencer
+            .state_update(number.into())
+            .await
+            .with_context(|| format!("Downloading state update {}", number))?;
+
+        let transaction = connection
+            .transaction()
+            .context("Create database transaction")?;
+        let reasons = compare(&transaction, block, state_update)
+            .with_context(|| format!("Comparing block {}", number))?;
+        drop(transaction);
+
+        if !reasons.is_empty() {
+            tracing::debug!(block=%number, ?reasons, "Block differs from the sequencer's");
+        }
+        mismatches.extend(reasons.into_iter().map(|reason| Mismatch {
+            block: number,
+            reason,
+        }));
+
+        number += 1;
+    }
+
+    Ok(mismatches)
+}
+
+/// Compares a block and its state update with the stored data, and returns the differences.
+fn compare(
+    transaction: &Transaction<'_>,
+    block: Block,
+    state_update: StateUpdate,
+) -> anyhow::Result<Vec<String>> {
+    let block_id = StarknetBlocksBlockId::Number(block.block_number);
+    let stored = match StarknetBlocksTable::get(transaction, block_id).context("Reading block")? {
+        Some(stored) => stored,
+        None => return Ok(vec!["missing from the database".to_owned()]),
+    };
+
+    let mut reasons = Vec::new();
+    let mut check = |field: &str, stored: String, expected: String| {
+        if stored != expected {
+            reasons.push(format!("{} is {} instead of {}", field, stored, expected));
+        }
+    };
+
+    check(
+        "hash",
+        stored.hash.0.to_string(),
+        block.block_hash.0.to_string(),
+    );
+    check(
+        "root",
+        stored.root.0.to_string(),
+        block.state_root.0.to_string(),
+    );
+    check(
+        "timestamp",
+        stored.timestamp.get().to_string(),
+        block.timestamp.get().to_string(),
+    );
+    check(
+        "gas price",
+        stored.gas_price.0.to_string(),
+        block.gas_price.unwrap_or(GasPrice::ZERO).0.to_string(),
+    );
+    check(
+        "sequencer address",
+        stored.sequencer_address.0.to_string(),
+        block
+            .sequencer_address
+            .unwrap_or(SequencerAddress(StarkHash::ZERO))
+            .0
+            .to_string(),
+    );
+
+    let stored_transactions =
+        StarknetTransactionsTable::get_transaction_data_for_block(transaction, block_id)
+            .context("Reading transactions")?;
+    if stored_transactions.len() != block.transactions.len() {
+        reasons.push(format!(
+            "has {} transactions instead of {}",
+            stored_transactions.len(),
+            block.transactions.len()
+        ));
+    } else {
+        let expected = block.transactions.iter().zip(&block.transaction_receipts);
+        for ((stored_tx, stored_receipt), (tx, receipt)) in stored_transactions.iter().zip(expected)
+        {
+            if stored_tx != tx {
+                reasons.push(format!("transaction {} differs", tx.hash().0));
+            } else if stored_receipt != receipt {
+                reasons.push(format!("receipt of transaction {} differs", tx.hash().0));
+            }
+        }
+    }
+
+    let stored_update =
+        StarknetStateUpdatesTable::get(transaction, stored.hash).context("Reading state update")?;
+    match stored_update {
+        None => reasons.push("state update is missing".to_owned()),
+        Some(stored_update) => reasons.extend(compare_state_updates(stored_update, state_update)),
+    }
+
+    Ok(reasons)
+}
+
+/// Compares a stored state update with the sequencer's, and returns the differences.
+///
+/// Diffs are compared regardless of their order, which follows the sequencer reply's maps. Nonces
+/// are not part of stored state updates, so they are only compared if any were stored.
+fn compare_state_updates(stored: StoredStateUpdate, expected: StateUpdate) -> Vec<String> {
+    fn sorted<T, K: Ord>(mut items: Vec<T>, key: impl FnMut(&T) -> K) -> Vec<T> {
+        items.sort_by_key(key);
+        items
+    }
+
+    let mut reasons = Vec::new();
+    let mut check = |differs: bool, what: &str| {
+        if differs {
+            reasons.push(format!("state update {} differ", what));
+        }
+    };
+
+    check(
+        (stored.block_hash, stored.old_root, stored.new_root)
+            != (expected.block_hash, expected.old_root, expected.new_root),
+        "roots",
+    );
+
+    let stored_diff = stored.state_diff;
+    let expected_diff = expected.state_diff;
+
+    let stored_storage = stored_diff
+        .storage_diffs
+        .into_iter()
+        .map(|diff| (diff.address, diff.key, diff.value))
+        .collect();
+    let expected_storage = expected_diff
+        .storage_diffs
+        .into_iter()
+        .flat_map(|(address, diffs)| {
+            diffs
+                .into_iter()
+                .map(move |diff| (address, diff.key, diff.value))
+        })
+        .collect();
+    check(
+        sorted(stored_storage, |(address, key, _)| (*address, *key))
+            != sorted(expected_storage, |(address, key, _)| (*address, *key)),
+        "storage diffs",
+    );
+
+    let stored_deployed = stored_diff
+        .deployed_contracts
+        .into_iter()
+        .map(|contract| (contract.address, contract.class_hash))
+        .collect();
+    let expected_deployed = expected_diff
+        .deployed_contracts
+        .into_iter()
+        .map(|contract| (contract.address, contract.class_hash))
+        .collect();
+    check(
+        sorted(stored_deployed, |contract| *contract)
+            != sorted(expected_deployed, |contract| *contract),
+        "deployed contracts",
+    );
+
+    // Sierra classes are stored alongside Cairo 0.x classes.
+    let stored_declared = stored_diff
+        .declared_contracts
+        .into_iter()
+        .map(|class| class.class_hash)
+        .collect();
+    let expected_declared = expected_diff
+        .declared_contracts
+        .into_iter()
+        .chain(
+            expected_diff
+                .declared_classes
+                .into_iter()
+                .map(|class| class.class_hash),
+        )
+        .collect();
+    check(
+        sorted(stored_declared, |class_hash| *class_hash)
+            != sorted(expected_declared, |class_hash| *class_hash),
+        "declared classes",
+    );
+
+    if !stored_diff.nonces.is_empty() {
+        let stored_nonces = stored_diff
+            .nonces
+            .into_iter()
+            .map(|nonce| (nonce.contract_address, nonce.nonce))
+            .collect();
+        let expected_nonces = expected_diff.nonces.into_iter().collect();
+        check(
+            sorted(stored_nonces, |(address, _)| *address)
+                != sorted(expected_nonces, |(address, _)| *address),
+            "nonces",
+        );
+    }
+
+    reasons
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{
+        ContractAddress, ContractNonce, GlobalRoot, StarknetBlockHash, StarknetBlockTimestamp,
+        StorageAddress, StorageValue,
+    };
+    use crate::rpc::v01::types::reply::state_update::Nonce;
+    use crate::sequencer::reply::state_update::{StateDiff, StorageDiff};
+    use crate::sequencer::reply::Status;
+    use crate::storage::{CanonicalBlocksTable, RefsTable, StarknetBlock, Storage};
+
+    fn block(number: u64) -> Block {
+        Block {
+            block_hash: StarknetBlockHash(StarkHash::from_u64(number + 1)),
+            block_number: StarknetBlockNumber::new_or_panic(number),
+            gas_price: Some(GasPrice(1)),
+            parent_block_hash: StarknetBlockHash(StarkHash::from_u64(number)),
+            sequencer_address: Some(SequencerAddress(StarkHash::from_u64(2))),
+            state_root: GlobalRoot(StarkHash::from_u64(3)),
+            status: Status::AcceptedOnL2,
+            timestamp: StarknetBlockTimestamp::new_or_panic(number),
+            transaction_receipts: vec![],
+            transactions: vec![],
+            starknet_version: None,
+            transaction_commitment: None,
+            event_commitment: None,
+        }
+    }
+
+    fn state_update(block: &Block) -> StateUpdate {
+        StateUpdate {
+            block_hash: Some(block.block_hash),
+            new_root: block.state_root,
+            old_root: GlobalRoot(StarkHash::ZERO),
+            state_diff: StateDiff {
+                storage_diffs: Default::default(),
+                deployed_contracts: vec![],
+                declared_contracts: vec![],
+                declared_classes: vec![],
+                nonces: Default::default(),
+            },
+        }
+    }
+
+    /// Inserts the block as `l2_update` would, except for the state itself.
+    fn insert(transaction: &Transaction<'_>, block: &Block, state_update: &StoredStateUpdate) {
+        let stored = StarknetBlock {
+            number: block.block_number,
+            hash: block.block_hash,
+            root: block.state_root,
+            timestamp: block.timestamp,
+            gas_price: block.gas_price.unwrap(),
+            sequencer_address: block.sequencer_address.unwrap(),
+        };
+        StarknetBlocksTable::insert(transaction, &stored, None).unwrap();
+        CanonicalBlocksTable::insert(transaction, block.block_number, block.block_hash).unwrap();
+        StarknetStateUpdatesTable::insert(transaction, block.block_hash, state_update).unwrap();
+    }
+
+    #[test]
+    fn rollback() {
+        let storage = Storage::in_memory().unwrap();
+        let mut connection = storage.connection().unwrap();
+        let transaction = connection.transaction().unwrap();
+
+        for number in 0..3 {
+            let block = block(number);
+            insert(&transaction, &block, &state_update(&block).into());
+        }
+        RefsTable::set_l1_l2_head(&transaction, Some(StarknetBlockNumber::new_or_panic(2)))
+            .unwrap();
+
+        super::rollback(&transaction, Some(StarknetBlockNumber::GENESIS)).unwrap();
+
+        let head = StarknetBlocksTable::get(&transaction, StarknetBlocksBlockId::Latest)
+            .unwrap()
+            .unwrap();
+        assert_eq!(head.number, StarknetBlockNumber::GENESIS);
+        assert_eq!(
+            RefsTable::get_l1_l2_head(&transaction).unwrap(),
+            Some(StarknetBlockNumber::GENESIS)
+        );
+
+        super::rollback(&transaction, None).unwrap();
+        assert_eq!(
+            StarknetBlocksTable::get(&transaction, StarknetBlocksBlockId::Latest).unwrap(),
+            None
+        );
+        assert_eq!(RefsTable::get_l1_l2_head(&transaction).unwrap(), None);
+    }
+
+    #[test]
+    fn compare() {
+        let storage = Storage::in_memory().unwrap();
+        let mut connection = storage.connection().unwrap();
+        let transaction = connection.transaction().unwrap();
+
+        let stored = block(0);
+        insert(&transaction, &stored, &state_update(&stored).into());
+
+        let reasons = super::compare(&transaction, stored.clone(), state_update(&stored)).unwrap();
+        assert_eq!(reasons, Vec::<String>::new());
+
+        let mut other = stored.clone();
+        other.block_hash = StarknetBlockHash(StarkHash::from_u64(100));
+        other.timestamp = StarknetBlockTimestamp::new_or_panic(100);
+        let reasons = super::compare(&transaction, other.clone(), state_update(&other)).unwrap();
+        assert_eq!(reasons.len(), 3, "{:?}", reasons);
+
+        let reasons = super::compare(&transaction, block(1), state_update(&block(1))).unwrap();
+        assert_eq!(reasons, vec!["missing from the database".to_owned()]);
+
+        // Several contracts, whose diffs are stored in whichever order the sequencer's maps
+        // yielded them, with nonces which were not stored.
+        let stored = block(2);
+        let mut expected = state_update(&stored);
+        for address in 1..4 {
+            let diffs = (1..3)
+                .map(|key| StorageDiff {
+                    key: StorageAddress::new_or_panic(StarkHash::from_u64(key)),
+                    value: StorageValue(StarkHash::from_u64(address + key)),
+                })
+                .collect();
+            let address = ContractAddress::new_or_panic(StarkHash::from_u64(address));
+            expected.state_diff.storage_diffs.insert(address, diffs);
+            expected
+                .state_diff
+                .nonces
+                .insert(address, ContractNonce(StarkHash::from_u64(1)));
+        }
+        let mut stored_update = StoredStateUpdate::from(expected.clone());
+        stored_update.state_diff.storage_diffs.reverse();
+        assert!(stored_update.state_diff.nonces.is_empty());
+        insert(&transaction, &stored, &stored_update);
+
+        let reasons = super::compare(&transaction, stored.clone(), expected.clone()).unwrap();
+        assert_eq!(reasons, Vec::<String>::new());
+
+        // Stored nonces are compared.
+        let stored = block(3);
+        let mut stored_update = StoredStateUpdate::from(expected.clone());
+        stored_update.block_hash = Some(stored.block_hash);
+        stored_update.state_diff.nonces = expected
+            .state_diff
+            .nonces
+            .iter()
+            .map(|(&contract_address, &nonce)| Nonce {
+                contract_address,
+                nonce,
+            })
+            .collect();
+        insert(&transaction, &stored, &stored_update);
+        expected.block_hash = Some(stored.block_hash);
+
+        let reasons = super::compare(&transaction, stored.clone(), expected.clone()).unwrap();
+        assert_eq!(reasons, Vec::<String>::new());
+
+        let address = ContractAddress::new_or_panic(StarkHash::from_u64(1));
+        expected
+            .state_diff
+            .nonces
+            .insert(address, ContractNonce(StarkHash::from_u64(2)));
+        expected.state_diff.storage_diffs.get_mut(&address).unwrap()[0].value =
+            StorageValue(StarkHash::from_u64(100));
+        let reasons = super::compare(&transaction, stored, expected).unwrap();
+        assert_eq!(
+            reasons,
+            vec![
+                "state update storage diffs differ".to_owned(),
+                "state update nonces differ".to_owned()
+            ]
+        );
+    }
+}
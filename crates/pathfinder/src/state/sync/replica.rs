@@ -0,0 +1,251 @@
+//! Follows a writer node which shares its database with this read-only replica.
+//!
+//! The replica does not sync by itself. Instead it watches the head of the [StarknetBlocksTable]
+//! and keeps the sync status, pending data and response cache up-to-date with the writer's
+//! progress.
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Context;
+use stark_hash::StarkHash;
+use tokio::sync::mpsc;
+
+use super::{
+    l2, pending, update_sync_status_current, update_sync_status_latest, PendingData, State,
+};
+use crate::core::{Chain, GlobalRoot, StarknetBlockHash, StarknetBlockNumber};
+use crate::rpc::cache::ResponseCache;
+use crate::sequencer;
+use crate::storage::{StarknetBlocksBlockId, StarknetBlocksTable, Storage};
+
+/// Follows the writer's progress by polling the database head every `head_poll_interval`.
+///
+/// If `pending_poll_interval` is set, the pending block is polled from the sequencer. Unlike
+/// the writer, the replica cannot write to the database to verify the pending state root, so
+/// pending data is served as received from the sequencer.
+///
+/// The replica does not see the writer's reorgs, so `response_cache` is cleared whenever the
+/// previous head is no longer part of the chain.
+///
+/// Returns once `shutdown` resolves.
+#[allow(clippy::too_many_arguments)]
+pub async fn follow<SequencerClient>(
+    storage: Storage,
+    chain: Chain,
+    sequencer: SequencerClient,
+    state: Arc<State>,
+    pending_data: PendingData,
+    response_cache: ResponseCache,
+    head_poll_interval: Duration,
+    pending_poll_interval: Option<Duration>,
+    shutdown: impl std::future::Future<Output = ()> + Send,
+) -> anyhow::Result<()>
+where
+    SequencerClient: sequencer::ClientApi + Clone + Send + Sync + 'static,
+{
+    let mut connection = storage
+        .connection()
+        .context("Creating database connection")?;
+
+    let mut head = tokio::task::block_in_place(|| read_head(&mut connection))?;
+
+    let (starting_block_num, starting_block_hash) = head
+        .map(|(number, hash, _)| (number, hash))
+        // Seems a better choice for an invalid block number than 0
+        .unwrap_or((StarknetBlockNumber::MAX, StarknetBlockHash(StarkHash::ZERO)));
+    let _status_sync = tokio::spawn(update_sync_status_latest(
+        Arc::clone(&state),
+        sequencer.clone(),
+        starting_block_hash,
+        starting_block_num,
+        chain,
+    ));
+
+    let (mut pending_handle, mut rx_pending) =
+        spawn_pending(sequencer.clone(), head, pending_poll_interval);
+
+    tokio::pin!(shutdown);
+
+    loop {
+        tokio::select! {
+            _ = &mut shutdown => {
+                if let Some(handle) = pending_handle.take() {
+                    handle.abort();
+                }
+                tracing::info!("Stopped following the writer");
+                return Ok(());
+            }
+            _ = tokio::time::sleep(head_poll_interval) => {
+                let new_head = tokio::task::block_in_place(|| read_head(&mut connection))?;
+                if new_head == head {
+                    continue;
+                }
+
+                pending_data.clear().await;
+                if let Some(handle) = pending_handle.take() {
+                    handle.abort();
+                }
+
+                if let Some((number, hash, _)) = head {
+                    let reorged =
+                        tokio::task::block_in_place(|| is_reorged(&mut connection, number, hash))?;
+                    if reorged {
+                        response_cache.clear();
+                        tracing::debug!(%number, "Writer reorged the previous head");
+                    }
+                }
+
+                match new_head {
+                    Some((number, hash, _)) => {
+                        update_sync_status_current(&state, hash, number).await;
+                        tracing::debug!(%number, "Writer updated the head");
+                    }
+                    None => tracing::info!("Writer removed all blocks"),
+                }
+
+                head = new_head;
+                (pending_handle, rx_pending) =
+                    spawn_pending(sequencer.clone(), head, pending_poll_interval);
+            }
+            Some(l2::Event::Pending(block, state_update)) = rx_pending.recv() => {
+                pending_data.set(block, state_update).await;
+                tracing::debug!("Updated pending data");
+            }
+        }
+    }
+}
+
+fn read_head(
+    connection: &mut rusqlite::Connection,
+) -> anyhow::Result<Option<(StarknetBlockNumber, StarknetBlockHash, GlobalRoot)>> {
+    let tx = connection
+        .transaction()
+        .context("Create database transaction")?;
+    let head = StarknetBlocksTable::get(&tx, StarknetBlocksBlockId::Latest)
+        .context("Query L2 head from database")?
+        .map(|block| (block.number, block.hash, block.root));
+
+    Ok(head)
+}
+
+/// Whether the block `number` is no longer `hash`, or gone.
+fn is_reorged(
+    connection: &mut rusqlite::Connection,
+    number: StarknetBlockNumber,
+    hash: StarknetBlockHash,
+) -> anyhow::Result<bool> {
+    let tx = connection
+        .transaction()
+        .context("Create database transaction")?;
+    let current = StarknetBlocksTable::get_hash(&tx, number.into())
+        .context("Query block hash from database")?;
+
+    Ok(current != Some(hash))
+}
+
+/// Polls the sequencer's pending block on top of `head`, until aborted.
+///
+/// Does nothing if there is no head or pending polling is disabled.
+fn spawn_pending(
+    sequencer: impl sequencer::ClientApi + Send + Sync + 'static,
+    head: Option<(StarknetBlockNumber, StarknetBlockHash, GlobalRoot)>,
+    poll_interval: Option<Duration>,
+) -> (
+    Option<tokio::task::JoinHandle<()>>,
+    mpsc::Receiver<l2::Event>,
+) {
+    let (tx, rx) = mpsc::channel(1);
+
+    let (head, poll_interval) = match (head, poll_interval) {
+        (Some((_, hash, root)), Some(poll_interval)) => ((hash, root), poll_interval),
+        _ => return (None, rx),
+    };
+
+    let handle = tokio::spawn(async move {
+        // The sequencer may be ahead of the writer, in which case polling resumes once the
+        // writer has caught up and this task is replaced.
+        loop {
+            if let Err(e) = pending::poll_pending(tx.clone(), &sequencer, head, poll_interval).await
+            {
+                tracing::debug!(reason=?e, "Pending polling failed");
+            }
+            tokio::time::sleep(poll_interval).await;
+        }
+    });
+
+    (Some(handle), rx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{GasPrice, SequencerAddress, StarknetBlockTimestamp};
+    use crate::rpc::v01::types::reply::{syncing, Syncing as SyncStatus};
+    use crate::storage::StarknetBlock;
+
+    fn block(number: u64) -> StarknetBlock {
+        StarknetBlock {
+            number: StarknetBlockNumber::new_or_panic(number),
+            hash: StarknetBlockHash(StarkHash::from_u64(number + 1)),
+            root: GlobalRoot(StarkHash::ZERO),
+            timestamp: StarknetBlockTimestamp::new_or_panic(number),
+            gas_price: GasPrice::ZERO,
+            sequencer_address: SequencerAddress(StarkHash::ZERO),
+        }
+    }
+
+    fn insert(storage: &Storage, block: &StarknetBlock) {
+        let mut connection = storage.connection().unwrap();
+        let tx = connection.transaction().unwrap();
+        StarknetBlocksTable::insert(&tx, block, None).unwrap();
+        tx.commit().unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn follows_writer_head() {
+        let storage = Storage::in_memory().unwrap();
+        let genesis = block(0);
+        insert(&storage, &genesis);
+
+        let state = Arc::new(State::default());
+        let starting = (genesis.hash, genesis.number).into();
+        *state.status.write().await = SyncStatus::Status(syncing::Status {
+            starting,
+            current: starting,
+            highest: starting,
+        });
+
+        // Nothing listens here, so the latest block is never known from the sequencer.
+        let sequencer = sequencer::Client::with_url("http://127.0.0.1:1".parse().unwrap()).unwrap();
+        let _jh = tokio::spawn(follow(
+            storage.clone(),
+            Chain::Testnet,
+            sequencer,
+            state.clone(),
+            PendingData::default(),
+            ResponseCache::default(),
+            Duration::from_millis(10),
+            None,
+            std::future::pending(),
+        ));
+
+        let next = block(1);
+        insert(&storage, &next);
+
+        let expected = (next.hash, next.number).into();
+        tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                if let SyncStatus::Status(status) = &*state.status.read().await {
+                    if status.current == expected {
+                        assert_eq!(status.highest, expected);
+                        assert_eq!(status.starting, starting);
+                        break;
+                    }
+                }
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+    }
+}
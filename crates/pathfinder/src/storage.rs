@@ -1,24 +1,32 @@
 //! Local storage.
 //!
-//! Currently this consists of a Sqlite backend implementation.
+//! Currently this consists of a Sqlite backend implementation. The sync and Merkle tree code, and
+//! the contract state reads of the RPC API, access it through the [StorageBackend] trait, which is
+//! also implemented by [InMemoryStorage]. The other RPC methods read the tables directly.
 
+mod backend;
 mod contract;
 mod ethereum;
 #[cfg(test)]
 pub(crate) mod fixtures;
+pub mod maintenance;
 pub mod merkle_tree;
+mod message;
 mod schema;
 mod state;
 
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
+pub use backend::{InMemoryStorage, StorageBackend};
 pub use contract::{ContractCodeTable, ContractsTable};
 pub use ethereum::{EthereumBlocksTable, EthereumTransactionsTable};
+pub use message::{L1MessageKind, L1MessagesTable, L2Message, L2MessagesTable, MessageDirection};
 pub use state::{
-    CanonicalBlocksTable, ContractsStateTable, EventFilterError, L1StateTable, L1TableBlockId,
-    RefsTable, StarknetBlock, StarknetBlocksBlockId, StarknetBlocksTable, StarknetEmittedEvent,
-    StarknetEventFilter, StarknetEventsTable, StarknetStateUpdatesTable, StarknetTransactionsTable,
+    CanonicalBlocksTable, ChainSpecTable, ContractsStateTable, EventFilterError, L1StateTable,
+    L1TableBlockId, RefsTable, StarknetBlock, StarknetBlocksBlockId, StarknetBlocksTable,
+    StarknetEmittedEvent, StarknetEventFilter, StarknetEventsTable, StarknetStateUpdatesTable,
+    StarknetTransactionsTable,
 };
 
 use anyhow::Context;
@@ -95,6 +103,49 @@ impl Storage {
         Ok(storage)
     }
 
+    /// Opens an existing database read-only, for use by a replica which shares the database of
+    /// a writer node.
+    ///
+    /// Nothing is migrated, so the writer must already have migrated the database to the schema
+    /// version of this application. The database must use WAL journaling so that the writer is
+    /// not blocked by readers.
+    pub fn open_read_only(database_path: PathBuf) -> anyhow::Result<Self> {
+        use rusqlite::OpenFlags;
+
+        let manager = SqliteConnectionManager::file(&database_path).with_flags(
+            OpenFlags::SQLITE_OPEN_READ_ONLY
+                | OpenFlags::SQLITE_OPEN_URI
+                | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        );
+        let pool = Pool::builder().build(manager)?;
+
+        let conn = pool.get()?;
+        let journal_mode = conn
+            .query_row("PRAGMA journal_mode", [], |row| row.get::<_, String>(0))
+            .context("Reading journal mode")?;
+        anyhow::ensure!(
+            journal_mode.eq_ignore_ascii_case("wal"),
+            "Read-only access requires a database in WAL journal mode, but it is in {} mode",
+            journal_mode
+        );
+
+        let version = schema_version(&conn)?;
+        let expected = schema::migrations().len();
+        anyhow::ensure!(
+            version == expected,
+            "Database version does not match this application ({} != {}), it must be migrated by the writer node first",
+            version,
+            expected
+        );
+
+        let inner = Inner {
+            database_path: Arc::new(database_path),
+            pool,
+        };
+
+        Ok(Storage(inner))
+    }
+
     /// Returns a new Sqlite [Connection] to the database.
     pub fn connection(&self) -> anyhow::Result<PooledConnection> {
         let conn = self.0.pool.get()?;
@@ -294,6 +345,7 @@ pub(crate) mod test_utils {
             }
             _ => transaction::Transaction::Declare(DeclareTransaction {
                 class_hash: ClassHash(StarkHash::from_hex_str(&"a".repeat(i + 3)).unwrap()),
+                compiled_class_hash: None,
                 max_fee: Fee(H128::zero()),
                 nonce: TransactionNonce(StarkHash::from_hex_str(&"b".repeat(i + 3)).unwrap()),
                 sender_address: ContractAddress::new_or_panic(
@@ -443,6 +495,32 @@ mod tests {
         migrate_database(&mut conn).unwrap_err();
     }
 
+    #[test]
+    fn read_only() {
+        let db_file = tempfile::NamedTempFile::new().unwrap();
+        let path = PathBuf::from(db_file.path());
+
+        // Rollback journaling is rejected.
+        Storage::migrate(path.clone(), JournalMode::Rollback).unwrap();
+        Storage::open_read_only(path.clone()).unwrap_err();
+
+        let writer = Storage::migrate(path.clone(), JournalMode::WAL).unwrap();
+        let reader = Storage::open_read_only(path).unwrap();
+
+        let conn = reader.connection().unwrap();
+        assert_eq!(schema_version(&conn).unwrap(), schema::migrations().len());
+        conn.execute("CREATE TABLE foo(id INTEGER PRIMARY KEY)", [])
+            .unwrap_err();
+
+        // Older schema versions are rejected.
+        writer
+            .connection()
+            .unwrap()
+            .pragma_update(None, VERSION_KEY, 1)
+            .unwrap();
+        Storage::open_read_only(reader.path().to_owned()).unwrap_err();
+    }
+
     #[test]
     fn foreign_keys_are_enforced() {
         let conn = rusqlite::Connection::open_in_memory().unwrap();
@@ -0,0 +1,637 @@
+//! The storage operations which the sync and Merkle tree code, and the RPC contract state reads,
+//! depend on.
+//!
+//! [StorageBackend] is implemented by SQLite [transactions](Transaction), which delegate to the
+//! [tables](super), and by [InMemoryStorage] which needs no database at all. The latter is meant
+//! for fast tests and benchmarks, and as a reference for evaluating other backends, such as a
+//! key-value store for the trie nodes.
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+
+use anyhow::Context;
+use rusqlite::Transaction;
+use stark_hash::StarkHash;
+
+use super::merkle_tree::{InMemoryNodeStorage, RcNodeStorage};
+use super::{
+    CanonicalBlocksTable, ContractCodeTable, ContractsStateTable, ContractsTable, L1StateTable,
+    L2Message, L2MessagesTable, RefsTable, StarknetBlock, StarknetBlocksBlockId,
+    StarknetBlocksTable, StarknetStateUpdatesTable, StarknetTransactionsTable,
+};
+use crate::core::{
+    ClassHash, ContractAddress, ContractNonce, ContractRoot, ContractStateHash, EventCommitment,
+    GlobalRoot, StarknetBlockHash, StarknetBlockNumber, TransactionCommitment,
+};
+use crate::ethereum::log::StateUpdateLog;
+use crate::rpc::v01::types::reply::StateUpdate;
+use crate::sequencer::reply::transaction;
+use crate::state::merkle_tree::NodeStorage;
+
+/// A handle to the stored StarkNet state.
+///
+/// Handles are cheap to copy, and all changes made through any copy are visible to the others.
+/// The methods mirror those of the [tables](super) they are named after.
+pub trait StorageBackend: Copy {
+    /// The storage of the global and contract state trees' nodes.
+    type Nodes: NodeStorage;
+
+    /// The nodes of the [global state tree](crate::state::state_tree::GlobalStateTree).
+    fn global_tree_nodes(self) -> anyhow::Result<Self::Nodes>;
+    /// The nodes of all [contract state trees](crate::state::state_tree::ContractsStateTree).
+    fn contract_tree_nodes(self) -> anyhow::Result<Self::Nodes>;
+
+    /// See [StarknetBlocksTable::get].
+    fn block(self, block: StarknetBlocksBlockId) -> anyhow::Result<Option<StarknetBlock>>;
+    /// See [StarknetBlocksTable::get_root].
+    fn block_root(self, block: StarknetBlocksBlockId) -> anyhow::Result<Option<GlobalRoot>>;
+    /// See [StarknetBlocksTable::get_latest_number].
+    fn latest_block_number(self) -> anyhow::Result<Option<StarknetBlockNumber>>;
+    /// See [StarknetBlocksTable::insert].
+    fn insert_block(self, block: &StarknetBlock, version: Option<&str>) -> anyhow::Result<()>;
+    /// See [StarknetBlocksTable::get_commitments].
+    fn block_commitments(
+        self,
+        number: StarknetBlockNumber,
+    ) -> anyhow::Result<Option<(TransactionCommitment, EventCommitment)>>;
+    /// See [StarknetBlocksTable::get_starknet_version].
+    fn block_starknet_version(self, number: StarknetBlockNumber) -> anyhow::Result<Option<String>>;
+    /// See [StarknetBlocksTable::update_commitments].
+    fn update_block_commitments(
+        self,
+        number: StarknetBlockNumber,
+        transaction_commitment: TransactionCommitment,
+        event_commitment: EventCommitment,
+    ) -> anyhow::Result<()>;
+    /// See [CanonicalBlocksTable::insert].
+    fn insert_canonical_block(
+        self,
+        number: StarknetBlockNumber,
+        hash: StarknetBlockHash,
+    ) -> anyhow::Result<()>;
+    /// Deletes all blocks from `reorg_tail` onwards, along with their transactions and state
+    /// updates. See [StarknetBlocksTable::reorg] and [CanonicalBlocksTable::reorg].
+    fn reorg_blocks(self, reorg_tail: StarknetBlockNumber) -> anyhow::Result<()>;
+
+    /// See [StarknetTransactionsTable::upsert].
+    fn upsert_transactions(
+        self,
+        block_hash: StarknetBlockHash,
+        block_number: StarknetBlockNumber,
+        transaction_data: &[(transaction::Transaction, transaction::Receipt)],
+    ) -> anyhow::Result<()>;
+
+    /// See [StarknetStateUpdatesTable::insert].
+    fn insert_state_update(
+        self,
+        block_hash: StarknetBlockHash,
+        state_update: &StateUpdate,
+    ) -> anyhow::Result<()>;
+
+    /// See [ContractsStateTable::upsert].
+    fn upsert_contract_state(
+        self,
+        state_hash: ContractStateHash,
+        class_hash: ClassHash,
+        root: ContractRoot,
+        nonce: ContractNonce,
+    ) -> anyhow::Result<()>;
+    /// See [ContractsStateTable::get_root_and_nonce].
+    fn contract_root_and_nonce(
+        self,
+        state_hash: ContractStateHash,
+    ) -> anyhow::Result<Option<(ContractRoot, ContractNonce)>>;
+    /// See [ContractsStateTable::get_class_hash].
+    fn contract_state_class_hash(
+        self,
+        state_hash: ContractStateHash,
+    ) -> anyhow::Result<Option<ClassHash>>;
+
+    /// See [ContractsTable::upsert].
+    fn upsert_contract(self, address: ContractAddress, class_hash: ClassHash)
+        -> anyhow::Result<()>;
+    /// See [ContractsTable::get_hash].
+    fn contract_class_hash(self, address: ContractAddress) -> anyhow::Result<Option<ClassHash>>;
+
+    /// See [ContractCodeTable::update_declared_on_if_null].
+    fn update_class_declared_on_if_null(
+        self,
+        class: ClassHash,
+        block: StarknetBlockHash,
+    ) -> anyhow::Result<bool>;
+
+    /// See [L2MessagesTable::insert].
+    fn insert_l2_message(self, message: &L2Message) -> anyhow::Result<()>;
+
+    /// See [L1StateTable::upsert].
+    fn upsert_l1_state(self, update: &StateUpdateLog) -> anyhow::Result<()>;
+    /// See [L1StateTable::get_root].
+    fn l1_root(self, number: StarknetBlockNumber) -> anyhow::Result<Option<GlobalRoot>>;
+    /// See [L1StateTable::reorg].
+    fn reorg_l1_state(self, reorg_tail: StarknetBlockNumber) -> anyhow::Result<()>;
+
+    /// See [RefsTable::get_l1_l2_head].
+    fn l1_l2_head(self) -> anyhow::Result<Option<StarknetBlockNumber>>;
+    /// See [RefsTable::set_l1_l2_head].
+    fn set_l1_l2_head(self, head: Option<StarknetBlockNumber>) -> anyhow::Result<()>;
+}
+
+/// The default backend, changes are persisted once the transaction is committed.
+impl<'tx> StorageBackend for &'tx Transaction<'tx> {
+    type Nodes = RcNodeStorage<'tx, 'static>;
+
+    fn global_tree_nodes(self) -> anyhow::Result<Self::Nodes> {
+        RcNodeStorage::open("tree_global", self)
+    }
+
+    fn contract_tree_nodes(self) -> anyhow::Result<Self::Nodes> {
+        RcNodeStorage::open("tree_contracts", self)
+    }
+
+    fn block(self, block: StarknetBlocksBlockId) -> anyhow::Result<Option<StarknetBlock>> {
+        StarknetBlocksTable::get(self, block)
+    }
+
+    fn block_root(self, block: StarknetBlocksBlockId) -> anyhow::Result<Option<GlobalRoot>> {
+        StarknetBlocksTable::get_root(self, block)
+    }
+
+    fn latest_block_number(self) -> anyhow::Result<Option<StarknetBlockNumber>> {
+        StarknetBlocksTable::get_latest_number(self)
+    }
+
+    fn insert_block(self, block: &StarknetBlock, version: Option<&str>) -> anyhow::Result<()> {
+        StarknetBlocksTable::insert(self, block, version)
+    }
+
+    fn block_commitments(
+        self,
+        number: StarknetBlockNumber,
+    ) -> anyhow::Result<Option<(TransactionCommitment, EventCommitment)>> {
+        StarknetBlocksTable::get_commitments(self, number)
+    }
+
+    fn block_starknet_version(self, number: StarknetBlockNumber) -> anyhow::Result<Option<String>> {
+        StarknetBlocksTable::get_starknet_version(self, number)
+    }
+
+    fn update_block_commitments(
+        self,
+        number: StarknetBlockNumber,
+        transaction_commitment: TransactionCommitment,
+        event_commitment: EventCommitment,
+    ) -> anyhow::Result<()> {
+        StarknetBlocksTable::update_commitments(
+            self,
+            number,
+            transaction_commitment,
+            event_commitment,
+        )
+    }
+
+    fn insert_canonical_block(
+        self,
+        number: StarknetBlockNumber,
+        hash: StarknetBlockHash,
+    ) -> anyhow::Result<()> {
+        CanonicalBlocksTable::insert(self, number, hash)
+    }
+
+    fn reorg_blocks(self, reorg_tail: StarknetBlockNumber) -> anyhow::Result<()> {
+        CanonicalBlocksTable::reorg(self, reorg_tail)
+            .context("Delete canonical blocks from database")?;
+        StarknetBlocksTable::reorg(self, reorg_tail).context("Delete L2 blocks from database")
+    }
+
+    fn upsert_transactions(
+        self,
+        block_hash: StarknetBlockHash,
+        block_number: StarknetBlockNumber,
+        transaction_data: &[(transaction::Transaction, transaction::Receipt)],
+    ) -> anyhow::Result<()> {
+        StarknetTransactionsTable::upsert(self, block_hash, block_number, transaction_data)
+    }
+
+    fn insert_state_update(
+        self,
+        block_hash: StarknetBlockHash,
+        state_update: &StateUpdate,
+    ) -> anyhow::Result<()> {
+        StarknetStateUpdatesTable::insert(self, block_hash, state_update)
+    }
+
+    fn upsert_contract_state(
+        self,
+        state_hash: ContractStateHash,
+        class_hash: ClassHash,
+        root: ContractRoot,
+        nonce: ContractNonce,
+    ) -> anyhow::Result<()> {
+        ContractsStateTable::upsert(self, state_hash, class_hash, root, nonce)
+    }
+
+    fn contract_root_and_nonce(
+        self,
+        state_hash: ContractStateHash,
+    ) -> anyhow::Result<Option<(ContractRoot, ContractNonce)>> {
+        ContractsStateTable::get_root_and_nonce(self, state_hash)
+    }
+
+    fn contract_state_class_hash(
+        self,
+        state_hash: ContractStateHash,
+    ) -> anyhow::Result<Option<ClassHash>> {
+        ContractsStateTable::get_class_hash(self, state_hash)
+    }
+
+    fn upsert_contract(
+        self,
+        address: ContractAddress,
+        class_hash: ClassHash,
+    ) -> anyhow::Result<()> {
+        ContractsTable::upsert(self, address, class_hash)
+    }
+
+    fn contract_class_hash(self, address: ContractAddress) -> anyhow::Result<Option<ClassHash>> {
+        ContractsTable::get_hash(self, address)
+    }
+
+    fn update_class_declared_on_if_null(
+        self,
+        class: ClassHash,
+        block: StarknetBlockHash,
+    ) -> anyhow::Result<bool> {
+        ContractCodeTable::update_declared_on_if_null(self, class, block)
+    }
+
+    fn insert_l2_message(self, message: &L2Message) -> anyhow::Result<()> {
+        L2MessagesTable::insert(self, message)
+    }
+
+    fn upsert_l1_state(self, update: &StateUpdateLog) -> anyhow::Result<()> {
+        L1StateTable::upsert(self, update)
+    }
+
+    fn l1_root(self, number: StarknetBlockNumber) -> anyhow::Result<Option<GlobalRoot>> {
+        L1StateTable::get_root(self, number.into())
+    }
+
+    fn reorg_l1_state(self, reorg_tail: StarknetBlockNumber) -> anyhow::Result<()> {
+        L1StateTable::reorg(self, reorg_tail)
+    }
+
+    fn l1_l2_head(self) -> anyhow::Result<Option<StarknetBlockNumber>> {
+        RefsTable::get_l1_l2_head(self)
+    }
+
+    fn set_l1_l2_head(self, head: Option<StarknetBlockNumber>) -> anyhow::Result<()> {
+        RefsTable::set_l1_l2_head(self, head)
+    }
+}
+
+/// A [StorageBackend] which keeps everything in memory, without any database.
+///
+/// It has the same semantics as the SQLite backend, except that classes are not stored: any
+/// class can be marked as declared.
+#[derive(Default)]
+pub struct InMemoryStorage {
+    global_nodes: InMemoryNodeStorage,
+    contract_nodes: InMemoryNodeStorage,
+    tables: RefCell<Tables>,
+}
+
+#[derive(Default)]
+struct Tables {
+    /// Blocks by number, along with their version and commitments.
+    blocks: BTreeMap<u64, StoredBlock>,
+    canonical_blocks: BTreeMap<u64, StarknetBlockHash>,
+    /// Transactions and receipts by block hash.
+    transactions: HashMap<StarkHash, Vec<(transaction::Transaction, transaction::Receipt)>>,
+    /// State updates by block hash.
+    state_updates: HashMap<StarkHash, StateUpdate>,
+    /// Contract state pre-images by state hash.
+    contract_states: HashMap<StarkHash, (ClassHash, ContractRoot, ContractNonce)>,
+    contracts: HashMap<ContractAddress, ClassHash>,
+    /// The block in which each class was declared.
+    declared_on: HashMap<ClassHash, StarknetBlockHash>,
+    l2_messages: Vec<L2Message>,
+    /// Global roots confirmed on L1 by block number.
+    l1_roots: BTreeMap<u64, GlobalRoot>,
+    l1_l2_head: Option<StarknetBlockNumber>,
+}
+
+struct StoredBlock {
+    block: StarknetBlock,
+    version: Option<String>,
+    commitments: Option<(TransactionCommitment, EventCommitment)>,
+}
+
+impl Tables {
+    fn block(&self, block: StarknetBlocksBlockId) -> Option<&StarknetBlock> {
+        let stored = match block {
+            StarknetBlocksBlockId::Number(number) => self.blocks.get(&number.get()),
+            StarknetBlocksBlockId::Hash(hash) => self
+                .blocks
+                .values()
+                .find(|stored| stored.block.hash == hash),
+            StarknetBlocksBlockId::Latest => self.blocks.values().next_back(),
+        };
+
+        stored.map(|stored| &stored.block)
+    }
+}
+
+impl StorageBackend for &InMemoryStorage {
+    type Nodes = InMemoryNodeStorage;
+
+    fn global_tree_nodes(self) -> anyhow::Result<Self::Nodes> {
+        Ok(self.global_nodes.clone())
+    }
+
+    fn contract_tree_nodes(self) -> anyhow::Result<Self::Nodes> {
+        Ok(self.contract_nodes.clone())
+    }
+
+    fn block(self, block: StarknetBlocksBlockId) -> anyhow::Result<Option<StarknetBlock>> {
+        Ok(self.tables.borrow().block(block).cloned())
+    }
+
+    fn block_root(self, block: StarknetBlocksBlockId) -> anyhow::Result<Option<GlobalRoot>> {
+        Ok(self.tables.borrow().block(block).map(|block| block.root))
+    }
+
+    fn latest_block_number(self) -> anyhow::Result<Option<StarknetBlockNumber>> {
+        Ok(self
+            .tables
+            .borrow()
+            .block(StarknetBlocksBlockId::Latest)
+            .map(|block| block.number))
+    }
+
+    fn insert_block(self, block: &StarknetBlock, version: Option<&str>) -> anyhow::Result<()> {
+        let mut tables = self.tables.borrow_mut();
+        anyhow::ensure!(
+            !tables.blocks.contains_key(&block.number.get()),
+            "Block {} already exists",
+            block.number.get()
+        );
+
+        tables.blocks.insert(
+            block.number.get(),
+            StoredBlock {
+                block: block.clone(),
+                version: version.map(ToOwned::to_owned),
+                commitments: None,
+            },
+        );
+
+        Ok(())
+    }
+
+    fn block_commitments(
+        self,
+        number: StarknetBlockNumber,
+    ) -> anyhow::Result<Option<(TransactionCommitment, EventCommitment)>> {
+        Ok(self
+            .tables
+            .borrow()
+            .blocks
+            .get(&number.get())
+            .and_then(|stored| stored.commitments))
+    }
+
+    fn block_starknet_version(self, number: StarknetBlockNumber) -> anyhow::Result<Option<String>> {
+        Ok(self
+            .tables
+            .borrow()
+            .blocks
+            .get(&number.get())
+            .and_then(|stored| stored.version.clone()))
+    }
+
+    fn update_block_commitments(
+        self,
+        number: StarknetBlockNumber,
+        transaction_commitment: TransactionCommitment,
+        event_commitment: EventCommitment,
+    ) -> anyhow::Result<()> {
+        if let Some(stored) = self.tables.borrow_mut().blocks.get_mut(&number.get()) {
+            stored.commitments = Some((transaction_commitment, event_commitment));
+        }
+
+        Ok(())
+    }
+
+    fn insert_canonical_block(
+        self,
+        number: StarknetBlockNumber,
+        hash: StarknetBlockHash,
+    ) -> anyhow::Result<()> {
+        let previous = self
+            .tables
+            .borrow_mut()
+            .canonical_blocks
+            .insert(number.get(), hash);
+        anyhow::ensure!(
+            previous.is_none(),
+            "Canonical block {} already exists",
+            number.get()
+        );
+
+        Ok(())
+    }
+
+    fn reorg_blocks(self, reorg_tail: StarknetBlockNumber) -> anyhow::Result<()> {
+        let mut tables = self.tables.borrow_mut();
+
+        tables
+            .canonical_blocks
+            .retain(|number, _| *number < reorg_tail.get());
+        let removed = tables.blocks.split_off(&reorg_tail.get());
+        for stored in removed.values() {
+            tables.transactions.remove(&stored.block.hash.0);
+            tables.state_updates.remove(&stored.block.hash.0);
+        }
+        // SQLite removes them along with their canonical block.
+        tables
+            .l2_messages
+            .retain(|message| message.block_number < reorg_tail);
+
+        Ok(())
+    }
+
+    fn upsert_transactions(
+        self,
+        block_hash: StarknetBlockHash,
+        _block_number: StarknetBlockNumber,
+        transaction_data: &[(transaction::Transaction, transaction::Receipt)],
+    ) -> anyhow::Result<()> {
+        if !transaction_data.is_empty() {
+            self.tables
+                .borrow_mut()
+                .transactions
+                .insert(block_hash.0, transaction_data.to_vec());
+        }
+
+        Ok(())
+    }
+
+    fn insert_state_update(
+        self,
+        block_hash: StarknetBlockHash,
+        state_update: &StateUpdate,
+    ) -> anyhow::Result<()> {
+        self.tables
+            .borrow_mut()
+            .state_updates
+            .insert(block_hash.0, state_update.clone());
+
+        Ok(())
+    }
+
+    fn upsert_contract_state(
+        self,
+        state_hash: ContractStateHash,
+        class_hash: ClassHash,
+        root: ContractRoot,
+        nonce: ContractNonce,
+    ) -> anyhow::Result<()> {
+        self.tables
+            .borrow_mut()
+            .contract_states
+            .entry(state_hash.0)
+            .or_insert((class_hash, root, nonce));
+
+        Ok(())
+    }
+
+    fn contract_root_and_nonce(
+        self,
+        state_hash: ContractStateHash,
+    ) -> anyhow::Result<Option<(ContractRoot, ContractNonce)>> {
+        Ok(self
+            .tables
+            .borrow()
+            .contract_states
+            .get(&state_hash.0)
+            .map(|(_, root, nonce)| (*root, *nonce)))
+    }
+
+    fn contract_state_class_hash(
+        self,
+        state_hash: ContractStateHash,
+    ) -> anyhow::Result<Option<ClassHash>> {
+        Ok(self
+            .tables
+            .borrow()
+            .contract_states
+            .get(&state_hash.0)
+            .map(|(class_hash, _, _)| *class_hash))
+    }
+
+    fn upsert_contract(
+        self,
+        address: ContractAddress,
+        class_hash: ClassHash,
+    ) -> anyhow::Result<()> {
+        self.tables
+            .borrow_mut()
+            .contracts
+            .insert(address, class_hash);
+
+        Ok(())
+    }
+
+    fn contract_class_hash(self, address: ContractAddress) -> anyhow::Result<Option<ClassHash>> {
+        Ok(self.tables.borrow().contracts.get(&address).copied())
+    }
+
+    fn update_class_declared_on_if_null(
+        self,
+        class: ClassHash,
+        block: StarknetBlockHash,
+    ) -> anyhow::Result<bool> {
+        let mut tables = self.tables.borrow_mut();
+        if tables.declared_on.contains_key(&class) {
+            return Ok(false);
+        }
+
+        tables.declared_on.insert(class, block);
+        Ok(true)
+    }
+
+    fn insert_l2_message(self, message: &L2Message) -> anyhow::Result<()> {
+        self.tables.borrow_mut().l2_messages.push(message.clone());
+
+        Ok(())
+    }
+
+    fn upsert_l1_state(self, update: &StateUpdateLog) -> anyhow::Result<()> {
+        self.tables
+            .borrow_mut()
+            .l1_roots
+            .insert(update.block_number.get(), update.global_root);
+
+        Ok(())
+    }
+
+    fn l1_root(self, number: StarknetBlockNumber) -> anyhow::Result<Option<GlobalRoot>> {
+        Ok(self.tables.borrow().l1_roots.get(&number.get()).copied())
+    }
+
+    fn reorg_l1_state(self, reorg_tail: StarknetBlockNumber) -> anyhow::Result<()> {
+        self.tables
+            .borrow_mut()
+            .l1_roots
+            .retain(|number, _| *number < reorg_tail.get());
+
+        Ok(())
+    }
+
+    fn l1_l2_head(self) -> anyhow::Result<Option<StarknetBlockNumber>> {
+        Ok(self.tables.borrow().l1_l2_head)
+    }
+
+    fn set_l1_l2_head(self, head: Option<StarknetBlockNumber>) -> anyhow::Result<()> {
+        self.tables.borrow_mut().l1_l2_head = head;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{MessageHash, StarknetTransactionHash};
+    use crate::storage::MessageDirection;
+    use web3::types::H256;
+
+    #[test]
+    fn in_memory_reorg_removes_messages() {
+        let storage = InMemoryStorage::default();
+        for number in 0..3 {
+            let message = L2Message {
+                hash: MessageHash(H256::from_low_u64_be(number)),
+                direction: MessageDirection::L2ToL1,
+                from_address: H256::zero(),
+                to_address: H256::zero(),
+                payload: vec![],
+                block_number: StarknetBlockNumber::new_or_panic(number),
+                transaction_hash: StarknetTransactionHash(StarkHash::from_u64(number)),
+            };
+            (&storage).insert_l2_message(&message).unwrap();
+        }
+
+        (&storage)
+            .reorg_blocks(StarknetBlockNumber::new_or_panic(1))
+            .unwrap();
+
+        let remaining = storage
+            .tables
+            .borrow()
+            .l2_messages
+            .iter()
+            .map(|message| message.block_number)
+            .collect::<Vec<_>>();
+        assert_eq!(remaining, vec![StarknetBlockNumber::GENESIS]);
+    }
+}
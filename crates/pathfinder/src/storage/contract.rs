@@ -1,6 +1,6 @@
-use crate::core::StarknetBlockHash;
+use crate::core::{StarknetBlockHash, StarknetBlockNumber};
 use crate::{
-    core::{ClassHash, ContractAddress, ContractClass},
+    core::{CasmHash, ClassHash, ContractAddress, ContractClass},
     state::{class_hash::extract_program_and_entry_points_by_type, CompressedContract},
 };
 
@@ -14,6 +14,9 @@ use rusqlite::{named_params, Connection, OptionalExtension, Transaction};
 /// - byte code
 /// - ABI
 /// - definition
+/// - [format](crate::state::ClassFormat)
+///
+/// The compiled CASM definitions of Sierra classes are kept in the `casm_definitions` table.
 pub struct ContractCodeTable {}
 
 impl ContractCodeTable {
@@ -42,6 +45,7 @@ impl ContractCodeTable {
             bytecode,
             definition,
             hash,
+            casm: None,
         };
 
         Self::insert_compressed(transaction, &contract)
@@ -53,9 +57,24 @@ impl ContractCodeTable {
     ) -> anyhow::Result<()> {
         // check magics to verify these are zstd compressed files
         let magic = &[0x28, 0xb5, 0x2f, 0xfd];
-        assert_eq!(&contract.abi[..4], magic);
-        assert_eq!(&contract.bytecode[..4], magic);
-        assert_eq!(&contract.definition[..4], magic);
+        anyhow::ensure!(
+            contract.abi.starts_with(magic),
+            "ABI is not zstd compressed"
+        );
+        anyhow::ensure!(
+            contract.bytecode.starts_with(magic),
+            "Bytecode is not zstd compressed"
+        );
+        anyhow::ensure!(
+            contract.definition.starts_with(magic),
+            "Definition is not zstd compressed"
+        );
+        if let Some(casm) = &contract.casm {
+            anyhow::ensure!(
+                casm.definition.starts_with(magic),
+                "Compiled class definition is not zstd compressed"
+            );
+        }
 
         connection.execute(
             r"INSERT INTO contract_code ( hash,  bytecode,  abi,  definition)
@@ -67,9 +86,44 @@ impl ContractCodeTable {
                 ":definition": &contract.definition[..],
             },
         )?;
+
+        // Cairo 0.x classes are stored using the default `class_format`. This keeps the insert
+        // above usable by the schema migrations that predate the column.
+        if let Some(casm) = &contract.casm {
+            connection.execute(
+                "UPDATE contract_code SET class_format = ? WHERE hash = ?",
+                rusqlite::params![contract.format() as i64, contract.hash],
+            )?;
+            connection.execute(
+                r"INSERT INTO casm_definitions ( hash,  compiled_class_hash,  definition)
+                                        VALUES (:hash, :compiled_class_hash, :definition)",
+                named_params! {
+                    ":hash": &contract.hash.0.to_be_bytes()[..],
+                    ":compiled_class_hash": &casm.hash.0.to_be_bytes()[..],
+                    ":definition": &casm.definition[..],
+                },
+            )?;
+        }
+
         Ok(())
     }
 
+    /// Returns the compiled class hash of a Sierra class, or [None] if the class does not exist
+    /// or is a Cairo 0.x class.
+    pub fn get_compiled_class_hash(
+        transaction: &Transaction<'_>,
+        hash: ClassHash,
+    ) -> anyhow::Result<Option<CasmHash>> {
+        transaction
+            .query_row(
+                "SELECT compiled_class_hash FROM casm_definitions WHERE hash = ?",
+                [hash],
+                |row| row.get("compiled_class_hash"),
+            )
+            .optional()
+            .map_err(|e| e.into())
+    }
+
     pub fn update_declared_on_if_null(
         transaction: &Transaction<'_>,
         class: ClassHash,
@@ -87,6 +141,21 @@ impl ContractCodeTable {
         }
     }
 
+    /// Clears `declared_on` of the classes declared in blocks from `reorg_tail` onwards, which
+    /// must be called before deleting those blocks.
+    pub fn reorg_declared_on(
+        transaction: &Transaction<'_>,
+        reorg_tail: StarknetBlockNumber,
+    ) -> anyhow::Result<()> {
+        transaction.execute(
+            "UPDATE contract_code SET declared_on = NULL
+                WHERE declared_on IN (SELECT hash FROM starknet_blocks WHERE number >= ?)",
+            [reorg_tail],
+        )?;
+
+        Ok(())
+    }
+
     pub fn get_class(
         transaction: &Transaction<'_>,
         hash: ClassHash,
@@ -133,6 +202,49 @@ impl ContractCodeTable {
         }))
     }
 
+    /// Returns the class definition exactly as it was downloaded from the sequencer.
+    pub fn get_definition(
+        transaction: &Transaction<'_>,
+        hash: ClassHash,
+    ) -> anyhow::Result<Option<Vec<u8>>> {
+        let definition = transaction
+            .query_row(
+                "SELECT definition FROM contract_code WHERE hash = ?",
+                [hash],
+                |row| row.get::<_, Vec<u8>>(0),
+            )
+            .optional()?;
+
+        definition
+            .map(|definition| {
+                zstd::decode_all(&*definition)
+                    .context("Corruption: invalid compressed column (definition)")
+            })
+            .transpose()
+    }
+
+    /// Returns the compiled CASM definition of a Sierra class, or [None] if the class does not
+    /// exist or is a Cairo 0.x class.
+    pub fn get_casm_definition(
+        transaction: &Transaction<'_>,
+        hash: ClassHash,
+    ) -> anyhow::Result<Option<Vec<u8>>> {
+        let definition = transaction
+            .query_row(
+                "SELECT definition FROM casm_definitions WHERE hash = ?",
+                [hash],
+                |row| row.get::<_, Vec<u8>>(0),
+            )
+            .optional()?;
+
+        definition
+            .map(|definition| {
+                zstd::decode_all(&*definition)
+                    .context("Corruption: invalid compressed column (casm definition)")
+            })
+            .transpose()
+    }
+
     /// Returns true for each [ClassHash] if the class definition already exists in the table.
     pub fn exists(connection: &Connection, classes: &[ClassHash]) -> anyhow::Result<Vec<bool>> {
         let mut stmt = connection.prepare("select 1 from contract_code where hash = ?")?;
@@ -270,6 +382,25 @@ mod tests {
         )
     }
 
+    #[test]
+    fn get_definition() {
+        let storage = Storage::in_memory().unwrap();
+        let mut conn = storage.connection().unwrap();
+        let transaction = conn.transaction().unwrap();
+
+        let (hash, _, _) = setup_class(&transaction);
+
+        let result = ContractCodeTable::get_definition(&transaction, hash).unwrap();
+        assert_eq!(
+            result.as_deref(),
+            Some(&br#"{"abi":{"see":"above"},"program":{"huge":"hash"},"entry_points_by_type":{"this might be a":"hash"}}"#[..])
+        );
+
+        let result =
+            ContractCodeTable::get_definition(&transaction, ClassHash(starkhash!("0456"))).unwrap();
+        assert_eq!(result, None);
+    }
+
     #[test]
     fn contracts_exist() {
         let storage = Storage::in_memory().unwrap();
@@ -284,4 +415,79 @@ mod tests {
 
         assert_eq!(result, expected);
     }
+
+    #[test]
+    fn compiled_class_hash() {
+        use crate::core::CasmHash;
+        use crate::state::{ClassFormat, CompressedCasmClass};
+
+        let storage = Storage::in_memory().unwrap();
+        let mut connection = storage.connection().unwrap();
+        let transaction = connection.transaction().unwrap();
+
+        let (cairo, _, _) = setup_class(&transaction);
+
+        let compress = |data: &[u8]| zstd::bulk::compress(data, 10).unwrap();
+        let sierra = CompressedContract {
+            abi: compress(b"abi"),
+            bytecode: compress(b"sierra program"),
+            definition: compress(b"definition"),
+            hash: ClassHash(starkhash!("0456")),
+            casm: Some(CompressedCasmClass {
+                definition: compress(b"casm definition"),
+                hash: CasmHash(starkhash!("0789")),
+            }),
+        };
+        ContractCodeTable::insert_compressed(&transaction, &sierra).unwrap();
+
+        let format = |hash: ClassHash| -> i64 {
+            transaction
+                .query_row(
+                    "SELECT class_format FROM contract_code WHERE hash = ?",
+                    [hash],
+                    |row| row.get(0),
+                )
+                .unwrap()
+        };
+        assert_eq!(format(cairo), ClassFormat::Cairo as i64);
+        assert_eq!(format(sierra.hash), ClassFormat::Sierra as i64);
+
+        let result = ContractCodeTable::get_compiled_class_hash(&transaction, sierra.hash).unwrap();
+        assert_eq!(result, Some(CasmHash(starkhash!("0789"))));
+
+        let result = ContractCodeTable::get_compiled_class_hash(&transaction, cairo).unwrap();
+        assert_eq!(result, None);
+
+        let result = ContractCodeTable::get_casm_definition(&transaction, sierra.hash).unwrap();
+        assert_eq!(result.as_deref(), Some(&b"casm definition"[..]));
+
+        let result = ContractCodeTable::get_casm_definition(&transaction, cairo).unwrap();
+        assert_eq!(result, None);
+    }
+
+    #[test]
+    fn uncompressed_casm_is_rejected() {
+        use crate::core::CasmHash;
+        use crate::state::CompressedCasmClass;
+
+        let storage = Storage::in_memory().unwrap();
+        let mut connection = storage.connection().unwrap();
+        let transaction = connection.transaction().unwrap();
+
+        let compress = |data: &[u8]| zstd::bulk::compress(data, 10).unwrap();
+        let sierra = CompressedContract {
+            abi: compress(b"abi"),
+            bytecode: compress(b"sierra program"),
+            definition: compress(b"definition"),
+            hash: ClassHash(starkhash!("0456")),
+            casm: Some(CompressedCasmClass {
+                definition: b"casm definition".to_vec(),
+                hash: CasmHash(starkhash!("0789")),
+            }),
+        };
+        ContractCodeTable::insert_compressed(&transaction, &sierra).unwrap_err();
+
+        let result = ContractCodeTable::exists(&transaction, &[sierra.hash]).unwrap();
+        assert_eq!(result, vec![false]);
+    }
 }
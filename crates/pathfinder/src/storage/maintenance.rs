@@ -0,0 +1,634 @@
+//! Offline maintenance of the database, used by the `pathfinder db` commands.
+//!
+//! None of these operations may run while a node is using the database. Nodes hold a
+//! [shared](DatabaseLock::shared) lock for as long as they run, while maintenance takes an
+//! [exclusive](DatabaseLock::exclusive) one. Either fails while the other is held.
+//!
+//! The lock is held on a separate `<database>.lock` file instead of the database itself, so that
+//! it does not interfere with the database's own transactions. Since it is an Sqlite lock it is
+//! released by the operating system if the process holding it dies.
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use anyhow::Context;
+use rusqlite::{Connection, ErrorCode, OpenFlags, OptionalExtension, Transaction};
+use stark_hash::{stark_hash, StarkHash};
+
+use super::merkle_tree::PersistedNode;
+use crate::core::{ClassHash, ContractNonce, ContractRoot, ContractStateHash, GlobalRoot};
+use crate::state::calculate_contract_state_hash;
+
+/// Prevents maintenance and nodes from using the database at the same time.
+///
+/// The lock is released when this is dropped.
+pub struct DatabaseLock(Connection);
+
+impl DatabaseLock {
+    /// Acquires the lock held by a running node. Any number of nodes (e.g. a writer and its
+    /// read-only replicas) may hold it at the same time.
+    ///
+    /// Fails without waiting if a maintenance command is running.
+    pub fn shared(database_path: &Path) -> anyhow::Result<Self> {
+        let connection = Self::open(database_path)?;
+
+        // A read transaction holds a SHARED lock on the file until it is closed.
+        connection
+            .execute_batch("CREATE TABLE IF NOT EXISTS lock (id INTEGER); BEGIN")
+            .and_then(|_| {
+                connection.query_row("SELECT count(1) FROM lock", [], |row| row.get::<_, i64>(0))
+            })
+            .map_err(|e| match is_busy(&e) {
+                true => {
+                    anyhow::anyhow!("The database is locked by a running `pathfinder db` command")
+                }
+                false => anyhow::Error::new(e).context("Acquiring database lock"),
+            })?;
+
+        Ok(Self(connection))
+    }
+
+    /// Acquires the lock for maintenance, excluding any node or other maintenance command.
+    ///
+    /// Fails without waiting if the database is in use.
+    pub fn exclusive(database_path: &Path) -> anyhow::Result<Self> {
+        let connection = Self::open(database_path)?;
+        connection
+            .execute_batch("BEGIN EXCLUSIVE")
+            .map_err(|e| match is_busy(&e) {
+                true => anyhow::anyhow!("The database is in use, stop any node using it first"),
+                false => anyhow::Error::new(e).context("Acquiring database lock"),
+            })?;
+
+        Ok(Self(connection))
+    }
+
+    fn open(database_path: &Path) -> anyhow::Result<Connection> {
+        let path = lock_path(database_path);
+        let connection = Connection::open(&path)
+            .with_context(|| format!("Opening lock file {}", path.display()))?;
+        connection
+            .busy_timeout(Duration::ZERO)
+            .context("Setting busy timeout")?;
+
+        Ok(connection)
+    }
+}
+
+fn lock_path(database_path: &Path) -> PathBuf {
+    let mut path = database_path.as_os_str().to_owned();
+    path.push(".lock");
+    path.into()
+}
+
+fn is_busy(e: &rusqlite::Error) -> bool {
+    matches!(e, rusqlite::Error::SqliteFailure(e, _) if e.code == ErrorCode::DatabaseBusy)
+}
+
+/// Opens an existing database for maintenance.
+///
+/// Unlike [Storage::migrate](super::Storage::migrate) this neither creates nor migrates the
+/// database.
+pub fn open(database_path: &Path) -> anyhow::Result<Connection> {
+    let connection = Connection::open_with_flags(
+        database_path,
+        OpenFlags::SQLITE_OPEN_READ_WRITE | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+    )
+    .with_context(|| format!("Opening database {}", database_path.display()))?;
+    super::enable_foreign_keys(&connection).context("Enabling foreign keys")?;
+
+    Ok(connection)
+}
+
+/// Row count and size of a table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableStats {
+    pub name: String,
+    pub rows: u64,
+    /// Size in bytes of the table and its indices.
+    ///
+    /// [None] if Sqlite was built without the `dbstat` virtual table.
+    pub size: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Stats {
+    pub schema_version: usize,
+    /// Size of the database in bytes, excluding the WAL.
+    pub size: u64,
+    pub tables: Vec<TableStats>,
+    /// Number of nodes in the global and contract state tries.
+    pub trie_nodes: u64,
+    /// Size in bytes of the full-text search index of the event keys.
+    pub event_index_size: Option<u64>,
+}
+
+/// Name of the event keys full-text search table, whose data is kept in shadow tables
+/// sharing this prefix.
+const EVENT_INDEX: &str = "starknet_events_keys";
+
+pub fn stats(connection: &Connection) -> anyhow::Result<Stats> {
+    let schema_version = super::schema_version(connection)?;
+    let size = connection
+        .query_row(
+            "SELECT page_count * page_size FROM pragma_page_count, pragma_page_size",
+            [],
+            |row| row.get(0),
+        )
+        .context("Querying database size")?;
+
+    let sizes = table_sizes(connection)?;
+
+    let mut stmt = connection
+        .prepare(
+            "SELECT name FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%' ORDER BY name",
+        )
+        .context("Preparing table query")?;
+    let names = stmt
+        .query_map([], |row| row.get::<_, String>(0))
+        .context("Querying tables")?
+        .collect::<Result<Vec<_>, _>>()
+        .context("Iterating over tables")?;
+
+    let tables = names
+        .into_iter()
+        .map(|name| {
+            let rows = connection
+                .query_row(&format!("SELECT count(1) FROM \"{}\"", name), [], |row| {
+                    row.get(0)
+                })
+                .with_context(|| format!("Counting rows of {}", name))?;
+            let size = sizes.as_ref().map(|s| s.get(&name).copied().unwrap_or(0));
+
+            Ok(TableStats { name, rows, size })
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    let trie_nodes = tables
+        .iter()
+        .filter(|t| t.name == "tree_global" || t.name == "tree_contracts")
+        .map(|t| t.rows)
+        .sum();
+
+    let event_index_size = sizes.as_ref().map(|s| {
+        s.iter()
+            .filter(|(name, _)| name.starts_with(EVENT_INDEX))
+            .map(|(_, size)| size)
+            .sum()
+    });
+
+    Ok(Stats {
+        schema_version,
+        size,
+        tables,
+        trie_nodes,
+        event_index_size,
+    })
+}
+
+/// Returns the size of each table including its indices, or [None] if `dbstat` is not available.
+fn table_sizes(connection: &Connection) -> anyhow::Result<Option<HashMap<String, u64>>> {
+    let mut stmt = match connection.prepare(
+        "SELECT m.tbl_name, sum(s.pgsize) FROM dbstat s JOIN sqlite_master m ON s.name = m.name GROUP BY m.tbl_name",
+    ) {
+        Ok(stmt) => stmt,
+        Err(e) => {
+            tracing::debug!(reason=%e, "dbstat is not available");
+            return Ok(None);
+        }
+    };
+
+    let sizes = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+        .context("Querying table sizes")?
+        .collect::<Result<HashMap<_, _>, _>>()
+        .context("Iterating over table sizes")?;
+
+    Ok(Some(sizes))
+}
+
+/// Fails unless the database is at the latest schema version, which operations on the tables
+/// expect.
+pub fn ensure_migrated(connection: &Connection) -> anyhow::Result<()> {
+    let version = super::schema_version(connection)?;
+    let latest = super::schema::migrations().len();
+    anyhow::ensure!(
+        version == latest,
+        "Database version {} is not the latest ({}), migrate it first",
+        version,
+        latest
+    );
+
+    Ok(())
+}
+
+/// Outcome of [check].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct CheckReport {
+    pub blocks_checked: usize,
+    pub contract_states_checked: usize,
+    /// Human readable description of each inconsistency found.
+    pub problems: Vec<String>,
+}
+
+/// Checks the consistency of the state tries.
+///
+/// For a random sample of `samples` blocks and contract states, their tries are walked down to the
+/// leaves and every node must exist and hash to its key. Contract state hashes are recomputed from
+/// their preimage.
+///
+/// All trie nodes are checked to have a reference count of at least the number of nodes
+/// referencing them. Roots are counted once for every commit, so a higher count is expected.
+pub fn check(transaction: &Transaction<'_>, samples: usize) -> anyhow::Result<CheckReport> {
+    ensure_migrated(transaction)?;
+
+    let mut report = CheckReport::default();
+
+    let mut stmt = transaction
+        .prepare("SELECT number, root FROM starknet_blocks ORDER BY random() LIMIT ?")
+        .context("Preparing block query")?;
+    let blocks = stmt
+        .query_map([samples], |row| {
+            Ok((row.get::<_, u64>(0)?, row.get::<_, GlobalRoot>(1)?))
+        })
+        .context("Querying blocks")?;
+    let mut verified = HashSet::new();
+    for block in blocks {
+        let (number, root) = block.context("Iterating over blocks")?;
+        if let Some(problem) = check_root(transaction, "tree_global", root.0, &mut verified)? {
+            report
+                .problems
+                .push(format!("Global root of block {}: {}", number, problem));
+        }
+        report.blocks_checked += 1;
+    }
+
+    let mut stmt = transaction
+        .prepare(
+            "SELECT state_hash, hash, root, nonce FROM contract_states ORDER BY random() LIMIT ?",
+        )
+        .context("Preparing contract state query")?;
+    let states = stmt
+        .query_map([samples], |row| {
+            Ok((
+                row.get::<_, ContractStateHash>(0)?,
+                row.get::<_, ClassHash>(1)?,
+                row.get::<_, ContractRoot>(2)?,
+                row.get::<_, ContractNonce>(3)?,
+            ))
+        })
+        .context("Querying contract states")?;
+    let mut verified = HashSet::new();
+    for state in states {
+        let (state_hash, class_hash, root, nonce) =
+            state.context("Iterating over contract states")?;
+
+        let expected = calculate_contract_state_hash(class_hash, root, nonce);
+        if expected != state_hash {
+            report.problems.push(format!(
+                "Contract state {}: hash of its preimage is {}",
+                state_hash.0, expected.0
+            ));
+        }
+        if let Some(problem) = check_root(transaction, "tree_contracts", root.0, &mut verified)? {
+            report.problems.push(format!(
+                "Root of contract state {}: {}",
+                state_hash.0, problem
+            ));
+        }
+        report.contract_states_checked += 1;
+    }
+
+    for table in ["tree_global", "tree_contracts"] {
+        report
+            .problems
+            .extend(check_ref_counts(transaction, table)?);
+    }
+
+    Ok(report)
+}
+
+/// Walks the trie of `root` down to its leaves, checking that every node exists and hashes to
+/// the key it is stored under. Since each node is hashed from its children's keys, this recomputes
+/// the root from the leaves.
+///
+/// Nodes in `verified` were already walked for an earlier root and their subtries are skipped, as
+/// the tries of different roots share most of their nodes. Walked nodes are added to it.
+fn check_root(
+    transaction: &Transaction<'_>,
+    table: &str,
+    root: StarkHash,
+    verified: &mut HashSet<StarkHash>,
+) -> anyhow::Result<Option<String>> {
+    /// Number of key bits, leaves are at this depth and are not stored as nodes.
+    const HEIGHT: usize = 251;
+
+    let mut stmt = transaction
+        .prepare_cached(&format!("SELECT data FROM {} WHERE hash = ?", table))
+        .with_context(|| format!("Preparing {} query", table))?;
+
+    // The empty trie has no nodes.
+    let mut pending = Vec::new();
+    if root != StarkHash::ZERO {
+        pending.push((root, 0));
+    }
+
+    while let Some((node, depth)) = pending.pop() {
+        if depth == HEIGHT || verified.contains(&node) {
+            continue;
+        }
+
+        let data = stmt
+            .query_row([&node.to_be_bytes()[..]], |row| row.get::<_, Vec<u8>>(0))
+            .optional()
+            .with_context(|| format!("Querying {}", table))?;
+        let data = match data {
+            Some(data) => data,
+            None => return Ok(Some(format!("node {} is missing", node))),
+        };
+
+        let hash = match PersistedNode::deserialize(&data) {
+            Ok(PersistedNode::Binary(binary)) => {
+                pending.push((binary.left, depth + 1));
+                pending.push((binary.right, depth + 1));
+
+                stark_hash(binary.left, binary.right)
+            }
+            Ok(PersistedNode::Edge(edge)) => {
+                if depth + edge.path.len() > HEIGHT {
+                    return Ok(Some(format!("edge {} extends past the leaves", node)));
+                }
+                pending.push((edge.child, depth + edge.path.len()));
+
+                let path = StarkHash::from_bits(&edge.path).context("Edge path overflows")?;
+                let mut length = [0; 32];
+                length[31] = edge.path.len() as u8;
+                let length = StarkHash::from_be_bytes(length).unwrap();
+
+                stark_hash(edge.child, path) + length
+            }
+            Ok(PersistedNode::Leaf) => return Ok(Some(format!("node {} is a leaf", node))),
+            Err(e) => return Ok(Some(format!("node {} is corrupt: {:#}", node, e))),
+        };
+
+        if hash != node {
+            return Ok(Some(format!("node {} hashes to {}", node, hash)));
+        }
+        verified.insert(node);
+    }
+
+    Ok(None)
+}
+
+/// Compares the reference counts of the nodes in `table` to the number of their parents.
+///
+/// Children are read from the serialized nodes directly, see
+/// [storage::merkle_tree](super::merkle_tree) for the format.
+fn check_ref_counts(transaction: &Transaction<'_>, table: &str) -> anyhow::Result<Vec<String>> {
+    let query = format!(
+        r"WITH children(hash) AS (
+            SELECT substr(data, 1, 32) FROM {table} WHERE length(data) IN (64, 65)
+            UNION ALL
+            SELECT substr(data, 33, 32) FROM {table} WHERE length(data) = 64
+        ),
+        parents(hash, count) AS (
+            SELECT hash, count(1) FROM children GROUP BY hash
+        )
+        SELECT t.hash, t.ref_count, coalesce(p.count, 0) FROM {table} t
+        LEFT JOIN parents p ON p.hash = t.hash
+        WHERE t.ref_count < coalesce(p.count, 0) OR t.ref_count = 0"
+    );
+
+    let mut stmt = transaction
+        .prepare(&query)
+        .with_context(|| format!("Preparing reference count query for {}", table))?;
+    let problems = stmt
+        .query_map([], |row| {
+            let hash = row.get::<_, Vec<u8>>(0)?;
+            let ref_count = row.get::<_, u64>(1)?;
+            let parents = row.get::<_, u64>(2)?;
+
+            Ok(format!(
+                "Node 0x{} in {} has a reference count of {} but {} parents",
+                hex::encode(hash),
+                table,
+                ref_count,
+                parents
+            ))
+        })
+        .with_context(|| format!("Querying reference counts of {}", table))?
+        .collect::<Result<Vec<_>, _>>()
+        .with_context(|| format!("Iterating over reference counts of {}", table))?;
+
+    Ok(problems)
+}
+
+/// Copies the database into a new file at `destination` with all free space removed.
+///
+/// Returns the size of the new database in bytes.
+pub fn vacuum_into(connection: &Connection, destination: &Path) -> anyhow::Result<u64> {
+    anyhow::ensure!(
+        !destination.exists(),
+        "Destination {} already exists",
+        destination.display()
+    );
+
+    let destination_str = destination
+        .to_str()
+        .context("Destination path is not valid UTF-8")?;
+    connection
+        .execute("VACUUM INTO ?", [destination_str])
+        .context("Vacuuming database")?;
+
+    let size = std::fs::metadata(destination)
+        .context("Reading size of vacuumed database")?
+        .len();
+
+    Ok(size)
+}
+
+/// Outcome of [migrate].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Migration {
+    pub from: usize,
+    pub to: usize,
+    pub duration: Duration,
+}
+
+/// Migrates the database to the latest schema version.
+///
+/// If `dry_run` is set, all pending migrations are applied in a single transaction which is then
+/// rolled back. This validates the migrations against the data and measures how long they take,
+/// without changing the database.
+pub fn migrate(connection: &mut Connection, dry_run: bool) -> anyhow::Result<Migration> {
+    let from = super::schema_version(connection)?;
+    let started_at = Instant::now();
+
+    match dry_run {
+        true => {
+            let migrations = super::schema::migrations();
+            anyhow::ensure!(
+                from <= migrations.len(),
+                "Database version is newer than this application ({} > {})",
+                from,
+                migrations.len()
+            );
+
+            let transaction = connection
+                .transaction()
+                .context("Create database transaction")?;
+            for (version, migration) in migrations.iter().enumerate().skip(from) {
+                migration(&transaction).with_context(|| format!("Migrating from {version}"))?;
+            }
+            transaction.rollback().context("Rolling back migrations")?;
+        }
+        false => super::migrate_database(connection)?,
+    }
+
+    Ok(Migration {
+        from,
+        to: super::schema::migrations().len(),
+        duration: started_at.elapsed(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::merkle_tree::MerkleTree;
+    use crate::storage::merkle_tree::RcNodeStorage;
+    use crate::storage::{JournalMode, Storage};
+
+    fn database() -> (tempfile::TempDir, PathBuf) {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("test.sqlite");
+        Storage::migrate(path.clone(), JournalMode::WAL).unwrap();
+
+        (dir, path)
+    }
+
+    #[test]
+    fn lock() {
+        let (_dir, path) = database();
+
+        let node = DatabaseLock::shared(&path).unwrap();
+        let replica = DatabaseLock::shared(&path).unwrap();
+        DatabaseLock::exclusive(&path).unwrap_err();
+
+        drop(node);
+        drop(replica);
+        let maintenance = DatabaseLock::exclusive(&path).unwrap();
+        DatabaseLock::shared(&path).unwrap_err();
+        DatabaseLock::exclusive(&path).unwrap_err();
+
+        drop(maintenance);
+        DatabaseLock::shared(&path).unwrap();
+    }
+
+    #[test]
+    fn stats() {
+        let (_dir, path) = database();
+        let connection = open(&path).unwrap();
+
+        let stats = super::stats(&connection).unwrap();
+        assert_eq!(
+            stats.schema_version,
+            crate::storage::schema::migrations().len()
+        );
+        assert_eq!(stats.trie_nodes, 0);
+        assert!(stats.tables.iter().any(|t| t.name == "starknet_blocks"));
+    }
+
+    #[test]
+    fn check() {
+        use crate::core::{
+            GasPrice, SequencerAddress, StarknetBlockHash, StarknetBlockNumber,
+            StarknetBlockTimestamp,
+        };
+        use crate::storage::{StarknetBlock, StarknetBlocksTable};
+
+        let (_dir, path) = database();
+        let mut connection = open(&path).unwrap();
+        let tx = connection.transaction().unwrap();
+
+        let storage = RcNodeStorage::open("tree_global", &tx).unwrap();
+        let mut tree = MerkleTree::with_storage(storage, StarkHash::ZERO).unwrap();
+        for i in 1..10 {
+            let key = StarkHash::from_u64(i).view_bits().to_bitvec();
+            tree.set(&key, StarkHash::from_u64(i)).unwrap();
+        }
+        let root = tree.commit().unwrap();
+
+        let block = |number: u64, root: StarkHash| StarknetBlock {
+            number: StarknetBlockNumber::new_or_panic(number),
+            hash: StarknetBlockHash(StarkHash::from_u64(number + 1)),
+            root: GlobalRoot(root),
+            timestamp: StarknetBlockTimestamp::new_or_panic(number),
+            gas_price: GasPrice::ZERO,
+            sequencer_address: SequencerAddress(StarkHash::ZERO),
+        };
+        StarknetBlocksTable::insert(&tx, &block(0, root), None).unwrap();
+
+        let report = super::check(&tx, 10).unwrap();
+        assert_eq!(report.blocks_checked, 1);
+        assert_eq!(report.problems, Vec::<String>::new());
+
+        // An inner node whose children were swapped.
+        let corrupted = tx
+            .execute(
+                "UPDATE tree_global SET data = substr(data, 33, 32) || substr(data, 1, 32)
+                WHERE rowid = (SELECT rowid FROM tree_global WHERE length(data) = 64 AND hash != ? LIMIT 1)",
+                [&root.to_be_bytes()[..]],
+            )
+            .unwrap();
+        assert_eq!(corrupted, 1);
+
+        let report = super::check(&tx, 10).unwrap();
+        assert_eq!(report.problems.len(), 1, "{:?}", report.problems);
+        assert!(report.problems[0].contains("hashes to"));
+
+        // A root without nodes.
+        StarknetBlocksTable::insert(&tx, &block(1, StarkHash::from_u64(1)), None).unwrap();
+        // A root which is no longer referenced.
+        tx.execute(
+            "UPDATE tree_global SET ref_count = 0 WHERE hash = ?",
+            [&root.to_be_bytes()[..]],
+        )
+        .unwrap();
+
+        let report = super::check(&tx, 10).unwrap();
+        assert_eq!(report.blocks_checked, 2);
+        assert_eq!(report.problems.len(), 3, "{:?}", report.problems);
+    }
+
+    #[test]
+    fn vacuum_into() {
+        let (dir, path) = database();
+        let connection = open(&path).unwrap();
+
+        let destination = dir.path().join("vacuumed.sqlite");
+        let size = super::vacuum_into(&connection, &destination).unwrap();
+        assert_eq!(size, std::fs::metadata(&destination).unwrap().len());
+
+        super::vacuum_into(&connection, &destination).unwrap_err();
+    }
+
+    #[test]
+    fn migrate_dry_run() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("test.sqlite");
+        // An empty database at version 0.
+        std::fs::File::create(&path).unwrap();
+
+        let mut connection = open(&path).unwrap();
+        let latest = crate::storage::schema::migrations().len();
+
+        let migration = migrate(&mut connection, true).unwrap();
+        assert_eq!((migration.from, migration.to), (0, latest));
+        assert_eq!(crate::storage::schema_version(&connection).unwrap(), 0);
+
+        let migration = migrate(&mut connection, false).unwrap();
+        assert_eq!((migration.from, migration.to), (0, latest));
+        assert_eq!(crate::storage::schema_version(&connection).unwrap(), latest);
+    }
+}
@@ -38,8 +38,17 @@
 //! #### [PersistedNode::Edge]
 //!
 //! This is stored as 65 bytes: [child (32), path (32), path length (1)]
+//!
+//! ## Other backends
+//!
+//! The tree only depends on [NodeStorage](crate::state::merkle_tree::NodeStorage), which
+//! key-value backends can implement with the same reference counting semantics. The
+//! [InMemoryNodeStorage] does so without any database, for tests and benchmarks.
 
 use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
 
 use anyhow::Context;
 use bitvec::{order::Msb0, prelude::BitVec, view::BitView};
@@ -177,6 +186,112 @@ impl<'a, 'queries> crate::state::merkle_tree::NodeStorage for RcNodeStorage<'a,
     }
 }
 
+/// Provides a reference counted, in-memory storage backend for the
+/// nodes of a Starknet Binary Merkle Patricia Tree.
+///
+/// Has the same reference counting semantics as [RcNodeStorage]. Clones share their nodes, so a
+/// clone can be used to load a tree committed by another.
+#[derive(Debug, Default, Clone)]
+pub struct InMemoryNodeStorage {
+    /// Nodes and their reference counts.
+    nodes: Rc<RefCell<HashMap<StarkHash, (PersistedNode, u64)>>>,
+}
+
+impl InMemoryNodeStorage {
+    /// Inserts the node, and increments the reference count of the node's children (if any).
+    /// Does nothing if the node already exists.
+    ///
+    /// A newly inserted node will have a reference count of zero.
+    pub fn upsert(&self, key: StarkHash, node: PersistedNode) -> anyhow::Result<()> {
+        let mut nodes = self.nodes.borrow_mut();
+
+        match nodes.get(&key) {
+            Some((existing, _)) if existing == &node => return Ok(()),
+            Some((existing, _)) => {
+                anyhow::bail!("Hash conflict! Existing: {:?}, new: {:?}", existing, node)
+            }
+            None => {}
+        }
+
+        let children = match &node {
+            PersistedNode::Binary(binary) => vec![binary.left, binary.right],
+            PersistedNode::Edge(edge) => vec![edge.child],
+            // Leaves are not stored, as their value is also their hash.
+            PersistedNode::Leaf => return Ok(()),
+        };
+        for child in children {
+            if let Some((_, count)) = nodes.get_mut(&child) {
+                *count += 1;
+            }
+        }
+
+        nodes.insert(key, (node, 0));
+
+        Ok(())
+    }
+
+    /// Returns the node given by `key`, or [None] if it doesn't exist.
+    pub fn get(&self, key: StarkHash) -> anyhow::Result<Option<PersistedNode>> {
+        Ok(self.nodes.borrow().get(&key).map(|(node, _)| node.clone()))
+    }
+
+    /// Increments the reference count of the node.
+    pub fn increment_ref_count(&self, key: StarkHash) -> anyhow::Result<()> {
+        if let Some((_, count)) = self.nodes.borrow_mut().get_mut(&key) {
+            *count += 1;
+        }
+
+        Ok(())
+    }
+
+    /// Decrements the reference count of the node and automatically deletes it
+    /// if the count becomes zero, which in turn decrements its children's counts.
+    pub fn decrement_ref_count(&self, key: StarkHash) -> anyhow::Result<()> {
+        let mut nodes = self.nodes.borrow_mut();
+
+        let mut pending = vec![key];
+        while let Some(key) = pending.pop() {
+            match nodes.get_mut(&key) {
+                Some((_, count)) if *count > 1 => *count -= 1,
+                Some(_) => match nodes.remove(&key) {
+                    Some((PersistedNode::Binary(binary), _)) => {
+                        pending.extend([binary.left, binary.right])
+                    }
+                    Some((PersistedNode::Edge(edge), _)) => pending.push(edge.child),
+                    _ => {}
+                },
+                None => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the number of stored nodes.
+    pub fn node_count(&self) -> usize {
+        self.nodes.borrow().len()
+    }
+}
+
+impl crate::state::merkle_tree::NodeStorage for InMemoryNodeStorage {
+    fn get(&self, key: StarkHash) -> anyhow::Result<Option<PersistedNode>> {
+        self.get(key)
+    }
+
+    fn upsert(&self, key: StarkHash, node: PersistedNode) -> anyhow::Result<()> {
+        self.upsert(key, node)
+    }
+
+    #[cfg(test)]
+    fn decrement_ref_count(&self, key: StarkHash) -> anyhow::Result<()> {
+        InMemoryNodeStorage::decrement_ref_count(self, key)
+    }
+
+    fn increment_ref_count(&self, key: StarkHash) -> anyhow::Result<()> {
+        self.increment_ref_count(key)
+    }
+}
+
 /// A binary node which can be read / written from an [RcNodeStorage].
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct PersistedBinaryNode {
@@ -838,4 +953,60 @@ mod tests {
             uut.delete_node(parent_key_2).unwrap();
         }
     }
+
+    mod in_memory {
+        use super::*;
+        use crate::starkhash;
+
+        #[test]
+        fn reference_counting() {
+            let uut = InMemoryNodeStorage::default();
+
+            let child_key = starkhash!("0111");
+            let child = PersistedNode::Edge(PersistedEdgeNode {
+                path: bitvec![Msb0, u8; 1, 0, 0],
+                child: starkhash!("123abc"),
+            });
+            let parent_key = starkhash!("def123");
+            let parent = PersistedNode::Binary(PersistedBinaryNode {
+                left: child_key,
+                right: starkhash!("0222"),
+            });
+
+            uut.upsert(child_key, child.clone()).unwrap();
+            uut.upsert(parent_key, parent.clone()).unwrap();
+            // Upserting again must not increment the child's count again.
+            uut.upsert(parent_key, parent.clone()).unwrap();
+            assert_eq!(uut.get(parent_key).unwrap(), Some(parent));
+
+            // The child is referenced by the parent and once more here.
+            uut.increment_ref_count(child_key).unwrap();
+            uut.increment_ref_count(parent_key).unwrap();
+
+            uut.decrement_ref_count(parent_key).unwrap();
+            assert_eq!(uut.get(parent_key).unwrap(), None);
+            assert_eq!(uut.get(child_key).unwrap(), Some(child));
+
+            uut.decrement_ref_count(child_key).unwrap();
+            assert_eq!(uut.node_count(), 0);
+        }
+
+        #[test]
+        fn conflict() {
+            let uut = InMemoryNodeStorage::default();
+
+            let key = starkhash!("123abc");
+            let node = PersistedNode::Binary(PersistedBinaryNode {
+                left: starkhash!("aaaa"),
+                right: starkhash!("bbbb"),
+            });
+            uut.upsert(key, node).unwrap();
+
+            let fail = PersistedNode::Binary(PersistedBinaryNode {
+                left: starkhash!("cccc"),
+                right: starkhash!("dddd"),
+            });
+            uut.upsert(key, fail).unwrap_err();
+        }
+    }
 }
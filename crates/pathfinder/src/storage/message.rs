@@ -0,0 +1,540 @@
+use anyhow::Context;
+use rusqlite::{named_params, Row, Transaction};
+use web3::types::{H160, H256, U256};
+
+use crate::{
+    core::{
+        EthereumAddress, EthereumBlockHash, EthereumBlockNumber, EthereumLogIndex,
+        EthereumTransactionHash, EthereumTransactionIndex, MessageHash, StarknetBlockNumber,
+        StarknetTransactionHash,
+    },
+    ethereum::{
+        log::{ConsumedMessageToL1Log, ConsumedMessageToL2Log, MessageLog, MessageToL2Log},
+        BlockOrigin, EthOrigin, TransactionOrigin,
+    },
+};
+
+/// Contains the [message logs](MessageLog) of the Starknet core contract.
+pub struct L1MessagesTable {}
+
+/// The kind of a [MessageLog], as stored in the [L1MessagesTable].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum L1MessageKind {
+    ToL2,
+    ConsumedToL2,
+    ConsumedToL1,
+}
+
+impl L1MessageKind {
+    fn of(log: &MessageLog) -> Self {
+        match log {
+            MessageLog::ToL2(_) => L1MessageKind::ToL2,
+            MessageLog::ConsumedToL2(_) => L1MessageKind::ConsumedToL2,
+            MessageLog::ConsumedToL1(_) => L1MessageKind::ConsumedToL1,
+        }
+    }
+
+    fn as_int(&self) -> i64 {
+        match self {
+            L1MessageKind::ToL2 => 0,
+            L1MessageKind::ConsumedToL2 => 1,
+            L1MessageKind::ConsumedToL1 => 2,
+        }
+    }
+}
+
+const L1_COLUMNS: &str = r"kind, hash, from_address, to_address, selector, payload, nonce, fee,
+    ethereum_block_hash, ethereum_block_number, ethereum_transaction_hash,
+    ethereum_transaction_index, ethereum_log_index";
+
+impl L1MessagesTable {
+    /// Inserts a [log](MessageLog), unless it is already stored.
+    pub fn insert(tx: &Transaction<'_>, log: &MessageLog) -> anyhow::Result<()> {
+        let (from_address, to_address, selector, payload, nonce, fee) = match log {
+            MessageLog::ToL2(log) => (
+                log.from_address.to_word(),
+                log.to_address,
+                Some(log.selector),
+                &log.payload,
+                Some(log.nonce),
+                Some(log.fee),
+            ),
+            MessageLog::ConsumedToL2(log) => (
+                log.from_address.to_word(),
+                log.to_address,
+                Some(log.selector),
+                &log.payload,
+                Some(log.nonce),
+                None,
+            ),
+            MessageLog::ConsumedToL1(log) => (
+                log.from_address,
+                log.to_address.to_word(),
+                None,
+                &log.payload,
+                None,
+                None,
+            ),
+        };
+        let fee = fee.map(|fee| {
+            let mut buf = [0u8; 32];
+            fee.to_big_endian(&mut buf);
+            buf
+        });
+        let origin = log.origin();
+
+        tx.execute(
+            r"INSERT OR IGNORE INTO l1_messages (
+                kind, hash, from_address, to_address, selector, payload, nonce, fee,
+                ethereum_block_hash, ethereum_block_number, ethereum_transaction_hash,
+                ethereum_transaction_index, ethereum_log_index
+            ) VALUES (
+                :kind, :hash, :from_address, :to_address, :selector, :payload, :nonce, :fee,
+                :ethereum_block_hash, :ethereum_block_number, :ethereum_transaction_hash,
+                :ethereum_transaction_index, :ethereum_log_index
+            )",
+            named_params! {
+                ":kind": L1MessageKind::of(log).as_int(),
+                ":hash": &log.hash().0[..],
+                ":from_address": &from_address[..],
+                ":to_address": &to_address[..],
+                ":selector": selector.as_ref().map(|selector| &selector[..]),
+                ":payload": encode_words(payload),
+                ":nonce": nonce.as_ref().map(|nonce| &nonce[..]),
+                ":fee": fee.as_ref().map(|fee| &fee[..]),
+                ":ethereum_block_hash": &origin.block.hash.0[..],
+                ":ethereum_block_number": origin.block.number.0,
+                ":ethereum_transaction_hash": &origin.transaction.hash.0[..],
+                ":ethereum_transaction_index": origin.transaction.index.0,
+                ":ethereum_log_index": origin.log_index.0,
+            },
+        )
+        .context("Inserting message log")?;
+
+        Ok(())
+    }
+
+    /// Returns the latest log of the given kind.
+    pub fn get_latest(
+        tx: &Transaction<'_>,
+        kind: L1MessageKind,
+    ) -> anyhow::Result<Option<MessageLog>> {
+        let mut statement = tx
+            .prepare(&format!(
+                r"SELECT {} FROM l1_messages WHERE kind = ?
+                ORDER BY ethereum_block_number DESC, ethereum_log_index DESC LIMIT 1",
+                L1_COLUMNS
+            ))
+            .context("Preparing statement")?;
+        let mut rows = statement
+            .query([kind.as_int()])
+            .context("Executing query")?;
+
+        match rows.next()? {
+            Some(row) => read_log(row).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the logs of the message with the given hash, oldest first.
+    pub fn get_by_hash(tx: &Transaction<'_>, hash: MessageHash) -> anyhow::Result<Vec<MessageLog>> {
+        let mut statement = tx
+            .prepare(&format!(
+                r"SELECT {} FROM l1_messages WHERE hash = ?
+                ORDER BY ethereum_block_number, ethereum_log_index",
+                L1_COLUMNS
+            ))
+            .context("Preparing statement")?;
+        let mut rows = statement.query([&hash.0[..]]).context("Executing query")?;
+
+        let mut logs = Vec::new();
+        while let Some(row) = rows.next()? {
+            logs.push(read_log(row)?);
+        }
+        Ok(logs)
+    }
+
+    /// Returns the hashes of the most recent messages sent from L1 to L2 by or to `address`,
+    /// newest first.
+    pub fn get_sent_by_address(
+        tx: &Transaction<'_>,
+        address: H256,
+        limit: usize,
+    ) -> anyhow::Result<Vec<MessageHash>> {
+        let mut statement = tx
+            .prepare(
+                r"SELECT hash FROM l1_messages
+                WHERE kind = :kind AND (from_address = :address OR to_address = :address)
+                ORDER BY ethereum_block_number DESC, ethereum_log_index DESC LIMIT :limit",
+            )
+            .context("Preparing statement")?;
+        let mut rows = statement
+            .query(named_params! {
+                ":kind": L1MessageKind::ToL2.as_int(),
+                ":address": &address[..],
+                ":limit": limit,
+            })
+            .context("Executing query")?;
+
+        let mut hashes = Vec::new();
+        while let Some(row) = rows.next()? {
+            hashes.push(MessageHash(read_word(row, "hash")?));
+        }
+        Ok(hashes)
+    }
+
+    /// Deletes the logs of the given kind from `from` onwards, i.e. which were invalidated by an
+    /// L1 reorg.
+    pub fn reorg(
+        tx: &Transaction<'_>,
+        kind: L1MessageKind,
+        from: EthereumBlockNumber,
+    ) -> anyhow::Result<()> {
+        tx.execute(
+            "DELETE FROM l1_messages WHERE kind = ? AND ethereum_block_number >= ?",
+            rusqlite::params![kind.as_int(), from.0],
+        )
+        .context("Deleting message logs")?;
+        Ok(())
+    }
+}
+
+fn read_log(row: &Row<'_>) -> anyhow::Result<MessageLog> {
+    let kind = row.get_ref_unwrap("kind").as_i64()?;
+    let hash = MessageHash(read_word(row, "hash")?);
+    let from_address = read_word(row, "from_address")?;
+    let to_address = read_word(row, "to_address")?;
+    let payload = decode_words(row.get_ref_unwrap("payload").as_blob()?)?;
+
+    let origin = EthOrigin {
+        block: BlockOrigin {
+            hash: EthereumBlockHash(read_word(row, "ethereum_block_hash")?),
+            number: EthereumBlockNumber(
+                row.get_ref_unwrap("ethereum_block_number").as_i64()? as u64
+            ),
+        },
+        transaction: TransactionOrigin {
+            hash: EthereumTransactionHash(read_word(row, "ethereum_transaction_hash")?),
+            index: EthereumTransactionIndex(
+                row.get_ref_unwrap("ethereum_transaction_index").as_i64()? as u64,
+            ),
+        },
+        log_index: EthereumLogIndex(row.get_ref_unwrap("ethereum_log_index").as_i64()? as u64),
+    };
+
+    let log = match kind {
+        0 => MessageLog::ToL2(MessageToL2Log {
+            origin,
+            hash,
+            from_address: word_to_address(from_address),
+            to_address,
+            selector: read_word(row, "selector")?,
+            payload,
+            nonce: read_word(row, "nonce")?,
+            fee: U256::from_big_endian(row.get_ref_unwrap("fee").as_blob()?),
+        }),
+        1 => MessageLog::ConsumedToL2(ConsumedMessageToL2Log {
+            origin,
+            hash,
+            from_address: word_to_address(from_address),
+            to_address,
+            selector: read_word(row, "selector")?,
+            payload,
+            nonce: read_word(row, "nonce")?,
+        }),
+        2 => MessageLog::ConsumedToL1(ConsumedMessageToL1Log {
+            origin,
+            hash,
+            from_address,
+            to_address: word_to_address(to_address),
+            payload,
+        }),
+        other => anyhow::bail!("Unknown message log kind {}", other),
+    };
+
+    Ok(log)
+}
+
+/// Contains the messages sent and consumed by StarkNet transactions.
+pub struct L2MessagesTable {}
+
+/// The direction of a message between L1 and L2.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MessageDirection {
+    L1ToL2,
+    L2ToL1,
+}
+
+impl MessageDirection {
+    fn as_int(&self) -> i64 {
+        match self {
+            MessageDirection::L1ToL2 => 0,
+            MessageDirection::L2ToL1 => 1,
+        }
+    }
+}
+
+/// A message consumed ([L1ToL2](MessageDirection::L1ToL2)) or sent
+/// ([L2ToL1](MessageDirection::L2ToL1)) by a StarkNet transaction.
+///
+/// Addresses are 32 byte words, with Ethereum addresses left-padded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct L2Message {
+    pub hash: MessageHash,
+    pub direction: MessageDirection,
+    pub from_address: H256,
+    pub to_address: H256,
+    pub payload: Vec<H256>,
+    pub block_number: StarknetBlockNumber,
+    pub transaction_hash: StarknetTransactionHash,
+}
+
+impl L2MessagesTable {
+    /// Inserts a message. It is removed along with its block by a reorg.
+    pub fn insert(tx: &Transaction<'_>, message: &L2Message) -> anyhow::Result<()> {
+        tx.execute(
+            r"INSERT INTO l2_messages
+                (hash, direction, from_address, to_address, payload, block_number, transaction_hash)
+            VALUES
+                (:hash, :direction, :from_address, :to_address, :payload, :block_number, :transaction_hash)",
+            named_params! {
+                ":hash": &message.hash.0[..],
+                ":direction": message.direction.as_int(),
+                ":from_address": &message.from_address[..],
+                ":to_address": &message.to_address[..],
+                ":payload": encode_words(&message.payload),
+                ":block_number": message.block_number,
+                ":transaction_hash": message.transaction_hash,
+            },
+        )
+        .context("Inserting message")?;
+
+        Ok(())
+    }
+
+    /// Returns the messages with the given hash, oldest first.
+    pub fn get_by_hash(tx: &Transaction<'_>, hash: MessageHash) -> anyhow::Result<Vec<L2Message>> {
+        let mut statement = tx
+            .prepare(
+                r"SELECT hash, direction, from_address, to_address, payload, block_number, transaction_hash
+                FROM l2_messages WHERE hash = ? ORDER BY block_number, rowid",
+            )
+            .context("Preparing statement")?;
+        let mut rows = statement.query([&hash.0[..]]).context("Executing query")?;
+
+        let mut messages = Vec::new();
+        while let Some(row) = rows.next()? {
+            messages.push(read_message(row)?);
+        }
+        Ok(messages)
+    }
+
+    /// Returns the hashes of the most recent messages sent or consumed by, or sent to `address`,
+    /// newest first.
+    pub fn get_by_address(
+        tx: &Transaction<'_>,
+        address: H256,
+        limit: usize,
+    ) -> anyhow::Result<Vec<MessageHash>> {
+        let mut statement = tx
+            .prepare(
+                r"SELECT hash FROM l2_messages
+                WHERE from_address = :address OR to_address = :address
+                ORDER BY block_number DESC, rowid DESC LIMIT :limit",
+            )
+            .context("Preparing statement")?;
+        let mut rows = statement
+            .query(named_params! {
+                ":address": &address[..],
+                ":limit": limit,
+            })
+            .context("Executing query")?;
+
+        let mut hashes = Vec::new();
+        while let Some(row) = rows.next()? {
+            hashes.push(MessageHash(read_word(row, "hash")?));
+        }
+        Ok(hashes)
+    }
+}
+
+fn read_message(row: &Row<'_>) -> anyhow::Result<L2Message> {
+    let direction = match row.get_ref_unwrap("direction").as_i64()? {
+        0 => MessageDirection::L1ToL2,
+        1 => MessageDirection::L2ToL1,
+        other => anyhow::bail!("Unknown message direction {}", other),
+    };
+
+    Ok(L2Message {
+        hash: MessageHash(read_word(row, "hash")?),
+        direction,
+        from_address: read_word(row, "from_address")?,
+        to_address: read_word(row, "to_address")?,
+        payload: decode_words(row.get_ref_unwrap("payload").as_blob()?)?,
+        block_number: row.get("block_number")?,
+        transaction_hash: row.get("transaction_hash")?,
+    })
+}
+
+fn read_word(row: &Row<'_>, column: &str) -> anyhow::Result<H256> {
+    let bytes = row.get_ref_unwrap(column).as_blob()?;
+    let bytes =
+        <[u8; 32]>::try_from(bytes).with_context(|| format!("{} is not 32 bytes long", column))?;
+    Ok(H256(bytes))
+}
+
+fn word_to_address(word: H256) -> EthereumAddress {
+    EthereumAddress(H160::from_slice(&word[12..]))
+}
+
+/// Payloads are stored as their concatenated 32 byte words.
+fn encode_words(words: &[H256]) -> Vec<u8> {
+    words.iter().flat_map(|word| word.0).collect()
+}
+
+fn decode_words(bytes: &[u8]) -> anyhow::Result<Vec<H256>> {
+    anyhow::ensure!(
+        bytes.len() % 32 == 0,
+        "Payload is not a multiple of 32 bytes"
+    );
+    Ok(bytes.chunks_exact(32).map(H256::from_slice).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{StarknetBlockHash, StarknetBlockTimestamp};
+    use crate::storage::{CanonicalBlocksTable, StarknetBlock, StarknetBlocksTable, Storage};
+    use stark_hash::StarkHash;
+
+    fn origin(block: u64, log_index: u64) -> EthOrigin {
+        EthOrigin {
+            block: BlockOrigin {
+                hash: EthereumBlockHash(H256::from_low_u64_be(block + 100)),
+                number: EthereumBlockNumber(block),
+            },
+            transaction: TransactionOrigin {
+                hash: EthereumTransactionHash(H256::from_low_u64_be(block + 200)),
+                index: EthereumTransactionIndex(3),
+            },
+            log_index: EthereumLogIndex(log_index),
+        }
+    }
+
+    fn logs() -> Vec<MessageLog> {
+        vec![
+            MessageLog::ToL2(MessageToL2Log {
+                origin: origin(1, 0),
+                hash: MessageHash(H256::from_low_u64_be(1)),
+                from_address: EthereumAddress(H160::from_low_u64_be(0x1234)),
+                to_address: H256::from_low_u64_be(0x5678),
+                selector: H256::from_low_u64_be(0x9abc),
+                payload: vec![H256::from_low_u64_be(1), H256::from_low_u64_be(2)],
+                nonce: H256::from_low_u64_be(7),
+                fee: U256::from(1000),
+            }),
+            MessageLog::ConsumedToL2(ConsumedMessageToL2Log {
+                origin: origin(2, 0),
+                hash: MessageHash(H256::from_low_u64_be(1)),
+                from_address: EthereumAddress(H160::from_low_u64_be(0x1234)),
+                to_address: H256::from_low_u64_be(0x5678),
+                selector: H256::from_low_u64_be(0x9abc),
+                payload: vec![H256::from_low_u64_be(1), H256::from_low_u64_be(2)],
+                nonce: H256::from_low_u64_be(7),
+            }),
+            MessageLog::ConsumedToL1(ConsumedMessageToL1Log {
+                origin: origin(2, 1),
+                hash: MessageHash(H256::from_low_u64_be(2)),
+                from_address: H256::from_low_u64_be(0x5678),
+                to_address: EthereumAddress(H160::from_low_u64_be(0x1234)),
+                payload: vec![],
+            }),
+        ]
+    }
+
+    #[test]
+    fn l1_messages() {
+        let storage = Storage::in_memory().unwrap();
+        let mut connection = storage.connection().unwrap();
+        let tx = connection.transaction().unwrap();
+
+        for log in logs() {
+            L1MessagesTable::insert(&tx, &log).unwrap();
+            // Inserting again is a no-op.
+            L1MessagesTable::insert(&tx, &log).unwrap();
+        }
+
+        let logs = logs();
+        assert_eq!(
+            L1MessagesTable::get_by_hash(&tx, MessageHash(H256::from_low_u64_be(1))).unwrap(),
+            logs[..2]
+        );
+        assert_eq!(
+            L1MessagesTable::get_latest(&tx, L1MessageKind::ConsumedToL1).unwrap(),
+            Some(logs[2].clone())
+        );
+        assert_eq!(
+            L1MessagesTable::get_sent_by_address(&tx, H256::from_low_u64_be(0x1234), 10).unwrap(),
+            vec![MessageHash(H256::from_low_u64_be(1))]
+        );
+
+        L1MessagesTable::reorg(&tx, L1MessageKind::ToL2, EthereumBlockNumber(1)).unwrap();
+        assert_eq!(
+            L1MessagesTable::get_latest(&tx, L1MessageKind::ToL2).unwrap(),
+            None
+        );
+        assert_eq!(
+            L1MessagesTable::get_latest(&tx, L1MessageKind::ConsumedToL2).unwrap(),
+            Some(logs[1].clone())
+        );
+    }
+
+    #[test]
+    fn l2_messages() {
+        let storage = Storage::in_memory().unwrap();
+        let mut connection = storage.connection().unwrap();
+        let tx = connection.transaction().unwrap();
+
+        let block = StarknetBlock {
+            number: StarknetBlockNumber::GENESIS,
+            hash: StarknetBlockHash(StarkHash::from_u64(1)),
+            root: crate::core::GlobalRoot(StarkHash::ZERO),
+            timestamp: StarknetBlockTimestamp::new_or_panic(0),
+            gas_price: crate::core::GasPrice::ZERO,
+            sequencer_address: crate::core::SequencerAddress(StarkHash::ZERO),
+        };
+        StarknetBlocksTable::insert(&tx, &block, None).unwrap();
+        CanonicalBlocksTable::insert(&tx, block.number, block.hash).unwrap();
+
+        let message = L2Message {
+            hash: MessageHash(H256::from_low_u64_be(2)),
+            direction: MessageDirection::L2ToL1,
+            from_address: H256::from_low_u64_be(0x5678),
+            to_address: H256::from_low_u64_be(0x1234),
+            payload: vec![H256::from_low_u64_be(3)],
+            block_number: block.number,
+            transaction_hash: StarknetTransactionHash(StarkHash::from_u64(4)),
+        };
+        L2MessagesTable::insert(&tx, &message).unwrap();
+
+        assert_eq!(
+            L2MessagesTable::get_by_hash(&tx, message.hash).unwrap(),
+            vec![message.clone()]
+        );
+        assert_eq!(
+            L2MessagesTable::get_by_address(&tx, H256::from_low_u64_be(0x5678), 10).unwrap(),
+            vec![message.hash]
+        );
+        assert_eq!(
+            L2MessagesTable::get_by_address(&tx, H256::from_low_u64_be(0x9999), 10).unwrap(),
+            vec![]
+        );
+
+        // Messages are removed with their block.
+        CanonicalBlocksTable::reorg(&tx, block.number).unwrap();
+        assert_eq!(
+            L2MessagesTable::get_by_hash(&tx, message.hash).unwrap(),
+            vec![]
+        );
+    }
+}
@@ -20,6 +20,12 @@ mod revision_0019;
 mod revision_0020;
 mod revision_0021;
 mod revision_0022;
+mod revision_0023;
+mod revision_0024;
+mod revision_0025;
+mod revision_0026;
+mod revision_0027;
+mod revision_0028;
 
 type MigrationFn = fn(&rusqlite::Transaction<'_>) -> anyhow::Result<()>;
 
@@ -49,5 +55,11 @@ pub fn migrations() -> &'static [MigrationFn] {
         revision_0020::migrate,
         revision_0021::migrate,
         revision_0022::migrate,
+        revision_0023::migrate,
+        revision_0024::migrate,
+        revision_0025::migrate,
+        revision_0026::migrate,
+        revision_0027::migrate,
+        revision_0028::migrate,
     ]
 }
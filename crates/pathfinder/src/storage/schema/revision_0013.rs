@@ -67,6 +67,7 @@ pub(crate) fn migrate(transaction: &Transaction<'_>) -> anyhow::Result<()> {
                     bytecode,
                     definition,
                     hash,
+                    casm: None,
                 })
                 .unwrap();
         }
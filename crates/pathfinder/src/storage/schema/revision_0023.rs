@@ -0,0 +1,28 @@
+use anyhow::Context;
+
+/// Adds support for Sierra classes and their compiled CASM.
+///
+/// More specifically, this migration:
+/// - adds the `contract_code.class_format` column, which tells a Cairo 0.x class (0) from a
+///   Sierra class (1); all existing classes are Cairo 0.x classes
+/// - adds the `casm_definitions` table, which holds the compiled CASM definition and compiled
+///   class hash of each Sierra class
+pub(crate) fn migrate(tx: &rusqlite::Transaction<'_>) -> anyhow::Result<()> {
+    tx.execute(
+        "ALTER TABLE contract_code ADD COLUMN class_format INTEGER NOT NULL DEFAULT 0",
+        [],
+    )
+    .context("Adding `class_format` column to `contract_code` table")?;
+
+    tx.execute(
+        r"CREATE TABLE casm_definitions (
+    hash                BLOB PRIMARY KEY NOT NULL REFERENCES contract_code(hash) ON DELETE CASCADE,
+    compiled_class_hash BLOB NOT NULL,
+    definition          BLOB NOT NULL
+)",
+        [],
+    )
+    .context("Creating `casm_definitions` table")?;
+
+    Ok(())
+}
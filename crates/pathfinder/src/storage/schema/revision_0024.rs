@@ -0,0 +1,21 @@
+use anyhow::Context;
+
+/// Adds the `transaction_commitment` and `event_commitment` columns to the `starknet_blocks`
+/// table.
+///
+/// Both are left `NULL` for blocks which were synced before this migration.
+pub(crate) fn migrate(tx: &rusqlite::Transaction<'_>) -> anyhow::Result<()> {
+    tx.execute(
+        "ALTER TABLE starknet_blocks ADD COLUMN transaction_commitment BLOB",
+        [],
+    )
+    .context("Adding `transaction_commitment` column to `starknet_blocks` table")?;
+
+    tx.execute(
+        "ALTER TABLE starknet_blocks ADD COLUMN event_commitment BLOB",
+        [],
+    )
+    .context("Adding `event_commitment` column to `starknet_blocks` table")?;
+
+    Ok(())
+}
@@ -0,0 +1,20 @@
+use anyhow::Context;
+
+/// Adds the `chain_spec` table which tags a database with the identity of the
+/// [custom chain](crate::core::ChainSpec) it was synced from.
+///
+/// The table stays empty for databases of the public Starknet networks.
+pub(crate) fn migrate(tx: &rusqlite::Transaction<'_>) -> anyhow::Result<()> {
+    tx.execute(
+        r"CREATE TABLE chain_spec (
+    idx          INTEGER PRIMARY KEY CHECK (idx = 1),
+    name         TEXT NOT NULL,
+    chain_id     BLOB NOT NULL,
+    genesis_hash BLOB NOT NULL
+)",
+        [],
+    )
+    .context("Creating `chain_spec` table")?;
+
+    Ok(())
+}
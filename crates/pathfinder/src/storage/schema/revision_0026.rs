@@ -0,0 +1,188 @@
+use anyhow::Context;
+use rusqlite::{named_params, Transaction};
+use web3::types::H256;
+
+use crate::core::MessageHash;
+
+/// Adds the `l1_messages` and `l2_messages` tables, which index the messages between L1 and L2
+/// by their hash and addresses.
+///
+/// `l2_messages` is backfilled from the stored receipts, while `l1_messages` is filled by the L1
+/// message sync which starts from the beginning.
+pub(crate) fn migrate(tx: &Transaction<'_>) -> anyhow::Result<()> {
+    tx.execute_batch(
+        r"CREATE TABLE l1_messages (
+    hash                       BLOB    NOT NULL,
+    kind                       INTEGER NOT NULL,
+    from_address               BLOB    NOT NULL,
+    to_address                 BLOB    NOT NULL,
+    selector                   BLOB,
+    payload                    BLOB    NOT NULL,
+    nonce                      BLOB,
+    fee                        BLOB,
+    ethereum_block_hash        BLOB    NOT NULL,
+    ethereum_block_number      INTEGER NOT NULL,
+    ethereum_transaction_hash  BLOB    NOT NULL,
+    ethereum_transaction_index INTEGER NOT NULL,
+    ethereum_log_index         INTEGER NOT NULL,
+    UNIQUE(ethereum_block_hash, ethereum_log_index)
+);
+CREATE INDEX l1_messages_hash ON l1_messages(hash);
+CREATE INDEX l1_messages_kind_block_number ON l1_messages(kind, ethereum_block_number);
+CREATE INDEX l1_messages_from_address ON l1_messages(from_address);
+CREATE INDEX l1_messages_to_address ON l1_messages(to_address);
+
+CREATE TABLE l2_messages (
+    hash             BLOB    NOT NULL,
+    direction        INTEGER NOT NULL,
+    from_address     BLOB    NOT NULL,
+    to_address       BLOB    NOT NULL,
+    payload          BLOB    NOT NULL,
+    block_number     INTEGER NOT NULL,
+    transaction_hash BLOB    NOT NULL,
+    FOREIGN KEY(block_number) REFERENCES canonical_blocks(number) ON DELETE CASCADE
+);
+CREATE INDEX l2_messages_hash ON l2_messages(hash);
+CREATE INDEX l2_messages_from_address ON l2_messages(from_address);
+CREATE INDEX l2_messages_to_address ON l2_messages(to_address);
+CREATE INDEX l2_messages_block_number ON l2_messages(block_number);",
+    )
+    .context("Creating message tables")?;
+
+    let todo: usize = tx
+        .query_row("SELECT count(1) FROM starknet_transactions", [], |r| {
+            r.get(0)
+        })
+        .context("Count rows in starknet transactions table")?;
+
+    if todo == 0 {
+        return Ok(());
+    }
+
+    tracing::info!(
+        num_transactions=%todo,
+        "Indexing the L1 and L2 messages of stored receipts, this may take a while.",
+    );
+
+    let mut query = tx
+        .prepare(
+            r"SELECT starknet_transactions.hash, receipt, canonical_blocks.number
+            FROM starknet_transactions
+            JOIN canonical_blocks ON starknet_transactions.block_hash = canonical_blocks.hash",
+        )
+        .context("Preparing transaction query")?;
+    let mut insert = tx
+        .prepare(
+            r"INSERT INTO l2_messages
+                    (hash, direction, from_address, to_address, payload, block_number, transaction_hash)
+            VALUES (:hash, :direction, :from_address, :to_address, :payload, :block_number, :transaction_hash)",
+        )
+        .context("Preparing insert statement")?;
+
+    let mut rows = query.query([]).context("Executing query")?;
+    while let Some(row) = rows.next()? {
+        let transaction_hash = row.get_ref_unwrap(0).as_blob()?;
+        let receipt = row.get_ref_unwrap(1).as_blob()?;
+        let block_number = row.get_ref_unwrap(2).as_i64()?;
+
+        let receipt = zstd::decode_all(receipt).context("Decompressing receipt")?;
+        let receipt: types::Receipt =
+            serde_json::from_slice(&receipt).context("Deserializing receipt")?;
+
+        let mut messages = Vec::new();
+        // Messages sent before nonces were introduced cannot be hashed.
+        if let Some(message) = receipt.l1_to_l2_consumed_message {
+            if let Some(nonce) = message.nonce {
+                let from_address = message.from_address.to_word();
+                let to_address = H256(message.to_address.get().to_be_bytes());
+                let payload = message
+                    .payload
+                    .iter()
+                    .map(|word| H256(word.0.to_be_bytes()))
+                    .collect::<Vec<_>>();
+                let hash = MessageHash::l1_to_l2(
+                    from_address,
+                    to_address,
+                    H256(nonce.0.to_be_bytes()),
+                    H256(message.selector.0.to_be_bytes()),
+                    &payload,
+                );
+                messages.push((hash, 0, from_address, to_address, payload));
+            }
+        }
+        for message in receipt.l2_to_l1_messages {
+            let from_address = H256(message.from_address.get().to_be_bytes());
+            let to_address = message.to_address.to_word();
+            let payload = message
+                .payload
+                .iter()
+                .map(|word| H256(word.0.to_be_bytes()))
+                .collect::<Vec<_>>();
+            let hash = MessageHash::l2_to_l1(from_address, to_address, &payload);
+            messages.push((hash, 1, from_address, to_address, payload));
+        }
+
+        for (hash, direction, from_address, to_address, payload) in messages {
+            let payload = payload.iter().flat_map(|word| word.0).collect::<Vec<u8>>();
+            insert
+                .execute(named_params! {
+                    ":hash": &hash.0[..],
+                    ":direction": direction,
+                    ":from_address": &from_address[..],
+                    ":to_address": &to_address[..],
+                    ":payload": payload,
+                    ":block_number": block_number,
+                    ":transaction_hash": transaction_hash,
+                })
+                .context("Inserting message")?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Partial-copy of types required for deserialisation, this lets us change the original types without breaking this migration.
+///
+/// Only the paths that are actually requried are kept for deserialisation.
+mod types {
+    use crate::core::{
+        ContractAddress, EntryPoint, EthereumAddress, L1ToL2MessageNonce, L1ToL2MessagePayloadElem,
+        L2ToL1MessagePayloadElem,
+    };
+    use crate::rpc::serde::{
+        EthereumAddressAsHexStr, L1ToL2MessagePayloadElemAsDecimalStr,
+        L2ToL1MessagePayloadElemAsDecimalStr,
+    };
+    use serde::Deserialize;
+    use serde_with::serde_as;
+
+    #[derive(Deserialize)]
+    pub struct Receipt {
+        #[serde(default)]
+        pub l1_to_l2_consumed_message: Option<L1ToL2Message>,
+        pub l2_to_l1_messages: Vec<L2ToL1Message>,
+    }
+
+    #[serde_as]
+    #[derive(Deserialize)]
+    pub struct L1ToL2Message {
+        #[serde_as(as = "EthereumAddressAsHexStr")]
+        pub from_address: EthereumAddress,
+        #[serde_as(as = "Vec<L1ToL2MessagePayloadElemAsDecimalStr>")]
+        pub payload: Vec<L1ToL2MessagePayloadElem>,
+        pub selector: EntryPoint,
+        pub to_address: ContractAddress,
+        #[serde(default)]
+        pub nonce: Option<L1ToL2MessageNonce>,
+    }
+
+    #[serde_as]
+    #[derive(Deserialize)]
+    pub struct L2ToL1Message {
+        pub from_address: ContractAddress,
+        #[serde_as(as = "Vec<L2ToL1MessagePayloadElemAsDecimalStr>")]
+        pub payload: Vec<L2ToL1MessagePayloadElem>,
+        #[serde_as(as = "EthereumAddressAsHexStr")]
+        pub to_address: EthereumAddress,
+    }
+}
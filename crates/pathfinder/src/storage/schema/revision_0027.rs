@@ -0,0 +1,12 @@
+use anyhow::Context;
+
+/// Adds the `l1_finalized_block` column to the `refs` table, which holds the latest finalized
+/// Ethereum block number.
+///
+/// It is left `NULL` until the node first queries the Ethereum endpoint for it.
+pub(crate) fn migrate(tx: &rusqlite::Transaction<'_>) -> anyhow::Result<()> {
+    tx.execute("ALTER TABLE refs ADD COLUMN l1_finalized_block INTEGER", [])
+        .context("Adding `l1_finalized_block` column to `refs` table")?;
+
+    Ok(())
+}
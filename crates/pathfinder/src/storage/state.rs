@@ -10,8 +10,9 @@ use crate::{
     core::{
         Chain, ClassHash, ContractAddress, ContractNonce, ContractRoot, ContractStateHash,
         EthereumBlockHash, EthereumBlockNumber, EthereumLogIndex, EthereumTransactionHash,
-        EthereumTransactionIndex, EventData, EventKey, GasPrice, GlobalRoot, SequencerAddress,
-        StarknetBlockHash, StarknetBlockNumber, StarknetBlockTimestamp, StarknetTransactionHash,
+        EthereumTransactionIndex, EventCommitment, EventData, EventKey, GasPrice, GlobalRoot,
+        SequencerAddress, StarknetBlockHash, StarknetBlockNumber, StarknetBlockTimestamp,
+        StarknetTransactionHash, TransactionCommitment,
     },
     ethereum::{log::StateUpdateLog, BlockOrigin, EthOrigin, TransactionOrigin},
     rpc::v01::types::reply::StateUpdate,
@@ -214,6 +215,88 @@ impl RefsTable {
 
         Ok(())
     }
+
+    /// Returns the latest finalized Ethereum block, if it is known.
+    pub fn get_l1_finalized_block(
+        tx: &Transaction<'_>,
+    ) -> anyhow::Result<Option<EthereumBlockNumber>> {
+        tx.query_row(
+            "SELECT l1_finalized_block FROM refs WHERE idx = 1",
+            [],
+            |row| row.get::<_, Option<i64>>(0),
+        )
+        .map(|number| number.map(|number| EthereumBlockNumber(number as u64)))
+        .map_err(|e| e.into())
+    }
+
+    /// Sets the latest finalized Ethereum block.
+    pub fn set_l1_finalized_block(
+        tx: &Transaction<'_>,
+        block: EthereumBlockNumber,
+    ) -> anyhow::Result<()> {
+        tx.execute(
+            "UPDATE refs SET l1_finalized_block = ? WHERE idx = 1",
+            [block.0],
+        )?;
+
+        Ok(())
+    }
+
+    /// Returns the latest Ethereum block which is safe from reorgs, if it is known.
+    pub fn get_l1_safe_block(tx: &Transaction<'_>) -> anyhow::Result<Option<EthereumBlockNumber>> {
+        tx.query_row("SELECT l1_safe_block FROM refs WHERE idx = 1", [], |row| {
+            row.get::<_, Option<i64>>(0)
+        })
+        .map(|number| number.map(|number| EthereumBlockNumber(number as u64)))
+        .map_err(|e| e.into())
+    }
+
+    /// Sets the latest Ethereum block which is safe from reorgs.
+    pub fn set_l1_safe_block(
+        tx: &Transaction<'_>,
+        block: EthereumBlockNumber,
+    ) -> anyhow::Result<()> {
+        tx.execute("UPDATE refs SET l1_safe_block = ? WHERE idx = 1", [block.0])?;
+
+        Ok(())
+    }
+}
+
+/// Tags the database with the identity of the [custom chain](crate::core::ChainSpec)
+/// it belongs to. Empty for the public Starknet networks.
+pub struct ChainSpecTable {}
+
+impl ChainSpecTable {
+    /// Returns the name and [identity](crate::core::ChainSpec::identity) of the custom chain
+    /// this database belongs to.
+    pub fn get(
+        tx: &Transaction<'_>,
+    ) -> anyhow::Result<Option<(String, (StarkHash, StarknetBlockHash))>> {
+        tx.query_row(
+            "SELECT name, chain_id, genesis_hash FROM chain_spec WHERE idx = 1",
+            [],
+            |row| {
+                let name = row.get(0)?;
+                let chain_id = StarkHash::from_be_slice(row.get_ref_unwrap(1).as_blob()?)
+                    .expect("Chain id should fit into a felt");
+                let genesis_hash = row.get(2)?;
+                Ok((name, (chain_id, genesis_hash)))
+            },
+        )
+        .optional()
+        .map_err(|e| e.into())
+    }
+
+    /// Tags the database with the given [ChainSpec](crate::core::ChainSpec).
+    pub fn set(tx: &Transaction<'_>, spec: &crate::core::ChainSpec) -> anyhow::Result<()> {
+        let (chain_id, genesis_hash) = spec.identity();
+        tx.execute(
+            "INSERT OR REPLACE INTO chain_spec (idx, name, chain_id, genesis_hash) VALUES (1, ?, ?, ?)",
+            params![spec.name, chain_id.as_be_bytes(), genesis_hash],
+        )?;
+
+        Ok(())
+    }
 }
 
 /// Stores all known [StarknetBlocks][StarknetBlock].
@@ -335,6 +418,62 @@ impl StarknetBlocksTable {
         .map_err(|e| e.into())
     }
 
+    /// Sets the [transaction](TransactionCommitment) and [event](EventCommitment) commitments
+    /// of the given block.
+    pub fn update_commitments(
+        tx: &Transaction<'_>,
+        number: StarknetBlockNumber,
+        transaction_commitment: TransactionCommitment,
+        event_commitment: EventCommitment,
+    ) -> anyhow::Result<()> {
+        tx.execute(
+            "UPDATE starknet_blocks SET transaction_commitment = ?, event_commitment = ? WHERE number = ?",
+            params![transaction_commitment, event_commitment, number],
+        )?;
+
+        Ok(())
+    }
+
+    /// Returns the [transaction](TransactionCommitment) and [event](EventCommitment) commitments
+    /// of the given block.
+    ///
+    /// Blocks which were synced before `revision_0024` have no commitments stored.
+    pub fn get_commitments(
+        tx: &Transaction<'_>,
+        number: StarknetBlockNumber,
+    ) -> anyhow::Result<Option<(TransactionCommitment, EventCommitment)>> {
+        tx.query_row(
+            "SELECT transaction_commitment, event_commitment FROM starknet_blocks WHERE number = ?",
+            [number],
+            |row| {
+                let transaction_commitment: Option<TransactionCommitment> = row.get(0)?;
+                let event_commitment: Option<EventCommitment> = row.get(1)?;
+                Ok(transaction_commitment.zip(event_commitment))
+            },
+        )
+        .optional()
+        .map(Option::flatten)
+        .map_err(|e| e.into())
+    }
+
+    /// Returns the Starknet version the given block was produced with.
+    ///
+    /// Blocks prior to Starknet 0.9.1 do not have a version.
+    pub fn get_starknet_version(
+        tx: &Transaction<'_>,
+        number: StarknetBlockNumber,
+    ) -> anyhow::Result<Option<String>> {
+        tx.query_row(
+            "SELECT starknet_versions.version FROM starknet_blocks
+                JOIN starknet_versions ON starknet_blocks.version_id = starknet_versions.id
+                WHERE starknet_blocks.number = ?",
+            [number],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| e.into())
+    }
+
     /// Deletes all rows from __head down-to reorg_tail__
     /// i.e. it deletes all rows where `block number >= reorg_tail`.
     pub fn reorg(tx: &Transaction<'_>, reorg_tail: StarknetBlockNumber) -> anyhow::Result<()> {
@@ -681,6 +820,20 @@ impl StarknetTransactionsTable {
         Ok(Some(transaction))
     }
 
+    /// Returns the hash of the block which contains the transaction.
+    pub fn get_block_hash(
+        tx: &Transaction<'_>,
+        transaction: StarknetTransactionHash,
+    ) -> anyhow::Result<Option<StarknetBlockHash>> {
+        tx.query_row(
+            "SELECT block_hash FROM starknet_transactions WHERE hash = ?1",
+            [transaction],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| e.into())
+    }
+
     pub fn get_transaction_with_receipt(
         tx: &Transaction<'_>,
         txn_hash: StarknetTransactionHash,
@@ -1192,6 +1345,24 @@ impl ContractsStateTable {
             .map_err(|e| e.into())
     }
 
+    /// Gets the class hash associated with the given state hash, or [None]
+    /// if it does not exist.
+    pub fn get_class_hash(
+        transaction: &Transaction<'_>,
+        state_hash: ContractStateHash,
+    ) -> anyhow::Result<Option<ClassHash>> {
+        transaction
+            .query_row(
+                "SELECT hash FROM contract_states WHERE state_hash = :state_hash",
+                named_params! {
+                    ":state_hash": state_hash
+                },
+                |row| row.get("hash"),
+            )
+            .optional()
+            .map_err(|e| e.into())
+    }
+
     /// Gets the nonce associated with the given state hash, or [None]
     /// if it does not exist.
     pub fn get_nonce(
@@ -1341,6 +1512,9 @@ mod tests {
             let result = ContractsStateTable::get_root(&transaction, state_hash).unwrap();
             assert_eq!(result, Some(root));
 
+            let result = ContractsStateTable::get_class_hash(&transaction, state_hash).unwrap();
+            assert_eq!(result, Some(hash));
+
             let result = ContractsStateTable::get_nonce(&transaction, state_hash).unwrap();
             assert_eq!(result, Some(nonce));
 
@@ -1383,6 +1557,36 @@ mod tests {
                 assert_eq!(None, RefsTable::get_l1_l2_head(&tx).unwrap());
             }
         }
+
+        #[test]
+        fn l1_finalized_block() {
+            let storage = Storage::in_memory().unwrap();
+            let mut connection = storage.connection().unwrap();
+            let tx = connection.transaction().unwrap();
+
+            assert_eq!(RefsTable::get_l1_finalized_block(&tx).unwrap(), None);
+
+            RefsTable::set_l1_finalized_block(&tx, EthereumBlockNumber(1234)).unwrap();
+            assert_eq!(
+                RefsTable::get_l1_finalized_block(&tx).unwrap(),
+                Some(EthereumBlockNumber(1234))
+            );
+        }
+
+        #[test]
+        fn l1_safe_block() {
+            let storage = Storage::in_memory().unwrap();
+            let mut connection = storage.connection().unwrap();
+            let tx = connection.transaction().unwrap();
+
+            assert_eq!(RefsTable::get_l1_safe_block(&tx).unwrap(), None);
+
+            RefsTable::set_l1_safe_block(&tx, EthereumBlockNumber(1234)).unwrap();
+            assert_eq!(
+                RefsTable::get_l1_safe_block(&tx).unwrap(),
+                Some(EthereumBlockNumber(1234))
+            );
+        }
     }
 
     mod l1_state_table {
@@ -1843,6 +2047,56 @@ mod tests {
             }
         }
 
+        mod commitments {
+            use super::*;
+
+            #[test]
+            fn none_before_update() {
+                with_default_blocks(|tx, blocks| {
+                    for block in blocks {
+                        assert_eq!(
+                            StarknetBlocksTable::get_commitments(tx, block.number).unwrap(),
+                            None
+                        );
+                    }
+                })
+            }
+
+            #[test]
+            fn some() {
+                with_default_blocks(|tx, blocks| {
+                    for (idx, block) in blocks.iter().enumerate() {
+                        let transaction_commitment =
+                            TransactionCommitment(StarkHash::from(idx as u64));
+                        let event_commitment = EventCommitment(StarkHash::from(100 + idx as u64));
+                        StarknetBlocksTable::update_commitments(
+                            tx,
+                            block.number,
+                            transaction_commitment,
+                            event_commitment,
+                        )
+                        .unwrap();
+
+                        assert_eq!(
+                            StarknetBlocksTable::get_commitments(tx, block.number).unwrap(),
+                            Some((transaction_commitment, event_commitment))
+                        );
+                    }
+                })
+            }
+
+            #[test]
+            fn non_existent_block() {
+                with_default_blocks(|tx, blocks| {
+                    let non_existent = blocks.last().unwrap().number + 1;
+                    assert_eq!(
+                        StarknetBlocksTable::get_commitments(tx, non_existent).unwrap(),
+                        None
+                    );
+                })
+            }
+        }
+
         mod reorg {
             use super::*;
 
@@ -1921,6 +2175,26 @@ mod tests {
                 // we should not have any nulls
                 assert_eq!(rows.len(), 2, "nulls were not expected in {rows:?}");
             }
+
+            #[test]
+            fn get_starknet_version() {
+                let storage = Storage::in_memory().unwrap();
+                let mut connection = storage.connection().unwrap();
+                let tx = connection.transaction().unwrap();
+
+                let blocks = super::create_blocks();
+                StarknetBlocksTable::insert(&tx, &blocks[0], None).unwrap();
+                StarknetBlocksTable::insert(&tx, &blocks[1], Some("0.9.1")).unwrap();
+
+                let result = StarknetBlocksTable::get_starknet_version(&tx, blocks[0].number);
+                assert_eq!(result.unwrap(), None);
+
+                let result = StarknetBlocksTable::get_starknet_version(&tx, blocks[1].number);
+                assert_eq!(result.unwrap().as_deref(), Some("0.9.1"));
+
+                let result = StarknetBlocksTable::get_starknet_version(&tx, blocks[2].number);
+                assert_eq!(result.unwrap(), None);
+            }
         }
 
         mod get_latest_number {
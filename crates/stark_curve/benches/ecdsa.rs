@@ -0,0 +1,41 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use stark_curve::ff::PrimeField;
+use stark_curve::{get_public_key, recover_public_key, sign, verify, FieldElement};
+
+pub fn criterion_benchmark(c: &mut Criterion) {
+    // Test vector from cairo-lang's `signature_test_data.json`, also used in tests.
+    let private_key = FieldElement::from_str_vartime(
+        "1699550429262868952957733065396688802326540225623380427551300052767936406476",
+    )
+    .unwrap();
+    let msg_hash = FieldElement::from_str_vartime(
+        "1625329696931188092440743921788884177164352056725166831961984538137308785215",
+    )
+    .unwrap();
+
+    let public_key = get_public_key(private_key).unwrap();
+    let signature = sign(private_key, msg_hash).unwrap();
+
+    c.bench_function("ecdsa_get_public_key", |b| {
+        b.iter(|| black_box(get_public_key(private_key).unwrap()));
+    });
+
+    c.bench_function("ecdsa_sign", |b| {
+        b.iter(|| black_box(sign(private_key, msg_hash).unwrap()));
+    });
+
+    c.bench_function("ecdsa_verify", |b| {
+        b.iter(|| black_box(verify(public_key, msg_hash, signature.r, signature.s).unwrap()));
+    });
+
+    c.bench_function("ecdsa_recover_public_key", |b| {
+        b.iter(|| {
+            black_box(
+                recover_public_key(msg_hash, signature.r, signature.s, signature.v).unwrap(),
+            )
+        });
+    });
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
@@ -40,6 +40,26 @@ impl AffinePoint {
         }
     }
 
+    /// Returns the point on the curve with the given x-coordinate, or `None` if there is none.
+    ///
+    /// There are two such points, `(x, y)` and `(x, -y)`. Which one is returned is unspecified,
+    /// use [AffinePoint::negate] to get the other one.
+    pub fn from_x(x: FieldElement) -> Option<Self> {
+        // y^2 = x^3 + ax + b with a=1 from stark curve
+        let y_squared = x * x * x + x + CURVE_BETA;
+        let y = Option::<FieldElement>::from(y_squared.sqrt())?;
+
+        Some(Self {
+            x,
+            y,
+            infinity: false,
+        })
+    }
+
+    pub fn negate(&mut self) {
+        self.y = -self.y;
+    }
+
     pub fn double(&mut self) {
         if self.infinity {
             return;
@@ -250,8 +270,15 @@ impl ProjectivePoint {
     }
 }
 
+/// Montgomery representation of the Stark curve constant b in `y^2 = x^3 + ax + b`.
+pub const CURVE_BETA: FieldElement = FieldElement::new([
+    3863487492851900874,
+    7432612994240712710,
+    12360725113329547591,
+    88155977965380735,
+]);
+
 /// Montgomery representation of the Stark curve generator G.
-#[allow(dead_code)]
 pub const CURVE_G: ProjectivePoint = ProjectivePoint {
     x: FieldElement::new([
         14484022957141291997,
@@ -441,6 +468,29 @@ mod tests {
         assert_eq!(ag_triple.x, result.x);
     }
 
+    #[test]
+    fn affine_from_x() {
+        let g = AffinePoint::from(&CURVE_G);
+
+        let mut point = AffinePoint::from_x(g.x).unwrap();
+        if point.y != g.y {
+            point.negate();
+        }
+        assert_eq!(point, g);
+
+        // There is no point on the curve with x = 0.
+        assert_eq!(AffinePoint::from_x(FieldElement::zero()), None);
+    }
+
+    #[test]
+    fn const_beta() {
+        let expected = FieldElement::from_str_vartime(
+            "3141592653589793238462643383279502884197169399375105820974944592307816406665",
+        )
+        .unwrap();
+        assert_eq!(CURVE_BETA, expected);
+    }
+
     #[test]
     fn const_generator() {
         let expected = projective_from_xy_str(
@@ -0,0 +1,484 @@
+//! ECDSA over the Stark curve, following the variant implemented in cairo-lang's
+//! `starkware.crypto.signature` module.
+//!
+//! This differs from textbook ECDSA in a couple of ways:
+//! - `r` is the x-coordinate of `k * G` as is, without reducing it modulo the curve order,
+//! - `r`, `w = s^-1` and the message hash must all fit in 251 bits,
+//! - public keys are commonly represented by their x-coordinate only.
+use crate::curve::{AffinePoint, ProjectivePoint, CURVE_G};
+use crate::field::FieldElement;
+use crate::scalar::{Scalar, ScalarRepr};
+
+use ff::{Field, PrimeField};
+use hmac::{Hmac, Mac, NewMac};
+use sha2::Sha256;
+
+/// A Stark curve ECDSA signature.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Signature {
+    pub r: FieldElement,
+    pub s: FieldElement,
+    /// Parity of the y-coordinate of `k * G`, required for [recover_public_key].
+    pub v: bool,
+}
+
+/// Errors returned by the signing, verification and key recovery functions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EcdsaError {
+    /// The private key is zero or not less than the curve order.
+    InvalidPrivateKey,
+    /// The message hash does not fit in 251 bits.
+    InvalidMessageHash,
+    /// `r` is zero or does not fit in 251 bits.
+    InvalidR,
+    /// `s` is zero or not less than the curve order, or its inverse does not fit in 251 bits.
+    InvalidS,
+    /// The recovered public key is the point at infinity.
+    InvalidSignature,
+}
+
+impl std::error::Error for EcdsaError {}
+
+impl std::fmt::Display for EcdsaError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidPrivateKey => f.write_str("Invalid private key"),
+            Self::InvalidMessageHash => f.write_str("Message hash must fit in 251 bits"),
+            Self::InvalidR => f.write_str("Invalid signature r value"),
+            Self::InvalidS => f.write_str("Invalid signature s value"),
+            Self::InvalidSignature => f.write_str("Invalid signature"),
+        }
+    }
+}
+
+/// Computes the public key, i.e. the x-coordinate of `private_key * G`.
+pub fn get_public_key(private_key: FieldElement) -> Result<FieldElement, EcdsaError> {
+    let private_key = to_scalar(private_key)
+        .filter(|d| !bool::from(d.is_zero()))
+        .ok_or(EcdsaError::InvalidPrivateKey)?;
+
+    let public_key = CURVE_G.multiply(&private_key.into_bits());
+
+    Ok(AffinePoint::from(&public_key).x)
+}
+
+/// Signs `msg_hash` with `private_key`, using a deterministic `k` generated as specified in
+/// [RFC6979](https://www.rfc-editor.org/rfc/rfc6979) with SHA-256.
+///
+/// Produces the same signatures as cairo-lang's `sign(msg_hash, priv_key)`.
+pub fn sign(private_key: FieldElement, msg_hash: FieldElement) -> Result<Signature, EcdsaError> {
+    let private_key = to_scalar(private_key)
+        .filter(|d| !bool::from(d.is_zero()))
+        .ok_or(EcdsaError::InvalidPrivateKey)?;
+    if !fits_251_bits(&msg_hash) {
+        return Err(EcdsaError::InvalidMessageHash);
+    }
+
+    // Not every k results in a valid signature, in which case cairo-lang retries with the next
+    // seed. This is exceedingly unlikely to ever happen.
+    let mut seed = None;
+    loop {
+        let k = generate_k(private_key, msg_hash, seed);
+        seed = Some(seed.map_or(1, |seed| seed + 1));
+
+        if let Some(signature) = sign_with_k(private_key, msg_hash, k) {
+            return Ok(signature);
+        }
+    }
+}
+
+/// Signs `msg_hash` with `private_key` using the given `k`, or returns `None` if `k` does not
+/// result in a valid signature.
+fn sign_with_k(private_key: Scalar, msg_hash: FieldElement, k: Scalar) -> Option<Signature> {
+    let point = CURVE_G.multiply(&k.into_bits());
+    if point.infinity {
+        return None;
+    }
+    let point = AffinePoint::from(&point);
+
+    let r = point.x;
+    if r.is_zero().into() || !fits_251_bits(&r) {
+        return None;
+    }
+
+    // Both fit in 251 bits and are therefore smaller than the curve order.
+    let r_scalar = to_scalar(r)?;
+    let msg_scalar = to_scalar(msg_hash)?;
+
+    let denominator = msg_scalar + r_scalar * private_key;
+    let w = k * Option::<Scalar>::from(denominator.invert())?;
+    let w_element = to_field_element(w);
+    if w.is_zero().into() || !fits_251_bits(&w_element) {
+        return None;
+    }
+
+    let s = Option::<Scalar>::from(w.invert())?;
+
+    Some(Signature {
+        r,
+        s: to_field_element(s),
+        v: is_odd(&point.y),
+    })
+}
+
+/// Verifies the signature (`r`, `s`) of `msg_hash` against `public_key`, given as x-coordinate.
+///
+/// Since the public key only defines the x-coordinate both candidate points are tried, as is done
+/// by cairo-lang's `verify`. Returns `Ok(false)` if `public_key` is not on the curve.
+pub fn verify(
+    public_key: FieldElement,
+    msg_hash: FieldElement,
+    r: FieldElement,
+    s: FieldElement,
+) -> Result<bool, EcdsaError> {
+    if !fits_251_bits(&msg_hash) {
+        return Err(EcdsaError::InvalidMessageHash);
+    }
+    if r.is_zero().into() || !fits_251_bits(&r) {
+        return Err(EcdsaError::InvalidR);
+    }
+    let s = to_scalar(s)
+        .filter(|s| !bool::from(s.is_zero()))
+        .ok_or(EcdsaError::InvalidS)?;
+    let w = Option::<Scalar>::from(s.invert()).ok_or(EcdsaError::InvalidS)?;
+    if !fits_251_bits(&to_field_element(w)) {
+        return Err(EcdsaError::InvalidS);
+    }
+
+    let mut public_key = match AffinePoint::from_x(public_key) {
+        Some(point) => point,
+        None => return Ok(false),
+    };
+
+    let msg_hash_g = CURVE_G.multiply(&msg_hash.into_bits());
+    let w_bits = w.into_bits();
+    let r_bits = r.into_bits();
+
+    let verify_point = |public_key: &AffinePoint| {
+        // x(w * (msg_hash * G + r * Q)) == r
+        let mut point = ProjectivePoint::from(public_key).multiply(&r_bits);
+        point.add(&msg_hash_g);
+        let point = point.multiply(&w_bits);
+
+        !point.infinity && AffinePoint::from(&point).x == r
+    };
+
+    if verify_point(&public_key) {
+        return Ok(true);
+    }
+
+    public_key.negate();
+    Ok(verify_point(&public_key))
+}
+
+/// Recovers the public key, as x-coordinate, which produced the signature (`r`, `s`, `v`) of
+/// `msg_hash`.
+pub fn recover_public_key(
+    msg_hash: FieldElement,
+    r: FieldElement,
+    s: FieldElement,
+    v: bool,
+) -> Result<FieldElement, EcdsaError> {
+    if !fits_251_bits(&msg_hash) {
+        return Err(EcdsaError::InvalidMessageHash);
+    }
+    if r.is_zero().into() || !fits_251_bits(&r) {
+        return Err(EcdsaError::InvalidR);
+    }
+    let s = to_scalar(s)
+        .filter(|s| !bool::from(s.is_zero()))
+        .ok_or(EcdsaError::InvalidS)?;
+
+    let mut point = AffinePoint::from_x(r).ok_or(EcdsaError::InvalidR)?;
+    if is_odd(&point.y) != v {
+        point.negate();
+    }
+
+    // Both fit in 251 bits and are therefore smaller than the curve order.
+    let r = to_scalar(r).ok_or(EcdsaError::InvalidR)?;
+    let msg_hash = to_scalar(msg_hash).ok_or(EcdsaError::InvalidMessageHash)?;
+    let r_inv = Option::<Scalar>::from(r.invert()).ok_or(EcdsaError::InvalidR)?;
+
+    // Q = r^-1 * (s * R - msg_hash * G)
+    let mut public_key = ProjectivePoint::from(&point).multiply(&(r_inv * s).into_bits());
+    public_key.add(&CURVE_G.multiply(&(-(r_inv * msg_hash)).into_bits()));
+    if public_key.infinity {
+        return Err(EcdsaError::InvalidSignature);
+    }
+
+    Ok(AffinePoint::from(&public_key).x)
+}
+
+/// Generates `k` deterministically as specified in RFC6979 section 3.2 using HMAC-SHA256, matching
+/// cairo-lang's `generate_k_rfc6979`.
+///
+/// The optional `seed` is used as additional entropy.
+fn generate_k(private_key: Scalar, msg_hash: FieldElement, seed: Option<u64>) -> Scalar {
+    type HmacSha256 = Hmac<Sha256>;
+
+    fn hmac(key: &[u8], parts: &[&[u8]]) -> [u8; 32] {
+        let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts keys of any size");
+        parts.iter().for_each(|part| mac.update(part));
+        mac.finalize().into_bytes().into()
+    }
+
+    // cairo-lang pads message hashes that are a nibble short of a full byte, which the `bits2int`
+    // truncation then removes again. As the hash is also less than the curve order, the result
+    // of `bits2octets` is always the hash itself.
+    let private_key = private_key.to_repr().0;
+    let msg_hash = msg_hash.to_repr().0;
+    let seed = seed.map(u64::to_be_bytes).unwrap_or_default();
+    let extra_entropy = &seed[seed.iter().take_while(|b| **b == 0).count()..];
+
+    let mut v = [0x01u8; 32];
+    let mut k = [0x00u8; 32];
+
+    k = hmac(&k, &[&v, &[0x00], &private_key, &msg_hash, extra_entropy]);
+    v = hmac(&k, &[&v]);
+    k = hmac(&k, &[&v, &[0x01], &private_key, &msg_hash, extra_entropy]);
+    v = hmac(&k, &[&v]);
+
+    loop {
+        v = hmac(&k, &[&v]);
+
+        // The curve order is 252 bits long, so `bits2int` keeps the 252 most significant bits.
+        let candidate = shift_right_4(v);
+        if let Some(candidate) = Option::<Scalar>::from(Scalar::from_repr(ScalarRepr(candidate))) {
+            if !bool::from(candidate.is_zero()) {
+                return candidate;
+            }
+        }
+
+        k = hmac(&k, &[&v, &[0x00]]);
+        v = hmac(&k, &[&v]);
+    }
+}
+
+fn shift_right_4(bytes: [u8; 32]) -> [u8; 32] {
+    let mut shifted = [0u8; 32];
+    shifted[0] = bytes[0] >> 4;
+    for (i, pair) in bytes.windows(2).enumerate() {
+        shifted[i + 1] = (pair[0] << 4) | (pair[1] >> 4);
+    }
+    shifted
+}
+
+/// Converts a [FieldElement] to a [Scalar], or `None` if it is not less than the curve order.
+fn to_scalar(element: FieldElement) -> Option<Scalar> {
+    Scalar::from_repr(ScalarRepr(element.to_repr().0)).into()
+}
+
+fn to_field_element(scalar: Scalar) -> FieldElement {
+    // The curve order is smaller than the field modulus so this cannot fail.
+    FieldElement::from_repr(crate::FieldElementRepr(scalar.to_repr().0)).unwrap()
+}
+
+fn fits_251_bits(element: &FieldElement) -> bool {
+    element.to_repr().0[0] < 0b1000
+}
+
+fn is_odd(element: &FieldElement) -> bool {
+    element.to_repr().0[31] & 1 == 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn from_hex(hex_str: &str) -> FieldElement {
+        let hex_str = hex_str.strip_prefix("0x").unwrap_or(hex_str);
+        let mut buf = [0u8; 32];
+        hex::decode_to_slice(format!("{:0>64}", hex_str), &mut buf).unwrap();
+        FieldElement::from_repr(crate::FieldElementRepr(buf)).unwrap()
+    }
+
+    /// Test vector from cairo-lang's `signature_test_data.json`.
+    mod cairo_lang_vector {
+        use super::*;
+        use pretty_assertions::assert_eq;
+
+        const PRIVATE_KEY: &str =
+            "0x3c1e9550e66958296d11b60f8e8e7a7ad990d07fa65d5f7652c4a6c87d4e3cc";
+        const PUBLIC_KEY: &str = "0x77a3b314db07c45076d11f62b6f9e748a39790441823307743cf00d6597ea43";
+        const MSG_HASH: &str = "0x397e76d1667c4454bfb83514e120583af836f8e32a516765497823eabe16a3f";
+        const R: &str = "0x173fd03d8b008ee7432977ac27d1e9d1a1f6c98b1a2f05fa84a21c84c44e882";
+        const S: &str = "0x4b6d75385aed025aa222f28a0adc6d58db78ff17e51c3f59e259b131cd5a1cc";
+
+        #[test]
+        fn public_key() {
+            let public_key = get_public_key(from_hex(PRIVATE_KEY)).unwrap();
+            assert_eq!(public_key, from_hex(PUBLIC_KEY));
+        }
+
+        #[test]
+        fn sign() {
+            let signature = super::sign(from_hex(PRIVATE_KEY), from_hex(MSG_HASH)).unwrap();
+            assert_eq!(signature.r, from_hex(R));
+            assert_eq!(signature.s, from_hex(S));
+        }
+
+        #[test]
+        fn verify() {
+            let valid = super::verify(
+                from_hex(PUBLIC_KEY),
+                from_hex(MSG_HASH),
+                from_hex(R),
+                from_hex(S),
+            )
+            .unwrap();
+            assert!(valid);
+        }
+
+        #[test]
+        fn recover() {
+            let signature = super::sign(from_hex(PRIVATE_KEY), from_hex(MSG_HASH)).unwrap();
+            let public_key =
+                recover_public_key(from_hex(MSG_HASH), signature.r, signature.s, signature.v)
+                    .unwrap();
+            assert_eq!(public_key, from_hex(PUBLIC_KEY));
+        }
+    }
+
+    #[test]
+    fn sign_with_given_k() {
+        // Also used by starknet-rs: signing 2 with private key 1 and k = 3.
+        let signature =
+            sign_with_k(Scalar::from(1), FieldElement::from(2), Scalar::from(3)).unwrap();
+
+        assert_eq!(
+            signature.r,
+            from_hex("0x411494b501a98abd8262b0da1351e17899a0c4ef23dd2f96fec5ba847310b20")
+        );
+        assert_eq!(
+            signature.s,
+            from_hex("0x405c3191ab3883ef2b763af35bc5f5d15b3b4e99461d70e84c654a351a7c81b")
+        );
+    }
+
+    #[test]
+    fn generate_k() {
+        // Expected values follow cairo-lang's `generate_k_rfc6979(msg_hash, priv_key, seed)`.
+        let cases = [
+            (
+                "0x397e76d1667c4454bfb83514e120583af836f8e32a516765497823eabe16a3f",
+                "0x3c1e9550e66958296d11b60f8e8e7a7ad990d07fa65d5f7652c4a6c87d4e3cc",
+                None,
+                "0x50a50e20a9fb5b33f618ce4ddec8df60f40d3ac3018453bcc002cee71140cd4",
+            ),
+            (
+                "0x397e76d1667c4454bfb83514e120583af836f8e32a516765497823eabe16a3f",
+                "0x3c1e9550e66958296d11b60f8e8e7a7ad990d07fa65d5f7652c4a6c87d4e3cc",
+                Some(1),
+                "0x380b536b55da025e145bc20c990bf703e86429e579fc8add31c206579b94f61",
+            ),
+            (
+                "0x7ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff",
+                "0x1",
+                None,
+                "0x665079a99a729baa81b2cbc576cb0ecbae6d4405934916f025e2d85fcf3955f",
+            ),
+            (
+                "0x0",
+                "0x1",
+                None,
+                "0x10497d369b3d525ca15ec29c104a694210bb59ff6cabfc10afe6df0283896d",
+            ),
+            (
+                "0x1ff",
+                "0x2dccce1da22003777062ee0870e9881b460a8b7eca276870f57c601f182136c",
+                Some(12345678),
+                "0x5185efdd9470956aeef4aedc7fe7e42d12182d276c9ead2eb845399102e2654",
+            ),
+        ];
+
+        for (msg_hash, private_key, seed, expected) in cases {
+            let private_key = to_scalar(from_hex(private_key)).unwrap();
+            let k = super::generate_k(private_key, from_hex(msg_hash), seed);
+            assert_eq!(to_field_element(k), from_hex(expected), "seed: {:?}", seed);
+        }
+    }
+
+    #[test]
+    fn sign_251_bit_message() {
+        let private_key = from_hex("0x1234");
+        let msg_hash = from_hex("0x7ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff");
+
+        let signature = sign(private_key, msg_hash).unwrap();
+        assert_eq!(
+            signature.r,
+            from_hex("0x1c98f712d1f0480671e85bb76ba709fa2b33276ed2c82e45f5cf7708ece27c4")
+        );
+        assert_eq!(
+            signature.s,
+            from_hex("0x1d84a5027b8afee571a33dd4bfc8ea700a81a01011206dfdf2030f0d6b36726")
+        );
+
+        let public_key = get_public_key(private_key).unwrap();
+        assert_eq!(
+            public_key,
+            from_hex("0x26da8d11938b76025862be14fdb8b28438827f73e75e86f7bfa38b196951fa7")
+        );
+        assert!(verify(public_key, msg_hash, signature.r, signature.s).unwrap());
+        assert_eq!(
+            recover_public_key(msg_hash, signature.r, signature.s, signature.v),
+            Ok(public_key)
+        );
+    }
+
+    #[test]
+    fn verify_wrong_message() {
+        let private_key = FieldElement::from(12345);
+        let public_key = get_public_key(private_key).unwrap();
+        let signature = sign(private_key, FieldElement::from(1)).unwrap();
+
+        let valid = verify(public_key, FieldElement::from(2), signature.r, signature.s).unwrap();
+        assert!(!valid);
+    }
+
+    #[test]
+    fn verify_public_key_not_on_curve() {
+        let signature = sign(FieldElement::from(12345), FieldElement::from(1)).unwrap();
+
+        let valid = verify(
+            FieldElement::zero(),
+            FieldElement::from(1),
+            signature.r,
+            signature.s,
+        )
+        .unwrap();
+        assert!(!valid);
+    }
+
+    #[test]
+    fn verify_out_of_range() {
+        let too_large = from_hex("0x800000000000000000000000000000000000000000000000000000000000000");
+        let one = FieldElement::one();
+
+        assert_eq!(
+            verify(one, too_large, one, one),
+            Err(EcdsaError::InvalidMessageHash)
+        );
+        assert_eq!(verify(one, one, too_large, one), Err(EcdsaError::InvalidR));
+        assert_eq!(
+            verify(one, one, FieldElement::zero(), one),
+            Err(EcdsaError::InvalidR)
+        );
+        assert_eq!(
+            verify(one, one, one, FieldElement::zero()),
+            Err(EcdsaError::InvalidS)
+        );
+    }
+
+    #[test]
+    fn invalid_private_key() {
+        assert_eq!(
+            get_public_key(FieldElement::zero()),
+            Err(EcdsaError::InvalidPrivateKey)
+        );
+        assert_eq!(
+            sign(FieldElement::zero(), FieldElement::one()),
+            Err(EcdsaError::InvalidPrivateKey)
+        );
+    }
+}
@@ -10,7 +10,7 @@ use ff::PrimeField;
 /// It's main use is to allow `pedersen_hash`.
 #[derive(PrimeField)]
 #[PrimeFieldModulus = "3618502788666131213697322783095070105623107215331596699973092056135872020481"]
-#[PrimeFieldGenerator = "7"]
+#[PrimeFieldGenerator = "3"]
 #[PrimeFieldReprEndianness = "big"]
 pub struct FieldElement([u64; 4]);
 
@@ -105,6 +105,15 @@ mod tests {
         assert_eq!(two, expected);
     }
 
+    #[test]
+    fn sqrt() {
+        for i in 1..100u64 {
+            let x = FieldElement::from(i);
+            let root = Option::<FieldElement>::from((x * x).sqrt()).unwrap();
+            assert!(root == x || root == -x);
+        }
+    }
+
     #[test]
     fn const_one_two_three() {
         let one = FieldElement::from(1);
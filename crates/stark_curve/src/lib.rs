@@ -1,11 +1,16 @@
 #![deny(rust_2018_idioms)]
 
 mod curve;
+mod ecdsa;
 mod field;
+mod scalar;
 
 pub use curve::{
-    AffinePoint, ProjectivePoint, PEDERSEN_P0, PEDERSEN_P1, PEDERSEN_P2, PEDERSEN_P3, PEDERSEN_P4,
+    AffinePoint, ProjectivePoint, CURVE_BETA, CURVE_G, PEDERSEN_P0, PEDERSEN_P1, PEDERSEN_P2,
+    PEDERSEN_P3, PEDERSEN_P4,
 };
+pub use ecdsa::{get_public_key, recover_public_key, sign, verify, EcdsaError, Signature};
 pub use field::{FieldElement, FieldElementRepr};
+pub use scalar::{Scalar, ScalarRepr};
 
 pub use ff;
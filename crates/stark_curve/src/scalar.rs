@@ -0,0 +1,65 @@
+// FIXME: needed because of mont_reduce generated by PrimeField derive
+// https://github.com/zkcrypto/ff/pull/83
+#![allow(clippy::too_many_arguments)]
+
+use bitvec::{array::BitArray, order::Lsb0};
+use ff::PrimeField;
+
+/// The scalar field of Starkware's curve, i.e. integers modulo the order of the curve generator.
+///
+/// It's main use is the ECDSA signature scheme.
+#[derive(PrimeField)]
+#[PrimeFieldModulus = "3618502788666131213697322783095070105526743751716087489154079457884512865583"]
+#[PrimeFieldGenerator = "3"]
+#[PrimeFieldReprEndianness = "big"]
+pub struct Scalar([u64; 4]);
+
+impl Scalar {
+    /// Transforms [Scalar] into little endian bit representation.
+    pub fn into_bits(self) -> BitArray<Lsb0, [u64; 4]> {
+        let bytes = self.to_repr().0;
+
+        let mut limbs = [0u64; 4];
+        for (limb, chunk) in limbs.iter_mut().zip(bytes.rchunks_exact(8)) {
+            // Chunks are exactly 8 bytes long so this cannot fail.
+            *limb = u64::from_be_bytes(chunk.try_into().unwrap());
+        }
+
+        limbs.into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ff::Field;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn bits_zero() {
+        let zero = Scalar::zero().into_bits();
+        let expected = BitArray::<Lsb0, [u64; 4]>::default();
+
+        assert_eq!(zero, expected);
+    }
+
+    #[test]
+    fn bits_two_pow_64() {
+        let scalar = Scalar::from(u64::MAX) + Scalar::one();
+
+        let mut expected = BitArray::<Lsb0, [u64; 4]>::default();
+        expected.set(64, true);
+
+        assert_eq!(scalar.into_bits(), expected);
+    }
+
+    #[test]
+    fn wraps_at_curve_order() {
+        let order_minus_one = Scalar::from_str_vartime(
+            "3618502788666131213697322783095070105526743751716087489154079457884512865582",
+        )
+        .unwrap();
+
+        assert_eq!(order_minus_one + Scalar::one(), Scalar::zero());
+    }
+}
@@ -0,0 +1,64 @@
+use ::stark_hash::{poseidon_hash, poseidon_hash_many, PoseidonHasher, StarkHash};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+pub fn criterion_benchmark(c: &mut Criterion) {
+    // These are the test vectors also used in tests.
+    let e0 = "0xb662f9017fa7956fd70e26129b1833e10ad000fd37b4d9f4e0ce6884b7bbe";
+    let e1 = "0x1fe356bf76102cdae1bfbdc173602ead228b12904c00dad9cf16e035468bea";
+
+    let e0 = StarkHash::from_hex_str(e0).unwrap();
+    let e1 = StarkHash::from_hex_str(e1).unwrap();
+
+    c.bench_function("poseidon_hash", |b| {
+        b.iter(|| {
+            black_box(poseidon_hash(e0, e1));
+        });
+    });
+
+    let mut rng = rand::thread_rng();
+
+    c.bench_function("random_poseidon_hash", |b| {
+        b.iter_batched(
+            || {
+                let a = StarkHash::random(&mut rng);
+                let b = StarkHash::random(&mut rng);
+                (a, b)
+            },
+            |(a, b)| black_box(poseidon_hash(a, b)),
+            criterion::BatchSize::SmallInput,
+        )
+    });
+
+    c.bench_function("random_poseidon_hash_many", |b| {
+        b.iter_batched_ref(
+            || {
+                std::iter::from_fn(|| Some(StarkHash::random(&mut rng)))
+                    .take(100)
+                    .collect::<Vec<_>>()
+            },
+            |input| black_box(poseidon_hash_many(input)),
+            criterion::BatchSize::SmallInput,
+        );
+    });
+
+    c.bench_function("random_poseidon_hasher", |b| {
+        b.iter_batched_ref(
+            || {
+                std::iter::from_fn(|| Some(StarkHash::random(&mut rng)))
+                    .take(100)
+                    .collect::<Vec<_>>()
+            },
+            |input| {
+                let mut hasher = PoseidonHasher::default();
+                for x in input.drain(..) {
+                    hasher.update(x);
+                }
+                black_box(hasher.finalize())
+            },
+            criterion::BatchSize::SmallInput,
+        );
+    });
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
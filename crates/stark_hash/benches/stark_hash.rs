@@ -1,4 +1,4 @@
-use ::stark_hash::{stark_hash, HashChain, StarkHash};
+use ::stark_hash::{stark_hash, stark_hash_batch, HashChain, StarkHash};
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
 
 pub fn criterion_benchmark(c: &mut Criterion) {
@@ -31,6 +31,31 @@ pub fn criterion_benchmark(c: &mut Criterion) {
         )
     });
 
+    // Compare hashing a batch of pairs one by one against `stark_hash_batch`.
+    let mut batch = c.benchmark_group("random_stark_hash_1000_pairs");
+    batch.bench_function("one_by_one", |b| {
+        b.iter_batched_ref(
+            || random_pairs(&mut rng, 1000),
+            |pairs| {
+                black_box(
+                    pairs
+                        .iter()
+                        .map(|(a, b)| stark_hash(*a, *b))
+                        .collect::<Vec<_>>(),
+                )
+            },
+            criterion::BatchSize::SmallInput,
+        );
+    });
+    batch.bench_function("batch", |b| {
+        b.iter_batched_ref(
+            || random_pairs(&mut rng, 1000),
+            |pairs| black_box(stark_hash_batch(pairs)),
+            criterion::BatchSize::SmallInput,
+        );
+    });
+    batch.finish();
+
     c.bench_function("random_hashchain", |b| {
         b.iter_batched_ref(
             || {
@@ -50,5 +75,11 @@ pub fn criterion_benchmark(c: &mut Criterion) {
     });
 }
 
+fn random_pairs(rng: &mut impl rand::Rng, count: usize) -> Vec<(StarkHash, StarkHash)> {
+    std::iter::repeat_with(|| (StarkHash::random(&mut *rng), StarkHash::random(&mut *rng)))
+        .take(count)
+        .collect()
+}
+
 criterion_group!(benches, criterion_benchmark);
 criterion_main!(benches);
@@ -3,6 +3,7 @@ use std::fmt::Write;
 use std::fs;
 use std::path::Path;
 
+use sha2::{Digest, Sha256};
 use stark_curve::*;
 
 fn generate_consts(bits: u32) -> Result<String, std::fmt::Error> {
@@ -91,6 +92,55 @@ fn push_point(buf: &mut String, p: &AffinePoint) -> std::fmt::Result {
     Ok(())
 }
 
+/// Generates the round constants of the Hades permutation used by StarkNet's Poseidon hash.
+///
+/// These are the same as in cairo-lang's `poseidon_params.py`, where each constant is the
+/// SHA-256 hash of "Hades{index}" reduced modulo the field prime.
+fn generate_poseidon_consts(
+    full_rounds: usize,
+    partial_rounds: usize,
+    width: usize,
+) -> Result<String, std::fmt::Error> {
+    let mut buf = String::with_capacity(128 * 1024);
+
+    writeln!(buf, "pub const POSEIDON_FULL_ROUNDS: usize = {};", full_rounds)?;
+    writeln!(
+        buf,
+        "pub const POSEIDON_PARTIAL_ROUNDS: usize = {};\n",
+        partial_rounds
+    )?;
+
+    let rounds = full_rounds + partial_rounds;
+    writeln!(
+        buf,
+        "pub const POSEIDON_ROUND_CONSTS: [[FieldElement; {}]; {}] = [",
+        width, rounds
+    )?;
+
+    for round in 0..rounds {
+        buf.push_str("    [\n");
+        for i in 0..width {
+            let digest = Sha256::digest(format!("Hades{}", width * round + i).as_bytes());
+
+            // Reduce the 256-bit digest modulo the field prime.
+            let constant = digest.iter().fold(FieldElement::from(0), |acc, byte| {
+                acc * FieldElement::from(256) + FieldElement::from(*byte as u64)
+            });
+
+            let limbs = constant.inner();
+            writeln!(
+                buf,
+                "        FieldElement::new([{}, {}, {}, {}]),",
+                limbs[0], limbs[1], limbs[2], limbs[3]
+            )?;
+        }
+        buf.push_str("    ],\n");
+    }
+
+    buf.push_str("];\n");
+    Ok(buf)
+}
+
 fn main() {
     let out_dir = env::var_os("OUT_DIR").unwrap();
     let dest_path = Path::new(&out_dir).join("curve_consts.rs");
@@ -98,4 +148,10 @@ fn main() {
     let consts = generate_consts(bits).expect("should had been able to format the curve constants");
     fs::write(dest_path, consts)
         .expect("should had been able to write to $OUT_DIR/curve_consts.rs");
+
+    let dest_path = Path::new(&out_dir).join("poseidon_consts.rs");
+    let consts = generate_poseidon_consts(8, 83, 3)
+        .expect("should had been able to format the poseidon constants");
+    fs::write(dest_path, consts)
+        .expect("should had been able to write to $OUT_DIR/poseidon_consts.rs");
 }
@@ -0,0 +1,14 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: Vec<[u8; 32]>| {
+    let values = data
+        .into_iter()
+        .filter_map(|bytes| stark_hash::StarkHash::from_be_bytes(bytes).ok())
+        .collect::<Vec<_>>();
+
+    let mut hasher = stark_hash::PoseidonHasher::default();
+    values.iter().for_each(|value| hasher.update(*value));
+
+    assert_eq!(hasher.finalize(), stark_hash::poseidon_hash_many(&values));
+});
@@ -266,6 +266,75 @@ impl std::ops::Add for StarkHash {
 ///
 /// [Starknet Pedersen hash]: https://docs.starkware.co/starkex-v3/crypto/pedersen-hash-function
 pub fn stark_hash(a: StarkHash, b: StarkHash) -> StarkHash {
+    let result = AffinePoint::from(&pedersen_point(a, b));
+
+    // Return x-coordinate
+    StarkHash::from(result.x)
+}
+
+/// The minimum number of hashes each thread has to compute before [stark_hash_batch] considers
+/// spreading the work over multiple threads. Below this splitting the work costs more than it
+/// saves.
+const BATCH_SIZE_PER_THREAD: usize = 256;
+
+/// Computes the [Starknet Pedersen hash] of each `(a, b)` pair in `pairs`.
+///
+/// Gives the same results as calling [stark_hash] on each pair in turn, but amortises the cost of
+/// converting the resulting points to affine coordinates over the whole batch. Large batches are
+/// also split across the threads of the global [rayon] pool, so that no threads are spawned per
+/// call.
+///
+/// [Starknet Pedersen hash]: https://docs.starkware.co/starkex-v3/crypto/pedersen-hash-function
+pub fn stark_hash_batch(pairs: &[(StarkHash, StarkHash)]) -> Vec<StarkHash> {
+    use rayon::prelude::*;
+
+    let threads = rayon::current_num_threads().min(pairs.len() / BATCH_SIZE_PER_THREAD);
+
+    if threads <= 1 {
+        return hash_batch(pairs);
+    }
+
+    let chunk_size = (pairs.len() + threads - 1) / threads;
+    pairs
+        .par_chunks(chunk_size)
+        .map(hash_batch)
+        .collect::<Vec<_>>()
+        .concat()
+}
+
+/// Hashes all pairs on the current thread, using Montgomery's trick to invert all `z`
+/// coordinates with a single field inversion.
+fn hash_batch(pairs: &[(StarkHash, StarkHash)]) -> Vec<StarkHash> {
+    use stark_curve::ff::Field;
+
+    let points = pairs
+        .iter()
+        .map(|(a, b)| pedersen_point(*a, *b))
+        .collect::<Vec<_>>();
+
+    // prefix[i] holds the product of the z coordinates of all points before i.
+    let mut prefix = Vec::with_capacity(points.len());
+    let mut product = FieldElement::one();
+    for point in &points {
+        prefix.push(product);
+        product *= point.z;
+    }
+
+    // Walking backwards, `inverse` is the inverse of the product of z up to and including i.
+    let mut inverse = product.invert().unwrap();
+    let mut hashes = vec![StarkHash::ZERO; points.len()];
+    for (i, point) in points.iter().enumerate().rev() {
+        let z_inverse = inverse * prefix[i];
+        inverse *= point.z;
+
+        hashes[i] = StarkHash::from(point.x * z_inverse);
+    }
+
+    hashes
+}
+
+/// Computes the Pedersen hash point, in projective coordinates, using precomputed points.
+fn pedersen_point(a: StarkHash, b: StarkHash) -> ProjectivePoint {
     let a = FieldElement::from(a).into_bits();
     let b = FieldElement::from(b).into_bits();
 
@@ -290,11 +359,7 @@ pub fn stark_hash(a: StarkHash, b: StarkHash) -> StarkHash {
     add_points(&mut acc, &b[..248], &CURVE_CONSTS_P3); // Add b_low * P3
     add_points(&mut acc, &b[248..252], &CURVE_CONSTS_P4); // Add b_high * P4
 
-    // Convert to affine
-    let result = AffinePoint::from(&acc);
-
-    // Return x-coordinate
-    StarkHash::from(result.x)
+    acc
 }
 
 impl From<StarkHash> for FieldElement {
@@ -531,6 +596,27 @@ mod tests {
         assert_eq!(hash, expected);
     }
 
+    #[test]
+    fn batch_matches_single() {
+        let mut rng = rand::thread_rng();
+
+        // Sizes below and above the threshold for spreading the work over threads.
+        for size in [0, 1, 7, 3 * BATCH_SIZE_PER_THREAD + 5] {
+            let pairs = std::iter::repeat_with(|| {
+                (StarkHash::random(&mut rng), StarkHash::random(&mut rng))
+            })
+            .take(size)
+            .collect::<Vec<_>>();
+
+            let expected = pairs
+                .iter()
+                .map(|(a, b)| stark_hash(*a, *b))
+                .collect::<Vec<_>>();
+
+            assert_eq!(stark_hash_batch(&pairs), expected, "size = {}", size);
+        }
+    }
+
     #[test]
     fn bytes_round_trip() {
         let original = [
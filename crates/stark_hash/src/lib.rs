@@ -2,7 +2,9 @@
 
 mod chain;
 mod hash;
+mod poseidon;
 mod serde;
 
 pub use chain::HashChain;
-pub use hash::{stark_hash, HexParseError, OverflowError, StarkHash};
+pub use hash::{stark_hash, stark_hash_batch, HexParseError, OverflowError, StarkHash};
+pub use poseidon::{poseidon_hash, poseidon_hash_many, poseidon_hash_single, PoseidonHasher};
@@ -0,0 +1,243 @@
+use crate::StarkHash;
+
+use stark_curve::FieldElement;
+
+include!(concat!(env!("OUT_DIR"), "/poseidon_consts.rs"));
+
+/// Computes the [Starknet Poseidon hash] of `a` and `b`.
+///
+/// [Starknet Poseidon hash]: https://docs.starknet.io/documentation/architecture_and_concepts/Hashing/hash-functions/#poseidon_hash
+pub fn poseidon_hash(a: StarkHash, b: StarkHash) -> StarkHash {
+    let mut state = [a.into(), b.into(), FieldElement::from(2)];
+    permute(&mut state);
+
+    state[0].into()
+}
+
+/// Computes the Starknet Poseidon hash of a single value.
+pub fn poseidon_hash_single(value: StarkHash) -> StarkHash {
+    let mut state = [value.into(), FieldElement::from(0), FieldElement::from(1)];
+    permute(&mut state);
+
+    state[0].into()
+}
+
+/// Computes the Starknet Poseidon hash of an arbitrary number of values.
+///
+/// Gives the same result as feeding each value to a [PoseidonHasher] in turn.
+pub fn poseidon_hash_many(values: &[StarkHash]) -> StarkHash {
+    let mut state = [FieldElement::from(0); 3];
+
+    let mut pairs = values.chunks_exact(2);
+    for pair in pairs.by_ref() {
+        state[0] += FieldElement::from(pair[0]);
+        state[1] += FieldElement::from(pair[1]);
+        permute(&mut state);
+    }
+
+    // Pad with a one, and a zero if that leaves an odd number of values.
+    match pairs.remainder() {
+        [last] => {
+            state[0] += FieldElement::from(*last);
+            state[1] += FieldElement::from(1);
+        }
+        _ => state[0] += FieldElement::from(1),
+    }
+    permute(&mut state);
+
+    state[0].into()
+}
+
+/// PoseidonHasher is the incremental version of the sponge construction used by cairo-lang's
+/// `poseidon_hash_many`.
+///
+/// Values are absorbed two at a time into the first two elements of the state, after which the
+/// state is permuted. On finalization the input is padded with a one, followed by a zero if that
+/// leaves an odd number of values, and the first element of the state is returned.
+#[derive(Default)]
+pub struct PoseidonHasher {
+    state: [FieldElement; 3],
+    buffer: Option<FieldElement>,
+}
+
+impl PoseidonHasher {
+    pub fn update(&mut self, value: StarkHash) {
+        match self.buffer.take() {
+            Some(previous) => {
+                self.state[0] += previous;
+                self.state[1] += FieldElement::from(value);
+                permute(&mut self.state);
+            }
+            None => self.buffer = Some(value.into()),
+        }
+    }
+
+    pub fn finalize(mut self) -> StarkHash {
+        match self.buffer.take() {
+            Some(last) => {
+                self.state[0] += last;
+                self.state[1] += FieldElement::from(1);
+            }
+            None => self.state[0] += FieldElement::from(1),
+        }
+        permute(&mut self.state);
+
+        self.state[0].into()
+    }
+}
+
+/// The Hades permutation with the Starknet parameters: a state of three elements, the cube as
+/// S-box and [POSEIDON_FULL_ROUNDS] full rounds, half of them before and half after
+/// [POSEIDON_PARTIAL_ROUNDS] partial rounds.
+fn permute(state: &mut [FieldElement; 3]) {
+    let half_full_rounds = POSEIDON_FULL_ROUNDS / 2;
+    let (first_full, rest) = POSEIDON_ROUND_CONSTS.split_at(half_full_rounds);
+    let (partial, last_full) = rest.split_at(POSEIDON_PARTIAL_ROUNDS);
+
+    first_full
+        .iter()
+        .for_each(|constants| full_round(state, constants));
+    partial
+        .iter()
+        .for_each(|constants| partial_round(state, constants));
+    last_full
+        .iter()
+        .for_each(|constants| full_round(state, constants));
+}
+
+fn full_round(state: &mut [FieldElement; 3], constants: &[FieldElement; 3]) {
+    for (element, constant) in state.iter_mut().zip(constants) {
+        *element += constant;
+        *element = cube(*element);
+    }
+    mix(state);
+}
+
+fn partial_round(state: &mut [FieldElement; 3], constants: &[FieldElement; 3]) {
+    for (element, constant) in state.iter_mut().zip(constants) {
+        *element += constant;
+    }
+    state[2] = cube(state[2]);
+    mix(state);
+}
+
+fn cube(x: FieldElement) -> FieldElement {
+    x * x * x
+}
+
+/// Multiplies the state by the MDS matrix `[[3, 1, 1], [1, -1, 1], [1, 1, -2]]`.
+fn mix(state: &mut [FieldElement; 3]) {
+    let sum = state[0] + state[1] + state[2];
+    let two = FieldElement::from(2);
+    let three = FieldElement::from(3);
+
+    state[0] = sum + two * state[0];
+    state[1] = sum - two * state[1];
+    state[2] = sum - three * state[2];
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn hash(hex_str: &str) -> StarkHash {
+        StarkHash::from_hex_str(hex_str).unwrap()
+    }
+
+    #[test]
+    fn round_constants() {
+        // The first round constant in cairo-lang's `poseidon_params.py`.
+        assert_eq!(
+            StarkHash::from(POSEIDON_ROUND_CONSTS[0][0]),
+            hash("0x6861759ea556a2339dd92f9562a30b9e58e2ad98109ae4780b7fd8eac77fe6f")
+        );
+        assert_eq!(POSEIDON_ROUND_CONSTS.len(), 91);
+    }
+
+    #[test]
+    fn permutation_of_zero() {
+        let mut state = [FieldElement::from(0); 3];
+        permute(&mut state);
+
+        let expected = [
+            hash("0x79e8d1e78258000a28fc9d49e233bc6852357968577b1e386550ed6a9086133"),
+            hash("0x3840d003d0f3f96dbb796ff6aa6a63be5b5404b91ccaabca256154cbb6fb984"),
+            hash("0x1eb39da3f7d3b04142d0ac83d9da00c9325a61fb2ef326e50b70eaa8a3c7cc7"),
+        ];
+        assert_eq!(state.map(StarkHash::from), expected);
+    }
+
+    #[test]
+    fn hash_pair() {
+        // Expected values match the cairo-lang Python implementation:
+        // `hex(poseidon_hash(1, 2))`
+        assert_eq!(
+            poseidon_hash(StarkHash::from(1u64), StarkHash::from(2u64)),
+            hash("0x5d44a3decb2b2e0cc71071f7b802f45dd792d064f0fc7316c46514f70f9891a")
+        );
+
+        let a = hash("0xb662f9017fa7956fd70e26129b1833e10ad000fd37b4d9f4e0ce6884b7bbe");
+        let b = hash("0x1fe356bf76102cdae1bfbdc173602ead228b12904c00dad9cf16e035468bea");
+        assert_eq!(
+            poseidon_hash(a, b),
+            hash("0x75540825a6ecc5dc7d7c2f5f868164182742227f1367d66c43ee51ec7937a81")
+        );
+    }
+
+    #[test]
+    fn hash_single() {
+        // `hex(poseidon_hash_single(1))`
+        assert_eq!(
+            poseidon_hash_single(StarkHash::from(1u64)),
+            hash("0x6d226d4c804cd74567f5ac59c6a4af1fe2a6eced19fb7560a9124579877da25")
+        );
+    }
+
+    #[test]
+    fn hash_many() {
+        // `hex(poseidon_hash_many([]))`
+        assert_eq!(
+            poseidon_hash_many(&[]),
+            hash("0x2272be0f580fd156823304800919530eaa97430e972d7213ee13f4fbf7a5dbc")
+        );
+
+        // `hex(poseidon_hash_many([1, 2, 3]))`
+        let values = [1u64, 2, 3].map(StarkHash::from);
+        assert_eq!(
+            poseidon_hash_many(&values),
+            hash("0x2f0d8840bcf3bc629598d8a6cc80cb7c0d9e52d93dab244bbf9cd0dca0ad082")
+        );
+    }
+
+    #[test]
+    fn hasher() {
+        // Expected values match the cairo-lang Python implementation:
+        // `hex(poseidon_hash_many(list(range(1, n + 1))))`
+        let cases = [
+            (
+                1,
+                "0x579e8877c7755365d5ec1ec7d3a94a457eff5d1f40482bbe9729c064cdead2",
+            ),
+            (
+                2,
+                "0x371cb6995ea5e7effcd2e174de264b5b407027a75a231a70c2c8d196107f0e7",
+            ),
+            (
+                4,
+                "0x26e3ad8b876e02bc8a4fc43dad40a8f81a6384083cabffa190bcf40d512ae1d",
+            ),
+            (
+                5,
+                "0x159f4ab3b9bdc95a6a4a9ffb36456ad33290ea1fa809e0445bc449b0ad62da4",
+            ),
+        ];
+
+        for (n, expected) in cases {
+            let mut hasher = PoseidonHasher::default();
+            (1..=n).for_each(|i| hasher.update(StarkHash::from(i as u64)));
+
+            assert_eq!(hasher.finalize(), hash(expected), "n = {}", n);
+        }
+    }
+}
@@ -47,7 +47,7 @@ except ModuleNotFoundError:
 
 
 # used from tests, and the query which asserts that the schema is of expected version.
-EXPECTED_SCHEMA_REVISION = 22
+EXPECTED_SCHEMA_REVISION = 28
 EXPECTED_CAIRO_VERSION = "0.10.2a0"
 
 # used by the sqlite adapter to communicate "contract state not found, nor was the patricia tree key"
@@ -218,7 +218,10 @@ def main():
         )
         sys.exit(1)
 
-    with sqlite3.connect(database_path) as connection:
+    # read-only replicas pass the database as a `file:` uri with `mode=ro`
+    with sqlite3.connect(
+        database_path, uri=database_path.startswith("file:")
+    ) as connection:
         # this is not a sort of "usual" isolation_level switch with sqlite like
         # read_uncommited or anything like that. instead this asks that the
         # python side doesn't inspect the queries and try to manage autocommit